@@ -0,0 +1,279 @@
+//! Python bindings for engram's query API: open an index, explain anchors,
+//! run semantic search, list tapes and iterate raw tape events, all as plain
+//! Python dicts and lists.
+
+use std::fs;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use engram::index::lineage::{Cardinality, EvidenceFragmentRef, EvidenceKind, LocationDelta};
+use engram::index::{EdgeRow, SqliteIndex};
+use engram::query::explain::{ExplainTraversal, explain_by_anchor};
+use engram::tape::compress::decompress_jsonl;
+use pyo3::IntoPyObjectExt;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_json::{Value, json};
+
+create_exception!(engram, EngramError, PyException);
+
+fn engram_err(err: impl Into<engram::Error>) -> PyErr {
+    let err = err.into();
+    EngramError::new_err(format!("{} error: {err}", err.class()))
+}
+
+/// `engram.Index(path)`: a read-mostly handle on an `index.sqlite`.
+#[pyclass(module = "engram")]
+struct Index {
+    index: Mutex<SqliteIndex>,
+}
+
+impl Index {
+    fn index(&self) -> MutexGuard<'_, SqliteIndex> {
+        self.index.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[pymethods]
+impl Index {
+    #[new]
+    fn new(path: &str) -> PyResult<Self> {
+        let index = SqliteIndex::open(path).map_err(engram_err)?;
+        Ok(Self {
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Evidence and lineage for `anchors`, as `engram explain` reports them:
+    /// `{"direct": [...], "lineage": [...], "touched_anchors": [...]}`.
+    #[pyo3(signature = (anchors, *, min_confidence=None, max_depth=None, forensics=false))]
+    fn explain<'py>(
+        &self,
+        py: Python<'py>,
+        anchors: Vec<String>,
+        min_confidence: Option<f32>,
+        max_depth: Option<usize>,
+        forensics: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let defaults = ExplainTraversal::default();
+        let traversal = ExplainTraversal {
+            min_confidence: min_confidence.unwrap_or(defaults.min_confidence),
+            max_depth: max_depth.unwrap_or(defaults.max_depth),
+            ..defaults
+        };
+        let result = explain_by_anchor(&*self.index(), &anchors, traversal, forensics)
+            .map_err(engram_err)?;
+        to_py(
+            py,
+            &json!({
+                "direct": result.direct.iter().map(evidence_json).collect::<Vec<_>>(),
+                "lineage": result.lineage.iter().map(edge_json).collect::<Vec<_>>(),
+                "touched_anchors": result.touched_anchors,
+                "truncated": result.truncated.is_some(),
+            }),
+        )
+    }
+
+    /// Events closest to `vector` among those embedded by `provider` (the
+    /// provider id `engram search --semantic` stores, e.g. `hashing:256`).
+    #[pyo3(signature = (vector, provider, limit=20))]
+    fn search<'py>(
+        &self,
+        py: Python<'py>,
+        vector: Vec<f32>,
+        provider: &str,
+        limit: usize,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let hits = self
+            .index()
+            .semantic_search(provider, &vector, limit)
+            .map_err(engram_err)?;
+        let hits = hits
+            .into_iter()
+            .map(|hit| {
+                json!({
+                    "tape_id": hit.tape_id,
+                    "event_offset": hit.event_offset,
+                    "kind": hit.kind,
+                    "score": hit.score,
+                })
+            })
+            .collect::<Vec<_>>();
+        to_py(py, &Value::Array(hits))
+    }
+
+    /// Every indexed tape with the metrics recorded at ingest.
+    fn tapes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let index = self.index();
+        let metrics = index
+            .all_tape_metrics()
+            .map_err(engram_err)?
+            .into_iter()
+            .collect::<std::collections::HashMap<_, _>>();
+        let tapes = index
+            .tape_ids()
+            .map_err(engram_err)?
+            .into_iter()
+            .map(|tape_id| {
+                let mut tape = json!({ "tape_id": tape_id });
+                if let Some(metrics) = metrics.get(&tape_id) {
+                    tape["metrics"] = json!({
+                        "event_count": metrics.event_count,
+                        "message_count": metrics.message_count,
+                        "tool_call_count": metrics.tool_call_count,
+                        "tool_error_count": metrics.tool_error_count,
+                        "edit_count": metrics.edit_count,
+                        "revert_count": metrics.revert_count,
+                        "started_at": metrics.started_at,
+                        "ended_at": metrics.ended_at,
+                        "duration_secs": metrics.duration_secs,
+                        "model": metrics.model,
+                        "harness": metrics.harness,
+                    });
+                }
+                tape
+            })
+            .collect::<Vec<_>>();
+        to_py(py, &Value::Array(tapes))
+    }
+
+    /// Catalogued events across tapes, filtered like `engram events`.
+    #[pyo3(signature = (*, kinds=Vec::new(), tool=None, file=None))]
+    fn events<'py>(
+        &self,
+        py: Python<'py>,
+        kinds: Vec<String>,
+        tool: Option<&str>,
+        file: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let events = self
+            .index()
+            .events_matching(&kinds, tool, file)
+            .map_err(engram_err)?
+            .into_iter()
+            .map(|(tape_id, event)| {
+                json!({
+                    "tape_id": tape_id,
+                    "offset": event.offset,
+                    "kind": event.kind,
+                    "tool": event.tool,
+                    "file": event.file,
+                    "t": event.timestamp,
+                    "exit": event.exit,
+                })
+            })
+            .collect::<Vec<_>>();
+        to_py(py, &Value::Array(events))
+    }
+}
+
+/// Iterator over the raw rows of one tape file, as dicts.
+#[pyclass(module = "engram")]
+struct TapeEvents {
+    rows: std::vec::IntoIter<Value>,
+}
+
+#[pymethods]
+impl TapeEvents {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        self.rows.next().map(|row| to_py(py, &row)).transpose()
+    }
+}
+
+/// Raw events of a tape (`.jsonl` or `.jsonl.zst`), one dict per line.
+/// Lines that are not JSON are skipped.
+#[pyfunction]
+fn read_tape(path: &str) -> PyResult<TapeEvents> {
+    let bytes = fs::read(path).map_err(engram_err)?;
+    let content = if path.ends_with(".zst") {
+        decompress_jsonl(&bytes).map_err(engram_err)?
+    } else {
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+    let rows = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .collect::<Vec<_>>();
+    Ok(TapeEvents {
+        rows: rows.into_iter(),
+    })
+}
+
+fn evidence_json(fragment: &EvidenceFragmentRef) -> Value {
+    json!({
+        "tape_id": fragment.tape_id,
+        "event_offset": fragment.event_offset,
+        "kind": match fragment.kind {
+            EvidenceKind::Edit => "edit",
+            EvidenceKind::Read => "read",
+            EvidenceKind::Tool => "tool",
+            EvidenceKind::Message => "message",
+        },
+        "file": fragment.file_path,
+        "t": fragment.timestamp,
+    })
+}
+
+fn edge_json(edge: &EdgeRow) -> Value {
+    json!({
+        "from_anchor": edge.from_anchor,
+        "to_anchor": edge.to_anchor,
+        "confidence": edge.confidence,
+        "location_delta": match edge.location_delta {
+            LocationDelta::Same => "same",
+            LocationDelta::Adjacent => "adjacent",
+            LocationDelta::Moved => "moved",
+            LocationDelta::Absent => "absent",
+        },
+        "cardinality": match edge.cardinality {
+            Cardinality::OneToOne => "1:1",
+            Cardinality::OneToMany => "1:N",
+            Cardinality::ManyToOne => "N:1",
+        },
+        "agent_link": edge.agent_link,
+        "note": edge.note,
+        "reverted": edge.reverted,
+    })
+}
+
+fn to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        Value::Null => Ok(py.None().into_bound(py)),
+        Value::Bool(flag) => flag.into_bound_py_any(py),
+        Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(int), _) => int.into_bound_py_any(py),
+            (None, Some(int)) => int.into_bound_py_any(py),
+            _ => number.as_f64().unwrap_or(f64::NAN).into_bound_py_any(py),
+        },
+        Value::String(text) => text.into_bound_py_any(py),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_py(py, item)?)?;
+            }
+            Ok(list.into_any())
+        }
+        Value::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, item) in fields {
+                dict.set_item(key, to_py(py, item)?)?;
+            }
+            Ok(dict.into_any())
+        }
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "engram")]
+fn engram_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add("EngramError", module.py().get_type::<EngramError>())?;
+    module.add_class::<Index>()?;
+    module.add_class::<TapeEvents>()?;
+    module.add_function(wrap_pyfunction!(read_tape, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,155 @@
+/// Lines hashed together to decide whether a chunk ends after a line.
+const CHUNK_WINDOW_LINES: usize = 3;
+/// A chunk ends where the window hash is divisible by this, so chunks
+/// average roughly this many lines once past the minimum.
+const CHUNK_TARGET_LINES: u64 = 12;
+const MIN_CHUNK_LINES: usize = 4;
+const MAX_CHUNK_LINES: usize = 48;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// A run of lines, 1-based and inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineChunk {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl LineChunk {
+    pub fn contains(self, line: u32) -> bool {
+        self.start <= line && line <= self.end
+    }
+}
+
+/// Split `text` into content-defined chunks of lines. Whether a chunk ends
+/// after a line depends only on that line and the few before it (compared
+/// ignoring whitespace), so an edit moves the boundaries around it and
+/// leaves chunks elsewhere in the file as they were.
+pub fn content_defined_chunks(text: &str) -> Vec<LineChunk> {
+    let line_hashes = text.lines().map(line_hash).collect::<Vec<_>>();
+    let mut chunks = Vec::new();
+    let mut start = 0usize;
+    for (at, _) in line_hashes.iter().enumerate() {
+        let len = at + 1 - start;
+        let window = &line_hashes[(at + 1).saturating_sub(CHUNK_WINDOW_LINES)..=at];
+        let boundary =
+            len >= MIN_CHUNK_LINES && window_hash(window).is_multiple_of(CHUNK_TARGET_LINES);
+        if boundary || len >= MAX_CHUNK_LINES {
+            chunks.push(LineChunk {
+                start: start as u32 + 1,
+                end: at as u32 + 1,
+            });
+            start = at + 1;
+        }
+    }
+    if start < line_hashes.len() {
+        chunks.push(LineChunk {
+            start: start as u32 + 1,
+            end: line_hashes.len() as u32,
+        });
+    }
+    chunks
+}
+
+/// The smallest run of whole chunks covering lines `start..=end`, or `None`
+/// when the range lies past the end of the file.
+pub fn chunk_span(chunks: &[LineChunk], start: u32, end: u32) -> Option<LineChunk> {
+    let first = chunks.iter().find(|chunk| chunk.contains(start))?;
+    let last = chunks
+        .iter()
+        .find(|chunk| chunk.contains(end))
+        .or(chunks.last())?;
+    Some(LineChunk {
+        start: first.start,
+        end: last.end.max(first.end),
+    })
+}
+
+fn line_hash(line: &str) -> u64 {
+    line.split_whitespace()
+        .flat_map(|word| word.bytes().chain([b' ']))
+        .fold(FNV_OFFSET, fnv_step)
+}
+
+fn window_hash(hashes: &[u64]) -> u64 {
+    hashes
+        .iter()
+        .flat_map(|hash| hash.to_le_bytes())
+        .fold(FNV_OFFSET, fnv_step)
+}
+
+fn fnv_step(hash: u64, byte: u8) -> u64 {
+    (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(lines: usize) -> Vec<String> {
+        (0..lines)
+            .map(|n| format!("    let value_{n} = compute_{}(input, {n});", n % 7))
+            .collect()
+    }
+
+    #[test]
+    fn chunks_cover_the_file_and_survive_edits_elsewhere() {
+        let lines = source(400);
+        let chunks = content_defined_chunks(&lines.join("\n"));
+        assert_eq!(chunks[0].start, 1);
+        assert_eq!(chunks.last().expect("chunks").end, 400);
+        assert!(
+            chunks
+                .windows(2)
+                .all(|pair| pair[1].start == pair[0].end + 1)
+        );
+        assert!(chunks.len() > 400 / MAX_CHUNK_LINES);
+        assert!(
+            chunks
+                .iter()
+                .all(|chunk| ((chunk.end - chunk.start) as usize) < MAX_CHUNK_LINES)
+        );
+
+        // One line inserted at 200: chunks well before it stay put, and
+        // chunks well after it only shift by a line.
+        let mut edited = lines.clone();
+        edited.insert(199, "    log_progress();".to_string());
+        let after = content_defined_chunks(&edited.join("\n"));
+        let before_edit = |chunk: &&LineChunk| chunk.end < 190;
+        assert_eq!(
+            chunks.iter().filter(before_edit).collect::<Vec<_>>(),
+            after.iter().filter(before_edit).collect::<Vec<_>>()
+        );
+        let shifted = after
+            .iter()
+            .filter(|chunk| chunk.start > 300)
+            .map(|chunk| LineChunk {
+                start: chunk.start - 1,
+                end: chunk.end - 1,
+            })
+            .collect::<Vec<_>>();
+        assert!(!shifted.is_empty());
+        assert!(shifted.iter().all(|chunk| chunks.contains(chunk)));
+    }
+
+    #[test]
+    fn chunk_span_widens_to_whole_chunks() {
+        let chunks = [
+            LineChunk { start: 1, end: 5 },
+            LineChunk { start: 6, end: 12 },
+            LineChunk { start: 13, end: 20 },
+        ];
+        assert_eq!(
+            chunk_span(&chunks, 7, 7),
+            Some(LineChunk { start: 6, end: 12 })
+        );
+        assert_eq!(
+            chunk_span(&chunks, 4, 14),
+            Some(LineChunk { start: 1, end: 20 })
+        );
+        assert_eq!(
+            chunk_span(&chunks, 18, 90),
+            Some(LineChunk { start: 13, end: 20 })
+        );
+        assert_eq!(chunk_span(&chunks, 21, 21), None);
+    }
+}
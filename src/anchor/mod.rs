@@ -1,12 +1,61 @@
+use std::borrow::Cow;
 use std::collections::HashSet;
 
+pub mod chunk;
+pub mod normalize;
+pub mod symbol;
 pub mod winnow;
 
 pub use winnow::{SpanAnchor, expand_winnow_anchor, fingerprint_similarity, fingerprint_text};
 
+use normalize::SpanNormalizer;
+
 const WINDOW_LINES: usize = 24;
 const WINDOW_OVERLAP_LINES: usize = 12;
 
+/// Anchors computed from text exactly as recorded.
+pub const ANCHOR_VERSION_RAW: u32 = 1;
+/// Anchors computed from text passed through [`normalize_line_endings`].
+pub const ANCHOR_VERSION_NORMALIZED: u32 = 2;
+
+pub fn anchor_version(normalize_line_endings: bool) -> u32 {
+    if normalize_line_endings {
+        ANCHOR_VERSION_NORMALIZED
+    } else {
+        ANCHOR_VERSION_RAW
+    }
+}
+
+/// Drop a leading UTF-8 BOM and turn CRLF and lone CR line endings into LF,
+/// so a Windows checkout fingerprints like the same file elsewhere.
+pub fn normalize_line_endings(text: &str) -> Cow<'_, str> {
+    let stripped = text.strip_prefix('\u{feff}').unwrap_or(text);
+    if !stripped.contains('\r') {
+        return if stripped.len() == text.len() {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Borrowed(stripped)
+        };
+    }
+    Cow::Owned(stripped.replace("\r\n", "\n").replace('\r', "\n"))
+}
+
+/// Characters kept by [`anchor_preview`].
+pub const ANCHOR_PREVIEW_CHARS: usize = 80;
+
+/// A one-line preview of the text an anchor was fingerprinted from: its
+/// first [`ANCHOR_PREVIEW_CHARS`] characters with whitespace runs collapsed.
+pub fn anchor_preview(text: &str) -> Option<String> {
+    let preview = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(ANCHOR_PREVIEW_CHARS)
+        .collect::<String>();
+    (!preview.is_empty()).then_some(preview)
+}
+
 pub fn fingerprint_anchor_hashes(text: &str) -> Vec<String> {
     collect_window_anchors(text, |window| {
         let fingerprint = fingerprint_text(window).fingerprint;
@@ -39,6 +88,42 @@ pub fn fingerprint_token_hashes(text: &str) -> Vec<String> {
     })
 }
 
+/// [`fingerprint_anchor_hashes`] of `text` as `normalizer` sees it, with
+/// every token tagged by the normalizer (`winnow:rust.v1:<hex>,...`).
+/// Without a normalizer this is [`fingerprint_anchor_hashes`].
+pub fn normalized_anchor_hashes(text: &str, normalizer: Option<&SpanNormalizer>) -> Vec<String> {
+    let Some(normalizer) = normalizer else {
+        return fingerprint_anchor_hashes(text);
+    };
+    let tag = normalizer.tag();
+    fingerprint_anchor_hashes(&normalizer.normalize(text))
+        .iter()
+        .map(|anchor| tag_winnow_anchor(anchor, &tag))
+        .collect()
+}
+
+/// [`fingerprint_token_hashes`] counterpart of [`normalized_anchor_hashes`].
+pub fn normalized_token_hashes(text: &str, normalizer: Option<&SpanNormalizer>) -> Vec<String> {
+    let Some(normalizer) = normalizer else {
+        return fingerprint_token_hashes(text);
+    };
+    let tag = normalizer.tag();
+    fingerprint_token_hashes(&normalizer.normalize(text))
+        .iter()
+        .map(|token| tag_winnow_anchor(token, &tag))
+        .collect()
+}
+
+fn tag_winnow_anchor(anchor: &str, tag: &str) -> String {
+    let tokens = anchor.strip_prefix("winnow:").unwrap_or(anchor);
+    let tagged = tokens
+        .split(',')
+        .map(|token| format!("{tag}:{token}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("winnow:{tagged}")
+}
+
 fn collect_window_anchors<F>(text: &str, anchors_for_window: F) -> Vec<String>
 where
     F: Fn(&str) -> Vec<String>,
@@ -106,7 +191,32 @@ fn split_lines_preserving_terminators(text: &str) -> Vec<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::{fingerprint_anchor_hashes, fingerprint_window_hashes};
+    use super::normalize::normalizer_for_tag;
+    use super::{
+        expand_winnow_anchor, fingerprint_anchor_hashes, fingerprint_similarity,
+        fingerprint_window_hashes, normalize_line_endings, normalized_anchor_hashes,
+        normalized_token_hashes,
+    };
+
+    #[test]
+    fn normalized_text_fingerprints_like_lf_text() {
+        let lf = (0..60)
+            .map(|n| format!("let value_{n} = compute(input, {n});\n"))
+            .collect::<String>();
+        let crlf = format!("\u{feff}{}", lf.replace('\n', "\r\n"));
+        let cr = lf.replace('\n', "\r");
+        assert_eq!(normalize_line_endings(&crlf), lf);
+        assert_eq!(normalize_line_endings(&cr), lf);
+        assert_eq!(normalize_line_endings(&lf), lf);
+        assert_ne!(
+            fingerprint_anchor_hashes(&cr),
+            fingerprint_anchor_hashes(&lf)
+        );
+        assert_eq!(
+            fingerprint_anchor_hashes(&normalize_line_endings(&cr)),
+            fingerprint_anchor_hashes(&lf)
+        );
+    }
 
     #[test]
     fn short_text_emits_window_anchor() {
@@ -149,4 +259,34 @@ mod tests {
             anchors.len()
         );
     }
+
+    #[test]
+    fn normalized_anchors_are_tagged_and_ignore_cosmetic_edits() {
+        let rust = normalizer_for_tag("rust.v1");
+        let plain = "fn total(items: &[u32]) -> u32 {\n    items.iter().sum()\n}\n";
+        let commented = "// Sum of all items.\nfn total(items: &[u32]) -> u32 {\n    items.iter().sum() // fast\n}\n";
+        assert_ne!(
+            fingerprint_anchor_hashes(plain),
+            fingerprint_anchor_hashes(commented)
+        );
+        let anchors = normalized_anchor_hashes(plain, rust);
+        assert_eq!(anchors, normalized_anchor_hashes(commented, rust));
+        assert!(anchors[0].starts_with("winnow:rust.v1:"));
+        assert!(
+            expand_winnow_anchor(&anchors[0])
+                .iter()
+                .all(|token| token.starts_with("winnow:rust.v1:"))
+        );
+        let tokens = normalized_token_hashes(plain, rust);
+        assert!(
+            tokens
+                .iter()
+                .all(|token| token.starts_with("winnow:rust.v1:"))
+        );
+        assert_eq!(fingerprint_similarity(&anchors[0], &anchors[0]), Some(1.0));
+        assert_eq!(
+            normalized_anchor_hashes(plain, None),
+            fingerprint_anchor_hashes(plain)
+        );
+    }
 }
@@ -0,0 +1,261 @@
+//! Per-language span normalizers. Before fingerprinting, a normalizer
+//! strips comments, writes interchangeable string quotes one way and drops
+//! trivia (trailing commas, trailing whitespace), so a cosmetic edit leaves
+//! a span's anchors alone. Lines are kept where they are: a removed comment
+//! leaves its line empty, so ranges and windows still line up with the file.
+//!
+//! Normalized anchors carry the normalizer's tag (`winnow:rust.v1:<hex>`);
+//! changing a normalizer's rules means bumping its version, which keeps its
+//! anchors from ever matching ones computed under the old rules.
+
+/// One language's normalization rules.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SpanNormalizer {
+    pub name: &'static str,
+    pub version: u32,
+    /// Lowercase file extensions the normalizer applies to.
+    pub extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+    /// Characters that delimit string (or character) literals.
+    string_quotes: &'static [char],
+    /// Quotes that mean the same thing, written as `"` after normalizing.
+    interchangeable_quotes: &'static [char],
+}
+
+const REGISTRY: &[SpanNormalizer] = &[
+    SpanNormalizer {
+        name: "rust",
+        version: 1,
+        extensions: &["rs"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        // `'` also starts lifetimes, so only `"` delimits strings.
+        string_quotes: &['"'],
+        interchangeable_quotes: &[],
+    },
+    SpanNormalizer {
+        name: "c",
+        version: 1,
+        extensions: &[
+            "c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx", "m", "mm", "java", "cs", "go", "kt",
+            "kts", "swift", "scala", "dart",
+        ],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        string_quotes: &['"', '\'', '`'],
+        interchangeable_quotes: &[],
+    },
+    SpanNormalizer {
+        name: "javascript",
+        version: 1,
+        extensions: &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        string_quotes: &['"', '\'', '`'],
+        interchangeable_quotes: &['"', '\''],
+    },
+    SpanNormalizer {
+        name: "python",
+        version: 1,
+        extensions: &["py", "pyi"],
+        line_comments: &["#"],
+        block_comments: &[],
+        string_quotes: &['"', '\''],
+        interchangeable_quotes: &['"', '\''],
+    },
+    SpanNormalizer {
+        name: "shell",
+        version: 1,
+        extensions: &["sh", "bash", "zsh"],
+        line_comments: &["#"],
+        block_comments: &[],
+        string_quotes: &['"', '\''],
+        interchangeable_quotes: &[],
+    },
+    SpanNormalizer {
+        name: "sql",
+        version: 1,
+        extensions: &["sql"],
+        line_comments: &["--"],
+        block_comments: &[("/*", "*/")],
+        string_quotes: &['\''],
+        interchangeable_quotes: &[],
+    },
+];
+
+/// Every span normalizer, for `anchors.normalizers`.
+pub fn normalizer_registry() -> &'static [SpanNormalizer] {
+    REGISTRY
+}
+
+/// The normalizer whose [`SpanNormalizer::tag`] is `tag`. Tags of retired
+/// versions find nothing.
+pub fn normalizer_for_tag(tag: &str) -> Option<&'static SpanNormalizer> {
+    REGISTRY.iter().find(|normalizer| normalizer.tag() == tag)
+}
+
+/// The normalizer for `path`'s extension, if `enabled` (normalizer names, or
+/// `all`) selects it.
+pub fn normalizer_for_path(path: &str, enabled: &[String]) -> Option<&'static SpanNormalizer> {
+    if enabled.is_empty() {
+        return None;
+    }
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let (_, extension) = name.rsplit_once('.')?;
+    let extension = extension.to_ascii_lowercase();
+    REGISTRY.iter().find(|normalizer| {
+        normalizer.extensions.contains(&extension.as_str())
+            && enabled
+                .iter()
+                .any(|name| name == "all" || name == normalizer.name)
+    })
+}
+
+impl SpanNormalizer {
+    /// `<name>.v<version>`, as recorded on tape events and in anchors.
+    pub fn tag(&self) -> String {
+        format!("{}.v{}", self.name, self.version)
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        let stripped = drop_trailing_commas(&self.strip_comments_and_quotes(text));
+        let mut out = stripped
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.ends_with('\n') {
+            out.push('\n');
+        }
+        out
+    }
+
+    fn strip_comments_and_quotes(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if self.string_quotes.contains(&c) {
+                let len = string_literal_len(rest, c);
+                let literal = &rest[..len];
+                if self.interchangeable_quotes.contains(&c) && c != '"' {
+                    let inner = &literal[c.len_utf8()..];
+                    let inner = inner.strip_suffix(c).unwrap_or(inner);
+                    out.push('"');
+                    out.push_str(inner);
+                    if literal.len() > c.len_utf8() && literal.ends_with(c) {
+                        out.push('"');
+                    }
+                } else {
+                    out.push_str(literal);
+                }
+                rest = &rest[len..];
+            } else if self
+                .line_comments
+                .iter()
+                .any(|marker| rest.starts_with(*marker))
+            {
+                rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+            } else if let Some((open, close)) = self
+                .block_comments
+                .iter()
+                .find(|(open, _)| rest.starts_with(*open))
+            {
+                let body = &rest[open.len()..];
+                let end = body.find(close).map_or(body.len(), |at| at + close.len());
+                // Keep the comment's line breaks so later lines stay put.
+                out.extend(body[..end].chars().filter(|c| *c == '\n'));
+                rest = &body[end..];
+            } else {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        out
+    }
+}
+
+/// The length of the literal at the start of `text`, opened by `quote`: up
+/// to and including the closing quote, or to the end of the line when it is
+/// not closed there.
+fn string_literal_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (at, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return at + c.len_utf8();
+        } else if c == '\n' && quote != '`' {
+            return at;
+        }
+    }
+    text.len()
+}
+
+/// Remove commas that only precede a closing bracket (whitespace and line
+/// breaks in between are kept).
+fn drop_trailing_commas(text: &str) -> String {
+    text.char_indices()
+        .filter(|(at, c)| *c != ',' || !text[at + 1..].trim_start().starts_with([')', ']', '}']))
+        .map(|(_, c)| c)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalizer_for_path, normalizer_for_tag, normalizer_registry};
+
+    #[test]
+    fn normalizers_are_selected_by_extension_and_config() {
+        let rust = normalizer_for_path("src/Lib.RS", &["rust".to_string()]).expect("rust");
+        assert_eq!(rust.tag(), "rust.v1");
+        assert_eq!(normalizer_for_tag("rust.v1"), Some(rust));
+        assert_eq!(normalizer_for_tag("rust.v0"), None);
+        assert!(normalizer_for_path("src/lib.rs", &["python".to_string()]).is_none());
+        assert!(normalizer_for_path("src/lib.rs", &[]).is_none());
+        assert_eq!(
+            normalizer_for_path("web/app.tsx", &["all".to_string()]).map(|n| n.name),
+            Some("javascript")
+        );
+        assert!(normalizer_for_path("Makefile", &["all".to_string()]).is_none());
+        let mut tags = normalizer_registry()
+            .iter()
+            .map(|normalizer| normalizer.tag())
+            .collect::<Vec<_>>();
+        tags.dedup();
+        assert_eq!(tags.len(), normalizer_registry().len());
+    }
+
+    #[test]
+    fn cosmetic_edits_normalize_to_the_same_text() {
+        let python = normalizer_for_tag("python.v1").expect("python");
+        let before = "def greet(name):\n    # say hello\n    return f'hi {name}'  \n";
+        let after = "def greet(name):\n\n    return f\"hi {name}\"  # friendlier\n";
+        assert_eq!(python.normalize(before), python.normalize(after));
+        assert_eq!(
+            python.normalize(before),
+            "def greet(name):\n\n    return f\"hi {name}\"\n"
+        );
+        // A `#` inside a string is not a comment.
+        assert_eq!(python.normalize("x = '#1'\n"), "x = \"#1\"\n");
+
+        let rust = normalizer_for_tag("rust.v1").expect("rust");
+        let before = "let v = vec![\n    1,\n    2,\n];\nlet s = \"// kept\"; /* gone\n   too */ let t: &'a str = s;\n";
+        assert_eq!(
+            rust.normalize(before),
+            "let v = vec![\n    1,\n    2\n];\nlet s = \"// kept\";\n let t: &'a str = s;\n"
+        );
+        assert_eq!(
+            rust.normalize(before).lines().count(),
+            before.lines().count()
+        );
+
+        let sql = normalizer_for_tag("sql.v1").expect("sql");
+        assert_eq!(
+            sql.normalize("select 'a--b' -- note\nfrom t\n"),
+            "select 'a--b'\nfrom t\n"
+        );
+    }
+}
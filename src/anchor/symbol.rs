@@ -0,0 +1,188 @@
+use super::chunk::LineChunk;
+
+/// Words that open a definition, in the languages agents most often edit.
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn",
+    "def",
+    "function",
+    "func",
+    "class",
+    "impl",
+    "trait",
+    "struct",
+    "enum",
+    "mod",
+    "interface",
+];
+/// Words that may precede a definition keyword on the same line.
+const DEFINITION_MODIFIERS: &[&str] = &[
+    "pub",
+    "pub(crate)",
+    "pub(super)",
+    "async",
+    "unsafe",
+    "const",
+    "extern",
+    "export",
+    "default",
+    "static",
+    "public",
+    "private",
+    "protected",
+    "abstract",
+];
+
+/// The innermost definition (function, class, impl, ...) whose body covers
+/// lines `start..=end`, found from indentation and braces rather than a
+/// parser. `None` when no definition encloses the whole range.
+pub fn enclosing_definition(text: &str, start: u32, end: u32) -> Option<LineChunk> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let first = start.checked_sub(1)? as usize;
+    let last = (end as usize).min(lines.len()).checked_sub(1)?;
+    if first > last {
+        return None;
+    }
+    let target_indent = lines[first..=last]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent(line))
+        .min()?;
+    (0..=first)
+        .rev()
+        .filter(|at| is_definition(lines[*at]) && indent(lines[*at]) <= target_indent)
+        .find_map(|at| {
+            let block_end = block_end(&lines, at)?;
+            (block_end >= last).then_some(LineChunk {
+                start: at as u32 + 1,
+                end: block_end as u32 + 1,
+            })
+        })
+}
+
+/// Every definition in `text` with the lines its block spans, in order of
+/// their first line; nested definitions (methods in an `impl` or class)
+/// are listed after the one enclosing them.
+pub fn definitions(text: &str) -> Vec<LineChunk> {
+    let lines = text.lines().collect::<Vec<_>>();
+    (0..lines.len())
+        .filter(|at| is_definition(lines[*at]))
+        .filter_map(|at| {
+            Some(LineChunk {
+                start: at as u32 + 1,
+                end: block_end(&lines, at)? as u32 + 1,
+            })
+        })
+        .collect()
+}
+
+fn is_definition(line: &str) -> bool {
+    for word in line.split_whitespace() {
+        let word = word.split(['(', '<', ':', '{']).next().unwrap_or_default();
+        if DEFINITION_KEYWORDS.contains(&word) {
+            return true;
+        }
+        if !DEFINITION_MODIFIERS.contains(&word) {
+            return false;
+        }
+    }
+    false
+}
+
+/// Last line of the block a definition at `at` opens: where its braces
+/// close, or for `:`-terminated headers, the last line indented past it.
+fn block_end(lines: &[&str], at: usize) -> Option<usize> {
+    let header_indent = indent(lines[at]);
+    let mut depth = 0i64;
+    let mut opened = false;
+    for (offset, line) in lines[at..].iter().enumerate() {
+        for ch in line.chars() {
+            match ch {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if opened && depth <= 0 {
+            return Some(at + offset);
+        }
+        if !opened && line.trim_end().ends_with(':') {
+            let body_end = lines[at + offset + 1..]
+                .iter()
+                .take_while(|line| line.trim().is_empty() || indent(line) > header_indent)
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(body, _)| at + offset + 1 + body)
+                .last();
+            return Some(body_end.unwrap_or(at + offset));
+        }
+        if !opened && line.trim_end().ends_with(';') {
+            return Some(at + offset);
+        }
+    }
+    None
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_innermost_enclosing_definition() {
+        let rust = "\
+use std::fmt;
+
+impl Widget {
+    pub fn new() -> Self {
+        Self { size: 1 }
+    }
+
+    fn grow(&mut self) {
+        self.size += 1;
+    }
+}
+";
+        assert_eq!(
+            enclosing_definition(rust, 9, 9),
+            Some(LineChunk { start: 8, end: 10 })
+        );
+        assert_eq!(
+            enclosing_definition(rust, 5, 9),
+            Some(LineChunk { start: 3, end: 11 })
+        );
+        assert_eq!(enclosing_definition(rust, 1, 1), None);
+        assert_eq!(
+            definitions(rust),
+            vec![
+                LineChunk { start: 3, end: 11 },
+                LineChunk { start: 4, end: 6 },
+                LineChunk { start: 8, end: 10 },
+            ]
+        );
+
+        let python = "\
+class Cart:
+    def total(self):
+        subtotal = sum(self.items)
+
+        return subtotal * TAX
+
+    def empty(self):
+        return not self.items
+";
+        assert_eq!(
+            enclosing_definition(python, 3, 3),
+            Some(LineChunk { start: 2, end: 5 })
+        );
+        assert_eq!(
+            enclosing_definition(python, 5, 8),
+            Some(LineChunk { start: 1, end: 8 })
+        );
+    }
+}
@@ -163,7 +163,8 @@ fn parse_fingerprint(raw: &str) -> Option<HashSet<u64>> {
         let values = rest
             .split(',')
             .filter(|value| !value.is_empty())
-            .map(|value| u64::from_str_radix(value, 16).ok())
+            // Tokens of normalized anchors are tagged: `rust.v1:<hex>`.
+            .map(|value| u64::from_str_radix(value.rsplit(':').next().unwrap_or(value), 16).ok())
             .collect::<Option<Vec<_>>>()?;
         return Some(values.into_iter().collect());
     }
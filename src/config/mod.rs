@@ -1,19 +1,206 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
 
+use crate::index::lineage::LINK_THRESHOLD_DEFAULT;
+use crate::index::similarity::{DEFAULT_SCORER, scorer_for_name};
 use crate::store::atomic::atomic_write;
+use crate::tape::environment::EnvironmentField;
+use crate::tape::refs::DEFAULT_ISSUE_PATTERNS;
+use crate::tape::worktree::RepoLayout;
 use serde::Deserialize;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Tapes indexed between automatic `maintain` runs unless configured.
+pub const DEFAULT_AUTO_MAINTAIN_AFTER_INGESTS: u64 = 200;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct EffectiveConfig {
     pub path: PathBuf,
+    /// The `profiles.<name>` entry applied over the layered config, if any.
+    pub profile: Option<String>,
     pub db: PathBuf,
     pub tapes_dir: PathBuf,
     pub additional_stores: Vec<PathBuf>,
     pub explain_default_limit: usize,
     pub peek: EffectivePeekConfig,
     pub metrics: EffectiveMetricsConfig,
+    pub audit: EffectiveAuditConfig,
     pub watch: Option<EffectiveWatchConfig>,
+    /// `watch.log`, or where it would go without a `watch` section; watchlist
+    /// hooks log there too.
+    pub watch_log: PathBuf,
+    pub storage: StorageDirs,
+    /// `storage.quota`: the store size `ingest` and `stats` warn about.
+    pub storage_quota: Option<StorageQuota>,
+    pub summarize: Option<EffectiveSummarizeConfig>,
+    pub export: EffectiveExportConfig,
+    pub embeddings: Option<EffectiveEmbeddingsConfig>,
+    pub maintenance: EffectiveMaintenanceConfig,
+    /// Ingest in bulk-load mode by default (see `engram ingest --fast-ingest`).
+    pub fast_ingest: bool,
+    /// Keep a compressed copy of every converted harness input.
+    pub retain_raw: bool,
+    /// Store the old/new text of every ingested edit in the objects dir.
+    pub retain_edits: bool,
+    /// Append a `state.snapshot` event (git HEAD and the hash of every
+    /// edited file on disk) to each ingested tape.
+    pub snapshot_state: bool,
+    /// Machine identity and tool versions stamped on each new tape's meta
+    /// event (`ingest.environment`).
+    pub environment: Vec<EnvironmentField>,
+    /// Per event kind (`tool.result`, ...), how ingest shrinks oversized
+    /// output fields.
+    pub sample_outputs: BTreeMap<String, OutputSampling>,
+    /// Per-tape caps on what ingest writes to the index.
+    pub ingest_limits: IngestLimits,
+    /// What ingest does with reads and edits of binary, generated or
+    /// oversized files (`ingest.binary`).
+    pub binary_files: BinaryFilePolicy,
+    /// Globs (`node_modules/**`, `*.lock`) of files whose reads and edits
+    /// ingest drops before indexing.
+    pub ignore: Vec<String>,
+    /// Regexes for the issue keys ingest records per tape (`issue_patterns`).
+    pub issue_patterns: Vec<String>,
+    /// Monorepo projects: name to repo-relative globs of the directories it
+    /// owns (`projects`). Ingest tags evidence with the first match by name.
+    pub projects: BTreeMap<String, Vec<String>>,
+    /// Strip BOMs and normalize CRLF/CR to LF before computing anchors
+    /// (`anchors.normalize_line_endings`).
+    pub normalize_line_endings: bool,
+    /// Span normalizers (`rust`, `python`, ... or `all`) applied to evidence
+    /// before fingerprinting (`anchors.normalizers`).
+    pub normalizers: Vec<String>,
+    /// Confidence at or above which a stored edge counts as lineage rather
+    /// than location-only (`lineage.link_threshold`).
+    pub link_threshold: f32,
+    /// Scorer for edits whose harness records no similarity
+    /// (`lineage.scorer`).
+    pub scorer: String,
+    /// The directory whose `.engram` is the repo-local store: the working
+    /// directory, or the main worktree's for a linked git worktree with
+    /// `worktrees.share_store` (see [`local_store_root`]).
+    pub store_root: PathBuf,
+}
+
+/// `ingest.sample.<kind>`: string fields longer than `max_bytes` are stored
+/// as their first and last `keep_bytes` plus size, line count and SHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSampling {
+    pub max_bytes: usize,
+    #[serde(default = "default_sample_keep_bytes")]
+    pub keep_bytes: usize,
+}
+
+fn default_sample_keep_bytes() -> usize {
+    2048
+}
+
+/// `ingest.limits`: per-tape caps on indexed events, evidence rows and
+/// lineage edges. Tapes past a cap are stored whole but indexed partially.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IngestLimits {
+    #[serde(default)]
+    pub max_events: Option<usize>,
+    #[serde(default)]
+    pub max_evidence_rows: Option<usize>,
+    #[serde(default)]
+    pub max_edges: Option<usize>,
+}
+
+/// `storage.quota`: `ingest` and `stats` warn when the index plus tapes
+/// exceed `max_bytes`, or will within `horizon_days` at their recent growth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StorageQuota {
+    pub max_bytes: u64,
+    #[serde(default = "default_quota_horizon_days")]
+    pub horizon_days: u32,
+}
+
+/// `storage.quota.horizon_days` when unset, and the horizon `stats`
+/// forecasts over without a quota.
+pub const DEFAULT_QUOTA_HORIZON_DAYS: u32 = 30;
+
+fn default_quota_horizon_days() -> u32 {
+    DEFAULT_QUOTA_HORIZON_DAYS
+}
+
+/// `ingest.binary`: how ingest treats reads and edits of binary files,
+/// generated assets (`*.min.js`, source maps) and texts over
+/// `max_text_bytes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BinaryFilePolicy {
+    #[serde(default)]
+    pub policy: BinaryPolicy,
+    #[serde(default = "default_binary_max_text_bytes")]
+    pub max_text_bytes: usize,
+}
+
+impl Default for BinaryFilePolicy {
+    fn default() -> Self {
+        Self {
+            policy: BinaryPolicy::default(),
+            max_text_bytes: default_binary_max_text_bytes(),
+        }
+    }
+}
+
+fn default_binary_max_text_bytes() -> usize {
+    1024 * 1024
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BinaryPolicy {
+    /// Drop the events from the tape.
+    Skip,
+    /// Keep the events, without the text anchors would be computed from.
+    #[default]
+    EvidenceOnly,
+    /// Treat them like any other file.
+    Full,
+}
+
+impl BinaryPolicy {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::EvidenceOnly => "evidence-only",
+            Self::Full => "full",
+        }
+    }
+}
+
+/// Where user-level data (global index, tapes, logs) and caches live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageLayout {
+    /// `~/.engram` and `~/.engram-cache`.
+    #[default]
+    Legacy,
+    /// `$XDG_DATA_HOME/engram` and `$XDG_CACHE_HOME/engram`.
+    Xdg,
+}
+
+/// How the index splits evidence by time; see `engram maintain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexPartitioning {
+    /// Everything lives in the main index file.
+    #[default]
+    None,
+    /// Tapes from past months move into one index file per month.
+    Monthly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageDirs {
+    pub layout: StorageLayout,
+    pub data_dir: PathBuf,
+    pub cache_dir: PathBuf,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -30,6 +217,64 @@ pub struct EffectiveMetricsConfig {
     pub log: PathBuf,
 }
 
+/// `audit`: a local log of every command run, its arguments and how long it
+/// took. Off unless enabled; it is never sent anywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveAuditConfig {
+    pub enabled: bool,
+    pub log: PathBuf,
+}
+
+/// `export`: how `engram export` treats data leaving the machine.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EffectiveExportConfig {
+    /// Name of the environment variable holding the HMAC key that replaces
+    /// tape ids, session ids, host and user names with pseudonyms. Exports
+    /// are not pseudonymized when unset.
+    pub pseudonymize_key_env: Option<String>,
+}
+
+/// OpenAI-compatible chat endpoint used by `explain --summarize`. Only
+/// present when the config has a `summarize:` section with both fields set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveSummarizeConfig {
+    pub base_url: String,
+    pub model: String,
+    /// Name of the environment variable holding the API key, if any.
+    pub api_key_env: Option<String>,
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProviderKind {
+    /// Local feature-hashing vectors; no network, no model download.
+    #[default]
+    Hashing,
+    /// OpenAI-compatible `POST {base_url}/embeddings`.
+    Openai,
+}
+
+/// Per-event embeddings computed at ingest for `search --semantic`. Only
+/// present when the config has an `embeddings:` section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveEmbeddingsConfig {
+    pub provider: EmbeddingProviderKind,
+    pub dims: usize,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    pub api_key_env: Option<String>,
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectiveMaintenanceConfig {
+    /// Run `engram maintain` automatically once this many tapes have been
+    /// indexed since the last run; 0 disables the automatic trigger.
+    pub auto_after_ingests: u64,
+    pub partitioning: IndexPartitioning,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EffectiveWatchConfig {
     pub debounce_secs: u64,
@@ -45,15 +290,105 @@ pub struct EffectiveWatchSource {
     pub glob: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ParsedConfig {
     pub db: Option<String>,
     pub tapes_dir: Option<String>,
     pub additional_stores: Vec<String>,
+    pub ignore: Vec<String>,
+    pub issue_patterns: Option<Vec<String>>,
+    pub projects: BTreeMap<String, Vec<String>>,
     pub explain: Option<ParsedExplainConfig>,
     pub peek: Option<ParsedPeekConfig>,
     pub metrics: Option<ParsedMetricsConfig>,
+    pub audit: Option<ParsedAuditConfig>,
     pub watch: Option<ParsedWatchConfig>,
+    pub storage: Option<ParsedStorageConfig>,
+    pub summarize: Option<ParsedSummarizeConfig>,
+    pub export: Option<ParsedExportConfig>,
+    pub embeddings: Option<ParsedEmbeddingsConfig>,
+    pub maintenance: Option<ParsedMaintenanceConfig>,
+    pub ingest: Option<ParsedIngestConfig>,
+    pub anchors: Option<ParsedAnchorsConfig>,
+    pub lineage: Option<ParsedLineageConfig>,
+    pub worktrees: Option<ParsedWorktreesConfig>,
+    pub profiles: BTreeMap<String, ParsedProfileConfig>,
+}
+
+/// `profiles.<name>`: a store and source set selected with `--profile` or
+/// `ENGRAM_PROFILE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedProfileConfig {
+    pub store: Option<String>,
+    pub db: Option<String>,
+    pub tapes_dir: Option<String>,
+    pub sources: Vec<ParsedWatchSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedAnchorsConfig {
+    pub normalize_line_endings: Option<bool>,
+    pub normalizers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedLineageConfig {
+    pub link_threshold: Option<f32>,
+    pub scorer: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedWorktreesConfig {
+    pub share_store: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedIngestConfig {
+    pub fast: Option<bool>,
+    pub retain_raw: Option<bool>,
+    pub retain_edits: Option<bool>,
+    pub snapshot: Option<bool>,
+    pub environment: Option<Vec<EnvironmentField>>,
+    pub sample: Option<BTreeMap<String, OutputSampling>>,
+    pub limits: Option<IngestLimits>,
+    pub binary: Option<BinaryFilePolicy>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedMaintenanceConfig {
+    pub auto_after_ingests: Option<u64>,
+    pub partitioning: Option<IndexPartitioning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedEmbeddingsConfig {
+    pub provider: Option<EmbeddingProviderKind>,
+    pub dims: Option<usize>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    pub api_key_env: Option<String>,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedExportConfig {
+    pub pseudonymize_key_env: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedSummarizeConfig {
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    pub api_key_env: Option<String>,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedStorageConfig {
+    pub layout: Option<StorageLayout>,
+    pub data_dir: Option<String>,
+    pub cache_dir: Option<String>,
+    pub quota: Option<StorageQuota>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -75,6 +410,12 @@ pub struct ParsedMetricsConfig {
     pub log: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedAuditConfig {
+    pub enabled: Option<bool>,
+    pub log: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParsedWatchConfig {
     pub debounce_secs: Option<u64>,
@@ -100,13 +441,163 @@ struct RawConfig {
     #[serde(default)]
     additional_stores: Option<Vec<String>>,
     #[serde(default)]
+    ignore: Option<Vec<String>>,
+    #[serde(default)]
+    issue_patterns: Option<Vec<String>>,
+    #[serde(default)]
+    projects: Option<BTreeMap<String, Vec<String>>>,
+    #[serde(default)]
     explain: Option<RawExplainConfig>,
     #[serde(default)]
     peek: Option<RawPeekConfig>,
     #[serde(default)]
     metrics: Option<RawMetricsConfig>,
     #[serde(default)]
+    audit: Option<RawAuditConfig>,
+    #[serde(default)]
     watch: Option<RawWatchConfig>,
+    #[serde(default)]
+    storage: Option<RawStorageConfig>,
+    #[serde(default)]
+    summarize: Option<RawSummarizeConfig>,
+    #[serde(default)]
+    export: Option<RawExportConfig>,
+    #[serde(default)]
+    embeddings: Option<RawEmbeddingsConfig>,
+    #[serde(default)]
+    maintenance: Option<RawMaintenanceConfig>,
+    #[serde(default)]
+    ingest: Option<RawIngestConfig>,
+    #[serde(default)]
+    anchors: Option<RawAnchorsConfig>,
+    #[serde(default)]
+    lineage: Option<RawLineageConfig>,
+    #[serde(default)]
+    worktrees: Option<RawWorktreesConfig>,
+    #[serde(default)]
+    profiles: Option<BTreeMap<String, RawProfileConfig>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawProfileConfig {
+    /// A data directory laid out like `.engram`: `index.sqlite`, `tapes/`,
+    /// `watch.log` and `metrics.jsonl` live under it unless set explicitly.
+    #[serde(default)]
+    store: Option<String>,
+    #[serde(default)]
+    db: Option<String>,
+    #[serde(default)]
+    tapes_dir: Option<String>,
+    #[serde(default)]
+    sources: Option<Vec<RawWatchSource>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAnchorsConfig {
+    #[serde(default)]
+    normalize_line_endings: Option<bool>,
+    #[serde(default)]
+    normalizers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLineageConfig {
+    #[serde(default)]
+    link_threshold: Option<f32>,
+    #[serde(default)]
+    scorer: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawWorktreesConfig {
+    /// Linked git worktrees use the main worktree's `.engram` store and
+    /// config instead of their own.
+    #[serde(default)]
+    share_store: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawIngestConfig {
+    #[serde(default)]
+    fast: Option<bool>,
+    #[serde(default)]
+    retain_raw: Option<bool>,
+    #[serde(default)]
+    retain_edits: Option<bool>,
+    #[serde(default)]
+    snapshot: Option<bool>,
+    #[serde(default)]
+    environment: Option<Vec<EnvironmentField>>,
+    #[serde(default)]
+    sample: Option<BTreeMap<String, OutputSampling>>,
+    #[serde(default)]
+    limits: Option<IngestLimits>,
+    #[serde(default)]
+    binary: Option<BinaryFilePolicy>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMaintenanceConfig {
+    #[serde(default)]
+    auto_after_ingests: Option<u64>,
+    #[serde(default)]
+    partitioning: Option<IndexPartitioning>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEmbeddingsConfig {
+    #[serde(default)]
+    provider: Option<EmbeddingProviderKind>,
+    #[serde(default)]
+    dims: Option<usize>,
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    api_key_env: Option<String>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawExportConfig {
+    #[serde(default)]
+    pseudonymize_key_env: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSummarizeConfig {
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    api_key_env: Option<String>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawStorageConfig {
+    #[serde(default)]
+    layout: Option<StorageLayout>,
+    #[serde(default)]
+    data_dir: Option<String>,
+    #[serde(default)]
+    cache_dir: Option<String>,
+    #[serde(default)]
+    quota: Option<StorageQuota>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -138,6 +629,15 @@ struct RawMetricsConfig {
     log: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAuditConfig {
+    #[serde(default)]
+    enabled: Option<bool>,
+    #[serde(default)]
+    log: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 struct RawWatchConfig {
@@ -165,6 +665,11 @@ pub enum ConfigError {
     Io(std::io::Error),
     Yaml(serde_yaml::Error),
     InvalidPath(String),
+    UnknownProfile(String),
+    UnknownNormalizer(String),
+    InvalidLinkThreshold(f32),
+    UnknownScorer(String),
+    InvalidProjectGlob(String, String),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -173,6 +678,34 @@ impl std::fmt::Display for ConfigError {
             Self::Io(err) => write!(f, "{err}"),
             Self::Yaml(err) => write!(f, "{err}"),
             Self::InvalidPath(value) => write!(f, "invalid path `{value}`"),
+            Self::UnknownProfile(name) => {
+                write!(f, "no `profiles.{name}` entry in any config file")
+            }
+            Self::InvalidLinkThreshold(value) => write!(
+                f,
+                "`lineage.link_threshold` must be between 0.0 and 1.0, got {value}"
+            ),
+            Self::UnknownScorer(name) => write!(
+                f,
+                "unknown similarity scorer `{name}` in `lineage.scorer` (expected one of {})",
+                crate::index::similarity::scorer_registry()
+                    .iter()
+                    .map(|scorer| format!("`{}`", scorer.name()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::InvalidProjectGlob(name, err) => {
+                write!(f, "invalid glob in `projects.{name}`: {err}")
+            }
+            Self::UnknownNormalizer(name) => write!(
+                f,
+                "unknown span normalizer `{name}` in `anchors.normalizers` (expected one of {} or `all`)",
+                crate::anchor::normalize::normalizer_registry()
+                    .iter()
+                    .map(|normalizer| format!("`{}`", normalizer.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
     }
 }
@@ -200,6 +733,20 @@ pub fn load_effective_config_with_override(
     home: &Path,
     config_override: Option<&Path>,
 ) -> Result<EffectiveConfig, ConfigError> {
+    load_effective_config_for_profile(cwd, home, config_override, None)
+}
+
+/// Like [`load_effective_config_with_override`], with the `profiles.<name>`
+/// entry from the nearest config layer that defines it applied on top: its
+/// `store`, `db` and `tapes_dir` replace the layered storage paths, and its
+/// `sources` replace the watch sources.
+pub fn load_effective_config_for_profile(
+    cwd: &Path,
+    home: &Path,
+    config_override: Option<&Path>,
+    profile: Option<&str>,
+) -> Result<EffectiveConfig, ConfigError> {
+    let cwd = &local_store_root(cwd, home);
     let user_config_path = ensure_user_config(home)?;
     let config_chain = if let Some(path) = config_override {
         vec![normalize_path(path)]
@@ -210,10 +757,22 @@ pub fn load_effective_config_with_override(
         .first()
         .cloned()
         .unwrap_or_else(|| user_config_path.clone());
-    let default_db = home.join(".engram").join("index.sqlite");
-    let default_tapes_dir = cwd.join(".engram").join("tapes");
-    let default_watch_log = home.join(".engram").join("watch.log");
-    let default_metrics_log = home.join(".engram").join("metrics.jsonl");
+    let storage = resolve_storage_dirs(&config_chain, home, |key| std::env::var_os(key))?;
+    let rebase = |path: PathBuf| rebase_legacy_path(path, home, &storage);
+    let selected = match profile {
+        Some(name) => Some(resolve_profile(&config_chain, home, name)?),
+        None => None,
+    };
+    let store = selected.as_ref().and_then(|profile| profile.store.clone());
+    let data_dir = store.as_deref().unwrap_or(&storage.data_dir);
+    let default_db = data_dir.join("index.sqlite");
+    let default_tapes_dir = match &store {
+        Some(store) => store.join("tapes"),
+        None => cwd.join(".engram").join("tapes"),
+    };
+    let default_watch_log = data_dir.join("watch.log");
+    let default_metrics_log = data_dir.join("metrics.jsonl");
+    let default_audit_log = storage.cache_dir.join("audit.log");
     let default_explain_limit = 10usize;
     let default_peek = EffectivePeekConfig {
         default_lines: 30,
@@ -221,13 +780,45 @@ pub fn load_effective_config_with_override(
         default_after: 10,
         grep_context: 5,
     };
-    let mut db = None;
-    let mut tapes_dir = None;
+    let mut db = selected.as_ref().and_then(|profile| {
+        profile
+            .db
+            .clone()
+            .or_else(|| profile.store.as_ref().map(|_| default_db.clone()))
+    });
+    let mut tapes_dir = selected.as_ref().and_then(|profile| {
+        profile
+            .tapes_dir
+            .clone()
+            .or_else(|| profile.store.as_ref().map(|_| default_tapes_dir.clone()))
+    });
     let mut additional_stores = None;
+    let mut ignore = None;
+    let mut issue_patterns = None;
+    let mut projects = None;
     let mut explain_default_limit = None;
     let mut peek = None;
     let mut metrics = None;
+    let mut audit = None;
     let mut watch = None;
+    let mut summarize = None;
+    let mut pseudonymize_key_env = None;
+    let mut embeddings = None;
+    let mut auto_after_ingests = None;
+    let mut partitioning = None;
+    let mut fast_ingest = None;
+    let mut retain_raw = None;
+    let mut retain_edits = None;
+    let mut snapshot_state = None;
+    let mut environment = None;
+    let mut sample_outputs = None;
+    let mut ingest_limits = None;
+    let mut binary_files = None;
+    let mut normalize_line_endings = None;
+    let mut normalizers = None;
+    let mut link_threshold = None;
+    let mut storage_quota = None;
+    let mut scorer = None;
 
     for layer_path in &config_chain {
         let raw = load_raw_config_file(layer_path)?;
@@ -235,7 +826,7 @@ pub fn load_effective_config_with_override(
         if db.is_none()
             && let Some(raw_db) = raw.db.as_deref()
         {
-            db = Some(resolve_path(raw_db, &base_dir, home)?);
+            db = Some(rebase(resolve_path(raw_db, &base_dir, home)?));
         }
         if additional_stores.is_none()
             && let Some(raw_stores) = raw.additional_stores.as_ref()
@@ -246,6 +837,46 @@ pub fn load_effective_config_with_override(
             }
             additional_stores = Some(resolved);
         }
+        if ignore.is_none() {
+            ignore = raw.ignore.clone();
+        }
+        if issue_patterns.is_none() {
+            issue_patterns = raw.issue_patterns.clone();
+        }
+        if projects.is_none() {
+            projects = raw.projects.clone();
+            check_projects(projects.as_ref())?;
+        }
+        if normalize_line_endings.is_none() {
+            normalize_line_endings = raw
+                .anchors
+                .as_ref()
+                .and_then(|anchors| anchors.normalize_line_endings);
+        }
+        if normalizers.is_none() {
+            normalizers = raw
+                .anchors
+                .as_ref()
+                .and_then(|anchors| anchors.normalizers.clone());
+            check_normalizers(normalizers.as_deref().unwrap_or_default())?;
+        }
+        if storage_quota.is_none() {
+            storage_quota = raw.storage.as_ref().and_then(|storage| storage.quota);
+        }
+        if link_threshold.is_none() {
+            link_threshold = raw
+                .lineage
+                .as_ref()
+                .and_then(|lineage| lineage.link_threshold);
+            check_link_threshold(link_threshold)?;
+        }
+        if scorer.is_none() {
+            scorer = raw
+                .lineage
+                .as_ref()
+                .and_then(|lineage| lineage.scorer.clone());
+            check_scorer(scorer.as_deref())?;
+        }
         if tapes_dir.is_none()
             && let Some(raw_tapes_dir) = raw.tapes_dir.as_deref()
         {
@@ -273,7 +904,7 @@ pub fn load_effective_config_with_override(
             && let Some(raw_metrics) = raw.metrics.as_ref()
         {
             let log = if let Some(raw_log) = raw_metrics.log.as_deref() {
-                resolve_path(raw_log, &base_dir, home)?
+                rebase(resolve_path(raw_log, &base_dir, home)?)
             } else {
                 default_metrics_log.clone()
             };
@@ -282,13 +913,25 @@ pub fn load_effective_config_with_override(
                 log,
             });
         }
+        if audit.is_none()
+            && let Some(raw_audit) = raw.audit.as_ref()
+        {
+            let log = match raw_audit.log.as_deref() {
+                Some(raw_log) => resolve_path(raw_log, &base_dir, home)?,
+                None => default_audit_log.clone(),
+            };
+            audit = Some(EffectiveAuditConfig {
+                enabled: raw_audit.enabled.unwrap_or(false),
+                log,
+            });
+        }
         if watch.is_none()
             && let Some(raw_watch) = raw.watch.as_ref()
         {
             let debounce_secs = raw_watch.debounce_secs.unwrap_or(5);
             let ingest_timeout_secs = raw_watch.ingest_timeout_secs.unwrap_or(120);
             let log = if let Some(raw_log) = raw_watch.log.as_deref() {
-                resolve_path(raw_log, &base_dir, home)?
+                rebase(resolve_path(raw_log, &base_dir, home)?)
             } else {
                 default_watch_log.clone()
             };
@@ -309,10 +952,86 @@ pub fn load_effective_config_with_override(
                 sources,
             });
         }
+        if summarize.is_none()
+            && let Some(raw_summarize) = raw.summarize.as_ref()
+            && let (Some(base_url), Some(model)) = (
+                raw_summarize.base_url.as_ref(),
+                raw_summarize.model.as_ref(),
+            )
+        {
+            summarize = Some(EffectiveSummarizeConfig {
+                base_url: base_url.clone(),
+                model: model.clone(),
+                api_key_env: raw_summarize.api_key_env.clone(),
+                timeout_secs: raw_summarize.timeout_secs.unwrap_or(120),
+            });
+        }
+        if pseudonymize_key_env.is_none()
+            && let Some(raw_export) = raw.export.as_ref()
+        {
+            pseudonymize_key_env = raw_export.pseudonymize_key_env.clone();
+        }
+        if embeddings.is_none()
+            && let Some(raw_embeddings) = raw.embeddings.as_ref()
+        {
+            embeddings = Some(EffectiveEmbeddingsConfig {
+                provider: raw_embeddings.provider.unwrap_or_default(),
+                dims: raw_embeddings.dims.unwrap_or(256),
+                base_url: raw_embeddings.base_url.clone(),
+                model: raw_embeddings.model.clone(),
+                api_key_env: raw_embeddings.api_key_env.clone(),
+                timeout_secs: raw_embeddings.timeout_secs.unwrap_or(60),
+            });
+        }
+        if let Some(raw_maintenance) = raw.maintenance.as_ref() {
+            if auto_after_ingests.is_none() {
+                auto_after_ingests = raw_maintenance.auto_after_ingests;
+            }
+            if partitioning.is_none() {
+                partitioning = raw_maintenance.partitioning;
+            }
+        }
+        if let Some(raw_ingest) = raw.ingest.as_ref() {
+            if fast_ingest.is_none() {
+                fast_ingest = raw_ingest.fast;
+            }
+            if retain_raw.is_none() {
+                retain_raw = raw_ingest.retain_raw;
+            }
+            if retain_edits.is_none() {
+                retain_edits = raw_ingest.retain_edits;
+            }
+            if snapshot_state.is_none() {
+                snapshot_state = raw_ingest.snapshot;
+            }
+            if environment.is_none() {
+                environment = raw_ingest.environment.clone();
+            }
+            if sample_outputs.is_none() {
+                sample_outputs = raw_ingest.sample.clone();
+            }
+            if ingest_limits.is_none() {
+                ingest_limits = raw_ingest.limits;
+            }
+            if binary_files.is_none() {
+                binary_files = raw_ingest.binary;
+            }
+        }
+    }
+
+    if let Some(sources) = selected.and_then(|profile| profile.sources) {
+        let watch = watch.get_or_insert_with(|| EffectiveWatchConfig {
+            debounce_secs: 5,
+            ingest_timeout_secs: 120,
+            log: default_watch_log.clone(),
+            sources: Vec::new(),
+        });
+        watch.sources = sources;
     }
 
     Ok(EffectiveConfig {
         path: config_path,
+        profile: profile.map(ToOwned::to_owned),
         db: db.unwrap_or(default_db),
         tapes_dir: tapes_dir.unwrap_or(default_tapes_dir),
         additional_stores: additional_stores.unwrap_or_default(),
@@ -322,10 +1041,251 @@ pub fn load_effective_config_with_override(
             enabled: true,
             log: default_metrics_log,
         }),
+        audit: audit.unwrap_or(EffectiveAuditConfig {
+            enabled: false,
+            log: default_audit_log,
+        }),
+        watch_log: watch
+            .as_ref()
+            .map_or(default_watch_log, |watch| watch.log.clone()),
         watch,
+        storage,
+        storage_quota,
+        summarize,
+        export: EffectiveExportConfig {
+            pseudonymize_key_env,
+        },
+        embeddings,
+        maintenance: EffectiveMaintenanceConfig {
+            auto_after_ingests: auto_after_ingests.unwrap_or(DEFAULT_AUTO_MAINTAIN_AFTER_INGESTS),
+            partitioning: partitioning.unwrap_or_default(),
+        },
+        fast_ingest: fast_ingest.unwrap_or(false),
+        retain_raw: retain_raw.unwrap_or(false),
+        retain_edits: retain_edits.unwrap_or(false),
+        snapshot_state: snapshot_state.unwrap_or(false),
+        environment: environment.unwrap_or_default(),
+        sample_outputs: sample_outputs.unwrap_or_default(),
+        ingest_limits: ingest_limits.unwrap_or_default(),
+        binary_files: binary_files.unwrap_or_default(),
+        ignore: ignore.unwrap_or_default(),
+        issue_patterns: issue_patterns.unwrap_or_else(|| {
+            DEFAULT_ISSUE_PATTERNS
+                .iter()
+                .map(|raw| raw.to_string())
+                .collect()
+        }),
+        projects: projects.unwrap_or_default(),
+        normalize_line_endings: normalize_line_endings.unwrap_or(false),
+        normalizers: normalizers.unwrap_or_default(),
+        link_threshold: link_threshold.unwrap_or(LINK_THRESHOLD_DEFAULT),
+        scorer: scorer.unwrap_or_else(|| DEFAULT_SCORER.to_string()),
+        store_root: cwd.to_path_buf(),
     })
 }
 
+struct SelectedProfile {
+    store: Option<PathBuf>,
+    db: Option<PathBuf>,
+    tapes_dir: Option<PathBuf>,
+    sources: Option<Vec<EffectiveWatchSource>>,
+}
+
+/// `profiles.<name>` from the nearest layer that defines it, with paths
+/// resolved against that layer.
+fn resolve_profile(
+    config_chain: &[PathBuf],
+    home: &Path,
+    name: &str,
+) -> Result<SelectedProfile, ConfigError> {
+    for layer_path in config_chain {
+        let Some(raw) = load_raw_config_file(layer_path)?
+            .profiles
+            .and_then(|mut profiles| profiles.remove(name))
+        else {
+            continue;
+        };
+        let base_dir = config_base_dir(layer_path)?;
+        let path = |raw: Option<&str>| {
+            raw.map(|raw| resolve_path(raw, &base_dir, home))
+                .transpose()
+        };
+        let sources = match raw.sources.as_ref() {
+            Some(raw_sources) => {
+                let mut sources = Vec::new();
+                for source in raw_sources {
+                    sources.push(EffectiveWatchSource {
+                        path: resolve_path(&source.path, &base_dir, home)?,
+                        pattern: source.pattern.clone(),
+                        glob: source.glob.clone(),
+                    });
+                }
+                Some(sources)
+            }
+            None => None,
+        };
+        return Ok(SelectedProfile {
+            store: path(raw.store.as_deref())?,
+            db: path(raw.db.as_deref())?,
+            tapes_dir: path(raw.tapes_dir.as_deref())?,
+            sources,
+        });
+    }
+    Err(ConfigError::UnknownProfile(name.to_string()))
+}
+
+fn resolve_storage_dirs(
+    config_chain: &[PathBuf],
+    home: &Path,
+    env: impl Fn(&str) -> Option<std::ffi::OsString>,
+) -> Result<StorageDirs, ConfigError> {
+    let mut layout = None;
+    let mut data_dir = None;
+    let mut cache_dir = None;
+    for layer_path in config_chain {
+        let Some(raw) = load_raw_config_file(layer_path)?.storage else {
+            continue;
+        };
+        let base_dir = config_base_dir(layer_path)?;
+        if layout.is_none() {
+            layout = raw.layout;
+        }
+        if data_dir.is_none()
+            && let Some(raw_dir) = raw.data_dir.as_deref()
+        {
+            data_dir = Some(resolve_path(raw_dir, &base_dir, home)?);
+        }
+        if cache_dir.is_none()
+            && let Some(raw_dir) = raw.cache_dir.as_deref()
+        {
+            cache_dir = Some(resolve_path(raw_dir, &base_dir, home)?);
+        }
+    }
+
+    let layout = layout.unwrap_or_default();
+    let defaults = default_storage_dirs(layout, home, env);
+    Ok(StorageDirs {
+        layout,
+        data_dir: data_dir.unwrap_or(defaults.data_dir),
+        cache_dir: cache_dir.unwrap_or(defaults.cache_dir),
+    })
+}
+
+/// Default data and cache roots for `layout`. Relative or empty XDG variables
+/// are ignored, as the XDG base directory spec requires.
+pub fn default_storage_dirs(
+    layout: StorageLayout,
+    home: &Path,
+    env: impl Fn(&str) -> Option<std::ffi::OsString>,
+) -> StorageDirs {
+    let xdg_dir = |key: &str, fallback: PathBuf| {
+        env(key)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .unwrap_or(fallback)
+            .join("engram")
+    };
+    match layout {
+        StorageLayout::Legacy => StorageDirs {
+            layout,
+            data_dir: home.join(".engram"),
+            cache_dir: home.join(".engram-cache"),
+        },
+        StorageLayout::Xdg => StorageDirs {
+            layout,
+            data_dir: xdg_dir("XDG_DATA_HOME", home.join(".local").join("share")),
+            cache_dir: xdg_dir("XDG_CACHE_HOME", home.join(".cache")),
+        },
+    }
+}
+
+/// The default user config names `~/.engram/index.sqlite` explicitly; when the
+/// data root moves, paths inside the legacy root follow it.
+fn rebase_legacy_path(path: PathBuf, home: &Path, storage: &StorageDirs) -> PathBuf {
+    let legacy_root = home.join(".engram");
+    if storage.data_dir == legacy_root {
+        return path;
+    }
+    match path.strip_prefix(&legacy_root) {
+        Ok(relative) if relative != Path::new("config.yml") => storage.data_dir.join(relative),
+        _ => path,
+    }
+}
+
+/// Files and directories that move from the legacy roots when the storage
+/// layout changes. `config.yml` always stays in `~/.engram`.
+const MIGRATED_DATA_ENTRIES: &[&str] = &[
+    "index.sqlite",
+    "index.sqlite-wal",
+    "index.sqlite-shm",
+    // Monthly partitions, next to the index (see `index::partitions`).
+    "index.partitions",
+    "tapes",
+    "metrics.jsonl",
+    "watch.log",
+];
+
+/// Move legacy `~/.engram` data and `~/.engram-cache` into `storage` when it
+/// points elsewhere. Entries already present at the destination are left
+/// alone so a half-finished migration resumes instead of clobbering data.
+/// Returns the `(from, to)` pairs that were moved.
+pub fn migrate_legacy_storage(
+    home: &Path,
+    storage: &StorageDirs,
+) -> Result<Vec<(PathBuf, PathBuf)>, ConfigError> {
+    let mut moves = Vec::new();
+    let legacy_data = home.join(".engram");
+    if storage.data_dir != legacy_data {
+        for entry in MIGRATED_DATA_ENTRIES {
+            moves.push((legacy_data.join(entry), storage.data_dir.join(entry)));
+        }
+    }
+    let legacy_cache = home.join(".engram-cache");
+    if storage.cache_dir != legacy_cache {
+        moves.push((legacy_cache, storage.cache_dir.clone()));
+    }
+
+    let mut moved = Vec::new();
+    for (from, to) in moves {
+        if !from.exists() || to.exists() {
+            continue;
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_path(&from, &to)?;
+        moved.push((from, to));
+    }
+    Ok(moved)
+}
+
+fn move_path(from: &Path, to: &Path) -> Result<(), ConfigError> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    // Cross-device moves (e.g. a separate /home) cannot rename.
+    if from.is_dir() {
+        for entry in walkdir::WalkDir::new(from) {
+            let entry = entry.map_err(|err| ConfigError::Io(err.into()))?;
+            let relative = entry
+                .path()
+                .strip_prefix(from)
+                .map_err(|_| ConfigError::InvalidPath(entry.path().display().to_string()))?;
+            let target = to.join(relative);
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&target)?;
+            } else {
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+        fs::remove_dir_all(from)?;
+    } else {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
 pub fn find_walkup_config(start: &Path, home: &Path) -> Option<PathBuf> {
     walkup_config_paths(start, home).into_iter().next()
 }
@@ -341,6 +1301,137 @@ pub fn ensure_user_config(home: &Path) -> Result<PathBuf, ConfigError> {
     Ok(config_path)
 }
 
+/// Add `sources` to the `watch.sources` list of the config file at
+/// `config_path`, creating the file and the `watch:` section as needed.
+/// Sources already listed (same expanded path, pattern and glob) are left
+/// out; the ones actually added are returned. The file is rewritten from
+/// its parsed form, so comments in it are not kept.
+pub fn append_watch_sources(
+    config_path: &Path,
+    home: &Path,
+    sources: &[EffectiveWatchSource],
+) -> Result<Vec<EffectiveWatchSource>, ConfigError> {
+    let mut root = read_config_mapping(config_path)?;
+    let watch = nested_mapping(&mut root, "watch", config_path)?;
+    let listed = watch
+        .entry("sources".into())
+        .or_insert_with(|| serde_yaml::Value::Sequence(Vec::new()));
+    if listed.is_null() {
+        *listed = serde_yaml::Value::Sequence(Vec::new());
+    }
+    let listed = listed.as_sequence_mut().ok_or_else(|| {
+        ConfigError::InvalidPath(format!(
+            "watch.sources in {} is not a list",
+            config_path.display()
+        ))
+    })?;
+
+    let mut existing = listed
+        .iter()
+        .filter_map(|value| serde_yaml::from_value::<RawWatchSource>(value.clone()).ok())
+        .map(|source| {
+            (
+                expand_tilde(&source.path, home),
+                source.pattern,
+                source.glob,
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut added = Vec::new();
+    for source in sources {
+        let key = (
+            expand_tilde(&source.path.to_string_lossy(), home),
+            source.pattern.clone(),
+            source.glob.clone(),
+        );
+        if existing.contains(&key) {
+            continue;
+        }
+        existing.push(key);
+        let mut entry = serde_yaml::Mapping::new();
+        entry.insert("path".into(), source.path.to_string_lossy().as_ref().into());
+        entry.insert("pattern".into(), source.pattern.as_str().into());
+        if let Some(glob) = &source.glob {
+            entry.insert("glob".into(), glob.as_str().into());
+        }
+        listed.push(serde_yaml::Value::Mapping(entry));
+        added.push(source.clone());
+    }
+    if !added.is_empty() {
+        write_config_mapping(config_path, &root)?;
+    }
+    Ok(added)
+}
+
+/// Set dotted keys (`ingest.retain_raw`) in the config file at
+/// `config_path`, creating the file and any missing sections. Like
+/// [`append_watch_sources`], this drops comments from the file.
+pub fn set_config_values(
+    config_path: &Path,
+    values: &[(&str, serde_yaml::Value)],
+) -> Result<(), ConfigError> {
+    let mut root = read_config_mapping(config_path)?;
+    for (key, value) in values {
+        let (sections, leaf) = match key.rsplit_once('.') {
+            Some((sections, leaf)) => (sections.split('.').collect::<Vec<_>>(), leaf),
+            None => (Vec::new(), *key),
+        };
+        let mut mapping = &mut root;
+        for section in sections {
+            mapping = nested_mapping(mapping, section, config_path)?;
+        }
+        mapping.insert(leaf.into(), value.clone());
+    }
+    write_config_mapping(config_path, &root)
+}
+
+fn read_config_mapping(config_path: &Path) -> Result<serde_yaml::Mapping, ConfigError> {
+    let content = match fs::read_to_string(config_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+    if content.trim().is_empty() {
+        return Ok(serde_yaml::Mapping::new());
+    }
+    match serde_yaml::from_str(&content)? {
+        serde_yaml::Value::Mapping(mapping) => Ok(mapping),
+        serde_yaml::Value::Null => Ok(serde_yaml::Mapping::new()),
+        _ => Err(ConfigError::InvalidPath(format!(
+            "{} is not a mapping",
+            config_path.display()
+        ))),
+    }
+}
+
+fn write_config_mapping(config_path: &Path, root: &serde_yaml::Mapping) -> Result<(), ConfigError> {
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    atomic_write(config_path, serde_yaml::to_string(root)?.as_bytes())?;
+    Ok(())
+}
+
+/// The `key` section of `mapping`, created when missing or empty.
+fn nested_mapping<'a>(
+    mapping: &'a mut serde_yaml::Mapping,
+    key: &str,
+    config_path: &Path,
+) -> Result<&'a mut serde_yaml::Mapping, ConfigError> {
+    let value = mapping
+        .entry(key.into())
+        .or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+    if value.is_null() {
+        *value = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+    }
+    value.as_mapping_mut().ok_or_else(|| {
+        ConfigError::InvalidPath(format!(
+            "{key} in {} is not a mapping",
+            config_path.display()
+        ))
+    })
+}
+
 fn config_base_dir(config_path: &Path) -> Result<PathBuf, ConfigError> {
     let config_dir = config_path.parent().ok_or_else(|| {
         ConfigError::InvalidPath(format!(
@@ -384,6 +1475,14 @@ fn normalize_path(path: &Path) -> PathBuf {
 
 fn parse_config(content: &str) -> Result<ParsedConfig, ConfigError> {
     let raw: RawConfig = serde_yaml::from_str(content)?;
+    check_projects(raw.projects.as_ref())?;
+    if let Some(anchors) = raw.anchors.as_ref() {
+        check_normalizers(anchors.normalizers.as_deref().unwrap_or_default())?;
+    }
+    if let Some(lineage) = raw.lineage.as_ref() {
+        check_link_threshold(lineage.link_threshold)?;
+        check_scorer(lineage.scorer.as_deref())?;
+    }
     let watch = raw.watch.map(|watch| ParsedWatchConfig {
         debounce_secs: watch.debounce_secs,
         ingest_timeout_secs: watch.ingest_timeout_secs,
@@ -403,6 +1502,9 @@ fn parse_config(content: &str) -> Result<ParsedConfig, ConfigError> {
         db: raw.db,
         tapes_dir: raw.tapes_dir,
         additional_stores: raw.additional_stores.unwrap_or_default(),
+        ignore: raw.ignore.unwrap_or_default(),
+        issue_patterns: raw.issue_patterns,
+        projects: raw.projects.unwrap_or_default(),
         explain: raw.explain.map(|explain| ParsedExplainConfig {
             default_limit: explain.default_limit,
         }),
@@ -416,7 +1518,82 @@ fn parse_config(content: &str) -> Result<ParsedConfig, ConfigError> {
             enabled: metrics.enabled,
             log: metrics.log,
         }),
+        audit: raw.audit.map(|audit| ParsedAuditConfig {
+            enabled: audit.enabled,
+            log: audit.log,
+        }),
         watch,
+        storage: raw.storage.map(|storage| ParsedStorageConfig {
+            layout: storage.layout,
+            data_dir: storage.data_dir,
+            cache_dir: storage.cache_dir,
+            quota: storage.quota,
+        }),
+        summarize: raw.summarize.map(|summarize| ParsedSummarizeConfig {
+            base_url: summarize.base_url,
+            model: summarize.model,
+            api_key_env: summarize.api_key_env,
+            timeout_secs: summarize.timeout_secs,
+        }),
+        export: raw.export.map(|export| ParsedExportConfig {
+            pseudonymize_key_env: export.pseudonymize_key_env,
+        }),
+        embeddings: raw.embeddings.map(|embeddings| ParsedEmbeddingsConfig {
+            provider: embeddings.provider,
+            dims: embeddings.dims,
+            base_url: embeddings.base_url,
+            model: embeddings.model,
+            api_key_env: embeddings.api_key_env,
+            timeout_secs: embeddings.timeout_secs,
+        }),
+        maintenance: raw.maintenance.map(|maintenance| ParsedMaintenanceConfig {
+            auto_after_ingests: maintenance.auto_after_ingests,
+            partitioning: maintenance.partitioning,
+        }),
+        ingest: raw.ingest.map(|ingest| ParsedIngestConfig {
+            fast: ingest.fast,
+            retain_raw: ingest.retain_raw,
+            retain_edits: ingest.retain_edits,
+            snapshot: ingest.snapshot,
+            environment: ingest.environment,
+            sample: ingest.sample,
+            limits: ingest.limits,
+            binary: ingest.binary,
+        }),
+        anchors: raw.anchors.map(|anchors| ParsedAnchorsConfig {
+            normalize_line_endings: anchors.normalize_line_endings,
+            normalizers: anchors.normalizers,
+        }),
+        lineage: raw.lineage.map(|lineage| ParsedLineageConfig {
+            link_threshold: lineage.link_threshold,
+            scorer: lineage.scorer,
+        }),
+        worktrees: raw.worktrees.map(|worktrees| ParsedWorktreesConfig {
+            share_store: worktrees.share_store,
+        }),
+        profiles: raw
+            .profiles
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, profile)| {
+                let parsed = ParsedProfileConfig {
+                    store: profile.store,
+                    db: profile.db,
+                    tapes_dir: profile.tapes_dir,
+                    sources: profile
+                        .sources
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|source| ParsedWatchSource {
+                            path: source.path,
+                            pattern: source.pattern,
+                            glob: source.glob,
+                        })
+                        .collect(),
+                };
+                (name, parsed)
+            })
+            .collect(),
     })
 }
 
@@ -425,11 +1602,72 @@ pub fn load_parsed_config_file(path: &Path) -> Result<ParsedConfig, ConfigError>
     parse_config(&content)
 }
 
+/// Every `anchors.normalizers` entry names a span normalizer or is `all`.
+fn check_normalizers(names: &[String]) -> Result<(), ConfigError> {
+    let registry = crate::anchor::normalize::normalizer_registry();
+    match names
+        .iter()
+        .find(|name| *name != "all" && !registry.iter().any(|n| n.name == *name))
+    {
+        Some(unknown) => Err(ConfigError::UnknownNormalizer(unknown.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Every `projects.<name>` entry is a list of valid globs.
+fn check_projects(projects: Option<&BTreeMap<String, Vec<String>>>) -> Result<(), ConfigError> {
+    for (name, globs) in projects.into_iter().flatten() {
+        for raw in globs {
+            glob::Pattern::new(raw)
+                .map_err(|err| ConfigError::InvalidProjectGlob(name.clone(), err.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_link_threshold(value: Option<f32>) -> Result<(), ConfigError> {
+    match value {
+        Some(value) if !(0.0..=1.0).contains(&value) => {
+            Err(ConfigError::InvalidLinkThreshold(value))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_scorer(name: Option<&str>) -> Result<(), ConfigError> {
+    match name {
+        Some(name) if scorer_for_name(name).is_none() => {
+            Err(ConfigError::UnknownScorer(name.to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
 fn load_raw_config_file(path: &Path) -> Result<RawConfig, ConfigError> {
     let content = fs::read_to_string(path)?;
     Ok(serde_yaml::from_str(&content)?)
 }
 
+/// The directory whose `.engram` holds the repo-local store and config for
+/// `cwd`. In a linked git worktree whose config (or, failing that, the main
+/// worktree's) sets `worktrees.share_store`, that is the same directory in
+/// the main worktree; everywhere else it is `cwd`.
+pub fn local_store_root(cwd: &Path, home: &Path) -> PathBuf {
+    let Some(layout) = RepoLayout::discover(cwd).filter(RepoLayout::is_linked_worktree) else {
+        return cwd.to_path_buf();
+    };
+    let Ok(relative) = cwd.strip_prefix(&layout.root) else {
+        return cwd.to_path_buf();
+    };
+    let main_cwd = layout.main_root.join(relative);
+    let shared = walkup_config_paths(cwd, home)
+        .into_iter()
+        .chain(walkup_config_paths(&main_cwd, home))
+        .find_map(|path| load_raw_config_file(&path).ok()?.worktrees?.share_store)
+        .unwrap_or(false);
+    if shared { main_cwd } else { cwd.to_path_buf() }
+}
+
 fn config_chain(cwd: &Path, home: &Path, user_config_path: &Path) -> Vec<PathBuf> {
     if !is_within_home(cwd, home) {
         return vec![user_config_path.to_path_buf()];
@@ -487,9 +1725,13 @@ pub fn expand_tilde(path: &str, home: &Path) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::{
-        config_chain, expand_tilde, find_walkup_config, load_effective_config,
-        load_effective_config_with_override, load_parsed_config_file, walkup_config_paths,
+        ConfigError, EffectiveWatchSource, StorageDirs, StorageLayout, append_watch_sources,
+        config_chain, default_storage_dirs, expand_tilde, find_walkup_config,
+        load_effective_config, load_effective_config_for_profile,
+        load_effective_config_with_override, load_parsed_config_file, migrate_legacy_storage,
+        set_config_values, walkup_config_paths,
     };
+    use crate::index::partitions::{partition_dir, partition_path};
     use std::path::Path;
 
     #[test]
@@ -717,6 +1959,47 @@ mod tests {
         assert_eq!(cfg.tapes_dir, home.join("compiled/tapes"));
     }
 
+    #[test]
+    fn profile_replaces_store_and_watch_sources() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let home = dir.path().join("home");
+        let workspace = home.join("workspace");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::create_dir_all(home.join(".engram")).expect("home");
+        std::fs::write(
+            home.join(".engram/config.yml"),
+            "db: ~/.engram/index.sqlite\nwatch:\n  sources:\n    - path: ~/sessions\n      pattern: \"*.jsonl\"\nprofiles:\n  work:\n    store: ~/work/.engram\n    sources:\n      - path: ~/work/sessions\n        pattern: \"*.jsonl\"\n  personal:\n    db: ~/personal.sqlite\n",
+        )
+        .expect("config");
+
+        let work = load_effective_config_for_profile(&workspace, &home, None, Some("work"))
+            .expect("work profile");
+        assert_eq!(work.profile.as_deref(), Some("work"));
+        assert_eq!(work.db, home.join("work/.engram/index.sqlite"));
+        assert_eq!(work.tapes_dir, home.join("work/.engram/tapes"));
+        assert_eq!(work.metrics.log, home.join("work/.engram/metrics.jsonl"));
+        let watch = work.watch.expect("watch");
+        assert_eq!(watch.sources.len(), 1);
+        assert_eq!(watch.sources[0].path, home.join("work/sessions"));
+        assert_eq!(watch.log, home.join("work/.engram/watch.log"));
+
+        let personal = load_effective_config_for_profile(&workspace, &home, None, Some("personal"))
+            .expect("personal profile");
+        assert_eq!(personal.db, home.join("personal.sqlite"));
+        assert_eq!(
+            personal.watch.expect("watch").sources[0].path,
+            home.join("sessions")
+        );
+
+        let plain = load_effective_config(&workspace, &home).expect("no profile");
+        assert_eq!(plain.profile, None);
+        assert_eq!(plain.db, home.join(".engram/index.sqlite"));
+
+        let err = load_effective_config_for_profile(&workspace, &home, None, Some("missing"))
+            .expect_err("unknown profile");
+        assert!(matches!(err, ConfigError::UnknownProfile(name) if name == "missing"));
+    }
+
     #[test]
     fn rejects_legacy_pre_rev2_source_schema() {
         let dir = tempfile::tempdir().expect("tempdir");
@@ -810,4 +2093,134 @@ mod tests {
         assert_eq!(watch.sources[0].pattern, "session-*.json");
         assert_eq!(watch.sources[0].glob, None);
     }
+
+    #[test]
+    fn xdg_layout_uses_xdg_variables_with_home_fallbacks() {
+        let home = Path::new("/home/tester");
+        let dirs = default_storage_dirs(StorageLayout::Xdg, home, |key| match key {
+            "XDG_DATA_HOME" => Some("/data".into()),
+            "XDG_CACHE_HOME" => Some("relative/cache".into()),
+            _ => None,
+        });
+        assert_eq!(dirs.data_dir, Path::new("/data/engram"));
+        assert_eq!(dirs.cache_dir, Path::new("/home/tester/.cache/engram"));
+
+        let legacy = default_storage_dirs(StorageLayout::Legacy, home, |_| None);
+        assert_eq!(legacy.data_dir, Path::new("/home/tester/.engram"));
+        assert_eq!(legacy.cache_dir, Path::new("/home/tester/.engram-cache"));
+    }
+
+    #[test]
+    fn storage_data_dir_rebases_default_db_and_migrates_legacy_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let home = dir.path().join("home");
+        let workspace = home.join("workspace");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::create_dir_all(home.join(".engram/tapes")).expect("legacy tapes");
+        std::fs::create_dir_all(home.join(".engram-cache")).expect("legacy cache");
+        std::fs::write(home.join(".engram/index.sqlite"), "db").expect("legacy db");
+        std::fs::write(home.join(".engram/tapes/a.jsonl.zst"), "tape").expect("legacy tape");
+        std::fs::write(
+            home.join(".engram/config.yml"),
+            "db: ~/.engram/index.sqlite\nstorage:\n  layout: xdg\n  data_dir: ~/data\n  cache_dir: ~/cache\n",
+        )
+        .expect("home config");
+
+        let cfg = load_effective_config(&workspace, &home).expect("config");
+        assert_eq!(cfg.storage.layout, StorageLayout::Xdg);
+        assert_eq!(cfg.db, home.join("data/index.sqlite"));
+        assert_eq!(cfg.metrics.log, home.join("data/metrics.jsonl"));
+
+        let moved = migrate_legacy_storage(&home, &cfg.storage).expect("migrate");
+        assert_eq!(moved.len(), 3);
+        assert!(home.join("data/index.sqlite").is_file());
+        assert!(home.join("data/tapes/a.jsonl.zst").is_file());
+        assert!(home.join("cache").is_dir());
+        assert!(!home.join(".engram/index.sqlite").exists());
+        assert!(home.join(".engram/config.yml").is_file());
+        assert!(
+            migrate_legacy_storage(&home, &cfg.storage)
+                .expect("second migrate")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn storage_migration_moves_index_partitions_with_the_index() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let home = dir.path().join("home");
+        let legacy_db = home.join(".engram/index.sqlite");
+        let partition = partition_path(&legacy_db, "2026-01");
+        std::fs::create_dir_all(partition.parent().expect("partition dir")).expect("partitions");
+        std::fs::write(&legacy_db, "db").expect("legacy db");
+        std::fs::write(&partition, "january").expect("legacy partition");
+        let storage = StorageDirs {
+            layout: StorageLayout::Xdg,
+            data_dir: home.join("data"),
+            cache_dir: home.join(".engram-cache"),
+        };
+
+        migrate_legacy_storage(&home, &storage).expect("migrate");
+        let moved = partition_path(&home.join("data/index.sqlite"), "2026-01");
+        assert_eq!(
+            std::fs::read_to_string(moved).expect("partition"),
+            "january"
+        );
+        assert!(!partition_dir(&legacy_db).exists());
+    }
+
+    #[test]
+    fn config_edits_append_sources_once_and_set_values() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let home = dir.path().join("home");
+        let workspace = dir.path().join("workspace");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = home.join(".engram/config.yml");
+        std::fs::create_dir_all(home.join(".engram")).expect("engram dir");
+        std::fs::write(
+            &config_path,
+            "db: ~/.engram/index.sqlite\nwatch:\n  debounce_secs: 2\n  sources:\n    - path: ~/.codex/sessions\n      pattern: \"*.jsonl\"\n",
+        )
+        .expect("config");
+
+        let codex = EffectiveWatchSource {
+            path: home.join(".codex/sessions"),
+            pattern: "*.jsonl".to_string(),
+            glob: None,
+        };
+        let claude = EffectiveWatchSource {
+            path: Path::new("~/.claude/projects").to_path_buf(),
+            pattern: "*.jsonl".to_string(),
+            glob: Some("*/*.jsonl".to_string()),
+        };
+        let added = append_watch_sources(&config_path, &home, &[codex.clone(), claude.clone()])
+            .expect("append");
+        assert_eq!(added, vec![claude.clone()]);
+        assert!(
+            append_watch_sources(&config_path, &home, &[codex, claude])
+                .expect("second append")
+                .is_empty()
+        );
+
+        let cfg = load_effective_config(&workspace, &home).expect("config");
+        assert_eq!(cfg.db, home.join(".engram/index.sqlite"));
+        let watch = cfg.watch.expect("watch");
+        assert_eq!(watch.debounce_secs, 2);
+        assert_eq!(watch.sources.len(), 2);
+        assert_eq!(watch.sources[1].path, home.join(".claude/projects"));
+        assert_eq!(watch.sources[1].glob.as_deref(), Some("*/*.jsonl"));
+
+        set_config_values(
+            &config_path,
+            &[
+                ("ingest.retain_raw", true.into()),
+                ("ignore", vec![".env*"].into()),
+            ],
+        )
+        .expect("set values");
+        let cfg = load_effective_config(&workspace, &home).expect("config");
+        assert!(cfg.retain_raw);
+        assert_eq!(cfg.ignore, vec![".env*".to_string()]);
+        assert_eq!(cfg.watch.expect("watch").sources.len(), 2);
+    }
 }
@@ -0,0 +1,314 @@
+use std::time::Duration;
+
+use serde_json::Value;
+#[cfg(feature = "native")]
+use serde_json::json;
+
+use crate::config::{EffectiveEmbeddingsConfig, EmbeddingProviderKind};
+use crate::query::narrative::excerpt_text;
+
+/// Event kinds that get an embedding at ingest: conversation and tool I/O,
+/// not code spans (those are covered by fingerprints).
+pub const EMBEDDED_EVENT_KINDS: &[&str] = &["msg.in", "msg.out", "tool.call", "tool.result"];
+
+/// Texts sent to a provider per request.
+#[cfg(feature = "native")]
+const EMBED_BATCH_SIZE: usize = 64;
+
+/// Source of embedding vectors. Vectors are only ever compared with vectors
+/// from the same [`EmbeddingProvider::id`].
+pub trait EmbeddingProvider {
+    /// Stable identifier stored with each vector (provider, model and size).
+    fn id(&self) -> String;
+    /// One vector per input text, in input order.
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError>;
+}
+
+#[derive(Debug)]
+pub enum EmbeddingError {
+    Config(String),
+    Http(String),
+    Response(String),
+}
+
+impl std::fmt::Display for EmbeddingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(message) => write!(f, "invalid embeddings config: {message}"),
+            Self::Http(message) => write!(f, "embeddings request failed: {message}"),
+            Self::Response(message) => write!(f, "unexpected embeddings response: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for EmbeddingError {}
+
+/// Provider described by an `embeddings:` config section.
+pub fn provider_from_config(
+    config: &EffectiveEmbeddingsConfig,
+) -> Result<Box<dyn EmbeddingProvider>, EmbeddingError> {
+    match config.provider {
+        EmbeddingProviderKind::Hashing => {
+            if config.dims == 0 {
+                return Err(EmbeddingError::Config("dims must be positive".into()));
+            }
+            Ok(Box::new(HashingEmbedder { dims: config.dims }))
+        }
+        EmbeddingProviderKind::Openai => {
+            let base_url = config
+                .base_url
+                .clone()
+                .ok_or_else(|| EmbeddingError::Config("openai provider needs base_url".into()))?;
+            let model = config
+                .model
+                .clone()
+                .ok_or_else(|| EmbeddingError::Config("openai provider needs model".into()))?;
+            let api_key = match config.api_key_env.as_deref() {
+                Some(var) => Some(std::env::var(var).map_err(|_| {
+                    EmbeddingError::Config(format!("environment variable `{var}` is not set"))
+                })?),
+                None => None,
+            };
+            Ok(Box::new(OpenAiEmbedder {
+                base_url,
+                model,
+                api_key,
+                timeout: Duration::from_secs(config.timeout_secs),
+            }))
+        }
+    }
+}
+
+/// Deterministic local embedder: signed feature hashing of words and
+/// character trigrams into `dims` buckets, L2-normalized. Trigrams let
+/// related word forms (`tokio`/`tokio-based`, `retry`/`retries`) land near
+/// each other without a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashingEmbedder {
+    pub dims: usize,
+}
+
+impl HashingEmbedder {
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dims];
+        let mut add = |feature: &str, weight: f32| {
+            let hash = fnv1a(feature.as_bytes());
+            let bucket = (hash % self.dims as u64) as usize;
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[bucket] += sign * weight;
+        };
+        for word in text
+            .split(|ch: char| !ch.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let word = word.to_lowercase();
+            add(&format!("w:{word}"), 1.0);
+            let padded = format!("#{word}#").chars().collect::<Vec<_>>();
+            for gram in padded.windows(3) {
+                add(&format!("g:{}", gram.iter().collect::<String>()), 0.5);
+            }
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+impl EmbeddingProvider for HashingEmbedder {
+    fn id(&self) -> String {
+        format!("hashing-{}", self.dims)
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
+
+/// Remote or local OpenAI-compatible embeddings endpoint.
+#[derive(Debug, Clone)]
+pub struct OpenAiEmbedder {
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    pub timeout: Duration,
+}
+
+impl EmbeddingProvider for OpenAiEmbedder {
+    fn id(&self) -> String {
+        format!("openai:{}", self.model)
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn embed(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        Err(EmbeddingError::Http(
+            "engram was built without HTTP support (the `native` feature)".into(),
+        ))
+    }
+
+    #[cfg(feature = "native")]
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let url = format!("{}/embeddings", self.base_url.trim_end_matches('/'));
+        let agent = ureq::AgentBuilder::new().timeout(self.timeout).build();
+        let mut out = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBED_BATCH_SIZE) {
+            let mut request = agent.post(&url);
+            if let Some(key) = &self.api_key {
+                request = request.set("Authorization", &format!("Bearer {key}"));
+            }
+            let response: Value = request
+                .send_json(json!({"model": self.model, "input": batch}))
+                .map_err(|err| EmbeddingError::Http(err.to_string()))?
+                .into_json()
+                .map_err(|err| EmbeddingError::Response(err.to_string()))?;
+            let mut data = response
+                .get("data")
+                .and_then(Value::as_array)
+                .cloned()
+                .ok_or_else(|| EmbeddingError::Response("missing data array".into()))?;
+            if data.len() != batch.len() {
+                return Err(EmbeddingError::Response(format!(
+                    "expected {} embeddings, got {}",
+                    batch.len(),
+                    data.len()
+                )));
+            }
+            data.sort_by_key(|item| item.get("index").and_then(Value::as_u64).unwrap_or(0));
+            for item in data {
+                let mut vector = item
+                    .get("embedding")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| EmbeddingError::Response("missing embedding".into()))?
+                    .iter()
+                    .map(|value| value.as_f64().unwrap_or(0.0) as f32)
+                    .collect::<Vec<_>>();
+                normalize(&mut vector);
+                out.push(vector);
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// One event of a tape selected for embedding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddableEvent {
+    pub event_offset: u64,
+    pub kind: String,
+    pub text: String,
+}
+
+/// Message and tool events of normalized tape JSONL, with the same condensed
+/// text `explain --summarize` sends. Offsets are line offsets, as in evidence.
+pub fn embeddable_events(jsonl: &str) -> Vec<EmbeddableEvent> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter_map(|(offset, line)| {
+            let row = serde_json::from_str::<Value>(line).ok()?;
+            let kind = row.get("k").and_then(Value::as_str)?;
+            if !EMBEDDED_EVENT_KINDS.contains(&kind) {
+                return None;
+            }
+            let text = excerpt_text(&row)?;
+            Some(EmbeddableEvent {
+                event_offset: offset as u64,
+                kind: kind.to_string(),
+                text,
+            })
+        })
+        .collect()
+}
+
+pub fn cosine_similarity(left: &[f32], right: &[f32]) -> f32 {
+    if left.len() != right.len() {
+        return 0.0;
+    }
+    let dot = left.iter().zip(right).map(|(a, b)| a * b).sum::<f32>();
+    let norms = left.iter().map(|a| a * a).sum::<f32>().sqrt()
+        * right.iter().map(|b| b * b).sum::<f32>().sqrt();
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+/// Little-endian `f32` bytes, the on-disk form of a vector.
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for value in vector.iter_mut() {
+            *value /= norm;
+        }
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashing_embedder_ranks_related_text_above_unrelated() {
+        let embedder = HashingEmbedder { dims: 256 };
+        let vectors = embedder
+            .embed(&[
+                "why did we switch to tokio".to_string(),
+                "assistant: migrating the runtime to tokio for async io".to_string(),
+                "user: rename the README heading".to_string(),
+            ])
+            .expect("embed");
+        let related = cosine_similarity(&vectors[0], &vectors[1]);
+        let unrelated = cosine_similarity(&vectors[0], &vectors[2]);
+        assert!(related > unrelated, "{related} <= {unrelated}");
+        assert_eq!(embedder.id(), "hashing-256");
+        assert_eq!(
+            vectors[0],
+            embedder
+                .embed(&["why did we switch to tokio".into()])
+                .expect("embed")[0]
+        );
+    }
+
+    #[test]
+    fn vectors_roundtrip_through_bytes() {
+        let vector = vec![0.25f32, -1.5, 3.0];
+        assert_eq!(decode_vector(&encode_vector(&vector)), vector);
+    }
+
+    #[test]
+    fn only_message_and_tool_events_are_embeddable() {
+        let jsonl = concat!(
+            "{\"k\":\"meta\",\"model\":\"gpt-5\"}\n",
+            "{\"k\":\"msg.in\",\"text\":\"switch to tokio\"}\n",
+            "{\"k\":\"code.edit\",\"file\":\"src/main.rs\"}\n",
+            "{\"k\":\"tool.call\",\"tool\":\"cargo\",\"args\":\"add tokio\"}\n",
+        );
+        let events = embeddable_events(jsonl);
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| (event.event_offset, event.kind.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(1, "msg.in"), (3, "tool.call")]
+        );
+        assert_eq!(events[0].text, "user: switch to tokio");
+    }
+}
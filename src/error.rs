@@ -0,0 +1,140 @@
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::ConfigError;
+use crate::embed::EmbeddingError;
+use crate::query::narrative::NarrativeError;
+use crate::query::snapshot::SnapshotError;
+use crate::tape::adapter::AdapterError;
+use crate::tape::hooks::HookPayloadError;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Any failure of the library, by the part of engram it came from. Module
+/// error types convert into it with `?`, so callers can match on the class
+/// and still reach the underlying error through [`std::error::Error::source`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A harness transcript could not be converted into a tape.
+    #[error("adapter: {0}")]
+    Adapter(#[from] AdapterError),
+    /// The SQLite index (or another engram database) failed.
+    #[cfg(feature = "native")]
+    #[error("index: {0}")]
+    Index(#[from] rusqlite::Error),
+    /// A tape could not be read, decompressed or parsed.
+    #[error("tape: {0}")]
+    Tape(#[from] TapeError),
+    /// A config file could not be read or resolved.
+    #[error("config: {0}")]
+    Config(#[from] ConfigError),
+    /// A query step failed: a call out (summaries, embeddings) or loading a
+    /// graph snapshot.
+    #[error("query: {0}")]
+    Query(#[from] QueryError),
+    /// A store file (watchlist, session manifest, ...) is not valid JSON.
+    #[error("store: {}: {source}", path.display())]
+    Store {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TapeError {
+    #[error("invalid tape JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("cannot decompress tape: {0}")]
+    Decompress(#[source] io::Error),
+    #[error("tape is not UTF-8: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+    #[error(transparent)]
+    Hook(#[from] HookPayloadError),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum QueryError {
+    #[error(transparent)]
+    Narrative(#[from] NarrativeError),
+    #[error(transparent)]
+    Embedding(#[from] EmbeddingError),
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+}
+
+impl Error {
+    /// A stable name for the class of failure: `adapter`, `index`, `tape`,
+    /// `config`, `query`, `store` or `io`.
+    pub fn class(&self) -> &'static str {
+        match self {
+            Self::Adapter(_) => "adapter",
+            #[cfg(feature = "native")]
+            Self::Index(_) => "index",
+            Self::Tape(_) => "tape",
+            Self::Config(_) => "config",
+            Self::Query(_) => "query",
+            Self::Store { .. } => "store",
+            Self::Io(_) => "io",
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Tape(TapeError::Json(value))
+    }
+}
+
+impl From<HookPayloadError> for Error {
+    fn from(value: HookPayloadError) -> Self {
+        Self::Tape(TapeError::Hook(value))
+    }
+}
+
+impl From<NarrativeError> for Error {
+    fn from(value: NarrativeError) -> Self {
+        Self::Query(QueryError::Narrative(value))
+    }
+}
+
+impl From<EmbeddingError> for Error {
+    fn from(value: EmbeddingError) -> Self {
+        Self::Query(QueryError::Embedding(value))
+    }
+}
+
+impl From<SnapshotError> for Error {
+    fn from(value: SnapshotError) -> Self {
+        Self::Query(QueryError::Snapshot(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn module_errors_convert_into_their_class() {
+        let parse = || -> Result<serde_json::Value> { Ok(serde_json::from_str("{")?) };
+        let err = parse().expect_err("invalid json");
+        assert_eq!(err.class(), "tape");
+        assert!(matches!(err, Error::Tape(TapeError::Json(_))));
+        assert!(err.source().is_some());
+
+        let err = Error::from(NarrativeError::MissingApiKey("KEY".into()));
+        assert_eq!(err.class(), "query");
+        assert_eq!(
+            err.to_string(),
+            "query: environment variable `KEY` is not set"
+        );
+        assert_eq!(Error::from(rusqlite::Error::InvalidQuery).class(), "index");
+    }
+}
@@ -0,0 +1,125 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tokio::task::spawn_blocking;
+
+use crate::error::{Result, TapeError};
+use crate::index::SqliteIndex;
+use crate::query::explain::{ExplainResult, ExplainTraversal, explain_by_anchor};
+use crate::tape::event::parse_jsonl_events;
+
+/// A [`SqliteIndex`] for async callers (servers, embedding pipelines). Every
+/// call runs on tokio's blocking pool, so SQLite never stalls the runtime
+/// and callers need not spawn blocking tasks themselves. Clones share one
+/// connection and its calls run one at a time.
+#[derive(Clone)]
+pub struct AsyncIndex {
+    index: Arc<Mutex<SqliteIndex>>,
+}
+
+impl AsyncIndex {
+    pub async fn open(path: impl Into<String>) -> Result<Self> {
+        let path = path.into();
+        let index = run_blocking(move || SqliteIndex::open(&path)).await?;
+        Ok(Self::from(index))
+    }
+
+    /// Run `f` against the index on the blocking pool.
+    pub async fn call<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&SqliteIndex) -> Result<T> + Send + 'static,
+    {
+        let index = Arc::clone(&self.index);
+        run_blocking(move || f(&index.lock().unwrap_or_else(PoisonError::into_inner))).await
+    }
+
+    /// Parse a tape's JSONL and index its events, as `engram ingest` does for
+    /// one tape.
+    pub async fn ingest_tape_jsonl(
+        &self,
+        tape_id: impl Into<String>,
+        jsonl: String,
+        link_threshold: f32,
+    ) -> Result<()> {
+        let tape_id = tape_id.into();
+        self.call(move |index| {
+            let events = parse_jsonl_events(&jsonl).map_err(TapeError::Json)?;
+            Ok(index.ingest_tape_events(&tape_id, &events, link_threshold)?)
+        })
+        .await
+    }
+
+    pub async fn explain_by_anchor(
+        &self,
+        anchors: Vec<String>,
+        traversal: ExplainTraversal,
+        include_forensics: bool,
+    ) -> Result<ExplainResult> {
+        self.call(move |index| {
+            Ok(explain_by_anchor(
+                index,
+                &anchors,
+                traversal,
+                include_forensics,
+            )?)
+        })
+        .await
+    }
+}
+
+impl From<SqliteIndex> for AsyncIndex {
+    fn from(index: SqliteIndex) -> Self {
+        Self {
+            index: Arc::new(Mutex::new(index)),
+        }
+    }
+}
+
+/// Run `f` on the blocking pool, re-raising its panic in the caller.
+async fn run_blocking<T, E, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    E: Into<crate::Error> + Send + 'static,
+    F: FnOnce() -> std::result::Result<T, E> + Send + 'static,
+{
+    match spawn_blocking(f).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ingests_and_explains_from_async_code() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("runtime");
+        let jsonl = r#"{"t":"2026-02-22T00:00:00Z","k":"code.read","file":"src/cart.rs","range":[1,3],"anchor_hashes":["cart-total"]}"#;
+        let index = AsyncIndex::from(SqliteIndex::open_in_memory().expect("open"));
+
+        let result = runtime
+            .block_on(async {
+                index
+                    .ingest_tape_jsonl("tape-1", jsonl.to_string(), 0.5)
+                    .await?;
+                index
+                    .explain_by_anchor(
+                        vec!["cart-total".to_string()],
+                        ExplainTraversal::default(),
+                        false,
+                    )
+                    .await
+            })
+            .expect("explain");
+        assert_eq!(result.direct.len(), 1);
+        assert_eq!(result.direct[0].tape_id, "tape-1");
+
+        let err = runtime
+            .block_on(index.ingest_tape_jsonl("tape-2", "{".to_string(), 0.5))
+            .expect_err("invalid tape");
+        assert_eq!(err.class(), "tape");
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use crate::index::EdgeRow;
+use crate::index::lineage::EvidenceFragmentRef;
+
+/// What a cache entry holds for one anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum CacheSlot {
+    Outbound,
+    Inbound,
+    Evidence,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum CachedRows {
+    Edges(Vec<EdgeRow>),
+    Evidence(Vec<EvidenceFragmentRef>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnchorCacheStats {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Times the whole cache was dropped because the index changed.
+    pub invalidations: u64,
+}
+
+/// Least-recently-used cache of per-anchor edge lists and evidence, see
+/// [`SqliteIndex::with_anchor_cache`](crate::index::SqliteIndex::with_anchor_cache).
+/// Entries are tagged with the index version they were read at; any write
+/// (from this connection or another process) empties the cache rather than
+/// tracking which anchors it touched.
+#[derive(Debug)]
+pub(crate) struct AnchorCache {
+    capacity: usize,
+    version: Option<(i64, u64)>,
+    tick: u64,
+    entries: HashMap<(CacheSlot, String), (u64, CachedRows)>,
+    stats: AnchorCacheStats,
+}
+
+impl AnchorCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            version: None,
+            tick: 0,
+            entries: HashMap::new(),
+            stats: AnchorCacheStats {
+                capacity,
+                ..AnchorCacheStats::default()
+            },
+        }
+    }
+
+    /// Drop every entry if the index is no longer at `version`.
+    pub(crate) fn sync_version(&mut self, version: (i64, u64)) {
+        if self.version != Some(version) {
+            if !self.entries.is_empty() {
+                self.entries.clear();
+                self.stats.invalidations += 1;
+            }
+            self.version = Some(version);
+        }
+    }
+
+    pub(crate) fn get(&mut self, slot: CacheSlot, anchor: &str) -> Option<CachedRows> {
+        self.tick += 1;
+        let tick = self.tick;
+        match self.entries.get_mut(&(slot, anchor.to_string())) {
+            Some((last_used, rows)) => {
+                *last_used = tick;
+                self.stats.hits += 1;
+                Some(rows.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&mut self, slot: CacheSlot, anchor: &str, rows: CachedRows) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        let key = (slot, anchor.to_string());
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            // Linear scan: capacities are small (hundreds of hot anchors), and
+            // a miss already paid for a SQLite query.
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (self.tick, rows));
+    }
+
+    pub(crate) fn stats(&self) -> AnchorCacheStats {
+        AnchorCacheStats {
+            entries: self.entries.len(),
+            ..self.stats
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(anchor: &str) -> CachedRows {
+        CachedRows::Evidence(vec![EvidenceFragmentRef {
+            tape_id: anchor.to_string(),
+            event_offset: 0,
+            kind: crate::index::lineage::EvidenceKind::Read,
+            file_path: "src/lib.rs".to_string(),
+            timestamp: "2026-03-01T00:00:00Z".to_string(),
+        }])
+    }
+
+    #[test]
+    fn evicts_least_recently_used_and_clears_on_version_change() {
+        let mut cache = AnchorCache::new(2);
+        cache.sync_version((1, 0));
+        cache.insert(CacheSlot::Evidence, "a", evidence("a"));
+        cache.insert(CacheSlot::Evidence, "b", evidence("b"));
+        assert!(cache.get(CacheSlot::Evidence, "a").is_some());
+        cache.insert(CacheSlot::Evidence, "c", evidence("c"));
+        assert!(cache.get(CacheSlot::Evidence, "b").is_none());
+        assert!(cache.get(CacheSlot::Evidence, "a").is_some());
+        assert!(cache.get(CacheSlot::Outbound, "a").is_none());
+
+        cache.sync_version((1, 0));
+        assert_eq!(cache.stats().entries, 2);
+        cache.sync_version((1, 1));
+        assert_eq!(
+            cache.stats(),
+            AnchorCacheStats {
+                capacity: 2,
+                entries: 0,
+                hits: 2,
+                misses: 2,
+                invalidations: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! `engram calibrate`: check stored edge confidences against the text the
+//! tapes still hold, and suggest a `lineage.link_threshold` that splits this
+//! codebase's edits into lineage and location-only where their similarity
+//! actually divides.
+
+/// Fewer samples than this give no suggested threshold.
+pub const MIN_CALIBRATION_SAMPLES: usize = 20;
+
+/// A stored confidence within this of the recomputed similarity agrees.
+pub const CONFIDENCE_TOLERANCE: f32 = 0.10;
+
+/// Thresholds are suggested on a grid of this many steps over `[0, 1]`.
+const THRESHOLD_STEPS: usize = 100;
+
+/// One stored edge whose before and after text are still on hand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationSample {
+    /// The confidence in the index, or `None` when the edit's edge is not
+    /// stored (the tape was ingested under other anchors) or another scorer
+    /// produced it.
+    pub stored_confidence: Option<f32>,
+    /// The edit's before and after text rescored with `lineage.scorer`.
+    pub similarity: f32,
+}
+
+/// What [`calibrate`] found over a set of samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationReport {
+    pub sampled: usize,
+    /// Stored confidences within [`CONFIDENCE_TOLERANCE`] of the similarity.
+    pub confidence_agrees: usize,
+    pub confidence_disagrees: usize,
+    pub confidence_missing: usize,
+    /// Samples the stored confidence puts on the other side of the current
+    /// threshold than the recomputed similarity does.
+    pub misclassified: usize,
+    /// Samples whose similarity reaches the current threshold.
+    pub lineage: usize,
+    /// `None` with fewer than [`MIN_CALIBRATION_SAMPLES`] samples or when
+    /// every sample has the same similarity.
+    pub suggested_link_threshold: Option<f32>,
+    /// Samples whose similarity reaches the suggested threshold.
+    pub lineage_suggested: Option<usize>,
+    /// Similarity at the 10th, 25th, 50th, 75th and 90th percentile.
+    pub similarity_percentiles: Vec<(u8, f32)>,
+}
+
+/// Compare `samples` against `link_threshold` and suggest a threshold: the
+/// grid point that best separates the similarities into two groups (Otsu's
+/// method, maximizing the variance between them).
+pub fn calibrate(samples: &[CalibrationSample], link_threshold: f32) -> CalibrationReport {
+    let mut report = CalibrationReport {
+        sampled: samples.len(),
+        confidence_agrees: 0,
+        confidence_disagrees: 0,
+        confidence_missing: 0,
+        misclassified: 0,
+        lineage: 0,
+        suggested_link_threshold: None,
+        lineage_suggested: None,
+        similarity_percentiles: Vec::new(),
+    };
+    for sample in samples {
+        match sample.stored_confidence {
+            Some(stored) => {
+                if (stored - sample.similarity).abs() <= CONFIDENCE_TOLERANCE {
+                    report.confidence_agrees += 1;
+                } else {
+                    report.confidence_disagrees += 1;
+                }
+                if (stored >= link_threshold) != (sample.similarity >= link_threshold) {
+                    report.misclassified += 1;
+                }
+            }
+            None => report.confidence_missing += 1,
+        }
+        if sample.similarity >= link_threshold {
+            report.lineage += 1;
+        }
+    }
+
+    let mut similarities = samples
+        .iter()
+        .map(|sample| sample.similarity)
+        .collect::<Vec<_>>();
+    similarities.sort_by(f32::total_cmp);
+    if let Some(last) = similarities.len().checked_sub(1) {
+        report.similarity_percentiles = [10, 25, 50, 75, 90]
+            .into_iter()
+            .map(|percentile| (percentile, similarities[last * percentile as usize / 100]))
+            .collect();
+    }
+    if similarities.len() >= MIN_CALIBRATION_SAMPLES
+        && let Some(threshold) = otsu_threshold(&similarities)
+    {
+        report.suggested_link_threshold = Some(threshold);
+        report.lineage_suggested = Some(
+            similarities
+                .iter()
+                .filter(|similarity| **similarity >= threshold)
+                .count(),
+        );
+    }
+    report
+}
+
+/// The grid threshold maximizing between-group variance, if any split
+/// leaves samples on both sides.
+fn otsu_threshold(similarities: &[f32]) -> Option<f32> {
+    let total = similarities.len() as f64;
+    let sum = similarities.iter().map(|s| f64::from(*s)).sum::<f64>();
+    // The best variance and the run of steps reaching it.
+    let mut best: Option<(f64, usize, usize)> = None;
+    for step in 1..THRESHOLD_STEPS {
+        let threshold = step as f32 / THRESHOLD_STEPS as f32;
+        let below = similarities
+            .iter()
+            .filter(|similarity| **similarity < threshold)
+            .map(|similarity| f64::from(*similarity))
+            .collect::<Vec<_>>();
+        if below.is_empty() || below.len() == similarities.len() {
+            continue;
+        }
+        let weight_below = below.len() as f64 / total;
+        let mean_below = below.iter().sum::<f64>() / below.len() as f64;
+        let mean_above = (sum - below.iter().sum::<f64>()) / (total - below.len() as f64);
+        let variance = weight_below * (1.0 - weight_below) * (mean_above - mean_below).powi(2);
+        match best {
+            Some((best_variance, first, last))
+                if (variance - best_variance).abs() <= 1e-12 && last + 1 == step =>
+            {
+                best = Some((best_variance, first, step));
+            }
+            Some((best_variance, ..)) if variance <= best_variance => {}
+            _ => best = Some((variance, step, step)),
+        }
+    }
+    // Every step in the gap between two groups splits them the same way;
+    // the middle of the gap leaves the most room on both sides.
+    best.map(|(_, first, last)| (first + last).div_ceil(2) as f32 / THRESHOLD_STEPS as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(stored: Option<f32>, similarity: f32) -> CalibrationSample {
+        CalibrationSample {
+            stored_confidence: stored,
+            similarity,
+        }
+    }
+
+    #[test]
+    fn suggests_the_threshold_between_rewrites_and_small_edits() {
+        let mut samples = Vec::new();
+        for idx in 0..15 {
+            samples.push(sample(Some(0.0), idx as f32 * 0.015));
+            samples.push(sample(None, 0.60 + idx as f32 * 0.02));
+        }
+        let report = calibrate(&samples, 0.30);
+        assert_eq!(report.sampled, 30);
+        assert_eq!(report.confidence_missing, 15);
+        assert_eq!(report.confidence_agrees, 7);
+        assert_eq!(report.confidence_disagrees, 8);
+        assert_eq!(report.misclassified, 0);
+        assert_eq!(report.lineage, 15);
+        let threshold = report.suggested_link_threshold.expect("suggestion");
+        assert!(threshold > 0.21 && threshold <= 0.60, "{threshold}");
+        assert_eq!(report.lineage_suggested, Some(15));
+        assert_eq!(report.similarity_percentiles.len(), 5);
+
+        let report = calibrate(&samples[..10], 0.30);
+        assert_eq!(report.suggested_link_threshold, None);
+        assert_eq!(
+            calibrate(&[sample(Some(0.5), 0.5); 30], 0.3).suggested_link_threshold,
+            None
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Bumped whenever what the cache stores, or how anchors are computed,
+/// changes; older caches are emptied on open.
+const FINGERPRINT_CACHE_VERSION: i64 = 1;
+
+/// Anchors computed from working-tree files, kept across commands in
+/// `<cache_dir>/fingerprints.sqlite`. Entries are keyed by file path and a
+/// caller-chosen key (a span, a chunking, ...), and belong to the file's
+/// `(mtime, size)` when computed: a file that changed since loses all of
+/// them on its next lookup.
+#[derive(Debug)]
+pub struct FingerprintCache {
+    conn: Connection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    mtime_ns: i64,
+    size: i64,
+}
+
+impl FingerprintCache {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(std::time::Duration::from_secs(2))?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != FINGERPRINT_CACHE_VERSION {
+            conn.execute_batch(&format!(
+                "DROP TABLE IF EXISTS cached_files;
+                 DROP TABLE IF EXISTS cached_anchors;
+                 PRAGMA user_version = {FINGERPRINT_CACHE_VERSION};"
+            ))?;
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS cached_files (
+                 path TEXT PRIMARY KEY,
+                 mtime_ns INTEGER NOT NULL,
+                 size INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS cached_anchors (
+                 path TEXT NOT NULL,
+                 key TEXT NOT NULL,
+                 value TEXT NOT NULL,
+                 PRIMARY KEY (path, key)
+             );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// The value cached for `file` under `key`, computing and storing it
+    /// with `compute` on a miss. Files that cannot be stat'ed (deleted, or
+    /// not files) bypass the cache.
+    pub fn get_or_compute<T, E, F>(&self, file: &Path, key: &str, compute: F) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Result<T, E>,
+    {
+        let Some(stamp) = file_stamp(file) else {
+            return compute();
+        };
+        let path = file.to_string_lossy();
+        if let Ok(Some(value)) = self.lookup(&path, stamp, key) {
+            return Ok(value);
+        }
+        let value = compute()?;
+        // A cache that cannot be written (read-only, locked) just misses.
+        let _ = self.store(&path, stamp, key, &value);
+        Ok(value)
+    }
+
+    /// Number of files with cached entries.
+    pub fn file_count(&self) -> rusqlite::Result<u64> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM cached_files", [], |row| row.get(0))?;
+        Ok(count.max(0) as u64)
+    }
+
+    fn lookup<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        stamp: FileStamp,
+        key: &str,
+    ) -> rusqlite::Result<Option<T>> {
+        let recorded = self
+            .conn
+            .query_row(
+                "SELECT mtime_ns, size FROM cached_files WHERE path = ?1",
+                params![path],
+                |row| {
+                    Ok(FileStamp {
+                        mtime_ns: row.get(0)?,
+                        size: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        if recorded != Some(stamp) {
+            return Ok(None);
+        }
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM cached_anchors WHERE path = ?1 AND key = ?2",
+                params![path, key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.and_then(|value| serde_json::from_str(&value).ok()))
+    }
+
+    fn store<T: Serialize>(
+        &self,
+        path: &str,
+        stamp: FileStamp,
+        key: &str,
+        value: &T,
+    ) -> rusqlite::Result<()> {
+        let Ok(value) = serde_json::to_string(value) else {
+            return Ok(());
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        let recorded = tx
+            .query_row(
+                "SELECT mtime_ns, size FROM cached_files WHERE path = ?1",
+                params![path],
+                |row| {
+                    Ok(FileStamp {
+                        mtime_ns: row.get(0)?,
+                        size: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        if recorded != Some(stamp) {
+            tx.execute("DELETE FROM cached_anchors WHERE path = ?1", params![path])?;
+            tx.execute(
+                "INSERT INTO cached_files (path, mtime_ns, size) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(path) DO UPDATE SET mtime_ns = excluded.mtime_ns, size = excluded.size",
+                params![path, stamp.mtime_ns, stamp.size],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO cached_anchors (path, key, value) VALUES (?1, ?2, ?3)",
+            params![path, key, value],
+        )?;
+        tx.commit()
+    }
+}
+
+fn file_stamp(file: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(file)
+        .ok()
+        .filter(|metadata| metadata.is_file())?;
+    let mtime_ns = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some(FileStamp {
+        mtime_ns: i64::try_from(mtime_ns).ok()?,
+        size: i64::try_from(metadata.len()).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::convert::Infallible;
+
+    use super::*;
+
+    #[test]
+    fn caches_until_the_file_changes() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let file = temp.path().join("lib.rs");
+        fs::write(&file, "fn a() {}\n").expect("write");
+        let cache = FingerprintCache::open(&temp.path().join("fingerprints.sqlite")).expect("open");
+        let computed = Cell::new(0);
+        let anchors = |text: &str| {
+            computed.set(computed.get() + 1);
+            Ok::<_, Infallible>(vec![text.to_string()])
+        };
+
+        let first = cache
+            .get_or_compute(&file, "span:1-1", || anchors("a"))
+            .expect("first");
+        let second = cache
+            .get_or_compute(&file, "span:1-1", || anchors("b"))
+            .expect("second");
+        assert_eq!(
+            (first, second),
+            (vec!["a".to_string()], vec!["a".to_string()])
+        );
+        assert_eq!(computed.get(), 1);
+        assert_eq!(cache.file_count().expect("count"), 1);
+
+        fs::write(&file, "fn a() { changed() }\n").expect("rewrite");
+        let third = cache
+            .get_or_compute(&file, "span:1-1", || anchors("c"))
+            .expect("third");
+        assert_eq!(third, vec!["c".to_string()]);
+        assert_eq!(computed.get(), 2);
+
+        let missing = temp.path().join("gone.rs");
+        let _ = cache
+            .get_or_compute(&missing, "span:1-1", || anchors("d"))
+            .expect("missing");
+        let _ = cache
+            .get_or_compute(&missing, "span:1-1", || anchors("d"))
+            .expect("missing");
+        assert_eq!(computed.get(), 4);
+    }
+}
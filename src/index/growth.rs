@@ -0,0 +1,162 @@
+//! Store size forecasting: ingest records the size of the index and the
+//! tapes directory after each run, and `ingest` / `stats` project that
+//! growth forward to warn before `storage.quota` is reached.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::index::partitions::partition_dir;
+
+/// Growth is averaged over the samples recorded in this many days before
+/// the newest one.
+pub const GROWTH_WINDOW_DAYS: i64 = 30;
+
+/// Ingest keeps at most this many samples; older ones are dropped.
+pub const MAX_GROWTH_SAMPLES: usize = 1000;
+
+/// Size of the store after one ingest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrowthSample {
+    pub recorded_at: String,
+    /// The index file with its WAL and monthly partitions.
+    pub index_bytes: u64,
+    pub tapes_bytes: u64,
+}
+
+impl GrowthSample {
+    pub fn total_bytes(&self) -> u64 {
+        self.index_bytes + self.tapes_bytes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrowthForecast {
+    pub current_bytes: u64,
+    /// Average growth over the window; `None` until two samples a moment
+    /// apart exist. Never negative: `gc` and maintenance shrink the store
+    /// in steps a forecast should not extrapolate.
+    pub bytes_per_day: Option<f64>,
+    pub horizon_days: u32,
+    pub projected_bytes: u64,
+}
+
+/// Project `current_bytes` `horizon_days` ahead at the average growth of
+/// `samples` (oldest first) over the last [`GROWTH_WINDOW_DAYS`].
+pub fn forecast(samples: &[GrowthSample], current_bytes: u64, horizon_days: u32) -> GrowthForecast {
+    let timed = samples
+        .iter()
+        .filter_map(|sample| {
+            let at = DateTime::parse_from_rfc3339(&sample.recorded_at).ok()?;
+            Some((at.with_timezone(&Utc), sample.total_bytes()))
+        })
+        .collect::<Vec<_>>();
+    let bytes_per_day = timed.last().and_then(|(newest, newest_bytes)| {
+        let window_start = *newest - chrono::Duration::days(GROWTH_WINDOW_DAYS);
+        let (oldest, oldest_bytes) = timed.iter().find(|(at, _)| *at >= window_start)?;
+        let days = (*newest - *oldest).num_seconds() as f64 / 86_400.0;
+        (days > 0.0).then(|| (*newest_bytes as f64 - *oldest_bytes as f64).max(0.0) / days)
+    });
+    let projected_bytes = current_bytes
+        + bytes_per_day
+            .map(|rate| (rate * f64::from(horizon_days)).round() as u64)
+            .unwrap_or(0);
+    GrowthForecast {
+        current_bytes,
+        bytes_per_day,
+        horizon_days,
+        projected_bytes,
+    }
+}
+
+/// Bytes on disk of the index at `db_path` (with its `-wal`/`-shm` files and
+/// partitions) and of everything under `tapes_dir`.
+pub fn store_bytes(db_path: &Path, tapes_dir: &Path) -> io::Result<(u64, u64)> {
+    let mut index_bytes = 0;
+    for suffix in ["", "-wal", "-shm"] {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        index_bytes += path_bytes(Path::new(&path))?;
+    }
+    index_bytes += path_bytes(&partition_dir(db_path))?;
+    Ok((index_bytes, path_bytes(tapes_dir)?))
+}
+
+fn path_bytes(path: &Path) -> io::Result<u64> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        total += path_bytes(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(recorded_at: &str, index_bytes: u64, tapes_bytes: u64) -> GrowthSample {
+        GrowthSample {
+            recorded_at: recorded_at.to_string(),
+            index_bytes,
+            tapes_bytes,
+        }
+    }
+
+    #[test]
+    fn projects_average_daily_growth_over_the_window() {
+        let samples = [
+            sample("2026-01-01T00:00:00Z", 0, 0),
+            sample("2026-03-01T00:00:00Z", 1_000, 1_000),
+            sample("2026-03-11T00:00:00Z", 3_000, 4_000),
+            sample("2026-03-21T00:00:00Z", 6_000, 5_000),
+        ];
+        let growth = forecast(&samples, 11_000, 30);
+        assert_eq!(growth.bytes_per_day, Some(450.0));
+        assert_eq!(growth.projected_bytes, 11_000 + 13_500);
+
+        let single = forecast(&samples[..1], 500, 30);
+        assert_eq!(single.bytes_per_day, None);
+        assert_eq!(single.projected_bytes, 500);
+
+        let shrinking = [
+            sample("2026-03-01T00:00:00Z", 9_000, 0),
+            sample("2026-03-02T00:00:00Z", 1_000, 0),
+        ];
+        assert_eq!(forecast(&shrinking, 1_000, 30).bytes_per_day, Some(0.0));
+    }
+
+    #[test]
+    fn measures_the_index_with_its_wal_and_partitions() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let db = temp.path().join("index.sqlite");
+        fs::write(&db, [0; 100]).expect("db");
+        fs::write(temp.path().join("index.sqlite-wal"), [0; 20]).expect("wal");
+        fs::create_dir_all(temp.path().join("index.partitions")).expect("partitions");
+        fs::write(temp.path().join("index.partitions/2026-01.sqlite"), [0; 3]).expect("part");
+        fs::create_dir_all(temp.path().join("tapes/nested")).expect("tapes");
+        fs::write(temp.path().join("tapes/a.jsonl.zst"), [0; 7]).expect("tape");
+        fs::write(temp.path().join("tapes/nested/b.jsonl.zst"), [0; 5]).expect("tape");
+        assert_eq!(
+            store_bytes(&db, &temp.path().join("tapes")).expect("bytes"),
+            (123, 12)
+        );
+        assert_eq!(
+            store_bytes(
+                &temp.path().join("missing.sqlite"),
+                &temp.path().join("none")
+            )
+            .expect("bytes"),
+            (0, 0)
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Partition key (`YYYY-MM`) for an RFC 3339 timestamp, or `None` when the
+/// timestamp does not start with a calendar month.
+pub fn partition_key(timestamp: &str) -> Option<String> {
+    let key = timestamp.get(..7)?;
+    valid_partition_key(key).then(|| key.to_string())
+}
+
+pub fn valid_partition_key(key: &str) -> bool {
+    let bytes = key.as_bytes();
+    bytes.len() == 7
+        && bytes[4] == b'-'
+        && bytes[..4].iter().all(u8::is_ascii_digit)
+        && bytes[5..].iter().all(u8::is_ascii_digit)
+        && matches!(key[5..].parse::<u8>(), Ok(1..=12))
+}
+
+/// Directory holding the per-month index files of `db_path`
+/// (`index.sqlite` → `index.partitions/`).
+pub fn partition_dir(db_path: &Path) -> PathBuf {
+    let stem = db_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "index".to_string());
+    db_path.with_file_name(format!("{stem}.partitions"))
+}
+
+pub fn partition_path(db_path: &Path, key: &str) -> PathBuf {
+    partition_dir(db_path).join(format!("{key}.sqlite"))
+}
+
+/// Existing partitions of `db_path` as `(key, path)`, oldest first.
+pub fn list_partitions(db_path: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let entries = match fs::read_dir(partition_dir(db_path)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut partitions = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(key) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".sqlite"))
+        else {
+            continue;
+        };
+        if valid_partition_key(key) && path.is_file() {
+            partitions.push((key.to_string(), path));
+        }
+    }
+    partitions.sort();
+    Ok(partitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_come_from_the_timestamp_month() {
+        assert_eq!(
+            partition_key("2025-11-03T09:15:00Z").as_deref(),
+            Some("2025-11")
+        );
+        assert_eq!(partition_key("2025-13-01T00:00:00Z"), None);
+        assert_eq!(partition_key("yesterday"), None);
+        assert_eq!(partition_key(""), None);
+        assert!(!valid_partition_key("2025-1"));
+        assert!(!valid_partition_key("../2025"));
+    }
+
+    #[test]
+    fn partitions_live_next_to_the_index_and_list_oldest_first() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let db = temp.path().join("index.sqlite");
+        assert_eq!(partition_dir(&db), temp.path().join("index.partitions"));
+        assert!(list_partitions(&db).expect("missing dir").is_empty());
+
+        fs::create_dir_all(partition_dir(&db)).expect("mkdir");
+        for name in [
+            "2026-02.sqlite",
+            "2025-12.sqlite",
+            "notes.sqlite",
+            "2026-01.sqlite-wal",
+        ] {
+            fs::write(partition_dir(&db).join(name), b"").expect("write");
+        }
+        let keys = list_partitions(&db)
+            .expect("list")
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["2025-12", "2026-02"]);
+        assert_eq!(
+            partition_path(&db, "2025-12"),
+            temp.path().join("index.partitions").join("2025-12.sqlite")
+        );
+    }
+}
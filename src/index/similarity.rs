@@ -0,0 +1,217 @@
+//! Edge confidence scoring. When an edit's harness records no similarity,
+//! the index scores the edit's before and after text with the scorer chosen
+//! in `lineage.scorer` and records the scorer's tag (`token-jaccard.v1`) on
+//! every edge it produces. A scorer whose scores change for the same input
+//! gets a new version, so scores already in an index are never read as if a
+//! different scorer had produced them.
+
+use std::collections::{HashMap, HashSet};
+
+/// Recorded on edges whose confidence the harness supplied on the event.
+pub const HARNESS_SCORER: &str = "harness";
+
+/// The scorer used when `lineage.scorer` is not set.
+pub const DEFAULT_SCORER: &str = "token-jaccard";
+
+/// Above this many characters on either side, [`LevenshteinRatio`] compares
+/// lines instead of characters.
+const LEVENSHTEIN_MAX_CHARS: usize = 4096;
+
+/// Character shingle length for [`ShingledCosine`].
+const SHINGLE_CHARS: usize = 3;
+
+/// Scores how much of an edit's before text survives in its after text.
+pub trait SimilarityScorer: Sync {
+    /// The `lineage.scorer` name.
+    fn name(&self) -> &'static str;
+    fn version(&self) -> u32;
+    /// A score in `[0, 1]`: 1 for identical texts, 0 for unrelated ones.
+    fn score(&self, before: &str, after: &str) -> f32;
+
+    /// `<name>.v<version>`, as recorded on edges.
+    fn tag(&self) -> String {
+        format!("{}.v{}", self.name(), self.version())
+    }
+}
+
+/// `1 - edit distance / longer length`, over characters (lines for long
+/// texts).
+pub struct LevenshteinRatio;
+
+/// Overlap of the two sides' sets of identifier and number tokens.
+pub struct TokenJaccard;
+
+/// Cosine of the two sides' character-trigram counts, whitespace collapsed.
+pub struct ShingledCosine;
+
+const REGISTRY: &[&dyn SimilarityScorer] = &[&LevenshteinRatio, &TokenJaccard, &ShingledCosine];
+
+/// Every built-in scorer, for `lineage.scorer`.
+pub fn scorer_registry() -> &'static [&'static dyn SimilarityScorer] {
+    REGISTRY
+}
+
+pub fn scorer_for_name(name: &str) -> Option<&'static dyn SimilarityScorer> {
+    REGISTRY
+        .iter()
+        .copied()
+        .find(|scorer| scorer.name() == name)
+}
+
+/// The scorer used when `lineage.scorer` is not set.
+pub fn default_scorer() -> &'static dyn SimilarityScorer {
+    &TokenJaccard
+}
+
+impl SimilarityScorer for LevenshteinRatio {
+    fn name(&self) -> &'static str {
+        "levenshtein"
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn score(&self, before: &str, after: &str) -> f32 {
+        let (distance, longest) = if before.chars().count() > LEVENSHTEIN_MAX_CHARS
+            || after.chars().count() > LEVENSHTEIN_MAX_CHARS
+        {
+            let before = before.lines().collect::<Vec<_>>();
+            let after = after.lines().collect::<Vec<_>>();
+            (levenshtein(&before, &after), before.len().max(after.len()))
+        } else {
+            let before = before.chars().collect::<Vec<_>>();
+            let after = after.chars().collect::<Vec<_>>();
+            (levenshtein(&before, &after), before.len().max(after.len()))
+        };
+        if longest == 0 {
+            return 1.0;
+        }
+        1.0 - distance as f32 / longest as f32
+    }
+}
+
+impl SimilarityScorer for TokenJaccard {
+    fn name(&self) -> &'static str {
+        "token-jaccard"
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn score(&self, before: &str, after: &str) -> f32 {
+        let before = tokens(before).collect::<HashSet<_>>();
+        let after = tokens(after).collect::<HashSet<_>>();
+        let union = before.union(&after).count();
+        if union == 0 {
+            return 1.0;
+        }
+        before.intersection(&after).count() as f32 / union as f32
+    }
+}
+
+impl SimilarityScorer for ShingledCosine {
+    fn name(&self) -> &'static str {
+        "shingle-cosine"
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn score(&self, before: &str, after: &str) -> f32 {
+        let before = shingle_counts(before);
+        let after = shingle_counts(after);
+        if before.is_empty() && after.is_empty() {
+            return 1.0;
+        }
+        let dot = before
+            .iter()
+            .filter_map(|(shingle, count)| after.get(shingle).map(|other| count * other))
+            .sum::<f64>();
+        let norm = |counts: &HashMap<Vec<char>, f64>| {
+            counts
+                .values()
+                .map(|count| count * count)
+                .sum::<f64>()
+                .sqrt()
+        };
+        let denominator = norm(&before) * norm(&after);
+        if denominator == 0.0 {
+            return 0.0;
+        }
+        (dot / denominator).clamp(0.0, 1.0) as f32
+    }
+}
+
+fn levenshtein<T: PartialEq>(before: &[T], after: &[T]) -> usize {
+    let mut previous = (0..=after.len()).collect::<Vec<_>>();
+    let mut current = vec![0; after.len() + 1];
+    for (i, left) in before.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, right) in after.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left != right);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[after.len()]
+}
+
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|token| !token.is_empty())
+}
+
+fn shingle_counts(text: &str) -> HashMap<Vec<char>, f64> {
+    let chars = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let chars = chars.chars().collect::<Vec<_>>();
+    let mut counts = HashMap::new();
+    if chars.len() < SHINGLE_CHARS {
+        if !chars.is_empty() {
+            counts.insert(chars, 1.0);
+        }
+        return counts;
+    }
+    for shingle in chars.windows(SHINGLE_CHARS) {
+        *counts.entry(shingle.to_vec()).or_insert(0.0) += 1.0;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_scorers_rank_small_edits_above_rewrites() {
+        let before = "let total = orders.iter().map(|order| order.amount).sum::<u64>();\n";
+        let tweak = "let total = orders.iter().map(|order| order.amount).sum::<u64>() + fees;\n";
+        let rewrite = "async fn fetch(client: Client) -> Result<Response> { client.get().await }\n";
+        for scorer in scorer_registry() {
+            assert_eq!(scorer.score(before, before), 1.0, "{}", scorer.name());
+            let small = scorer.score(before, tweak);
+            let large = scorer.score(before, rewrite);
+            assert!(small > 0.7 && small < 1.0, "{}: {small}", scorer.name());
+            assert!(large < 0.3, "{}: {large}", scorer.name());
+            assert_eq!(scorer.score("", ""), 1.0, "{}", scorer.name());
+            assert_eq!(scorer.score("abc", ""), 0.0, "{}", scorer.name());
+        }
+    }
+
+    #[test]
+    fn scorers_are_found_by_name_and_tagged_by_version() {
+        assert_eq!(
+            scorer_for_name("levenshtein").map(|scorer| scorer.tag()),
+            Some("levenshtein.v1".to_string())
+        );
+        assert!(scorer_for_name("cosine").is_none());
+        assert_eq!(default_scorer().name(), DEFAULT_SCORER);
+        assert_eq!(LevenshteinRatio.score("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+        let long = "line\n".repeat(LEVENSHTEIN_MAX_CHARS);
+        let longer = format!("{long}extra\n");
+        let score = LevenshteinRatio.score(&long, &longer);
+        assert!(score > 0.99 && score < 1.0, "{score}");
+    }
+}
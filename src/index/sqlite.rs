@@ -0,0 +1,4526 @@
+use std::cell::RefCell;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Deref;
+use std::path::Path;
+
+use rusqlite::{Connection, params};
+
+use crate::anchor::normalize::{SpanNormalizer, normalizer_for_tag};
+use crate::anchor::{
+    ANCHOR_VERSION_RAW, anchor_preview, expand_winnow_anchor, normalized_anchor_hashes,
+    normalized_token_hashes,
+};
+use crate::config::IngestLimits;
+use crate::embed::{cosine_similarity, decode_vector, encode_vector};
+use crate::index::cache::{AnchorCache, AnchorCacheStats, CacheSlot, CachedRows};
+use crate::index::growth::GrowthSample;
+use crate::index::lineage::{
+    Cardinality, EvidenceFragmentRef, EvidenceKind, LINK_THRESHOLD_DEFAULT, LocationDelta,
+    SpanEdge, StoredEdgeClass, Tombstone,
+};
+use crate::index::similarity::{HARNESS_SCORER, SimilarityScorer, default_scorer};
+use crate::tape::catalog::EventRecord;
+use crate::tape::event::{FileRange, TapeEventAt, TapeEventData};
+use crate::tape::metrics::TapeMetrics;
+use crate::tape::origin::{TapeOrigin, tape_origin};
+use crate::tape::paths::{PathPolicy, repo_relative_path, slash_path};
+use crate::tape::refs::{IssueRef, IssueRefSource};
+use crate::tape::spawn::SpawnCall;
+use crate::tape::split::TapeSplit;
+
+use super::*;
+
+impl IngestDiagnostics {
+    fn take_evidence_row(&mut self, limits: IngestLimits) -> bool {
+        if self.evidence_rows < limits.max_evidence_rows.unwrap_or(usize::MAX) {
+            self.evidence_rows += 1;
+            true
+        } else {
+            self.evidence_rows_dropped += 1;
+            false
+        }
+    }
+
+    fn take_edge(&mut self, limits: IngestLimits) -> bool {
+        if self.edges < limits.max_edges.unwrap_or(usize::MAX) {
+            self.edges += 1;
+            true
+        } else {
+            self.edges_dropped += 1;
+            false
+        }
+    }
+}
+
+/// The schema version every opened index is migrated to.
+const SCHEMA_VERSION: i64 = 26;
+
+type SchemaStep = fn(&SqliteIndex) -> rusqlite::Result<()>;
+
+/// `SCHEMAS[n - 1]` creates schema vn, idempotently.
+const SCHEMAS: [SchemaStep; SCHEMA_VERSION as usize] = [
+    SqliteIndex::create_schema_v1,
+    SqliteIndex::create_schema_v2,
+    SqliteIndex::create_schema_v3,
+    SqliteIndex::create_schema_v4,
+    SqliteIndex::create_schema_v5,
+    SqliteIndex::create_schema_v6,
+    SqliteIndex::create_schema_v7,
+    SqliteIndex::create_schema_v8,
+    SqliteIndex::create_schema_v9,
+    SqliteIndex::create_schema_v10,
+    SqliteIndex::create_schema_v11,
+    SqliteIndex::create_schema_v12,
+    SqliteIndex::create_schema_v13,
+    SqliteIndex::create_schema_v14,
+    SqliteIndex::create_schema_v15,
+    SqliteIndex::create_schema_v16,
+    SqliteIndex::create_schema_v17,
+    SqliteIndex::create_schema_v18,
+    SqliteIndex::create_schema_v19,
+    SqliteIndex::create_schema_v20,
+    SqliteIndex::create_schema_v21,
+    SqliteIndex::create_schema_v22,
+    SqliteIndex::create_schema_v23,
+    SqliteIndex::create_schema_v24,
+    SqliteIndex::create_schema_v25,
+    SqliteIndex::create_schema_v26,
+];
+
+/// `MIGRATIONS[n - 1]` moves an index at schema vn to v(n + 1).
+const MIGRATIONS: [SchemaStep; SCHEMA_VERSION as usize - 1] = [
+    SqliteIndex::migrate_v1_to_v2,
+    SqliteIndex::migrate_v2_to_v3,
+    SqliteIndex::migrate_v3_to_v4,
+    SqliteIndex::migrate_v4_to_v5,
+    SqliteIndex::migrate_v5_to_v6,
+    SqliteIndex::migrate_v6_to_v7,
+    SqliteIndex::migrate_v7_to_v8,
+    SqliteIndex::migrate_v8_to_v9,
+    SqliteIndex::migrate_v9_to_v10,
+    SqliteIndex::migrate_v10_to_v11,
+    SqliteIndex::migrate_v11_to_v12,
+    SqliteIndex::migrate_v12_to_v13,
+    SqliteIndex::migrate_v13_to_v14,
+    SqliteIndex::migrate_v14_to_v15,
+    SqliteIndex::migrate_v15_to_v16,
+    SqliteIndex::migrate_v16_to_v17,
+    SqliteIndex::migrate_v17_to_v18,
+    SqliteIndex::migrate_v18_to_v19,
+    SqliteIndex::migrate_v19_to_v20,
+    SqliteIndex::migrate_v20_to_v21,
+    SqliteIndex::migrate_v21_to_v22,
+    SqliteIndex::migrate_v22_to_v23,
+    SqliteIndex::migrate_v23_to_v24,
+    SqliteIndex::migrate_v24_to_v25,
+    SqliteIndex::migrate_v25_to_v26,
+];
+
+pub struct SqliteIndex {
+    conn: Connection,
+    anchor_cache: RefCell<Option<AnchorCache>>,
+    /// Schema names of sibling indexes attached with
+    /// [`SqliteIndex::attach_read_only`].
+    attached: Vec<String>,
+    /// Confidence from which a stored edge reads back as lineage.
+    link_threshold: f32,
+    /// Scores edits whose harness recorded no similarity.
+    scorer: &'static dyn SimilarityScorer,
+    /// RFC 3339 cutoff from [`SqliteIndex::with_as_of`]: evidence, edges,
+    /// tombstones and annotations recorded after it are not read.
+    as_of: Option<String>,
+}
+
+/// Bulk-load scope from [`SqliteIndex::bulk_load`]. While it is alive commits
+/// run with `synchronous = NORMAL`: in WAL mode a crash or power loss can
+/// lose the most recent commits but cannot corrupt the index. [`finish`]
+/// (or dropping the guard) restores `synchronous = FULL` and checkpoints the
+/// WAL into the database file, so everything loaded is durable afterwards.
+///
+/// [`finish`]: BulkLoad::finish
+pub struct BulkLoad<'a> {
+    index: &'a SqliteIndex,
+    finished: bool,
+}
+
+impl BulkLoad<'_> {
+    pub fn finish(mut self) -> rusqlite::Result<()> {
+        self.finished = true;
+        self.index.end_bulk_load()
+    }
+}
+
+impl Drop for BulkLoad<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.index.end_bulk_load();
+        }
+    }
+}
+
+impl SqliteIndex {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        let index = Self {
+            conn,
+            anchor_cache: RefCell::new(None),
+            attached: Vec::new(),
+            link_threshold: LINK_THRESHOLD_DEFAULT,
+            scorer: default_scorer(),
+            as_of: None,
+        };
+        index.init_schema()?;
+        Ok(index)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        let index = Self {
+            conn,
+            anchor_cache: RefCell::new(None),
+            attached: Vec::new(),
+            link_threshold: LINK_THRESHOLD_DEFAULT,
+            scorer: default_scorer(),
+            as_of: None,
+        };
+        index.init_schema()?;
+        Ok(index)
+    }
+
+    /// Keep the edge lists and evidence of up to `capacity` recently queried
+    /// anchors in memory, for long-lived processes that ask about the same
+    /// spans over and over. Any write to the index, from this connection or
+    /// another, empties the cache before the next lookup.
+    pub fn with_anchor_cache(self, capacity: usize) -> Self {
+        *self.anchor_cache.borrow_mut() = Some(AnchorCache::new(capacity));
+        self
+    }
+
+    /// Classify edges read back from the index against `link_threshold`
+    /// (`lineage.link_threshold`) instead of [`LINK_THRESHOLD_DEFAULT`].
+    pub fn with_link_threshold(mut self, link_threshold: f32) -> Self {
+        self.link_threshold = link_threshold;
+        self
+    }
+
+    /// Score edits without a recorded similarity with `scorer`
+    /// (`lineage.scorer`) instead of the default.
+    pub fn with_similarity_scorer(mut self, scorer: &'static dyn SimilarityScorer) -> Self {
+        self.scorer = scorer;
+        self
+    }
+
+    /// Read the index as it stood at `as_of` (RFC 3339, compared as text):
+    /// only evidence, tombstones and annotations timestamped at or before it,
+    /// and only edges whose `to_anchor` has such evidence (edges carry no
+    /// time of their own).
+    pub fn with_as_of(mut self, as_of: impl Into<String>) -> Self {
+        self.as_of = Some(as_of.into());
+        self
+    }
+
+    pub fn anchor_cache_stats(&self) -> Option<AnchorCacheStats> {
+        self.anchor_cache.borrow().as_ref().map(AnchorCache::stats)
+    }
+
+    /// Attach another engram index read-only, so edge, evidence and tombstone
+    /// lookups (and with them every lineage walk) see both stores. The
+    /// sibling must be at this index's schema version; running any engram
+    /// command in its repository migrates it.
+    pub fn attach_read_only(&mut self, path: &Path) -> rusqlite::Result<()> {
+        let schema = format!("sibling{}", self.attached.len());
+        let uri = format!(
+            "file:{}?mode=ro",
+            path.to_string_lossy()
+                .replace('%', "%25")
+                .replace('?', "%3f")
+                .replace('#', "%23")
+        );
+        self.conn
+            .execute(&format!("ATTACH DATABASE ?1 AS {schema}"), params![uri])?;
+        let version = |schema: &str| -> rusqlite::Result<i64> {
+            self.conn
+                .query_row(&format!("PRAGMA {schema}.user_version"), [], |row| {
+                    row.get(0)
+                })
+        };
+        let (own, theirs) = (version("main")?, version(&schema)?);
+        if own != theirs {
+            self.conn
+                .execute_batch(&format!("DETACH DATABASE {schema}"))?;
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "{} is at index schema v{theirs}, expected v{own}",
+                path.display()
+            )));
+        }
+        self.attached.push(schema);
+        if let Some(cache) = self.anchor_cache.get_mut() {
+            *cache = AnchorCache::new(cache.stats().capacity);
+        }
+        Ok(())
+    }
+
+    /// `select(schema)` for `main` and every attached sibling, joined with
+    /// `UNION` so a row stored in more than one index comes back once.
+    fn across_stores(&self, select: impl Fn(&str) -> String) -> String {
+        std::iter::once("main")
+            .chain(self.attached.iter().map(String::as_str))
+            .map(select)
+            .collect::<Vec<_>>()
+            .join(" UNION ")
+    }
+
+    /// Serve `load` from the anchor cache when enabled and still current.
+    fn cached_rows(
+        &self,
+        slot: CacheSlot,
+        anchor: &str,
+        load: impl FnOnce() -> rusqlite::Result<CachedRows>,
+    ) -> rusqlite::Result<CachedRows> {
+        if self.anchor_cache.borrow().is_none() {
+            return load();
+        }
+        // `data_version` moves when another connection commits;
+        // `total_changes` when this one writes.
+        let data_version: i64 = self
+            .conn
+            .query_row("PRAGMA data_version", [], |row| row.get(0))?;
+        let version = (data_version, self.conn.total_changes());
+        {
+            let mut cache = self.anchor_cache.borrow_mut();
+            let cache = cache.as_mut().expect("cache enabled");
+            cache.sync_version(version);
+            if let Some(rows) = cache.get(slot, anchor) {
+                return Ok(rows);
+            }
+        }
+        let rows = load()?;
+        if let Some(cache) = self.anchor_cache.borrow_mut().as_mut() {
+            cache.insert(slot, anchor, rows.clone());
+        }
+        Ok(rows)
+    }
+
+    fn init_schema(&self) -> rusqlite::Result<()> {
+        // `auto_vacuum` only takes effect on a database without tables (or at
+        // the next VACUUM), so it has to come before WAL writes the header.
+        self.conn.execute_batch(
+            "
+            PRAGMA auto_vacuum = INCREMENTAL;
+            PRAGMA foreign_keys = ON;
+            PRAGMA journal_mode = WAL;
+            PRAGMA synchronous = FULL;
+            ",
+        )?;
+
+        let version: i64 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let current = match version {
+            0 => {
+                if self.table_exists("evidence")? {
+                    self.migrate_legacy_schema_to_v1()?;
+                } else {
+                    self.create_schema_v1()?;
+                    self.conn.execute_batch("PRAGMA user_version = 1;")?;
+                }
+                1
+            }
+            1..=SCHEMA_VERSION => {
+                // Repair anything missing at the recorded version before
+                // stepping up from it.
+                SCHEMAS[version as usize - 1](self)?;
+                version
+            }
+            _ => return Err(rusqlite::Error::InvalidQuery),
+        };
+        for migrate in &MIGRATIONS[current as usize - 1..] {
+            migrate(self)?;
+        }
+        Ok(())
+    }
+
+    fn column_exists(&self, table: &str, column: &str) -> rusqlite::Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    fn table_exists(&self, name: &str) -> rusqlite::Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![name],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    fn create_schema_v1(&self) -> rusqlite::Result<()> {
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS evidence (
+                anchor TEXT NOT NULL,
+                tape_id TEXT NOT NULL,
+                event_offset INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                UNIQUE(anchor, tape_id, event_offset, kind)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_evidence_anchor ON evidence(anchor);
+
+            CREATE TABLE IF NOT EXISTS edges (
+                from_anchor TEXT NOT NULL,
+                to_anchor TEXT NOT NULL,
+                confidence REAL NOT NULL CHECK (confidence >= 0.0 AND confidence <= 1.0),
+                location_delta TEXT NOT NULL,
+                cardinality TEXT NOT NULL,
+                agent_link INTEGER NOT NULL CHECK (agent_link IN (0, 1)),
+                note TEXT NOT NULL DEFAULT '',
+                UNIQUE(from_anchor, to_anchor, confidence, location_delta, cardinality, agent_link, note)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_edges_from_anchor ON edges(from_anchor);
+            CREATE INDEX IF NOT EXISTS idx_edges_to_anchor ON edges(to_anchor);
+
+            CREATE TABLE IF NOT EXISTS tombstones (
+                anchor TEXT NOT NULL,
+                tape_id TEXT NOT NULL,
+                event_offset INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                range_start INTEGER NOT NULL,
+                range_end INTEGER NOT NULL,
+                timestamp TEXT NOT NULL,
+                UNIQUE(anchor, tape_id, event_offset)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_tombstones_anchor ON tombstones(anchor);
+
+            CREATE TABLE IF NOT EXISTS tapes (
+                tape_id TEXT PRIMARY KEY
+            );
+            ",
+        )?;
+        Ok(())
+    }
+
+    fn create_schema_v2(&self) -> rusqlite::Result<()> {
+        self.create_schema_v1()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS dispatch_links (
+                tape_id TEXT NOT NULL,
+                uuid TEXT NOT NULL,
+                first_turn_index INTEGER NOT NULL,
+                direction TEXT NOT NULL CHECK(direction IN ('received', 'sent')),
+                PRIMARY KEY (tape_id, uuid)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_dispatch_links_uuid ON dispatch_links(uuid);
+            CREATE INDEX IF NOT EXISTS idx_dispatch_links_tape ON dispatch_links(tape_id);
+            CREATE INDEX IF NOT EXISTS idx_dispatch_links_received
+                ON dispatch_links(tape_id, direction, first_turn_index);
+            ",
+        )?;
+        Ok(())
+    }
+
+    fn create_schema_v3(&self) -> rusqlite::Result<()> {
+        self.create_schema_v2()?;
+        self.ensure_query_feedback_schema()?;
+        Ok(())
+    }
+
+    fn create_schema_v4(&self) -> rusqlite::Result<()> {
+        self.create_schema_v3()?;
+        self.ensure_tape_metrics_columns()?;
+        Ok(())
+    }
+
+    fn create_schema_v5(&self) -> rusqlite::Result<()> {
+        self.create_schema_v4()?;
+        if !self.column_exists("edges", "reverted")? {
+            self.conn.execute_batch(
+                "ALTER TABLE edges ADD COLUMN reverted INTEGER NOT NULL DEFAULT 0
+                    CHECK (reverted IN (0, 1));",
+            )?;
+        }
+        Ok(())
+    }
+
+    fn create_schema_v6(&self) -> rusqlite::Result<()> {
+        self.create_schema_v5()?;
+        for table in ["evidence", "tombstones"] {
+            if !self.column_exists(table, "raw_file_path")? {
+                self.conn.execute_batch(&format!(
+                    "ALTER TABLE {table} ADD COLUMN raw_file_path TEXT;"
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn create_schema_v7(&self) -> rusqlite::Result<()> {
+        self.create_schema_v6()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS event_embeddings (
+                tape_id TEXT NOT NULL,
+                event_offset INTEGER NOT NULL,
+                provider TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                PRIMARY KEY (tape_id, event_offset, provider)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_event_embeddings_provider
+                ON event_embeddings(provider);
+            ",
+        )
+    }
+
+    fn create_schema_v8(&self) -> rusqlite::Result<()> {
+        self.create_schema_v7()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS maintenance_state (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                tapes_since_maintain INTEGER NOT NULL DEFAULT 0,
+                last_maintained_at TEXT
+            );
+
+            INSERT OR IGNORE INTO maintenance_state (id) VALUES (1);
+            ",
+        )
+    }
+
+    fn create_schema_v9(&self) -> rusqlite::Result<()> {
+        self.create_schema_v8()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS annotations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                target TEXT NOT NULL,
+                note TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS annotation_anchors (
+                annotation_id INTEGER NOT NULL REFERENCES annotations(id) ON DELETE CASCADE,
+                anchor TEXT NOT NULL,
+                PRIMARY KEY (annotation_id, anchor)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_annotation_anchors_anchor
+                ON annotation_anchors(anchor);
+            ",
+        )
+    }
+
+    fn create_schema_v10(&self) -> rusqlite::Result<()> {
+        self.create_schema_v9()?;
+        self.add_missing_columns(
+            "tapes",
+            &[
+                ("source_path", "TEXT"),
+                ("source_start", "INTEGER"),
+                ("source_end", "INTEGER"),
+                ("source_sha256", "TEXT"),
+                ("adapter", "TEXT"),
+                ("adapter_version", "TEXT"),
+                ("converted_at", "TEXT"),
+            ],
+        )
+    }
+
+    fn create_schema_v11(&self) -> rusqlite::Result<()> {
+        self.create_schema_v10()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS events (
+                tape_id TEXT NOT NULL,
+                event_offset INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                tool TEXT,
+                file_path TEXT,
+                timestamp TEXT NOT NULL,
+                PRIMARY KEY (tape_id, event_offset)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_events_kind ON events(kind);
+            CREATE INDEX IF NOT EXISTS idx_events_tool ON events(tool);
+            CREATE INDEX IF NOT EXISTS idx_events_file_path ON events(file_path);
+            CREATE INDEX IF NOT EXISTS idx_events_timestamp ON events(timestamp);
+            ",
+        )
+    }
+
+    fn create_schema_v12(&self) -> rusqlite::Result<()> {
+        self.create_schema_v11()?;
+        self.add_missing_columns(
+            "events",
+            &[("exit_code", "INTEGER"), ("output_bytes", "INTEGER")],
+        )
+    }
+
+    fn create_schema_v13(&self) -> rusqlite::Result<()> {
+        self.create_schema_v12()?;
+        self.add_missing_columns("tapes", &[("thrash_loop_count", "INTEGER")])
+    }
+
+    fn create_schema_v14(&self) -> rusqlite::Result<()> {
+        self.create_schema_v13()?;
+        self.add_missing_columns("tapes", &[("anchor_version", "INTEGER")])
+    }
+
+    fn create_schema_v15(&self) -> rusqlite::Result<()> {
+        self.create_schema_v14()?;
+        self.conn.execute_batch(
+            "
+            CREATE INDEX IF NOT EXISTS idx_evidence_file_path
+                ON evidence(file_path, timestamp);
+            CREATE INDEX IF NOT EXISTS idx_evidence_timestamp ON evidence(timestamp);
+            ",
+        )
+    }
+
+    fn create_schema_v16(&self) -> rusqlite::Result<()> {
+        self.create_schema_v15()?;
+        self.add_missing_columns(
+            "tapes",
+            &[
+                ("coverage_read", "TEXT"),
+                ("coverage_edit", "TEXT"),
+                ("coverage_tool", "TEXT"),
+            ],
+        )
+    }
+
+    fn create_schema_v17(&self) -> rusqlite::Result<()> {
+        self.create_schema_v16()?;
+        self.add_missing_columns("tapes", &[("cwd", "TEXT")])?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS spawn_calls (
+                tape_id TEXT NOT NULL,
+                event_offset INTEGER NOT NULL,
+                harness TEXT NOT NULL,
+                command TEXT NOT NULL,
+                cwd TEXT,
+                earliest TEXT NOT NULL,
+                latest TEXT NOT NULL,
+                PRIMARY KEY (tape_id, event_offset)
+            );
+
+            CREATE TABLE IF NOT EXISTS spawn_links (
+                tape_id TEXT NOT NULL,
+                parent_tape_id TEXT NOT NULL,
+                parent_offset INTEGER NOT NULL,
+                PRIMARY KEY (tape_id, parent_tape_id, parent_offset)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_spawn_links_parent ON spawn_links(parent_tape_id);
+            ",
+        )
+    }
+
+    fn create_schema_v18(&self) -> rusqlite::Result<()> {
+        self.create_schema_v17()?;
+        self.add_missing_columns("tapes", &[("session_id", "TEXT")])
+    }
+
+    fn create_schema_v19(&self) -> rusqlite::Result<()> {
+        self.create_schema_v18()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS refs (
+                tape_id TEXT NOT NULL,
+                ref_key TEXT NOT NULL,
+                event_offset INTEGER NOT NULL,
+                source TEXT NOT NULL,
+                PRIMARY KEY (tape_id, ref_key, event_offset, source)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_refs_key ON refs(ref_key COLLATE NOCASE);
+            ",
+        )
+    }
+
+    fn create_schema_v20(&self) -> rusqlite::Result<()> {
+        self.create_schema_v19()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS tape_splits (
+                tape_id TEXT PRIMARY KEY,
+                parent_tape_id TEXT NOT NULL,
+                part INTEGER NOT NULL,
+                parts INTEGER NOT NULL,
+                first_offset INTEGER NOT NULL,
+                last_offset INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_tape_splits_parent ON tape_splits(parent_tape_id);
+            ",
+        )
+    }
+
+    fn create_schema_v21(&self) -> rusqlite::Result<()> {
+        self.create_schema_v20()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS tape_joins (
+                tape_id TEXT NOT NULL,
+                member_tape_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                PRIMARY KEY (tape_id, member_tape_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_tape_joins_member ON tape_joins(member_tape_id);
+            ",
+        )
+    }
+
+    fn create_schema_v22(&self) -> rusqlite::Result<()> {
+        self.create_schema_v21()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS anchors (
+                anchor TEXT PRIMARY KEY,
+                first_seen TEXT NOT NULL,
+                anchor_version INTEGER,
+                preview TEXT,
+                occurrences INTEGER NOT NULL DEFAULT 0,
+                alias_of TEXT
+            );
+            ",
+        )
+    }
+
+    fn create_schema_v23(&self) -> rusqlite::Result<()> {
+        self.create_schema_v22()?;
+        self.add_missing_columns("edges", &[("scorer", "TEXT")])
+    }
+
+    fn create_schema_v24(&self) -> rusqlite::Result<()> {
+        self.create_schema_v23()?;
+        self.add_missing_columns("evidence", &[("project", "TEXT")])?;
+        self.conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_evidence_project ON evidence(project, tape_id);",
+        )
+    }
+
+    fn create_schema_v25(&self) -> rusqlite::Result<()> {
+        self.create_schema_v24()?;
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS growth_samples (
+                recorded_at TEXT NOT NULL,
+                index_bytes INTEGER NOT NULL,
+                tapes_bytes INTEGER NOT NULL
+            );
+            ",
+        )
+    }
+
+    fn create_schema_v26(&self) -> rusqlite::Result<()> {
+        self.create_schema_v25()?;
+        self.add_missing_columns("tapes", &[("hostname", "TEXT"), ("user", "TEXT")])
+    }
+
+    /// `ALTER TABLE table ADD COLUMN` for each of `columns` not there yet.
+    fn add_missing_columns(&self, table: &str, columns: &[(&str, &str)]) -> rusqlite::Result<()> {
+        for (column, decl) in columns {
+            if !self.column_exists(table, column)? {
+                self.conn
+                    .execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl};"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn ensure_tape_metrics_columns(&self) -> rusqlite::Result<()> {
+        self.add_missing_columns(
+            "tapes",
+            &[
+                ("event_count", "INTEGER"),
+                ("message_count", "INTEGER"),
+                ("tool_call_count", "INTEGER"),
+                ("tool_error_count", "INTEGER"),
+                ("edit_count", "INTEGER"),
+                ("revert_count", "INTEGER"),
+                ("started_at", "TEXT"),
+                ("ended_at", "TEXT"),
+                ("duration_secs", "INTEGER"),
+                ("model", "TEXT"),
+                ("harness", "TEXT"),
+            ],
+        )
+    }
+
+    fn ensure_query_feedback_schema(&self) -> rusqlite::Result<()> {
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS query_results (
+                result_id TEXT PRIMARY KEY,
+                command TEXT NOT NULL,
+                payload_json TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_query_results_command
+                ON query_results(command, created_at);
+
+            CREATE TABLE IF NOT EXISTS result_feedback (
+                result_id TEXT PRIMARY KEY,
+                outcome TEXT NOT NULL,
+                note TEXT,
+                rated_at TEXT NOT NULL,
+                FOREIGN KEY(result_id) REFERENCES query_results(result_id) ON DELETE CASCADE
+            );
+            ",
+        )
+    }
+
+    fn migrate_v1_to_v2(&self) -> rusqlite::Result<()> {
+        self.create_schema_v2()?;
+        self.conn.execute_batch("PRAGMA user_version = 2;")?;
+        Ok(())
+    }
+
+    fn migrate_v2_to_v3(&self) -> rusqlite::Result<()> {
+        self.create_schema_v3()?;
+        self.conn.execute_batch("PRAGMA user_version = 3;")?;
+        Ok(())
+    }
+
+    fn migrate_v3_to_v4(&self) -> rusqlite::Result<()> {
+        self.create_schema_v4()?;
+        self.conn.execute_batch("PRAGMA user_version = 4;")?;
+        Ok(())
+    }
+
+    fn migrate_v4_to_v5(&self) -> rusqlite::Result<()> {
+        self.create_schema_v5()?;
+        self.conn.execute_batch("PRAGMA user_version = 5;")?;
+        Ok(())
+    }
+
+    fn migrate_v5_to_v6(&self) -> rusqlite::Result<()> {
+        self.create_schema_v6()?;
+        self.conn.execute_batch("PRAGMA user_version = 6;")?;
+        Ok(())
+    }
+
+    fn migrate_v6_to_v7(&self) -> rusqlite::Result<()> {
+        self.create_schema_v7()?;
+        self.conn.execute_batch("PRAGMA user_version = 7;")?;
+        Ok(())
+    }
+
+    fn migrate_v7_to_v8(&self) -> rusqlite::Result<()> {
+        self.create_schema_v8()?;
+        self.conn.execute_batch("PRAGMA user_version = 8;")?;
+        Ok(())
+    }
+
+    fn migrate_v8_to_v9(&self) -> rusqlite::Result<()> {
+        self.create_schema_v9()?;
+        self.conn.execute_batch("PRAGMA user_version = 9;")?;
+        Ok(())
+    }
+
+    fn migrate_v9_to_v10(&self) -> rusqlite::Result<()> {
+        self.create_schema_v10()?;
+        self.conn.execute_batch("PRAGMA user_version = 10;")?;
+        Ok(())
+    }
+
+    fn migrate_v10_to_v11(&self) -> rusqlite::Result<()> {
+        self.create_schema_v11()?;
+        self.conn.execute_batch("PRAGMA user_version = 11;")?;
+        Ok(())
+    }
+
+    fn migrate_v11_to_v12(&self) -> rusqlite::Result<()> {
+        self.create_schema_v12()?;
+        self.conn.execute_batch("PRAGMA user_version = 12;")?;
+        Ok(())
+    }
+
+    fn migrate_v12_to_v13(&self) -> rusqlite::Result<()> {
+        self.create_schema_v13()?;
+        self.conn.execute_batch("PRAGMA user_version = 13;")?;
+        Ok(())
+    }
+
+    fn migrate_v13_to_v14(&self) -> rusqlite::Result<()> {
+        self.create_schema_v14()?;
+        self.conn.execute_batch("PRAGMA user_version = 14;")?;
+        Ok(())
+    }
+
+    fn migrate_v14_to_v15(&self) -> rusqlite::Result<()> {
+        self.create_schema_v15()?;
+        self.conn.execute_batch("PRAGMA user_version = 15;")?;
+        Ok(())
+    }
+
+    fn migrate_v15_to_v16(&self) -> rusqlite::Result<()> {
+        self.create_schema_v16()?;
+        self.conn.execute_batch("PRAGMA user_version = 16;")?;
+        Ok(())
+    }
+
+    fn migrate_v16_to_v17(&self) -> rusqlite::Result<()> {
+        self.create_schema_v17()?;
+        self.conn.execute_batch("PRAGMA user_version = 17;")?;
+        Ok(())
+    }
+
+    fn migrate_v17_to_v18(&self) -> rusqlite::Result<()> {
+        self.create_schema_v18()?;
+        self.conn.execute_batch("PRAGMA user_version = 18;")?;
+        Ok(())
+    }
+
+    fn migrate_v18_to_v19(&self) -> rusqlite::Result<()> {
+        self.create_schema_v19()?;
+        self.conn.execute_batch("PRAGMA user_version = 19;")?;
+        Ok(())
+    }
+
+    fn migrate_v19_to_v20(&self) -> rusqlite::Result<()> {
+        self.create_schema_v20()?;
+        self.conn.execute_batch("PRAGMA user_version = 20;")?;
+        Ok(())
+    }
+
+    fn migrate_v20_to_v21(&self) -> rusqlite::Result<()> {
+        self.create_schema_v21()?;
+        self.conn.execute_batch("PRAGMA user_version = 21;")?;
+        Ok(())
+    }
+
+    fn migrate_v21_to_v22(&self) -> rusqlite::Result<()> {
+        self.create_schema_v22()?;
+        self.conn.execute_batch("PRAGMA user_version = 22;")?;
+        Ok(())
+    }
+
+    fn migrate_v22_to_v23(&self) -> rusqlite::Result<()> {
+        self.create_schema_v23()?;
+        self.conn.execute_batch("PRAGMA user_version = 23;")?;
+        Ok(())
+    }
+
+    fn migrate_v23_to_v24(&self) -> rusqlite::Result<()> {
+        self.create_schema_v24()?;
+        self.conn.execute_batch("PRAGMA user_version = 24;")?;
+        Ok(())
+    }
+
+    fn migrate_v24_to_v25(&self) -> rusqlite::Result<()> {
+        self.create_schema_v25()?;
+        self.conn.execute_batch("PRAGMA user_version = 25;")?;
+        Ok(())
+    }
+
+    fn migrate_v25_to_v26(&self) -> rusqlite::Result<()> {
+        self.create_schema_v26()?;
+        self.conn.execute_batch("PRAGMA user_version = 26;")?;
+        Ok(())
+    }
+
+    fn migrate_legacy_schema_to_v1(&self) -> rusqlite::Result<()> {
+        self.conn.execute_batch(
+            "
+            BEGIN IMMEDIATE;
+            ALTER TABLE evidence RENAME TO evidence_legacy;
+            ALTER TABLE edges RENAME TO edges_legacy;
+            ALTER TABLE tombstones RENAME TO tombstones_legacy;
+            COMMIT;
+            ",
+        )?;
+
+        self.create_schema_v1()?;
+        self.conn.execute_batch(
+            "
+            INSERT OR IGNORE INTO evidence (anchor, tape_id, event_offset, kind, file_path, timestamp)
+            SELECT anchor, tape_id, event_offset, kind, file_path, timestamp
+            FROM evidence_legacy;
+
+            INSERT OR IGNORE INTO edges (
+                from_anchor, to_anchor, confidence, location_delta, cardinality,
+                agent_link, note
+            )
+            SELECT
+                from_anchor,
+                to_anchor,
+                confidence,
+                location_delta,
+                cardinality,
+                agent_link,
+                COALESCE(note, '')
+            FROM edges_legacy;
+
+            INSERT OR IGNORE INTO tombstones (
+                anchor, tape_id, event_offset, file_path, range_start, range_end, timestamp
+            )
+            SELECT anchor, tape_id, event_offset, file_path, range_start, range_end, timestamp
+            FROM tombstones_legacy;
+
+            DROP TABLE evidence_legacy;
+            DROP TABLE edges_legacy;
+            DROP TABLE tombstones_legacy;
+            PRAGMA user_version = 1;
+            ",
+        )?;
+        Ok(())
+    }
+
+    pub fn insert_evidence(
+        &self,
+        anchor: &str,
+        fragment: &EvidenceFragmentRef,
+    ) -> rusqlite::Result<()> {
+        Self::validate_anchor(anchor)?;
+        Self::insert_evidence_on(&self.conn, anchor, fragment, None)
+    }
+
+    fn insert_evidence_on(
+        conn: &Connection,
+        anchor: &str,
+        fragment: &EvidenceFragmentRef,
+        project: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        Self::validate_anchor(anchor)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO evidence (anchor, tape_id, event_offset, kind, file_path, timestamp, project)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                anchor,
+                fragment.tape_id,
+                fragment.event_offset,
+                encode_evidence_kind(fragment.kind),
+                fragment.file_path,
+                fragment.timestamp,
+                project
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn insert_edge(&self, edge: &SpanEdge, link_threshold: f32) -> rusqlite::Result<()> {
+        Self::validate_anchor(&edge.from_anchor)?;
+        Self::validate_anchor(&edge.to_anchor)?;
+        Self::insert_edge_on(&self.conn, edge, link_threshold, None)
+    }
+
+    fn insert_edge_on(
+        conn: &Connection,
+        edge: &SpanEdge,
+        _link_threshold: f32,
+        scorer: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        Self::validate_anchor(&edge.from_anchor)?;
+        Self::validate_anchor(&edge.to_anchor)?;
+        Self::validate_confidence(edge.confidence)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO edges (
+                from_anchor, to_anchor, confidence, location_delta, cardinality,
+                agent_link, note, scorer
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                edge.from_anchor,
+                edge.to_anchor,
+                edge.confidence,
+                encode_location_delta(edge.location_delta),
+                encode_cardinality(edge.cardinality),
+                if edge.agent_link { 1_i64 } else { 0_i64 },
+                edge.note.as_deref().unwrap_or(""),
+                scorer
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// True when an earlier edge already moved `after` content to `before`
+    /// content, i.e. this edit undoes it. Works across tapes because edges are
+    /// keyed by content anchors, not by session.
+    fn restores_earlier_edit_on(
+        conn: &Connection,
+        before_anchors: &[String],
+        after_anchors: &[String],
+    ) -> rusqlite::Result<bool> {
+        let mut stmt = conn.prepare_cached(
+            "SELECT 1 FROM edges WHERE from_anchor = ?1 AND to_anchor = ?2 LIMIT 1",
+        )?;
+        for after_anchor in after_anchors {
+            for before_anchor in before_anchors {
+                if stmt.exists(params![after_anchor, before_anchor])? {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    fn mark_edge_reverted_on(
+        conn: &Connection,
+        from_anchor: &str,
+        to_anchor: &str,
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            "UPDATE edges SET reverted = 1 WHERE from_anchor = ?1 AND to_anchor = ?2",
+            params![from_anchor, to_anchor],
+        )?;
+        Ok(())
+    }
+
+    pub fn insert_tombstone(&self, tombstone: &Tombstone) -> rusqlite::Result<()> {
+        Self::insert_tombstone_on(&self.conn, tombstone)
+    }
+
+    fn insert_tombstone_on(conn: &Connection, tombstone: &Tombstone) -> rusqlite::Result<()> {
+        for anchor in &tombstone.anchor_hashes {
+            Self::validate_anchor(anchor)?;
+            conn.execute(
+                "INSERT OR IGNORE INTO tombstones (
+                    anchor, tape_id, event_offset, file_path, range_start, range_end, timestamp
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    anchor,
+                    tombstone.tape_id,
+                    tombstone.event_offset,
+                    tombstone.file_path,
+                    tombstone.range_at_deletion.start,
+                    tombstone.range_at_deletion.end,
+                    tombstone.timestamp
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn insert_dispatch_link(&self, tape_id: &str, link: &DispatchLink) -> rusqlite::Result<()> {
+        Self::insert_dispatch_link_on(&self.conn, tape_id, link)
+    }
+
+    fn insert_dispatch_link_on(
+        conn: &Connection,
+        tape_id: &str,
+        link: &DispatchLink,
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            "INSERT OR IGNORE INTO dispatch_links (tape_id, uuid, first_turn_index, direction)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                tape_id,
+                link.uuid,
+                link.first_turn_index,
+                encode_dispatch_direction(link.direction)
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_query_result(
+        &self,
+        result_id: &str,
+        command: &str,
+        payload_json: &str,
+        created_at: &str,
+    ) -> rusqlite::Result<()> {
+        self.ensure_query_feedback_schema()?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO query_results (result_id, command, payload_json, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![result_id, command, payload_json, created_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn query_result_exists(&self, result_id: &str) -> rusqlite::Result<bool> {
+        self.ensure_query_feedback_schema()?;
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM query_results WHERE result_id = ?1",
+            params![result_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    pub fn upsert_result_feedback(
+        &self,
+        result_id: &str,
+        outcome: &str,
+        note: Option<&str>,
+        rated_at: &str,
+    ) -> rusqlite::Result<()> {
+        self.ensure_query_feedback_schema()?;
+        self.conn.execute(
+            "INSERT INTO result_feedback (result_id, outcome, note, rated_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(result_id) DO UPDATE SET
+               outcome = excluded.outcome,
+               note = excluded.note,
+               rated_at = excluded.rated_at",
+            params![result_id, outcome, note, rated_at],
+        )?;
+        Ok(())
+    }
+
+    /// Relax per-commit fsyncs for a large load; see [`BulkLoad`]. This is the
+    /// only way the index leaves `synchronous = FULL`.
+    pub fn bulk_load(&self) -> rusqlite::Result<BulkLoad<'_>> {
+        self.conn.execute_batch("PRAGMA synchronous = NORMAL;")?;
+        Ok(BulkLoad {
+            index: self,
+            finished: false,
+        })
+    }
+
+    fn end_bulk_load(&self) -> rusqlite::Result<()> {
+        self.conn.execute_batch("PRAGMA synchronous = FULL;")?;
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(FULL)", [], |_| Ok(()))
+    }
+
+    #[cfg(test)]
+    fn synchronous_level(&self) -> rusqlite::Result<i64> {
+        self.conn
+            .query_row("PRAGMA synchronous", [], |row| row.get(0))
+    }
+
+    /// Count `count` newly indexed tapes toward the next automatic
+    /// maintenance run; returns the running total since the last run.
+    pub fn note_tapes_indexed(&self, count: u64) -> rusqlite::Result<u64> {
+        self.conn.execute(
+            "UPDATE maintenance_state SET tapes_since_maintain = tapes_since_maintain + ?1
+             WHERE id = 1",
+            params![count as i64],
+        )?;
+        let total: i64 = self.conn.query_row(
+            "SELECT tapes_since_maintain FROM maintenance_state WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(total.max(0) as u64)
+    }
+
+    /// Record the store's size after an ingest, keeping the newest
+    /// [`MAX_GROWTH_SAMPLES`](growth::MAX_GROWTH_SAMPLES).
+    pub fn record_growth_sample(&self, sample: &GrowthSample) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO growth_samples (recorded_at, index_bytes, tapes_bytes)
+             VALUES (?1, ?2, ?3)",
+            params![
+                sample.recorded_at,
+                sample.index_bytes as i64,
+                sample.tapes_bytes as i64
+            ],
+        )?;
+        self.conn.execute(
+            "DELETE FROM growth_samples WHERE rowid NOT IN (
+                SELECT rowid FROM growth_samples ORDER BY recorded_at DESC, rowid DESC LIMIT ?1
+             )",
+            params![growth::MAX_GROWTH_SAMPLES as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Recorded store sizes, oldest first.
+    pub fn growth_samples(&self) -> rusqlite::Result<Vec<GrowthSample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT recorded_at, index_bytes, tapes_bytes FROM growth_samples
+             ORDER BY recorded_at, rowid",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(GrowthSample {
+                recorded_at: row.get(0)?,
+                index_bytes: row.get::<_, i64>(1)?.max(0) as u64,
+                tapes_bytes: row.get::<_, i64>(2)?.max(0) as u64,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Refresh planner statistics, reclaim free pages and checkpoint the WAL.
+    /// An index created before incremental auto-vacuum existed is converted
+    /// with one full `VACUUM`; later runs only free what is unused.
+    pub fn maintain(&self, now: &str) -> rusqlite::Result<MaintenanceReport> {
+        let pragma = |name: &str| -> rusqlite::Result<i64> {
+            self.conn
+                .query_row(&format!("PRAGMA {name}"), [], |row| row.get(0))
+        };
+        let pages_before = pragma("page_count")?;
+        let free_pages_before = pragma("freelist_count")?;
+        let converted_to_incremental_vacuum = pragma("auto_vacuum")? != 2;
+        if converted_to_incremental_vacuum {
+            self.conn
+                .execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")?;
+        } else {
+            self.conn.execute_batch("PRAGMA incremental_vacuum;")?;
+        }
+        self.conn.execute_batch("ANALYZE; PRAGMA optimize;")?;
+        self.conn.execute(
+            "UPDATE maintenance_state SET tapes_since_maintain = 0, last_maintained_at = ?1
+             WHERE id = 1",
+            params![now],
+        )?;
+        let (checkpoint_busy, wal_frames_checkpointed) =
+            self.conn
+                .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+                    Ok((row.get::<_, i64>(0)? != 0, row.get::<_, i64>(2)?))
+                })?;
+        Ok(MaintenanceReport {
+            pages_before,
+            pages_after: pragma("page_count")?,
+            free_pages_before,
+            free_pages_after: pragma("freelist_count")?,
+            converted_to_incremental_vacuum,
+            wal_frames_checkpointed: wal_frames_checkpointed.max(0),
+            checkpoint_busy,
+        })
+    }
+
+    /// Copy recorded query results and their ratings from another index file
+    /// (used by `reindex` so a rebuilt index keeps its feedback). Returns the
+    /// number of `(query_results, result_feedback)` rows copied.
+    pub fn import_query_feedback(&self, source_path: &str) -> rusqlite::Result<(usize, usize)> {
+        self.ensure_query_feedback_schema()?;
+        self.conn
+            .execute("ATTACH DATABASE ?1 AS source", params![source_path])?;
+        let copied = (|| -> rusqlite::Result<(usize, usize)> {
+            let has_table = |name: &str| -> rusqlite::Result<bool> {
+                self.conn.query_row(
+                    "SELECT COUNT(*) > 0 FROM source.sqlite_master WHERE type = 'table' AND name = ?1",
+                    params![name],
+                    |row| row.get(0),
+                )
+            };
+            let mut results = 0;
+            let mut feedback = 0;
+            if has_table("query_results")? {
+                results = self.conn.execute(
+                    "INSERT OR IGNORE INTO query_results (result_id, command, payload_json, created_at)
+                     SELECT result_id, command, payload_json, created_at FROM source.query_results",
+                    [],
+                )?;
+            }
+            if has_table("result_feedback")? {
+                feedback = self.conn.execute(
+                    "INSERT OR IGNORE INTO result_feedback (result_id, outcome, note, rated_at)
+                     SELECT f.result_id, f.outcome, f.note, f.rated_at
+                     FROM source.result_feedback f
+                     JOIN query_results q ON q.result_id = f.result_id",
+                    [],
+                )?;
+            }
+            Ok((results, feedback))
+        })();
+        self.conn.execute_batch("DETACH DATABASE source;")?;
+        copied
+    }
+
+    /// Copy human annotations from another index file (used by `reindex`).
+    /// Returns the number of annotations copied.
+    pub fn import_annotations(&self, source_path: &str) -> rusqlite::Result<usize> {
+        self.conn
+            .execute("ATTACH DATABASE ?1 AS source", params![source_path])?;
+        let copied = (|| -> rusqlite::Result<usize> {
+            let has_table: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM source.sqlite_master
+                 WHERE type = 'table' AND name = 'annotation_anchors'",
+                [],
+                |row| row.get(0),
+            )?;
+            if !has_table {
+                return Ok(0);
+            }
+            let tx = self.conn.unchecked_transaction()?;
+            let copied = tx.execute(
+                "INSERT OR IGNORE INTO annotations (id, target, note, created_at)
+                 SELECT id, target, note, created_at FROM source.annotations",
+                [],
+            )?;
+            tx.execute(
+                "INSERT OR IGNORE INTO annotation_anchors (annotation_id, anchor)
+                 SELECT annotation_id, anchor FROM source.annotation_anchors",
+                [],
+            )?;
+            tx.commit()?;
+            Ok(copied)
+        })();
+        self.conn.execute_batch("DETACH DATABASE source;")?;
+        copied
+    }
+
+    /// Attach a human note to every anchor of `target` (a span's anchors, or
+    /// a single anchor). Returns the annotation id.
+    pub fn add_annotation(
+        &self,
+        target: &str,
+        anchors: &[String],
+        note: &str,
+        created_at: &str,
+    ) -> rusqlite::Result<i64> {
+        for anchor in anchors {
+            Self::validate_anchor(anchor)?;
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO annotations (target, note, created_at) VALUES (?1, ?2, ?3)",
+            params![target, note, created_at],
+        )?;
+        let id = tx.last_insert_rowid();
+        for anchor in anchors {
+            tx.execute(
+                "INSERT OR IGNORE INTO annotation_anchors (annotation_id, anchor) VALUES (?1, ?2)",
+                params![id, anchor],
+            )?;
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Whether a note with this exact text is already attached to `target`.
+    pub fn has_annotation(&self, target: &str, note: &str) -> rusqlite::Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM annotations WHERE target = ?1 AND note = ?2",
+            params![target, note],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Annotations attached to any of `anchors`, oldest first, each with the
+    /// subset of `anchors` it matched.
+    pub fn annotations_for_anchors(&self, anchors: &[String]) -> rusqlite::Result<Vec<Annotation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, a.target, a.note, a.created_at
+             FROM annotation_anchors aa
+             JOIN annotations a ON a.id = aa.annotation_id
+             WHERE aa.anchor = ?1
+               AND (?2 IS NULL OR a.created_at <= ?2)",
+        )?;
+        let mut by_id = BTreeMap::<i64, Annotation>::new();
+        for anchor in anchors {
+            let mut rows = stmt.query(params![anchor, self.as_of])?;
+            while let Some(row) = rows.next()? {
+                let id: i64 = row.get(0)?;
+                let annotation = match by_id.entry(id) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(Annotation {
+                        id,
+                        target: row.get(1)?,
+                        note: row.get(2)?,
+                        created_at: row.get(3)?,
+                        matched_anchors: Vec::new(),
+                    }),
+                };
+                if !annotation.matched_anchors.contains(anchor) {
+                    annotation.matched_anchors.push(anchor.clone());
+                }
+            }
+        }
+        Ok(by_id.into_values().collect())
+    }
+
+    pub fn evidence_for_anchor(&self, anchor: &str) -> rusqlite::Result<Vec<EvidenceFragmentRef>> {
+        match self.cached_rows(CacheSlot::Evidence, anchor, || {
+            self.load_evidence_for_anchor(anchor)
+                .map(CachedRows::Evidence)
+        })? {
+            CachedRows::Evidence(rows) => Ok(rows),
+            CachedRows::Edges(_) => unreachable!("evidence slot holds evidence"),
+        }
+    }
+
+    fn load_evidence_for_anchor(&self, anchor: &str) -> rusqlite::Result<Vec<EvidenceFragmentRef>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} ORDER BY timestamp ASC, tape_id ASC, event_offset ASC",
+            self.across_stores(|schema| format!(
+                "SELECT tape_id, event_offset, kind, file_path, timestamp
+                 FROM {schema}.evidence
+                 WHERE anchor = ?1
+                   AND (?2 IS NULL OR timestamp <= ?2)"
+            ))
+        ))?;
+
+        let mut rows = stmt.query(params![anchor, self.as_of])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(EvidenceFragmentRef {
+                tape_id: row.get(0)?,
+                event_offset: row.get(1)?,
+                kind: decode_evidence_kind(&row.get::<_, String>(2)?),
+                file_path: row.get(3)?,
+                timestamp: row.get(4)?,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Evidence on files matching `file_glob` (SQLite `GLOB`), with its
+    /// anchor, inside `range`, oldest first, across attached stores.
+    pub fn evidence_for_file(
+        &self,
+        file_glob: &str,
+        range: &TimeRange,
+    ) -> rusqlite::Result<Vec<(String, EvidenceFragmentRef)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} ORDER BY timestamp ASC, tape_id ASC, event_offset ASC",
+            self.across_stores(|schema| format!(
+                "SELECT anchor, tape_id, event_offset, kind, file_path, timestamp
+                 FROM {schema}.evidence
+                 WHERE file_path GLOB ?1
+                   AND (?2 IS NULL OR timestamp >= ?2)
+                   AND (?3 IS NULL OR timestamp < ?3)"
+            ))
+        ))?;
+        let rows = stmt.query_map(params![file_glob, range.since, range.until], |row| {
+            Ok((
+                row.get(0)?,
+                EvidenceFragmentRef {
+                    tape_id: row.get(1)?,
+                    event_offset: row.get(2)?,
+                    kind: decode_evidence_kind(&row.get::<_, String>(3)?),
+                    file_path: row.get(4)?,
+                    timestamp: row.get(5)?,
+                },
+            ))
+        })?;
+        rows.collect()
+    }
+
+    /// Lineage edges created inside `range`, each with the time of the
+    /// earliest evidence for its `to_anchor` there (edges carry no time of
+    /// their own; the edit that produced one recorded its target). Oldest
+    /// first, across attached stores.
+    pub fn edges_in_window(&self, range: &TimeRange) -> rusqlite::Result<Vec<(String, EdgeRow)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} ORDER BY 10 ASC, 3 DESC",
+            self.across_stores(|schema| format!(
+                "SELECT edges.from_anchor, edges.to_anchor, edges.confidence,
+                        edges.location_delta, edges.cardinality, edges.agent_link,
+                        edges.note, edges.reverted, edges.scorer, MIN(evidence.timestamp)
+                 FROM {schema}.edges AS edges
+                 JOIN {schema}.evidence AS evidence ON evidence.anchor = edges.to_anchor
+                 WHERE (?1 IS NULL OR evidence.timestamp >= ?1)
+                   AND (?2 IS NULL OR evidence.timestamp < ?2)
+                 GROUP BY edges.rowid"
+            ))
+        ))?;
+        let rows = stmt.query_map(params![range.since, range.until], |row| {
+            Ok((row.get(9)?, decode_edge_row(row, self.link_threshold)?))
+        })?;
+        rows.collect()
+    }
+
+    /// Every stored edge, across attached stores, before any confidence
+    /// filtering.
+    pub fn all_edges(&self) -> rusqlite::Result<Vec<EdgeRow>> {
+        let mut stmt = self.conn.prepare(&self.across_stores(|schema| {
+            format!(
+                "SELECT from_anchor, to_anchor, confidence, location_delta, cardinality,
+                        agent_link, note, reverted, scorer
+                 FROM {schema}.edges"
+            )
+        }))?;
+        let rows = stmt.query_map([], |row| decode_edge_row(row, self.link_threshold))?;
+        rows.collect()
+    }
+
+    pub fn window_anchor_stats_for_file(
+        &self,
+        file_path: &str,
+    ) -> rusqlite::Result<Vec<(String, u64)>> {
+        let file_path = slash_path(file_path);
+        let collation = if PathPolicy::host().folds_case(&file_path) {
+            "COLLATE NOCASE"
+        } else {
+            ""
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT anchor, COUNT(*) AS hits
+             FROM evidence
+             WHERE file_path = ?1 {collation}
+               AND instr(anchor, ',') > 0
+             GROUP BY anchor
+             ORDER BY hits DESC, anchor ASC"
+        ))?;
+
+        let mut rows = stmt.query(params![file_path])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push((row.get(0)?, row.get(1)?));
+        }
+        Ok(out)
+    }
+
+    pub fn outbound_edges(
+        &self,
+        from_anchor: &str,
+        min_confidence: f32,
+        include_forensics: bool,
+    ) -> rusqlite::Result<Vec<EdgeRow>> {
+        let edges = self.edges_at(CacheSlot::Outbound, from_anchor)?;
+        Ok(filter_edges(edges, min_confidence, include_forensics))
+    }
+
+    pub fn inbound_edges(
+        &self,
+        to_anchor: &str,
+        min_confidence: f32,
+        include_forensics: bool,
+    ) -> rusqlite::Result<Vec<EdgeRow>> {
+        let edges = self.edges_at(CacheSlot::Inbound, to_anchor)?;
+        Ok(filter_edges(edges, min_confidence, include_forensics))
+    }
+
+    /// Every stored edge leaving (`Outbound`) or entering (`Inbound`) `anchor`,
+    /// highest confidence first, before any confidence filtering.
+    fn edges_at(&self, slot: CacheSlot, anchor: &str) -> rusqlite::Result<Vec<EdgeRow>> {
+        let column = match slot {
+            CacheSlot::Outbound => "from_anchor",
+            CacheSlot::Inbound => "to_anchor",
+            CacheSlot::Evidence => unreachable!("edge slots only"),
+        };
+        let rows = self.cached_rows(slot, anchor, || {
+            let mut stmt = self.conn.prepare(&format!(
+                "{} ORDER BY confidence DESC",
+                self.across_stores(|schema| format!(
+                    "SELECT from_anchor, to_anchor, confidence, location_delta, cardinality,
+                            agent_link, note, reverted, scorer
+                     FROM {schema}.edges AS edges
+                     WHERE {column} = ?1
+                       AND (?2 IS NULL OR EXISTS (
+                           SELECT 1 FROM {schema}.evidence AS evidence
+                           WHERE evidence.anchor = edges.to_anchor
+                             AND evidence.timestamp <= ?2
+                       ))"
+                ))
+            ))?;
+            let mut rows = stmt.query(params![anchor, self.as_of])?;
+            let mut out = Vec::new();
+            while let Some(row) = rows.next()? {
+                out.push(decode_edge_row(row, self.link_threshold)?);
+            }
+            Ok(CachedRows::Edges(out))
+        })?;
+        match rows {
+            CachedRows::Edges(edges) => Ok(edges),
+            CachedRows::Evidence(_) => unreachable!("edge slots hold edges"),
+        }
+    }
+
+    pub fn tombstones_for_anchor(&self, anchor: &str) -> rusqlite::Result<Vec<Tombstone>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} ORDER BY event_offset ASC",
+            self.across_stores(|schema| format!(
+                "SELECT tape_id, event_offset, file_path, range_start, range_end, timestamp
+                 FROM {schema}.tombstones
+                 WHERE anchor = ?1
+                   AND (?2 IS NULL OR timestamp <= ?2)"
+            ))
+        ))?;
+
+        let mut rows = stmt.query(params![anchor, self.as_of])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(Tombstone {
+                anchor_hashes: vec![anchor.to_string()],
+                tape_id: row.get(0)?,
+                event_offset: row.get(1)?,
+                file_path: row.get(2)?,
+                range_at_deletion: FileRange {
+                    start: row.get(3)?,
+                    end: row.get(4)?,
+                },
+                timestamp: row.get(5)?,
+            });
+        }
+
+        Ok(out)
+    }
+
+    pub fn has_event_embeddings(&self, tape_id: &str, provider: &str) -> rusqlite::Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM event_embeddings WHERE tape_id = ?1 AND provider = ?2",
+            params![tape_id, provider],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Store `(event_offset, kind, vector)` embeddings for a tape, replacing
+    /// any earlier vector from the same provider.
+    pub fn upsert_event_embeddings(
+        &self,
+        tape_id: &str,
+        provider: &str,
+        embeddings: &[(u64, &str, Vec<f32>)],
+    ) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO event_embeddings
+                    (tape_id, event_offset, provider, kind, vector)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (offset, kind, vector) in embeddings {
+                stmt.execute(params![
+                    tape_id,
+                    *offset as i64,
+                    provider,
+                    kind,
+                    encode_vector(vector)
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Highest-scoring stored events for `query` among vectors from
+    /// `provider`, by cosine similarity. This is a full scan; the vectors of
+    /// one provider are assumed to fit a local index comfortably.
+    pub fn semantic_search(
+        &self,
+        provider: &str,
+        query: &[f32],
+        limit: usize,
+    ) -> rusqlite::Result<Vec<SemanticHit>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tape_id, event_offset, kind, vector FROM event_embeddings WHERE provider = ?1",
+        )?;
+        let rows = stmt.query_map(params![provider], |row| {
+            let vector: Vec<u8> = row.get(3)?;
+            Ok(SemanticHit {
+                tape_id: row.get(0)?,
+                event_offset: row.get::<_, i64>(1)? as u64,
+                kind: row.get(2)?,
+                score: cosine_similarity(query, &decode_vector(&vector)),
+            })
+        })?;
+        let mut hits = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.tape_id.cmp(&b.tape_id))
+                .then_with(|| a.event_offset.cmp(&b.event_offset))
+        });
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    pub fn referenced_tape_ids(&self) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tape_id FROM evidence
+             UNION
+             SELECT tape_id FROM tombstones
+             UNION
+             SELECT parent_tape_id FROM tape_splits
+             UNION
+             SELECT member_tape_id FROM tape_joins",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(row.get(0)?);
+        }
+        Ok(out)
+    }
+
+    pub fn has_tape(&self, tape_id: &str) -> rusqlite::Result<bool> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT 1 FROM tapes WHERE tape_id = ?1 LIMIT 1")?;
+        let mut rows = stmt.query(params![tape_id])?;
+        Ok(rows.next()?.is_some())
+    }
+
+    pub fn tape_ids(&self) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tape_id FROM tapes ORDER BY tape_id ASC")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Tapes that started before `cutoff` (compared as text, so a `YYYY-MM`
+    /// month works) and still have evidence or tombstones in this index, as
+    /// `(tape_id, started_at)` oldest first.
+    pub fn resident_tapes_started_before(
+        &self,
+        cutoff: &str,
+    ) -> rusqlite::Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tape_id, started_at FROM tapes
+             WHERE started_at IS NOT NULL AND started_at < ?1
+               AND tape_id IN (SELECT tape_id FROM evidence UNION SELECT tape_id FROM tombstones)
+             ORDER BY started_at ASC, tape_id ASC",
+        )?;
+        let rows = stmt.query_map(params![cutoff], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Drop the evidence and tombstones of tapes that now live in a
+    /// partition. Their `tapes` rows, dispatch links and embeddings stay, and
+    /// so do edges: those are keyed by content anchors, so they keep lineage
+    /// connected across partitions. Returns the number of rows removed.
+    pub fn detach_tape_evidence(&self, tape_ids: &[String]) -> rusqlite::Result<usize> {
+        self.delete_tape_rows(tape_ids, &["evidence", "tombstones"])
+    }
+
+    /// Forget tapes entirely (used after their partition was dropped).
+    /// Edges are left alone because they cannot be attributed to one tape.
+    pub fn forget_tapes(&self, tape_ids: &[String]) -> rusqlite::Result<usize> {
+        // Registry entries stay, like edges; only their counts drop.
+        for tape_id in tape_ids {
+            self.conn.execute(
+                "UPDATE anchors SET occurrences = MAX(0, occurrences - counted.n)
+                 FROM (
+                     SELECT anchor, COUNT(*) AS n FROM evidence
+                     WHERE tape_id = ?1 GROUP BY anchor
+                 ) AS counted
+                 WHERE anchors.anchor = counted.anchor",
+                params![tape_id],
+            )?;
+        }
+        let removed = self.delete_tape_rows(
+            tape_ids,
+            &[
+                "evidence",
+                "tombstones",
+                "dispatch_links",
+                "event_embeddings",
+                "events",
+                "spawn_calls",
+                "spawn_links",
+                "refs",
+                "tape_splits",
+                "tape_joins",
+                "tapes",
+            ],
+        )?;
+        let orphaned = self.conn.execute(
+            "DELETE FROM spawn_links WHERE NOT EXISTS (
+                SELECT 1 FROM spawn_calls
+                WHERE spawn_calls.tape_id = spawn_links.parent_tape_id
+                  AND spawn_calls.event_offset = spawn_links.parent_offset
+             )",
+            [],
+        )?;
+        Ok(removed + orphaned)
+    }
+
+    fn delete_tape_rows(&self, tape_ids: &[String], tables: &[&str]) -> rusqlite::Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute_batch(
+            "CREATE TEMP TABLE IF NOT EXISTS doomed_tapes (tape_id TEXT PRIMARY KEY);
+             DELETE FROM temp.doomed_tapes;",
+        )?;
+        for tape_id in tape_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO temp.doomed_tapes (tape_id) VALUES (?1)",
+                params![tape_id],
+            )?;
+        }
+        let mut removed = 0;
+        for table in tables {
+            removed += tx.execute(
+                &format!(
+                    "DELETE FROM main.{table} WHERE tape_id IN (SELECT tape_id FROM temp.doomed_tapes)"
+                ),
+                [],
+            )?;
+        }
+        tx.execute("DELETE FROM temp.doomed_tapes", [])?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Replace the event catalog of `tape_id` (see `engram events`).
+    pub fn record_tape_events(
+        &self,
+        tape_id: &str,
+        records: &[EventRecord],
+    ) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM events WHERE tape_id = ?1", params![tape_id])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO events (
+                    tape_id, event_offset, kind, tool, file_path, timestamp, exit_code,
+                    output_bytes
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for record in records {
+                stmt.execute(params![
+                    tape_id,
+                    record.offset as i64,
+                    record.kind,
+                    record.tool,
+                    record.file,
+                    record.timestamp,
+                    record.exit,
+                    record.output_bytes.map(|bytes| bytes as i64)
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Replace the harness-starting calls of `tape_id` and note the directory
+    /// the tape ran in, then link it to the tapes it started and the call
+    /// that started it. A spawned tape has the call's harness and starts in
+    /// the call's window, in the call's directory or below it when both are
+    /// known. Needs the tapes' metrics recorded first. Returns the number of
+    /// links now touching `tape_id`.
+    pub fn record_spawn_calls(
+        &self,
+        tape_id: &str,
+        cwd: Option<&str>,
+        calls: &[SpawnCall],
+    ) -> rusqlite::Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO tapes (tape_id) VALUES (?1)",
+            params![tape_id],
+        )?;
+        tx.execute(
+            "UPDATE tapes SET cwd = ?2 WHERE tape_id = ?1",
+            params![tape_id, cwd],
+        )?;
+        tx.execute(
+            "DELETE FROM spawn_calls WHERE tape_id = ?1",
+            params![tape_id],
+        )?;
+        tx.execute(
+            "DELETE FROM spawn_links WHERE tape_id = ?1 OR parent_tape_id = ?1",
+            params![tape_id],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO spawn_calls (
+                    tape_id, event_offset, harness, command, cwd, earliest, latest
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for call in calls {
+                stmt.execute(params![
+                    tape_id,
+                    call.offset as i64,
+                    call.harness,
+                    call.command,
+                    call.cwd,
+                    call.earliest,
+                    call.latest
+                ])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR IGNORE INTO spawn_links (tape_id, parent_tape_id, parent_offset)
+             SELECT tapes.tape_id, spawn_calls.tape_id, spawn_calls.event_offset
+             FROM spawn_calls
+             JOIN tapes ON tapes.harness = spawn_calls.harness
+                AND tapes.tape_id != spawn_calls.tape_id
+                AND tapes.started_at BETWEEN spawn_calls.earliest AND spawn_calls.latest
+                AND (spawn_calls.cwd IS NULL OR tapes.cwd IS NULL
+                     OR tapes.cwd = spawn_calls.cwd
+                     OR substr(tapes.cwd, 1, length(spawn_calls.cwd) + 1)
+                        = spawn_calls.cwd || '/')
+             WHERE spawn_calls.tape_id = ?1 OR tapes.tape_id = ?1",
+            params![tape_id],
+        )?;
+        let links = tx.query_row(
+            "SELECT COUNT(*) FROM spawn_links WHERE tape_id = ?1 OR parent_tape_id = ?1",
+            params![tape_id],
+            |row| row.get::<_, i64>(0),
+        )?;
+        tx.commit()?;
+        Ok(links.max(0) as usize)
+    }
+
+    /// The calls that started `tape_id`.
+    pub fn spawn_parents(&self, tape_id: &str) -> rusqlite::Result<Vec<SpawnLinkRow>> {
+        self.spawn_links_where("spawn_links.tape_id = ?1", params![tape_id])
+    }
+
+    /// The tapes started by calls in `tape_id`.
+    pub fn spawn_children(&self, tape_id: &str) -> rusqlite::Result<Vec<SpawnLinkRow>> {
+        self.spawn_links_where("spawn_links.parent_tape_id = ?1", params![tape_id])
+    }
+
+    pub fn all_spawn_links(&self) -> rusqlite::Result<Vec<SpawnLinkRow>> {
+        self.spawn_links_where("1 = 1", params![])
+    }
+
+    fn spawn_links_where(
+        &self,
+        condition: &str,
+        values: &[&dyn rusqlite::ToSql],
+    ) -> rusqlite::Result<Vec<SpawnLinkRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT spawn_links.parent_tape_id, spawn_links.parent_offset, spawn_links.tape_id,
+                    spawn_calls.command
+             FROM spawn_links
+             JOIN spawn_calls ON spawn_calls.tape_id = spawn_links.parent_tape_id
+                AND spawn_calls.event_offset = spawn_links.parent_offset
+             WHERE {condition}
+             ORDER BY spawn_links.parent_tape_id, spawn_links.parent_offset, spawn_links.tape_id"
+        ))?;
+        let rows = stmt.query_map(values, |row| {
+            Ok(SpawnLinkRow {
+                parent_tape_id: row.get(0)?,
+                parent_offset: row.get::<_, i64>(1)?.max(0) as u64,
+                child_tape_id: row.get(2)?,
+                command: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Replace the issue keys `tape_id` mentions (see [`crate::tape::refs`]).
+    pub fn record_issue_refs(&self, tape_id: &str, refs: &[IssueRef]) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM refs WHERE tape_id = ?1", params![tape_id])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO refs (tape_id, ref_key, event_offset, source)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for issue in refs {
+                stmt.execute(params![
+                    tape_id,
+                    issue.key,
+                    issue.offset as i64,
+                    issue.source.as_str()
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// The issue keys `tape_id` mentions, in tape order.
+    pub fn issue_refs(&self, tape_id: &str) -> rusqlite::Result<Vec<IssueRef>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ref_key, event_offset, source FROM refs
+             WHERE tape_id = ?1
+             ORDER BY event_offset, ref_key, source",
+        )?;
+        let rows = stmt.query_map(params![tape_id], |row| {
+            let source = row.get::<_, String>(2)?;
+            Ok(IssueRef {
+                key: row.get(0)?,
+                offset: row.get::<_, i64>(1)?.max(0) as u64,
+                source: IssueRefSource::parse(&source).unwrap_or(IssueRefSource::Message),
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Record (or clear) the tape `tape_id` was cut from.
+    pub fn record_tape_split(
+        &self,
+        tape_id: &str,
+        split: Option<&TapeSplit>,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM tape_splits WHERE tape_id = ?1",
+            params![tape_id],
+        )?;
+        if let Some(split) = split {
+            self.conn.execute(
+                "INSERT INTO tape_splits (
+                    tape_id, parent_tape_id, part, parts, first_offset, last_offset
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    tape_id,
+                    split.parent_tape_id,
+                    split.part as i64,
+                    split.parts as i64,
+                    split.first_offset as i64,
+                    split.last_offset as i64
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The tapes cut from `parent_tape_id`, in part order.
+    pub fn split_parts(&self, parent_tape_id: &str) -> rusqlite::Result<Vec<(String, TapeSplit)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tape_id, part, parts, first_offset, last_offset FROM tape_splits
+             WHERE parent_tape_id = ?1
+             ORDER BY part, tape_id",
+        )?;
+        let rows = stmt.query_map(params![parent_tape_id], |row| {
+            let count = |at: usize| row.get::<_, i64>(at).map(|value| value.max(0) as u64);
+            Ok((
+                row.get(0)?,
+                TapeSplit {
+                    parent_tape_id: parent_tape_id.to_string(),
+                    part: count(1)?,
+                    parts: count(2)?,
+                    first_offset: count(3)?,
+                    last_offset: count(4)?,
+                },
+            ))
+        })?;
+        rows.collect()
+    }
+
+    /// Replace the member tapes `tape_id` was joined from (empty for tapes
+    /// that are not joins).
+    pub fn record_tape_join(&self, tape_id: &str, members: &[String]) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM tape_joins WHERE tape_id = ?1",
+            params![tape_id],
+        )?;
+        for (position, member) in members.iter().enumerate() {
+            tx.execute(
+                "INSERT OR IGNORE INTO tape_joins (tape_id, member_tape_id, position)
+                 VALUES (?1, ?2, ?3)",
+                params![tape_id, member, position as i64],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// The joined tape that `member_tape_id` is part of, if any.
+    pub fn joined_into(&self, member_tape_id: &str) -> rusqlite::Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tape_id FROM tape_joins WHERE member_tape_id = ?1
+             ORDER BY tape_id LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![member_tape_id])?;
+        rows.next()?.map(|row| row.get(0)).transpose()
+    }
+
+    /// Tapes that mention `key`, compared case-insensitively.
+    pub fn tapes_with_issue(&self, key: &str) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT tape_id FROM refs
+             WHERE ref_key = ?1 COLLATE NOCASE
+             ORDER BY tape_id",
+        )?;
+        let rows = stmt.query_map(params![key], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Tapes with evidence in the `projects` entry `name`.
+    pub fn tapes_in_project(&self, name: &str) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT tape_id FROM evidence
+             WHERE project = ?1
+             ORDER BY tape_id",
+        )?;
+        let rows = stmt.query_map(params![name], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// The projects a tape has evidence in, sorted.
+    pub fn tape_projects(&self, tape_id: &str) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT project FROM evidence
+             WHERE tape_id = ?1 AND project IS NOT NULL
+             ORDER BY project",
+        )?;
+        let rows = stmt.query_map(params![tape_id], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// The project ingest tagged an evidence event with, if any.
+    pub fn evidence_project(
+        &self,
+        tape_id: &str,
+        event_offset: u64,
+    ) -> rusqlite::Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT project FROM evidence
+             WHERE tape_id = ?1 AND event_offset = ?2 AND project IS NOT NULL
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![tape_id, event_offset])?;
+        match rows.next()? {
+            Some(row) => row.get(0),
+            None => Ok(None),
+        }
+    }
+
+    /// Cataloged events matching every given filter, oldest first. `kinds`
+    /// matches any of its entries; `file_glob` uses SQLite `GLOB` syntax.
+    pub fn events_matching(
+        &self,
+        kinds: &[String],
+        tool: Option<&str>,
+        file_glob: Option<&str>,
+    ) -> rusqlite::Result<Vec<(String, EventRecord)>> {
+        let mut clauses = Vec::new();
+        let mut values: Vec<&dyn rusqlite::ToSql> = Vec::new();
+        if !kinds.is_empty() {
+            clauses.push(format!("kind IN ({})", vec!["?"; kinds.len()].join(", ")));
+            values.extend(kinds.iter().map(|kind| kind as &dyn rusqlite::ToSql));
+        }
+        if let Some(tool) = tool.as_ref() {
+            clauses.push("tool = ?".to_string());
+            values.push(tool);
+        }
+        if let Some(file_glob) = file_glob.as_ref() {
+            clauses.push("file_path GLOB ?".to_string());
+            values.push(file_glob);
+        }
+        let filter = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT tape_id, {EVENT_COLUMNS} FROM events
+             {filter}
+             ORDER BY timestamp, tape_id, event_offset"
+        ))?;
+        let rows = stmt.query_map(values.as_slice(), |row| {
+            Ok((row.get(0)?, decode_event_record(row, 1)?))
+        })?;
+        rows.collect()
+    }
+
+    /// Every cataloged `tool.call` / `tool.result` with the harness that
+    /// recorded its tape (see `engram tools`).
+    pub fn tool_events(&self) -> rusqlite::Result<Vec<(Option<String>, String, EventRecord)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT tapes.harness, events.tape_id, {EVENT_COLUMNS} FROM events
+             LEFT JOIN tapes ON tapes.tape_id = events.tape_id
+             WHERE kind IN ('tool.call', 'tool.result')
+             ORDER BY timestamp"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, decode_event_record(row, 2)?))
+        })?;
+        rows.collect()
+    }
+
+    pub fn record_tape_metrics(
+        &self,
+        tape_id: &str,
+        metrics: &TapeMetrics,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO tapes (tape_id) VALUES (?1)",
+            params![tape_id],
+        )?;
+        self.conn.execute(
+            "UPDATE tapes SET
+                event_count = ?2,
+                message_count = ?3,
+                tool_call_count = ?4,
+                tool_error_count = ?5,
+                edit_count = ?6,
+                revert_count = MAX(COALESCE(revert_count, 0), ?7),
+                started_at = ?8,
+                ended_at = ?9,
+                duration_secs = ?10,
+                model = ?11,
+                harness = ?12,
+                thrash_loop_count = ?13,
+                coverage_read = ?14,
+                coverage_edit = ?15,
+                coverage_tool = ?16,
+                session_id = ?17,
+                hostname = ?18,
+                user = ?19
+             WHERE tape_id = ?1",
+            params![
+                tape_id,
+                metrics.event_count as i64,
+                metrics.message_count as i64,
+                metrics.tool_call_count as i64,
+                metrics.tool_error_count as i64,
+                metrics.edit_count as i64,
+                metrics.revert_count as i64,
+                metrics.started_at,
+                metrics.ended_at,
+                metrics.duration_secs,
+                metrics.model,
+                metrics.harness,
+                metrics.thrash_loop_count as i64,
+                metrics.coverage_read,
+                metrics.coverage_edit,
+                metrics.coverage_tool,
+                metrics.session_id,
+                metrics.hostname,
+                metrics.user
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record which [`crate::anchor::anchor_version`] the tape's evidence was
+    /// computed with, and note it on the registry anchors it first named.
+    pub fn set_tape_anchor_version(&self, tape_id: &str, version: u32) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO tapes (tape_id) VALUES (?1)",
+            params![tape_id],
+        )?;
+        self.conn.execute(
+            "UPDATE tapes SET anchor_version = ?2 WHERE tape_id = ?1",
+            params![tape_id, version],
+        )?;
+        self.conn.execute(
+            "UPDATE anchors SET anchor_version = ?2
+             WHERE anchor_version IS NULL
+               AND anchor IN (SELECT anchor FROM evidence WHERE tape_id = ?1)",
+            params![tape_id, version],
+        )?;
+        Ok(())
+    }
+
+    /// Add a tape's evidence anchors to the registry: count its evidence
+    /// rows, move `first_seen` earlier when it predates the entry, and keep
+    /// the first preview recorded.
+    fn register_anchors_on(
+        conn: &Connection,
+        tape_id: &str,
+        previews: &HashMap<String, String>,
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            "INSERT INTO anchors (anchor, first_seen, occurrences)
+             SELECT anchor, MIN(timestamp), COUNT(*) FROM evidence
+             WHERE tape_id = ?1
+             GROUP BY anchor
+             ON CONFLICT (anchor) DO UPDATE SET
+                 first_seen = MIN(first_seen, excluded.first_seen),
+                 occurrences = occurrences + excluded.occurrences",
+            params![tape_id],
+        )?;
+        let mut stmt = conn.prepare_cached(
+            "UPDATE anchors SET preview = ?2 WHERE anchor = ?1 AND preview IS NULL",
+        )?;
+        for (anchor, preview) in previews {
+            stmt.execute(params![anchor, preview])?;
+        }
+        Ok(())
+    }
+
+    /// Registry entries for `anchors`, in the order given; unknown anchors
+    /// are skipped.
+    pub fn anchor_info(&self, anchors: &[String]) -> rusqlite::Result<Vec<AnchorInfo>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT anchor, first_seen, anchor_version, preview, occurrences, alias_of
+             FROM anchors WHERE anchor = ?1",
+        )?;
+        let mut out = Vec::new();
+        for anchor in anchors {
+            let mut rows = stmt.query(params![anchor])?;
+            if let Some(row) = rows.next()? {
+                out.push(AnchorInfo {
+                    anchor: row.get(0)?,
+                    first_seen: row.get(1)?,
+                    anchor_version: row.get(2)?,
+                    preview: row.get(3)?,
+                    occurrences: row.get::<_, i64>(4)?.max(0) as u64,
+                    alias_of: row.get(5)?,
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    /// Record that `anchor` is now fingerprinted as `alias_of`, for
+    /// fingerprint migrations. Returns whether `anchor` is in the registry.
+    pub fn record_anchor_alias(&self, anchor: &str, alias_of: &str) -> rusqlite::Result<bool> {
+        Self::validate_anchor(alias_of)?;
+        let updated = self.conn.execute(
+            "UPDATE anchors SET alias_of = ?2 WHERE anchor = ?1",
+            params![anchor, alias_of],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Copy anchor aliases from another index file (used by `reindex`, which
+    /// rebuilds the rest of the registry from the tapes). Returns the number
+    /// of aliases carried over.
+    pub fn import_anchor_aliases(&self, source_path: &str) -> rusqlite::Result<usize> {
+        self.conn
+            .execute("ATTACH DATABASE ?1 AS source", params![source_path])?;
+        let copied = (|| -> rusqlite::Result<usize> {
+            let has_table: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM source.sqlite_master
+                 WHERE type = 'table' AND name = 'anchors'",
+                [],
+                |row| row.get(0),
+            )?;
+            if !has_table {
+                return Ok(0);
+            }
+            self.conn.execute(
+                "UPDATE anchors SET alias_of = (
+                     SELECT alias_of FROM source.anchors AS old
+                     WHERE old.anchor = anchors.anchor
+                 )
+                 WHERE anchor IN (
+                     SELECT anchor FROM source.anchors WHERE alias_of IS NOT NULL
+                 )",
+                [],
+            )
+        })();
+        self.conn.execute_batch("DETACH DATABASE source;")?;
+        copied
+    }
+
+    /// Tapes per anchor version; tapes indexed before versions were
+    /// recorded count as [`ANCHOR_VERSION_RAW`].
+    pub fn anchor_version_counts(&self) -> rusqlite::Result<BTreeMap<u32, u64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(anchor_version, ?1), COUNT(*) FROM tapes GROUP BY 1 ORDER BY 1",
+        )?;
+        let rows = stmt.query_map(params![ANCHOR_VERSION_RAW], |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)?.max(0) as u64))
+        })?;
+        rows.collect()
+    }
+
+    pub fn tape_metrics(&self, tape_id: &str) -> rusqlite::Result<Option<TapeMetrics>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TAPE_METRICS_COLUMNS} FROM tapes WHERE tape_id = ?1 AND event_count IS NOT NULL"
+        ))?;
+        let mut rows = stmt.query(params![tape_id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(decode_tape_metrics(row, 0)?));
+        }
+        Ok(None)
+    }
+
+    pub fn all_tape_metrics(&self) -> rusqlite::Result<Vec<(String, TapeMetrics)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT tape_id, {TAPE_METRICS_COLUMNS}
+             FROM tapes
+             WHERE event_count IS NOT NULL
+             ORDER BY tape_id ASC"
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push((row.get(0)?, decode_tape_metrics(row, 1)?));
+        }
+        Ok(out)
+    }
+
+    pub fn ingest_tape_events(
+        &self,
+        tape_id: &str,
+        events: &[TapeEventAt],
+        link_threshold: f32,
+    ) -> rusqlite::Result<()> {
+        self.ingest_tape_events_with_dispatch(tape_id, events, &[], link_threshold)
+    }
+
+    pub fn ingest_tape_events_with_dispatch(
+        &self,
+        tape_id: &str,
+        events: &[TapeEventAt],
+        dispatch_links: &[DispatchLink],
+        link_threshold: f32,
+    ) -> rusqlite::Result<()> {
+        self.ingest_tape_events_limited(
+            tape_id,
+            events,
+            dispatch_links,
+            link_threshold,
+            IngestLimits::default(),
+        )
+        .map(|_| ())
+    }
+
+    /// Index a tape like `ingest_tape_events_with_dispatch`, stopping at
+    /// `limits`: events past `max_events` are skipped, and evidence rows and
+    /// edges past their caps are not written. The diagnostics say what was
+    /// dropped.
+    pub fn ingest_tape_events_limited(
+        &self,
+        tape_id: &str,
+        events: &[TapeEventAt],
+        dispatch_links: &[DispatchLink],
+        link_threshold: f32,
+        limits: IngestLimits,
+    ) -> rusqlite::Result<IngestDiagnostics> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut budget = IngestDiagnostics {
+            events_total: events.len(),
+            ..IngestDiagnostics::default()
+        };
+        // Meta and origin come from the whole tape even when its tail is cut.
+        let all_events = events;
+        let events = &events[..events.len().min(limits.max_events.unwrap_or(usize::MAX))];
+        budget.events_indexed = events.len();
+        let mut reverted_edits = 0i64;
+        let project_root = all_events
+            .iter()
+            .find_map(|item| match &item.event.data {
+                TapeEventData::Meta(meta) => meta.cwd.as_deref(),
+                _ => None,
+            })
+            .map(Path::new);
+        let mut raw_paths: Vec<(u64, String)> = Vec::new();
+        // First text each anchor was seen in, for the registry's preview.
+        let mut previews = HashMap::<String, String>::new();
+        let mut note_preview = |text: Option<&str>, anchors: &[String]| {
+            let Some(preview) = text.and_then(anchor_preview) else {
+                return;
+            };
+            for anchor in anchors {
+                previews
+                    .entry(anchor.clone())
+                    .or_insert_with(|| preview.clone());
+            }
+        };
+        let mut relativize = |offset: u64, raw: &str| {
+            let file = repo_relative_path(raw, project_root);
+            if file != raw {
+                raw_paths.push((offset, raw.to_string()));
+            }
+            file
+        };
+        for item in events {
+            match &item.event.data {
+                TapeEventData::CodeRead(read) => {
+                    let fragment = EvidenceFragmentRef {
+                        tape_id: tape_id.to_string(),
+                        event_offset: item.offset,
+                        kind: EvidenceKind::Read,
+                        file_path: relativize(item.offset, &read.file),
+                        timestamp: item.event.timestamp.clone(),
+                    };
+                    let anchors = read_evidence_anchors(read);
+                    note_preview(read.text.as_deref(), &anchors);
+                    for anchor in anchors {
+                        if budget.take_evidence_row(limits) {
+                            Self::insert_evidence_on(
+                                tx.deref(),
+                                &anchor,
+                                &fragment,
+                                read.project.as_deref(),
+                            )?;
+                        }
+                    }
+                }
+                TapeEventData::CodeEdit(edit) => {
+                    let file_path = relativize(item.offset, &edit.file);
+                    let normalizer = edit.normalizer.as_deref().and_then(normalizer_for_tag);
+                    // Individual tokens for evidence rows (one DB row per hash).
+                    let before_tokens = edit_side_tokens(
+                        edit.before_text.as_deref(),
+                        edit.before_hash.as_deref(),
+                        &edit.before_anchor_hashes,
+                        normalizer,
+                    );
+                    let after_tokens = edit_side_tokens(
+                        edit.after_text.as_deref(),
+                        edit.after_hash.as_deref(),
+                        &edit.after_anchor_hashes,
+                        normalizer,
+                    );
+                    // The newer text wins for tokens both sides share.
+                    note_preview(edit.after_text.as_deref(), &after_tokens);
+                    note_preview(edit.before_text.as_deref(), &before_tokens);
+                    // Window-level anchors for edges (avoids N×M explosion).
+                    let before_edge = edit_side_edge_anchors(
+                        edit.before_text.as_deref(),
+                        edit.before_hash.as_deref(),
+                        &edit.before_anchor_hashes,
+                        normalizer,
+                    );
+                    let after_edge = edit_side_edge_anchors(
+                        edit.after_text.as_deref(),
+                        edit.after_hash.as_deref(),
+                        &edit.after_anchor_hashes,
+                        normalizer,
+                    );
+
+                    if !before_tokens.is_empty() {
+                        let fragment = EvidenceFragmentRef {
+                            tape_id: tape_id.to_string(),
+                            event_offset: item.offset,
+                            kind: EvidenceKind::Edit,
+                            file_path: file_path.clone(),
+                            timestamp: item.event.timestamp.clone(),
+                        };
+                        for anchor in &before_tokens {
+                            if budget.take_evidence_row(limits) {
+                                Self::insert_evidence_on(
+                                    tx.deref(),
+                                    anchor,
+                                    &fragment,
+                                    edit.project.as_deref(),
+                                )?;
+                            }
+                        }
+                    }
+
+                    if !after_tokens.is_empty() {
+                        let fragment = EvidenceFragmentRef {
+                            tape_id: tape_id.to_string(),
+                            event_offset: item.offset,
+                            kind: EvidenceKind::Edit,
+                            file_path: file_path.clone(),
+                            timestamp: item.event.timestamp.clone(),
+                        };
+                        for anchor in &after_tokens {
+                            if budget.take_evidence_row(limits) {
+                                Self::insert_evidence_on(
+                                    tx.deref(),
+                                    anchor,
+                                    &fragment,
+                                    edit.project.as_deref(),
+                                )?;
+                            }
+                        }
+                    }
+
+                    if !before_edge.is_empty() && !after_edge.is_empty() {
+                        let (confidence, scorer) = if before_edge == after_edge {
+                            (1.0, None)
+                        } else if let Some(similarity) = edit.similarity {
+                            (similarity, Some(HARNESS_SCORER.to_string()))
+                        } else if let (Some(before), Some(after)) =
+                            (edit.before_text.as_deref(), edit.after_text.as_deref())
+                        {
+                            let score = match normalizer {
+                                Some(normalizer) => self.scorer.score(
+                                    &normalizer.normalize(before),
+                                    &normalizer.normalize(after),
+                                ),
+                                None => self.scorer.score(before, after),
+                            };
+                            (score, Some(self.scorer.tag()))
+                        } else {
+                            (0.0, None)
+                        };
+                        Self::validate_confidence(confidence)?;
+                        let reverted = before_edge != after_edge
+                            && Self::restores_earlier_edit_on(
+                                tx.deref(),
+                                &before_edge,
+                                &after_edge,
+                            )?;
+                        if reverted {
+                            reverted_edits += 1;
+                        }
+                        for before_anchor in &before_edge {
+                            for after_anchor in &after_edge {
+                                if !budget.take_edge(limits) {
+                                    continue;
+                                }
+                                Self::insert_edge_on(
+                                    tx.deref(),
+                                    &SpanEdge {
+                                        from_anchor: before_anchor.clone(),
+                                        to_anchor: after_anchor.clone(),
+                                        confidence,
+                                        location_delta: LocationDelta::Same,
+                                        cardinality: Cardinality::OneToOne,
+                                        agent_link: false,
+                                        note: None,
+                                    },
+                                    link_threshold,
+                                    scorer.as_deref(),
+                                )?;
+                                if reverted {
+                                    Self::mark_edge_reverted_on(
+                                        tx.deref(),
+                                        before_anchor,
+                                        after_anchor,
+                                    )?;
+                                }
+                            }
+                        }
+                    }
+
+                    if after_tokens.is_empty() && !before_tokens.is_empty() {
+                        let range = edit
+                            .before_range
+                            .or(edit.after_range)
+                            .map(|r| FileRange {
+                                start: r.start,
+                                end: r.end,
+                            })
+                            .unwrap_or(FileRange { start: 0, end: 0 });
+                        Self::insert_tombstone_on(
+                            tx.deref(),
+                            &Tombstone {
+                                anchor_hashes: before_tokens,
+                                tape_id: tape_id.to_string(),
+                                event_offset: item.offset,
+                                file_path: file_path.clone(),
+                                range_at_deletion: range,
+                                timestamp: item.event.timestamp.clone(),
+                            },
+                        )?;
+                    }
+                }
+                TapeEventData::SpanLink(link) => {
+                    let from_anchor = encode_span_link_anchor(
+                        &repo_relative_path(&link.from_file, project_root),
+                        link.from_range,
+                    );
+                    let to_anchor = encode_span_link_anchor(
+                        &repo_relative_path(&link.to_file, project_root),
+                        link.to_range,
+                    );
+                    if !budget.take_edge(limits) {
+                        continue;
+                    }
+                    Self::insert_edge_on(
+                        tx.deref(),
+                        &SpanEdge {
+                            from_anchor,
+                            to_anchor,
+                            confidence: 1.0,
+                            location_delta: LocationDelta::Moved,
+                            cardinality: Cardinality::OneToOne,
+                            agent_link: true,
+                            note: link.note.clone(),
+                        },
+                        link_threshold,
+                        None,
+                    )?;
+                }
+                TapeEventData::Meta(_) | TapeEventData::Other { .. } => {}
+            }
+        }
+
+        for link in dispatch_links {
+            Self::insert_dispatch_link_on(tx.deref(), tape_id, link)?;
+        }
+
+        // Keep what the harness originally recorded next to the repo-relative path.
+        for (offset, raw) in raw_paths {
+            for table in ["evidence", "tombstones"] {
+                tx.execute(
+                    &format!(
+                        "UPDATE {table} SET raw_file_path = ?3
+                         WHERE tape_id = ?1 AND event_offset = ?2"
+                    ),
+                    params![tape_id, offset, raw],
+                )?;
+            }
+        }
+
+        Self::register_anchors_on(tx.deref(), tape_id, &previews)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO tapes (tape_id) VALUES (?1)",
+            params![tape_id],
+        )?;
+        if let Some(origin) = tape_origin(all_events) {
+            tx.execute(
+                "UPDATE tapes SET source_path = ?2, source_start = ?3, source_end = ?4,
+                    source_sha256 = ?5, adapter = ?6, adapter_version = ?7
+                 WHERE tape_id = ?1",
+                params![
+                    tape_id,
+                    origin.path,
+                    origin.byte_range[0] as i64,
+                    origin.byte_range[1] as i64,
+                    origin.sha256,
+                    origin.adapter,
+                    origin.adapter_version
+                ],
+            )?;
+        }
+        if reverted_edits > 0 {
+            tx.execute(
+                "UPDATE tapes SET revert_count = MAX(COALESCE(revert_count, 0), ?2)
+                 WHERE tape_id = ?1",
+                params![tape_id, reverted_edits],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(budget)
+    }
+
+    /// Record when ingest converted `tape_id`; the first conversion wins.
+    pub fn set_tape_converted_at(&self, tape_id: &str, converted_at: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE tapes SET converted_at = COALESCE(converted_at, ?2) WHERE tape_id = ?1",
+            params![tape_id, converted_at],
+        )?;
+        Ok(())
+    }
+
+    /// Raw source of an ingested tape, or `None` for tapes that were not
+    /// converted from a harness file (or are not indexed).
+    pub fn tape_origin(&self, tape_id: &str) -> rusqlite::Result<Option<TapeOrigin>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TAPE_ORIGIN_COLUMNS} FROM tapes
+             WHERE tape_id = ?1 AND source_path IS NOT NULL"
+        ))?;
+        let mut rows = stmt.query(params![tape_id])?;
+        match rows.next()? {
+            Some(row) => decode_tape_origin(row, 0).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Every tape converted from a raw harness file, with its origin.
+    pub fn converted_tapes(&self) -> rusqlite::Result<Vec<(String, TapeOrigin)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT tape_id, {TAPE_ORIGIN_COLUMNS} FROM tapes
+             WHERE source_path IS NOT NULL
+             ORDER BY tape_id"
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push((row.get(0)?, decode_tape_origin(row, 1)?));
+        }
+        Ok(out)
+    }
+
+    /// Copy conversion timestamps from the index at `source_path` (a reindex
+    /// rebuilds everything else in `tapes` from the tapes themselves).
+    pub fn import_tape_origins(&self, source_path: &str) -> rusqlite::Result<usize> {
+        self.conn
+            .execute("ATTACH DATABASE ?1 AS source", params![source_path])?;
+        let copied = (|| -> rusqlite::Result<usize> {
+            let has_column: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('tapes', 'source')
+                 WHERE name = 'converted_at'",
+                [],
+                |row| row.get(0),
+            )?;
+            if !has_column {
+                return Ok(0);
+            }
+            self.conn.execute(
+                "UPDATE tapes SET converted_at = (
+                    SELECT s.converted_at FROM source.tapes s WHERE s.tape_id = tapes.tape_id
+                 )
+                 WHERE converted_at IS NULL AND EXISTS (
+                    SELECT 1 FROM source.tapes s
+                    WHERE s.tape_id = tapes.tape_id AND s.converted_at IS NOT NULL
+                 )",
+                [],
+            )
+        })();
+        self.conn.execute_batch("DETACH DATABASE source;")?;
+        copied
+    }
+
+    /// Every dispatch link, by marker.
+    pub fn all_dispatch_links(&self) -> rusqlite::Result<Vec<DispatchLinkRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tape_id, uuid, first_turn_index, direction
+             FROM dispatch_links
+             ORDER BY uuid ASC, tape_id ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(DispatchLinkRow {
+                tape_id: row.get(0)?,
+                uuid: row.get(1)?,
+                first_turn_index: row.get(2)?,
+                direction: decode_dispatch_direction(&row.get::<_, String>(3)?),
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn dispatch_links_for_tape(&self, tape_id: &str) -> rusqlite::Result<Vec<DispatchLink>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT uuid, first_turn_index, direction
+             FROM dispatch_links
+             WHERE tape_id = ?1
+             ORDER BY first_turn_index ASC, uuid ASC",
+        )?;
+        let mut rows = stmt.query(params![tape_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(DispatchLink {
+                uuid: row.get(0)?,
+                first_turn_index: row.get(1)?,
+                direction: decode_dispatch_direction(&row.get::<_, String>(2)?),
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn dispatch_links_for_uuid(&self, uuid: &str) -> rusqlite::Result<Vec<DispatchLinkRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tape_id, uuid, first_turn_index, direction
+             FROM dispatch_links
+             WHERE uuid = ?1
+             ORDER BY first_turn_index ASC, tape_id ASC",
+        )?;
+        let mut rows = stmt.query(params![uuid])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(DispatchLinkRow {
+                tape_id: row.get(0)?,
+                uuid: row.get(1)?,
+                first_turn_index: row.get(2)?,
+                direction: decode_dispatch_direction(&row.get::<_, String>(3)?),
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn latest_received_dispatch_before_turn(
+        &self,
+        tape_id: &str,
+        turn_index: i64,
+    ) -> rusqlite::Result<Option<DispatchLink>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT uuid, first_turn_index, direction
+             FROM dispatch_links
+             WHERE tape_id = ?1
+               AND direction = 'received'
+               AND first_turn_index < ?2
+             ORDER BY first_turn_index DESC, uuid ASC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![tape_id, turn_index])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(DispatchLink {
+                uuid: row.get(0)?,
+                first_turn_index: row.get(1)?,
+                direction: decode_dispatch_direction(&row.get::<_, String>(2)?),
+            }));
+        }
+        Ok(None)
+    }
+
+    pub fn sent_dispatch_for_uuid(&self, uuid: &str) -> rusqlite::Result<Option<DispatchLinkRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tape_id, uuid, first_turn_index, direction
+             FROM dispatch_links
+             WHERE uuid = ?1
+               AND direction = 'sent'
+             ORDER BY first_turn_index DESC, tape_id ASC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![uuid])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(DispatchLinkRow {
+                tape_id: row.get(0)?,
+                uuid: row.get(1)?,
+                first_turn_index: row.get(2)?,
+                direction: decode_dispatch_direction(&row.get::<_, String>(3)?),
+            }));
+        }
+        Ok(None)
+    }
+}
+
+const EVENT_COLUMNS: &str =
+    "event_offset, kind, tool, file_path, timestamp, exit_code, output_bytes";
+
+fn decode_event_record(row: &rusqlite::Row<'_>, first: usize) -> rusqlite::Result<EventRecord> {
+    Ok(EventRecord {
+        offset: row.get::<_, i64>(first)?.max(0) as u64,
+        kind: row.get(first + 1)?,
+        tool: row.get(first + 2)?,
+        file: row.get(first + 3)?,
+        timestamp: row.get(first + 4)?,
+        exit: row.get(first + 5)?,
+        output_bytes: row
+            .get::<_, Option<i64>>(first + 6)?
+            .map(|bytes| bytes.max(0) as u64),
+    })
+}
+
+const TAPE_METRICS_COLUMNS: &str = "event_count, message_count, tool_call_count, \
+     tool_error_count, edit_count, revert_count, started_at, ended_at, duration_secs, model, harness, \
+     thrash_loop_count, coverage_read, coverage_edit, coverage_tool, session_id, hostname, user";
+
+fn decode_tape_metrics(row: &rusqlite::Row<'_>, first: usize) -> rusqlite::Result<TapeMetrics> {
+    let count = |idx: usize| -> rusqlite::Result<u64> {
+        Ok(row.get::<_, Option<i64>>(first + idx)?.unwrap_or(0).max(0) as u64)
+    };
+    Ok(TapeMetrics {
+        event_count: count(0)?,
+        message_count: count(1)?,
+        tool_call_count: count(2)?,
+        tool_error_count: count(3)?,
+        edit_count: count(4)?,
+        revert_count: count(5)?,
+        started_at: row.get(first + 6)?,
+        ended_at: row.get(first + 7)?,
+        duration_secs: row.get(first + 8)?,
+        model: row.get(first + 9)?,
+        harness: row.get(first + 10)?,
+        thrash_loop_count: count(11)?,
+        coverage_read: row.get(first + 12)?,
+        coverage_edit: row.get(first + 13)?,
+        coverage_tool: row.get(first + 14)?,
+        session_id: row.get(first + 15)?,
+        hostname: row.get(first + 16)?,
+        user: row.get(first + 17)?,
+    })
+}
+
+const TAPE_ORIGIN_COLUMNS: &str =
+    "source_path, source_start, source_end, source_sha256, adapter, adapter_version, converted_at";
+
+fn decode_tape_origin(row: &rusqlite::Row<'_>, first: usize) -> rusqlite::Result<TapeOrigin> {
+    Ok(TapeOrigin {
+        path: row.get(first)?,
+        byte_range: [
+            row.get::<_, i64>(first + 1)? as u64,
+            row.get::<_, i64>(first + 2)? as u64,
+        ],
+        sha256: row.get(first + 3)?,
+        adapter: row.get(first + 4)?,
+        adapter_version: row.get(first + 5)?,
+        converted_at: row.get(first + 6)?,
+    })
+}
+
+fn encode_span_link_anchor(file: &str, range: crate::tape::event::FileRange) -> String {
+    format!("span:{file}:{}-{}", range.start, range.end)
+}
+
+/// Anchors used to insert evidence rows for a code-read event.
+/// Returns individual winnow hash tokens so each can be indexed by equality.
+fn read_evidence_anchors(read: &crate::tape::event::CodeReadEvent) -> Vec<String> {
+    if let Some(text) = read.text.as_deref() {
+        let normalizer = read.normalizer.as_deref().and_then(normalizer_for_tag);
+        return normalized_token_hashes(text, normalizer);
+    }
+    expand_legacy_anchors(None, &read.anchor_hashes)
+}
+
+/// Anchors used to insert evidence rows for one side of a code-edit event.
+/// Returns individual winnow hash tokens, tagged when the event names a
+/// span normalizer.
+fn edit_side_tokens(
+    text: Option<&str>,
+    hash: Option<&str>,
+    anchors: &[String],
+    normalizer: Option<&SpanNormalizer>,
+) -> Vec<String> {
+    if let Some(text) = text {
+        return normalized_token_hashes(text, normalizer);
+    }
+    expand_legacy_anchors(hash, anchors)
+}
+
+/// Anchors used to insert edge rows for one side of a code-edit event.
+///
+/// * When `text` is available, returns window-level (comma-separated)
+///   fingerprints — one per 24-line window — to prevent N×M edge explosion.
+/// * For legacy tape events with pre-computed `anchor_hashes`, expands any
+///   comma-separated winnow entries into individual tokens so that edge
+///   targets remain queryable by exact equality.
+fn edit_side_edge_anchors(
+    text: Option<&str>,
+    hash: Option<&str>,
+    anchors: &[String],
+    normalizer: Option<&SpanNormalizer>,
+) -> Vec<String> {
+    if let Some(text) = text {
+        return normalized_anchor_hashes(text, normalizer);
+    }
+    expand_legacy_anchors(hash, anchors)
+}
+
+/// Expand legacy anchor_hashes into individual winnow tokens.
+/// A comma-separated entry like "winnow:aaa,bbb" becomes ["winnow:aaa","winnow:bbb"].
+fn expand_legacy_anchors(hash: Option<&str>, anchors: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+
+    for anchor in anchors {
+        for token in expand_winnow_anchor(anchor) {
+            if seen.insert(token.clone()) {
+                out.push(token);
+            }
+        }
+        // Non-winnow anchors (e.g. span: links) are kept as-is.
+        if !anchor.starts_with("winnow:") && seen.insert(anchor.clone()) {
+            out.push(anchor.clone());
+        }
+    }
+
+    if out.is_empty()
+        && let Some(anchor) = hash.filter(|h| h.starts_with("winnow:"))
+    {
+        for token in expand_winnow_anchor(anchor) {
+            if seen.insert(token.clone()) {
+                out.push(token);
+            }
+        }
+    }
+
+    out
+}
+
+impl SqliteIndex {
+    fn validate_anchor(anchor: &str) -> rusqlite::Result<()> {
+        if anchor.is_empty() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "anchor_hash must not be empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_confidence(confidence: f32) -> rusqlite::Result<()> {
+        if !(0.0..=1.0).contains(&confidence) {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "confidence must be in [0.0, 1.0]".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn encode_evidence_kind(kind: EvidenceKind) -> &'static str {
+    match kind {
+        EvidenceKind::Edit => "edit",
+        EvidenceKind::Read => "read",
+        EvidenceKind::Tool => "tool",
+        EvidenceKind::Message => "message",
+    }
+}
+
+fn decode_evidence_kind(raw: &str) -> EvidenceKind {
+    match raw {
+        "edit" => EvidenceKind::Edit,
+        "read" => EvidenceKind::Read,
+        "tool" => EvidenceKind::Tool,
+        "message" => EvidenceKind::Message,
+        _ => EvidenceKind::Read,
+    }
+}
+
+fn encode_location_delta(delta: LocationDelta) -> &'static str {
+    match delta {
+        LocationDelta::Same => "same",
+        LocationDelta::Adjacent => "adjacent",
+        LocationDelta::Moved => "moved",
+        LocationDelta::Absent => "absent",
+    }
+}
+
+/// An [`EdgeRow`] from the first nine columns of `row`: `from_anchor,
+/// to_anchor, confidence, location_delta, cardinality, agent_link, note,
+/// reverted, scorer`, classified against `link_threshold`.
+fn decode_edge_row(row: &rusqlite::Row<'_>, link_threshold: f32) -> rusqlite::Result<EdgeRow> {
+    let confidence: f32 = row.get(2)?;
+    let agent_link = row.get::<_, i64>(5)? != 0;
+    let note: String = row.get(6)?;
+    Ok(EdgeRow {
+        from_anchor: row.get(0)?,
+        to_anchor: row.get(1)?,
+        confidence,
+        location_delta: decode_location_delta(&row.get::<_, String>(3)?),
+        cardinality: decode_cardinality(&row.get::<_, String>(4)?),
+        agent_link,
+        note: if note.is_empty() { None } else { Some(note) },
+        stored_class: derive_stored_class(agent_link, confidence, link_threshold),
+        reverted: row.get::<_, i64>(7)? != 0,
+        scorer: row.get(8)?,
+    })
+}
+
+fn decode_location_delta(raw: &str) -> LocationDelta {
+    match raw {
+        "same" => LocationDelta::Same,
+        "adjacent" => LocationDelta::Adjacent,
+        "moved" => LocationDelta::Moved,
+        "absent" => LocationDelta::Absent,
+        _ => LocationDelta::Absent,
+    }
+}
+
+fn encode_cardinality(cardinality: Cardinality) -> &'static str {
+    match cardinality {
+        Cardinality::OneToOne => "1:1",
+        Cardinality::OneToMany => "1:N",
+        Cardinality::ManyToOne => "N:1",
+    }
+}
+
+fn decode_cardinality(raw: &str) -> Cardinality {
+    match raw {
+        "1:1" => Cardinality::OneToOne,
+        "1:N" => Cardinality::OneToMany,
+        "N:1" => Cardinality::ManyToOne,
+        _ => Cardinality::OneToOne,
+    }
+}
+
+fn encode_dispatch_direction(direction: DispatchDirection) -> &'static str {
+    match direction {
+        DispatchDirection::Received => "received",
+        DispatchDirection::Sent => "sent",
+    }
+}
+
+fn decode_dispatch_direction(raw: &str) -> DispatchDirection {
+    match raw {
+        "sent" => DispatchDirection::Sent,
+        _ => DispatchDirection::Received,
+    }
+}
+
+fn derive_stored_class(agent_link: bool, confidence: f32, link_threshold: f32) -> StoredEdgeClass {
+    if !agent_link && confidence < link_threshold {
+        StoredEdgeClass::LocationOnly
+    } else {
+        StoredEdgeClass::Lineage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anchor::fingerprint_token_hashes;
+    use crate::index::lineage::LINK_THRESHOLD_DEFAULT;
+    use crate::tape::event::{
+        CodeEditEvent, CodeReadEvent, FileRange, MetaEvent, TapeEvent, TapeEventData,
+    };
+
+    fn read_event(anchor: &str, file: &str, offset: u64) -> TapeEventAt {
+        TapeEventAt {
+            offset,
+            event: TapeEvent {
+                timestamp: "2026-02-22T00:00:00Z".to_string(),
+                data: TapeEventData::CodeRead(CodeReadEvent {
+                    file: file.to_string(),
+                    range: FileRange { start: 1, end: 1 },
+                    text: None,
+                    anchor_hashes: vec![anchor.to_string()],
+                    normalizer: None,
+                    project: None,
+                }),
+            },
+        }
+    }
+
+    fn edit_event(
+        before_hash: Option<&str>,
+        after_hash: Option<&str>,
+        file: &str,
+        offset: u64,
+    ) -> TapeEventAt {
+        edit_event_with_similarity(before_hash, after_hash, Some(0.80), file, offset)
+    }
+
+    fn edit_event_with_similarity(
+        before_hash: Option<&str>,
+        after_hash: Option<&str>,
+        similarity: Option<f32>,
+        file: &str,
+        offset: u64,
+    ) -> TapeEventAt {
+        TapeEventAt {
+            offset,
+            event: TapeEvent {
+                timestamp: "2026-02-22T00:00:01Z".to_string(),
+                data: TapeEventData::CodeEdit(CodeEditEvent {
+                    file: file.to_string(),
+                    before_range: Some(FileRange { start: 10, end: 12 }),
+                    after_range: Some(FileRange { start: 10, end: 13 }),
+                    before_text: None,
+                    after_text: None,
+                    before_hash: before_hash.map(ToOwned::to_owned),
+                    after_hash: after_hash.map(ToOwned::to_owned),
+                    before_anchor_hashes: before_hash
+                        .map(|anchor| vec![anchor.to_string()])
+                        .unwrap_or_default(),
+                    after_anchor_hashes: after_hash
+                        .map(|anchor| vec![anchor.to_string()])
+                        .unwrap_or_default(),
+                    similarity,
+                    normalizer: None,
+                    project: None,
+                }),
+            },
+        }
+    }
+
+    #[test]
+    fn limited_ingest_caps_events_evidence_and_edges() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let events = vec![
+            edit_event(Some("a1"), Some("a2"), "src/lib.rs", 0),
+            edit_event(Some("b1"), Some("b2"), "src/lib.rs", 1),
+            read_event("late-read", "src/lib.rs", 2),
+        ];
+        let limits = IngestLimits {
+            max_events: Some(2),
+            max_evidence_rows: Some(3),
+            max_edges: Some(1),
+        };
+
+        let diagnostics = index
+            .ingest_tape_events_limited("tape-1", &events, &[], LINK_THRESHOLD_DEFAULT, limits)
+            .expect("ingest succeeds");
+        assert_eq!(
+            diagnostics,
+            IngestDiagnostics {
+                events_total: 3,
+                events_indexed: 2,
+                evidence_rows: 3,
+                evidence_rows_dropped: 1,
+                edges: 1,
+                edges_dropped: 1,
+            }
+        );
+        assert!(diagnostics.is_partial());
+        assert_eq!(index.evidence_for_anchor("b1").expect("b1").len(), 1);
+        assert!(index.evidence_for_anchor("b2").expect("b2").is_empty());
+        assert!(
+            index
+                .evidence_for_anchor("late-read")
+                .expect("read")
+                .is_empty()
+        );
+        assert!(
+            index
+                .outbound_edges("b1", 0.5, true)
+                .expect("edges")
+                .is_empty()
+        );
+
+        let unlimited = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let diagnostics = unlimited
+            .ingest_tape_events_limited(
+                "tape-1",
+                &events,
+                &[],
+                LINK_THRESHOLD_DEFAULT,
+                IngestLimits::default(),
+            )
+            .expect("ingest succeeds");
+        assert!(!diagnostics.is_partial());
+        assert_eq!(diagnostics.evidence_rows, 5);
+    }
+
+    #[test]
+    fn ingests_reads_edits_edges_and_tombstones() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let events = vec![
+            read_event("read-anchor", "src/lib.rs", 0),
+            edit_event(Some("before"), Some("after"), "src/lib.rs", 1),
+            edit_event(Some("deleted"), None, "src/lib.rs", 2),
+        ];
+
+        index
+            .ingest_tape_events("tape-1", &events, LINK_THRESHOLD_DEFAULT)
+            .expect("ingest succeeds");
+
+        let read_refs = index
+            .evidence_for_anchor("read-anchor")
+            .expect("read evidence query");
+        assert_eq!(read_refs.len(), 1);
+        assert_eq!(read_refs[0].kind, EvidenceKind::Read);
+
+        let edit_refs = index
+            .evidence_for_anchor("after")
+            .expect("edit evidence query");
+        assert_eq!(edit_refs.len(), 1);
+        assert_eq!(edit_refs[0].kind, EvidenceKind::Edit);
+        let before_refs = index
+            .evidence_for_anchor("before")
+            .expect("before evidence query");
+        assert_eq!(before_refs.len(), 1);
+        assert_eq!(before_refs[0].kind, EvidenceKind::Edit);
+
+        let edges = index
+            .outbound_edges("before", 0.50, false)
+            .expect("edge query");
+        assert_eq!(edges.len(), 1);
+
+        let edges_forensics = index
+            .outbound_edges("before", 0.50, true)
+            .expect("edge query with forensics");
+        assert_eq!(edges_forensics.len(), 1);
+
+        let tombstones = index
+            .tombstones_for_anchor("deleted")
+            .expect("tombstone query");
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].file_path, "src/lib.rs");
+    }
+
+    #[test]
+    fn ingests_windowed_edit_text_as_direct_evidence() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let before_text = (1..=72)
+            .map(|line| format!("fn before_{line}() {{ value_{line}(); }}\n"))
+            .collect::<String>();
+        let after_text = (1..=72)
+            .map(|line| format!("fn after_{line}() {{ value_{line}(); }}\n"))
+            .collect::<String>();
+        // Individual tokens are stored in evidence; window-level anchors are
+        // only used for edges.
+        let before_tokens = fingerprint_token_hashes(&before_text);
+        let after_tokens = fingerprint_token_hashes(&after_text);
+        let events = vec![TapeEventAt {
+            offset: 1,
+            event: TapeEvent {
+                timestamp: "2026-02-22T00:00:01Z".to_string(),
+                data: TapeEventData::CodeEdit(CodeEditEvent {
+                    file: "src/lib.rs".to_string(),
+                    before_range: Some(FileRange { start: 10, end: 12 }),
+                    after_range: Some(FileRange { start: 10, end: 13 }),
+                    before_text: Some(before_text),
+                    after_text: Some(after_text),
+                    before_hash: None,
+                    after_hash: None,
+                    before_anchor_hashes: Vec::new(),
+                    after_anchor_hashes: Vec::new(),
+                    similarity: Some(0.80),
+                    normalizer: None,
+                    project: None,
+                }),
+            },
+        }];
+
+        index
+            .ingest_tape_events("tape-1", &events, LINK_THRESHOLD_DEFAULT)
+            .expect("ingest succeeds");
+
+        assert!(before_tokens.len() >= 3, "tokens={before_tokens:?}");
+        assert!(after_tokens.len() >= 3, "tokens={after_tokens:?}");
+
+        let before_refs = index
+            .evidence_for_anchor(&before_tokens[0])
+            .expect("before winnow token evidence");
+        assert_eq!(before_refs.len(), 1);
+        assert_eq!(before_refs[0].kind, EvidenceKind::Edit);
+
+        let after_refs = index
+            .evidence_for_anchor(&after_tokens[0])
+            .expect("after winnow token evidence");
+        assert_eq!(after_refs.len(), 1);
+        assert_eq!(after_refs[0].kind, EvidenceKind::Edit);
+    }
+
+    #[test]
+    fn span_link_is_agent_edge_and_survives_min_confidence() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let events = vec![TapeEventAt {
+            offset: 5,
+            event: TapeEvent {
+                timestamp: "2026-02-22T00:00:03Z".to_string(),
+                data: TapeEventData::SpanLink(crate::tape::event::SpanLinkEvent {
+                    from_file: "src/a.rs".to_string(),
+                    from_range: FileRange { start: 1, end: 2 },
+                    to_file: "src/b.rs".to_string(),
+                    to_range: FileRange { start: 10, end: 20 },
+                    note: Some("extract".to_string()),
+                }),
+            },
+        }];
+
+        index
+            .ingest_tape_events("tape-2", &events, LINK_THRESHOLD_DEFAULT)
+            .expect("ingest succeeds");
+
+        let from = "span:src/a.rs:1-2";
+        let edges = index
+            .outbound_edges(from, 0.99, false)
+            .expect("edge query for span link");
+        assert_eq!(edges.len(), 1);
+        assert!(edges[0].agent_link);
+        assert_eq!(edges[0].note.as_deref(), Some("extract"));
+    }
+
+    #[test]
+    fn ingest_is_idempotent_for_same_tape_events() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let events = vec![
+            read_event("read-anchor", "src/lib.rs", 0),
+            edit_event(Some("before"), Some("after"), "src/lib.rs", 1),
+        ];
+
+        index
+            .ingest_tape_events("tape-1", &events, LINK_THRESHOLD_DEFAULT)
+            .expect("first ingest");
+        index
+            .ingest_tape_events("tape-1", &events, LINK_THRESHOLD_DEFAULT)
+            .expect("second ingest");
+
+        assert_eq!(
+            index
+                .evidence_for_anchor("read-anchor")
+                .expect("read evidence")
+                .len(),
+            1
+        );
+        assert_eq!(
+            index
+                .evidence_for_anchor("after")
+                .expect("edit evidence")
+                .len(),
+            1
+        );
+        assert_eq!(
+            index
+                .outbound_edges("before", 0.0, true)
+                .expect("edges")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn edit_undoing_earlier_edit_is_flagged_reverted_across_tapes() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        index
+            .ingest_tape_events(
+                "tape-1",
+                &[edit_event(Some("a"), Some("b"), "src/lib.rs", 0)],
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("first ingest");
+        index
+            .ingest_tape_events(
+                "tape-2",
+                &[edit_event(Some("b"), Some("a"), "src/lib.rs", 0)],
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("second ingest");
+
+        let forward = index.outbound_edges("a", 0.0, true).expect("edges");
+        assert_eq!(forward.len(), 1);
+        assert!(!forward[0].reverted);
+        let backward = index.outbound_edges("b", 0.0, true).expect("edges");
+        assert_eq!(backward.len(), 1);
+        assert!(backward[0].reverted);
+
+        let revert_count: Option<i64> = index
+            .conn
+            .query_row(
+                "SELECT revert_count FROM tapes WHERE tape_id = 'tape-2'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("tape row");
+        assert_eq!(revert_count, Some(1));
+    }
+
+    #[test]
+    fn ingest_relativizes_paths_against_meta_cwd_and_keeps_raw() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let meta = TapeEventAt {
+            offset: 0,
+            event: TapeEvent {
+                timestamp: "2026-02-22T00:00:00Z".to_string(),
+                data: TapeEventData::Meta(MetaEvent {
+                    model: None,
+                    repo_head: None,
+                    cwd: Some("/Users/dev/project".to_string()),
+                    label: None,
+                    coverage_read: None,
+                    coverage_edit: None,
+                    coverage_tool: None,
+                    origin: None,
+                    environment: None,
+                }),
+            },
+        };
+        let events = vec![
+            meta,
+            read_event("read-anchor", "/Users/dev/project/src/lib.rs", 1),
+            read_event("other-anchor", "src/main.rs", 2),
+        ];
+        index
+            .ingest_tape_events("tape-1", &events, LINK_THRESHOLD_DEFAULT)
+            .expect("ingest succeeds");
+
+        let refs = index.evidence_for_anchor("read-anchor").expect("evidence");
+        assert_eq!(refs[0].file_path, "src/lib.rs");
+        let raw: Option<String> = index
+            .conn
+            .query_row(
+                "SELECT raw_file_path FROM evidence WHERE anchor = 'read-anchor'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("raw path");
+        assert_eq!(raw.as_deref(), Some("/Users/dev/project/src/lib.rs"));
+        let untouched: Option<String> = index
+            .conn
+            .query_row(
+                "SELECT raw_file_path FROM evidence WHERE anchor = 'other-anchor'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("raw path");
+        assert_eq!(untouched, None);
+    }
+
+    #[test]
+    fn ingest_rolls_back_when_event_contains_invalid_anchor() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let events = vec![
+            read_event("anchor-1", "src/lib.rs", 0),
+            read_event("", "src/lib.rs", 1),
+        ];
+
+        let err = index.ingest_tape_events("tape-1", &events, LINK_THRESHOLD_DEFAULT);
+        assert!(err.is_err());
+        assert_eq!(
+            index
+                .evidence_for_anchor("anchor-1")
+                .expect("query after rollback")
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn location_only_edges_are_hidden_without_forensics_even_with_low_min_confidence() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let events = vec![edit_event_with_similarity(
+            Some("before"),
+            Some("after"),
+            Some(0.20),
+            "src/lib.rs",
+            1,
+        )];
+
+        index
+            .ingest_tape_events("tape-1", &events, LINK_THRESHOLD_DEFAULT)
+            .expect("ingest succeeds");
+
+        let without_forensics = index
+            .outbound_edges("before", 0.10, false)
+            .expect("non-forensics query");
+        assert_eq!(without_forensics.len(), 0);
+
+        let with_forensics = index
+            .outbound_edges("before", 0.10, true)
+            .expect("forensics query");
+        assert_eq!(with_forensics.len(), 1);
+        assert_eq!(
+            with_forensics[0].stored_class,
+            StoredEdgeClass::LocationOnly
+        );
+    }
+
+    #[test]
+    fn invalid_similarity_rejects_ingest_and_rolls_back() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let events = vec![
+            read_event("anchor-1", "src/lib.rs", 0),
+            edit_event_with_similarity(Some("a"), Some("b"), Some(1.2), "src/lib.rs", 1),
+        ];
+
+        let err = index.ingest_tape_events("tape-1", &events, LINK_THRESHOLD_DEFAULT);
+        assert!(err.is_err());
+        assert_eq!(
+            index
+                .evidence_for_anchor("anchor-1")
+                .expect("query after rollback")
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn ingest_persists_dispatch_links_and_queries_by_tape_and_uuid() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        let events = vec![read_event("anchor-1", "src/lib.rs", 0)];
+        let links = vec![
+            DispatchLink {
+                uuid: "11111111-1111-4111-8111-111111111111".to_string(),
+                first_turn_index: 0,
+                direction: DispatchDirection::Received,
+            },
+            DispatchLink {
+                uuid: "22222222-2222-4222-8222-222222222222".to_string(),
+                first_turn_index: 3,
+                direction: DispatchDirection::Sent,
+            },
+        ];
+
+        index
+            .ingest_tape_events_with_dispatch(
+                "tape-dispatch",
+                &events,
+                &links,
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("ingest dispatch links");
+
+        let by_tape = index
+            .dispatch_links_for_tape("tape-dispatch")
+            .expect("dispatch links by tape");
+        assert_eq!(by_tape.len(), 2);
+        assert_eq!(by_tape[0].direction, DispatchDirection::Received);
+        assert_eq!(by_tape[1].direction, DispatchDirection::Sent);
+
+        let by_uuid = index
+            .dispatch_links_for_uuid("22222222-2222-4222-8222-222222222222")
+            .expect("dispatch links by uuid");
+        assert_eq!(by_uuid.len(), 1);
+        assert_eq!(by_uuid[0].tape_id, "tape-dispatch");
+        assert_eq!(by_uuid[0].direction, DispatchDirection::Sent);
+    }
+
+    #[test]
+    fn latest_received_dispatch_before_turn_selects_most_recent_prior() {
+        let index = SqliteIndex::open_in_memory().expect("in-memory sqlite");
+        index
+            .insert_dispatch_link(
+                "tape-a",
+                &DispatchLink {
+                    uuid: "a".to_string(),
+                    first_turn_index: 1,
+                    direction: DispatchDirection::Received,
+                },
+            )
+            .expect("insert");
+        index
+            .insert_dispatch_link(
+                "tape-a",
+                &DispatchLink {
+                    uuid: "b".to_string(),
+                    first_turn_index: 7,
+                    direction: DispatchDirection::Received,
+                },
+            )
+            .expect("insert");
+        index
+            .insert_dispatch_link(
+                "tape-a",
+                &DispatchLink {
+                    uuid: "c".to_string(),
+                    first_turn_index: 9,
+                    direction: DispatchDirection::Sent,
+                },
+            )
+            .expect("insert");
+
+        let link = index
+            .latest_received_dispatch_before_turn("tape-a", 8)
+            .expect("query")
+            .expect("link");
+        assert_eq!(link.uuid, "b");
+        assert_eq!(link.direction, DispatchDirection::Received);
+    }
+
+    #[test]
+    fn dispatch_links_are_idempotent_on_repeat_ingest() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        let events = vec![read_event("anchor-1", "src/lib.rs", 0)];
+        let links = vec![DispatchLink {
+            uuid: "33333333-3333-4333-8333-333333333333".to_string(),
+            first_turn_index: 2,
+            direction: DispatchDirection::Received,
+        }];
+
+        index
+            .ingest_tape_events_with_dispatch(
+                "tape-repeat",
+                &events,
+                &links,
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("first ingest");
+        index
+            .ingest_tape_events_with_dispatch(
+                "tape-repeat",
+                &events,
+                &links,
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("second ingest");
+
+        let by_tape = index.dispatch_links_for_tape("tape-repeat").expect("query");
+        assert_eq!(by_tape.len(), 1);
+        assert_eq!(by_tape[0].uuid, "33333333-3333-4333-8333-333333333333");
+    }
+
+    #[test]
+    fn sent_dispatch_for_uuid_is_none_when_uuid_is_only_received() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        index
+            .insert_dispatch_link(
+                "tape-r1",
+                &DispatchLink {
+                    uuid: "44444444-4444-4444-8444-444444444444".to_string(),
+                    first_turn_index: 0,
+                    direction: DispatchDirection::Received,
+                },
+            )
+            .expect("insert");
+        index
+            .insert_dispatch_link(
+                "tape-r2",
+                &DispatchLink {
+                    uuid: "44444444-4444-4444-8444-444444444444".to_string(),
+                    first_turn_index: 1,
+                    direction: DispatchDirection::Received,
+                },
+            )
+            .expect("insert");
+
+        let parent = index
+            .sent_dispatch_for_uuid("44444444-4444-4444-8444-444444444444")
+            .expect("query parent");
+        assert!(parent.is_none());
+    }
+
+    #[test]
+    fn latest_received_dispatch_before_turn_handles_long_running_tapes() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        for i in 0..25 {
+            index
+                .insert_dispatch_link(
+                    "tape-long",
+                    &DispatchLink {
+                        uuid: format!("long-{i:02}"),
+                        first_turn_index: i,
+                        direction: DispatchDirection::Received,
+                    },
+                )
+                .expect("insert");
+        }
+
+        let picked = index
+            .latest_received_dispatch_before_turn("tape-long", 21)
+            .expect("query")
+            .expect("picked");
+        assert_eq!(picked.first_turn_index, 20);
+        assert_eq!(picked.uuid, "long-20");
+    }
+
+    #[test]
+    fn latest_received_dispatch_before_turn_returns_none_when_edit_precedes_dispatch() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        index
+            .insert_dispatch_link(
+                "tape-pre",
+                &DispatchLink {
+                    uuid: "later-dispatch".to_string(),
+                    first_turn_index: 10,
+                    direction: DispatchDirection::Received,
+                },
+            )
+            .expect("insert");
+
+        let picked = index
+            .latest_received_dispatch_before_turn("tape-pre", 3)
+            .expect("query");
+        assert!(picked.is_none());
+    }
+
+    #[test]
+    fn query_results_and_feedback_round_trip() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        index
+            .record_query_result(
+                "result_123",
+                "explain",
+                "{\"query\":{\"command\":\"explain\"}}",
+                "2026-04-03T00:00:00Z",
+            )
+            .expect("record query result");
+
+        assert!(
+            index
+                .query_result_exists("result_123")
+                .expect("query result exists"),
+            "expected recorded result to be queryable"
+        );
+
+        index
+            .upsert_result_feedback(
+                "result_123",
+                "found_answer",
+                Some("prevented a bad edit"),
+                "2026-04-03T00:01:00Z",
+            )
+            .expect("insert feedback");
+        index
+            .upsert_result_feedback(
+                "result_123",
+                "partially_helped",
+                None,
+                "2026-04-03T00:02:00Z",
+            )
+            .expect("update feedback");
+
+        let (outcome, note, rated_at): (String, Option<String>, String) = index
+            .conn
+            .query_row(
+                "SELECT outcome, note, rated_at FROM result_feedback WHERE result_id = ?1",
+                params!["result_123"],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .expect("feedback row");
+        assert_eq!(outcome, "partially_helped");
+        assert_eq!(note, None);
+        assert_eq!(rated_at, "2026-04-03T00:02:00Z");
+    }
+
+    #[test]
+    fn tape_metrics_round_trip_and_skip_unmeasured_tapes() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        index
+            .ingest_tape_events("unmeasured", &[], LINK_THRESHOLD_DEFAULT)
+            .expect("ingest");
+        let metrics = TapeMetrics {
+            event_count: 5,
+            message_count: 2,
+            tool_call_count: 1,
+            tool_error_count: 1,
+            edit_count: 2,
+            revert_count: 1,
+            started_at: Some("2026-02-22T00:00:00Z".to_string()),
+            ended_at: Some("2026-02-22T00:01:00Z".to_string()),
+            duration_secs: Some(60),
+            model: Some("gpt-5".to_string()),
+            harness: Some("codex-cli".to_string()),
+            session_id: Some("s-1".to_string()),
+            thrash_loop_count: 1,
+            coverage_read: Some("partial".to_string()),
+            coverage_edit: Some("partial".to_string()),
+            coverage_tool: Some("full".to_string()),
+            hostname: Some("laptop".to_string()),
+            user: None,
+        };
+        index
+            .record_tape_metrics("measured", &metrics)
+            .expect("record metrics");
+
+        assert_eq!(index.tape_metrics("unmeasured").expect("query"), None);
+        assert_eq!(
+            index.tape_metrics("measured").expect("query"),
+            Some(metrics.clone())
+        );
+        let all = index.all_tape_metrics().expect("all metrics");
+        assert_eq!(all, vec![("measured".to_string(), metrics)]);
+        let version: i64 = index
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .expect("user_version");
+        assert_eq!(version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn opening_a_v11_index_steps_it_to_the_current_schema() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join("index.sqlite");
+        {
+            let old = SqliteIndex {
+                conn: Connection::open(&path).expect("sqlite"),
+                anchor_cache: RefCell::new(None),
+                attached: Vec::new(),
+                link_threshold: LINK_THRESHOLD_DEFAULT,
+                scorer: default_scorer(),
+                as_of: None,
+            };
+            old.create_schema_v11().expect("v11 schema");
+            old.conn
+                .execute_batch("PRAGMA user_version = 11;")
+                .expect("stamp v11");
+            assert!(!old.column_exists("tapes", "hostname").expect("column"));
+            assert!(!old.table_exists("anchors").expect("table"));
+        }
+
+        let index = SqliteIndex::open(&path.to_string_lossy()).expect("migrate");
+        let version: i64 = index
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .expect("user_version");
+        assert_eq!(version, SCHEMA_VERSION);
+        for (table, column) in [
+            ("events", "exit_code"),
+            ("tapes", "thrash_loop_count"),
+            ("tapes", "session_id"),
+            ("tapes", "hostname"),
+            ("edges", "scorer"),
+            ("evidence", "project"),
+        ] {
+            assert!(
+                index.column_exists(table, column).expect("column"),
+                "{table}.{column}"
+            );
+        }
+        for table in [
+            "spawn_links",
+            "refs",
+            "tape_joins",
+            "anchors",
+            "growth_samples",
+        ] {
+            assert!(index.table_exists(table).expect("table"), "{table}");
+        }
+    }
+
+    #[test]
+    fn bulk_load_relaxes_sync_until_finished_or_dropped() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join("index.sqlite");
+        let index = SqliteIndex::open(&path.to_string_lossy()).expect("index");
+        const NORMAL: i64 = 1;
+        const FULL: i64 = 2;
+        assert_eq!(index.synchronous_level().expect("level"), FULL);
+
+        let bulk = index.bulk_load().expect("bulk");
+        assert_eq!(index.synchronous_level().expect("level"), NORMAL);
+        index
+            .record_tape_metrics("tape-a", &TapeMetrics::default())
+            .expect("write during bulk load");
+        bulk.finish().expect("finish");
+        assert_eq!(index.synchronous_level().expect("level"), FULL);
+
+        {
+            let _bulk = index.bulk_load().expect("bulk");
+        }
+        assert_eq!(index.synchronous_level().expect("level"), FULL);
+        assert!(index.has_tape("tape-a").expect("has tape"));
+    }
+
+    #[test]
+    fn maintain_resets_ingest_counter_and_keeps_incremental_vacuum() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join("index.sqlite");
+        let index = SqliteIndex::open(&path.to_string_lossy()).expect("index");
+        assert_eq!(index.note_tapes_indexed(3).expect("note"), 3);
+        assert_eq!(index.note_tapes_indexed(2).expect("note"), 5);
+
+        let report = index.maintain("2026-03-01T00:00:00Z").expect("maintain");
+        assert!(!report.converted_to_incremental_vacuum);
+        assert!(!report.checkpoint_busy);
+        assert_eq!(report.free_pages_after, 0);
+        assert_eq!(index.note_tapes_indexed(0).expect("note"), 0);
+        let last: Option<String> = index
+            .conn
+            .query_row(
+                "SELECT last_maintained_at FROM maintenance_state",
+                [],
+                |row| row.get(0),
+            )
+            .expect("state");
+        assert_eq!(last.as_deref(), Some("2026-03-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn annotations_match_any_anchor_and_import_into_a_fresh_index() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let old_path = temp.path().join("old.sqlite");
+        let old = SqliteIndex::open(&old_path.to_string_lossy()).expect("index");
+        let span = vec!["a1".to_string(), "a2".to_string()];
+        old.add_annotation(
+            "src/lib.rs:1-3",
+            &span,
+            "keep the retry",
+            "2026-03-01T00:00:00Z",
+        )
+        .expect("annotate span");
+        old.add_annotation(
+            "a3",
+            &["a3".to_string()],
+            "hot path",
+            "2026-03-02T00:00:00Z",
+        )
+        .expect("annotate anchor");
+        assert!(
+            old.add_annotation("x", &[String::new()], "bad", "now")
+                .is_err()
+        );
+
+        let found = old
+            .annotations_for_anchors(&["a2".to_string(), "a1".to_string(), "zz".to_string()])
+            .expect("lookup");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].note, "keep the retry");
+        assert_eq!(found[0].matched_anchors, vec!["a2", "a1"]);
+        drop(old);
+
+        let fresh = SqliteIndex::open_in_memory().expect("fresh");
+        assert_eq!(
+            fresh
+                .import_annotations(&old_path.to_string_lossy())
+                .expect("import"),
+            2
+        );
+        let found = fresh
+            .annotations_for_anchors(&["a3".to_string()])
+            .expect("lookup");
+        assert_eq!(found[0].target, "a3");
+    }
+
+    #[test]
+    fn anchor_cache_serves_repeat_lookups_until_the_index_changes() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join("index.sqlite");
+        let index = SqliteIndex::open(&path.to_string_lossy())
+            .expect("index")
+            .with_anchor_cache(8);
+        index
+            .ingest_tape_events(
+                "tape-1",
+                &[edit_event(Some("before"), Some("after"), "src/lib.rs", 1)],
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("ingest");
+
+        for _ in 0..3 {
+            assert_eq!(
+                index
+                    .outbound_edges("before", 0.50, false)
+                    .expect("edges")
+                    .len(),
+                1
+            );
+            assert_eq!(
+                index.evidence_for_anchor("after").expect("evidence").len(),
+                1
+            );
+        }
+        let stats = index.anchor_cache_stats().expect("cache enabled");
+        assert_eq!((stats.hits, stats.misses, stats.entries), (4, 2, 2));
+
+        // A write from another connection is seen on the next lookup.
+        let other = SqliteIndex::open(&path.to_string_lossy()).expect("second connection");
+        other
+            .ingest_tape_events(
+                "tape-2",
+                &[edit_event(Some("after"), Some("later"), "src/lib.rs", 1)],
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("ingest");
+        assert_eq!(
+            index.evidence_for_anchor("after").expect("evidence").len(),
+            2
+        );
+        assert_eq!(index.anchor_cache_stats().expect("stats").invalidations, 1);
+        assert!(
+            SqliteIndex::open_in_memory()
+                .expect("index")
+                .anchor_cache_stats()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn scans_evidence_by_file_and_edges_by_time_window() {
+        let index = SqliteIndex::open_in_memory().expect("index");
+        index
+            .ingest_tape_events(
+                "tape-1",
+                &[
+                    read_event("notes", "docs/notes.md", 0),
+                    read_event("early", "src/lib.rs", 1),
+                    edit_event(Some("before"), Some("after"), "src/lib.rs", 2),
+                ],
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("ingest");
+        let range = |since: Option<&str>, until: Option<&str>| TimeRange {
+            since: since.map(str::to_owned),
+            until: until.map(str::to_owned),
+        };
+
+        let anchors = |range: TimeRange| {
+            index
+                .evidence_for_file("src/*", &range)
+                .expect("evidence")
+                .into_iter()
+                .map(|(anchor, _)| anchor)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(anchors(range(None, None)).len(), 3);
+        assert_eq!(
+            anchors(range(None, Some("2026-02-22T00:00:01Z"))),
+            vec!["early"]
+        );
+        let mut late = anchors(range(Some("2026-02-22T00:00:01Z"), None));
+        late.sort();
+        assert_eq!(late, vec!["after", "before"]);
+
+        let edges = index.edges_in_window(&range(None, None)).expect("edges");
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].0, "2026-02-22T00:00:01Z");
+        assert_eq!(
+            (
+                edges[0].1.from_anchor.as_str(),
+                edges[0].1.to_anchor.as_str()
+            ),
+            ("before", "after")
+        );
+        assert!(
+            index
+                .edges_in_window(&range(Some("2026-02-23T00:00:00Z"), None))
+                .expect("edges")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn as_of_hides_evidence_and_edges_recorded_after_the_cutoff() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join("index.sqlite");
+        SqliteIndex::open(&path.to_string_lossy())
+            .expect("index")
+            .ingest_tape_events(
+                "tape-1",
+                &[
+                    read_event("early", "src/lib.rs", 0),
+                    edit_event(Some("before"), Some("after"), "src/lib.rs", 1),
+                ],
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("ingest");
+
+        let now = SqliteIndex::open(&path.to_string_lossy()).expect("index");
+        assert_eq!(now.evidence_for_anchor("after").expect("evidence").len(), 1);
+        assert_eq!(
+            now.inbound_edges("after", 0.0, true).expect("edges").len(),
+            1
+        );
+
+        let then = SqliteIndex::open(&path.to_string_lossy())
+            .expect("index")
+            .with_as_of("2026-02-22T00:00:00Z");
+        assert_eq!(
+            then.evidence_for_anchor("early").expect("evidence").len(),
+            1
+        );
+        assert!(
+            then.evidence_for_anchor("after")
+                .expect("evidence")
+                .is_empty()
+        );
+        assert!(
+            then.inbound_edges("after", 0.0, true)
+                .expect("edges")
+                .is_empty()
+        );
+        assert!(
+            then.outbound_edges("before", 0.0, true)
+                .expect("edges")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn spawn_calls_link_tapes_either_way_round_and_forget_cleans_up() {
+        let index = SqliteIndex::open_in_memory().expect("index");
+        let started = |tape_id: &str, harness: &str, at: &str| {
+            index
+                .record_tape_metrics(
+                    tape_id,
+                    &TapeMetrics {
+                        started_at: Some(at.to_string()),
+                        harness: Some(harness.to_string()),
+                        ..TapeMetrics::default()
+                    },
+                )
+                .expect("metrics");
+        };
+        let call = SpawnCall {
+            offset: 3,
+            harness: "codex-cli".to_string(),
+            command: "codex exec fix".to_string(),
+            cwd: Some("/work/app".to_string()),
+            earliest: "2026-03-01T10:00:00Z".to_string(),
+            latest: "2026-03-01T10:01:00Z".to_string(),
+        };
+
+        started("parent", "claude-code", "2026-03-01T09:00:00Z");
+        index
+            .record_spawn_calls("parent", Some("/work/app"), std::slice::from_ref(&call))
+            .expect("parent calls");
+        started("child", "codex-cli", "2026-03-01T10:00:30Z");
+        started("elsewhere", "codex-cli", "2026-03-01T10:00:40Z");
+        started("too-late", "codex-cli", "2026-03-01T10:05:00Z");
+        assert_eq!(
+            index
+                .record_spawn_calls("child", Some("/work/app/api"), &[])
+                .expect("child"),
+            1
+        );
+        assert_eq!(
+            index
+                .record_spawn_calls("elsewhere", Some("/work/application"), &[])
+                .expect("elsewhere"),
+            0
+        );
+        index
+            .record_spawn_calls("too-late", None, &[])
+            .expect("too late");
+
+        let expected = vec![SpawnLinkRow {
+            parent_tape_id: "parent".to_string(),
+            parent_offset: 3,
+            child_tape_id: "child".to_string(),
+            command: "codex exec fix".to_string(),
+        }];
+        assert_eq!(index.spawn_parents("child").expect("parents"), expected);
+        // Recording the parent again finds the child already indexed.
+        index
+            .record_spawn_calls("parent", Some("/work/app"), &[call])
+            .expect("parent again");
+        assert_eq!(index.spawn_children("parent").expect("children"), expected);
+
+        index.forget_tapes(&["parent".to_string()]).expect("forget");
+        assert!(index.spawn_parents("child").expect("parents").is_empty());
+    }
+
+    #[test]
+    fn issue_refs_are_replaced_per_tape_and_found_by_key() {
+        let index = SqliteIndex::open_in_memory().expect("index");
+        let issue = |key: &str, offset: u64, source: IssueRefSource| IssueRef {
+            key: key.to_string(),
+            offset,
+            source,
+        };
+        index
+            .record_issue_refs("a", &[issue("ENG-1", 0, IssueRefSource::Meta)])
+            .expect("a");
+        let refs = vec![
+            issue("ENG-1", 2, IssueRefSource::Git),
+            issue("PAY-7", 1, IssueRefSource::Message),
+        ];
+        index.record_issue_refs("b", &refs).expect("b");
+        index.record_issue_refs("b", &refs).expect("b again");
+
+        assert_eq!(
+            index.tapes_with_issue("eng-1").expect("eng-1"),
+            ["a".to_string(), "b".to_string()]
+        );
+        assert_eq!(
+            index.issue_refs("b").expect("refs"),
+            [refs[1].clone(), refs[0].clone()]
+        );
+        index.forget_tapes(&["a".to_string()]).expect("forget");
+        assert_eq!(
+            index.tapes_with_issue("ENG-1").expect("eng-1"),
+            ["b".to_string()]
+        );
+    }
+
+    #[test]
+    fn split_parts_keep_their_parent_referenced() {
+        let index = SqliteIndex::open_in_memory().expect("index");
+        let split = |part: u64, first_offset: u64, last_offset: u64| TapeSplit {
+            parent_tape_id: "parent".to_string(),
+            part,
+            parts: 2,
+            first_offset,
+            last_offset,
+        };
+        index
+            .record_tape_split("second", Some(&split(2, 5, 9)))
+            .expect("second");
+        index
+            .record_tape_split("first", Some(&split(1, 0, 4)))
+            .expect("first");
+        index.record_tape_split("other", None).expect("unsplit");
+
+        assert_eq!(
+            index.split_parts("parent").expect("parts"),
+            [
+                ("first".to_string(), split(1, 0, 4)),
+                ("second".to_string(), split(2, 5, 9)),
+            ]
+        );
+        assert!(
+            index
+                .referenced_tape_ids()
+                .expect("referenced")
+                .contains(&"parent".to_string())
+        );
+        index
+            .forget_tapes(&["first".to_string(), "second".to_string()])
+            .expect("forget");
+        assert!(index.referenced_tape_ids().expect("referenced").is_empty());
+    }
+
+    #[test]
+    fn joined_tapes_keep_their_members_referenced() {
+        let index = SqliteIndex::open_in_memory().expect("index");
+        let members = ["a".to_string(), "b".to_string()];
+        index.record_tape_join("joined", &members).expect("join");
+        index.record_tape_join("plain", &[]).expect("plain");
+
+        assert_eq!(
+            index.joined_into("b").expect("b"),
+            Some("joined".to_string())
+        );
+        assert_eq!(index.joined_into("plain").expect("plain"), None);
+        let mut referenced = index.referenced_tape_ids().expect("referenced");
+        referenced.sort();
+        assert_eq!(referenced, members);
+        index.forget_tapes(&["joined".to_string()]).expect("forget");
+        assert!(index.joined_into("a").expect("a").is_none());
+    }
+
+    #[test]
+    fn anchor_registry_tracks_first_seen_previews_and_occurrences() {
+        let index = SqliteIndex::open_in_memory().expect("index");
+        let text = "fn   retry_upload(attempts: u32) {\n    backoff(attempts)\n}\n";
+        let mut with_text = read_event("ignored", "src/lib.rs", 0);
+        with_text.event.timestamp = "2026-03-02T00:00:00Z".to_string();
+        if let TapeEventData::CodeRead(read) = &mut with_text.event.data {
+            read.text = Some(text.to_string());
+        }
+        let anchors = fingerprint_token_hashes(text);
+        let anchor = anchors[0].clone();
+        let mut bare = read_event(&anchor, "src/lib.rs", 0);
+        bare.event.timestamp = "2026-03-03T00:00:00Z".to_string();
+
+        index
+            .ingest_tape_events("late", &[bare], LINK_THRESHOLD_DEFAULT)
+            .expect("ingest late");
+        index.set_tape_anchor_version("late", 2).expect("version");
+        index
+            .ingest_tape_events("early", &[with_text], LINK_THRESHOLD_DEFAULT)
+            .expect("ingest early");
+        index.set_tape_anchor_version("early", 1).expect("version");
+
+        let info = index
+            .anchor_info(&[anchor.clone(), "unknown".to_string()])
+            .expect("info");
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0].first_seen, "2026-03-02T00:00:00Z");
+        assert_eq!(info[0].anchor_version, Some(2));
+        assert_eq!(
+            info[0].preview.as_deref(),
+            Some("fn retry_upload(attempts: u32) { backoff(attempts) }")
+        );
+        assert_eq!(info[0].occurrences, 2);
+
+        assert!(
+            index
+                .record_anchor_alias(&anchor, "v2-anchor")
+                .expect("alias")
+        );
+        assert!(
+            !index
+                .record_anchor_alias("unknown", "v2-anchor")
+                .expect("alias")
+        );
+        index.forget_tapes(&["early".to_string()]).expect("forget");
+        let info = index.anchor_info(&[anchor]).expect("info");
+        assert_eq!(info[0].occurrences, 1);
+        assert_eq!(info[0].alias_of.as_deref(), Some("v2-anchor"));
+    }
+
+    #[test]
+    fn detaching_moves_out_evidence_but_keeps_tape_rows_and_edges() {
+        let index = SqliteIndex::open_in_memory().expect("index");
+        let events = vec![
+            edit_event(Some("before"), Some("after"), "src/lib.rs", 1),
+            edit_event(Some("deleted"), None, "src/lib.rs", 2),
+        ];
+        for (tape_id, started_at) in [
+            ("tape-old", "2025-11-30T23:00:00Z"),
+            ("tape-new", "2025-12-01T00:00:00Z"),
+        ] {
+            index
+                .ingest_tape_events(tape_id, &events, LINK_THRESHOLD_DEFAULT)
+                .expect("ingest");
+            index
+                .record_tape_metrics(
+                    tape_id,
+                    &TapeMetrics {
+                        started_at: Some(started_at.to_string()),
+                        ..TapeMetrics::default()
+                    },
+                )
+                .expect("metrics");
+        }
+
+        let resident = index
+            .resident_tapes_started_before("2025-12")
+            .expect("resident");
+        assert_eq!(
+            resident,
+            vec![("tape-old".to_string(), "2025-11-30T23:00:00Z".to_string())]
+        );
+        assert!(
+            index
+                .detach_tape_evidence(&["tape-old".to_string()])
+                .expect("detach")
+                > 0
+        );
+        assert!(
+            index
+                .resident_tapes_started_before("2025-12")
+                .expect("resident")
+                .is_empty()
+        );
+        let tapes = |anchor: &str| {
+            index
+                .evidence_for_anchor(anchor)
+                .expect("evidence")
+                .into_iter()
+                .map(|fragment| fragment.tape_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tapes("after"), vec!["tape-new"]);
+        assert!(index.has_tape("tape-old").expect("has tape"));
+        assert_eq!(
+            index
+                .outbound_edges("before", 0.50, false)
+                .expect("edges")
+                .len(),
+            1
+        );
+
+        index
+            .forget_tapes(&["tape-old".to_string()])
+            .expect("forget");
+        assert_eq!(index.tape_ids().expect("tape ids"), vec!["tape-new"]);
+    }
+
+    #[test]
+    fn semantic_search_scores_only_matching_provider_vectors() {
+        let index = SqliteIndex::open_in_memory().expect("index");
+        index
+            .upsert_event_embeddings(
+                "tape-a",
+                "hashing-2",
+                &[
+                    (1, "msg.in", vec![1.0, 0.0]),
+                    (4, "msg.out", vec![0.6, 0.8]),
+                ],
+            )
+            .expect("store a");
+        index
+            .upsert_event_embeddings("tape-b", "other", &[(0, "msg.in", vec![1.0, 0.0])])
+            .expect("store b");
+        assert!(
+            index
+                .has_event_embeddings("tape-a", "hashing-2")
+                .expect("has")
+        );
+        assert!(
+            !index
+                .has_event_embeddings("tape-b", "hashing-2")
+                .expect("has")
+        );
+
+        let hits = index
+            .semantic_search("hashing-2", &[0.0, 1.0], 10)
+            .expect("search");
+        assert_eq!(
+            hits.iter()
+                .map(|hit| (hit.tape_id.as_str(), hit.event_offset))
+                .collect::<Vec<_>>(),
+            vec![("tape-a", 4), ("tape-a", 1)]
+        );
+        assert!((hits[0].score - 0.8).abs() < 1e-6);
+        assert_eq!(hits[1].score, 0.0);
+    }
+}
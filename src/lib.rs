@@ -1,6 +1,10 @@
 pub mod anchor;
 pub mod config;
+pub mod embed;
+pub mod error;
 pub mod index;
 pub mod query;
 pub mod store;
 pub mod tape;
+
+pub use error::{Error, Result};
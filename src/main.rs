@@ -1,4 +1,5 @@
-use std::collections::{HashMap, HashSet};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::{self, File, OpenOptions};
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
@@ -10,26 +11,126 @@ use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
 use chrono::Utc;
-use clap::{Args, Parser, Subcommand, ValueEnum};
-use engram::anchor::fingerprint_token_hashes;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use engram::anchor::chunk::{LineChunk, chunk_span, content_defined_chunks};
+use engram::anchor::normalize::{SpanNormalizer, normalizer_for_path, normalizer_for_tag};
+use engram::anchor::symbol::enclosing_definition;
+use engram::anchor::{
+    anchor_version, expand_winnow_anchor, fingerprint_text, fingerprint_token_hashes,
+    normalize_line_endings, normalized_anchor_hashes, normalized_token_hashes,
+};
 use engram::config::{
-    EffectiveWatchConfig, EffectiveWatchSource, ensure_user_config,
-    load_effective_config_with_override,
+    BinaryFilePolicy, BinaryPolicy, DEFAULT_AUTO_MAINTAIN_AFTER_INGESTS,
+    DEFAULT_QUOTA_HORIZON_DAYS, EffectiveConfig, EffectiveEmbeddingsConfig, EffectiveExportConfig,
+    EffectiveSummarizeConfig, EffectiveWatchConfig, EffectiveWatchSource, IndexPartitioning,
+    IngestLimits, OutputSampling, StorageLayout, StorageQuota, append_watch_sources,
+    default_storage_dirs, ensure_user_config, expand_tilde, load_effective_config,
+    load_effective_config_for_profile, local_store_root, migrate_legacy_storage, set_config_values,
+};
+use engram::embed::{embeddable_events, provider_from_config};
+use engram::index::calibrate::{
+    CONFIDENCE_TOLERANCE, CalibrationSample, MIN_CALIBRATION_SAMPLES, calibrate,
 };
+use engram::index::fingerprints::FingerprintCache;
+use engram::index::growth::{GrowthSample, forecast, store_bytes};
 use engram::index::lineage::{
     Cardinality, EvidenceFragmentRef, EvidenceKind, LINK_THRESHOLD_DEFAULT, LocationDelta,
     StoredEdgeClass,
 };
-use engram::index::{DispatchDirection, DispatchLink, DispatchLinkRow, EdgeRow, SqliteIndex};
+use engram::index::partitions::{
+    list_partitions, partition_dir, partition_key, partition_path, valid_partition_key,
+};
+use engram::index::similarity::{DEFAULT_SCORER, default_scorer, scorer_for_name};
+use engram::index::{
+    DispatchDirection, DispatchLink, DispatchLinkRow, EdgeRow, IngestDiagnostics,
+    MaintenanceReport, SemanticHit, SqliteIndex, TimeRange,
+};
+use engram::query::cypher::cypher_script;
+use engram::query::drift::{
+    DriftState, ExpectedEdit, check_span_with_tokens, content_tokens, surviving_edits,
+};
+use engram::query::episodes::{
+    DEFAULT_EPISODE_MAX_GAP_HOURS, DEFAULT_EPISODE_MIN_KEYWORD_OVERLAP, EpisodeSession,
+    cluster_episodes, prompt_keywords,
+};
 use engram::query::explain::{
-    ExplainTraversal, PrettyConfidenceTier, explain_by_anchor, pretty_tier,
+    ExplainTraversal, LineagePath, LineageWalker, PrettyConfidenceTier, TraversalTruncation,
+    TruncationReason, best_lineage_paths, explain_by_anchor, pretty_tier, retrieve_direct,
+};
+use engram::query::humanize::{DisplayZone, display_date, display_timestamp, relative_time};
+use engram::query::lens::{LensRegion, lens_regions};
+use engram::query::markdown::tape_markdown;
+use engram::query::mermaid::{MermaidSession, lineage_flowchart};
+use engram::query::narrative::{NarrativeExcerpt, excerpt_text, request_narrative, truncate_chars};
+use engram::query::porcelain::{PorcelainVersion, porcelain_line};
+use engram::query::pseudonym::Pseudonymizer;
+use engram::query::session_graph::{
+    SessionLink, SessionLinkKind, SessionNode, SourcedTape, continuation_links, session_graph_dot,
+    session_graph_mermaid, session_id_chains, shared_file_links, source_chains, subagent_links,
+};
+use engram::query::snapshot::{GRAPH_SNAPSHOT_VERSION, encode_graph_snapshot};
+use engram::store::aliases::{
+    TapeIdAliases, load_tape_id_aliases, save_tape_id_aliases, tape_id_aliases_path,
 };
 use engram::store::atomic::atomic_write;
+use engram::store::lock::{LockError, StoreLock, lock_path_for_db};
+use engram::store::protected::{
+    ProtectedTape, load_protected_tapes, protected_tapes_path, save_protected_tapes,
+};
+use engram::store::sessions::{
+    SESSION_REF_PREFIX, SessionPart, append_session_part, list_record_sessions,
+    load_record_session, save_record_session, session_manifest_path, stitch_parts,
+    valid_session_name,
+};
+use engram::store::watchlist::{WatchEntry, load_watchlist, save_watchlist, watchlist_path};
 use engram::tape::adapter::{
-    AdapterId, adapter_registry, convert_with_adapter, discover_sessions_with_adapter,
+    AdapterId, adapter_registry, adapter_version_is_older, artifact_location, convert_with_adapter,
+    discover_sessions_with_adapter, skipped_input_lines,
+};
+use engram::tape::adapters::vscode::{chat_editing_session_bundle, is_chat_editing_state_path};
+use engram::tape::binary::{BinaryCounts, apply_binary_policy};
+use engram::tape::catalog::{EventRecord, event_records_from_jsonl};
+use engram::tape::comments::{CommentDirective, scan_comment_directives};
+use engram::tape::compress::{compress_jsonl, decompress_jsonl, salvage_jsonl};
+use engram::tape::diff::diff_tape_rows;
+use engram::tape::edits::{EditObject, stamp_edit_objects};
+use engram::tape::environment::{
+    EnvironmentField, TapeEnvironment, harness_version, stamp_environment,
+};
+use engram::tape::event::{
+    FileRange, TapeEventAt, TapeEventData, parse_jsonl_events, parse_jsonl_events_lossy,
+};
+use engram::tape::github::{
+    GITHUB_API_URL, GITHUB_PR_HARNESS, PullRequestRef, fetch_pull_request_review, review_tape_jsonl,
+};
+use engram::tape::hooks::{hook_payload_to_events, hook_tape_meta};
+use engram::tape::identity::{canonical_jsonl, names_content, tape_id as canonical_tape_id};
+use engram::tape::ignore::{IgnoreRules, drop_ignored_events};
+use engram::tape::join::{join_tapes_jsonl, tape_join_members};
+use engram::tape::metrics::{
+    TapeMetrics, ThrashLoop, tape_metrics_from_jsonl, thrash_loops_from_jsonl,
+};
+use engram::tape::normalize::{normalize_evidence_text, stamp_span_normalizers};
+use engram::tape::notebook::{is_notebook_path, normalize_notebook_evidence, notebook_view};
+use engram::tape::origin::{TapeOrigin, stamp_meta_field, stamp_origin, tape_origin};
+use engram::tape::patch::{EditApplication, apply_edit, unified_hunks};
+use engram::tape::paths::{
+    PathPolicy, canonicalize_or_normalize, is_absolute_slash, normalize_path, repo_relative_path,
+    slash_path,
+};
+use engram::tape::projects::{ProjectRules, stamp_projects};
+use engram::tape::refs::{IssuePatterns, issue_refs_from_jsonl};
+use engram::tape::rotation::{
+    is_gzip_path, read_transcript, transcript_extension, uncompressed_path,
 };
-use engram::tape::compress::{compress_jsonl, decompress_jsonl};
-use engram::tape::event::{TapeEventAt, TapeEventData, parse_jsonl_events};
+use engram::tape::sample::sample_large_fields;
+use engram::tape::snapshot::{SnapshotFileState, snapshot_event, snapshot_file_state};
+use engram::tape::spawn::{spawn_calls_from_jsonl, tape_cwd};
+use engram::tape::split::{
+    DEFAULT_IDLE_GAP_SECS, SplitAt, auto_split_points, resolve_split_at, split_tape_jsonl,
+    tape_split,
+};
+use engram::tape::worktree::stamp_repo_scopes;
 use notify::event::{ModifyKind, RenameMode};
 use notify::{
     Config as NotifyConfig, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
@@ -40,13 +141,21 @@ use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 
 const MAX_QUERY_WINDOW_ANCHORS: usize = 16;
+const FINGERPRINT_CACHE_FILE: &str = "fingerprints.sqlite";
 const DEFAULT_WINDOW_BEFORE_RATIO_NUM: usize = 3;
 const DEFAULT_WINDOW_BEFORE_RATIO_DEN: usize = 4;
 const SAFE_RESULT_SESSION_THRESHOLD: usize = 25;
+const SEMANTIC_CANDIDATES: usize = 200;
+const SEMANTIC_MATCHES_PER_SESSION: usize = 3;
 
 const TAPE_SUFFIX: &str = ".jsonl.zst";
 const TRANSCRIPT_WINDOW_RADIUS: usize = 2;
 const CURSOR_GUARD_WINDOW: usize = 512;
+const SKIPPED_LINE_SAMPLE: usize = 10;
+/// How long a recorder waits for another writer to release the store lock.
+const STORE_LOCK_WAIT: Duration = Duration::from_secs(60);
+const STORE_LOCK_POLL: Duration = Duration::from_millis(100);
+const WATCH_HOOK_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Debug)]
 struct CliError {
@@ -83,30 +192,224 @@ impl From<serde_json::Error> for CliError {
 #[command(name = "engram")]
 #[command(about = "A local-first causal index over code history")]
 struct Cli {
+    /// Use the `profiles.<NAME>` store and sources from the config
+    /// (default: `ENGRAM_PROFILE`).
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Command,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
-    Init,
+    Init(InitArgs),
     Ingest(IngestArgs),
     Watch(WatchArgs),
+    Sources(SourcesArgs),
+    Discover(DiscoverArgs),
     Fingerprint,
+    Reindex,
+    Maintain,
+    Calibrate(CalibrateArgs),
     Record(RecordArgs),
+    HookReceiver(HookReceiverArgs),
+    Watchlist(WatchlistArgs),
+    Annotate(AnnotateArgs),
+    ScanComments(ScanCommentsArgs),
+    Provenance(ProvenanceArgs),
     Explain(ExplainArgs),
+    Bisect(BisectArgs),
+    Origin(OriginArgs),
+    Why(WhyArgs),
+    Lens(LensArgs),
     Grep(GrepArgs),
+    Events(EventsArgs),
+    Tools(ToolsArgs),
+    PromptLog(PromptLogArgs),
+    Drift(DriftArgs),
+    Search(SearchArgs),
     Peek(PeekArgs),
     Rate(RateArgs),
-    Tapes,
+    Tapes(TapesArgs),
+    Sessions(SessionsArgs),
     Show(ShowArgs),
-    Gc,
+    Replay(ReplayArgs),
+    Stats(StatsArgs),
+    Verify(VerifyArgs),
+    Export(ExportArgs),
+    Gc(GcArgs),
+    Protect(ProtectArgs),
+    Import(ImportArgs),
+    Split(SplitArgs),
+    Join(JoinArgs),
+    Completions(CompletionsArgs),
+    Man(ManArgs),
+}
+
+#[derive(Args, Debug, Default)]
+struct InitArgs {
+    /// Walk through discovered harness sources, storage, retention and
+    /// redaction on the terminal and write the answers to the config.
+    #[arg(long)]
+    interactive: bool,
 }
 
 #[derive(Args, Debug, Default)]
 struct IngestArgs {
     #[arg(value_name = "PATH")]
     paths: Vec<PathBuf>,
+    /// Bulk-load mode: skip per-commit fsyncs and checkpoint once at the end.
+    /// A crash mid-ingest may lose recent tapes from the index (re-run ingest
+    /// to recover) but cannot corrupt it.
+    #[arg(long)]
+    fast_ingest: bool,
+    /// Regenerate tapes produced by older adapter versions from their raw
+    /// sources instead of discovering new input.
+    #[arg(long, conflicts_with = "paths")]
+    reconvert: bool,
+}
+
+#[derive(Args, Debug)]
+struct VerifyArgs {
+    /// Recompute the content hash of every stored tape and report those that
+    /// no longer match their tape id or do not decompress.
+    #[arg(long)]
+    tapes: bool,
+    /// Rewrite tapes that no longer decompress from the complete lines before
+    /// the damage, re-indexing them under the id of the salvaged content.
+    #[arg(long, requires = "tapes")]
+    salvage: bool,
+}
+
+#[derive(Args, Debug)]
+struct CalibrateArgs {
+    /// Edits to sample, spread evenly over every indexed tape.
+    #[arg(long, value_name = "N", default_value_t = 500)]
+    sample: usize,
+    /// Write the suggested threshold to `lineage.link_threshold`.
+    #[arg(long)]
+    write: bool,
+    /// The config to write to (default: the config in effect).
+    #[arg(long, requires = "write")]
+    config: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Default)]
+struct GcArgs {
+    /// Delete whole monthly index partitions older than this month (`YYYY-MM`)
+    /// before collecting unreferenced tapes.
+    #[arg(long, value_name = "YYYY-MM")]
+    drop_partitions_before: Option<String>,
+    /// Report what would be deleted without deleting anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct ProtectArgs {
+    #[command(subcommand)]
+    command: ProtectCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ProtectCommand {
+    /// Keep a tape through `gc`, whether or not the index references it.
+    Add {
+        tape_id: String,
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Let `gc` collect a tape again.
+    Remove {
+        tape_id: String,
+    },
+    List,
+}
+
+#[derive(Args, Debug)]
+struct SplitArgs {
+    tape_id: String,
+    /// Start a new part at this tape offset, or at the first event at or
+    /// after this RFC 3339 timestamp. Repeatable.
+    #[arg(
+        long,
+        value_name = "OFFSET|TIMESTAMP",
+        required_unless_present = "auto"
+    )]
+    at: Vec<String>,
+    /// Also start a new part at every user prompt that follows
+    /// `--idle-gap` seconds without events.
+    #[arg(long)]
+    auto: bool,
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_IDLE_GAP_SECS)]
+    idle_gap: i64,
+    /// Report the parts without writing or reindexing anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct JoinArgs {
+    /// The tapes to stitch together, two or more.
+    #[arg(required = true, num_args = 2.., value_name = "TAPE_ID")]
+    tape_ids: Vec<String>,
+    /// Report the joined tape without writing or reindexing anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct SourcesArgs {
+    #[command(subcommand)]
+    command: SourcesCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum SourcesCommand {
+    /// Report each configured watch source: files matched, the newest
+    /// session, when it was last scanned, which adapters recognize its
+    /// files and its recent ingest failures.
+    Status {
+        /// Recent failures to list per source.
+        #[arg(long, value_name = "N", default_value_t = 5)]
+        failures: usize,
+        /// Newest files per source to run adapter detection on.
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        sample: usize,
+    },
+}
+
+#[derive(Args, Debug)]
+struct DiscoverArgs {
+    /// Append the sources found to `watch.sources` in the config.
+    #[arg(long)]
+    add: bool,
+    /// The config to append to (default: ~/.engram/config.yml).
+    #[arg(long, requires = "add")]
+    config: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ImportArgs {
+    #[command(subcommand)]
+    command: ImportCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportCommand {
+    /// Record a pull request's review discussion as a tape, each comment
+    /// anchored to the lines it was left on.
+    GithubPr {
+        /// `owner/repo#123` or the pull request URL.
+        #[arg(value_name = "PR")]
+        pr: String,
+        #[arg(long, value_name = "URL", default_value = GITHUB_API_URL)]
+        api_url: String,
+        /// Environment variable holding the API token; unauthenticated
+        /// when unset.
+        #[arg(long, value_name = "VAR", default_value = "GITHUB_TOKEN")]
+        token_env: String,
+    },
 }
 
 #[derive(Args, Debug, Default)]
@@ -119,15 +422,251 @@ struct WatchArgs {
 struct RecordArgs {
     #[arg(long)]
     stdin: bool,
+    /// Exit 0 even when the recorded command fails.
+    #[arg(long)]
+    no_propagate: bool,
+    /// Kill the recorded command after this many seconds.
+    #[arg(long, value_name = "SECS")]
+    timeout: Option<u64>,
+    /// Keep at most this many bytes of each of stdout and stderr in the tape.
+    #[arg(long, value_name = "BYTES")]
+    max_output_bytes: Option<usize>,
+    /// Append this recording as the next part of a named session.
+    #[arg(long, value_name = "NAME")]
+    session: Option<String>,
     #[arg(trailing_var_arg = true)]
     command: Vec<String>,
 }
 
+#[derive(Args, Debug, Default)]
+struct HookReceiverArgs {
+    /// Hook payload JSON; read from stdin when omitted (Codex `notify` passes it here).
+    #[arg(value_name = "JSON")]
+    payload: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct WatchlistArgs {
+    #[command(subcommand)]
+    command: WatchlistCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum WatchlistCommand {
+    /// Watch a repo-relative path glob, or an anchor pattern with `--anchor`.
+    Add {
+        pattern: String,
+        #[arg(long)]
+        anchor: bool,
+        /// Shell command run with each notification as JSON on stdin.
+        #[arg(long, value_name = "COMMAND")]
+        hook: Option<String>,
+    },
+    /// Stop watching a pattern.
+    Remove {
+        pattern: String,
+    },
+    List,
+}
+
+#[derive(Args, Debug)]
+struct CompletionsArgs {
+    shell: clap_complete::Shell,
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ManArgs {
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+}
+
+/// Export the causal graph. With `export.pseudonymize_key_env` set, tape,
+/// session, host and user ids in the output are pseudonymized; export is
+/// the only place that happens, so stores merged with `additional_stores`
+/// or `explain --also` are queried under their real ids.
+#[derive(Args, Debug)]
+struct ExportArgs {
+    /// Write a graph snapshot (anchors, edges and evidence references, in
+    /// the format documented in `engram::query::snapshot`) to this path.
+    #[arg(long, value_name = "PATH")]
+    graph: PathBuf,
+    /// `binary` is the snapshot format; `cypher` a script of CREATE
+    /// statements for Neo4j (`cypher-shell < out.cypher`).
+    #[arg(long, value_enum, default_value_t = GraphExportFormat::Binary)]
+    format: GraphExportFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphExportFormat {
+    Binary,
+    Cypher,
+}
+
+#[derive(Args, Debug)]
+struct TapesArgs {
+    #[arg(long)]
+    with_metrics: bool,
+    /// Only tapes that mention this issue key (see `issue_patterns`).
+    /// Repeatable; a tape mentioning any of them is listed.
+    #[arg(long, value_name = "KEY")]
+    issue: Vec<String>,
+    /// Only tapes with evidence in this `projects` entry. Repeatable; a tape
+    /// in any of them is listed.
+    #[arg(long, value_name = "NAME")]
+    project: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct StatsArgs {
+    /// Only tapes with evidence in this `projects` entry. Repeatable; a tape
+    /// in any of them is counted.
+    #[arg(long, value_name = "NAME")]
+    project: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct SessionsArgs {
+    /// Draw the graph (see `--format`) instead of printing it as JSON.
+    #[arg(long)]
+    graph: bool,
+    #[arg(long, value_enum, default_value_t = SessionGraphFormat::Dot)]
+    format: SessionGraphFormat,
+    /// Link two tapes for shared files only when they both edited at least
+    /// this many of the same files.
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    min_shared_files: usize,
+}
+
 #[derive(Args, Debug)]
 struct ShowArgs {
     tape_id: String,
-    #[arg(long)]
+    /// Second tape for `--diff`.
+    #[arg(value_name = "OTHER_TAPE_ID", requires = "diff")]
+    other_tape_id: Option<String>,
+    #[arg(long, conflicts_with = "diff")]
     raw: bool,
+    /// Report the raw harness file the tape was converted from.
+    #[arg(long, conflicts_with_all = ["raw", "diff"])]
+    origin: bool,
+    /// Render the tape's edits as unified diffs grouped by file, using
+    /// edit texts retained in the objects dir (`ingest.retain_edits`).
+    #[arg(long, conflicts_with_all = ["raw", "origin", "diff"])]
+    patch: bool,
+    /// Compare with OTHER_TAPE_ID: events present in only one tape, and
+    /// events aligned by call id or timestamp whose content changed.
+    #[arg(long, requires = "other_tape_id")]
+    diff: bool,
+    /// `markdown` renders the conversation for a chat or wiki post: tool
+    /// calls folded into `<details>` blocks, edits as diff fences.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ShowFormat::Json,
+        conflicts_with_all = ["raw", "origin", "patch", "diff"]
+    )]
+    format: ShowFormat,
+}
+
+#[derive(Args, Debug)]
+struct ReplayArgs {
+    tape_id: String,
+    /// Directory to apply the edits in, or a git ref to check out into a
+    /// fresh worktree under `.engram/replay/`.
+    #[arg(long, value_name = "DIR|REF")]
+    onto: String,
+    /// Stop after the edit at this tape offset, to reconstruct an
+    /// intermediate state.
+    #[arg(long, value_name = "OFFSET")]
+    through: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+struct AnnotateArgs {
+    /// `<file>:<start>-<end>`, a file, or an anchor with `--anchor`.
+    target: String,
+    #[arg(long)]
+    note: String,
+    #[arg(long)]
+    anchor: bool,
+}
+
+#[derive(Args, Debug)]
+struct ScanCommentsArgs {
+    /// Files or directories to scan; defaults to the working tree.
+    #[arg(value_name = "PATH")]
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ProvenanceArgs {
+    #[command(subcommand)]
+    command: ProvenanceCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ProvenanceCommand {
+    /// Report spans of the working tree that other engram stores saw first.
+    Scan {
+        /// Another store's `.engram` directory or index file. Repeatable.
+        #[arg(value_name = "OTHER_INDEX", required = true)]
+        stores: Vec<PathBuf>,
+        /// Files or directories to scan; defaults to the working tree.
+        #[arg(long = "path", value_name = "PATH")]
+        paths: Vec<PathBuf>,
+        /// Fraction of a span's fingerprint tokens the other store must hold.
+        #[arg(long, default_value_t = 0.6)]
+        min_overlap: f32,
+        /// Ignore blocks shorter than this many non-blank lines.
+        #[arg(long, default_value_t = 3)]
+        min_lines: usize,
+    },
+}
+
+#[derive(Args, Debug)]
+struct BisectArgs {
+    /// `<file>:<start>-<end>` that regressed.
+    target: String,
+    /// When the span was last known bad (RFC 3339 or `YYYY-MM-DD`).
+    #[arg(long)]
+    bad: String,
+    /// When the span was last known good.
+    #[arg(long)]
+    good: String,
+    #[arg(long, default_value_t = 0.5)]
+    min_confidence: f32,
+}
+
+#[derive(Args, Debug)]
+struct OriginArgs {
+    /// `<file>:<start>-<end>` to trace back.
+    target: String,
+    #[arg(long, default_value_t = 0.5)]
+    min_confidence: f32,
+}
+
+#[derive(Args, Debug)]
+struct WhyArgs {
+    /// `<file>:<line>` (or `<file>:<start>-<end>`); the enclosing function,
+    /// or the chunk around it, is explained.
+    target: String,
+    #[arg(long, default_value_t = 0.5)]
+    min_confidence: f32,
+    #[arg(long, value_enum, default_value_t = TimezoneArg::Local)]
+    timezone: TimezoneArg,
+    /// Print one stable tab-separated record instead of the sentence.
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "v1")]
+    porcelain: Option<PorcelainArg>,
+}
+
+#[derive(Args, Debug)]
+struct LensArgs {
+    /// File to annotate, relative to the current directory.
+    file: String,
+    /// Print stable tab-separated records instead of JSON.
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "v1")]
+    porcelain: Option<PorcelainArg>,
 }
 
 #[derive(Args, Debug)]
@@ -147,6 +686,22 @@ struct ExplainArgs {
     since: Option<String>,
     #[arg(long)]
     until: Option<String>,
+    /// Explain as of this time or git commit: only evidence and edges
+    /// recorded by then, with a file target read as it was at that commit
+    /// (for a time, the last commit before it).
+    #[arg(long, value_name = "TIME|COMMIT", conflicts_with = "until")]
+    at: Option<String>,
+    /// When a file target is not in the working tree (a sparse checkout or
+    /// partial clone), read it from `HEAD` in git instead.
+    #[arg(long, conflicts_with = "at")]
+    from_git: bool,
+    /// Read a file target as it is at this git revision instead of in the
+    /// working tree; unlike `--at`, the whole index is still searched.
+    #[arg(long, value_name = "REV", conflicts_with_all = ["at", "from_git"])]
+    rev: Option<String>,
+    /// Read a file target as it is staged in the git index.
+    #[arg(long, conflicts_with_all = ["at", "from_git", "rev"])]
+    staged: bool,
     #[arg(long)]
     count: bool,
     #[arg(long, default_value_t = 50, hide = true)]
@@ -161,6 +716,141 @@ struct ExplainArgs {
     forensics: bool,
     #[arg(long, hide = true)]
     pretty: bool,
+    /// With `--pretty`: `-v` adds lineage edges and paths, `-vv` adds
+    /// per-touch detail, tombstones and truncation.
+    #[arg(short, long, action = clap::ArgAction::Count, requires = "pretty")]
+    verbose: u8,
+    /// With `--pretty`: show timestamps in this zone, with relative times.
+    #[arg(long, value_enum, default_value_t = TimezoneArg::Local, requires = "pretty")]
+    timezone: TimezoneArg,
+    #[arg(long, conflicts_with_all = ["pretty", "count"])]
+    json_lines: bool,
+    #[arg(long)]
+    paths: bool,
+    #[arg(long, conflicts_with_all = ["pretty", "count", "json_lines"])]
+    summarize: bool,
+    /// `mermaid` prints a flowchart of the lineage chain and the sessions
+    /// that touched it instead of JSON.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ExplainFormat::Json,
+        conflicts_with_all = ["pretty", "count", "json_lines", "summarize"]
+    )]
+    format: ExplainFormat,
+    /// Another repository's `.engram` directory (or index file) to attach
+    /// read-only, so lineage can cross into it. Repeatable.
+    #[arg(long, value_name = "ENGRAM_DIR")]
+    also: Vec<PathBuf>,
+    /// Leave out sessions recorded by this harness (e.g. `gemini-cli`).
+    /// Repeatable.
+    #[arg(long, value_name = "HARNESS")]
+    exclude_harness: Vec<String>,
+    /// Leave out this tape (a tape id or unique prefix). Repeatable.
+    #[arg(long, value_name = "TAPE_ID")]
+    exclude_tape: Vec<String>,
+    /// Leave out evidence on files matching this glob or under this
+    /// directory. Repeatable.
+    #[arg(long, value_name = "GLOB")]
+    exclude_path: Vec<String>,
+    /// Keep only sessions whose tapes mention this issue key (see
+    /// `issue_patterns`). Repeatable; any of them matches.
+    #[arg(long, value_name = "KEY")]
+    issue: Vec<String>,
+    /// Keep only evidence ingest tagged with this `projects` entry.
+    /// Repeatable; any of them matches.
+    #[arg(long, value_name = "NAME")]
+    project: Vec<String>,
+    /// Take `<file>:<line>` (or a line range) and explain the
+    /// content-defined chunks around it instead of the exact lines. Same as
+    /// `--granularity hunk`.
+    #[arg(long, conflicts_with_all = ["anchor", "granularity"])]
+    auto_span: bool,
+    /// How much text around a `<file>:<line>` or `<file>:<start>-<end>`
+    /// target to fingerprint: the lines themselves, the chunks around them,
+    /// the enclosing function, or the whole file.
+    #[arg(long, value_enum, conflicts_with = "anchor")]
+    granularity: Option<ExplainGranularity>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TimezoneArg {
+    Local,
+    Utc,
+}
+
+impl From<TimezoneArg> for DisplayZone {
+    fn from(zone: TimezoneArg) -> Self {
+        match zone {
+            TimezoneArg::Local => DisplayZone::Local,
+            TimezoneArg::Utc => DisplayZone::Utc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PorcelainArg {
+    V1,
+}
+
+impl From<PorcelainArg> for PorcelainVersion {
+    fn from(version: PorcelainArg) -> Self {
+        match version {
+            PorcelainArg::V1 => PorcelainVersion::V1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ShowFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SessionGraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExplainFormat {
+    Json,
+    Mermaid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExplainGranularity {
+    Line,
+    Hunk,
+    Function,
+    File,
+}
+
+impl ExplainGranularity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Line => "line",
+            Self::Hunk => "hunk",
+            Self::Function => "function",
+            Self::File => "file",
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct SearchArgs {
+    query: String,
+    #[arg(long)]
+    semantic: bool,
+    #[arg(long)]
+    limit: Option<usize>,
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+    #[arg(long)]
+    since: Option<String>,
+    #[arg(long)]
+    until: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -178,6 +868,65 @@ struct GrepArgs {
     count: bool,
 }
 
+#[derive(Args, Debug)]
+struct EventsArgs {
+    /// Event kind (`tool.call`, `code.edit`, ...). Repeatable.
+    #[arg(long = "kind", value_name = "KIND")]
+    kinds: Vec<String>,
+    #[arg(long)]
+    tool: Option<String>,
+    /// Repo-relative file path or glob.
+    #[arg(long, value_name = "GLOB")]
+    file: Option<String>,
+    #[arg(long)]
+    since: Option<String>,
+    #[arg(long)]
+    until: Option<String>,
+    /// Only events whose raw JSON line contains this text.
+    #[arg(long, value_name = "TEXT")]
+    grep: Option<String>,
+    #[arg(long, default_value_t = 100)]
+    limit: usize,
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+}
+
+#[derive(Args, Debug)]
+struct ToolsArgs {
+    #[arg(long)]
+    since: Option<String>,
+    #[arg(long)]
+    until: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct PromptLogArgs {
+    #[arg(long)]
+    since: Option<String>,
+    #[arg(long)]
+    until: Option<String>,
+    /// Only prompts whose turn read or edited a matching repo-relative path.
+    #[arg(long, value_name = "GLOB")]
+    file: Option<String>,
+    #[arg(long, default_value_t = 100)]
+    limit: usize,
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+}
+
+#[derive(Args, Debug)]
+struct DriftArgs {
+    /// Repo-relative files, directories or globs to check (default: every
+    /// file with recorded edits).
+    paths: Vec<String>,
+    /// Only consider sessions at or after this date.
+    #[arg(long)]
+    since: Option<String>,
+    /// Also list edits that are still intact.
+    #[arg(long)]
+    all: bool,
+}
+
 #[derive(Args, Debug)]
 struct PeekArgs {
     session_id: String,
@@ -230,6 +979,8 @@ struct RepoPaths {
     tapes: PathBuf,
     objects: PathBuf,
     cursors: PathBuf,
+    inflight: PathBuf,
+    sessions: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -247,6 +998,30 @@ struct RuntimeContext {
     metrics_enabled: bool,
     metrics_log: PathBuf,
     watch: Option<EffectiveWatchConfig>,
+    watch_log: PathBuf,
+    summarize: Option<EffectiveSummarizeConfig>,
+    export: EffectiveExportConfig,
+    embeddings: Option<EffectiveEmbeddingsConfig>,
+    auto_maintain_after_ingests: u64,
+    partitioning: IndexPartitioning,
+    fast_ingest: bool,
+    retain_raw: bool,
+    retain_edits: bool,
+    snapshot_state: bool,
+    environment: Vec<EnvironmentField>,
+    normalize_line_endings: bool,
+    normalizers: Vec<String>,
+    link_threshold: f32,
+    /// `lineage.scorer`, checked against the scorer registry.
+    scorer: String,
+    fingerprint_cache: PathBuf,
+    sample_outputs: BTreeMap<String, OutputSampling>,
+    ingest_limits: IngestLimits,
+    binary_files: BinaryFilePolicy,
+    ignore: Vec<String>,
+    issue_patterns: Vec<String>,
+    projects: BTreeMap<String, Vec<String>>,
+    storage_quota: Option<StorageQuota>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -262,6 +1037,11 @@ struct IngestFileState {
     cursor_guard: IngestCursorGuard,
     adapter: String,
     tape_id: String,
+    /// The first bytes of the source, by which a rotated copy of it under
+    /// another path is recognized. Absent in states written before rotation
+    /// handling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    head: Option<IngestCursorGuard>,
 }
 
 #[derive(Debug, Clone)]
@@ -272,7 +1052,8 @@ struct TapeRow {
 
 fn main() -> ExitCode {
     match run() {
-        Ok(()) => ExitCode::SUCCESS,
+        Ok(code) => code,
+        Err(err) if err.code == "broken_pipe" => ExitCode::SUCCESS,
         Err(err) => {
             let payload = error_payload(&err);
             eprintln!("{payload}");
@@ -281,57 +1062,188 @@ fn main() -> ExitCode {
     }
 }
 
-fn run() -> Result<(), CliError> {
+fn run() -> Result<ExitCode, CliError> {
     if maybe_print_spec_help()? {
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
     let cwd = std::env::current_dir().map_err(|err| CliError::io("cwd_error", err))?;
-    let paths = repo_paths(&cwd)?;
-    match cli.command {
-        Command::Init => cmd_init(&paths),
+    let profile = cli
+        .profile
+        .or_else(|| std::env::var("ENGRAM_PROFILE").ok())
+        .filter(|name| !name.is_empty());
+    let started = Instant::now();
+    let result = run_command(&cwd, profile.as_deref(), cli.command);
+    append_audit(
+        &cwd,
+        profile.as_deref(),
+        matches.subcommand_name().unwrap_or_default(),
+        started.elapsed(),
+        &result,
+    );
+    result
+}
+
+fn run_command(cwd: &Path, profile: Option<&str>, command: Command) -> Result<ExitCode, CliError> {
+    let store_root = home_dir()
+        .map(|home| local_store_root(cwd, &home))
+        .unwrap_or_else(|_| cwd.to_path_buf());
+    let paths = repo_paths(&store_root)?;
+    let result = match command {
+        Command::Init(args) if args.interactive => cmd_init_interactive(&paths),
+        Command::Init(_) => cmd_init(&paths),
         Command::Ingest(args) => {
-            let context = resolve_runtime_context(&cwd)?;
-            cmd_ingest(&cwd, &paths, &context, args)
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_ingest(cwd, &paths, &context, args)
         }
-        Command::Watch(args) => cmd_watch(&cwd, args),
+        Command::Watch(args) => cmd_watch(cwd, profile, args),
+        Command::Sources(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_sources(&context, args)
+        }
+        Command::Discover(args) => cmd_discover(cwd, args),
         Command::Fingerprint => {
-            let context = resolve_runtime_context(&cwd)?;
+            let context = resolve_runtime_context(cwd, profile)?;
             cmd_fingerprint(&paths, &context)
         }
+        Command::Reindex => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_reindex(&paths, &context)
+        }
+        Command::Maintain => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_maintain(&paths, &context)
+        }
+        Command::Calibrate(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_calibrate(&paths, &context, args)
+        }
         Command::Record(args) => {
-            let context = resolve_runtime_context(&cwd)?;
-            cmd_record(&cwd, &paths, &context, args)
+            let context = resolve_runtime_context(cwd, profile)?;
+            return cmd_record(cwd, &paths, &context, args);
+        }
+        Command::HookReceiver(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_hook_receiver(cwd, &paths, &context, args)
+        }
+        Command::Watchlist(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_watchlist(&paths, &context, args)
+        }
+        Command::Annotate(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_annotate(cwd, &paths, &context, args)
+        }
+        Command::ScanComments(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_scan_comments(cwd, &paths, &context, args)
+        }
+        Command::Provenance(args) => cmd_provenance(cwd, args),
+        Command::Replay(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_replay(cwd, &paths, &context, args)
+        }
+        Command::Bisect(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_bisect(cwd, &paths, &context, args)
+        }
+        Command::Origin(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_origin(cwd, &paths, &context, args)
+        }
+        Command::Why(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_why(cwd, &paths, &context, args)
+        }
+        Command::Lens(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_lens(cwd, &paths, &context, args)
         }
         Command::Explain(args) => {
-            let context = resolve_runtime_context(&cwd)?;
-            cmd_explain(&cwd, &paths, &context, args)
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_explain(cwd, &paths, &context, args)
         }
         Command::Grep(args) => {
-            let context = resolve_runtime_context(&cwd)?;
+            let context = resolve_runtime_context(cwd, profile)?;
             cmd_grep(&paths, &context, args)
         }
+        Command::Events(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_events(&paths, &context, args)
+        }
+        Command::Tools(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_tools(&paths, &context, args)
+        }
+        Command::PromptLog(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_prompt_log(&paths, &context, args)
+        }
+        Command::Drift(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_drift(cwd, &paths, &context, args)
+        }
+        Command::Search(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_search(&paths, &context, args)
+        }
         Command::Peek(args) => {
-            let context = resolve_runtime_context(&cwd)?;
+            let context = resolve_runtime_context(cwd, profile)?;
             cmd_peek(&paths, &context, args)
         }
         Command::Rate(args) => {
-            let context = resolve_runtime_context(&cwd)?;
+            let context = resolve_runtime_context(cwd, profile)?;
             cmd_rate(&paths, &context, args)
         }
-        Command::Tapes => {
-            let context = resolve_runtime_context(&cwd)?;
-            cmd_tapes(&paths, &context)
+        Command::Tapes(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_tapes(&paths, &context, args)
+        }
+        Command::Sessions(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_sessions(&paths, &context, args)
         }
         Command::Show(args) => {
-            let context = resolve_runtime_context(&cwd)?;
+            let context = resolve_runtime_context(cwd, profile)?;
             cmd_show(&paths, &context, args)
         }
-        Command::Gc => {
-            let context = resolve_runtime_context(&cwd)?;
-            cmd_gc(&paths, &context)
+        Command::Stats(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_stats(&paths, &context, args)
         }
-    }
+        Command::Verify(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_verify(&paths, &context, args)
+        }
+        Command::Export(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_export(&paths, &context, args)
+        }
+        Command::Gc(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_gc(&paths, &context, args)
+        }
+        Command::Protect(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_protect(&paths, &context, args)
+        }
+        Command::Split(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_split(&paths, &context, args)
+        }
+        Command::Join(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_join(&paths, &context, args)
+        }
+        Command::Import(args) => {
+            let context = resolve_runtime_context(cwd, profile)?;
+            cmd_import(&paths, &context, args)
+        }
+        Command::Completions(args) => cmd_completions(args),
+        Command::Man(args) => cmd_man(args),
+    };
+    result.map(|()| ExitCode::SUCCESS)
 }
 
 fn error_payload(err: &CliError) -> Value {
@@ -366,7 +1278,10 @@ read them.
 
 COMMANDS:
   explain    Find provenance for code (by fingerprint)
+  why        One sentence on the last session that changed a line
+  lens       Per-function provenance for editor code lenses (JSON)
   grep       Find provenance for a term (by text search)
+  search     Find conceptually related sessions (--semantic)
   peek       Read content from a provenance session
   rate       Record whether a returned result was useful
   ingest     Import transcripts into the index
@@ -380,6 +1295,8 @@ const HELP_EXPLAIN: &str = r#"Find the conversations that produced this code.
 Returns the root of each provenance chain — the highest-level
 context explaining WHY this code exists. Results include chain
 metadata (children, depth) so you can walk down to HOW with peek.
+Sessions are grouped into episodes (same repo head or prompt topic,
+close in time) with a one-line summary each.
 Returns metadata only. Use peek <session_id> to read content.
 
 USAGE:
@@ -394,7 +1311,17 @@ OPTIONS:
   --min-confidence N        Only results above this match quality (0.0-1.0)
   --since <date>            Only sessions after this date
   --until <date>            Only sessions before this date
+  --at <date|commit>        Explain as the index and file stood then
+  --from-git                Read a file missing from a sparse checkout from HEAD
+  --rev <rev>               Read the file target as it is at this git revision
+  --staged                  Read the file target as it is staged in the git index
   --count                   Show counts only, no content (token budgeting)
+  --json-lines              Stream NDJSON records (query, edge, session, summary)
+  --paths                   Show lineage as best-confidence chains to each root
+  --summarize               Narrative of why, from the configured LLM (opt-in)
+  --exclude-harness <name>  Leave out sessions from this harness (repeatable)
+  --exclude-tape <id>       Leave out this tape (repeatable)
+  --exclude-path <glob>     Leave out evidence on matching files (repeatable)
 
 EXAMPLES:
   engram explain src/server.ts:40-78
@@ -496,25 +1423,111 @@ fn maybe_print_spec_help() -> Result<bool, CliError> {
     Ok(false)
 }
 
-fn cmd_init(paths: &RepoPaths) -> Result<(), CliError> {
-    let home = home_dir()?;
-    ensure_user_config(&home).map_err(|err| CliError::new("config_error", err.to_string()))?;
-    ensure_local_store(paths)?;
-    let local_tapes_dir = paths.root.join("tapes");
-    let context = RuntimeContext {
-        config_path: paths.root.join("config.yml"),
-        db_path: paths.root.join("index.sqlite"),
-        tapes_dir: local_tapes_dir.clone(),
-        tape_lookup_dirs: vec![local_tapes_dir, home.join(".engram").join("tapes")],
-        additional_stores: Vec::new(),
+fn cmd_completions(args: CompletionsArgs) -> Result<(), CliError> {
+    let mut command = Cli::command();
+    let Some(out_dir) = args.out_dir else {
+        clap_complete::generate(args.shell, &mut command, "engram", &mut io::stdout());
+        return Ok(());
+    };
+    fs::create_dir_all(&out_dir).map_err(|err| CliError::io("mkdir_error", err))?;
+    let path = clap_complete::generate_to(args.shell, &mut command, "engram", &out_dir)
+        .map_err(|err| CliError::io("write_error", err))?;
+    print_json(&json!({
+        "status": "ok",
+        "shell": args.shell.to_string(),
+        "path": path_string(&path),
+    }))
+}
+
+fn cmd_man(args: ManArgs) -> Result<(), CliError> {
+    let command = Cli::command();
+    let Some(out_dir) = args.out_dir else {
+        return clap_mangen::Man::new(command)
+            .render(&mut io::stdout())
+            .map_err(|err| CliError::io("write_error", err));
+    };
+    fs::create_dir_all(&out_dir).map_err(|err| CliError::io("mkdir_error", err))?;
+    let mut command = command.disable_help_subcommand(true);
+    command.build();
+    let mut pages = Vec::new();
+    write_man_pages(command, &out_dir, &mut pages)
+        .map_err(|err| CliError::io("write_error", err))?;
+    let mut pages = pages
+        .iter()
+        .map(|path| path_string(path))
+        .collect::<Vec<_>>();
+    pages.sort();
+    print_json(&json!({
+        "status": "ok",
+        "out_dir": path_string(&out_dir),
+        "pages": pages,
+    }))
+}
+
+/// `clap_mangen::generate_to`, collecting the pages it writes so files
+/// already in `out_dir` are not reported.
+fn write_man_pages(
+    command: clap::Command,
+    out_dir: &Path,
+    pages: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    for sub in command.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        write_man_pages(sub.clone(), out_dir, pages)?;
+    }
+    pages.push(clap_mangen::Man::new(command).generate_to(out_dir)?);
+    Ok(())
+}
+
+fn cmd_init(paths: &RepoPaths) -> Result<(), CliError> {
+    let home = home_dir()?;
+    ensure_user_config(&home).map_err(|err| CliError::new("config_error", err.to_string()))?;
+    ensure_local_store(paths)?;
+    let storage = paths
+        .root
+        .parent()
+        .and_then(|cwd| load_effective_config(cwd, &home).ok())
+        .map(|config| config.storage)
+        .unwrap_or_else(|| {
+            default_storage_dirs(StorageLayout::Legacy, &home, |key| std::env::var_os(key))
+        });
+    let local_tapes_dir = paths.root.join("tapes");
+    let context = RuntimeContext {
+        config_path: paths.root.join("config.yml"),
+        db_path: paths.root.join("index.sqlite"),
+        tapes_dir: local_tapes_dir.clone(),
+        tape_lookup_dirs: vec![local_tapes_dir, storage.data_dir.join("tapes")],
+        additional_stores: Vec::new(),
         explain_default_limit: 10,
         peek_default_lines: 40,
         peek_default_before: 30,
         peek_default_after: 10,
         peek_grep_context: 5,
         metrics_enabled: true,
-        metrics_log: home.join(".engram").join("metrics.jsonl"),
+        metrics_log: storage.data_dir.join("metrics.jsonl"),
         watch: None,
+        watch_log: storage.data_dir.join("watch.log"),
+        summarize: None,
+        export: EffectiveExportConfig::default(),
+        embeddings: None,
+        auto_maintain_after_ingests: DEFAULT_AUTO_MAINTAIN_AFTER_INGESTS,
+        partitioning: IndexPartitioning::None,
+        fast_ingest: false,
+        retain_raw: false,
+        retain_edits: false,
+        snapshot_state: false,
+        environment: Vec::new(),
+        normalize_line_endings: false,
+        normalizers: Vec::new(),
+        link_threshold: LINK_THRESHOLD_DEFAULT,
+        scorer: DEFAULT_SCORER.to_string(),
+        fingerprint_cache: storage.cache_dir.join(FINGERPRINT_CACHE_FILE),
+        sample_outputs: BTreeMap::new(),
+        ingest_limits: IngestLimits::default(),
+        binary_files: BinaryFilePolicy::default(),
+        ignore: Vec::new(),
+        issue_patterns: Vec::new(),
+        projects: BTreeMap::new(),
+        storage_quota: None,
     };
     print_context_conspicuity(&context);
     if context.config_path.exists() {
@@ -542,12 +1555,13 @@ fn cmd_rate(paths: &RepoPaths, context: &RuntimeContext, args: RateArgs) -> Resu
     print_context_conspicuity(context);
     ensure_db_parent(&context.db_path)?;
 
-    let index = SqliteIndex::open(&path_string(&context.db_path))?;
+    let index = open_index(context)?;
     if !index.query_result_exists(&args.result_id)? {
         return Err(CliError::new("unknown_result_id", args.result_id));
     }
 
     let rated_at = Utc::now().to_rfc3339();
+    let _lock = wait_for_store_lock(context, "rate")?;
     index.upsert_result_feedback(
         &args.result_id,
         args.outcome.as_str(),
@@ -565,18 +1579,590 @@ fn cmd_rate(paths: &RepoPaths, context: &RuntimeContext, args: RateArgs) -> Resu
     }))
 }
 
+/// Globs `init --interactive` offers to add to `ignore`, so reads and edits
+/// of credential files never reach a tape.
+const SECRET_FILE_GLOBS: &[&str] = &[".env", ".env.*", "*.pem", "*.key", "id_rsa", "id_ed25519"];
+
+/// First-run setup: discovery, source selection, storage, retention and
+/// redaction, asked on stderr and answered on stdin. End of input takes the
+/// default of every remaining question.
+fn cmd_init_interactive(paths: &RepoPaths) -> Result<(), CliError> {
+    let home = home_dir()?;
+    let user_config =
+        ensure_user_config(&home).map_err(|err| CliError::new("config_error", err.to_string()))?;
+    ensure_local_store(paths)?;
+    let mut input = io::stdin().lock();
+
+    let (_, found) = discover_harnesses(&home);
+    if found.is_empty() {
+        eprintln!("No harness sessions found; `engram discover --add` adds them later.");
+    }
+    let mut sources = Vec::new();
+    for found in found {
+        let question = format!(
+            "Watch {} sessions in {} ({} files)?",
+            found.adapter.as_str(),
+            path_string(&found.source.path),
+            found.files
+        );
+        if prompt_yes_no(&mut input, &question, true)? {
+            sources.push(found.source);
+        }
+    }
+    let storage = prompt_choice(
+        &mut input,
+        "Keep the index and tapes in this repo or globally in ~/.engram?",
+        &["repo", "global"],
+    )?;
+    let retain_raw = prompt_yes_no(
+        &mut input,
+        "Keep a compressed copy of every raw harness session (ingest.retain_raw)?",
+        false,
+    )?;
+    let retain_edits = prompt_yes_no(
+        &mut input,
+        "Keep the full text of every edit (ingest.retain_edits)?",
+        false,
+    )?;
+    let redact = prompt_yes_no(
+        &mut input,
+        &format!(
+            "Drop reads and edits of secret files ({}) from tapes?",
+            SECRET_FILE_GLOBS.join(", ")
+        ),
+        true,
+    )?;
+
+    let local_config = paths.root.join("config.yml");
+    let config_path = if storage == "repo" {
+        local_config.clone()
+    } else {
+        user_config.clone()
+    };
+    let created = !config_path.exists();
+    let mut values = vec![
+        ("ingest.retain_raw", retain_raw.into()),
+        ("ingest.retain_edits", retain_edits.into()),
+    ];
+    if storage == "repo" {
+        values.push(("db", ".engram/index.sqlite".into()));
+        values.push(("tapes_dir", ".engram/tapes".into()));
+    }
+    // The nearest config that sets `ignore` wins, so the globs already in
+    // effect are kept alongside the new ones.
+    let mut ignore = paths
+        .root
+        .parent()
+        .and_then(|cwd| load_effective_config(cwd, &home).ok())
+        .map(|config| config.ignore)
+        .unwrap_or_default();
+    if redact {
+        for glob in SECRET_FILE_GLOBS {
+            if !ignore.iter().any(|existing| existing == glob) {
+                ignore.push((*glob).to_string());
+            }
+        }
+        values.push(("ignore", ignore.clone().into()));
+    }
+    set_config_values(&config_path, &values)
+        .map_err(|err| CliError::new("config_error", err.to_string()))?;
+    // `watch` runs from the user config, whichever store the repo uses.
+    let added = append_watch_sources(&user_config, &home, &sources)
+        .map_err(|err| CliError::new("config_error", err.to_string()))?;
+
+    let mut payload = json!({
+        "status": "ok",
+        "created": created,
+        "config": path_string(&config_path),
+        "storage": storage,
+        "watch_config": path_string(&user_config),
+        "sources_added": watch_sources_json(&added),
+        "retain_raw": retain_raw,
+        "retain_edits": retain_edits,
+        "ignore": ignore,
+    });
+    if storage == "global" && local_config.exists() {
+        payload["shadowed_by"] = json!(path_string(&local_config));
+    }
+    print_json(&payload)
+}
+
+/// One answer from `input`, or `None` at end of input or on an empty line.
+fn prompt_answer(input: &mut impl io::BufRead, question: &str) -> Result<Option<String>, CliError> {
+    eprint!("{question} ");
+    let _ = io::stderr().flush();
+    let mut line = String::new();
+    let read = input
+        .read_line(&mut line)
+        .map_err(|err| CliError::io("read_error", err))?;
+    if read == 0 {
+        eprintln!();
+    }
+    let answer = line.trim();
+    Ok((!answer.is_empty()).then(|| answer.to_ascii_lowercase()))
+}
+
+fn prompt_yes_no(
+    input: &mut impl io::BufRead,
+    question: &str,
+    default: bool,
+) -> Result<bool, CliError> {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    loop {
+        match prompt_answer(input, &format!("{question} {hint}"))?.as_deref() {
+            None => return Ok(default),
+            Some("y" | "yes") => return Ok(true),
+            Some("n" | "no") => return Ok(false),
+            Some(_) => eprintln!("Answer y or n."),
+        }
+    }
+}
+
+/// One of `choices`; the first is the default.
+fn prompt_choice(
+    input: &mut impl io::BufRead,
+    question: &str,
+    choices: &[&'static str],
+) -> Result<&'static str, CliError> {
+    let hint = format!("[{}]", choices.join("/"));
+    loop {
+        let Some(answer) = prompt_answer(input, &format!("{question} {hint}"))? else {
+            return Ok(choices[0]);
+        };
+        if let Some(choice) = choices.iter().find(|choice| choice.starts_with(&answer)) {
+            return Ok(choice);
+        }
+        eprintln!("Answer one of {}.", choices.join(", "));
+    }
+}
+
+/// Rebuild the index from stored tapes into a fresh file and swap it in.
+/// Tapes replay oldest first so cross-tape linking sees them in the order
+/// they were originally ingested; recorded query results and ratings are
+/// carried over from the old index when it is still readable.
+fn cmd_reindex(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let _lock = lock_store(context, "reindex")?;
+
+    let mut tapes = Vec::new();
+    let mut seen = HashSet::new();
+    let mut failures = Vec::new();
+    // Tapes cut up by `split` or stitched by `join` stay out of the index;
+    // their parts and joined tapes stand in.
+    let mut split_parents = HashSet::new();
+    let mut stitched = HashSet::new();
+    // Tapes still named by the hash of their bytes move to canonical ids.
+    let mut migrated = Vec::new();
+    let mut id_conflicts = Vec::new();
+    let mut aliases = TapeIdAliases::default();
+    for dir in [&context.tapes_dir, &paths.tapes] {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let aliases_path = tape_id_aliases_path(dir);
+        let mut dir_aliases = load_tape_id_aliases(&aliases_path)
+            .map_err(|err| CliError::new("tape_id_aliases_error", err.to_string()))?;
+        let migrated_before = migrated.len();
+        for entry in entries {
+            let entry = entry.map_err(|err| CliError::io("read_dir_error", err))?;
+            let path = entry.path();
+            let Some(tape_id) = tape_id_from_path(&path) else {
+                continue;
+            };
+            if !path.is_file() || seen.contains(&tape_id) {
+                continue;
+            }
+            match read_tape_content(&path) {
+                Ok(content) => {
+                    let (tape_id, path) = match migrate_tape_id(dir, &tape_id, &path, &content)? {
+                        TapeIdMigration::Moved(new_tape_id, new_path) => {
+                            dir_aliases.insert(tape_id.clone(), new_tape_id.clone());
+                            migrated.push(json!({"tape_id": tape_id, "new_tape_id": new_tape_id}));
+                            (new_tape_id, new_path)
+                        }
+                        TapeIdMigration::Conflict(new_tape_id) => {
+                            id_conflicts.push(json!({
+                                "tape_id": tape_id,
+                                "new_tape_id": new_tape_id,
+                                "path": path,
+                            }));
+                            (tape_id, path)
+                        }
+                        TapeIdMigration::Unchanged => (tape_id, path),
+                    };
+                    if !seen.insert(tape_id.clone()) {
+                        continue;
+                    }
+                    let started_at = content
+                        .lines()
+                        .find_map(|line| serde_json::from_str::<Value>(line).ok())
+                        .and_then(|row| row.get("t").and_then(Value::as_str).map(str::to_owned))
+                        .unwrap_or_default();
+                    if let Some(split) = tape_split(&content) {
+                        split_parents.insert(split.parent_tape_id);
+                    }
+                    stitched.extend(tape_join_members(&content));
+                    tapes.push((started_at, tape_id, path));
+                }
+                Err(err) => failures.push(json!({"path": path, "error": err.message})),
+            }
+        }
+        if migrated.len() > migrated_before {
+            save_tape_id_aliases(&aliases_path, &dir_aliases)
+                .map_err(|err| CliError::new("tape_id_aliases_error", err.to_string()))?;
+        }
+        for (old, new) in dir_aliases.aliases {
+            aliases.insert(old, new);
+        }
+    }
+    if !migrated.is_empty() {
+        follow_tape_id_aliases(paths, &aliases)?;
+    }
+    let current = |tape_id: String| match aliases.resolve(&tape_id) {
+        Some(new) => new.to_string(),
+        None => tape_id,
+    };
+    let split_parents = split_parents
+        .into_iter()
+        .map(current)
+        .collect::<HashSet<_>>();
+    let stitched = stitched.into_iter().map(current).collect::<HashSet<_>>();
+    let scanned = tapes.len();
+    tapes.retain(|(_, tape_id, _)| !split_parents.contains(tape_id));
+    let split_skipped = scanned - tapes.len();
+    tapes.retain(|(_, tape_id, _)| !stitched.contains(tape_id));
+    tapes.sort();
+
+    let fresh_path = context
+        .db_path
+        .with_extension(format!("reindex-{}.tmp", std::process::id()));
+    remove_sqlite_files(&fresh_path, true)?;
+    let index = open_index_at(context, &fresh_path)?;
+    // The fresh file only goes live after the rename below, so a crash here
+    // loses nothing and the load can skip per-commit fsyncs.
+    let bulk = index.bulk_load()?;
+    let mut reindexed = 0usize;
+    let mut partial_ingest = Vec::new();
+    for (_, tape_id, path) in &tapes {
+        let content = normalize_tape_evidence(context, read_tape_content(path)?)?;
+        let events = match parse_jsonl_events(&content) {
+            Ok(value) => value,
+            Err(err) => {
+                failures.push(json!({"path": path, "error": err.to_string()}));
+                continue;
+            }
+        };
+        let dispatch_links = extract_dispatch_links_from_transcript(&content);
+        let diagnostics = index.ingest_tape_events_limited(
+            tape_id,
+            &events,
+            &dispatch_links,
+            context.link_threshold,
+            context.ingest_limits,
+        )?;
+        if diagnostics.is_partial() {
+            partial_ingest.push(partial_ingest_json(tape_id, path, &diagnostics));
+        }
+        index.record_tape_metrics(tape_id, &tape_metrics_from_jsonl(&content))?;
+        record_tape_events(&index, tape_id, &content, context)?;
+        if let Err(err) = embed_tape_events(&index, context.embeddings.as_ref(), tape_id, &content)
+        {
+            failures.push(json!({"path": path, "error": err.message}));
+        }
+        reindexed += 1;
+    }
+    bulk.finish()?;
+
+    let (preserved_results, preserved_feedback, feedback_error) = if context.db_path.exists() {
+        match index.import_query_feedback(&path_string(&context.db_path)) {
+            Ok((results, feedback)) => (results, feedback, None),
+            Err(err) => (0, 0, Some(err.to_string())),
+        }
+    } else {
+        (0, 0, None)
+    };
+    let (preserved_annotations, preserved_origins, preserved_aliases) =
+        if feedback_error.is_none() && context.db_path.exists() {
+            (
+                index.import_annotations(&path_string(&context.db_path))?,
+                index.import_tape_origins(&path_string(&context.db_path))?,
+                index.import_anchor_aliases(&path_string(&context.db_path))?,
+            )
+        } else {
+            (0, 0, 0)
+        };
+    // Partitions are rebuilt from the tapes too, replacing whatever was there.
+    match fs::remove_dir_all(partition_dir(&context.db_path)) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(CliError::io("remove_dir_error", err)),
+    }
+    let partitions = roll_into_partitions(&index, context)?;
+    drop(index);
+
+    remove_sqlite_files(&context.db_path, false)?;
+    fs::rename(&fresh_path, &context.db_path).map_err(|err| CliError::io("rename_error", err))?;
+
+    let mut payload = json!({
+        "status": ingest_status(&failures, &partial_ingest),
+        "db": context.db_path,
+        "scanned_tapes": scanned,
+        "reindexed_tapes": reindexed,
+        "migrated_tape_ids": migrated,
+        "tape_id_conflicts": id_conflicts,
+        "split_parents_skipped": split_skipped,
+        "joined_members_skipped": scanned - split_skipped - tapes.len(),
+        "preserved_query_results": preserved_results,
+        "preserved_feedback": preserved_feedback,
+        "preserved_annotations": preserved_annotations,
+        "preserved_conversion_times": preserved_origins,
+        "preserved_anchor_aliases": preserved_aliases,
+        "failure_count": failures.len(),
+        "failures": failures,
+    });
+    if !partial_ingest.is_empty() {
+        payload["partial_ingest"] = json!(partial_ingest);
+    }
+    if let Some(message) = feedback_error {
+        payload["feedback_error"] = json!(message);
+    }
+    if let Some(partitions) = partitions {
+        payload["partitions"] = partitions;
+    }
+    print_json(&payload)
+}
+
+/// What [`migrate_tape_id`] did with a tape.
+enum TapeIdMigration {
+    /// The tape already has its canonical id (or is not content-named).
+    Unchanged,
+    /// The tape now lives at its canonical id and path; a copy already
+    /// there with the same events was kept instead of it.
+    Moved(String, PathBuf),
+    /// Another file with different events sits at the canonical path. Both
+    /// are left in place and the tape keeps its old id.
+    Conflict(String),
+}
+
+/// Move a tape named by the hash of its bytes to its canonical id.
+fn migrate_tape_id(
+    dir: &Path,
+    tape_id: &str,
+    path: &Path,
+    content: &str,
+) -> Result<TapeIdMigration, CliError> {
+    if !is_content_tape_id(tape_id) {
+        return Ok(TapeIdMigration::Unchanged);
+    }
+    let new_tape_id = tape_id_for_contents(content);
+    if new_tape_id == tape_id {
+        return Ok(TapeIdMigration::Unchanged);
+    }
+    let new_path = tape_path_for_tapes_dir(dir, &new_tape_id);
+    if !new_path.exists() {
+        fs::rename(path, &new_path).map_err(|err| CliError::io("rename_error", err))?;
+        return Ok(TapeIdMigration::Moved(new_tape_id, new_path));
+    }
+    let same_events = read_tape_content(&new_path)
+        .is_ok_and(|existing| canonical_jsonl(&existing) == canonical_jsonl(content));
+    if !same_events {
+        return Ok(TapeIdMigration::Conflict(new_tape_id));
+    }
+    fs::remove_file(path).map_err(|err| CliError::io("remove_error", err))?;
+    Ok(TapeIdMigration::Moved(new_tape_id, new_path))
+}
+
+/// Point protected tapes, session manifests and ingest cursors at the ids
+/// their tapes were migrated to.
+fn follow_tape_id_aliases(paths: &RepoPaths, aliases: &TapeIdAliases) -> Result<(), CliError> {
+    let protected_path = protected_tapes_path(&paths.root);
+    let mut protected = load_protected_tapes(&protected_path)
+        .map_err(|err| CliError::new("protected_tapes_error", err.to_string()))?;
+    let mut changed = false;
+    for entry in &mut protected.entries {
+        if let Some(new) = aliases.resolve(&entry.tape_id) {
+            entry.tape_id = new.to_string();
+            changed = true;
+        }
+    }
+    if changed {
+        save_protected_tapes(&protected_path, &protected)
+            .map_err(|err| CliError::new("protected_tapes_error", err.to_string()))?;
+    }
+
+    for mut session in list_record_sessions(&paths.sessions)
+        .map_err(|err| CliError::new("session_read_error", err.to_string()))?
+    {
+        let mut changed = false;
+        for part in &mut session.parts {
+            if let Some(new) = aliases.resolve(&part.tape_id) {
+                part.tape_id = new.to_string();
+                changed = true;
+            }
+        }
+        if changed {
+            save_record_session(&paths.sessions, &session)
+                .map_err(|err| CliError::new("session_write_error", err.to_string()))?;
+        }
+    }
+
+    let Ok(entries) = fs::read_dir(&paths.cursors) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let path = entry
+            .map_err(|err| CliError::io("read_dir_error", err))?
+            .path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(mut state) = serde_json::from_str::<IngestFileState>(&content) else {
+            continue;
+        };
+        if let Some(new) = aliases.resolve(&state.tape_id) {
+            state.tape_id = new.to_string();
+            let content = serde_json::to_string_pretty(&state)
+                .map_err(|err| CliError::new("cursor_state_error", err.to_string()))?;
+            atomic_write(&path, content.as_bytes())
+                .map_err(|err| CliError::io("write_error", err))?;
+        }
+    }
+    Ok(())
+}
+
+/// Catalog a tape's events for `engram events`, up to `max_events`, note
+/// the anchor version its evidence was fingerprinted with, and link it to
+/// the tapes it started or was started by (see [`engram::tape::spawn`]).
+fn record_tape_events(
+    index: &SqliteIndex,
+    tape_id: &str,
+    content: &str,
+    context: &RuntimeContext,
+) -> Result<(), CliError> {
+    let mut records = event_records_from_jsonl(content);
+    records.truncate(context.ingest_limits.max_events.unwrap_or(usize::MAX));
+    index.record_tape_events(tape_id, &records)?;
+    index.set_tape_anchor_version(tape_id, anchor_version(context.normalize_line_endings))?;
+    index.record_spawn_calls(
+        tape_id,
+        tape_cwd(content).as_deref(),
+        &spawn_calls_from_jsonl(content),
+    )?;
+    index.record_issue_refs(
+        tape_id,
+        &issue_refs_from_jsonl(content, &issue_patterns(context)?),
+    )?;
+    index.record_tape_split(tape_id, tape_split(content).as_ref())?;
+    index.record_tape_join(tape_id, &tape_join_members(content))?;
+    Ok(())
+}
+
+/// Normalize the evidence text of a tape when `anchors.normalize_line_endings`
+/// is on, so CRLF and BOM-prefixed edits fingerprint like their LF source,
+/// and stamp the `anchors.normalizers` that apply to each event and the git
+/// worktree and submodule each event's file belongs to.
+fn normalize_tape_evidence(context: &RuntimeContext, jsonl: String) -> Result<String, CliError> {
+    let (jsonl, _) = normalize_notebook_evidence(&jsonl)
+        .map_err(|err| CliError::new("normalize_error", err.to_string()))?;
+    let (jsonl, _) = stamp_span_normalizers(&jsonl, &context.normalizers)
+        .map_err(|err| CliError::new("normalize_error", err.to_string()))?;
+    let (jsonl, _) = stamp_repo_scopes(&jsonl)
+        .map_err(|err| CliError::new("normalize_error", err.to_string()))?;
+    let (jsonl, _) = stamp_projects(&jsonl, &project_rules(context)?)
+        .map_err(|err| CliError::new("normalize_error", err.to_string()))?;
+    if !context.normalize_line_endings {
+        return Ok(jsonl);
+    }
+    let (normalized, _) = normalize_evidence_text(&jsonl)
+        .map_err(|err| CliError::new("normalize_error", err.to_string()))?;
+    Ok(normalized)
+}
+
+/// Current file text as evidence anchors see it: notebooks as their cell
+/// view (see [`notebook_view`]).
+fn evidence_view(context: &RuntimeContext, file: &Path, text: String) -> String {
+    let text = if is_notebook_path(&file.to_string_lossy()) {
+        notebook_view(&text).unwrap_or(text)
+    } else {
+        text
+    };
+    if context.normalize_line_endings {
+        normalize_line_endings(&text).into_owned()
+    } else {
+        text
+    }
+}
+
+/// `partial` when some inputs failed, `partial_ingest` when every input
+/// was read but `ingest.limits` kept part of a tape out of the index.
+fn ingest_status(failures: &[Value], partial_ingest: &[Value]) -> &'static str {
+    if !failures.is_empty() {
+        "partial"
+    } else if !partial_ingest.is_empty() {
+        "partial_ingest"
+    } else {
+        "ok"
+    }
+}
+
+/// What `ingest.limits` dropped from one tape.
+fn partial_ingest_json(tape_id: &str, path: &Path, diagnostics: &IngestDiagnostics) -> Value {
+    json!({
+        "tape_id": tape_id,
+        "path": path_string(path),
+        "events_total": diagnostics.events_total,
+        "events_indexed": diagnostics.events_indexed,
+        "evidence_rows": diagnostics.evidence_rows,
+        "evidence_rows_dropped": diagnostics.evidence_rows_dropped,
+        "edges": diagnostics.edges,
+        "edges_dropped": diagnostics.edges_dropped,
+    })
+}
+
+/// Remove a SQLite database's WAL and shared-memory files (and the database
+/// itself with `include_db`), so a file renamed into its place is not paired
+/// with a stale log.
+fn remove_sqlite_files(db_path: &Path, include_db: bool) -> Result<(), CliError> {
+    let db = db_path.as_os_str();
+    let suffixes: &[&str] = if include_db {
+        &["-wal", "-shm", ""]
+    } else {
+        &["-wal", "-shm"]
+    };
+    for suffix in suffixes {
+        let mut name = db.to_os_string();
+        name.push(suffix);
+        match fs::remove_file(PathBuf::from(name)) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(CliError::io("remove_file_error", err)),
+        }
+    }
+    Ok(())
+}
+
 fn cmd_record(
     cwd: &Path,
     paths: &RepoPaths,
     context: &RuntimeContext,
     args: RecordArgs,
-) -> Result<(), CliError> {
+) -> Result<ExitCode, CliError> {
     if args.stdin && !args.command.is_empty() {
         return Err(CliError::new(
             "invalid_record_args",
             "use either `engram record --stdin` or `engram record <command>`",
         ));
     }
+    if let Some(name) = args.session.as_deref()
+        && !valid_session_name(name)
+    {
+        return Err(CliError::new(
+            "invalid_session_name",
+            format!("session name `{name}` may only contain letters, digits, `.`, `_` and `-`"),
+        ));
+    }
 
     ensure_local_store(paths)?;
     print_context_conspicuity(context);
@@ -585,13 +2171,16 @@ fn cmd_record(
         io::stdin()
             .read_to_string(&mut stdin_buf)
             .map_err(|err| CliError::io("stdin_error", err))?;
-        return record_transcript(
+        let _lock = wait_for_store_lock(context, "record")?;
+        record_transcript(
             paths,
-            &context.db_path,
+            context,
             &stdin_buf,
             json!({ "mode": "stdin" }),
             None,
-        );
+            args.session.as_deref(),
+        )?;
+        return Ok(ExitCode::SUCCESS);
     }
 
     if args.command.is_empty() {
@@ -601,25 +2190,160 @@ fn cmd_record(
         ));
     }
 
-    let transcript = capture_command_tape(cwd, &args.command)?;
+    let limits = CaptureLimits {
+        timeout: args.timeout.map(Duration::from_secs),
+        max_output_bytes: args.max_output_bytes,
+    };
+    let transcript = capture_command_tape(cwd, &args.command, limits)?;
+    let _lock = wait_for_store_lock(context, "record")?;
     record_transcript(
         paths,
-        &context.db_path,
+        context,
         &transcript.raw_jsonl,
         json!({
             "mode": "command",
             "command": args.command,
             "exit_code": transcript.exit_code,
             "success": transcript.success,
+            "timed_out": transcript.timed_out,
         }),
         Some(json!({
             "argv": transcript.argv,
             "exit": transcript.exit_code,
             "success": transcript.success,
+            "timed_out": transcript.timed_out,
             "stdout_bytes": transcript.stdout_bytes,
             "stderr_bytes": transcript.stderr_bytes,
+            "stdout_truncated": transcript.stdout_truncated,
+            "stderr_truncated": transcript.stderr_truncated,
         })),
-    )
+        args.session.as_deref(),
+    )?;
+    if args.no_propagate {
+        return Ok(ExitCode::SUCCESS);
+    }
+    Ok(recorded_exit_code(&transcript))
+}
+
+/// Engram's own exit status for a recorded command: the child's code, 124
+/// after `--timeout` (as timeout(1) does), 128+N for a signal, else 1.
+fn recorded_exit_code(transcript: &CapturedCommandTape) -> ExitCode {
+    if transcript.timed_out {
+        return ExitCode::from(124);
+    }
+    if let Some(signal) = transcript.signal {
+        return ExitCode::from(128u8.saturating_add(u8::try_from(signal).unwrap_or(0)));
+    }
+    match u8::try_from(transcript.exit_code) {
+        Ok(code) => ExitCode::from(code),
+        Err(_) => ExitCode::FAILURE,
+    }
+}
+
+fn cmd_hook_receiver(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: HookReceiverArgs,
+) -> Result<(), CliError> {
+    let raw = match args.payload {
+        Some(payload) => payload,
+        None => {
+            let mut stdin_buf = String::new();
+            io::stdin()
+                .read_to_string(&mut stdin_buf)
+                .map_err(|err| CliError::io("stdin_error", err))?;
+            stdin_buf
+        }
+    };
+    let payload: Value = serde_json::from_str(raw.trim())
+        .map_err(|err| CliError::new("invalid_hook_payload", err.to_string()))?;
+    let now = now_iso8601();
+    let hook = hook_payload_to_events(&payload, &now)
+        .map_err(|err| CliError::new("invalid_hook_payload", err.to_string()))?;
+
+    ensure_local_store(paths)?;
+    fs::create_dir_all(&paths.inflight).map_err(|err| CliError::io("mkdir_error", err))?;
+    let inflight_path = paths.inflight.join(format!(
+        "{}-{}.jsonl",
+        hook.harness,
+        inflight_file_stem(&hook.session_id)
+    ));
+    // Concurrent hooks of one session queue here, so only the first writes
+    // the meta line and a flush never drops an append.
+    let _lock = if hook.events.is_empty() && !hook.flush {
+        None
+    } else {
+        Some(wait_for_store_lock(context, "hook-receiver")?)
+    };
+    if !hook.events.is_empty() {
+        let mut lines = String::new();
+        let mut file = match OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(&inflight_path)
+        {
+            Ok(file) => {
+                let mut meta =
+                    hook_tape_meta(hook.harness, &hook.session_id, hook.cwd.as_deref(), &now);
+                if let Some(head) = git_head(cwd) {
+                    meta["repo_head"] = json!(head);
+                }
+                lines.push_str(&meta.to_string());
+                lines.push('\n');
+                file
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => OpenOptions::new()
+                .append(true)
+                .open(&inflight_path)
+                .map_err(|err| CliError::io("write_error", err))?,
+            Err(err) => return Err(CliError::io("write_error", err)),
+        };
+        for event in &hook.events {
+            lines.push_str(&event.to_string());
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())
+            .map_err(|err| CliError::io("write_error", err))?;
+    }
+
+    let mut tape = Value::Null;
+    if hook.flush && inflight_path.exists() {
+        let transcript =
+            fs::read_to_string(&inflight_path).map_err(|err| CliError::io("read_error", err))?;
+        if transcript.lines().count() > 1 {
+            let transcript = stamp_environment(
+                &transcript,
+                &TapeEnvironment::capture(&context.environment, None),
+            )?;
+            tape = Value::Object(store_transcript(paths, context, &transcript)?);
+        }
+        fs::remove_file(&inflight_path).map_err(|err| CliError::io("remove_error", err))?;
+    }
+
+    print_json(&json!({
+        "status": "ok",
+        "harness": hook.harness,
+        "session_id": hook.session_id,
+        "appended": hook.events.len(),
+        "flushed": hook.flush,
+        "inflight": if hook.flush { Value::Null } else { json!(inflight_path) },
+        "tape": tape,
+    }))
+}
+
+/// Session ids come from the harness; keep only characters safe in a file name.
+fn inflight_file_stem(session_id: &str) -> String {
+    session_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
 }
 
 fn cmd_ingest(
@@ -631,6 +2355,12 @@ fn cmd_ingest(
     ensure_local_store(paths)?;
     print_context_conspicuity(context);
     fs::create_dir_all(&context.tapes_dir).map_err(|err| CliError::io("mkdir_error", err))?;
+    ensure_db_parent(&context.db_path)?;
+    let _lock = lock_store(context, "ingest")?;
+    if args.reconvert {
+        return reconvert_tapes(paths, context);
+    }
+    let ignore_rules = ignore_rules(context)?;
     let (mut candidates, mut failures) = discover_ingest_candidates(cwd, &args.paths)?;
     let home = home_dir()?;
     if args.paths.is_empty() {
@@ -644,13 +2374,37 @@ fn cmd_ingest(
     candidates.sort();
     candidates.dedup();
     ensure_db_parent(&context.db_path)?;
-    let index = SqliteIndex::open(&path_string(&context.db_path))?;
+    let index = open_index(context)?;
+    let fast_ingest = args.fast_ingest || context.fast_ingest;
+    let bulk = if fast_ingest {
+        Some(index.bulk_load()?)
+    } else {
+        None
+    };
 
     let mut scanned = 0usize;
     let mut imported = 0usize;
     let mut skipped_unchanged = 0usize;
     let mut skipped_existing_tape = 0usize;
     let mut skipped_non_transcript = 0usize;
+    let mut skipped_lines = 0usize;
+    let mut skipped_input = Vec::new();
+    let mut retained_raw = 0usize;
+    let mut sampled_fields = 0usize;
+    let mut ignored_events = 0usize;
+    let mut binary_counts = BinaryCounts::default();
+    let mut partial_ingest = Vec::new();
+    let mut retained_edits = 0usize;
+    let mut state_snapshots = 0usize;
+    let mut watchlist_hits = Vec::new();
+    let mut rotated_sources = Vec::new();
+    // Snapshot cursor heads before this run overwrites any state, so a new
+    // file under a rotated path does not hide the state its old copy needs.
+    let mut states_by_head = candidates
+        .iter()
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .any(|abs_path| !cursor_state_path(paths, &abs_path).exists())
+        .then(|| cursor_states_by_head(paths));
 
     for path in candidates {
         scanned += 1;
@@ -675,42 +2429,90 @@ fn cmd_ingest(
             }
         };
 
-        let prior_state = match load_ingest_state_for_path(paths, &abs_path) {
+        let unpacked = match unpacked_source(&abs_path) {
             Ok(value) => value,
             Err(err) => {
                 failures.push(json!({
                     "path": path_string(&abs_path),
-                    "error": err.message,
+                    "error": err.to_string(),
                 }));
                 continue;
             }
         };
+        let source_len = unpacked
+            .as_ref()
+            .map_or(metadata.len(), |bytes| bytes.len() as u64);
 
-        let mut should_run_full = prior_state.is_none();
-        let mut full_reason = None::<&str>;
-        if let Some(prev) = prior_state.as_ref() {
-            let prior_tape_path = tape_path_for_tapes_dir(&context.tapes_dir, &prev.tape_id);
-            let prior_tape_missing = !prior_tape_path.exists();
-            let prior_tape_unindexed = !index.has_tape(&prev.tape_id)?;
-            if prior_tape_missing || prior_tape_unindexed {
+        let mut prior_state = match load_ingest_state_for_path(paths, &abs_path) {
+            Ok(value) => value,
+            Err(err) => {
+                failures.push(json!({
+                    "path": path_string(&abs_path),
+                    "error": err.message,
+                }));
+                continue;
+            }
+        };
+        let mut rotated = false;
+        if prior_state.is_none()
+            && let Some(by_head) = states_by_head.as_ref()
+        {
+            match rotated_cursor_state(by_head, &abs_path, unpacked.as_deref(), source_len) {
+                Ok(Some(state)) => {
+                    rotated = true;
+                    rotated_sources.push(path_string(&abs_path));
+                    prior_state = Some(state);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    failures.push(json!({
+                        "path": path_string(&abs_path),
+                        "error": err.message,
+                    }));
+                    continue;
+                }
+            }
+        }
+
+        let mut should_run_full = prior_state.is_none();
+        let mut full_reason = None::<&str>;
+        if let Some(prev) = prior_state.as_ref() {
+            let prior_tape_path = tape_path_for_tapes_dir(&context.tapes_dir, &prev.tape_id);
+            let prior_tape_missing = !prior_tape_path.exists();
+            let prior_tape_unindexed = !index.has_tape(&prev.tape_id)?;
+            if prior_tape_missing || prior_tape_unindexed {
                 should_run_full = true;
                 full_reason = Some(if prior_tape_missing {
                     "cursor_tape_missing"
                 } else {
                     "cursor_tape_unindexed"
                 });
-            } else if metadata.len() < prev.byte_cursor {
+            } else if source_len < prev.byte_cursor {
                 should_run_full = true;
                 full_reason = Some("cursor_past_eof");
             } else {
-                match ingest_cursor_guard_matches(&abs_path, &prev.cursor_guard, metadata.len()) {
+                match ingest_cursor_guard_matches(
+                    &abs_path,
+                    unpacked.as_deref(),
+                    &prev.cursor_guard,
+                    source_len,
+                ) {
                     Ok(false) => {
                         should_run_full = true;
                         full_reason = Some("guard_mismatch");
                     }
                     Ok(true) => {
-                        if metadata.len() == prev.byte_cursor {
+                        if source_len == prev.byte_cursor {
                             skipped_unchanged += 1;
+                            // Keep the adopted cursor under the new path.
+                            if rotated
+                                && let Err(err) = save_ingest_state_for_path(paths, &abs_path, prev)
+                            {
+                                failures.push(json!({
+                                    "path": path_string(&abs_path),
+                                    "error": err.message,
+                                }));
+                            }
                             continue;
                         }
                     }
@@ -732,29 +2534,33 @@ fn cmd_ingest(
         if !should_run_full {
             let prev = prior_state.as_ref().expect("known state");
             adapter_hint = adapter_id_from_name(&prev.adapter);
-            let mut file = match File::open(&abs_path) {
-                Ok(value) => value,
-                Err(err) => {
+            if let Some(bytes) = unpacked.as_deref() {
+                ingest_bytes = bytes[prev.byte_cursor as usize..].to_vec();
+            } else {
+                let mut file = match File::open(&abs_path) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        failures.push(json!({
+                            "path": path_string(&abs_path),
+                            "error": err.to_string(),
+                        }));
+                        continue;
+                    }
+                };
+                if let Err(err) = file.seek(SeekFrom::Start(prev.byte_cursor)) {
+                    failures.push(json!({
+                        "path": path_string(&abs_path),
+                        "error": err.to_string(),
+                    }));
+                    continue;
+                }
+                if let Err(err) = file.read_to_end(&mut ingest_bytes) {
                     failures.push(json!({
                         "path": path_string(&abs_path),
                         "error": err.to_string(),
                     }));
                     continue;
                 }
-            };
-            if let Err(err) = file.seek(SeekFrom::Start(prev.byte_cursor)) {
-                failures.push(json!({
-                    "path": path_string(&abs_path),
-                    "error": err.to_string(),
-                }));
-                continue;
-            }
-            if let Err(err) = file.read_to_end(&mut ingest_bytes) {
-                failures.push(json!({
-                    "path": path_string(&abs_path),
-                    "error": err.to_string(),
-                }));
-                continue;
             }
             let complete = complete_ingest_prefix_len(&abs_path, &ingest_bytes);
             if complete == 0 {
@@ -766,7 +2572,7 @@ fn cmd_ingest(
         }
 
         if should_run_full {
-            let all_bytes = match fs::read(&abs_path) {
+            let all_bytes = match read_ingest_source(&abs_path, unpacked.as_deref()) {
                 Ok(value) => value,
                 Err(err) => {
                     failures.push(json!({
@@ -799,15 +2605,8 @@ fn cmd_ingest(
             }
         };
 
-        let adapter = if let Some(adapter) = adapter_hint {
-            if convert_with_adapter(adapter, ingest_input).is_ok() {
-                Some(adapter)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let adapter =
+            adapter_hint.filter(|&adapter| convert_with_adapter(adapter, ingest_input).is_ok());
         let adapter = if let Some(value) = adapter {
             value
         } else if let Some(value) = detect_adapter_for_input(&abs_path, ingest_input) {
@@ -818,7 +2617,7 @@ fn cmd_ingest(
         } else {
             should_run_full = true;
             full_reason = Some("adapter_parse_mismatch");
-            let all_bytes = match fs::read(&abs_path) {
+            let all_bytes = match read_ingest_source(&abs_path, unpacked.as_deref()) {
                 Ok(value) => value,
                 Err(err) => {
                     failures.push(json!({
@@ -875,6 +2674,61 @@ fn cmd_ingest(
                 continue;
             }
         };
+        let start_offset = next_cursor - ingest_bytes.len() as u64;
+        let skipped = skipped_input_lines(adapter, ingest_input);
+        if !skipped.is_empty() {
+            skipped_lines += skipped.len();
+            skipped_input.push(json!({
+                "path": path_string(&abs_path),
+                "start_offset": start_offset,
+                "count": skipped.len(),
+                "lines": skipped
+                    .iter()
+                    .take(SKIPPED_LINE_SAMPLE)
+                    .map(|issue| json!({ "line": issue.line, "error": issue.error }))
+                    .collect::<Vec<_>>(),
+            }));
+        }
+        let origin = TapeOrigin::for_input(
+            &path_string(&abs_path),
+            start_offset,
+            &ingest_bytes,
+            adapter,
+        );
+        let (normalized, ignored) = drop_ignored_events(&normalized, &ignore_rules)?;
+        ignored_events += ignored;
+        let (normalized, binary) = apply_binary_policy(&normalized, &context.binary_files)?;
+        binary_counts.skipped += binary.skipped;
+        binary_counts.evidence_only += binary.evidence_only;
+        let (normalized, sampled) = sample_large_fields(&normalized, &context.sample_outputs)?;
+        sampled_fields += sampled;
+        let normalized = if context.retain_edits {
+            let (normalized, objects) = stamp_edit_objects(&normalized)?;
+            retained_edits += retain_edit_objects(paths, &objects)?;
+            normalized
+        } else {
+            normalized
+        };
+        let normalized = normalize_tape_evidence(context, normalized)?;
+        let normalized = stamp_origin(&normalized, &origin)?;
+        let normalized = stamp_environment(
+            &normalized,
+            &TapeEnvironment::capture(&context.environment, harness_version(adapter, ingest_input)),
+        )?;
+        let normalized = match context
+            .snapshot_state
+            .then(|| state_snapshot_event(&normalized, cwd))
+            .flatten()
+        {
+            Some(snapshot) => {
+                state_snapshots += 1;
+                format!("{normalized}{snapshot}\n")
+            }
+            None => normalized,
+        };
+        if context.retain_raw && retain_raw_copy(paths, &origin.sha256, ingest_input)? {
+            retained_raw += 1;
+        }
         let events = match parse_jsonl_events(&normalized) {
             Ok(events) => events,
             Err(err) => {
@@ -901,18 +2755,41 @@ fn cmd_ingest(
 
         let already_indexed = index.has_tape(&tape_id)?;
         if !already_indexed {
-            index.ingest_tape_events_with_dispatch(
+            let diagnostics = index.ingest_tape_events_limited(
                 &tape_id,
                 &events,
                 &dispatch_links,
-                LINK_THRESHOLD_DEFAULT,
+                context.link_threshold,
+                context.ingest_limits,
             )?;
+            if diagnostics.is_partial() {
+                partial_ingest.push(partial_ingest_json(&tape_id, &abs_path, &diagnostics));
+            }
+            index.record_tape_metrics(&tape_id, &tape_metrics_from_jsonl(&normalized))?;
+            record_tape_events(&index, &tape_id, &normalized, context)?;
+            watchlist_hits.extend(notify_watchlist(paths, context, &tape_id, &events)?);
             imported += 1;
         } else {
             skipped_existing_tape += 1;
         }
+        index.set_tape_converted_at(&tape_id, &now_iso8601())?;
+        if let Err(err) =
+            embed_tape_events(&index, context.embeddings.as_ref(), &tape_id, &normalized)
+        {
+            failures.push(json!({
+                "path": path_string(&abs_path),
+                "error": err.message,
+            }));
+        }
 
-        let cursor_guard = match build_cursor_guard(&abs_path, next_cursor) {
+        let guards =
+            build_cursor_guard(&abs_path, unpacked.as_deref(), next_cursor).and_then(|guard| {
+                Ok((
+                    guard,
+                    build_cursor_head(&abs_path, unpacked.as_deref(), next_cursor)?,
+                ))
+            });
+        let (cursor_guard, head) = match guards {
             Ok(value) => value,
             Err(err) => {
                 failures.push(json!({
@@ -927,6 +2804,7 @@ fn cmd_ingest(
             cursor_guard,
             adapter: adapter.as_str().to_string(),
             tape_id,
+            head: Some(head.clone()),
         };
         if let Err(err) = save_ingest_state_for_path(paths, &abs_path, &state) {
             failures.push(json!({
@@ -935,18 +2813,144 @@ fn cmd_ingest(
             }));
             continue;
         }
+        if let Some(by_head) = states_by_head.as_mut() {
+            by_head.insert((head.len, head.hash), state);
+        }
     }
 
-    print_json(&json!({
-        "status": if failures.is_empty() { "ok" } else { "partial" },
+    let mut payload = json!({
+        "status": ingest_status(&failures, &partial_ingest),
         "scanned_inputs": scanned,
         "imported_tapes": imported,
         "skipped_unchanged": skipped_unchanged,
         "skipped_existing_tape": skipped_existing_tape,
         "skipped_non_transcript": skipped_non_transcript,
+        "skipped_lines": skipped_lines,
+        "skipped_input": skipped_input,
+        "rotated_sources": rotated_sources,
+        "fast_ingest": fast_ingest,
         "failure_count": failures.len(),
         "failures": failures,
-    }))
+    });
+    if context.retain_raw {
+        payload["retained_raw_copies"] = json!(retained_raw);
+    }
+    if !context.sample_outputs.is_empty() {
+        payload["sampled_fields"] = json!(sampled_fields);
+    }
+    if !ignore_rules.is_empty() {
+        payload["ignored_events"] = json!(ignored_events);
+    }
+    if context.binary_files.policy != BinaryPolicy::Full {
+        payload["binary_events"] = json!({
+            "policy": context.binary_files.policy.as_str(),
+            "skipped": binary_counts.skipped,
+            "evidence_only": binary_counts.evidence_only,
+        });
+    }
+    if context.retain_edits {
+        payload["retained_edit_objects"] = json!(retained_edits);
+    }
+    if context.snapshot_state {
+        payload["state_snapshots"] = json!(state_snapshots);
+    }
+    if !partial_ingest.is_empty() {
+        payload["partial_ingest"] = json!(partial_ingest);
+    }
+    if !watchlist_hits.is_empty() {
+        payload["watchlist"] = json!(watchlist_hits);
+    }
+    if let Some(bulk) = bulk {
+        bulk.finish()?;
+    }
+    if let Some(report) = auto_maintain(&index, context, imported)? {
+        payload["maintenance"] = report;
+    }
+    if imported > 0 {
+        let (index_bytes, tapes_bytes) = store_bytes(&context.db_path, &context.tapes_dir)
+            .map_err(|err| CliError::io("read_error", err))?;
+        index.record_growth_sample(&GrowthSample {
+            recorded_at: now_iso8601(),
+            index_bytes,
+            tapes_bytes,
+        })?;
+    }
+    let storage = storage_forecast_json(&index, context)?;
+    if storage.get("warning").is_some() {
+        payload["storage"] = storage;
+    }
+    print_json(&payload)
+}
+
+/// The store's size and projected growth (see `storage.quota`), with a
+/// `warning` and settings that would slow it down when it is, or within the
+/// quota's horizon will be, over quota.
+fn storage_forecast_json(index: &SqliteIndex, context: &RuntimeContext) -> Result<Value, CliError> {
+    let (index_bytes, tapes_bytes) = store_bytes(&context.db_path, &context.tapes_dir)
+        .map_err(|err| CliError::io("read_error", err))?;
+    let quota = context.storage_quota;
+    let horizon_days = quota.map_or(DEFAULT_QUOTA_HORIZON_DAYS, |quota| quota.horizon_days);
+    let forecast = forecast(
+        &index.growth_samples()?,
+        index_bytes + tapes_bytes,
+        horizon_days,
+    );
+    let mut value = json!({
+        "index_bytes": index_bytes,
+        "tapes_bytes": tapes_bytes,
+        "total_bytes": forecast.current_bytes,
+        "bytes_per_day": forecast.bytes_per_day,
+        "horizon_days": forecast.horizon_days,
+        "projected_bytes": forecast.projected_bytes,
+        "quota_bytes": quota.map(|quota| quota.max_bytes),
+    });
+    let Some(quota) = quota else {
+        return Ok(value);
+    };
+    let warning = if forecast.current_bytes > quota.max_bytes {
+        format!(
+            "store is {} bytes, over the `storage.quota` of {} bytes",
+            forecast.current_bytes, quota.max_bytes
+        )
+    } else if forecast.projected_bytes > quota.max_bytes {
+        format!(
+            "store is projected to reach {} bytes within {} days, over the `storage.quota` of {} bytes",
+            forecast.projected_bytes, forecast.horizon_days, quota.max_bytes
+        )
+    } else {
+        return Ok(value);
+    };
+    value["warning"] = json!(warning);
+    value["suggestions"] = json!(quota_suggestions(context));
+    Ok(value)
+}
+
+/// Retention and sampling settings not yet in use that would shrink the
+/// store or slow its growth.
+fn quota_suggestions(context: &RuntimeContext) -> Vec<&'static str> {
+    let mut suggestions = Vec::new();
+    if context.partitioning == IndexPartitioning::Monthly {
+        suggestions.push("drop old months with `engram gc --drop-partitions-before YYYY-MM`");
+    } else {
+        suggestions.push(
+            "set `maintenance.partitioning: monthly`, then drop old months with `engram gc --drop-partitions-before YYYY-MM`",
+        );
+    }
+    if !context.sample_outputs.contains_key("tool.result") {
+        suggestions.push(
+            "set `ingest.sample.tool.result` to keep only the head and tail of long tool output",
+        );
+    }
+    if context.binary_files.policy == BinaryPolicy::Full {
+        suggestions.push(
+            "set `ingest.binary.policy: evidence-only` to stop indexing binary and generated files",
+        );
+    }
+    if context.ingest_limits == IngestLimits::default() {
+        suggestions.push("set `ingest.limits` to cap how much of one tape is indexed");
+    }
+    suggestions.push("add globs for generated and vendored files to `ignore`");
+    suggestions
 }
 
 #[derive(Debug, Clone)]
@@ -964,12 +2968,17 @@ enum WatchIngestResult {
     TimedOut,
 }
 
-fn cmd_watch(cwd: &Path, args: WatchArgs) -> Result<(), CliError> {
+fn cmd_watch(cwd: &Path, profile: Option<&str>, args: WatchArgs) -> Result<(), CliError> {
     let home = home_dir()?;
-    cmd_watch_with_home(cwd, args, &home)
+    cmd_watch_with_home(cwd, profile, args, &home)
 }
 
-fn cmd_watch_with_home(cwd: &Path, args: WatchArgs, home: &Path) -> Result<(), CliError> {
+fn cmd_watch_with_home(
+    cwd: &Path,
+    profile: Option<&str>,
+    args: WatchArgs,
+    home: &Path,
+) -> Result<(), CliError> {
     let config_override = args.config.as_ref().map(|path| {
         if path.is_absolute() {
             path.clone()
@@ -977,9 +2986,10 @@ fn cmd_watch_with_home(cwd: &Path, args: WatchArgs, home: &Path) -> Result<(), C
             cwd.join(path)
         }
     });
-    let config = load_effective_config_with_override(cwd, home, config_override.as_deref())
+    let config = load_effective_config_for_profile(cwd, home, config_override.as_deref(), profile)
         .map_err(|err| CliError::new("config_error", err.to_string()))?;
-    let tape_lookup_dirs = tape_lookup_dirs(cwd, home, &config);
+    migrate_storage(home, &config)?;
+    let tape_lookup_dirs = tape_lookup_dirs(home, &config);
     let context = RuntimeContext {
         config_path: config.path,
         db_path: config.db,
@@ -994,6 +3004,29 @@ fn cmd_watch_with_home(cwd: &Path, args: WatchArgs, home: &Path) -> Result<(), C
         metrics_enabled: config.metrics.enabled,
         metrics_log: config.metrics.log,
         watch: config.watch,
+        watch_log: config.watch_log,
+        summarize: config.summarize,
+        export: config.export,
+        embeddings: config.embeddings,
+        auto_maintain_after_ingests: config.maintenance.auto_after_ingests,
+        partitioning: config.maintenance.partitioning,
+        fast_ingest: config.fast_ingest,
+        retain_raw: config.retain_raw,
+        retain_edits: config.retain_edits,
+        snapshot_state: config.snapshot_state,
+        environment: config.environment.clone(),
+        normalize_line_endings: config.normalize_line_endings,
+        normalizers: config.normalizers.clone(),
+        link_threshold: config.link_threshold,
+        scorer: config.scorer.clone(),
+        fingerprint_cache: config.storage.cache_dir.join(FINGERPRINT_CACHE_FILE),
+        sample_outputs: config.sample_outputs.clone(),
+        ingest_limits: config.ingest_limits,
+        binary_files: config.binary_files,
+        ignore: config.ignore.clone(),
+        issue_patterns: config.issue_patterns.clone(),
+        projects: config.projects.clone(),
+        storage_quota: config.storage_quota,
     };
     print_context_conspicuity(&context);
 
@@ -1201,11 +3234,7 @@ fn watch_path_matches(runtime: &WatchSourceRuntime, path: &Path) -> bool {
 }
 
 fn watch_glob_match_options() -> glob::MatchOptions {
-    glob::MatchOptions {
-        case_sensitive: true,
-        require_literal_separator: true,
-        require_literal_leading_dot: false,
-    }
+    PathPolicy::host().glob_options()
 }
 
 fn watch_path_relative_to_source(runtime: &WatchSourceRuntime, path: &Path) -> Option<PathBuf> {
@@ -1240,6 +3269,8 @@ fn run_watch_ingest(
                 &context,
                 IngestArgs {
                     paths: vec![changed],
+                    fast_ingest: false,
+                    reconvert: false,
                 },
             )
         });
@@ -1259,30 +3290,505 @@ fn watch_log_line(log: &mut File, message: &str) -> Result<(), CliError> {
     writeln!(log, "[{}] {}", now_iso8601(), message).map_err(|err| CliError::io("write_error", err))
 }
 
+/// One `ingest …` line of the watch log.
+struct WatchLogIngest {
+    at: String,
+    path: PathBuf,
+    outcome: &'static str,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+fn parse_watch_log_ingest(line: &str) -> Option<WatchLogIngest> {
+    let (at, message) = line.strip_prefix('[')?.split_once("] ")?;
+    let (outcome, rest) = if let Some(rest) = message.strip_prefix("ingest ok path=") {
+        ("ok", rest)
+    } else if let Some(rest) = message.strip_prefix("ingest timeout path=") {
+        ("timeout", rest)
+    } else {
+        ("failed", message.strip_prefix("ingest failed path=")?)
+    };
+    let mut entry = WatchLogIngest {
+        at: at.to_string(),
+        path: PathBuf::from(rest),
+        outcome,
+        code: None,
+        message: None,
+    };
+    if outcome == "failed"
+        && let Some((path, rest)) = rest.split_once(" code=")
+    {
+        let (code, message) = rest.split_once(" message=").unwrap_or((rest, ""));
+        entry.path = PathBuf::from(path);
+        entry.code = Some(code.to_string());
+        entry.message = Some(message.to_string());
+    }
+    Some(entry)
+}
+
+/// A runtime for matching files against `source` outside of `watch`.
+fn watch_source_runtime(
+    source: &EffectiveWatchSource,
+) -> Result<WatchSourceRuntime, glob::PatternError> {
+    Ok(WatchSourceRuntime {
+        source: source.clone(),
+        match_root: fs::canonicalize(&source.path).unwrap_or_else(|_| source.path.clone()),
+        pattern: glob::Pattern::new(&source.pattern)?,
+        glob: source.glob.as_deref().map(glob::Pattern::new).transpose()?,
+        debounce: Duration::ZERO,
+        ingest_timeout: Duration::ZERO,
+    })
+}
+
+/// The files under the source that `watch` would ingest, newest first, with
+/// their modification times.
+fn watch_source_files(runtime: &WatchSourceRuntime) -> Vec<(chrono::DateTime<Utc>, PathBuf)> {
+    let mut matched = WalkDir::new(&runtime.source.path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && watch_path_matches(runtime, entry.path()))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((chrono::DateTime::<Utc>::from(modified), entry.into_path()))
+        })
+        .collect::<Vec<_>>();
+    matched.sort_by(|a, b| b.cmp(a));
+    matched
+}
+
+/// The bytes ingest converts for `path` when they are not the file's own:
+/// a gzip transcript decompressed, or a VS Code chat editing session with
+/// its snapshots inlined.
+fn unpacked_source(path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    if is_gzip_path(path) {
+        read_transcript(path).map(Some)
+    } else if is_chat_editing_state_path(path) {
+        chat_editing_session_bundle(path).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// The adapter that converts `path`, if any.
+fn detect_adapter_for_file(path: &Path) -> Option<AdapterId> {
+    let bytes = match unpacked_source(path).ok()? {
+        Some(bytes) => bytes,
+        None => fs::read(path).ok()?,
+    };
+    detect_adapter_for_input(path, &String::from_utf8_lossy(&bytes))
+}
+
+/// A location where `discover` found sessions its harness's adapter reads.
+struct DiscoveredSource {
+    adapter: AdapterId,
+    source: EffectiveWatchSource,
+    files: usize,
+}
+
+/// Probe every adapter's artifact locations under `home`: the per-harness
+/// report `discover` prints, and the locations worth watching.
+fn discover_harnesses(home: &Path) -> (Vec<Value>, Vec<DiscoveredSource>) {
+    let rfc3339 =
+        |at: &chrono::DateTime<Utc>| at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let mut harnesses = Vec::new();
+    let mut found_sources = Vec::new();
+    for descriptor in adapter_registry() {
+        let mut locations = Vec::new();
+        let mut files = 0usize;
+        let mut range: Option<(chrono::DateTime<Utc>, chrono::DateTime<Utc>)> = None;
+        for template in descriptor.artifact_path_templates {
+            let Some(location) = artifact_location(template) else {
+                locations.push(json!({"template": template, "probed": false}));
+                continue;
+            };
+            let source = EffectiveWatchSource {
+                path: expand_tilde(&location.root, home),
+                pattern: location.pattern.clone(),
+                glob: location.glob.clone(),
+            };
+            let matched = watch_source_runtime(&source)
+                .map(|runtime| watch_source_files(&runtime))
+                .unwrap_or_default();
+            let newest = matched.first().map(|(at, _)| *at);
+            let oldest = matched.last().map(|(at, _)| *at);
+            if let (Some(oldest), Some(newest)) = (oldest, newest) {
+                range = Some(range.map_or((oldest, newest), |(first, last)| {
+                    (first.min(oldest), last.max(newest))
+                }));
+            }
+            // Detection converts the whole file, so only the newest one is
+            // tried; a template whose files the adapter does not read (tool
+            // output sidecars) is reported but not suggested as a source.
+            let detected = matched
+                .first()
+                .and_then(|(_, path)| detect_adapter_for_file(path));
+            if detected == Some(descriptor.id) {
+                found_sources.push(DiscoveredSource {
+                    adapter: descriptor.id,
+                    source: EffectiveWatchSource {
+                        path: PathBuf::from(&location.root),
+                        pattern: location.pattern.clone(),
+                        glob: location.glob.clone(),
+                    },
+                    files: matched.len(),
+                });
+            }
+            files += matched.len();
+            locations.push(json!({
+                "template": template,
+                "probed": true,
+                "path": path_string(&source.path),
+                "pattern": location.pattern,
+                "glob": location.glob,
+                "files": matched.len(),
+                "oldest_at": oldest.as_ref().map(rfc3339),
+                "newest_at": newest.as_ref().map(rfc3339),
+                "detected": detected.map(AdapterId::as_str),
+            }));
+        }
+        harnesses.push(json!({
+            "adapter": descriptor.id.as_str(),
+            "found": files > 0,
+            "files": files,
+            "oldest_at": range.as_ref().map(|(oldest, _)| rfc3339(oldest)),
+            "newest_at": range.as_ref().map(|(_, newest)| rfc3339(newest)),
+            "locations": locations,
+        }));
+    }
+    (harnesses, found_sources)
+}
+
+fn watch_sources_json(sources: &[EffectiveWatchSource]) -> Vec<Value> {
+    sources
+        .iter()
+        .map(|source| {
+            json!({
+                "path": path_string(&source.path),
+                "pattern": source.pattern,
+                "glob": source.glob,
+            })
+        })
+        .collect()
+}
+
+fn cmd_discover(cwd: &Path, args: DiscoverArgs) -> Result<(), CliError> {
+    let home = home_dir()?;
+    let (harnesses, found) = discover_harnesses(&home);
+    let found_sources = found
+        .into_iter()
+        .map(|found| found.source)
+        .collect::<Vec<_>>();
+    let mut payload = json!({
+        "status": "ok",
+        "harnesses": harnesses,
+        "sources": watch_sources_json(&found_sources),
+    });
+    if args.add {
+        let config_path = match args.config {
+            Some(path) if path.is_absolute() => path,
+            Some(path) => cwd.join(path),
+            None => ensure_user_config(&home)
+                .map_err(|err| CliError::new("config_error", err.to_string()))?,
+        };
+        let added = append_watch_sources(&config_path, &home, &found_sources)
+            .map_err(|err| CliError::new("config_error", err.to_string()))?;
+        payload["config"] = json!(path_string(&config_path));
+        payload["added"] = json!(watch_sources_json(&added));
+    }
+    print_json(&payload)
+}
+
+fn cmd_sources(context: &RuntimeContext, args: SourcesArgs) -> Result<(), CliError> {
+    let SourcesCommand::Status { failures, sample } = args.command;
+    print_context_conspicuity(context);
+    let Some(watch) = context.watch.as_ref() else {
+        return print_json(&json!({"status": "ok", "log": null, "sources": []}));
+    };
+    let log = match fs::read_to_string(&watch.log) {
+        Ok(content) => content
+            .lines()
+            .filter_map(parse_watch_log_ingest)
+            .collect::<Vec<_>>(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(CliError::io("read_error", err)),
+    };
+    let sources = watch
+        .sources
+        .iter()
+        .map(|source| watch_source_status(source, &log, failures, sample))
+        .collect::<Vec<_>>();
+    let healthy = sources.iter().all(|source| source["status"] == "ok");
+    print_json(&json!({
+        "status": if healthy { "ok" } else { "degraded" },
+        "log": path_string(&watch.log),
+        "sources": sources,
+    }))
+}
+
+fn watch_source_status(
+    source: &EffectiveWatchSource,
+    log: &[WatchLogIngest],
+    failures: usize,
+    sample: usize,
+) -> Value {
+    let mut status = json!({
+        "path": path_string(&source.path),
+        "pattern": source.pattern,
+        "glob": source.glob,
+        "exists": source.path.is_dir(),
+        "files_matched": 0,
+        "newest_session_at": null,
+        "last_scan_at": null,
+        "adapters": {},
+        "recent_failures": [],
+    });
+    let runtime = match watch_source_runtime(source) {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            status["status"] = json!("invalid");
+            status["error"] = json!(err.to_string());
+            return status;
+        }
+    };
+    if !source.path.is_dir() {
+        status["status"] = json!("missing");
+        return status;
+    }
+    let matched = watch_source_files(&runtime);
+    status["files_matched"] = json!(matched.len());
+    if let Some((newest, _)) = matched.first() {
+        status["newest_session_at"] =
+            json!(newest.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    }
+
+    // Watch ingests run from the source directory, so their cursors live in
+    // its store; a cursor is rewritten every time its file is scanned.
+    let source_paths = repo_paths(&source.path).ok();
+    let cursor_scan = matched
+        .iter()
+        .filter_map(|(_, path)| {
+            let paths = source_paths.as_ref()?;
+            let abs_path = fs::canonicalize(path).ok()?;
+            let modified = fs::metadata(cursor_state_path(paths, &abs_path))
+                .ok()?
+                .modified()
+                .ok()?;
+            Some(
+                chrono::DateTime::<Utc>::from(modified)
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            )
+        })
+        .max();
+    let entries = log
+        .iter()
+        .filter(|entry| watch_path_relative_to_source(&runtime, &entry.path).is_some())
+        .collect::<Vec<_>>();
+    let log_scan = entries
+        .iter()
+        .filter_map(|entry| chrono::DateTime::parse_from_rfc3339(&entry.at).ok())
+        .max()
+        .map(|at| {
+            at.with_timezone(&Utc)
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        });
+    status["last_scan_at"] = json!(cursor_scan.max(log_scan));
+
+    let mut adapters = BTreeMap::<&str, u64>::new();
+    for (_, path) in matched.iter().take(sample) {
+        let detected = detect_adapter_for_file(path);
+        *adapters
+            .entry(detected.map_or("undetected", AdapterId::as_str))
+            .or_default() += 1;
+    }
+    status["adapters"] = json!(adapters);
+    status["recent_failures"] = json!(
+        entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.outcome != "ok")
+            .take(failures)
+            .map(|entry| json!({
+                "at": entry.at,
+                "path": path_string(&entry.path),
+                "outcome": entry.outcome,
+                "code": entry.code,
+                "message": entry.message,
+            }))
+            .collect::<Vec<_>>()
+    );
+
+    let sampled = matched.len().min(sample);
+    status["status"] = json!(if matched.is_empty() {
+        "empty"
+    } else if entries.last().is_some_and(|entry| entry.outcome != "ok") {
+        "failing"
+    } else if sampled > 0 && adapters.get("undetected") == Some(&(sampled as u64)) {
+        "undetected"
+    } else {
+        "ok"
+    });
+    status
+}
+
 struct CapturedCommandTape {
     raw_jsonl: String,
     argv: Vec<String>,
     exit_code: i32,
+    signal: Option<i32>,
     success: bool,
+    timed_out: bool,
     stdout_bytes: usize,
     stderr_bytes: usize,
+    stdout_truncated: bool,
+    stderr_truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CaptureLimits {
+    timeout: Option<Duration>,
+    max_output_bytes: Option<usize>,
+}
+
+/// Grace period for output readers after a timed-out command is killed;
+/// grandchildren that inherited the pipes may keep them open.
+const CAPTURE_DRAIN_GRACE: Duration = Duration::from_secs(1);
+
+/// One captured output stream: the kept prefix and the full byte count.
+#[derive(Default)]
+struct CapturedStream {
+    kept: Vec<u8>,
+    total_bytes: usize,
+}
+
+impl CapturedStream {
+    fn truncated(&self) -> bool {
+        self.kept.len() < self.total_bytes
+    }
+
+    /// Kept text, ending in an explicit marker when bytes were dropped.
+    fn tape_text(&self, name: &str) -> String {
+        let mut text = String::from_utf8_lossy(&self.kept).into_owned();
+        if self.truncated() {
+            text.push_str(&format!(
+                "\n[engram: {name} truncated after {} of {} bytes]",
+                self.kept.len(),
+                self.total_bytes
+            ));
+        }
+        text
+    }
+}
+
+type CaptureReader = (
+    Arc<std::sync::Mutex<CapturedStream>>,
+    std::thread::JoinHandle<io::Result<()>>,
+);
+
+/// Drain `reader` to the end so the child never blocks on a full pipe,
+/// keeping at most `limit` bytes. The shared stream is updated as bytes
+/// arrive so a timed-out capture can keep what was read.
+fn spawn_capture_reader<R: Read + Send + 'static>(
+    mut reader: R,
+    limit: Option<usize>,
+) -> CaptureReader {
+    let stream = Arc::new(std::sync::Mutex::new(CapturedStream::default()));
+    let shared = Arc::clone(&stream);
+    let handle = std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            let read = reader.read(&mut buf)?;
+            if read == 0 {
+                return Ok(());
+            }
+            let mut stream = shared.lock().expect("capture lock");
+            stream.total_bytes += read;
+            let room = limit.map_or(read, |limit| {
+                limit.saturating_sub(stream.kept.len()).min(read)
+            });
+            stream.kept.extend_from_slice(&buf[..room]);
+        }
+    });
+    (stream, handle)
+}
+
+fn finish_capture_reader(
+    (stream, handle): CaptureReader,
+    grace: Option<Duration>,
+) -> Result<CapturedStream, CliError> {
+    if let Some(grace) = grace {
+        let deadline = Instant::now() + grace;
+        while !handle.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+    if grace.is_none() || handle.is_finished() {
+        handle
+            .join()
+            .map_err(|_| CliError::new("command_output_error", "output reader panicked"))?
+            .map_err(|err| CliError::io("command_output_error", err))?;
+    }
+    let mut stream = stream.lock().expect("capture lock");
+    Ok(std::mem::take(&mut *stream))
 }
 
-fn capture_command_tape(cwd: &Path, command: &[String]) -> Result<CapturedCommandTape, CliError> {
+fn capture_command_tape(
+    cwd: &Path,
+    command: &[String],
+    limits: CaptureLimits,
+) -> Result<CapturedCommandTape, CliError> {
     let mut proc = ProcessCommand::new(&command[0]);
     if command.len() > 1 {
         proc.args(&command[1..]);
     }
-    proc.current_dir(cwd);
+    proc.current_dir(cwd)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
 
     let started_at = now_iso8601();
-    let output = proc
-        .output()
+    let started = Instant::now();
+    let mut child = proc
+        .spawn()
         .map_err(|err| CliError::new("command_spawn_error", err.to_string()))?;
+    let stdout_reader = spawn_capture_reader(
+        child.stdout.take().expect("piped stdout"),
+        limits.max_output_bytes,
+    );
+    let stderr_reader = spawn_capture_reader(
+        child.stderr.take().expect("piped stderr"),
+        limits.max_output_bytes,
+    );
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|err| CliError::io("command_wait_error", err))?
+        {
+            break status;
+        }
+        if limits
+            .timeout
+            .is_some_and(|timeout| started.elapsed() >= timeout)
+        {
+            timed_out = true;
+            let _ = child.kill();
+            break child
+                .wait()
+                .map_err(|err| CliError::io("command_wait_error", err))?;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    let grace = timed_out.then_some(CAPTURE_DRAIN_GRACE);
+    let stdout = finish_capture_reader(stdout_reader, grace)?;
+    let stderr = finish_capture_reader(stderr_reader, grace)?;
     let finished_at = now_iso8601();
 
-    let exit_code = output.status.code().unwrap_or(-1);
-    let success = output.status.success();
+    let exit_code = status.code().unwrap_or(-1);
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+    #[cfg(not(unix))]
+    let signal = None;
+    let success = status.success() && !timed_out;
     let command_text = command.join(" ");
     let args_text = if command.len() > 1 {
         command[1..].join(" ")
@@ -1306,14 +3812,27 @@ fn capture_command_tape(cwd: &Path, command: &[String]) -> Result<CapturedComman
         "args": args_text,
         "cwd": cwd_text,
     }));
-    lines.push(json!({
+    let mut result = json!({
         "t": finished_at,
         "k": "tool.result",
         "tool": command[0],
         "exit": exit_code,
-        "stdout": String::from_utf8_lossy(&output.stdout),
-        "stderr": String::from_utf8_lossy(&output.stderr),
-    }));
+        "stdout": stdout.tape_text("stdout"),
+        "stderr": stderr.tape_text("stderr"),
+    });
+    if stdout.truncated() {
+        result["stdout_truncated"] = json!(true);
+        result["stdout_bytes"] = json!(stdout.total_bytes);
+    }
+    if stderr.truncated() {
+        result["stderr_truncated"] = json!(true);
+        result["stderr_bytes"] = json!(stderr.total_bytes);
+    }
+    if timed_out {
+        result["timed_out"] = json!(true);
+        result["timeout_secs"] = json!(limits.timeout.map(|timeout| timeout.as_secs()));
+    }
+    lines.push(result);
 
     let raw_jsonl = lines
         .iter()
@@ -1326,9 +3845,13 @@ fn capture_command_tape(cwd: &Path, command: &[String]) -> Result<CapturedComman
         raw_jsonl,
         argv: command.to_vec(),
         exit_code,
+        signal,
         success,
-        stdout_bytes: output.stdout.len(),
-        stderr_bytes: output.stderr.len(),
+        timed_out,
+        stdout_bytes: stdout.total_bytes,
+        stderr_bytes: stderr.total_bytes,
+        stdout_truncated: stdout.truncated(),
+        stderr_truncated: stderr.truncated(),
     })
 }
 
@@ -1342,10 +3865,7 @@ fn discover_local_transcript_candidates(cwd: &Path) -> Result<Vec<PathBuf>, CliE
         if path.starts_with(cwd.join(".engram")) {
             continue;
         }
-        let extension = path
-            .extension()
-            .and_then(|value| value.to_str())
-            .map(|value| value.to_ascii_lowercase());
+        let extension = transcript_extension(path);
         if matches!(extension.as_deref(), Some("json") | Some("jsonl")) {
             out.push(path.to_path_buf());
         }
@@ -1356,55 +3876,64 @@ fn discover_local_transcript_candidates(cwd: &Path) -> Result<Vec<PathBuf>, CliE
 }
 
 fn detect_adapter_for_input(path: &Path, input: &str) -> Option<AdapterId> {
-    let lower_path = path.to_string_lossy().to_ascii_lowercase();
-    let preferred =
-        if lower_path.contains(".codex/sessions") || lower_path.ends_with("history.jsonl") {
-            Some(AdapterId::CodexCli)
-        } else if lower_path.contains(".claude/projects") {
-            Some(AdapterId::ClaudeCode)
-        } else if lower_path.contains("opencode") {
-            Some(AdapterId::OpenCode)
-        } else if lower_path.contains("cursor") {
-            Some(AdapterId::Cursor)
-        } else if lower_path.contains("gemini") {
-            Some(AdapterId::GeminiCli)
-        } else if lower_path.contains(".openclaw") || lower_path.contains("openclaw") {
-            Some(AdapterId::OpenClaw)
-        } else {
-            None
-        };
+    let lower_path = uncompressed_path(path)
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    let preferred = if lower_path.contains("chateditingsessions") {
+        Some(AdapterId::VsCodeChat)
+    } else if lower_path.contains(".codex/sessions") || lower_path.ends_with("history.jsonl") {
+        Some(AdapterId::CodexCli)
+    } else if lower_path.contains(".claude/projects") {
+        Some(AdapterId::ClaudeCode)
+    } else if lower_path.contains("opencode") {
+        Some(AdapterId::OpenCode)
+    } else if lower_path.contains("cursor") {
+        Some(AdapterId::Cursor)
+    } else if lower_path.contains("gemini") {
+        Some(AdapterId::GeminiCli)
+    } else if lower_path.contains(".openclaw") || lower_path.contains("openclaw") {
+        Some(AdapterId::OpenClaw)
+    } else if lower_path.ends_with("conversations.json") {
+        Some(AdapterId::ChatGpt)
+    } else {
+        None
+    };
 
-    if let Some(adapter) = preferred {
-        if convert_with_adapter(adapter, input).is_ok() {
-            return Some(adapter);
-        }
+    if let Some(adapter) = preferred
+        && convert_with_adapter(adapter, input).is_ok()
+    {
+        return Some(adapter);
     }
 
-    for adapter in [
+    // The ChatGPT export and VS Code session shapes are checked first: a
+    // one-line export is also a JSON line the JSON Lines adapters would
+    // accept.
+    [
+        AdapterId::ChatGpt,
+        AdapterId::VsCodeChat,
         AdapterId::CodexCli,
         AdapterId::ClaudeCode,
         AdapterId::OpenCode,
         AdapterId::Cursor,
         AdapterId::GeminiCli,
         AdapterId::OpenClaw,
-    ] {
-        if convert_with_adapter(adapter, input).is_ok() {
-            return Some(adapter);
-        }
-    }
-    None
+    ]
+    .into_iter()
+    .find(|&adapter| convert_with_adapter(adapter, input).is_ok())
 }
 
 fn cmd_fingerprint(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError> {
     ensure_local_store(paths)?;
     print_context_conspicuity(context);
     ensure_db_parent(&context.db_path)?;
-    let index = SqliteIndex::open(&path_string(&context.db_path))?;
+    let _lock = lock_store(context, "fingerprint")?;
+    let index = open_index(context)?;
 
     let mut scanned = 0usize;
     let mut fingerprinted = 0usize;
     let mut skipped_existing = 0usize;
     let mut failures = Vec::new();
+    let mut watchlist_hits = Vec::new();
 
     let entries = fs::read_dir(&paths.tapes).map_err(|err| CliError::io("read_dir_error", err))?;
     for entry in entries {
@@ -1423,7 +3952,7 @@ fn cmd_fingerprint(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), Cl
         }
 
         let content = match read_tape_content(&path) {
-            Ok(value) => value,
+            Ok(value) => normalize_tape_evidence(context, value)?,
             Err(err) => {
                 failures.push(json!({
                     "path": path,
@@ -1447,19 +3976,505 @@ fn cmd_fingerprint(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), Cl
             &tape_id,
             &events,
             &dispatch_links,
-            LINK_THRESHOLD_DEFAULT,
+            context.link_threshold,
         )?;
-        fingerprinted += 1;
-    }
+        index.record_tape_metrics(&tape_id, &tape_metrics_from_jsonl(&content))?;
+        record_tape_events(&index, &tape_id, &content, context)?;
+        watchlist_hits.extend(notify_watchlist(paths, context, &tape_id, &events)?);
+        if let Err(err) = embed_tape_events(&index, context.embeddings.as_ref(), &tape_id, &content)
+        {
+            failures.push(json!({
+                "path": path,
+                "error": err.message,
+            }));
+        }
+        fingerprinted += 1;
+    }
 
-    print_json(&json!({
+    let mut payload = json!({
         "status": if failures.is_empty() { "ok" } else { "partial" },
         "scanned_tapes": scanned,
         "fingerprinted_tapes": fingerprinted,
         "skipped_existing_tapes": skipped_existing,
         "failure_count": failures.len(),
         "failures": failures,
-    }))
+    });
+    if !watchlist_hits.is_empty() {
+        payload["watchlist"] = json!(watchlist_hits);
+    }
+    if let Some(report) = auto_maintain(&index, context, fingerprinted)? {
+        payload["maintenance"] = report;
+    }
+    print_json(&payload)
+}
+
+fn cmd_watchlist(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: WatchlistArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    let _lock = match args.command {
+        WatchlistCommand::List => None,
+        _ => Some(wait_for_store_lock(context, "watchlist")?),
+    };
+    let path = watchlist_path(&paths.root);
+    let mut watchlist =
+        load_watchlist(&path).map_err(|err| CliError::new("watchlist_error", err.to_string()))?;
+    let mut payload = json!({"status": "ok"});
+    match args.command {
+        WatchlistCommand::Add {
+            pattern,
+            anchor,
+            hook,
+        } => {
+            if let Err(err) = glob::Pattern::new(&pattern) {
+                return Err(CliError::new(
+                    "invalid_watch_pattern",
+                    format!("`{pattern}` is not a valid glob: {err}"),
+                ));
+            }
+            payload["added"] = json!(watchlist.add(WatchEntry {
+                pattern,
+                anchor,
+                hook,
+                added_at: now_iso8601(),
+            }));
+        }
+        WatchlistCommand::Remove { pattern } => {
+            let removed = watchlist.remove(&pattern);
+            if removed == 0 {
+                return Err(CliError::new(
+                    "watch_pattern_not_found",
+                    format!("`{pattern}` is not on the watchlist"),
+                ));
+            }
+            payload["removed"] = json!(removed);
+        }
+        WatchlistCommand::List => {}
+    }
+    save_watchlist(&path, &watchlist)
+        .map_err(|err| CliError::new("write_error", err.to_string()))?;
+    payload["entries"] = json!(watchlist.entries);
+    print_json(&payload)
+}
+
+/// Match a newly indexed tape against the repo watchlist. Each hit is
+/// appended to `.engram/notifications.jsonl`; hits whose entry has a hook
+/// are queued for it, and the hooks run once the store lock is released
+/// (see [`StoreGuard`]).
+fn notify_watchlist(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    tape_id: &str,
+    events: &[TapeEventAt],
+) -> Result<Vec<Value>, CliError> {
+    let watchlist = load_watchlist(&watchlist_path(&paths.root))
+        .map_err(|err| CliError::new("watchlist_error", err.to_string()))?;
+    if watchlist.entries.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut notifications = Vec::new();
+    for hit in watchlist.matches(events) {
+        let notification = json!({
+            "t": now_iso8601(),
+            "pattern": hit.pattern,
+            "tape_id": tape_id,
+            "matches": hit.matches.iter().map(|found| {
+                let mut entry = json!({
+                    "offset": found.event_offset,
+                    "kind": found.kind,
+                    "file": found.file,
+                });
+                if let Some(anchor) = &found.anchor {
+                    entry["anchor"] = json!(anchor);
+                }
+                entry
+            }).collect::<Vec<_>>(),
+        });
+        if let Some(hook) = &hit.hook {
+            PENDING_WATCH_HOOKS.with_borrow_mut(|pending| {
+                pending.push(PendingWatchHook {
+                    hook: hook.clone(),
+                    notification: notification.clone(),
+                    log: context.watch_log.clone(),
+                });
+            });
+        }
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(paths.root.join("notifications.jsonl"))
+            .map_err(|err| CliError::io("write_error", err))?;
+        writeln!(log, "{notification}").map_err(|err| CliError::io("write_error", err))?;
+        notifications.push(notification);
+    }
+    Ok(notifications)
+}
+
+struct PendingWatchHook {
+    hook: String,
+    notification: Value,
+    /// The watch log, which gets the hook's stderr and outcome.
+    log: PathBuf,
+}
+
+thread_local! {
+    /// Hooks [`notify_watchlist`] queued while this thread held the store
+    /// lock.
+    static PENDING_WATCH_HOOKS: RefCell<Vec<PendingWatchHook>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Run the queued watchlist hooks, logging how each ended; a failing hook
+/// never fails the command that found the hit.
+fn run_pending_watch_hooks() {
+    for pending in PENDING_WATCH_HOOKS.take() {
+        let pattern = pending.notification["pattern"].as_str().unwrap_or_default();
+        let tape_id = pending.notification["tape_id"].as_str().unwrap_or_default();
+        let outcome = run_watch_hook(&pending.hook, &pending.notification, &pending.log)
+            .unwrap_or_else(|err| format!("error={err}"));
+        if let Ok(mut log) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&pending.log)
+        {
+            let _ = watch_log_line(
+                &mut log,
+                &format!("watch hook pattern={pattern} tape_id={tape_id} {outcome}"),
+            );
+        }
+    }
+}
+
+/// Pipe `notification` to `hook` (`sh -c`) with its stderr appended to
+/// `log`, killing it after [`WATCH_HOOK_TIMEOUT`]. Returns how it ended:
+/// `exit=CODE`, `exit=signal` or `timed_out=SECSs`.
+fn run_watch_hook(hook: &str, notification: &Value, log: &Path) -> io::Result<String> {
+    if let Some(parent) = log.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let stderr = OpenOptions::new().create(true).append(true).open(log)?;
+    let mut child = ProcessCommand::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(stderr)
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // From another thread: a hook that never reads its stdin must not
+        // block us on a full pipe. Its end of the pipe closes when it exits.
+        let input = notification.to_string();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                return Ok(status
+                    .code()
+                    .map_or_else(|| "exit=signal".to_string(), |code| format!("exit={code}")));
+            }
+            Ok(None) if started.elapsed() >= WATCH_HOOK_TIMEOUT => {
+                let _ = child.kill();
+                child.wait()?;
+                return Ok(format!("timed_out={}s", WATCH_HOOK_TIMEOUT.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(err) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// Rescore indexed edits whose before and after text the tapes (or the
+/// objects dir) still hold with `lineage.scorer`, compare the scores with
+/// the stored edge confidence and `lineage.link_threshold`, and suggest a
+/// threshold fitted to this codebase; `--write` stores it in the config.
+fn cmd_calibrate(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: CalibrateArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let indexes = open_query_indexes(context)?;
+    let scorer = scorer_for_name(&context.scorer).unwrap_or_else(default_scorer);
+    let mut candidates = Vec::new();
+    for tape_id in indexes[0].tape_ids()? {
+        let Some(tape_path) = resolve_tape_path(context, &tape_id) else {
+            continue;
+        };
+        let content = normalize_tape_evidence(context, read_tape_content(&tape_path)?)?;
+        for row in parse_jsonl_rows(&content)? {
+            if row.value.get("k").and_then(Value::as_str) != Some("code.edit") {
+                continue;
+            }
+            let normalizer = row
+                .value
+                .get("normalizer")
+                .and_then(Value::as_str)
+                .and_then(normalizer_for_tag);
+            let edge_side = |side: &str| {
+                row.value
+                    .get(format!("{side}_text"))
+                    .and_then(Value::as_str)
+                    .map(|text| normalized_anchor_hashes(text, normalizer))
+                    .and_then(|anchors| anchors.into_iter().next())
+            };
+            let (Some(from), Some(to)) = (edge_side("before"), edge_side("after")) else {
+                continue;
+            };
+            if from == to {
+                continue;
+            }
+            let (Some(before), _) = edit_side_text(paths, &row.value, "before") else {
+                continue;
+            };
+            let (Some(after), _) = edit_side_text(paths, &row.value, "after") else {
+                continue;
+            };
+            let similarity = match normalizer {
+                Some(normalizer) => scorer.score(
+                    &normalizer.normalize(&before),
+                    &normalizer.normalize(&after),
+                ),
+                None => scorer.score(&before, &after),
+            };
+            let mut stored = None;
+            for index in &indexes {
+                stored = index
+                    .outbound_edges(&from, 0.0, true)?
+                    .into_iter()
+                    .find(|edge| edge.to_anchor == to);
+                if stored.is_some() {
+                    break;
+                }
+            }
+            // A score from another scorer (or the harness) is on another
+            // scale, so it is not compared.
+            let (stored_confidence, other_scorer) = match stored {
+                Some(edge) if edge.scorer.as_deref() == Some(scorer.tag().as_str()) => {
+                    (Some(edge.confidence), false)
+                }
+                Some(_) => (None, true),
+                None => (None, false),
+            };
+            candidates.push((
+                CalibrationSample {
+                    stored_confidence,
+                    similarity,
+                },
+                other_scorer,
+            ));
+        }
+    }
+    let found = candidates.len();
+    let samples = if found > args.sample && args.sample > 0 {
+        (0..args.sample)
+            .map(|slot| candidates[slot * found / args.sample])
+            .collect::<Vec<_>>()
+    } else {
+        candidates
+    };
+    let other_scorer = samples.iter().filter(|(_, other)| *other).count();
+    let samples = samples
+        .into_iter()
+        .map(|(sample, _)| sample)
+        .collect::<Vec<_>>();
+    let report = calibrate(&samples, context.link_threshold);
+
+    let round = |value: f32| (f64::from(value) * 100.0).round() / 100.0;
+    let mut payload = json!({
+        "status": "ok",
+        "link_threshold": round(context.link_threshold),
+        "edits_found": found,
+        "sampled": report.sampled,
+        "scorer": scorer.tag(),
+        "stored_confidence": {
+            "agrees": report.confidence_agrees,
+            "disagrees": report.confidence_disagrees,
+            "missing": report.confidence_missing - other_scorer,
+            "other_scorer": other_scorer,
+            "tolerance": round(CONFIDENCE_TOLERANCE),
+        },
+        "misclassified": report.misclassified,
+        "similarity_percentiles": report
+            .similarity_percentiles
+            .iter()
+            .map(|(percentile, value)| (format!("p{percentile}"), json!(round(*value))))
+            .collect::<serde_json::Map<_, _>>(),
+        "current": {
+            "lineage": report.lineage,
+            "location_only": report.sampled - report.lineage,
+        },
+        "suggested_link_threshold": report.suggested_link_threshold.map(round),
+    });
+    if let Some(lineage) = report.lineage_suggested {
+        payload["suggested"] = json!({
+            "lineage": lineage,
+            "location_only": report.sampled - lineage,
+        });
+    }
+    if args.write {
+        let Some(threshold) = report.suggested_link_threshold else {
+            return Err(CliError::new(
+                "calibrate_error",
+                format!(
+                    "{} edits with before and after text sampled; at least {MIN_CALIBRATION_SAMPLES} are needed to suggest a threshold",
+                    report.sampled
+                ),
+            ));
+        };
+        let config_path = args.config.unwrap_or_else(|| context.config_path.clone());
+        let _lock = wait_for_store_lock(context, "calibrate")?;
+        set_config_values(
+            &config_path,
+            &[("lineage.link_threshold", round(threshold).into())],
+        )
+        .map_err(|err| CliError::new("config_error", err.to_string()))?;
+        payload["written"] = json!(path_string(&config_path));
+    }
+    print_json(&payload)
+}
+
+fn cmd_maintain(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let _lock = lock_store(context, "maintain")?;
+    let index = open_index(context)?;
+    let partitions = roll_into_partitions(&index, context)?;
+    let report = index.maintain(&now_iso8601())?;
+    let mut payload = maintenance_report_json(&report);
+    payload["status"] = json!("ok");
+    payload["db"] = json!(context.db_path);
+    if let Some(partitions) = partitions {
+        payload["partitions"] = partitions;
+    }
+    print_json(&payload)
+}
+
+/// Count tapes an ingest just indexed and run maintenance once
+/// `maintenance.auto_after_ingests` have accumulated since the last run.
+fn auto_maintain(
+    index: &SqliteIndex,
+    context: &RuntimeContext,
+    indexed: usize,
+) -> Result<Option<Value>, CliError> {
+    let threshold = context.auto_maintain_after_ingests;
+    if threshold == 0 || indexed == 0 {
+        return Ok(None);
+    }
+    if index.note_tapes_indexed(indexed as u64)? < threshold {
+        return Ok(None);
+    }
+    let partitions = roll_into_partitions(index, context)?;
+    let report = index.maintain(&now_iso8601())?;
+    let mut payload = maintenance_report_json(&report);
+    payload["trigger"] = json!("auto");
+    if let Some(partitions) = partitions {
+        payload["partitions"] = partitions;
+    }
+    Ok(Some(payload))
+}
+
+/// With `maintenance.partitioning: monthly`, move tapes that started before
+/// the current month out of the main index into one index file per month.
+/// Each partition is built from the stored tapes, so it stands on its own and
+/// can be deleted whole; the main index keeps the current month plus every
+/// tape row, dispatch link and edge, so lineage still crosses partitions.
+fn roll_into_partitions(
+    index: &SqliteIndex,
+    context: &RuntimeContext,
+) -> Result<Option<Value>, CliError> {
+    if context.partitioning != IndexPartitioning::Monthly {
+        return Ok(None);
+    }
+    let current_month = partition_key(&now_iso8601()).unwrap_or_default();
+    let mut by_partition = BTreeMap::<String, Vec<String>>::new();
+    for (tape_id, started_at) in index.resident_tapes_started_before(&current_month)? {
+        if let Some(key) = partition_key(&started_at) {
+            by_partition.entry(key).or_default().push(tape_id);
+        }
+    }
+    if !by_partition.is_empty() {
+        fs::create_dir_all(partition_dir(&context.db_path))
+            .map_err(|err| CliError::io("mkdir_error", err))?;
+    }
+
+    let mut moved = Vec::new();
+    let mut partitions = Vec::new();
+    let mut failures = Vec::new();
+    for (key, tape_ids) in &by_partition {
+        let partition = open_index_at(context, &partition_path(&context.db_path, key))?;
+        let bulk = partition.bulk_load()?;
+        let mut partition_moved = 0usize;
+        for tape_id in tape_ids {
+            let Some(path) = resolve_tape_path(context, tape_id) else {
+                failures.push(json!({"tape_id": tape_id, "error": "tape file not found"}));
+                continue;
+            };
+            let content = match read_tape_content(&path)
+                .and_then(|content| normalize_tape_evidence(context, content))
+            {
+                Ok(value) => value,
+                Err(err) => {
+                    failures.push(json!({"tape_id": tape_id, "error": err.message}));
+                    continue;
+                }
+            };
+            let events = match parse_jsonl_events(&content) {
+                Ok(value) => value,
+                Err(err) => {
+                    failures.push(json!({"tape_id": tape_id, "error": err.to_string()}));
+                    continue;
+                }
+            };
+            let dispatch_links = extract_dispatch_links_from_transcript(&content);
+            partition.ingest_tape_events_with_dispatch(
+                tape_id,
+                &events,
+                &dispatch_links,
+                context.link_threshold,
+            )?;
+            partition.record_tape_metrics(tape_id, &tape_metrics_from_jsonl(&content))?;
+            moved.push(tape_id.clone());
+            partition_moved += 1;
+        }
+        bulk.finish()?;
+        partitions.push(json!({"partition": key, "moved_tapes": partition_moved}));
+    }
+    // Only detach once every partition is written: a crash before this point
+    // leaves the tapes in both places, which queries deduplicate.
+    index.detach_tape_evidence(&moved)?;
+
+    Ok(Some(json!({
+        "scheme": "monthly",
+        "current": current_month,
+        "moved_tapes": moved.len(),
+        "updated": partitions,
+        "failure_count": failures.len(),
+        "failures": failures,
+    })))
+}
+
+fn maintenance_report_json(report: &MaintenanceReport) -> Value {
+    json!({
+        "pages_before": report.pages_before,
+        "pages_after": report.pages_after,
+        "free_pages_before": report.free_pages_before,
+        "free_pages_after": report.free_pages_after,
+        "converted_to_incremental_vacuum": report.converted_to_incremental_vacuum,
+        "wal_frames_checkpointed": report.wal_frames_checkpointed,
+        "checkpoint_busy": report.checkpoint_busy,
+    })
 }
 
 fn discover_ingest_candidates(
@@ -1516,10 +4531,7 @@ fn discover_ingest_candidates(
                 if entry_path.starts_with(scope_root.join(".engram")) {
                     continue;
                 }
-                let extension = entry_path
-                    .extension()
-                    .and_then(|value| value.to_str())
-                    .map(|value| value.to_ascii_lowercase());
+                let extension = transcript_extension(entry_path);
                 if matches!(extension.as_deref(), Some("json") | Some("jsonl")) {
                     candidates.push(entry_path.to_path_buf());
                 }
@@ -1527,14 +4539,11 @@ fn discover_ingest_candidates(
             continue;
         }
 
-        let extension = canonical
-            .extension()
-            .and_then(|value| value.to_str())
-            .map(|value| value.to_ascii_lowercase());
+        let extension = transcript_extension(&canonical);
         if !matches!(extension.as_deref(), Some("json") | Some("jsonl")) {
             failures.push(json!({
                 "path": path_string(&canonical),
-                "error": "path is not a .json/.jsonl transcript candidate",
+                "error": "path is not a .json/.jsonl (or .gz) transcript candidate",
             }));
             continue;
         }
@@ -1553,6 +4562,160 @@ fn discover_ingest_candidates(
     Ok((candidates, failures))
 }
 
+/// `ingest --reconvert`: regenerate every tape whose adapter has shipped a
+/// newer version since it was converted, from the same raw bytes, then drop
+/// the old tape from the store, the index (and its partitions) and the
+/// ingest cursor. Tapes whose raw source is gone or rewritten are skipped.
+fn reconvert_tapes(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError> {
+    let ignore_rules = ignore_rules(context)?;
+    ensure_db_parent(&context.db_path)?;
+    let index = open_index(context)?;
+    let partitions =
+        list_partitions(&context.db_path).map_err(|err| CliError::io("read_dir_error", err))?;
+    let mut reconverted = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failures = Vec::new();
+    for (tape_id, origin) in index.converted_tapes()? {
+        let Some(adapter) = adapter_id_from_name(&origin.adapter) else {
+            skipped.push(json!({"tape_id": tape_id, "reason": "unknown_adapter"}));
+            continue;
+        };
+        if !adapter_version_is_older(&origin.adapter_version, adapter.version()) {
+            continue;
+        }
+        let from_raw_copy;
+        let raw = match read_source_range(&origin) {
+            Ok(raw) => {
+                from_raw_copy = false;
+                raw
+            }
+            Err(_) if let Some(raw) = read_raw_copy(paths, &origin) => {
+                from_raw_copy = true;
+                raw
+            }
+            Err(reason) => {
+                skipped.push(json!({"tape_id": tape_id, "path": origin.path, "reason": reason}));
+                continue;
+            }
+        };
+        let converted = std::str::from_utf8(&raw)
+            .map_err(|err| err.to_string())
+            .and_then(|input| {
+                convert_with_adapter(adapter, input)
+                    .map(|normalized| (input, normalized))
+                    .map_err(|err| err.to_string())
+            });
+        let (input, normalized) = match converted {
+            Ok(value) => value,
+            Err(error) => {
+                failures.push(json!({"tape_id": tape_id, "path": origin.path, "error": error}));
+                continue;
+            }
+        };
+        let new_origin = TapeOrigin::for_input(&origin.path, origin.byte_range[0], &raw, adapter);
+        let (normalized, _) = drop_ignored_events(&normalized, &ignore_rules)?;
+        let (normalized, _) = apply_binary_policy(&normalized, &context.binary_files)?;
+        let (normalized, _) = sample_large_fields(&normalized, &context.sample_outputs)?;
+        let normalized = if context.retain_edits {
+            let (normalized, objects) = stamp_edit_objects(&normalized)?;
+            retain_edit_objects(paths, &objects)?;
+            normalized
+        } else {
+            normalized
+        };
+        let normalized = normalize_tape_evidence(context, normalized)?;
+        let mut normalized = stamp_origin(&normalized, &new_origin)?;
+        // Snapshots and the environment describe the workspace and machine
+        // when the session was first ingested; re-reading them now would
+        // record different ones.
+        if let Some(path) = resolve_tape_path(context, &tape_id) {
+            let content = read_tape_content(&path)?;
+            if let Some(environment) = extract_meta(&parse_jsonl_events(&content)?)
+                .and_then(|meta| meta.get("environment").cloned())
+            {
+                normalized = stamp_meta_field(&normalized, "environment", environment)?;
+            }
+            for line in content.lines() {
+                if serde_json::from_str::<Value>(line)
+                    .is_ok_and(|row| row.get("k").and_then(Value::as_str) == Some("state.snapshot"))
+                {
+                    normalized.push_str(line);
+                    normalized.push('\n');
+                }
+            }
+        }
+        let events = parse_jsonl_events(&normalized)?;
+        let new_tape_id = tape_id_for_contents(&normalized);
+        // Forget first: an adapter bump that did not change this tape's
+        // output yields the same id, which is then simply re-indexed.
+        let old = std::slice::from_ref(&tape_id);
+        index.forget_tapes(old)?;
+        for (_, path) in &partitions {
+            SqliteIndex::open(&path_string(path))?.forget_tapes(old)?;
+        }
+        let new_tape_path = tape_path_for_tapes_dir(&context.tapes_dir, &new_tape_id);
+        if !new_tape_path.exists() {
+            let compressed =
+                compress_jsonl(&normalized).map_err(|err| CliError::io("compress_error", err))?;
+            atomic_write(&new_tape_path, &compressed)
+                .map_err(|err| CliError::io("write_error", err))?;
+        }
+        if !index.has_tape(&new_tape_id)? {
+            index.ingest_tape_events_with_dispatch(
+                &new_tape_id,
+                &events,
+                &extract_dispatch_links_from_transcript(input),
+                context.link_threshold,
+            )?;
+            index.record_tape_metrics(&new_tape_id, &tape_metrics_from_jsonl(&normalized))?;
+            record_tape_events(&index, &new_tape_id, &normalized, context)?;
+        }
+        index.set_tape_converted_at(&new_tape_id, &now_iso8601())?;
+        if let Err(err) = embed_tape_events(
+            &index,
+            context.embeddings.as_ref(),
+            &new_tape_id,
+            &normalized,
+        ) {
+            failures.push(json!({"tape_id": new_tape_id, "error": err.message}));
+        }
+
+        if new_tape_id != tape_id {
+            match fs::remove_file(tape_path_for_tapes_dir(&context.tapes_dir, &tape_id)) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(CliError::io("remove_error", err)),
+            }
+        }
+        let source = Path::new(&origin.path);
+        if let Some(mut state) = load_ingest_state_for_path(paths, source)?
+            && state.tape_id == tape_id
+        {
+            state.tape_id = new_tape_id.clone();
+            state.adapter = adapter.as_str().to_string();
+            save_ingest_state_for_path(paths, source, &state)?;
+        }
+        reconverted.push(json!({
+            "tape_id": tape_id,
+            "new_tape_id": new_tape_id,
+            "path": origin.path,
+            "adapter": adapter.as_str(),
+            "from_version": origin.adapter_version,
+            "to_version": adapter.version(),
+            "from_raw_copy": from_raw_copy,
+        }));
+    }
+
+    print_json(&json!({
+        "status": if failures.is_empty() { "ok" } else { "partial" },
+        "reconverted_tapes": reconverted.len(),
+        "reconverted": reconverted,
+        "skipped": skipped,
+        "failure_count": failures.len(),
+        "failures": failures,
+    }))
+}
+
 fn adapter_id_from_name(raw: &str) -> Option<AdapterId> {
     match raw {
         "claude-code" => Some(AdapterId::ClaudeCode),
@@ -1561,6 +4724,8 @@ fn adapter_id_from_name(raw: &str) -> Option<AdapterId> {
         "gemini-cli" => Some(AdapterId::GeminiCli),
         "cursor" => Some(AdapterId::Cursor),
         "openclaw" => Some(AdapterId::OpenClaw),
+        "chatgpt" => Some(AdapterId::ChatGpt),
+        "vscode-chat" => Some(AdapterId::VsCodeChat),
         _ => None,
     }
 }
@@ -1597,17 +4762,48 @@ fn save_ingest_state_for_path(
     atomic_write(&state_path, content.as_bytes()).map_err(|err| CliError::io("write_error", err))
 }
 
-fn build_cursor_guard(path: &Path, byte_cursor: u64) -> Result<IngestCursorGuard, CliError> {
-    let guard_len = usize::min(CURSOR_GUARD_WINDOW, byte_cursor as usize);
-    let guard_offset = byte_cursor.saturating_sub(guard_len as u64);
-    let mut bytes = vec![0u8; guard_len];
-    if guard_len > 0 {
+/// All bytes of an ingest source: `unpacked` when a `.gz` source was
+/// already decompressed, the file otherwise.
+fn read_ingest_source(path: &Path, unpacked: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    match unpacked {
+        Some(bytes) => Ok(bytes.to_vec()),
+        None => fs::read(path),
+    }
+}
+
+/// `len` bytes of an ingest source starting at `offset`.
+fn read_ingest_window(
+    path: &Path,
+    unpacked: Option<&[u8]>,
+    offset: u64,
+    len: usize,
+) -> Result<Vec<u8>, CliError> {
+    if let Some(bytes) = unpacked {
+        let start = usize::try_from(offset).unwrap_or(usize::MAX);
+        return bytes
+            .get(start..start.saturating_add(len))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| CliError::new("read_error", "cursor window is past end of input"));
+    }
+    let mut bytes = vec![0u8; len];
+    if len > 0 {
         let mut file = File::open(path).map_err(|err| CliError::io("read_error", err))?;
-        file.seek(SeekFrom::Start(guard_offset))
+        file.seek(SeekFrom::Start(offset))
             .map_err(|err| CliError::io("read_error", err))?;
         file.read_exact(&mut bytes)
             .map_err(|err| CliError::io("read_error", err))?;
     }
+    Ok(bytes)
+}
+
+fn build_cursor_guard(
+    path: &Path,
+    unpacked: Option<&[u8]>,
+    byte_cursor: u64,
+) -> Result<IngestCursorGuard, CliError> {
+    let guard_len = usize::min(CURSOR_GUARD_WINDOW, byte_cursor as usize);
+    let guard_offset = byte_cursor.saturating_sub(guard_len as u64);
+    let bytes = read_ingest_window(path, unpacked, guard_offset, guard_len)?;
     Ok(IngestCursorGuard {
         offset: guard_offset,
         len: guard_len as u32,
@@ -1615,8 +4811,23 @@ fn build_cursor_guard(path: &Path, byte_cursor: u64) -> Result<IngestCursorGuard
     })
 }
 
+fn build_cursor_head(
+    path: &Path,
+    unpacked: Option<&[u8]>,
+    byte_cursor: u64,
+) -> Result<IngestCursorGuard, CliError> {
+    let head_len = usize::min(CURSOR_GUARD_WINDOW, byte_cursor as usize);
+    let bytes = read_ingest_window(path, unpacked, 0, head_len)?;
+    Ok(IngestCursorGuard {
+        offset: 0,
+        len: head_len as u32,
+        hash: sha256_hex_bytes(&bytes),
+    })
+}
+
 fn ingest_cursor_guard_matches(
     path: &Path,
+    unpacked: Option<&[u8]>,
     guard: &IngestCursorGuard,
     file_len: u64,
 ) -> Result<bool, CliError> {
@@ -1624,100 +4835,164 @@ fn ingest_cursor_guard_matches(
     if guard_end > file_len {
         return Ok(false);
     }
-    let mut bytes = vec![0u8; guard.len as usize];
-    if !bytes.is_empty() {
-        let mut file = File::open(path).map_err(|err| CliError::io("read_error", err))?;
-        file.seek(SeekFrom::Start(guard.offset))
-            .map_err(|err| CliError::io("read_error", err))?;
-        file.read_exact(&mut bytes)
-            .map_err(|err| CliError::io("read_error", err))?;
-    }
+    let bytes = read_ingest_window(path, unpacked, guard.offset, guard.len as usize)?;
     Ok(sha256_hex_bytes(&bytes) == guard.hash)
 }
 
-fn complete_ingest_prefix_len(path: &Path, bytes: &[u8]) -> usize {
-    let extension = path
-        .extension()
-        .and_then(|value| value.to_str())
-        .map(|value| value.to_ascii_lowercase());
-    if matches!(extension.as_deref(), Some("json")) {
-        return bytes.len();
+/// Cursor states keyed by the length and hash of the source head they were
+/// taken from. Log rotation moves a transcript (often compressing it) and
+/// starts a fresh file under the old name; matching heads lets the moved
+/// copy continue from the cursor it was ingested to instead of importing
+/// the same session again.
+fn cursor_states_by_head(paths: &RepoPaths) -> HashMap<(u32, String), IngestFileState> {
+    let mut out = HashMap::new();
+    let Ok(entries) = fs::read_dir(&paths.cursors) else {
+        return out;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let Ok(state) = serde_json::from_str::<IngestFileState>(&content) else {
+            continue;
+        };
+        if let Some(head) = state.head.clone() {
+            out.insert((head.len, head.hash), state);
+        }
     }
-    complete_jsonl_prefix_len(bytes)
+    out
 }
 
-fn complete_jsonl_prefix_len(bytes: &[u8]) -> usize {
-    let mut offset = 0usize;
-    let mut complete = 0usize;
-
-    while offset < bytes.len() {
-        if let Some(rel_newline) = bytes[offset..].iter().position(|value| *value == b'\n') {
-            let line_end = offset + rel_newline + 1;
-            let mut line = &bytes[offset..line_end];
-            if let Some(stripped) = line.strip_suffix(b"\n") {
-                line = stripped;
-            }
-            if let Some(stripped) = line.strip_suffix(b"\r") {
-                line = stripped;
-            }
-            if line.is_empty() {
-                complete = line_end;
-                offset = line_end;
-                continue;
-            }
-            if serde_json::from_slice::<Value>(line).is_ok() {
-                complete = line_end;
-                offset = line_end;
-                continue;
-            }
-            break;
+/// The cursor state of the source this one was rotated from, if any.
+fn rotated_cursor_state(
+    by_head: &HashMap<(u32, String), IngestFileState>,
+    path: &Path,
+    unpacked: Option<&[u8]>,
+    source_len: u64,
+) -> Result<Option<IngestFileState>, CliError> {
+    let lens = by_head
+        .keys()
+        .map(|(len, _)| *len)
+        .filter(|len| *len > 0 && u64::from(*len) <= source_len)
+        .collect::<BTreeSet<_>>();
+    let Some(&longest) = lens.last() else {
+        return Ok(None);
+    };
+    let head = read_ingest_window(path, unpacked, 0, longest as usize)?;
+    for len in lens.into_iter().rev() {
+        let key = (len, sha256_hex_bytes(&head[..len as usize]));
+        if let Some(state) = by_head.get(&key) {
+            return Ok(Some(state.clone()));
         }
+    }
+    Ok(None)
+}
 
-        let mut line = &bytes[offset..];
-        if let Some(stripped) = line.strip_suffix(b"\r") {
-            line = stripped;
-        }
-        if line.is_empty() {
-            break;
-        }
-        if serde_json::from_slice::<Value>(line).is_ok() {
-            complete = bytes.len();
-        }
-        break;
+fn complete_ingest_prefix_len(path: &Path, bytes: &[u8]) -> usize {
+    let extension = transcript_extension(path);
+    if matches!(extension.as_deref(), Some("json")) {
+        return bytes.len();
     }
+    complete_jsonl_prefix_len(bytes)
+}
 
+/// Bytes of `bytes` that form finished lines. Every newline-terminated line
+/// counts, including one that is not JSON (a crash-truncated write,
+/// interleaved log text), which adapters skip; an unterminated last line
+/// counts only once it parses.
+fn complete_jsonl_prefix_len(bytes: &[u8]) -> usize {
+    let complete = bytes
+        .iter()
+        .rposition(|value| *value == b'\n')
+        .map_or(0, |at| at + 1);
+    let mut tail = &bytes[complete..];
+    if complete == 0 {
+        tail = tail.strip_prefix("\u{feff}".as_bytes()).unwrap_or(tail);
+    }
+    let tail = tail.strip_suffix(b"\r").unwrap_or(tail);
+    if !tail.is_empty() && serde_json::from_slice::<Value>(tail).is_ok() {
+        return bytes.len();
+    }
     complete
 }
 
 fn record_transcript(
     paths: &RepoPaths,
-    db_path: &Path,
+    context: &RuntimeContext,
     transcript: &str,
     extra: Value,
     command_summary: Option<Value>,
+    session: Option<&str>,
 ) -> Result<(), CliError> {
-    let events = parse_jsonl_events(transcript)?;
-    let dispatch_links = extract_dispatch_links_from_transcript(transcript);
-    let tape_id = tape_id_for_contents(transcript);
+    let transcript = stamp_environment(
+        transcript,
+        &TapeEnvironment::capture(&context.environment, None),
+    )?;
+    let mut payload = store_transcript(paths, context, &transcript)?;
+    payload.insert("record".to_string(), extra);
+    if let Some(command_summary) = command_summary {
+        payload.insert("recorded_command".to_string(), command_summary);
+    }
+    if let Some(name) = session {
+        let tape_id = payload
+            .get("tape_id")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let (manifest, part) = append_session_part(&paths.sessions, name, &tape_id, &now_iso8601())
+            .map_err(|err| CliError::new("session_write_error", err.to_string()))?;
+        payload.insert(
+            "session".to_string(),
+            json!({
+                "name": name,
+                "ref": format!("{SESSION_REF_PREFIX}{name}"),
+                "part": part.part,
+                "part_count": manifest.parts.len(),
+            }),
+        );
+    }
+    print_json(&Value::Object(payload))
+}
+
+/// Store and index a normalized transcript as a content-addressed tape,
+/// returning the tape summary fields of the record payload.
+fn store_transcript(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    transcript: &str,
+) -> Result<Map<String, Value>, CliError> {
+    let db_path = context.db_path.as_path();
+    let transcript = normalize_tape_evidence(context, transcript.to_string())?;
+    let events = parse_jsonl_events(&transcript)?;
+    let dispatch_links = extract_dispatch_links_from_transcript(&transcript);
+    let tape_id = tape_id_for_contents(&transcript);
     let tape_path = tape_path_for_id(paths, &tape_id);
     let tape_file_exists = tape_path.exists();
     ensure_db_parent(db_path)?;
-    let index = SqliteIndex::open(&path_string(db_path))?;
+    let index = open_index_at(context, db_path)?;
     let already_indexed = index.has_tape(&tape_id)?;
+    let mut watchlist_hits = Vec::new();
 
     if !already_indexed {
         index.ingest_tape_events_with_dispatch(
             &tape_id,
             &events,
             &dispatch_links,
-            LINK_THRESHOLD_DEFAULT,
+            context.link_threshold,
         )?;
+        index.record_tape_metrics(&tape_id, &tape_metrics_from_jsonl(&transcript))?;
+        record_tape_events(&index, &tape_id, &transcript, context)?;
+        watchlist_hits = notify_watchlist(paths, context, &tape_id, &events)?;
     }
     if !tape_file_exists {
         let compressed =
-            compress_jsonl(transcript).map_err(|err| CliError::io("compress_error", err))?;
+            compress_jsonl(&transcript).map_err(|err| CliError::io("compress_error", err))?;
         atomic_write(&tape_path, &compressed).map_err(|err| CliError::io("write_error", err))?;
     }
+    let embedding_error =
+        embed_tape_events(&index, context.embeddings.as_ref(), &tape_id, &transcript)
+            .err()
+            .map(|err| err.message);
 
     let compressed_len = fs::metadata(&tape_path)
         .map_err(|err| CliError::io("metadata_error", err))?
@@ -1737,30 +5012,93 @@ fn record_transcript(
     payload.insert("already_indexed".to_string(), json!(already_indexed));
     payload.insert("tape_file_exists".to_string(), json!(tape_file_exists));
     payload.insert("meta".to_string(), json!(extract_meta(&events)));
-    payload.insert("record".to_string(), extra);
-    if let Some(command_summary) = command_summary {
-        payload.insert("recorded_command".to_string(), command_summary);
+    if let Some(message) = embedding_error {
+        payload.insert("embedding_error".to_string(), json!(message));
     }
-
-    print_json(&Value::Object(payload))
+    if !watchlist_hits.is_empty() {
+        payload.insert("watchlist".to_string(), json!(watchlist_hits));
+    }
+    if let Some(report) = auto_maintain(&index, context, usize::from(!already_indexed))? {
+        payload.insert("maintenance".to_string(), report);
+    }
+    Ok(payload)
 }
 
 fn now_iso8601() -> String {
     Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
 }
 
+/// The `state.snapshot` event for a freshly converted tape, taken in the
+/// tape's project root (its meta `cwd`), or `cwd` when it names none.
+fn state_snapshot_event(normalized: &str, cwd: &Path) -> Option<Value> {
+    let root = normalized
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|row| row.get("k").and_then(Value::as_str) == Some("meta"))
+        .and_then(|meta| meta.get("cwd").and_then(Value::as_str).map(PathBuf::from))
+        .filter(|root| root.is_dir())
+        .unwrap_or_else(|| cwd.to_path_buf());
+    snapshot_event(
+        normalized,
+        &root,
+        git_head(&root).as_deref(),
+        &now_iso8601(),
+    )
+}
+
+/// Compare the files of a tape's latest `state.snapshot` with the disk, so
+/// explain can tell whether the code a session left behind is still there.
+fn workspace_state_json(rows: &[TapeRow]) -> Option<Value> {
+    let snapshot = rows
+        .iter()
+        .rev()
+        .find(|row| row.value.get("k").and_then(Value::as_str) == Some("state.snapshot"))?;
+    let root = PathBuf::from(snapshot.value.get("root").and_then(Value::as_str)?);
+    let files = snapshot
+        .value
+        .get("files")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| {
+            let file = entry.get("file").and_then(Value::as_str)?;
+            let sha256 = entry.get("sha256").and_then(Value::as_str);
+            Some(json!({
+                "file": file,
+                "state": snapshot_file_state(&root, file, sha256).as_str(),
+            }))
+        })
+        .collect::<Vec<_>>();
+    let drifted = files
+        .iter()
+        .filter(|file| file["state"] != SnapshotFileState::Unchanged.as_str())
+        .count();
+    Some(json!({
+        "snapshot_at": snapshot.value.get("t"),
+        "head": snapshot.value.get("head"),
+        "state": if drifted == 0 { "unchanged" } else { "drifted" },
+        "drifted_files": drifted,
+        "files": files,
+    }))
+}
+
 fn git_head(cwd: &Path) -> Option<String> {
+    git_output(cwd, &["rev-parse", "HEAD"])
+}
+
+/// Trimmed stdout of a successful, non-empty `git` run in `cwd`.
+fn git_output(cwd: &Path, args: &[&str]) -> Option<String> {
     let output = ProcessCommand::new("git")
-        .arg("rev-parse")
-        .arg("HEAD")
+        .args(args)
         .current_dir(cwd)
         .output()
         .ok()?;
     if !output.status.success() {
         return None;
     }
-    let head = String::from_utf8(output.stdout).ok()?;
-    let trimmed = head.trim();
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let trimmed = stdout.trim();
     if trimmed.is_empty() {
         None
     } else {
@@ -1768,10 +5106,41 @@ fn git_head(cwd: &Path) -> Option<String> {
     }
 }
 
-fn cmd_tapes(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError> {
+fn cmd_tapes(paths: &RepoPaths, context: &RuntimeContext, args: TapesArgs) -> Result<(), CliError> {
     ensure_local_store(paths)?;
     print_context_conspicuity(context);
+    let index = if args.with_metrics || !args.issue.is_empty() || !args.project.is_empty() {
+        ensure_db_parent(&context.db_path)?;
+        Some(open_index(context)?)
+    } else {
+        None
+    };
+    let issue_tapes = match index.as_ref() {
+        Some(index) if !args.issue.is_empty() => {
+            let mut tape_ids = HashSet::new();
+            for key in &args.issue {
+                tape_ids.extend(index.tapes_with_issue(key)?);
+            }
+            Some(tape_ids)
+        }
+        _ => None,
+    };
+    let project_tapes = match index.as_ref() {
+        Some(index) if !args.project.is_empty() => Some(tapes_in_projects(index, &args.project)?),
+        _ => None,
+    };
     let mut tapes = Vec::new();
+    let mut session_parts = HashMap::new();
+    for session in list_record_sessions(&paths.sessions)
+        .map_err(|err| CliError::new("session_read_error", err.to_string()))?
+    {
+        for part in session.parts {
+            session_parts.insert(
+                part.tape_id,
+                json!({"name": session.name, "part": part.part}),
+            );
+        }
+    }
 
     let entries = fs::read_dir(&paths.tapes).map_err(|err| CliError::io("read_dir_error", err))?;
     for entry in entries {
@@ -1784,10 +5153,19 @@ fn cmd_tapes(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError
         let Some(tape_id) = tape_id_from_path(&path) else {
             continue;
         };
+        if issue_tapes
+            .as_ref()
+            .is_some_and(|tape_ids| !tape_ids.contains(&tape_id))
+            || project_tapes
+                .as_ref()
+                .is_some_and(|tape_ids| !tape_ids.contains(&tape_id))
+        {
+            continue;
+        }
 
         let bytes = fs::read(&path).map_err(|err| CliError::io("read_error", err))?;
-        let content =
-            decompress_jsonl(&bytes).map_err(|err| CliError::io("decompress_error", err))?;
+        let content = decompress_jsonl(&bytes)
+            .map_err(|err| CliError::new("decompress_error", err.to_string()))?;
         let events = parse_jsonl_events(&content)?;
         let meta = extract_meta(&events);
         let timestamp = meta
@@ -1796,14 +5174,46 @@ fn cmd_tapes(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError
             .and_then(Value::as_str)
             .map(ToOwned::to_owned);
 
-        tapes.push(json!({
+        let mut tape = json!({
             "tape_id": tape_id,
             "path": path,
             "compressed_bytes": bytes.len(),
             "event_count": events.len(),
             "timestamp": timestamp,
             "meta": meta,
-        }));
+        });
+        if args.with_metrics
+            && let Some(index) = index.as_ref()
+        {
+            let metrics = match index.tape_metrics(&tape_id)? {
+                Some(metrics) => metrics,
+                None => tape_metrics_from_jsonl(&content),
+            };
+            tape["metrics"] = tape_metrics_json(&metrics);
+        }
+        if issue_tapes.is_some()
+            && let Some(index) = index.as_ref()
+        {
+            let keys = index
+                .issue_refs(&tape_id)?
+                .into_iter()
+                .map(|issue| issue.key)
+                .collect::<BTreeSet<_>>();
+            tape["issues"] = json!(keys);
+        }
+        if project_tapes.is_some()
+            && let Some(index) = index.as_ref()
+        {
+            tape["projects"] = json!(index.tape_projects(&tape_id)?);
+        }
+        let thrash_loops = thrash_loops_from_jsonl(&content);
+        if !thrash_loops.is_empty() {
+            tape["thrash_loops"] = thrash_loops_json(&thrash_loops);
+        }
+        if let Some(session) = session_parts.remove(&tape_id) {
+            tape["session"] = session;
+        }
+        tapes.push(tape);
     }
 
     tapes.sort_by(|a, b| {
@@ -1817,428 +5227,3073 @@ fn cmd_tapes(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError
     print_json(&json!({ "tapes": tapes }))
 }
 
-fn cmd_show(paths: &RepoPaths, context: &RuntimeContext, args: ShowArgs) -> Result<(), CliError> {
+/// How work flowed across sessions: every indexed tape, linked to the next
+/// tape of the same session, its subagents, the tapes it dispatched to or
+/// started, and the tapes that edited the same files.
+fn cmd_sessions(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: SessionsArgs,
+) -> Result<(), CliError> {
     ensure_local_store(paths)?;
     print_context_conspicuity(context);
-    let Some(tape_path) = resolve_tape_path(context, &args.tape_id) else {
-        return Err(CliError::new(
-            "tape_not_found",
-            format!("tape `{}` not found", args.tape_id),
-        ));
-    };
-
-    let content = read_tape_content(&tape_path)?;
-    if args.raw {
-        print!("{content}");
-        return Ok(());
+    ensure_db_parent(&context.db_path)?;
+    let mut nodes = BTreeMap::<String, SessionNode>::new();
+    let mut sourced = BTreeMap::<String, SourcedTape>::new();
+    let mut files_by_tape = BTreeMap::<String, BTreeSet<String>>::new();
+    let mut dispatches = Vec::new();
+    let mut links = Vec::new();
+    for index in open_query_indexes(context)? {
+        for (tape_id, metrics) in index.all_tape_metrics()? {
+            nodes.entry(tape_id.clone()).or_insert(SessionNode {
+                tape_id,
+                harness: metrics.harness,
+                session_id: metrics.session_id,
+                started_at: metrics.started_at,
+            });
+        }
+        for (tape_id, origin) in index.converted_tapes()? {
+            sourced.entry(tape_id.clone()).or_insert(SourcedTape {
+                tape_id,
+                source_path: origin.path,
+                source_start: origin.byte_range[0],
+            });
+        }
+        for (tape_id, record) in index.events_matching(&["code.edit".to_string()], None, None)? {
+            if let Some(file) = record.file {
+                files_by_tape.entry(tape_id).or_default().insert(file);
+            }
+        }
+        dispatches.extend(index.all_dispatch_links()?);
+        links.extend(
+            index
+                .all_spawn_links()?
+                .into_iter()
+                .map(|link| SessionLink {
+                    from: link.parent_tape_id,
+                    to: link.child_tape_id,
+                    kind: SessionLinkKind::Spawned,
+                    label: Some(link.command),
+                }),
+        );
     }
 
-    let events = parse_jsonl_events(&content)?;
-    let rows = parse_jsonl_rows(&content)?;
-    let compacted = rows
+    let mut chains = session_id_chains(&nodes.values().cloned().collect::<Vec<_>>());
+    let sourced = sourced.into_values().collect::<Vec<_>>();
+    chains.extend(source_chains(&sourced));
+    for session in list_record_sessions(&paths.sessions)
+        .map_err(|err| CliError::new("session_read_error", err.to_string()))?
+    {
+        let mut parts = session.parts;
+        parts.sort_by_key(|part| part.part);
+        chains.push(parts.into_iter().map(|part| part.tape_id).collect());
+    }
+    links.extend(continuation_links(&chains));
+    links.extend(subagent_links(&sourced));
+    for sent in dispatches
         .iter()
-        .map(|row| compact_event(row.offset, &row.value))
-        .collect::<Vec<_>>();
+        .filter(|link| link.direction == DispatchDirection::Sent)
+    {
+        for received in dispatches.iter().filter(|link| {
+            link.direction == DispatchDirection::Received
+                && link.uuid == sent.uuid
+                && link.tape_id != sent.tape_id
+        }) {
+            links.push(SessionLink {
+                from: sent.tape_id.clone(),
+                to: received.tape_id.clone(),
+                kind: SessionLinkKind::Dispatch,
+                label: None,
+            });
+        }
+    }
+    links.extend(shared_file_links(&files_by_tape, args.min_shared_files));
+    links.sort();
+    links.dedup();
+    for link in &links {
+        for tape_id in [&link.from, &link.to] {
+            nodes.entry(tape_id.clone()).or_insert_with(|| SessionNode {
+                tape_id: tape_id.clone(),
+                harness: None,
+                session_id: None,
+                started_at: None,
+            });
+        }
+    }
+    let mut nodes = nodes.into_values().collect::<Vec<_>>();
+    nodes.sort_by(|a, b| {
+        a.started_at
+            .cmp(&b.started_at)
+            .then_with(|| a.tape_id.cmp(&b.tape_id))
+    });
 
+    if args.graph {
+        match args.format {
+            SessionGraphFormat::Dot => print!("{}", session_graph_dot(&nodes, &links)),
+            SessionGraphFormat::Mermaid => print!("{}", session_graph_mermaid(&nodes, &links)),
+        }
+        return Ok(());
+    }
     print_json(&json!({
-        "tape_id": args.tape_id,
-        "path": tape_path,
-        "event_count": events.len(),
-        "meta": extract_meta(&events),
-        "events": compacted,
+        "sessions": nodes
+            .iter()
+            .map(|node| json!({
+                "tape_id": node.tape_id,
+                "harness": node.harness,
+                "session_id": node.session_id,
+                "started_at": node.started_at,
+            }))
+            .collect::<Vec<_>>(),
+        "links": links
+            .iter()
+            .map(|link| json!({
+                "from": link.from,
+                "to": link.to,
+                "kind": link.kind.as_str(),
+                "label": link.label,
+            }))
+            .collect::<Vec<_>>(),
     }))
 }
 
-fn cmd_gc(paths: &RepoPaths, context: &RuntimeContext) -> Result<(), CliError> {
+fn cmd_export(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: ExportArgs,
+) -> Result<(), CliError> {
     ensure_local_store(paths)?;
     print_context_conspicuity(context);
     ensure_db_parent(&context.db_path)?;
-    let index = SqliteIndex::open(&path_string(&context.db_path))?;
-    let referenced = index
-        .referenced_tape_ids()?
-        .into_iter()
-        .collect::<HashSet<_>>();
-
-    let mut deleted = Vec::new();
-    let mut kept = 0usize;
-
-    let entries = fs::read_dir(&paths.tapes).map_err(|err| CliError::io("read_dir_error", err))?;
-    for entry in entries {
-        let entry = entry.map_err(|err| CliError::io("read_dir_error", err))?;
-        let path = entry.path();
-        let Some(tape_id) = tape_id_from_path(&path) else {
-            continue;
-        };
-
-        if referenced.contains(&tape_id) {
-            kept += 1;
-            continue;
+    let mut evidence = Vec::new();
+    let mut edges = Vec::new();
+    let mut tapes = BTreeMap::new();
+    for index in open_query_indexes(context)? {
+        evidence.extend(index.evidence_for_file("*", &TimeRange::default())?);
+        edges.extend(index.all_edges()?);
+        if args.format == GraphExportFormat::Cypher {
+            tapes.extend(index.all_tape_metrics()?);
         }
-
-        fs::remove_file(&path).map_err(|err| CliError::io("remove_file_error", err))?;
-        deleted.push(tape_id);
     }
-
-    deleted.sort();
+    let pseudonymizer = export_pseudonymizer(&context.export)?;
+    if let Some(pseudonymizer) = &pseudonymizer {
+        for (_, fragment) in &mut evidence {
+            pseudonymizer.fragment(fragment);
+        }
+        tapes = tapes
+            .into_iter()
+            .map(|(tape_id, mut metrics)| {
+                pseudonymizer.metrics(&mut metrics);
+                (pseudonymizer.tape_id(&tape_id), metrics)
+            })
+            .collect();
+    }
+    let bytes = match args.format {
+        GraphExportFormat::Binary => encode_graph_snapshot(&evidence, &edges),
+        GraphExportFormat::Cypher => cypher_script(&evidence, &edges, &tapes).into_bytes(),
+    };
+    fs::write(&args.graph, &bytes).map_err(|err| CliError::io("write_error", err))?;
+    let anchors = evidence
+        .iter()
+        .map(|(anchor, _)| anchor.as_str())
+        .chain(
+            edges
+                .iter()
+                .flat_map(|edge| [edge.from_anchor.as_str(), edge.to_anchor.as_str()]),
+        )
+        .collect::<HashSet<_>>();
     print_json(&json!({
-        "status": "ok",
-        "deleted_tape_ids": deleted,
-        "deleted_count": deleted.len(),
-        "kept_count": kept,
+        "graph": path_string(&args.graph),
+        "format": match args.format {
+            GraphExportFormat::Binary => "binary",
+            GraphExportFormat::Cypher => "cypher",
+        },
+        "format_version": (args.format == GraphExportFormat::Binary)
+            .then_some(GRAPH_SNAPSHOT_VERSION),
+        "anchors": anchors.len(),
+        "edges": edges.len(),
+        "evidence": evidence.len(),
+        "bytes": bytes.len(),
+        "pseudonymized": pseudonymizer.is_some(),
     }))
 }
 
-fn cmd_explain(
-    cwd: &Path,
+/// The HMAC key for `export.pseudonymize_key_env`, read from the named
+/// environment variable; `None` when exports are not pseudonymized.
+fn export_pseudonymizer(config: &EffectiveExportConfig) -> Result<Option<Pseudonymizer>, CliError> {
+    let Some(var) = config.pseudonymize_key_env.as_deref() else {
+        return Ok(None);
+    };
+    match std::env::var(var) {
+        Ok(key) if !key.is_empty() => Ok(Some(Pseudonymizer::new(key.as_bytes()))),
+        _ => Err(CliError::new(
+            "pseudonymize_key_missing",
+            format!("export.pseudonymize_key_env names {var}, which is not set"),
+        )),
+    }
+}
+
+/// `verify --tapes`: read every tape in the lookup dirs the way queries do
+/// and list the ones that fail, so corruption shows up before a session
+/// window or replay quietly comes back wrong.
+fn cmd_verify(
     paths: &RepoPaths,
     context: &RuntimeContext,
-    args: ExplainArgs,
+    args: VerifyArgs,
 ) -> Result<(), CliError> {
-    ensure_local_store(paths)?;
-    print_context_conspicuity(context);
-    ensure_db_parent(&context.db_path)?;
-
-    let indexes = open_query_indexes(context)?;
-    let target = args
-        .target
-        .clone()
-        .ok_or_else(|| CliError::new("invalid_explain_target", "target is required"))?;
-    let target_kind = classify_explain_target(cwd, context, &indexes, &target, args.anchor)?;
-
-    let mut query_anchors = Vec::new();
-    let mut raw_sessions: Vec<Value>;
-    let mut dispatch_lineage = Vec::new();
-    let mut lineage = Vec::new();
-    let mut tombstones = Vec::new();
-    let mut score_by_session = HashMap::new();
-    let date_filter = DateFilter::parse(args.since.as_deref(), args.until.as_deref())?;
-
-    match target_kind {
-        ExplainTarget::FileRange { file, start, end } => {
-            let span_texts = read_file_span_variants(&cwd.join(file), start, end)?;
-            query_anchors = derive_anchor_candidates(&span_texts);
-            let traversal = ExplainTraversal {
-                min_confidence: args.min_confidence,
-                max_fanout: args.max_fanout,
-                max_edges: args.max_edges,
-                max_depth: args.depth,
-            };
-            let result =
-                explain_across_indexes(&indexes, &query_anchors, traversal, args.forensics)?;
-            let touches =
-                collect_touch_evidence(&indexes, &result.direct, &result.touched_anchors)?;
-            raw_sessions = build_session_windows(context, touches)?;
-            let (chain, dispatch_sessions) =
-                collect_dispatch_upstream_sessions(context, &indexes[0], &raw_sessions)?;
-            dispatch_lineage = chain;
-            raw_sessions.extend(dispatch_sessions);
-            lineage = result.lineage.iter().map(edge_to_json).collect::<Vec<_>>();
-            score_by_session = collect_anchor_scores(&indexes, &query_anchors)?;
-
-            if args.include_deleted {
-                for anchor in &result.touched_anchors {
-                    for index in &indexes {
-                        for tombstone in index.tombstones_for_anchor(anchor)? {
-                            tombstones.push(json!({
-                                "anchor": anchor,
-                                "tape_id": tombstone.tape_id,
-                                "event_offset": tombstone.event_offset,
-                                "file_path": tombstone.file_path,
-                                "range": {
-                                    "start": tombstone.range_at_deletion.start,
-                                    "end": tombstone.range_at_deletion.end
-                                },
-                                "timestamp": tombstone.timestamp,
-                            }));
-                        }
-                    }
-                }
+    if !args.tapes {
+        return Err(CliError::new(
+            "invalid_args",
+            "nothing to verify; pass --tapes",
+        ));
+    }
+    let mut dirs = context.tape_lookup_dirs.clone();
+    push_tape_lookup_dir(&mut dirs, paths.tapes.clone());
+    let mut checked = 0usize;
+    let mut corrupt = Vec::new();
+    let mut salvageable = Vec::new();
+    let mut seen = HashSet::new();
+    for dir in &dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let mut tape_paths = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && tape_id_from_path(path).is_some())
+            .collect::<Vec<_>>();
+        tape_paths.sort();
+        for path in tape_paths {
+            let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+            if !seen.insert(canonical) {
+                continue;
             }
-        }
-        ExplainTarget::FileWhole { file } => {
-            let full_text = fs::read_to_string(cwd.join(file))
-                .map_err(|err| CliError::io("read_span_error", err))?;
-            query_anchors = derive_anchor_candidates(&[full_text]);
-            let traversal = ExplainTraversal {
-                min_confidence: args.min_confidence,
-                max_fanout: args.max_fanout,
-                max_edges: args.max_edges,
-                max_depth: args.depth,
-            };
-            let result =
-                explain_across_indexes(&indexes, &query_anchors, traversal, args.forensics)?;
-            let touches =
-                collect_touch_evidence(&indexes, &result.direct, &result.touched_anchors)?;
-            raw_sessions = build_session_windows(context, touches)?;
-            let (chain, dispatch_sessions) =
-                collect_dispatch_upstream_sessions(context, &indexes[0], &raw_sessions)?;
-            dispatch_lineage = chain;
-            raw_sessions.extend(dispatch_sessions);
-            lineage = result.lineage.iter().map(edge_to_json).collect::<Vec<_>>();
-            score_by_session = collect_anchor_scores(&indexes, &query_anchors)?;
-        }
-        ExplainTarget::Literal(text) => {
-            query_anchors = if args.anchor {
-                vec![text]
-            } else {
-                derive_anchor_candidates(&[text])
-            };
-            let traversal = ExplainTraversal {
-                min_confidence: args.min_confidence,
-                max_fanout: args.max_fanout,
-                max_edges: args.max_edges,
-                max_depth: args.depth,
+            checked += 1;
+            let Err(err) = read_tape_content(&path) else {
+                continue;
             };
-            let result =
-                explain_across_indexes(&indexes, &query_anchors, traversal, args.forensics)?;
-            let touches =
-                collect_touch_evidence(&indexes, &result.direct, &result.touched_anchors)?;
-            raw_sessions = build_session_windows(context, touches)?;
-            let (chain, dispatch_sessions) =
-                collect_dispatch_upstream_sessions(context, &indexes[0], &raw_sessions)?;
-            dispatch_lineage = chain;
-            raw_sessions.extend(dispatch_sessions);
-            lineage = result.lineage.iter().map(edge_to_json).collect::<Vec<_>>();
-            score_by_session = collect_anchor_scores(&indexes, &query_anchors)?;
-
-            if args.include_deleted {
-                for anchor in &result.touched_anchors {
-                    for index in &indexes {
-                        for tombstone in index.tombstones_for_anchor(anchor)? {
-                            tombstones.push(json!({
-                                "anchor": anchor,
-                                "tape_id": tombstone.tape_id,
-                                "event_offset": tombstone.event_offset,
-                                "file_path": tombstone.file_path,
-                                "range": {
-                                    "start": tombstone.range_at_deletion.start,
-                                    "end": tombstone.range_at_deletion.end
-                                },
-                                "timestamp": tombstone.timestamp,
-                            }));
-                        }
-                    }
+            let mut entry = json!({
+                "tape_id": tape_id_from_path(&path),
+                "path": path_string(&path),
+                "error": err.code,
+                "detail": err.message,
+            });
+            // Only a tape that fails to decompress has an undamaged prefix;
+            // a hash mismatch could be anywhere in it.
+            if err.code == "decompress_error"
+                && let Ok(bytes) = fs::read(&path)
+            {
+                let salvaged = salvage_jsonl(&bytes);
+                let lines = salvaged.content.lines().count();
+                entry["salvageable_lines"] = json!(lines);
+                if lines > 0 {
+                    salvageable.push((path.clone(), salvaged.content));
                 }
             }
+            corrupt.push(entry);
         }
     }
 
-    if args.pretty {
-        print_pretty_explain(&target, &[], &raw_sessions, &tombstones);
-        return Ok(());
+    let mut salvaged = Vec::new();
+    if args.salvage && !salvageable.is_empty() {
+        ensure_db_parent(&context.db_path)?;
+        let _lock = lock_store(context, "verify")?;
+        let index = open_index(context)?;
+        let partitions =
+            list_partitions(&context.db_path).map_err(|err| CliError::io("read_dir_error", err))?;
+        for (path, content) in salvageable {
+            salvaged.push(salvage_tape(
+                paths,
+                context,
+                &index,
+                &partitions,
+                &path,
+                &content,
+            )?);
+        }
     }
 
-    let mut sessions = format_sessions_for_agent(
-        context,
-        &indexes[0],
-        raw_sessions,
-        &score_by_session,
-        args.grep_filter.as_deref(),
-    )?;
-    sessions.retain(|session| session_matches_date_filter(session, &date_filter));
-    annotate_chain_fields(&mut sessions, &dispatch_lineage);
-    sessions.sort_by(|a, b| {
-        let a_depth = a.get("depth").and_then(Value::as_u64).unwrap_or(0);
-        let b_depth = b.get("depth").and_then(Value::as_u64).unwrap_or(0);
-        let a_score = a.get("confidence").and_then(Value::as_f64).unwrap_or(0.0);
-        let b_score = b.get("confidence").and_then(Value::as_f64).unwrap_or(0.0);
-        let a_ts = a.get("timestamp").and_then(Value::as_str).unwrap_or("");
-        let b_ts = b.get("timestamp").and_then(Value::as_str).unwrap_or("");
-        a_depth
-            .cmp(&b_depth)
-            .then_with(|| {
-                b_score
-                    .partial_cmp(&a_score)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            })
-            .then_with(|| b_ts.cmp(a_ts))
+    let mut payload = json!({
+        "status": if corrupt.is_empty() { "ok" } else { "corrupt" },
+        "checked_tapes": checked,
+        "corrupt_count": corrupt.len(),
+        "corrupt": corrupt,
     });
-    if sessions.is_empty() {
-        return Err(CliError::new("no_results", target));
+    if args.salvage {
+        payload["salvaged"] = json!(salvaged);
     }
+    print_json(&payload)
+}
 
-    let (sessions, returned, total, time_range, truncated) = apply_session_truncation(
-        sessions,
-        args.limit,
-        args.offset,
-        context.explain_default_limit,
-    );
-    if sessions.is_empty() {
-        return Err(CliError::new("no_results", target));
-    }
-    let chain_metadata = build_chain_metadata(&sessions);
-    append_metrics(
-        context,
-        "explain",
-        &target,
-        Value::Null,
-        Value::Null,
-        Value::Null,
-        Value::Null,
-    );
+/// Replace the damaged tape at `path` with `content`, the complete lines
+/// salvaged from it: a new tape under the content's id, indexed in place of
+/// the old one when the old one was indexed, with the ingest cursor of its
+/// source pointed at it.
+fn salvage_tape(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    index: &SqliteIndex,
+    partitions: &[(String, PathBuf)],
+    path: &Path,
+    content: &str,
+) -> Result<Value, CliError> {
+    let tape_id = tape_id_from_path(path).unwrap_or_default();
+    let (events, issues) = parse_jsonl_events_lossy(content);
+    let new_tape_id = tape_id_for_contents(content);
+    let new_path = path.with_file_name(format!("{new_tape_id}{TAPE_SUFFIX}"));
+    if !new_path.exists() {
+        let compressed =
+            compress_jsonl(content).map_err(|err| CliError::io("compress_error", err))?;
+        atomic_write(&new_path, &compressed).map_err(|err| CliError::io("write_error", err))?;
+    }
+    let reindexed = index.has_tape(&tape_id)?;
+    if reindexed {
+        let old = std::slice::from_ref(&tape_id);
+        index.forget_tapes(old)?;
+        for (_, partition) in partitions {
+            SqliteIndex::open(&path_string(partition))?.forget_tapes(old)?;
+        }
+        if !index.has_tape(&new_tape_id)? {
+            index.ingest_tape_events_with_dispatch(
+                &new_tape_id,
+                &events,
+                &[],
+                context.link_threshold,
+            )?;
+            index.record_tape_metrics(&new_tape_id, &tape_metrics_from_jsonl(content))?;
+            record_tape_events(index, &new_tape_id, content, context)?;
+        }
+        index.set_tape_converted_at(&new_tape_id, &now_iso8601())?;
+    }
+    if new_tape_id != tape_id {
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(CliError::io("remove_error", err)),
+        }
+    }
+    if let Some(origin) = tape_origin(&events) {
+        let source = Path::new(&origin.path);
+        if let Some(mut state) = load_ingest_state_for_path(paths, source)?
+            && state.tape_id == tape_id
+        {
+            state.tape_id = new_tape_id.clone();
+            save_ingest_state_for_path(paths, source, &state)?;
+        }
+    }
+    Ok(json!({
+        "tape_id": tape_id,
+        "new_tape_id": new_tape_id,
+        "path": path_string(&new_path),
+        "recovered_events": events.len(),
+        "skipped_lines": issues.len(),
+        "reindexed": reindexed,
+    }))
+}
 
-    emit_query_result(
-        &indexes[0],
-        "explain",
-        json!({
-        "query": {
-            "command": "explain",
-            "target": target,
-            "anchors": query_anchors,
-            "grep_filter": args.grep_filter,
-            "limit": args.limit,
-            "offset": args.offset,
-            "min_confidence": args.min_confidence,
-            "since": args.since,
-            "until": args.until,
-            "count": args.count,
-            "max_fanout": args.max_fanout,
-            "max_edges": args.max_edges,
-            "depth": args.depth,
-            "forensics": args.forensics,
-            "include_deleted": args.include_deleted,
-        },
-        "sessions": sessions,
-        "chains": chain_metadata,
-        "lineage": lineage,
-        "dispatch_lineage": dispatch_lineage,
-        "tombstones": tombstones,
-        "stores_queried": indexes.len(),
-        "returned": returned,
-        "total": total,
-        "time_range": time_range,
-        "truncated": truncated,
-        }),
+fn cmd_stats(paths: &RepoPaths, context: &RuntimeContext, args: StatsArgs) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let index = open_index(context)?;
+    let mut all = index.all_tape_metrics()?;
+    if !args.project.is_empty() {
+        let tape_ids = tapes_in_projects(&index, &args.project)?;
+        all.retain(|(tape_id, _)| tape_ids.contains(tape_id));
+    }
+
+    let mut by_harness = HashMap::<String, Vec<&TapeMetrics>>::new();
+    let mut by_model = HashMap::<String, Vec<&TapeMetrics>>::new();
+    for (_, metrics) in &all {
+        by_harness
+            .entry(
+                metrics
+                    .harness
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+            )
+            .or_default()
+            .push(metrics);
+        by_model
+            .entry(
+                metrics
+                    .model
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+            )
+            .or_default()
+            .push(metrics);
+    }
+    let group_json = |key: &str, groups: HashMap<String, Vec<&TapeMetrics>>| {
+        let mut out = groups
+            .into_iter()
+            .map(|(name, members)| {
+                let mut value = aggregate_tape_metrics_json(&members);
+                value[key] = json!(name);
+                value
+            })
+            .collect::<Vec<_>>();
+        out.sort_by(|a, b| {
+            let a_name = a.get(key).and_then(Value::as_str).unwrap_or("");
+            let b_name = b.get(key).and_then(Value::as_str).unwrap_or("");
+            a_name.cmp(b_name)
+        });
+        out
+    };
+
+    let totals = aggregate_tape_metrics_json(&all.iter().map(|(_, m)| m).collect::<Vec<_>>());
+    let mut payload = json!({
+        "status": "ok",
+        "totals": totals,
+        "by_harness": group_json("harness", by_harness),
+        "by_model": group_json("model", by_model),
+    });
+    if !args.project.is_empty() {
+        payload["projects"] = json!(args.project);
+    }
+    payload["storage"] = storage_forecast_json(&index, context)?;
+    print_json(&payload)
+}
+
+/// Tapes with evidence in any of the `projects` entries `names`.
+fn tapes_in_projects(index: &SqliteIndex, names: &[String]) -> Result<HashSet<String>, CliError> {
+    let mut tape_ids = HashSet::new();
+    for name in names {
+        tape_ids.extend(index.tapes_in_project(name)?);
+    }
+    Ok(tape_ids)
+}
+
+fn tape_metrics_json(metrics: &TapeMetrics) -> Value {
+    json!({
+        "event_count": metrics.event_count,
+        "message_count": metrics.message_count,
+        "tool_call_count": metrics.tool_call_count,
+        "tool_error_count": metrics.tool_error_count,
+        "tool_error_rate": metrics.tool_error_rate(),
+        "edit_count": metrics.edit_count,
+        "revert_count": metrics.revert_count,
+        "revert_ratio": metrics.revert_ratio(),
+        "messages_per_edit": metrics.messages_per_edit(),
+        "started_at": metrics.started_at,
+        "ended_at": metrics.ended_at,
+        "duration_secs": metrics.duration_secs,
+        "model": metrics.model,
+        "harness": metrics.harness,
+        "hostname": metrics.hostname,
+        "user": metrics.user,
+        "thrash_loop_count": metrics.thrash_loop_count,
+    })
+}
+
+fn thrash_loops_json(loops: &[ThrashLoop]) -> Value {
+    json!(
+        loops
+            .iter()
+            .map(|thrash| json!({
+                "tool": thrash.tool,
+                "args": thrash.args,
+                "exit": thrash.exit,
+                "repeats": thrash.result_offsets.len(),
+                "offsets": thrash.result_offsets,
+            }))
+            .collect::<Vec<_>>()
     )
 }
 
-fn cmd_grep(paths: &RepoPaths, context: &RuntimeContext, args: GrepArgs) -> Result<(), CliError> {
+fn aggregate_tape_metrics_json(members: &[&TapeMetrics]) -> Value {
+    let mut total = TapeMetrics::default();
+    let mut duration_secs = 0i64;
+    for metrics in members {
+        total.event_count += metrics.event_count;
+        total.message_count += metrics.message_count;
+        total.tool_call_count += metrics.tool_call_count;
+        total.tool_error_count += metrics.tool_error_count;
+        total.edit_count += metrics.edit_count;
+        total.revert_count += metrics.revert_count;
+        total.thrash_loop_count += metrics.thrash_loop_count;
+        duration_secs += metrics.duration_secs.unwrap_or(0).max(0);
+    }
+    json!({
+        "tapes": members.len(),
+        "event_count": total.event_count,
+        "message_count": total.message_count,
+        "tool_call_count": total.tool_call_count,
+        "tool_error_count": total.tool_error_count,
+        "tool_error_rate": total.tool_error_rate(),
+        "edit_count": total.edit_count,
+        "revert_count": total.revert_count,
+        "revert_ratio": total.revert_ratio(),
+        "messages_per_edit": total.messages_per_edit(),
+        "thrash_loop_count": total.thrash_loop_count,
+        "thrashing_tapes": members.iter().filter(|m| m.thrash_loop_count > 0).count(),
+        "duration_secs": duration_secs,
+    })
+}
+
+fn cmd_show(paths: &RepoPaths, context: &RuntimeContext, args: ShowArgs) -> Result<(), CliError> {
     ensure_local_store(paths)?;
     print_context_conspicuity(context);
-    ensure_db_parent(&context.db_path)?;
+    if args.diff {
+        return show_tape_diff(
+            paths,
+            context,
+            &args.tape_id,
+            args.other_tape_id.as_deref().unwrap_or_default(),
+        );
+    }
+    let Some(tape) = read_tape_or_session(paths, context, &args.tape_id)? else {
+        return Err(CliError::new(
+            "tape_not_found",
+            format!("tape `{}` not found", args.tape_id),
+        ));
+    };
 
-    let indexes = open_query_indexes(context)?;
-    let (raw_sessions, grep_rank_by_session) =
-        collect_grep_matches(context, &indexes, &args.pattern)?;
-    let score_by_session = grep_rank_by_session
+    let content = tape.content;
+    if args.origin {
+        return show_tape_origin(paths, context, &args.tape_id, &tape.path, &content);
+    }
+    if args.patch {
+        return show_tape_patch(paths, &args.tape_id, &tape.path, &content);
+    }
+    if args.raw {
+        print!("{content}");
+        return Ok(());
+    }
+
+    let rows = parse_jsonl_rows(&content)?;
+    if args.format == ShowFormat::Markdown {
+        let rows = rows
+            .into_iter()
+            .map(|row| (row.offset, row.value))
+            .collect::<Vec<_>>();
+        print!(
+            "{}",
+            tape_markdown(&args.tape_id, &rows, |edit| {
+                (
+                    edit_side_text(paths, edit, "before").0,
+                    edit_side_text(paths, edit, "after").0,
+                )
+            })
+        );
+        return Ok(());
+    }
+    let events = parse_jsonl_events(&content)?;
+    let compacted = rows
         .iter()
-        .map(|(session_id, rank)| (session_id.clone(), rank.match_count as f32))
-        .collect::<HashMap<_, _>>();
-    let date_filter = DateFilter::parse(args.since.as_deref(), args.until.as_deref())?;
-    let mut sessions =
-        format_sessions_for_agent(context, &indexes[0], raw_sessions, &score_by_session, None)?;
-    sessions.retain(|session| session_matches_date_filter(session, &date_filter));
-    sessions.sort_by(|a, b| compare_grep_sessions(a, b, &grep_rank_by_session));
-    if sessions.is_empty() {
-        return Err(CliError::new("no_results", args.pattern));
+        .map(|row| compact_event(row.offset, &row.value))
+        .collect::<Vec<_>>();
+
+    let mut payload = json!({
+        "tape_id": args.tape_id,
+        "path": tape.path,
+        "event_count": events.len(),
+        "meta": extract_meta(&events),
+        "events": compacted,
+    });
+    if let Some(parts) = tape.parts {
+        payload["parts"] = json!(parts);
     }
+    print_json(&payload)
+}
 
-    let (sessions, returned, total, time_range, truncated) = apply_session_truncation(
-        sessions,
-        args.limit,
-        args.offset,
-        context.explain_default_limit,
-    );
-    if sessions.is_empty() {
-        return Err(CliError::new("no_results", args.pattern));
+/// Trace a tape back to its raw source: the index row when the tape is
+/// indexed (it also knows the conversion time), else the tape's own meta.
+/// `show --patch`: every edit with recoverable text, rendered per file in
+/// chronological order. Text comes from the objects dir when the edit
+/// carries `before_object`/`after_object`, else from the tape itself.
+fn show_tape_patch(
+    paths: &RepoPaths,
+    tape_id: &str,
+    tape_path: &Path,
+    content: &str,
+) -> Result<(), CliError> {
+    let edits = chronological_edits(content)?;
+
+    let mut files: Vec<(String, Vec<u64>, String)> = Vec::new();
+    let mut from_objects = 0usize;
+    let mut without_content = Vec::new();
+    for row in &edits {
+        let file = row.value.get("file").and_then(Value::as_str).unwrap_or("");
+        let (before, before_object) = edit_side_text(paths, &row.value, "before");
+        let (after, after_object) = edit_side_text(paths, &row.value, "after");
+        if before.is_none() && after.is_none() {
+            without_content.push(row.offset);
+            continue;
+        }
+        if before_object || after_object {
+            from_objects += 1;
+        }
+        let start = |key: &str| {
+            row.value
+                .get(key)
+                .and_then(|range| range.get(0))
+                .and_then(Value::as_u64)
+                .map_or(1, |line| line as u32)
+        };
+        let hunks = unified_hunks(
+            before.as_deref().unwrap_or(""),
+            after.as_deref().unwrap_or(""),
+            start("before_range"),
+            start("after_range"),
+        );
+        let index = match files.iter().position(|(name, _, _)| name == file) {
+            Some(index) => index,
+            None => {
+                files.push((
+                    file.to_string(),
+                    Vec::new(),
+                    format!("--- a/{file}\n+++ b/{file}\n"),
+                ));
+                files.len() - 1
+            }
+        };
+        files[index].1.push(row.offset);
+        files[index].2.push_str(&hunks);
     }
 
-    let metrics_sessions = if args.count { Vec::new() } else { sessions };
-    append_metrics(
-        context,
-        "grep",
-        &args.pattern,
-        Value::Null,
-        Value::Null,
-        Value::Null,
-        Value::Null,
-    );
+    print_json(&json!({
+        "tape_id": tape_id,
+        "path": tape_path,
+        "edit_count": edits.len() - without_content.len(),
+        "edits_from_objects": from_objects,
+        "edits_without_content": without_content,
+        "files": files
+            .into_iter()
+            .map(|(file, offsets, patch)| json!({"file": file, "edits": offsets, "patch": patch}))
+            .collect::<Vec<_>>(),
+    }))
+}
 
-    emit_query_result(
-        &indexes[0],
-        "grep",
-        json!({
-        "query": {
-            "command": "grep",
-            "pattern": args.pattern,
-            "limit": args.limit,
-            "offset": args.offset,
-            "since": args.since,
-            "until": args.until,
-            "count": args.count,
-        },
-        "sessions": metrics_sessions,
-        "lineage": [],
-        "dispatch_lineage": [],
-        "tombstones": [],
-        "stores_queried": indexes.len(),
-        "returned": returned,
-        "total": total,
-        "time_range": time_range,
-        "truncated": truncated,
-        }),
-    )
+/// The tape's `code.edit` rows ordered by timestamp, then offset.
+fn chronological_edits(content: &str) -> Result<Vec<TapeRow>, CliError> {
+    let mut edits = parse_jsonl_rows(content)?
+        .into_iter()
+        .filter(|row| row.value.get("k").and_then(Value::as_str) == Some("code.edit"))
+        .collect::<Vec<_>>();
+    edits.sort_by(|left, right| {
+        let time = |row: &TapeRow| {
+            row.value
+                .get("t")
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+        };
+        time(left)
+            .cmp(&time(right))
+            .then(left.offset.cmp(&right.offset))
+    });
+    Ok(edits)
+}
+
+/// One side of an edit: the retained object when present and intact
+/// (flagged `true`), else the text inline in the tape.
+fn edit_side_text(paths: &RepoPaths, edit: &Value, side: &str) -> (Option<String>, bool) {
+    let retained = edit
+        .get(format!("{side}_object"))
+        .and_then(Value::as_str)
+        .and_then(|sha256| {
+            let bytes = fs::read(edit_object_path(paths, sha256)).ok()?;
+            let text = decompress_jsonl(&bytes).ok()?;
+            (format!("{:x}", Sha256::digest(text.as_bytes())) == sha256).then_some(text)
+        });
+    match retained {
+        Some(text) => (Some(text), true),
+        None => (
+            edit.get(format!("{side}_text"))
+                .and_then(Value::as_str)
+                .map(str::to_owned),
+            false,
+        ),
+    }
 }
 
-fn cmd_peek(paths: &RepoPaths, context: &RuntimeContext, args: PeekArgs) -> Result<(), CliError> {
+/// Re-apply a tape's edits, in order, onto a scratch directory or a fresh
+/// worktree of a git ref. Conflicting edits are reported and skipped; the
+/// rest still apply.
+fn cmd_replay(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: ReplayArgs,
+) -> Result<(), CliError> {
     ensure_local_store(paths)?;
     print_context_conspicuity(context);
-    ensure_db_parent(&context.db_path)?;
+    let Some(tape) = read_tape_or_session(paths, context, &args.tape_id)? else {
+        return Err(CliError::new(
+            "tape_not_found",
+            format!("tape `{}` not found", args.tape_id),
+        ));
+    };
+    let events = parse_jsonl_events(&tape.content)?;
+    let project_root = events.iter().find_map(|item| match &item.event.data {
+        TapeEventData::Meta(meta) => meta.cwd.as_deref().map(PathBuf::from),
+        _ => None,
+    });
 
-    let indexes = open_query_indexes(context)?;
-    let session_id = args.session_id;
-    let Some(tape_path) = resolve_tape_path(context, &session_id) else {
-        return Err(CliError::new("session_not_found", session_id));
+    let onto_dir = cwd.join(&args.onto);
+    let (worktree, created_worktree) = if onto_dir.is_dir() {
+        (onto_dir, false)
+    } else {
+        (
+            replay_worktree(cwd, paths, &args.tape_id, &args.onto)?,
+            true,
+        )
     };
-    let raw_text = read_tape_content(&tape_path)?;
-    let rows = parse_jsonl_rows(&raw_text)?;
-    let total_lines = raw_text.lines().count();
-    let content_lines = raw_text.lines().collect::<Vec<_>>();
-    let timestamp = extract_latest_timestamp_from_rows(&rows);
-    let grep_context = context.peek_grep_context.max(1);
 
-    let (window_start, window_end, content) = if let Some(pattern) = args.grep_filter.as_deref() {
-        let mut hits = Vec::new();
-        for (idx, line) in content_lines.iter().enumerate() {
-            if line.contains(pattern) {
-                hits.push(idx);
-            }
+    let mut applied = Vec::new();
+    let mut already_applied = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut without_content = Vec::new();
+    for row in chronological_edits(&tape.content)? {
+        if args.through.is_some_and(|through| row.offset > through) {
+            continue;
         }
-        if hits.is_empty() {
-            return Err(CliError::new("no_results", pattern.to_string()));
+        let raw_file = row.value.get("file").and_then(Value::as_str).unwrap_or("");
+        let file = repo_relative_path(raw_file, project_root.as_deref());
+        let (before, _) = edit_side_text(paths, &row.value, "before");
+        let (after, _) = edit_side_text(paths, &row.value, "after");
+        if before.is_none() && after.is_none() {
+            without_content.push(row.offset);
+            continue;
         }
-        let mut ranges = Vec::new();
-        for idx in hits {
-            let start = idx.saturating_sub(grep_context);
-            let end = usize::min(total_lines.saturating_sub(1), idx + grep_context);
-            ranges.push((start, end));
+        let conflict = |reason: &str| json!({"offset": row.offset, "file": file, "reason": reason});
+        if file.is_empty() || is_absolute_slash(&file) || file.split('/').any(|part| part == "..") {
+            conflicts.push(conflict("outside_worktree"));
+            continue;
         }
-        ranges.sort_unstable();
-        let mut merged: Vec<(usize, usize)> = Vec::new();
-        for (start, end) in ranges {
-            if let Some(last) = merged.last_mut()
-                && start <= last.1.saturating_add(1)
-            {
-                last.1 = usize::max(last.1, end);
-                continue;
+        let target = worktree.join(&file);
+        let current = match fs::read_to_string(&target) {
+            Ok(text) => Some(text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(CliError::io("read_error", err)),
+        };
+        let before_line = row
+            .value
+            .get("before_range")
+            .and_then(|range| range.get(0))
+            .and_then(Value::as_u64)
+            .map(|line| line as u32);
+        match apply_edit(
+            current.as_deref(),
+            before.as_deref(),
+            after.as_deref(),
+            before_line,
+        ) {
+            Ok(EditApplication::Applied(content)) => {
+                atomic_write(&target, content.as_bytes())
+                    .map_err(|err| CliError::io("write_error", err))?;
+                applied.push(json!({"offset": row.offset, "file": file}));
             }
-            merged.push((start, end));
-        }
-        let mut out = Vec::new();
-        let mut first = usize::MAX;
-        let mut last = 0usize;
-        for (start, end) in merged {
-            first = usize::min(first, start);
-            last = usize::max(last, end);
-            for idx in start..=end {
-                out.push(json!({
-                    "line": idx + 1,
-                    "text": content_lines.get(idx).copied().unwrap_or_default(),
-                }));
+            Ok(EditApplication::AlreadyApplied) => {
+                already_applied.push(json!({"offset": row.offset, "file": file}));
             }
+            Err(reason) => conflicts.push(conflict(reason)),
         }
-        (first + 1, last + 1, out)
-    } else {
+    }
+
+    print_json(&json!({
+        "status": if conflicts.is_empty() { "ok" } else { "conflicts" },
+        "tape_id": args.tape_id,
+        "onto": args.onto,
+        "worktree": worktree,
+        "created_worktree": created_worktree,
+        "through": args.through,
+        "applied": applied,
+        "already_applied": already_applied,
+        "conflict_count": conflicts.len(),
+        "conflicts": conflicts,
+        "edits_without_content": without_content,
+    }))
+}
+
+/// Check `git_ref` out, detached, into `.engram/replay/<tape>-<ref>`.
+fn replay_worktree(
+    cwd: &Path,
+    paths: &RepoPaths,
+    tape_id: &str,
+    git_ref: &str,
+) -> Result<PathBuf, CliError> {
+    let slug = git_ref
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
+        .collect::<String>();
+    let short_id = tape_id.get(..12).unwrap_or(tape_id).replace(':', "-");
+    let worktree = paths.root.join("replay").join(format!("{short_id}-{slug}"));
+    if worktree.exists() {
+        return Err(CliError::new(
+            "replay_target_exists",
+            format!(
+                "{} already exists; remove it or pass it to --onto",
+                worktree.display()
+            ),
+        ));
+    }
+    let output = ProcessCommand::new("git")
+        .args(["worktree", "add", "--detach"])
+        .arg(&worktree)
+        .arg(git_ref)
+        .current_dir(cwd)
+        .output()
+        .map_err(|err| CliError::new("git_error", err.to_string()))?;
+    if !output.status.success() {
+        return Err(CliError::new(
+            "invalid_replay_target",
+            format!(
+                "`{git_ref}` is neither a directory nor a checkout-able git ref: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    Ok(worktree)
+}
+
+fn show_tape_origin(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    tape_id: &str,
+    tape_path: &Path,
+    content: &str,
+) -> Result<(), CliError> {
+    let events = parse_jsonl_events(content)?;
+    let indexed = if context.db_path.exists() {
+        open_index(context)?.tape_origin(tape_id)?
+    } else {
+        None
+    };
+    let origin = indexed.or_else(|| tape_origin(&events).cloned());
+    let origin = origin.map(|origin| {
+        json!({
+            "path": origin.path,
+            "byte_range": origin.byte_range,
+            "sha256": origin.sha256,
+            "adapter": origin.adapter,
+            "adapter_version": origin.adapter_version,
+            "converted_at": origin.converted_at,
+            "source_exists": Path::new(&origin.path).is_file(),
+            "source_matches": read_source_range(&origin).is_ok(),
+            "raw_copy": read_raw_copy(paths, &origin).is_some(),
+        })
+    });
+    print_json(&json!({
+        "tape_id": tape_id,
+        "path": tape_path,
+        "origin": origin,
+    }))
+}
+
+/// The raw bytes a tape was converted from, or why they are gone:
+/// `source_missing`, or `source_changed` when the range no longer hashes to
+/// the recorded digest (the harness rewrote or truncated the file).
+fn read_source_range(origin: &TapeOrigin) -> Result<Vec<u8>, &'static str> {
+    let bytes = match unpacked_source(Path::new(&origin.path)) {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => fs::read(&origin.path).map_err(|_| "source_missing")?,
+        Err(_) => return Err("source_missing"),
+    };
+    let [start, end] = origin.byte_range;
+    let range = usize::try_from(start).unwrap_or(usize::MAX)..usize::try_from(end).unwrap_or(0);
+    let raw = bytes.get(range).ok_or("source_changed")?;
+    if format!("{:x}", Sha256::digest(raw)) != origin.sha256 {
+        return Err("source_changed");
+    }
+    Ok(raw.to_vec())
+}
+
+/// Where `retain_raw` keeps converted input, keyed by its SHA-256.
+fn raw_copy_path(paths: &RepoPaths, sha256: &str) -> PathBuf {
+    paths.objects.join("raw").join(format!("{sha256}.zst"))
+}
+
+fn retain_raw_copy(paths: &RepoPaths, sha256: &str, raw: &str) -> Result<bool, CliError> {
+    let path = raw_copy_path(paths, sha256);
+    if path.exists() {
+        return Ok(false);
+    }
+    let compressed = compress_jsonl(raw).map_err(|err| CliError::io("compress_error", err))?;
+    atomic_write(&path, &compressed).map_err(|err| CliError::io("write_error", err))?;
+    Ok(true)
+}
+
+/// The configured `projects:`, compiled.
+fn project_rules(context: &RuntimeContext) -> Result<ProjectRules, CliError> {
+    ProjectRules::new(&context.projects)
+        .map_err(|err| CliError::new("invalid_project_pattern", err.to_string()))
+}
+
+/// The configured `issue_patterns:`, compiled.
+fn issue_patterns(context: &RuntimeContext) -> Result<IssuePatterns, CliError> {
+    IssuePatterns::new(&context.issue_patterns)
+        .map_err(|err| CliError::new("invalid_issue_pattern", err.to_string()))
+}
+
+/// The configured `ignore:` globs, compiled.
+fn ignore_rules(context: &RuntimeContext) -> Result<IgnoreRules, CliError> {
+    IgnoreRules::new(&context.ignore)
+        .map_err(|err| CliError::new("invalid_ignore_pattern", err.to_string()))
+}
+
+/// Where `retain_edits` keeps the old/new text of an edit, keyed by SHA-256.
+fn edit_object_path(paths: &RepoPaths, sha256: &str) -> PathBuf {
+    paths.objects.join("edits").join(format!("{sha256}.zst"))
+}
+
+/// Write edit texts not yet in the objects dir; returns how many were new.
+fn retain_edit_objects(paths: &RepoPaths, objects: &[EditObject]) -> Result<usize, CliError> {
+    let mut written = 0usize;
+    for object in objects {
+        let path = edit_object_path(paths, &object.sha256);
+        if path.exists() {
+            continue;
+        }
+        let compressed =
+            compress_jsonl(&object.text).map_err(|err| CliError::io("compress_error", err))?;
+        atomic_write(&path, &compressed).map_err(|err| CliError::io("write_error", err))?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// The retained copy of a tape's raw input, if one exists and is intact.
+fn read_raw_copy(paths: &RepoPaths, origin: &TapeOrigin) -> Option<Vec<u8>> {
+    let bytes = fs::read(raw_copy_path(paths, &origin.sha256)).ok()?;
+    let raw = decompress_jsonl(&bytes).ok()?.into_bytes();
+    (format!("{:x}", Sha256::digest(&raw)) == origin.sha256).then_some(raw)
+}
+
+fn show_tape_diff(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    tape_a: &str,
+    tape_b: &str,
+) -> Result<(), CliError> {
+    let mut rows = Vec::with_capacity(2);
+    for tape_id in [tape_a, tape_b] {
+        let Some(tape) = read_tape_or_session(paths, context, tape_id)? else {
+            return Err(CliError::new(
+                "tape_not_found",
+                format!("tape `{tape_id}` not found"),
+            ));
+        };
+        rows.push(
+            parse_jsonl_rows(&tape.content)?
+                .into_iter()
+                .map(|row| (row.offset, row.value))
+                .collect::<Vec<_>>(),
+        );
+    }
+    let (rows_a, rows_b) = (&rows[0], &rows[1]);
+    let diff = diff_tape_rows(rows_a, rows_b);
+    let event_at = |rows: &[(u64, Value)], offset: u64| {
+        rows.iter()
+            .find(|(row_offset, _)| *row_offset == offset)
+            .map(|(_, event)| compact_event(offset, event))
+            .unwrap_or(Value::Null)
+    };
+
+    print_json(&json!({
+        "tape_a": tape_a,
+        "tape_b": tape_b,
+        "identical": diff.is_identical(),
+        "summary": {
+            "same": diff.same,
+            "only_a": diff.only_a.len(),
+            "only_b": diff.only_b.len(),
+            "changed": diff.changed.len(),
+        },
+        "only_a": diff.only_a.iter().map(|offset| event_at(rows_a, *offset)).collect::<Vec<_>>(),
+        "only_b": diff.only_b.iter().map(|offset| event_at(rows_b, *offset)).collect::<Vec<_>>(),
+        "changed": diff.changed.iter().map(|changed| json!({
+            "key": changed.key,
+            "fields": changed.fields,
+            "a": event_at(rows_a, changed.offset_a),
+            "b": event_at(rows_b, changed.offset_b),
+        })).collect::<Vec<_>>(),
+    }))
+}
+
+fn cmd_gc(paths: &RepoPaths, context: &RuntimeContext, args: GcArgs) -> Result<(), CliError> {
+    if let Some(month) = args.drop_partitions_before.as_deref()
+        && !valid_partition_key(month)
+    {
+        return Err(CliError::new(
+            "invalid_partition",
+            format!("`{month}` is not a partition month; expected YYYY-MM"),
+        ));
+    }
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let _lock = if args.dry_run {
+        None
+    } else {
+        Some(lock_store(context, "gc")?)
+    };
+    let index = open_index(context)?;
+    let protected = load_protected_tapes(&protected_tapes_path(&paths.root))
+        .map_err(|err| CliError::new("protected_tapes_error", err.to_string()))?;
+
+    let mut dropped_partitions = Vec::new();
+    let mut kept_partitions = Vec::new();
+    let mut forgotten = HashSet::new();
+    let partitions =
+        list_partitions(&context.db_path).map_err(|err| CliError::io("read_dir_error", err))?;
+    let mut referenced = index
+        .referenced_tape_ids()?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    for (key, path) in partitions {
+        let partition = SqliteIndex::open(&path_string(&path))?;
+        if args
+            .drop_partitions_before
+            .as_deref()
+            .is_some_and(|month| key.as_str() < month)
+        {
+            let tape_ids = partition.tape_ids()?;
+            let protected_ids = tape_ids
+                .iter()
+                .filter(|tape_id| protected.contains(tape_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            // Dropping the partition would take a protected tape's
+            // evidence with it.
+            if !protected_ids.is_empty() {
+                kept_partitions.push(json!({"partition": key, "protected_tapes": protected_ids}));
+                referenced.extend(partition.referenced_tape_ids()?);
+                continue;
+            }
+            if args.dry_run {
+                forgotten.extend(tape_ids.iter().cloned());
+            } else {
+                drop(partition);
+                remove_sqlite_files(&path, true)?;
+                index.forget_tapes(&tape_ids)?;
+            }
+            dropped_partitions.push(json!({"partition": key, "tapes": tape_ids.len()}));
+            continue;
+        }
+        referenced.extend(partition.referenced_tape_ids()?);
+    }
+    referenced.retain(|tape_id| !forgotten.contains(tape_id));
+    // `record --session` parts without code evidence are named only by
+    // their session's manifest.
+    for session in list_record_sessions(&paths.sessions)
+        .map_err(|err| CliError::new("session_read_error", err.to_string()))?
+    {
+        referenced.extend(session.parts.into_iter().map(|part| part.tape_id));
+    }
+    // The index may still name a migrated tape by its old id.
+    let aliases = load_tape_id_aliases(&tape_id_aliases_path(&paths.tapes))
+        .map_err(|err| CliError::new("tape_id_aliases_error", err.to_string()))?;
+    let migrated = referenced
+        .iter()
+        .filter_map(|tape_id| aliases.resolve(tape_id).map(ToOwned::to_owned))
+        .collect::<Vec<_>>();
+    referenced.extend(migrated);
+
+    let mut deleted = Vec::new();
+    let mut kept_protected = Vec::new();
+    let mut kept = 0usize;
+
+    let entries = fs::read_dir(&paths.tapes).map_err(|err| CliError::io("read_dir_error", err))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| CliError::io("read_dir_error", err))?;
+        let path = entry.path();
+        let Some(tape_id) = tape_id_from_path(&path) else {
+            continue;
+        };
+
+        if referenced.contains(&tape_id) {
+            kept += 1;
+            continue;
+        }
+        if protected.contains(&tape_id) {
+            kept += 1;
+            kept_protected.push(tape_id);
+            continue;
+        }
+
+        if !args.dry_run {
+            fs::remove_file(&path).map_err(|err| CliError::io("remove_file_error", err))?;
+        }
+        deleted.push(tape_id);
+    }
+
+    deleted.sort();
+    kept_protected.sort();
+    print_json(&json!({
+        "status": "ok",
+        "dry_run": args.dry_run,
+        "deleted_tape_ids": deleted,
+        "deleted_count": deleted.len(),
+        "kept_count": kept,
+        "protected_tape_ids": kept_protected,
+        "dropped_partitions": dropped_partitions,
+        "kept_partitions": kept_partitions,
+    }))
+}
+
+fn cmd_protect(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: ProtectArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    let _lock = match args.command {
+        ProtectCommand::List => None,
+        _ => Some(wait_for_store_lock(context, "protect")?),
+    };
+    let path = protected_tapes_path(&paths.root);
+    let mut protected = load_protected_tapes(&path)
+        .map_err(|err| CliError::new("protected_tapes_error", err.to_string()))?;
+    let mut payload = json!({"status": "ok"});
+    match args.command {
+        ProtectCommand::Add { tape_id, note } => {
+            if tape_id.trim().is_empty() {
+                return Err(CliError::new(
+                    "invalid_tape_id",
+                    "tape id must not be empty",
+                ));
+            }
+            payload["added"] = json!(protected.add(ProtectedTape {
+                tape_id,
+                note,
+                protected_at: now_iso8601(),
+            }));
+        }
+        ProtectCommand::Remove { tape_id } => {
+            if !protected.remove(&tape_id) {
+                return Err(CliError::new(
+                    "tape_not_protected",
+                    format!("`{tape_id}` is not protected"),
+                ));
+            }
+            payload["removed"] = json!(true);
+        }
+        ProtectCommand::List => {}
+    }
+    save_protected_tapes(&path, &protected)
+        .map_err(|err| CliError::new("write_error", err.to_string()))?;
+    payload["entries"] = json!(protected.entries);
+    print_json(&payload)
+}
+
+/// Cut a tape into one tape per task. The parts replace the parent in the
+/// index; the parent's file stays, linked from each part's meta.
+fn cmd_split(paths: &RepoPaths, context: &RuntimeContext, args: SplitArgs) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let _lock = if args.dry_run {
+        None
+    } else {
+        Some(lock_store(context, "split")?)
+    };
+    let Some(path) = resolve_tape_path(context, &args.tape_id) else {
+        return Err(CliError::new(
+            "tape_not_found",
+            format!("tape `{}` not found", args.tape_id),
+        ));
+    };
+    let content = read_tape_content(&path)?;
+    let index = open_index(context)?;
+    let existing = index.split_parts(&args.tape_id)?;
+    if !existing.is_empty() {
+        return Err(CliError::new(
+            "already_split",
+            format!(
+                "tape `{}` was already split into {} parts",
+                args.tape_id,
+                existing.len()
+            ),
+        ));
+    }
+
+    let mut points = Vec::new();
+    for raw in &args.at {
+        let point = SplitAt::parse(raw)
+            .and_then(|at| resolve_split_at(&content, at))
+            .ok_or_else(|| {
+                CliError::new(
+                    "invalid_split_point",
+                    format!("`{raw}` is not an offset or timestamp inside the tape"),
+                )
+            })?;
+        points.push(point);
+    }
+    if args.auto {
+        points.extend(auto_split_points(&content, args.idle_gap));
+    }
+    let parts = split_tape_jsonl(&args.tape_id, &content, &points);
+    if parts.len() < 2 {
+        return Err(CliError::new(
+            "no_split_points",
+            "no split point leaves events on both sides",
+        ));
+    }
+
+    let mut out = Vec::with_capacity(parts.len());
+    if !args.dry_run {
+        forget_in_all_indexes(context, &index, std::slice::from_ref(&args.tape_id))?;
+    }
+    for (split, part) in &parts {
+        let mut entry = json!({
+            "part": split.part,
+            "offsets": [split.first_offset, split.last_offset],
+            "event_count": part.lines().count(),
+        });
+        if !args.dry_run {
+            let stored = store_transcript(paths, context, part)?;
+            entry["tape_id"] = stored.get("tape_id").cloned().unwrap_or(Value::Null);
+        }
+        out.push(entry);
+    }
+    print_json(&json!({
+        "status": "ok",
+        "tape_id": args.tape_id,
+        "dry_run": args.dry_run,
+        "parts": out,
+    }))
+}
+
+/// Stitch several tapes of one task into one. The joined tape replaces its
+/// members in the index; their files stay, listed in its meta.
+fn cmd_join(paths: &RepoPaths, context: &RuntimeContext, args: JoinArgs) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let _lock = if args.dry_run {
+        None
+    } else {
+        Some(lock_store(context, "join")?)
+    };
+    let index = open_index(context)?;
+    let mut members = Vec::with_capacity(args.tape_ids.len());
+    for tape_id in &args.tape_ids {
+        if members.iter().any(|(seen, _)| seen == tape_id) {
+            return Err(CliError::new(
+                "duplicate_tape",
+                format!("tape `{tape_id}` is listed twice"),
+            ));
+        }
+        let Some(path) = resolve_tape_path(context, tape_id) else {
+            return Err(CliError::new(
+                "tape_not_found",
+                format!("tape `{tape_id}` not found"),
+            ));
+        };
+        if let Some(joined) = index.joined_into(tape_id)? {
+            return Err(CliError::new(
+                "already_joined",
+                format!("tape `{tape_id}` is already part of `{joined}`"),
+            ));
+        }
+        members.push((tape_id.clone(), read_tape_content(&path)?));
+    }
+
+    let joined = join_tapes_jsonl(&members);
+    let mut payload = json!({
+        "status": "ok",
+        "members": args.tape_ids,
+        "event_count": joined.lines().count(),
+        "dry_run": args.dry_run,
+    });
+    if !args.dry_run {
+        forget_in_all_indexes(context, &index, &args.tape_ids)?;
+        let stored = store_transcript(paths, context, &joined)?;
+        payload["tape_id"] = stored.get("tape_id").cloned().unwrap_or(Value::Null);
+    }
+    print_json(&payload)
+}
+
+/// Drop tapes from the main index and every partition.
+fn forget_in_all_indexes(
+    context: &RuntimeContext,
+    index: &SqliteIndex,
+    tape_ids: &[String],
+) -> Result<(), CliError> {
+    index.forget_tapes(tape_ids)?;
+    for (_, partition) in
+        list_partitions(&context.db_path).map_err(|err| CliError::io("read_dir_error", err))?
+    {
+        SqliteIndex::open(&path_string(&partition))?.forget_tapes(tape_ids)?;
+    }
+    Ok(())
+}
+
+fn cmd_import(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: ImportArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    let ImportCommand::GithubPr {
+        pr,
+        api_url,
+        token_env,
+    } = args.command;
+    let pr = PullRequestRef::parse(&pr).ok_or_else(|| {
+        CliError::new(
+            "invalid_pull_request",
+            format!("`{pr}` is not `owner/repo#NUMBER` or a pull request URL"),
+        )
+    })?;
+    let token = std::env::var(&token_env)
+        .ok()
+        .filter(|token| !token.is_empty());
+    let review = fetch_pull_request_review(&api_url, token.as_deref(), &pr)
+        .map_err(|err| CliError::new("github_error", err.to_string()))?;
+    let transcript = review_tape_jsonl(&pr, &review);
+    let _lock = wait_for_store_lock(context, "import")?;
+    let mut payload = store_transcript(paths, context, &transcript)?;
+    payload.insert(
+        "import".to_string(),
+        json!({
+            "source": GITHUB_PR_HARNESS,
+            "pull_request": pr.to_string(),
+            "authenticated": token.is_some(),
+            "reviews": review.reviews.len(),
+            "comments": review.comments.len(),
+        }),
+    );
+    print_json(&Value::Object(payload))
+}
+
+fn cmd_annotate(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: AnnotateArgs,
+) -> Result<(), CliError> {
+    if args.note.trim().is_empty() {
+        return Err(CliError::new("invalid_note", "--note must not be empty"));
+    }
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let index = open_index(context)?;
+    let target_kind = classify_explain_target(cwd, context, &[], &args.target, args.anchor)?;
+    let anchors = explain_target_anchors(cwd, &target_kind, args.anchor, context)?;
+    if anchors.is_empty() {
+        return Err(CliError::new(
+            "invalid_annotate_target",
+            format!("`{}` has no content to anchor a note to", args.target),
+        ));
+    }
+    let created_at = now_iso8601();
+    let _lock = wait_for_store_lock(context, "annotate")?;
+    let id = index.add_annotation(&args.target, &anchors, &args.note, &created_at)?;
+    print_json(&json!({
+        "status": "ok",
+        "id": id,
+        "target": args.target,
+        "note": args.note,
+        "anchor_count": anchors.len(),
+        "created_at": created_at,
+    }))
+}
+
+/// Materialize `engram:note` comments as annotations and `engram:link`
+/// comments as one span-link tape, so lineage hints can live in the code.
+fn cmd_scan_comments(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: ScanCommentsArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let _lock = lock_store(context, "scan-comments")?;
+    let index = open_index(context)?;
+    let roots = if args.paths.is_empty() {
+        vec![cwd.to_path_buf()]
+    } else {
+        args.paths.iter().map(|path| cwd.join(path)).collect()
+    };
+
+    let mut scanned_files = 0usize;
+    let mut notes_added = 0usize;
+    let mut notes_existing = 0usize;
+    let mut issues = Vec::new();
+    let mut link_lines = Vec::new();
+    let mut latest_mtime = None;
+    for file in comment_scan_candidates(cwd, &roots)? {
+        let Ok(text) = fs::read_to_string(&file) else {
+            continue;
+        };
+        scanned_files += 1;
+        if !text.contains("engram:") {
+            continue;
+        }
+        let relative = slash_path(&file.strip_prefix(cwd).unwrap_or(&file).to_string_lossy());
+        let (directives, file_issues) = scan_comment_directives(&text);
+        for issue in file_issues {
+            issues.push(json!({"file": relative, "line": issue.line, "error": issue.error}));
+        }
+        for found in directives {
+            let span = found.span;
+            match found.directive {
+                CommentDirective::Note { text: note } => {
+                    let target = format!("{relative}:{}-{}", span.start, span.end);
+                    if index.has_annotation(&target, &note)? {
+                        notes_existing += 1;
+                        continue;
+                    }
+                    let anchors = explain_target_anchors(
+                        cwd,
+                        &ExplainTarget::FileRange {
+                            file: relative.clone(),
+                            start: span.start,
+                            end: span.end,
+                        },
+                        false,
+                        context,
+                    )?;
+                    index.add_annotation(&target, &anchors, &note, &now_iso8601())?;
+                    notes_added += 1;
+                }
+                CommentDirective::Link {
+                    to_file,
+                    to_range,
+                    note,
+                } => {
+                    if !cwd.join(&to_file).is_file() {
+                        issues.push(json!({
+                            "file": relative,
+                            "line": found.line,
+                            "error": format!("link target `{to_file}` does not exist"),
+                        }));
+                        continue;
+                    }
+                    let modified = fs::metadata(&file)
+                        .and_then(|meta| meta.modified())
+                        .map(chrono::DateTime::<Utc>::from)
+                        .map_err(|err| CliError::io("metadata_error", err))?;
+                    latest_mtime = latest_mtime.max(Some(modified));
+                    link_lines.push(json!({
+                        "t": modified.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                        "k": "span.link",
+                        "from_file": relative,
+                        "from_range": [span.start, span.end],
+                        "to_file": to_file,
+                        "to_range": [to_range.start, to_range.end],
+                        "note": note,
+                    }));
+                }
+            }
+        }
+    }
+
+    // Timestamps come from file mtimes, so rescanning an unchanged tree
+    // yields the same content-addressed tape.
+    let link_count = link_lines.len();
+    let tape = match latest_mtime {
+        Some(latest) => {
+            let mut transcript = serde_json::to_string(&json!({
+                "t": latest.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                "k": "meta",
+                "label": "scan-comments",
+                "cwd": path_string(cwd),
+            }))?;
+            transcript.push('\n');
+            for line in link_lines {
+                transcript.push_str(&serde_json::to_string(&line)?);
+                transcript.push('\n');
+            }
+            Value::Object(store_transcript(paths, context, &transcript)?)
+        }
+        None => Value::Null,
+    };
+    print_json(&json!({
+        "status": "ok",
+        "scanned_files": scanned_files,
+        "notes_added": notes_added,
+        "notes_existing": notes_existing,
+        "links": link_count,
+        "tape": tape,
+        "issue_count": issues.len(),
+        "issues": issues,
+    }))
+}
+
+/// Files under `roots`, skipping hidden directories (`.git`, `.engram`) and
+/// build output.
+fn comment_scan_candidates(cwd: &Path, roots: &[PathBuf]) -> Result<Vec<PathBuf>, CliError> {
+    let mut out = Vec::new();
+    for root in roots {
+        if !root.exists() {
+            return Err(CliError::new(
+                "invalid_path",
+                format!("`{}` does not exist", root.display()),
+            ));
+        }
+        let walker = WalkDir::new(root).into_iter().filter_entry(|entry| {
+            entry.path() == root
+                || entry.path() == cwd
+                || !entry.file_type().is_dir()
+                || !entry.file_name().to_str().is_some_and(|name| {
+                    name.starts_with('.') || matches!(name, "target" | "node_modules")
+                })
+        });
+        for entry in walker.filter_map(Result::ok) {
+            if entry.file_type().is_file() {
+                out.push(entry.into_path());
+            }
+        }
+    }
+    out.sort();
+    out.dedup();
+    Ok(out)
+}
+
+/// Fingerprint blocks of the working tree and look their tokens up in other
+/// stores, reporting blocks whose code those stores' sessions touched: code an
+/// agent most likely copied in from another repository.
+fn cmd_provenance(cwd: &Path, args: ProvenanceArgs) -> Result<(), CliError> {
+    let ProvenanceCommand::Scan {
+        stores,
+        paths,
+        min_overlap,
+        min_lines,
+    } = args.command;
+    if !(0.0..=1.0).contains(&min_overlap) {
+        return Err(CliError::new(
+            "invalid_min_overlap",
+            "--min-overlap must be in [0.0, 1.0]",
+        ));
+    }
+    // Each store is attached read-only to an empty in-memory index, so the
+    // lookups see only that store and never write to it.
+    let mut others = Vec::new();
+    for store in &stores {
+        let db_path = sibling_index_path(store)?;
+        let mut index = SqliteIndex::open_in_memory()?;
+        index.attach_read_only(&db_path).map_err(|err| {
+            CliError::new(
+                "sibling_index_error",
+                format!("cannot attach {}: {err}", db_path.display()),
+            )
+        })?;
+        others.push((path_string(store), index));
+    }
+    let roots = if paths.is_empty() {
+        vec![cwd.to_path_buf()]
+    } else {
+        paths.iter().map(|path| cwd.join(path)).collect()
+    };
+
+    let mut scanned_files = 0usize;
+    let mut scanned_spans = 0usize;
+    let mut matches = Vec::new();
+    for file in comment_scan_candidates(cwd, &roots)? {
+        let Ok(text) = fs::read_to_string(&file) else {
+            continue;
+        };
+        scanned_files += 1;
+        let relative = slash_path(&file.strip_prefix(cwd).unwrap_or(&file).to_string_lossy());
+        for (range, block) in provenance_blocks(&text, min_lines) {
+            let tokens = fingerprint_token_hashes(&block);
+            if tokens.is_empty() {
+                continue;
+            }
+            scanned_spans += 1;
+            for (store, index) in &others {
+                let mut matched = 0usize;
+                let mut sessions: BTreeMap<String, (BTreeMap<String, usize>, String, String)> =
+                    BTreeMap::new();
+                for token in &tokens {
+                    let evidence = index.evidence_for_anchor(token)?;
+                    if evidence.is_empty() {
+                        continue;
+                    }
+                    matched += 1;
+                    for fragment in evidence {
+                        let (files, first, last) =
+                            sessions.entry(fragment.tape_id).or_insert_with(|| {
+                                (
+                                    BTreeMap::new(),
+                                    fragment.timestamp.clone(),
+                                    fragment.timestamp.clone(),
+                                )
+                            });
+                        *files.entry(fragment.file_path).or_default() += 1;
+                        if fragment.timestamp < *first {
+                            *first = fragment.timestamp.clone();
+                        }
+                        if fragment.timestamp > *last {
+                            *last = fragment.timestamp;
+                        }
+                    }
+                }
+                let overlap = matched as f32 / tokens.len() as f32;
+                if matched == 0 || overlap < min_overlap {
+                    continue;
+                }
+                let mut sessions = sessions
+                    .into_iter()
+                    .map(|(tape_id, (files, first, last))| {
+                        json!({
+                            "tape_id": tape_id,
+                            "files": files.into_keys().collect::<Vec<_>>(),
+                            "first_seen": first,
+                            "last_seen": last,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                sessions.sort_by(|left, right| {
+                    left["first_seen"]
+                        .as_str()
+                        .cmp(&right["first_seen"].as_str())
+                });
+                matches.push(json!({
+                    "file": relative,
+                    "range": {"start": range.start, "end": range.end},
+                    "store": store,
+                    "overlap": overlap,
+                    "matched_tokens": matched,
+                    "total_tokens": tokens.len(),
+                    "sessions": sessions,
+                }));
+            }
+        }
+    }
+    print_json(&json!({
+        "status": "ok",
+        "stores": stores.iter().map(|store| path_string(store)).collect::<Vec<_>>(),
+        "scanned_files": scanned_files,
+        "scanned_spans": scanned_spans,
+        "match_count": matches.len(),
+        "matches": matches,
+    }))
+}
+
+/// Runs of at least `min_lines` non-blank lines, with their 1-based ranges.
+fn provenance_blocks(text: &str, min_lines: usize) -> Vec<(FileRange, String)> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut out = Vec::new();
+    let mut start = 0usize;
+    while start < lines.len() {
+        if lines[start].trim().is_empty() {
+            start += 1;
+            continue;
+        }
+        let end = (start..lines.len())
+            .take_while(|&idx| !lines[idx].trim().is_empty())
+            .last()
+            .unwrap_or(start);
+        if end + 1 - start >= min_lines.max(1) {
+            out.push((
+                FileRange {
+                    start: start as u32 + 1,
+                    end: end as u32 + 1,
+                },
+                lines[start..=end].join("\n"),
+            ));
+        }
+        start = end + 1;
+    }
+    out
+}
+
+fn cmd_explain(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: ExplainArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+
+    let target = args
+        .target
+        .clone()
+        .ok_or_else(|| CliError::new("invalid_explain_target", "target is required"))?;
+    let at = args
+        .at
+        .as_deref()
+        .map(|raw| resolve_explain_at(cwd, raw))
+        .transpose()?;
+    let mut indexes = open_query_indexes(context)?;
+    let at_root;
+    let mut from_git = None;
+    let mut rev_commit = None;
+    let cwd = match &at {
+        Some(at) => {
+            let cutoff = at.cutoff.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+            indexes = indexes
+                .into_iter()
+                .map(|index| index.with_as_of(cutoff.clone()))
+                .collect();
+            at_root = match &at.commit {
+                Some(commit) => checkout_target_at(cwd, paths, commit, &target)?,
+                None => cwd.to_path_buf(),
+            };
+            at_root.as_path()
+        }
+        None if args.from_git && !args.anchor && !cwd.join(target_file(&target)).exists() => {
+            let head = git_output(cwd, &["rev-parse", "--verify", "--quiet", "HEAD^{commit}"])
+                .ok_or_else(|| {
+                    CliError::new(
+                        "git_error",
+                        format!("`{target}` is not in the working tree and there is no HEAD to read it from"),
+                    )
+                })?;
+            at_root = checkout_target_at(cwd, paths, &head, &target)?;
+            from_git = Some(head);
+            at_root.as_path()
+        }
+        None if args.staged => {
+            at_root = checkout_target_at(cwd, paths, "", &target)?;
+            at_root.as_path()
+        }
+        None => match args.rev.as_deref() {
+            Some(raw) => {
+                let commit = git_output(
+                    cwd,
+                    &[
+                        "rev-parse",
+                        "--verify",
+                        "--quiet",
+                        &format!("{raw}^{{commit}}"),
+                    ],
+                )
+                .ok_or_else(|| {
+                    CliError::new("invalid_rev", format!("`{raw}` is not a git revision"))
+                })?;
+                at_root = checkout_target_at(cwd, paths, &commit, &target)?;
+                rev_commit = Some(commit);
+                at_root.as_path()
+            }
+            None => cwd,
+        },
+    };
+    let context = &attach_sibling_stores(context, &mut indexes[0], &args.also)?;
+    let granularity = args
+        .granularity
+        .or(args.auto_span.then_some(ExplainGranularity::Hunk));
+    let (target_kind, resolved_span) = match granularity {
+        Some(granularity) => {
+            let (target_kind, span) = granular_target(cwd, context, &target, granularity)?;
+            (target_kind, Some(span))
+        }
+        None => (
+            classify_explain_target(cwd, context, &indexes, &target, args.anchor)?,
+            None,
+        ),
+    };
+
+    let mut file_chunks = None;
+    let mut date_filter = DateFilter::parse(args.since.as_deref(), args.until.as_deref())?;
+    if let Some(at) = &at {
+        date_filter.until = Some(at.cutoff);
+    }
+    let exclusions = EvidenceExclusions::parse(
+        &args.exclude_harness,
+        &args.exclude_tape,
+        &args.exclude_path,
+        &args.issue,
+        &args.project,
+    )?;
+    let query = json!({
+        "command": "explain",
+        "target": target,
+        "grep_filter": args.grep_filter,
+        "limit": args.limit,
+        "offset": args.offset,
+        "min_confidence": args.min_confidence,
+        "since": args.since,
+        "until": args.until,
+        "at": at.as_ref().map(|at| json!({
+            "cutoff": at.cutoff.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "commit": at.commit,
+        })),
+        "from_git": from_git,
+        "rev": args.rev.as_ref().map(|rev| json!({"rev": rev, "commit": rev_commit})),
+        "staged": args.staged,
+        "count": args.count,
+        "max_fanout": args.max_fanout,
+        "max_edges": args.max_edges,
+        "depth": args.depth,
+        "forensics": args.forensics,
+        "include_deleted": args.include_deleted,
+        "also": args.also.iter().map(|path| path_string(path)).collect::<Vec<_>>(),
+        "exclude_harness": args.exclude_harness,
+        "exclude_tape": args.exclude_tape,
+        "exclude_path": args.exclude_path,
+        "issue": args.issue,
+        "project": args.project,
+        "granularity": granularity.map(ExplainGranularity::as_str),
+        "resolved_span": resolved_span,
+    });
+    let whole_file = matches!(target_kind, ExplainTarget::FileWhole { .. });
+    let query_anchors = match target_kind {
+        ExplainTarget::FileWhole { file } => {
+            let chunks = file_chunk_anchors(cwd, context, &file)?;
+            let mut anchors = explain_target_anchors(
+                cwd,
+                &ExplainTarget::FileWhole { file },
+                args.anchor,
+                context,
+            )?;
+            let mut seen = anchors.iter().cloned().collect::<HashSet<_>>();
+            for (_, chunk_anchors) in &chunks {
+                anchors.extend(
+                    chunk_anchors
+                        .iter()
+                        .filter(|anchor| seen.insert((*anchor).clone()))
+                        .cloned(),
+                );
+            }
+            file_chunks = Some(chunks);
+            anchors
+        }
+        target_kind => explain_target_anchors(cwd, &target_kind, args.anchor, context)?,
+    };
+    let traversal = ExplainTraversal {
+        min_confidence: args.min_confidence,
+        max_fanout: args.max_fanout,
+        max_edges: args.max_edges,
+        max_depth: args.depth,
+    };
+    let with_tombstones = args.include_deleted && !whole_file;
+    if args.json_lines {
+        return stream_explain_json_lines(
+            context,
+            &indexes,
+            &args,
+            &target,
+            query,
+            &query_anchors,
+            traversal,
+            &exclusions,
+            &date_filter,
+            file_chunks.as_deref(),
+            with_tombstones,
+        );
+    }
+
+    let result = explain_across_indexes(&indexes, &query_anchors, traversal, args.forensics)?;
+    let touches = collect_touch_evidence(&indexes, &result.direct, &result.touched_anchors)?;
+    let touches = exclusions.filter_touches(&indexes, touches)?;
+    let mut raw_sessions = build_session_windows(context, touches)?;
+    let (dispatch_lineage, dispatch_sessions) =
+        collect_dispatch_upstream_sessions(context, &indexes[0], &raw_sessions)?;
+    raw_sessions.extend(exclusions.filter_sessions(&indexes, dispatch_sessions)?);
+    let lineage_paths = if args.paths {
+        best_lineage_paths(&result.lineage, &query_anchors)
+    } else {
+        Vec::new()
+    };
+    let score_by_session = collect_anchor_scores(&indexes, &query_anchors)?;
+    let tombstones = if with_tombstones {
+        explain_tombstones(&indexes, &result.touched_anchors)?
+    } else {
+        Vec::new()
+    };
+    let engram::query::explain::ExplainResult {
+        lineage,
+        touched_anchors,
+        truncated: traversal_truncated,
+        ..
+    } = result;
+
+    let (spawned, spawn_sessions) =
+        collect_spawn_upstream_sessions(context, &indexes, &raw_sessions)?;
+    raw_sessions.extend(exclusions.filter_sessions(&indexes, spawn_sessions)?);
+
+    let annotations = collect_annotations(&indexes, &touched_anchors)?;
+    let shown_anchors = query_anchors
+        .iter()
+        .chain(
+            lineage
+                .iter()
+                .flat_map(|edge| [&edge.from_anchor, &edge.to_anchor]),
+        )
+        .cloned()
+        .collect::<Vec<_>>();
+    let previews = collect_anchor_previews(&indexes, &shown_anchors)?;
+    if args.pretty {
+        return print_pretty_explain(
+            context,
+            &target,
+            args.verbose,
+            args.timezone.into(),
+            &annotations,
+            &query_anchors,
+            &previews,
+            &lineage,
+            &lineage_paths,
+            &raw_sessions,
+            &tombstones,
+            traversal_truncated,
+        );
+    }
+
+    let mut sessions = format_sessions_for_agent(
+        context,
+        &indexes[0],
+        raw_sessions,
+        &score_by_session,
+        args.grep_filter.as_deref(),
+    )?;
+    sessions.retain(|session| session_matches_date_filter(session, &date_filter));
+    let regions = match &file_chunks {
+        Some(chunks) => {
+            let shown = sessions
+                .iter()
+                .filter_map(|session| session.get("session_id").and_then(Value::as_str))
+                .collect::<HashSet<_>>();
+            Some(file_regions_json(&indexes, chunks, &shown)?)
+        }
+        None => None,
+    };
+    let chain_links = dispatch_lineage
+        .iter()
+        .chain(&spawned)
+        .cloned()
+        .collect::<Vec<_>>();
+    annotate_chain_fields(&mut sessions, &chain_links);
+    annotate_spawned_by(&mut sessions, &spawned);
+    sessions.sort_by(explain_session_order);
+    if sessions.is_empty() {
+        return Err(CliError::new("no_results", target));
+    }
+
+    let (sessions, returned, total, time_range, truncated) = apply_session_truncation(
+        sessions,
+        args.limit,
+        args.offset,
+        context.explain_default_limit,
+    );
+    if sessions.is_empty() {
+        return Err(CliError::new("no_results", target));
+    }
+    if args.format == ExplainFormat::Mermaid {
+        let sessions = mermaid_sessions(&indexes, &sessions, &touched_anchors)?;
+        print!(
+            "{}",
+            lineage_flowchart(&target, &query_anchors, &lineage, &sessions)
+        );
+        return Ok(());
+    }
+    let chain_metadata = build_chain_metadata(&sessions);
+    let mut sessions = sessions;
+    let episodes = build_episodes(context, &mut sessions)?;
+    let narrative = if args.summarize {
+        Some(summarize_sessions(context, &target, &sessions)?)
+    } else {
+        None
+    };
+    append_metrics(
+        context,
+        "explain",
+        &target,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+    );
+
+    let version = anchor_version(context.normalize_line_endings);
+    let mut query = query;
+    query["anchor_previews"] = json!(
+        query_anchors
+            .iter()
+            .filter_map(|anchor| Some((anchor, previews.get(anchor)?)))
+            .collect::<BTreeMap<_, _>>()
+    );
+    query["anchors"] = json!(query_anchors);
+    query["anchor_version"] = json!(version);
+    let mismatch = anchor_version_mismatch_json(&indexes, version)?;
+    let mut payload = json!({
+        "query": query,
+        "sessions": sessions,
+        "chains": chain_metadata,
+        "episodes": episodes,
+        "lineage": lineage_with_trust_json(&indexes, &lineage, &previews)?,
+        "annotations": annotations,
+        "dispatch_lineage": dispatch_lineage,
+        "spawned": spawned,
+        "tombstones": tombstones,
+        "stores_queried": indexes.len(),
+        "returned": returned,
+        "total": total,
+        "time_range": time_range,
+        "truncated": truncated,
+        "traversal_truncated": traversal_truncation_json(traversal_truncated),
+    });
+    if let Some(narrative) = narrative {
+        payload["narrative"] = narrative;
+    }
+    if let Some(mismatch) = mismatch {
+        payload["anchor_version_mismatch"] = mismatch;
+    }
+    if let Some(regions) = regions {
+        payload["regions"] = regions;
+    }
+    if args.paths {
+        payload["paths"] = Value::Array(lineage_paths.iter().map(lineage_path_to_json).collect());
+    }
+    emit_query_result(&indexes[0], "explain", payload)
+}
+
+/// Explain's session ranking: shallowest in a dispatch chain first, then by
+/// confidence, then most recent.
+fn explain_session_order(a: &Value, b: &Value) -> std::cmp::Ordering {
+    let a_depth = a.get("depth").and_then(Value::as_u64).unwrap_or(0);
+    let b_depth = b.get("depth").and_then(Value::as_u64).unwrap_or(0);
+    let a_score = a.get("confidence").and_then(Value::as_f64).unwrap_or(0.0);
+    let b_score = b.get("confidence").and_then(Value::as_f64).unwrap_or(0.0);
+    let a_ts = a.get("timestamp").and_then(Value::as_str).unwrap_or("");
+    let b_ts = b.get("timestamp").and_then(Value::as_str).unwrap_or("");
+    a_depth
+        .cmp(&b_depth)
+        .then_with(|| {
+            b_score
+                .partial_cmp(&a_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .then_with(|| b_ts.cmp(a_ts))
+}
+
+/// `explain --json-lines`: the explain pipeline, written as it runs. Each
+/// lineage edge is written as the walk reaches it and each session as soon
+/// as its tape has been read, so neither the lineage graph nor the session
+/// list is held in memory; edges are only kept for `--paths`, which ranks
+/// whole chains. Sessions are ranked before their tapes are read, on the
+/// latest touch rather than the tape's last event.
+#[allow(clippy::too_many_arguments)]
+fn stream_explain_json_lines(
+    context: &RuntimeContext,
+    indexes: &[SqliteIndex],
+    args: &ExplainArgs,
+    target: &str,
+    query: Value,
+    query_anchors: &[String],
+    traversal: ExplainTraversal,
+    exclusions: &EvidenceExclusions,
+    date_filter: &DateFilter,
+    file_chunks: Option<&[(LineChunk, Vec<String>)]>,
+    with_tombstones: bool,
+) -> Result<(), CliError> {
+    print_json(&json!({"type": "query", "query": query}))?;
+
+    let mut direct = Vec::new();
+    let mut lineage = Vec::new();
+    let mut touched_anchors = Vec::new();
+    let mut seen_lineage = HashSet::new();
+    let mut seen_anchors = HashSet::new();
+    let mut traversal_truncated = None;
+    for anchor in query_anchors {
+        if seen_anchors.insert(anchor.clone()) {
+            touched_anchors.push(anchor.clone());
+        }
+    }
+    for index in indexes {
+        direct.extend(retrieve_direct(index, query_anchors)?);
+        let mut walker = LineageWalker::new(index, query_anchors, traversal, args.forensics);
+        for step in walker.by_ref() {
+            let edge = step?.edge;
+            if !seen_lineage.insert(edge_dedup_key(&edge)) {
+                continue;
+            }
+            print_json(&json!({"type": "edge", "edge": edge_to_json(&edge)}))?;
+            for anchor in [&edge.from_anchor, &edge.to_anchor] {
+                if seen_anchors.insert(anchor.clone()) {
+                    touched_anchors.push(anchor.clone());
+                }
+            }
+            if args.paths {
+                lineage.push(edge);
+            }
+        }
+        traversal_truncated = TraversalTruncation::merge(traversal_truncated, walker.truncation());
+    }
+
+    let touches = collect_touch_evidence(indexes, &direct, &touched_anchors)?;
+    let touches = exclusions.filter_touches(indexes, touches)?;
+    let mut raw_sessions = session_touch_groups(touches);
+    let (dispatch_lineage, dispatch_sessions) =
+        collect_dispatch_upstream_sessions(context, &indexes[0], &raw_sessions)?;
+    raw_sessions.extend(exclusions.filter_sessions(indexes, dispatch_sessions)?);
+    let (spawned, spawn_sessions) =
+        collect_spawn_upstream_sessions(context, indexes, &raw_sessions)?;
+    raw_sessions.extend(exclusions.filter_sessions(indexes, spawn_sessions)?);
+
+    // Chain fields and ranking need every session id but not the sessions.
+    let score_by_session = collect_anchor_scores(indexes, query_anchors)?;
+    let mut ranks = raw_sessions
+        .iter()
+        .map(|raw| {
+            let session_id = raw.get("tape_id").and_then(Value::as_str).unwrap_or("");
+            json!({
+                "session_id": session_id,
+                "confidence": score_by_session.get(session_id).copied().unwrap_or(0.0),
+                "timestamp": raw.get("latest_touch_timestamp"),
+            })
+        })
+        .collect::<Vec<_>>();
+    let chain_links = dispatch_lineage
+        .iter()
+        .chain(&spawned)
+        .cloned()
+        .collect::<Vec<_>>();
+    annotate_chain_fields(&mut ranks, &chain_links);
+    annotate_spawned_by(&mut ranks, &spawned);
+    let mut ranked = raw_sessions.into_iter().zip(ranks).collect::<Vec<_>>();
+    ranked.sort_by(|(_, a), (_, b)| explain_session_order(a, b));
+
+    let max_return = usize::min(
+        args.limit.unwrap_or(context.explain_default_limit),
+        SAFE_RESULT_SESSION_THRESHOLD,
+    );
+    let mut total = 0usize;
+    let mut time_range: Option<(String, String)> = None;
+    let mut shown = HashSet::new();
+    // Just the fields chains and episodes are built from.
+    let mut returned = Vec::new();
+    for (mut raw, rank) in ranked {
+        if raw.get("windows").is_none() {
+            add_session_windows(context, &mut raw)?;
+        }
+        let Some(mut session) = format_sessions_for_agent(
+            context,
+            &indexes[0],
+            vec![raw],
+            &score_by_session,
+            args.grep_filter.as_deref(),
+        )?
+        .pop() else {
+            continue;
+        };
+        if !session_matches_date_filter(&session, date_filter) {
+            continue;
+        }
+        total += 1;
+        if let Some(timestamp) = session
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .filter(|timestamp| !timestamp.is_empty())
+        {
+            let timestamp = timestamp.to_string();
+            time_range = Some(match time_range {
+                Some((start, end)) => (start.min(timestamp.clone()), end.max(timestamp)),
+                None => (timestamp.clone(), timestamp),
+            });
+        }
+        if file_chunks.is_some() {
+            shown.insert(rank["session_id"].as_str().unwrap_or_default().to_string());
+        }
+        if total <= args.offset || returned.len() >= max_return {
+            continue;
+        }
+        for field in ["depth", "parent", "children", "chain_length", "spawned_by"] {
+            if let Some(value) = rank.get(field) {
+                session[field] = value.clone();
+            }
+        }
+        print_json(&json!({"type": "session", "session": session}))?;
+        returned.push(json!({
+            "session_id": session["session_id"],
+            "timestamp": session["timestamp"],
+            "depth": session["depth"],
+            "parent": session["parent"],
+            "children": session["children"],
+        }));
+    }
+    if returned.is_empty() {
+        return Err(CliError::new("no_results", target));
+    }
+
+    let chains = build_chain_metadata(&returned);
+    let episodes = build_episodes(context, &mut returned)?;
+    append_metrics(
+        context,
+        "explain",
+        target,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+    );
+    let tombstones = if with_tombstones {
+        explain_tombstones(indexes, &touched_anchors)?
+    } else {
+        Vec::new()
+    };
+    let time_range = match time_range {
+        Some((start, end)) => json!({"start": start, "end": end}),
+        None => json!({"start": Value::Null, "end": Value::Null}),
+    };
+    let start = usize::min(args.offset, total);
+    let mut summary = json!({
+        "chains": chains,
+        "episodes": episodes,
+        "annotations": collect_annotations(indexes, &touched_anchors)?,
+        "dispatch_lineage": dispatch_lineage,
+        "spawned": spawned,
+        "tombstones": tombstones,
+        "stores_queried": indexes.len(),
+        "returned": returned.len(),
+        "total": total,
+        "time_range": time_range,
+        "truncated": start > 0 || start + returned.len() < total,
+        "traversal_truncated": traversal_truncation_json(traversal_truncated),
+    });
+    let version = anchor_version(context.normalize_line_endings);
+    if let Some(mismatch) = anchor_version_mismatch_json(indexes, version)? {
+        summary["anchor_version_mismatch"] = mismatch;
+    }
+    if let Some(chunks) = file_chunks {
+        let shown = shown.iter().map(String::as_str).collect::<HashSet<_>>();
+        summary["regions"] = file_regions_json(indexes, chunks, &shown)?;
+    }
+    if args.paths {
+        summary["paths"] = Value::Array(
+            best_lineage_paths(&lineage, query_anchors)
+                .iter()
+                .map(lineage_path_to_json)
+                .collect(),
+        );
+    }
+
+    let result_id = record_query_payload(&indexes[0], "explain", &summary)?;
+    summary["type"] = json!("summary");
+    summary["rating_hint"] = json!(rating_hint(&result_id));
+    summary["result_id"] = Value::String(result_id);
+    print_json(&summary)
+}
+
+/// Returned sessions with the touched anchors each has evidence on, for
+/// `explain --format mermaid`.
+fn mermaid_sessions(
+    indexes: &[SqliteIndex],
+    sessions: &[Value],
+    touched_anchors: &[String],
+) -> Result<Vec<MermaidSession>, CliError> {
+    let mut anchors_by_tape = HashMap::<String, BTreeSet<String>>::new();
+    for anchor in touched_anchors {
+        for index in indexes {
+            for fragment in index.evidence_for_anchor(anchor)? {
+                anchors_by_tape
+                    .entry(fragment.tape_id)
+                    .or_default()
+                    .insert(anchor.clone());
+            }
+        }
+    }
+    Ok(sessions
+        .iter()
+        .filter_map(|session| {
+            let session_id = session.get("session_id").and_then(Value::as_str)?;
+            Some(MermaidSession {
+                session_id: session_id.to_string(),
+                confidence: session
+                    .get("confidence")
+                    .and_then(Value::as_f64)
+                    .unwrap_or(0.0),
+                anchors: anchors_by_tape.remove(session_id).unwrap_or_default(),
+            })
+        })
+        .collect())
+}
+
+/// Tapes whose evidence was fingerprinted under a different anchor version
+/// than `configured`; their anchors may miss the query's. `None` when every
+/// tape matches.
+fn anchor_version_mismatch_json(
+    indexes: &[SqliteIndex],
+    configured: u32,
+) -> Result<Option<Value>, CliError> {
+    let mut counts = BTreeMap::<u32, u64>::new();
+    for index in indexes {
+        for (version, tapes) in index.anchor_version_counts()? {
+            *counts.entry(version).or_default() += tapes;
+        }
+    }
+    if counts.keys().all(|version| *version == configured) {
+        return Ok(None);
+    }
+    let tapes = counts
+        .iter()
+        .map(|(version, tapes)| json!({"anchor_version": version, "tapes": tapes}))
+        .collect::<Vec<_>>();
+    Ok(Some(json!({
+        "configured": configured,
+        "tapes": tapes,
+        "hint": "run `engram reindex` to refingerprint every tape under the configured anchors",
+    })))
+}
+
+/// Filter the event catalog of every queried store. `--grep` is the only
+/// filter that reads tapes, and only the ones the others left.
+fn cmd_events(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: EventsArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let date_filter = DateFilter::parse(args.since.as_deref(), args.until.as_deref())?;
+    let indexes = open_query_indexes(context)?;
+
+    let mut seen = HashSet::new();
+    let mut matches = Vec::new();
+    for index in &indexes {
+        for (tape_id, record) in
+            index.events_matching(&args.kinds, args.tool.as_deref(), args.file.as_deref())?
+        {
+            if seen.insert((tape_id.clone(), record.offset)) {
+                matches.push((tape_id, record));
+            }
+        }
+    }
+    matches.retain(|(_, record)| {
+        session_matches_date_filter(&json!({"timestamp": record.timestamp}), &date_filter)
+    });
+    if let Some(pattern) = args.grep.as_deref() {
+        let mut lines_by_tape = HashMap::<String, Vec<String>>::new();
+        let mut kept = Vec::with_capacity(matches.len());
+        for (tape_id, record) in matches {
+            if !lines_by_tape.contains_key(&tape_id) {
+                let lines = match resolve_tape_path(context, &tape_id) {
+                    Some(path) => read_tape_content(&path)?
+                        .lines()
+                        .map(str::to_owned)
+                        .collect(),
+                    None => Vec::new(),
+                };
+                lines_by_tape.insert(tape_id.clone(), lines);
+            }
+            let line = lines_by_tape[&tape_id].get(record.offset as usize);
+            if line.is_some_and(|line| line.contains(pattern)) {
+                kept.push((tape_id, record));
+            }
+        }
+        matches = kept;
+    }
+    matches.sort_by(|(left_tape, left), (right_tape, right)| {
+        (&left.timestamp, left_tape, left.offset).cmp(&(&right.timestamp, right_tape, right.offset))
+    });
+
+    let total = matches.len();
+    let events = matches
+        .into_iter()
+        .skip(args.offset)
+        .take(args.limit)
+        .map(|(tape_id, record)| {
+            json!({
+                "tape_id": tape_id,
+                "offset": record.offset,
+                "timestamp": record.timestamp,
+                "kind": record.kind,
+                "tool": record.tool,
+                "file": record.file,
+            })
+        })
+        .collect::<Vec<_>>();
+    print_json(&json!({
+        "query": {
+            "command": "events",
+            "kind": args.kinds,
+            "tool": args.tool,
+            "file": args.file,
+            "since": args.since,
+            "until": args.until,
+            "grep": args.grep,
+            "limit": args.limit,
+            "offset": args.offset,
+        },
+        "stores_queried": indexes.len(),
+        "total": total,
+        "returned": events.len(),
+        "truncated": args.offset + events.len() < total,
+        "events": events,
+    }))
+}
+
+/// Check the last recorded session of every edited file against the disk:
+/// each of its edits whose result the session kept should still be there.
+/// Edits that are not are changes made outside any recorded session.
+fn cmd_drift(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: DriftArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let date_filter = DateFilter::parse(args.since.as_deref(), None)?;
+    let cache = open_fingerprint_cache(context);
+    let tokens_key = format!("tokens:v{}", anchor_version(context.normalize_line_endings));
+    let selectors = args
+        .paths
+        .iter()
+        .map(|raw| {
+            let raw = slash_path(raw.trim_end_matches('/'));
+            glob::Pattern::new(&raw)
+                .map(|pattern| (raw.clone(), pattern))
+                .map_err(|err| CliError::new("invalid_path_glob", format!("`{raw}`: {err}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let selected = |file: &str| {
+        selectors.is_empty()
+            || selectors.iter().any(|(raw, pattern)| {
+                file == raw
+                    || pattern.matches_with(file, watch_glob_match_options())
+                    || file
+                        .strip_prefix(raw.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+    };
+    let indexes = open_query_indexes(context)?;
+
+    // Per file, the edits of the latest session that edited it.
+    let mut seen = HashSet::new();
+    let mut edits_by_file = BTreeMap::<String, Vec<(String, EventRecord)>>::new();
+    for index in &indexes {
+        for (tape_id, record) in index.events_matching(&["code.edit".to_string()], None, None)? {
+            let Some(file) = record.file.clone().filter(|file| selected(file)) else {
+                continue;
+            };
+            if session_matches_date_filter(&json!({"timestamp": record.timestamp}), &date_filter)
+                && seen.insert((tape_id.clone(), record.offset))
+            {
+                edits_by_file
+                    .entry(file)
+                    .or_default()
+                    .push((tape_id, record));
+            }
+        }
+    }
+
+    let mut rows_by_tape = HashMap::<String, Vec<TapeRow>>::new();
+    let mut reports = Vec::new();
+    let (mut edits_checked, mut drifted) = (0usize, 0usize);
+    for (file, edits) in &edits_by_file {
+        let Some(latest_tape) = edits
+            .iter()
+            .max_by(|(left_tape, left), (right_tape, right)| {
+                (&left.timestamp, left_tape).cmp(&(&right.timestamp, right_tape))
+            })
+            .map(|(tape_id, _)| tape_id.clone())
+        else {
+            continue;
+        };
+        if !rows_by_tape.contains_key(&latest_tape) {
+            let rows = match resolve_tape_path(context, &latest_tape) {
+                Some(path) => parse_jsonl_rows(&read_tape_content(&path)?)?,
+                None => Vec::new(),
+            };
+            rows_by_tape.insert(latest_tape.clone(), rows);
+        }
+        let rows = &rows_by_tape[&latest_tape];
+        let mut offsets = edits
+            .iter()
+            .filter(|(tape_id, _)| *tape_id == latest_tape)
+            .map(|(_, record)| record.offset)
+            .collect::<Vec<_>>();
+        offsets.sort_unstable();
+        let expected = offsets
+            .iter()
+            .filter_map(|offset| {
+                let row = rows.iter().find(|row| row.offset == *offset)?;
+                let (before_text, _) = edit_side_text(paths, &row.value, "before");
+                let (after_text, _) = edit_side_text(paths, &row.value, "after");
+                Some(ExpectedEdit {
+                    tape_id: latest_tape.clone(),
+                    offset: *offset,
+                    timestamp: row
+                        .value
+                        .get("t")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    before_text,
+                    after_text: after_text?,
+                })
+            })
+            .collect::<Vec<_>>();
+        let current = fs::read_to_string(cwd.join(file))
+            .ok()
+            .map(|text| evidence_view(context, Path::new(file), text));
+        let current_tokens = match &current {
+            Some(text) => {
+                cached_fingerprints(cache.as_ref(), &cwd.join(file), &tokens_key, || {
+                    Ok(content_tokens(text))
+                })?
+                .into_iter()
+                .collect()
+            }
+            None => HashSet::new(),
+        };
+        for edit in surviving_edits(expected) {
+            edits_checked += 1;
+            let check =
+                check_span_with_tokens(current.as_deref(), &current_tokens, &edit.after_text);
+            if check.state != DriftState::Intact {
+                drifted += 1;
+            } else if !args.all {
+                continue;
+            }
+            reports.push(json!({
+                "file": file,
+                "tape_id": edit.tape_id,
+                "offset": edit.offset,
+                "timestamp": edit.timestamp,
+                "anchor": fingerprint_text(&edit.after_text).fingerprint,
+                "state": check.state.as_str(),
+                "coverage": check.coverage,
+            }));
+        }
+    }
+
+    print_json(&json!({
+        "query": {
+            "command": "drift",
+            "paths": args.paths,
+            "since": args.since,
+        },
+        "stores_queried": indexes.len(),
+        "files_checked": edits_by_file.len(),
+        "edits_checked": edits_checked,
+        "drifted": drifted,
+        "edits": reports,
+    }))
+}
+
+/// One `msg.in` of the prompt log with the files its turn touched.
+struct LoggedPrompt {
+    tape_id: String,
+    record: EventRecord,
+    files: BTreeSet<String>,
+    matches_file: bool,
+}
+
+/// Every user prompt across the queried stores, oldest first. A prompt's
+/// turn runs until the next `msg.in` of its tape; the files read or edited
+/// in it come from the event catalog, so only the returned page of prompts
+/// reads tapes (for their text).
+fn cmd_prompt_log(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: PromptLogArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let date_filter = DateFilter::parse(args.since.as_deref(), args.until.as_deref())?;
+    let indexes = open_query_indexes(context)?;
+    let kinds = ["msg.in", "code.read", "code.edit"].map(str::to_string);
+    let file_kinds = ["code.read", "code.edit"].map(str::to_string);
+
+    let mut seen = HashSet::new();
+    let mut by_tape = BTreeMap::<String, Vec<EventRecord>>::new();
+    let mut matching_touches = HashSet::new();
+    for index in &indexes {
+        for (tape_id, record) in index.events_matching(&kinds, None, None)? {
+            if seen.insert((tape_id.clone(), record.offset)) {
+                by_tape.entry(tape_id).or_default().push(record);
+            }
+        }
+        if let Some(file) = args.file.as_deref() {
+            for (tape_id, record) in index.events_matching(&file_kinds, None, Some(file))? {
+                matching_touches.insert((tape_id, record.offset));
+            }
+        }
+    }
+
+    let mut prompts = Vec::<LoggedPrompt>::new();
+    for (tape_id, mut records) in by_tape {
+        records.sort_by_key(|record| record.offset);
+        for record in records {
+            if record.kind == "msg.in" {
+                prompts.push(LoggedPrompt {
+                    tape_id: tape_id.clone(),
+                    record,
+                    files: BTreeSet::new(),
+                    matches_file: false,
+                });
+            } else if let Some(prompt) = prompts.last_mut().filter(|p| p.tape_id == tape_id) {
+                prompt.matches_file |= matching_touches.contains(&(tape_id.clone(), record.offset));
+                prompt.files.extend(record.file);
+            }
+        }
+    }
+    prompts.retain(|prompt| {
+        (args.file.is_none() || prompt.matches_file)
+            && session_matches_date_filter(
+                &json!({"timestamp": prompt.record.timestamp}),
+                &date_filter,
+            )
+    });
+    prompts.sort_by(|left, right| {
+        (&left.record.timestamp, &left.tape_id, left.record.offset).cmp(&(
+            &right.record.timestamp,
+            &right.tape_id,
+            right.record.offset,
+        ))
+    });
+
+    let total = prompts.len();
+    let mut rows_by_tape = HashMap::<String, Vec<TapeRow>>::new();
+    let mut page = Vec::new();
+    for prompt in prompts.into_iter().skip(args.offset).take(args.limit) {
+        if !rows_by_tape.contains_key(&prompt.tape_id) {
+            let rows = match resolve_tape_path(context, &prompt.tape_id) {
+                Some(path) => parse_jsonl_rows(&read_tape_content(&path)?)?,
+                None => Vec::new(),
+            };
+            rows_by_tape.insert(prompt.tape_id.clone(), rows);
+        }
+        let text = rows_by_tape[&prompt.tape_id]
+            .iter()
+            .find(|row| row.offset == prompt.record.offset)
+            .and_then(|row| row.value.get("text"))
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        page.push(json!({
+            "tape_id": prompt.tape_id,
+            "offset": prompt.record.offset,
+            "timestamp": prompt.record.timestamp,
+            "text": text,
+            "files": prompt.files,
+        }));
+    }
+    print_json(&json!({
+        "query": {
+            "command": "prompt-log",
+            "since": args.since,
+            "until": args.until,
+            "file": args.file,
+            "limit": args.limit,
+            "offset": args.offset,
+        },
+        "stores_queried": indexes.len(),
+        "total": total,
+        "returned": page.len(),
+        "truncated": args.offset + page.len() < total,
+        "prompts": page,
+    }))
+}
+
+/// Per-tool call, failure and output-size counts from the event catalog.
+#[derive(Debug, Default)]
+struct ToolUsage {
+    calls: usize,
+    results: usize,
+    errors: usize,
+    output_bytes: Vec<u64>,
+}
+
+impl ToolUsage {
+    fn add(&mut self, record: &EventRecord) {
+        if record.kind == "tool.call" {
+            self.calls += 1;
+            return;
+        }
+        self.results += 1;
+        if record.exit.is_some_and(|exit| exit != 0) {
+            self.errors += 1;
+        }
+        if let Some(bytes) = record.output_bytes {
+            self.output_bytes.push(bytes);
+        }
+    }
+
+    fn to_json(&self, tool: &str) -> Value {
+        let mut sizes = self.output_bytes.clone();
+        sizes.sort_unstable();
+        let median = match sizes.len() {
+            0 => None,
+            len if len % 2 == 1 => Some(sizes[len / 2]),
+            len => Some((sizes[len / 2 - 1] + sizes[len / 2]) / 2),
+        };
+        // Results without a matching call (harnesses that only log results)
+        // still count as calls for the rate.
+        let attempts = self.calls.max(self.results);
+        json!({
+            "tool": tool,
+            "calls": self.calls,
+            "results": self.results,
+            "errors": self.errors,
+            "error_rate": if attempts == 0 { 0.0 } else { self.errors as f64 / attempts as f64 },
+            "median_result_bytes": median,
+        })
+    }
+}
+
+fn tool_usage_json(usage: &BTreeMap<String, ToolUsage>) -> Vec<Value> {
+    let mut tools = usage.iter().collect::<Vec<_>>();
+    tools.sort_by(|(left_name, left), (right_name, right)| {
+        (right.calls.max(right.results), left_name).cmp(&(left.calls.max(left.results), right_name))
+    });
+    tools
+        .into_iter()
+        .map(|(tool, usage)| usage.to_json(tool))
+        .collect()
+}
+
+/// Tool calls, error rates and median result sizes per tool and per
+/// harness, from the event catalog of every queried store.
+fn cmd_tools(paths: &RepoPaths, context: &RuntimeContext, args: ToolsArgs) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let date_filter = DateFilter::parse(args.since.as_deref(), args.until.as_deref())?;
+    let indexes = open_query_indexes(context)?;
+
+    let mut seen = HashSet::new();
+    let mut by_tool = BTreeMap::<String, ToolUsage>::new();
+    let mut by_harness = BTreeMap::<String, BTreeMap<String, ToolUsage>>::new();
+    let mut tapes = HashSet::new();
+    for index in &indexes {
+        for (harness, tape_id, record) in index.tool_events()? {
+            if !session_matches_date_filter(&json!({"timestamp": record.timestamp}), &date_filter)
+                || !seen.insert((tape_id.clone(), record.offset))
+            {
+                continue;
+            }
+            let tool = record.tool.clone().unwrap_or_else(|| "unknown".to_string());
+            let harness = harness.unwrap_or_else(|| "unknown".to_string());
+            by_tool.entry(tool.clone()).or_default().add(&record);
+            by_harness
+                .entry(harness)
+                .or_default()
+                .entry(tool)
+                .or_default()
+                .add(&record);
+            tapes.insert(tape_id);
+        }
+    }
+
+    print_json(&json!({
+        "query": {
+            "command": "tools",
+            "since": args.since,
+            "until": args.until,
+        },
+        "stores_queried": indexes.len(),
+        "tapes": tapes.len(),
+        "tools": tool_usage_json(&by_tool),
+        "harnesses": by_harness
+            .iter()
+            .map(|(harness, usage)| json!({"harness": harness, "tools": tool_usage_json(usage)}))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+fn cmd_grep(paths: &RepoPaths, context: &RuntimeContext, args: GrepArgs) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+
+    let indexes = open_query_indexes(context)?;
+    let (raw_sessions, grep_rank_by_session) =
+        collect_grep_matches(context, &indexes, &args.pattern)?;
+    let score_by_session = grep_rank_by_session
+        .iter()
+        .map(|(session_id, rank)| (session_id.clone(), rank.match_count as f32))
+        .collect::<HashMap<_, _>>();
+    let date_filter = DateFilter::parse(args.since.as_deref(), args.until.as_deref())?;
+    let mut sessions =
+        format_sessions_for_agent(context, &indexes[0], raw_sessions, &score_by_session, None)?;
+    sessions.retain(|session| session_matches_date_filter(session, &date_filter));
+    sessions.sort_by(|a, b| compare_grep_sessions(a, b, &grep_rank_by_session));
+    if sessions.is_empty() {
+        return Err(CliError::new("no_results", args.pattern));
+    }
+
+    let (sessions, returned, total, time_range, truncated) = apply_session_truncation(
+        sessions,
+        args.limit,
+        args.offset,
+        context.explain_default_limit,
+    );
+    if sessions.is_empty() {
+        return Err(CliError::new("no_results", args.pattern));
+    }
+
+    let metrics_sessions = if args.count { Vec::new() } else { sessions };
+    append_metrics(
+        context,
+        "grep",
+        &args.pattern,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+    );
+
+    emit_query_result(
+        &indexes[0],
+        "grep",
+        json!({
+        "query": {
+            "command": "grep",
+            "pattern": args.pattern,
+            "limit": args.limit,
+            "offset": args.offset,
+            "since": args.since,
+            "until": args.until,
+            "count": args.count,
+        },
+        "sessions": metrics_sessions,
+        "lineage": [],
+        "dispatch_lineage": [],
+        "tombstones": [],
+        "stores_queried": indexes.len(),
+        "returned": returned,
+        "total": total,
+        "time_range": time_range,
+        "truncated": truncated,
+        }),
+    )
+}
+
+fn cmd_search(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: SearchArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    if !args.semantic {
+        return Err(CliError::new(
+            "invalid_search",
+            "search needs --semantic; use grep for literal text",
+        ));
+    }
+    let config = context.embeddings.as_ref().ok_or_else(|| {
+        CliError::new(
+            "embeddings_not_configured",
+            "add an embeddings: section to config.yml and re-run ingest",
+        )
+    })?;
+    let provider = provider_from_config(config)
+        .map_err(|err| CliError::new("embedding_error", err.to_string()))?;
+    let query_vector = provider
+        .embed(std::slice::from_ref(&args.query))
+        .map_err(|err| CliError::new("embedding_error", err.to_string()))?
+        .pop()
+        .unwrap_or_default();
+
+    let indexes = open_query_indexes(context)?;
+    let mut matches_by_session: HashMap<String, Vec<SemanticHit>> = HashMap::new();
+    for index in &indexes {
+        for hit in index.semantic_search(&provider.id(), &query_vector, SEMANTIC_CANDIDATES)? {
+            let matches = matches_by_session.entry(hit.tape_id.clone()).or_default();
+            if !matches
+                .iter()
+                .any(|existing| existing.event_offset == hit.event_offset)
+            {
+                matches.push(hit);
+            }
+        }
+    }
+
+    let mut cache = HashMap::new();
+    let mut raw_sessions = Vec::new();
+    let mut score_by_session = HashMap::new();
+    for (tape_id, matches) in &mut matches_by_session {
+        matches.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let best = &matches[0];
+        let rows = load_tape_rows_cached(context, &mut cache, tape_id)?;
+        let windows = event_window(rows, best.event_offset, TRANSCRIPT_WINDOW_RADIUS)
+            .into_iter()
+            .collect::<Vec<_>>();
+        score_by_session.insert(tape_id.clone(), best.score);
+        raw_sessions.push(json!({
+            "tape_id": tape_id,
+            "tape_present_locally": !rows.is_empty(),
+            "touch_count": matches.len(),
+            "latest_touch_timestamp": extract_latest_timestamp_from_rows(rows),
+            "touches": [],
+            "windows": windows,
+        }));
+    }
+
+    let date_filter = DateFilter::parse(args.since.as_deref(), args.until.as_deref())?;
+    let mut sessions =
+        format_sessions_for_agent(context, &indexes[0], raw_sessions, &score_by_session, None)?;
+    sessions.retain(|session| session_matches_date_filter(session, &date_filter));
+    for session in &mut sessions {
+        let session_id = session
+            .get("session_id")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let matches = matches_by_session
+            .get(session_id)
+            .map(|matches| {
+                matches
+                    .iter()
+                    .take(SEMANTIC_MATCHES_PER_SESSION)
+                    .map(|hit| json!({"offset": hit.event_offset, "kind": hit.kind, "score": hit.score}))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        session["matches"] = json!(matches);
+    }
+    sessions.sort_by(|a, b| {
+        let a_score = a.get("confidence").and_then(Value::as_f64).unwrap_or(0.0);
+        let b_score = b.get("confidence").and_then(Value::as_f64).unwrap_or(0.0);
+        let a_ts = a.get("timestamp").and_then(Value::as_str).unwrap_or("");
+        let b_ts = b.get("timestamp").and_then(Value::as_str).unwrap_or("");
+        b_score
+            .partial_cmp(&a_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b_ts.cmp(a_ts))
+    });
+
+    let (sessions, returned, total, time_range, truncated) = apply_session_truncation(
+        sessions,
+        args.limit,
+        args.offset,
+        context.explain_default_limit,
+    );
+    if sessions.is_empty() {
+        return Err(CliError::new("no_results", args.query));
+    }
+    append_metrics(
+        context,
+        "search",
+        &args.query,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+    );
+
+    emit_query_result(
+        &indexes[0],
+        "search",
+        json!({
+            "query": {
+                "command": "search",
+                "text": args.query,
+                "semantic": true,
+                "provider": provider.id(),
+                "limit": args.limit,
+                "offset": args.offset,
+                "since": args.since,
+                "until": args.until,
+            },
+            "sessions": sessions,
+            "lineage": [],
+            "dispatch_lineage": [],
+            "tombstones": [],
+            "stores_queried": indexes.len(),
+            "returned": returned,
+            "total": total,
+            "time_range": time_range,
+            "truncated": truncated,
+        }),
+    )
+}
+
+/// Embed a tape's message and tool events with the configured provider, once
+/// per provider. A no-op when no `embeddings:` section is configured.
+fn embed_tape_events(
+    index: &SqliteIndex,
+    config: Option<&EffectiveEmbeddingsConfig>,
+    tape_id: &str,
+    jsonl: &str,
+) -> Result<usize, CliError> {
+    let Some(config) = config else {
+        return Ok(0);
+    };
+    let provider = provider_from_config(config)
+        .map_err(|err| CliError::new("embedding_error", err.to_string()))?;
+    let provider_id = provider.id();
+    if index.has_event_embeddings(tape_id, &provider_id)? {
+        return Ok(0);
+    }
+    let events = embeddable_events(jsonl);
+    if events.is_empty() {
+        return Ok(0);
+    }
+    let texts = events
+        .iter()
+        .map(|event| event.text.clone())
+        .collect::<Vec<_>>();
+    let vectors = provider
+        .embed(&texts)
+        .map_err(|err| CliError::new("embedding_error", err.to_string()))?;
+    let rows = events
+        .iter()
+        .zip(vectors)
+        .map(|(event, vector)| (event.event_offset, event.kind.as_str(), vector))
+        .collect::<Vec<_>>();
+    index.upsert_event_embeddings(tape_id, &provider_id, &rows)?;
+    Ok(rows.len())
+}
+
+fn cmd_peek(paths: &RepoPaths, context: &RuntimeContext, args: PeekArgs) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+
+    let indexes = open_query_indexes(context)?;
+    let session_id = args.session_id;
+    let Some(tape) = read_tape_or_session(paths, context, &session_id)? else {
+        return Err(CliError::new("session_not_found", session_id));
+    };
+    let raw_text = tape.content;
+    let rows = parse_jsonl_rows(&raw_text)?;
+    let total_lines = raw_text.lines().count();
+    let content_lines = raw_text.lines().collect::<Vec<_>>();
+    let timestamp = extract_latest_timestamp_from_rows(&rows);
+    let grep_context = context.peek_grep_context.max(1);
+
+    let (window_start, window_end, content) = if let Some(pattern) = args.grep_filter.as_deref() {
+        let mut hits = Vec::new();
+        for (idx, line) in content_lines.iter().enumerate() {
+            if line.contains(pattern) {
+                hits.push(idx);
+            }
+        }
+        if hits.is_empty() {
+            return Err(CliError::new("no_results", pattern.to_string()));
+        }
+        let mut ranges = Vec::new();
+        for idx in hits {
+            let start = idx.saturating_sub(grep_context);
+            let end = usize::min(total_lines.saturating_sub(1), idx + grep_context);
+            ranges.push((start, end));
+        }
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in ranges {
+            if let Some(last) = merged.last_mut()
+                && start <= last.1.saturating_add(1)
+            {
+                last.1 = usize::max(last.1, end);
+                continue;
+            }
+            merged.push((start, end));
+        }
+        let mut out = Vec::new();
+        let mut first = usize::MAX;
+        let mut last = 0usize;
+        for (start, end) in merged {
+            first = usize::min(first, start);
+            last = usize::max(last, end);
+            for idx in start..=end {
+                out.push(json!({
+                    "line": idx + 1,
+                    "text": content_lines.get(idx).copied().unwrap_or_default(),
+                }));
+            }
+        }
+        (first + 1, last + 1, out)
+    } else {
         let anchor_line = default_peek_anchor_line(&indexes[0], &session_id, &rows);
         if let Some(start) = args.start {
             let line_count = args.lines.unwrap_or(context.peek_default_lines).max(1);
@@ -2280,92 +8335,1025 @@ fn cmd_peek(paths: &RepoPaths, context: &RuntimeContext, args: PeekArgs) -> Resu
         }
     };
 
-    if content.is_empty() {
-        return Err(CliError::new("no_results", session_id.clone()));
+    if content.is_empty() {
+        return Err(CliError::new("no_results", session_id.clone()));
+    }
+    let window_lines = content.len();
+    append_metrics(
+        context,
+        "peek",
+        &session_id,
+        Value::String(session_id.clone()),
+        json!(window_start),
+        json!(window_lines),
+        json!(total_lines),
+    );
+
+    emit_query_result(
+        &indexes[0],
+        "peek",
+        json!({
+        "query": {
+            "command": "peek",
+            "session_id": session_id,
+            "start": args.start,
+            "lines": args.lines,
+            "before": args.before,
+            "after": args.after,
+            "grep_filter": args.grep_filter,
+        },
+        "session": {
+            "session_id": session_id,
+            "timestamp": timestamp,
+            "window_start": window_start,
+            "window_end": window_end,
+            "total_lines": total_lines,
+            "content": content,
+        }
+        }),
+    )
+}
+
+#[derive(Debug, Clone)]
+enum ExplainTarget {
+    FileRange { file: String, start: u32, end: u32 },
+    FileWhole { file: String },
+    Literal(String),
+}
+
+fn open_query_indexes(context: &RuntimeContext) -> Result<Vec<SqliteIndex>, CliError> {
+    let mut indexes = Vec::new();
+    indexes.push(open_index(context)?);
+    for (_, path) in
+        list_partitions(&context.db_path).map_err(|err| CliError::io("read_dir_error", err))?
+    {
+        indexes.push(open_index_at(context, &path)?);
+    }
+    for store in &context.additional_stores {
+        if store.exists() {
+            indexes.push(open_index_at(context, store)?);
+        }
+    }
+    Ok(indexes)
+}
+
+/// Edits between `--good` and `--bad` to the span or its lineage ancestors,
+/// grouped by session and ranked: an edit of the span's current code counts
+/// fully, an edit of an ancestor half, and ties go to the session closest to
+/// `--bad`.
+fn cmd_bisect(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: BisectArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let good = parse_date_bound(Some(&args.good), DateBound::Since)?
+        .ok_or_else(|| CliError::new("invalid_date", "--good is required"))?;
+    let bad = parse_date_bound(Some(&args.bad), DateBound::Until)?
+        .ok_or_else(|| CliError::new("invalid_date", "--bad is required"))?;
+    if good >= bad {
+        return Err(CliError::new(
+            "invalid_bisect_range",
+            "--good must be earlier than --bad",
+        ));
+    }
+    let (file, start, end) = parse_file_range_target(&args.target)?;
+    let anchors = explain_target_anchors(
+        cwd,
+        &ExplainTarget::FileRange {
+            file: file.to_string(),
+            start,
+            end,
+        },
+        false,
+        context,
+    )?;
+    let indexes = open_query_indexes(context)?;
+    let traversal = ExplainTraversal {
+        min_confidence: args.min_confidence,
+        ..ExplainTraversal::default()
+    };
+    let result = explain_across_indexes(&indexes, &anchors, traversal, false)?;
+    let direct = result.direct.iter().map(touch_key).collect::<HashSet<_>>();
+    let touches = collect_touch_evidence(&indexes, &result.direct, &result.touched_anchors)?;
+
+    struct Candidate {
+        score: f32,
+        direct_edits: usize,
+        lineage_edits: usize,
+        first_edit: String,
+        last_edit: String,
+        files: BTreeSet<String>,
+        offsets: BTreeSet<u64>,
+    }
+    let mut candidates = BTreeMap::<String, Candidate>::new();
+    for fragment in touches {
+        if fragment.kind != EvidenceKind::Edit {
+            continue;
+        }
+        let Ok(at) = chrono::DateTime::parse_from_rfc3339(&fragment.timestamp) else {
+            continue;
+        };
+        let at = at.with_timezone(&Utc);
+        if at <= good || at > bad {
+            continue;
+        }
+        let is_direct = direct.contains(&touch_key(&fragment));
+        let candidate = candidates
+            .entry(fragment.tape_id.clone())
+            .or_insert_with(|| Candidate {
+                score: 0.0,
+                direct_edits: 0,
+                lineage_edits: 0,
+                first_edit: fragment.timestamp.clone(),
+                last_edit: fragment.timestamp.clone(),
+                files: BTreeSet::new(),
+                offsets: BTreeSet::new(),
+            });
+        if !candidate.offsets.insert(fragment.event_offset) {
+            continue;
+        }
+        if is_direct {
+            candidate.score += 1.0;
+            candidate.direct_edits += 1;
+        } else {
+            candidate.score += 0.5;
+            candidate.lineage_edits += 1;
+        }
+        candidate.first_edit = candidate.first_edit.clone().min(fragment.timestamp.clone());
+        candidate.last_edit = candidate.last_edit.clone().max(fragment.timestamp.clone());
+        candidate.files.insert(fragment.file_path);
+    }
+
+    let total_score = candidates.values().map(|c| c.score).sum::<f32>();
+    let mut ranked = candidates.into_iter().collect::<Vec<_>>();
+    ranked.sort_by(|(_, left), (_, right)| {
+        right
+            .score
+            .total_cmp(&left.score)
+            .then_with(|| right.last_edit.cmp(&left.last_edit))
+    });
+    let sessions = ranked
+        .into_iter()
+        .map(|(tape_id, candidate)| {
+            json!({
+                "tape_id": tape_id,
+                "likelihood": candidate.score / total_score,
+                "direct_edits": candidate.direct_edits,
+                "lineage_edits": candidate.lineage_edits,
+                "first_edit": candidate.first_edit,
+                "last_edit": candidate.last_edit,
+                "files": candidate.files,
+                "offsets": candidate.offsets,
+            })
+        })
+        .collect::<Vec<_>>();
+    print_json(&json!({
+        "target": args.target,
+        "good": good.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "bad": bad.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "lineage_edges": result.lineage.len(),
+        "candidate_count": sessions.len(),
+        "sessions": sessions,
+    }))
+}
+
+/// Follow lineage from the span back to its roots and report the earliest
+/// session that touched one of them, preferring edits over reads: the tape,
+/// the user prompt that led to it, and the session's model and repo head.
+fn cmd_origin(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: OriginArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let (file, start, end) = parse_file_range_target(&args.target)?;
+    let anchors = explain_target_anchors(
+        cwd,
+        &ExplainTarget::FileRange {
+            file: file.to_string(),
+            start,
+            end,
+        },
+        false,
+        context,
+    )?;
+    let indexes = open_query_indexes(context)?;
+    let traversal = ExplainTraversal {
+        min_confidence: args.min_confidence,
+        ..ExplainTraversal::default()
+    };
+    let result = explain_across_indexes(&indexes, &anchors, traversal, false)?;
+
+    // With no lineage the queried anchors are their own roots.
+    let chains = best_lineage_paths(&result.lineage, &anchors);
+    let roots = if chains.is_empty() {
+        anchors.clone()
+    } else {
+        chains
+            .iter()
+            .map(|chain| chain.root().to_string())
+            .collect::<Vec<_>>()
+    };
+    let mut earliest: Option<(String, EvidenceFragmentRef)> = None;
+    for root in &roots {
+        for index in &indexes {
+            for fragment in index.evidence_for_anchor(root)? {
+                // Edits introduce code; a read only shows it already existed.
+                let rank = (
+                    fragment.kind != EvidenceKind::Edit,
+                    fragment.timestamp.clone(),
+                );
+                let better = earliest.as_ref().is_none_or(|(_, current)| {
+                    rank < (
+                        current.kind != EvidenceKind::Edit,
+                        current.timestamp.clone(),
+                    )
+                });
+                if better {
+                    earliest = Some((root.clone(), fragment));
+                }
+            }
+        }
+    }
+
+    let Some((root, fragment)) = earliest else {
+        return print_json(&json!({
+            "target": args.target,
+            "found": false,
+            "origin": null,
+            "chain": null,
+            "lineage_edges": result.lineage.len(),
+        }));
+    };
+    let (prompt, meta) = match resolve_tape_path(context, &fragment.tape_id) {
+        Some(tape_path) => {
+            let content = read_tape_content(&tape_path)?;
+            let prompt = prompt_before(&parse_jsonl_rows(&content)?, fragment.event_offset);
+            (prompt, extract_meta(&parse_jsonl_events(&content)?))
+        }
+        None => (None, None),
+    };
+    let meta_field = |name: &str| meta.as_ref().and_then(|meta| meta.get(name)).cloned();
+    let chain = chains.iter().find(|chain| chain.root() == root);
+    print_json(&json!({
+        "target": args.target,
+        "found": true,
+        "origin": {
+            "tape_id": fragment.tape_id,
+            "offset": fragment.event_offset,
+            "timestamp": fragment.timestamp,
+            "kind": evidence_kind_name(fragment.kind),
+            "file": fragment.file_path,
+            "anchor": root,
+            "prompt": prompt,
+            "model": meta_field("model"),
+            "label": meta_field("label"),
+            "repo_head": meta_field("repo_head"),
+        },
+        "chain": chain.map(|chain| json!({
+            "anchors": chain.anchors,
+            "confidence": chain.confidence,
+            "hops": chain.edges.len(),
+        })),
+        "lineage_edges": result.lineage.len(),
+    }))
+}
+
+/// Longest prompt excerpt `why` prints.
+const MAX_WHY_PROMPT_CHARS: usize = 120;
+
+/// `explain` reduced to one sentence: the latest session whose edit left
+/// the code around `<file>:<line>` as it is now, when, with which harness
+/// and model, and the prompt that led to it.
+fn cmd_why(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: WhyArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let usage = || CliError::new("invalid_why_target", "expected <file>:<line>");
+    let (target, _) = granular_target(cwd, context, &args.target, ExplainGranularity::Function)
+        .map_err(|err| match err.code {
+            "invalid_explain_target" => usage(),
+            _ => err,
+        })?;
+    let ExplainTarget::FileRange { file, start, end } = &target else {
+        return Err(usage());
+    };
+    let span = format!("{} (lines {start}-{end})", args.target);
+    let anchors = explain_target_anchors(cwd, &target, false, context)?;
+    let indexes = open_query_indexes(context)?;
+    let traversal = ExplainTraversal {
+        min_confidence: args.min_confidence,
+        ..ExplainTraversal::default()
+    };
+    let result = explain_across_indexes(&indexes, &anchors, traversal, false)?;
+    let latest = result
+        .direct
+        .into_iter()
+        .filter(|fragment| fragment.kind == EvidenceKind::Edit)
+        .max_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    let porcelain = args.porcelain.map(PorcelainVersion::from);
+    let Some(fragment) = latest else {
+        match porcelain {
+            Some(PorcelainVersion::V1) => println!(
+                "{}",
+                porcelain_line(&["why", file, &start.to_string(), &end.to_string()])
+            ),
+            None => println!("No recorded session changed {span}."),
+        }
+        return Ok(());
+    };
+
+    let mut metrics = None;
+    for index in &indexes {
+        if let Some(found) = index.tape_metrics(&fragment.tape_id)? {
+            metrics = Some(found);
+            break;
+        }
     }
-    let window_lines = content.len();
-    append_metrics(
-        context,
-        "peek",
-        &session_id,
-        Value::String(session_id.clone()),
-        json!(window_start),
-        json!(window_lines),
-        json!(total_lines),
-    );
+    let (prompt, meta_model) = match resolve_tape_path(context, &fragment.tape_id) {
+        Some(tape_path) => {
+            let content = read_tape_content(&tape_path)?;
+            let prompt = prompt_before(&parse_jsonl_rows(&content)?, fragment.event_offset);
+            let model = extract_meta(&parse_jsonl_events(&content)?)
+                .and_then(|meta| meta.get("model").and_then(Value::as_str).map(str::to_owned));
+            (prompt, model)
+        }
+        None => (None, None),
+    };
+    let harness = metrics.as_ref().and_then(|metrics| metrics.harness.clone());
+    let model = metrics.and_then(|metrics| metrics.model).or(meta_model);
+    let prompt = prompt.map(|prompt| prompt.trim_start_matches("user: ").to_string());
 
-    emit_query_result(
-        &indexes[0],
-        "peek",
-        json!({
-        "query": {
-            "command": "peek",
-            "session_id": session_id,
-            "start": args.start,
-            "lines": args.lines,
-            "before": args.before,
-            "after": args.after,
-            "grep_filter": args.grep_filter,
+    if let Some(PorcelainVersion::V1) = porcelain {
+        println!(
+            "{}",
+            porcelain_line(&[
+                "why",
+                file,
+                &start.to_string(),
+                &end.to_string(),
+                &fragment.tape_id,
+                &fragment.timestamp,
+                harness.as_deref().unwrap_or_default(),
+                model.as_deref().unwrap_or_default(),
+                prompt.as_deref().unwrap_or_default(),
+            ])
+        );
+        return Ok(());
+    }
+    let mut sentence = format!("{span} was last changed by session {}", fragment.tape_id);
+    if let Some(date) = display_date(&fragment.timestamp, args.timezone.into()) {
+        sentence.push_str(&format!(" on {date}"));
+    }
+    let agent = [harness, model].into_iter().flatten().collect::<Vec<_>>();
+    if !agent.is_empty() {
+        sentence.push_str(&format!(" ({})", agent.join("/")));
+    }
+    match prompt {
+        Some(prompt) => sentence.push_str(&format!(
+            ": \"{}\"",
+            truncate_chars(&prompt, MAX_WHY_PROMPT_CHARS)
+        )),
+        None => sentence.push('.'),
+    }
+    println!("{sentence}");
+    Ok(())
+}
+
+/// Per-region provenance for editors to render as code lenses: each
+/// definition and each chunk of code between definitions, with how often
+/// recorded sessions touched it and the latest of them. Regions are
+/// fingerprinted through the fingerprint cache and matched against the
+/// evidence recorded on the file alone, without a lineage walk.
+fn cmd_lens(
+    cwd: &Path,
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    args: LensArgs,
+) -> Result<(), CliError> {
+    ensure_local_store(paths)?;
+    print_context_conspicuity(context);
+    ensure_db_parent(&context.db_path)?;
+    let file = args.file.as_str();
+    let text =
+        fs::read_to_string(cwd.join(file)).map_err(|err| CliError::io("read_span_error", err))?;
+    let text = evidence_view(context, Path::new(file), text);
+    let indexes = open_query_indexes(context)?;
+
+    let mut by_anchor = HashMap::<String, Vec<EvidenceFragmentRef>>::new();
+    let mut seen = HashSet::new();
+    for recorded in recorded_file_paths(cwd, file) {
+        let pattern = glob::Pattern::escape(&recorded);
+        for index in &indexes {
+            for (anchor, fragment) in index.evidence_for_file(&pattern, &TimeRange::default())? {
+                let key = (
+                    anchor.clone(),
+                    fragment.tape_id.clone(),
+                    fragment.event_offset,
+                );
+                if seen.insert(key) {
+                    by_anchor.entry(anchor).or_default().push(fragment);
+                }
+            }
+        }
+    }
+
+    let mut regions = Vec::new();
+    for region in lens_regions(&text) {
+        let anchors = explain_target_anchors(
+            cwd,
+            &ExplainTarget::FileRange {
+                file: file.to_string(),
+                start: region.span.start,
+                end: region.span.end,
+            },
+            false,
+            context,
+        )?;
+        regions.push(lens_region_json(&region, &anchors, &by_anchor));
+    }
+    if let Some(PorcelainVersion::V1) = args.porcelain.map(PorcelainVersion::from) {
+        for region in &regions {
+            println!("{}", lens_porcelain_line(file, region));
+        }
+        return Ok(());
+    }
+    print_json(&json!({
+        "file": file,
+        "line_count": text.lines().count(),
+        "regions": regions,
+    }))
+}
+
+/// The forms a file may be recorded under in evidence: relative to the
+/// repository, and absolute both as written and with symlinks resolved.
+fn recorded_file_paths(cwd: &Path, file: &str) -> Vec<String> {
+    let absolute = normalize_path(&cwd.join(file));
+    let relative = repo_relative_path(&absolute.to_string_lossy(), Some(cwd));
+    let mut forms = Vec::new();
+    for form in [
+        relative.clone(),
+        format!("./{relative}"),
+        slash_path(&absolute.to_string_lossy()),
+        slash_path(&canonicalize_or_normalize(&absolute).to_string_lossy()),
+    ] {
+        if !forms.contains(&form) {
+            forms.push(form);
+        }
+    }
+    forms
+}
+
+/// A `lens_region_json` value as a porcelain v1 `region` record; the free
+/// text label comes last.
+fn lens_porcelain_line(file: &str, region: &Value) -> String {
+    let text = |value: &Value| match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    let last = &region["last_session"];
+    porcelain_line(&[
+        "region",
+        file,
+        &text(&region["start"]),
+        &text(&region["end"]),
+        &text(&region["kind"]),
+        &text(&region["touch_count"]),
+        &text(&region["session_count"]),
+        &text(&last["tape_id"]),
+        &text(&last["timestamp"]),
+        &text(&last["kind"]),
+        &last["confidence"]
+            .as_f64()
+            .map(|confidence| format!("{confidence:.3}"))
+            .unwrap_or_default(),
+        &text(&region["label"]),
+    ])
+}
+
+fn lens_region_json(
+    region: &LensRegion,
+    anchors: &[String],
+    by_anchor: &HashMap<String, Vec<EvidenceFragmentRef>>,
+) -> Value {
+    let mut touches = HashMap::<(&str, u64), &EvidenceFragmentRef>::new();
+    let mut hits = HashMap::<&str, HashSet<&str>>::new();
+    for anchor in anchors {
+        for fragment in by_anchor.get(anchor).into_iter().flatten() {
+            touches.insert((&fragment.tape_id, fragment.event_offset), fragment);
+            hits.entry(&fragment.tape_id).or_default().insert(anchor);
+        }
+    }
+    // Like explain's session confidence: the share of the region's anchors
+    // the session's evidence covers.
+    let last_session = touches
+        .values()
+        .max_by(|a, b| (&a.timestamp, &a.tape_id).cmp(&(&b.timestamp, &b.tape_id)))
+        .map(|fragment| {
+            let covered = hits.get(fragment.tape_id.as_str()).map_or(0, HashSet::len);
+            json!({
+                "tape_id": fragment.tape_id,
+                "timestamp": fragment.timestamp,
+                "kind": evidence_kind_name(fragment.kind),
+                "confidence": covered as f32 / anchors.len().max(1) as f32,
+            })
+        });
+    json!({
+        "start": region.span.start,
+        "end": region.span.end,
+        "kind": if region.label.is_some() { "definition" } else { "chunk" },
+        "label": region.label,
+        "touch_count": touches.len(),
+        "session_count": hits.len(),
+        "last_session": last_session,
+    })
+}
+
+/// Attach each `--also` store to `index` read-only and return a context that
+/// also resolves tapes from the sibling stores. A directory is read as an
+/// `.engram` data directory (`index.sqlite` and `tapes/`); any other path is
+/// taken to be the index file itself, with tapes in a `tapes` directory
+/// beside it.
+fn attach_sibling_stores(
+    context: &RuntimeContext,
+    index: &mut SqliteIndex,
+    also: &[PathBuf],
+) -> Result<RuntimeContext, CliError> {
+    let mut context = context.clone();
+    let own_db = fs::canonicalize(&context.db_path).ok();
+    for store in also {
+        let db_path = sibling_index_path(store)?;
+        if fs::canonicalize(&db_path).ok() == own_db {
+            continue;
+        }
+        index.attach_read_only(&db_path).map_err(|err| {
+            CliError::new(
+                "sibling_index_error",
+                format!("cannot attach {}: {err}", db_path.display()),
+            )
+        })?;
+        push_tape_lookup_dir(
+            &mut context.tape_lookup_dirs,
+            db_path.with_file_name("tapes"),
+        );
+    }
+    Ok(context)
+}
+
+/// The index file of a sibling store given as a `.engram` data directory or
+/// as the index file itself.
+fn sibling_index_path(store: &Path) -> Result<PathBuf, CliError> {
+    let db_path = if store.is_dir() {
+        store.join("index.sqlite")
+    } else {
+        store.to_path_buf()
+    };
+    if !db_path.is_file() {
+        return Err(CliError::new(
+            "invalid_store",
+            format!("no engram index at {}", db_path.display()),
+        ));
+    }
+    Ok(db_path)
+}
+
+fn classify_explain_target(
+    cwd: &Path,
+    _context: &RuntimeContext,
+    _indexes: &[SqliteIndex],
+    target: &str,
+    anchor_mode: bool,
+) -> Result<ExplainTarget, CliError> {
+    if anchor_mode {
+        return Ok(ExplainTarget::Literal(target.to_string()));
+    }
+
+    if has_span_shape(target) {
+        let (file, start, end) = parse_file_range_target(target)?;
+        if cwd.join(file).exists() {
+            return Ok(ExplainTarget::FileRange {
+                file: file.to_string(),
+                start,
+                end,
+            });
+        }
+    }
+
+    if cwd.join(target).is_file() {
+        return Ok(ExplainTarget::FileWhole {
+            file: target.to_string(),
+        });
+    }
+
+    Ok(ExplainTarget::Literal(target.to_string()))
+}
+
+/// Where `explain --at` stands: nothing recorded after `cutoff` is read, and
+/// file targets resolve against `commit` when there is one.
+#[derive(Debug, Clone)]
+struct ExplainAt {
+    cutoff: chrono::DateTime<Utc>,
+    commit: Option<String>,
+}
+
+/// Read `--at` as a time (its commit is the last one on `HEAD` before it) or
+/// else as a git commit (its cutoff is the commit time).
+fn resolve_explain_at(cwd: &Path, raw: &str) -> Result<ExplainAt, CliError> {
+    if let Ok(Some(cutoff)) = parse_date_bound(Some(raw), DateBound::Until) {
+        let before = format!(
+            "--before={}",
+            cutoff.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        );
+        return Ok(ExplainAt {
+            cutoff,
+            commit: git_output(cwd, &["rev-list", "-1", &before, "HEAD"]),
+        });
+    }
+    let invalid = || {
+        CliError::new(
+            "invalid_at",
+            format!("`{raw}` is neither a date nor a git commit"),
+        )
+    };
+    let commit = git_output(
+        cwd,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{raw}^{{commit}}"),
+        ],
+    )
+    .ok_or_else(invalid)?;
+    let committed =
+        git_output(cwd, &["show", "-s", "--format=%cI", &commit]).ok_or_else(invalid)?;
+    let cutoff = chrono::DateTime::parse_from_rfc3339(&committed)
+        .map_err(|_| invalid())?
+        .with_timezone(&Utc);
+    Ok(ExplainAt {
+        cutoff,
+        commit: Some(commit),
+    })
+}
+
+/// The file part of a `<file>`, `<file>:<line>` or `<file>:<start>-<end>`
+/// explain target.
+fn target_file(target: &str) -> &str {
+    match target.rsplit_once(':') {
+        Some((file, lines))
+            if !lines.is_empty() && lines.chars().all(|c| c.is_ascii_digit() || c == '-') =>
+        {
+            file
+        }
+        _ => target,
+    }
+}
+
+/// The directory an `explain --at`, `--from-git`, `--rev` or `--staged`
+/// target resolves in: for a file target, a copy of the file as it was at
+/// `commit` (as staged in the git index when `commit` is empty) under
+/// `.engram/at/<commit>` or `.engram/at/staged`, laid out like the
+/// repository; otherwise `cwd`.
+fn checkout_target_at(
+    cwd: &Path,
+    paths: &RepoPaths,
+    commit: &str,
+    target: &str,
+) -> Result<PathBuf, CliError> {
+    let file = target_file(target);
+    let (label, dir) = match commit {
+        "" => ("the git index", "staged"),
+        commit => (commit, commit),
+    };
+    let output = ProcessCommand::new("git")
+        .arg("show")
+        .arg(format!("{commit}:./{file}"))
+        .current_dir(cwd)
+        .output()
+        .map_err(|err| CliError::new("git_error", err.to_string()))?;
+    if !output.status.success() {
+        if cwd.join(file).exists() {
+            return Err(CliError::new(
+                "target_not_at_commit",
+                format!("`{file}` does not exist in {label}"),
+            ));
+        }
+        // Not a file then or now: a literal target.
+        return Ok(cwd.to_path_buf());
+    }
+    let prefix = git_output(cwd, &["rev-parse", "--show-prefix"]).unwrap_or_default();
+    let root = paths.root.join("at").join(dir).join(prefix);
+    let path = root.join(file);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| CliError::io("mkdir_error", err))?;
+    }
+    fs::write(&path, output.stdout).map_err(|err| CliError::io("write_error", err))?;
+    Ok(root)
+}
+
+/// A file's content-defined chunks, each with the anchors of its text.
+fn file_chunk_anchors(
+    cwd: &Path,
+    context: &RuntimeContext,
+    file: &str,
+) -> Result<Vec<(LineChunk, Vec<String>)>, CliError> {
+    let path = cwd.join(file);
+    let normalizer = span_normalizer(context, &path);
+    let key = format!(
+        "chunks:v{}{}",
+        anchor_version(context.normalize_line_endings),
+        normalizer_key(normalizer)
+    );
+    let chunks: Vec<(u32, u32, Vec<String>)> = cached_fingerprints(
+        open_fingerprint_cache(context).as_ref(),
+        &path,
+        &key,
+        || {
+            let text =
+                fs::read_to_string(&path).map_err(|err| CliError::io("read_span_error", err))?;
+            let text = evidence_view(context, &path, text);
+            let lines = text.lines().collect::<Vec<_>>();
+            Ok(content_defined_chunks(&text)
+                .into_iter()
+                .map(|chunk| {
+                    let chunk_text = lines[chunk.start as usize - 1..chunk.end as usize].join("\n");
+                    (
+                        chunk.start,
+                        chunk.end,
+                        derive_anchor_candidates(&[chunk_text], normalizer),
+                    )
+                })
+                .collect())
         },
-        "session": {
-            "session_id": session_id,
-            "timestamp": timestamp,
-            "window_start": window_start,
-            "window_end": window_end,
-            "total_lines": total_lines,
-            "content": content,
+    )?;
+    Ok(chunks
+        .into_iter()
+        .map(|(start, end, anchors)| (LineChunk { start, end }, anchors))
+        .collect())
+}
+
+/// Whole-file explain's per-region summary: runs of adjacent chunks with
+/// the same history, each with the sessions (of those in `shown`) whose
+/// evidence matches it. Regions no session touched are left out.
+fn file_regions_json(
+    indexes: &[SqliteIndex],
+    chunks: &[(LineChunk, Vec<String>)],
+    shown: &HashSet<&str>,
+) -> Result<Value, CliError> {
+    let mut regions: Vec<(LineChunk, BTreeMap<String, f32>)> = Vec::new();
+    for (chunk, anchors) in chunks {
+        let scores = collect_anchor_scores(indexes, anchors)?
+            .into_iter()
+            .filter(|(session_id, _)| shown.contains(session_id.as_str()))
+            .collect::<BTreeMap<_, _>>();
+        match regions.last_mut() {
+            Some((region, region_scores)) if region_scores.keys().eq(scores.keys()) => {
+                region.end = chunk.end;
+                for (session_id, score) in scores {
+                    let best = region_scores.entry(session_id).or_default();
+                    *best = best.max(score);
+                }
+            }
+            _ => regions.push((*chunk, scores)),
+        }
+    }
+    Ok(Value::Array(
+        regions
+            .into_iter()
+            .filter(|(_, scores)| !scores.is_empty())
+            .map(|(region, scores)| {
+                let mut sessions = scores.into_iter().collect::<Vec<_>>();
+                sessions.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                json!({
+                    "start": region.start,
+                    "end": region.end,
+                    "sessions": sessions
+                        .into_iter()
+                        .map(|(session_id, confidence)| {
+                            json!({"session_id": session_id, "confidence": confidence})
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect(),
+    ))
+}
+
+/// Resolve a `<file>:<line>` or `<file>:<start>-<end>` target at a
+/// `--granularity`: the lines themselves, the content-defined chunks
+/// covering them, their enclosing definition (the chunks when there is
+/// none), or the whole file.
+fn granular_target(
+    cwd: &Path,
+    context: &RuntimeContext,
+    target: &str,
+    granularity: ExplainGranularity,
+) -> Result<(ExplainTarget, Value), CliError> {
+    let usage = || {
+        CliError::new(
+            "invalid_explain_target",
+            format!(
+                "--granularity {} expects <file>:<line> or <file>:<start>-<end>",
+                granularity.as_str()
+            ),
+        )
+    };
+    let (file, start, end) = if has_span_shape(target) {
+        parse_file_range_target(target)?
+    } else {
+        let (file, line) = target.rsplit_once(':').ok_or_else(usage)?;
+        let line = line.parse::<u32>().map_err(|_| usage())?;
+        if line == 0 {
+            return Err(usage());
         }
+        (file, line, line)
+    };
+    let text =
+        fs::read_to_string(cwd.join(file)).map_err(|err| CliError::io("read_span_error", err))?;
+    let text = evidence_view(context, Path::new(file), text);
+    let line_count = text.lines().count() as u32;
+    if start > line_count {
+        return Err(CliError::new(
+            "invalid_span",
+            format!("line {start} is past the end of {file} ({line_count} lines)"),
+        ));
+    }
+    let requested = json!({"start": start, "end": end});
+    if granularity == ExplainGranularity::File {
+        return Ok((
+            ExplainTarget::FileWhole {
+                file: file.to_string(),
+            },
+            json!({"file": file, "requested": requested, "start": 1, "end": line_count}),
+        ));
+    }
+    let definition = (granularity == ExplainGranularity::Function)
+        .then(|| enclosing_definition(&text, start, end))
+        .flatten();
+    let span = match (granularity, definition) {
+        (_, Some(span)) => span,
+        (ExplainGranularity::Line, None) => LineChunk { start, end },
+        _ => chunk_span(&content_defined_chunks(&text), start, end).ok_or_else(usage)?,
+    };
+    Ok((
+        ExplainTarget::FileRange {
+            file: file.to_string(),
+            start: span.start,
+            end: span.end,
+        },
+        json!({
+            "file": file,
+            "requested": requested,
+            "start": span.start,
+            "end": span.end,
+            "definition": definition.is_some(),
         }),
-    )
+    ))
 }
 
-#[derive(Debug, Clone)]
-enum ExplainTarget {
-    FileRange { file: String, start: u32, end: u32 },
-    FileWhole { file: String },
-    Literal(String),
+/// Anchors a target stands for: the fingerprint of a span or file, the
+/// fingerprint of literal text, or the literal itself in anchor mode.
+/// Under `anchors.normalize_line_endings`, text goes through
+/// [`normalize_line_endings`] first, as tape evidence does, and file text
+/// through the `anchors.normalizers` entry for its language. File anchors
+/// come from the fingerprint cache when the file is unchanged.
+fn explain_target_anchors(
+    cwd: &Path,
+    target: &ExplainTarget,
+    anchor_mode: bool,
+    context: &RuntimeContext,
+) -> Result<Vec<String>, CliError> {
+    let normalize = context.normalize_line_endings;
+    let version = anchor_version(normalize);
+    let cache = open_fingerprint_cache(context);
+    Ok(match target {
+        ExplainTarget::FileRange { file, start, end } => {
+            let path = cwd.join(file);
+            let normalizer = span_normalizer(context, &path);
+            cached_fingerprints(
+                cache.as_ref(),
+                &path,
+                &format!(
+                    "span:{start}-{end}:v{version}{}",
+                    normalizer_key(normalizer)
+                ),
+                || {
+                    let span_texts = read_file_span_variants(&path, *start, *end, normalize)?;
+                    Ok(derive_anchor_candidates(&span_texts, normalizer))
+                },
+            )?
+        }
+        ExplainTarget::FileWhole { file } => {
+            let path = cwd.join(file);
+            let normalizer = span_normalizer(context, &path);
+            let key = format!("file:v{version}{}", normalizer_key(normalizer));
+            cached_fingerprints(cache.as_ref(), &path, &key, || {
+                let full_text = fs::read_to_string(&path)
+                    .map_err(|err| CliError::io("read_span_error", err))?;
+                Ok(derive_anchor_candidates(
+                    &[evidence_view(context, &path, full_text)],
+                    normalizer,
+                ))
+            })?
+        }
+        ExplainTarget::Literal(text) if anchor_mode => vec![text.clone()],
+        ExplainTarget::Literal(text) if normalize => {
+            derive_anchor_candidates(&[normalize_line_endings(text).into_owned()], None)
+        }
+        ExplainTarget::Literal(text) => derive_anchor_candidates(std::slice::from_ref(text), None),
+    })
 }
 
-fn open_query_indexes(context: &RuntimeContext) -> Result<Vec<SqliteIndex>, CliError> {
-    let mut indexes = Vec::new();
-    indexes.push(SqliteIndex::open(&path_string(&context.db_path))?);
-    for store in &context.additional_stores {
-        if store.exists() {
-            indexes.push(SqliteIndex::open(&path_string(store))?);
-        }
+/// The working-tree fingerprint cache, or `None` when it cannot be opened
+/// (commands then fingerprint from scratch).
+fn open_fingerprint_cache(context: &RuntimeContext) -> Option<FingerprintCache> {
+    if let Some(parent) = context.fingerprint_cache.parent() {
+        fs::create_dir_all(parent).ok()?;
     }
-    Ok(indexes)
+    FingerprintCache::open(&context.fingerprint_cache).ok()
 }
 
-fn classify_explain_target(
-    cwd: &Path,
-    _context: &RuntimeContext,
-    _indexes: &[SqliteIndex],
-    target: &str,
-    anchor_mode: bool,
-) -> Result<ExplainTarget, CliError> {
-    if anchor_mode {
-        return Ok(ExplainTarget::Literal(target.to_string()));
+fn cached_fingerprints<T, F>(
+    cache: Option<&FingerprintCache>,
+    file: &Path,
+    key: &str,
+    compute: F,
+) -> Result<T, CliError>
+where
+    T: Serialize + serde::de::DeserializeOwned,
+    F: FnOnce() -> Result<T, CliError>,
+{
+    match cache {
+        Some(cache) => cache.get_or_compute(file, key, compute),
+        None => compute(),
     }
+}
 
-    if has_span_shape(target) {
-        let (file, start, end) = parse_file_range_target(target)?;
-        if cwd.join(file).exists() {
-            return Ok(ExplainTarget::FileRange {
-                file: file.to_string(),
-                start,
-                end,
-            });
+/// Annotations on any of `anchors` across every queried store, deduplicated.
+/// Registry previews of `anchors` (see [`engram::anchor::anchor_preview`]),
+/// for those any index has one. A window fingerprint takes the preview of
+/// its first token that has one.
+fn collect_anchor_previews(
+    indexes: &[SqliteIndex],
+    anchors: &[String],
+) -> Result<BTreeMap<String, String>, CliError> {
+    let mut out = BTreeMap::new();
+    let mut looked_up = HashSet::new();
+    for anchor in anchors {
+        if !looked_up.insert(anchor) {
+            continue;
+        }
+        let mut candidates = vec![anchor.clone()];
+        let tokens = expand_winnow_anchor(anchor);
+        if tokens.len() > 1 {
+            candidates.extend(tokens);
+        }
+        'indexes: for index in indexes {
+            for info in index.anchor_info(&candidates)? {
+                if let Some(preview) = info.preview {
+                    out.insert(anchor.clone(), preview);
+                    break 'indexes;
+                }
+            }
         }
     }
+    Ok(out)
+}
 
-    if cwd.join(target).is_file() {
-        return Ok(ExplainTarget::FileWhole {
-            file: target.to_string(),
-        });
+fn collect_annotations(
+    indexes: &[SqliteIndex],
+    anchors: &[String],
+) -> Result<Vec<Value>, CliError> {
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for index in indexes {
+        for annotation in index.annotations_for_anchors(anchors)? {
+            if !seen.insert((annotation.note.clone(), annotation.created_at.clone())) {
+                continue;
+            }
+            out.push(json!({
+                "id": annotation.id,
+                "target": annotation.target,
+                "note": annotation.note,
+                "created_at": annotation.created_at,
+                "matched_anchors": annotation.matched_anchors.len(),
+            }));
+        }
     }
-
-    Ok(ExplainTarget::Literal(target.to_string()))
+    Ok(out)
 }
 
 fn has_span_shape(target: &str) -> bool {
@@ -2606,20 +9594,16 @@ fn format_sessions_for_agent(
             .get("touches")
             .and_then(Value::as_array)
             .map(|touches| {
-                touches
-                    .iter()
-                    .filter_map(|touch| touch.get("file_path").and_then(Value::as_str))
-                    .map(ToOwned::to_owned)
-                    .collect::<HashSet<_>>()
+                dedup_file_paths(
+                    touches
+                        .iter()
+                        .filter_map(|touch| touch.get("file_path").and_then(Value::as_str)),
+                )
             })
             .unwrap_or_default();
         if files_touched.is_empty() {
-            for file in collect_files_touched_from_rows(&rows) {
-                files_touched.insert(file);
-            }
+            files_touched = collect_files_touched_from_rows(&rows);
         }
-        let mut files_touched = files_touched.into_iter().collect::<Vec<_>>();
-        files_touched.sort();
 
         let (refs_up, refs_down) = dispatch_ref_counts(primary_index, session_id)?;
         let timestamp = raw
@@ -2629,7 +9613,7 @@ fn format_sessions_for_agent(
             .map(ToOwned::to_owned)
             .unwrap_or_else(|| extract_latest_timestamp_from_rows(&rows));
 
-        out.push(json!({
+        let mut session = json!({
             "session_id": session_id,
             "timestamp": timestamp,
             "window_start": window_start,
@@ -2639,12 +9623,231 @@ fn format_sessions_for_agent(
             "refs_up": refs_up,
             "refs_down": refs_down,
             "files_touched": files_touched,
-        }));
+        });
+        if let Some(state) = workspace_state_json(&rows) {
+            session["workspace_state"] = state;
+        }
+        if let Some(coverage) = coverage_json(&rows) {
+            let low_trust = raw
+                .get("touches")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|touch| touch.get("kind").and_then(Value::as_str))
+                .any(|kind| is_low_trust_grade(coverage.get(kind).and_then(Value::as_str)));
+            session["coverage"] = coverage;
+            session["low_trust"] = json!(low_trust);
+        }
+        out.push(session);
     }
 
     Ok(out)
 }
 
+/// The coverage grades the tape's adapter declared in its `meta`, as
+/// `{read, edit, tool}`; `None` for tapes that declare none.
+fn coverage_json(rows: &[TapeRow]) -> Option<Value> {
+    let meta = rows.iter().find(|row| {
+        row.value.get("k").and_then(Value::as_str) == Some("meta")
+            && ["coverage.read", "coverage.edit", "coverage.tool"]
+                .iter()
+                .any(|field| row.value.get(field).is_some())
+    })?;
+    Some(json!({
+        "read": meta.value.get("coverage.read"),
+        "edit": meta.value.get("coverage.edit"),
+        "tool": meta.value.get("coverage.tool"),
+    }))
+}
+
+/// Evidence from an adapter that only partly captures (or does not
+/// capture) a kind of event can miss or misplace it.
+fn is_low_trust_grade(grade: Option<&str>) -> bool {
+    matches!(grade, Some("partial" | "none"))
+}
+
+/// Lineage edges as JSON, each flagged `low_trust` when every edit behind
+/// its newer anchor comes from tapes whose adapter grades edit coverage
+/// `partial` or `none`.
+fn lineage_with_trust_json(
+    indexes: &[SqliteIndex],
+    lineage: &[EdgeRow],
+    previews: &BTreeMap<String, String>,
+) -> Result<Vec<Value>, CliError> {
+    let mut edit_grades = HashMap::<String, Option<String>>::new();
+    let mut out = Vec::with_capacity(lineage.len());
+    for edge in lineage {
+        let (mut edits, mut weak) = (0usize, 0usize);
+        for index in indexes {
+            for fragment in index.evidence_for_anchor(&edge.to_anchor)? {
+                if fragment.kind != EvidenceKind::Edit {
+                    continue;
+                }
+                if !edit_grades.contains_key(&fragment.tape_id) {
+                    let mut grade = None;
+                    for index in indexes {
+                        if let Some(metrics) = index.tape_metrics(&fragment.tape_id)? {
+                            grade = metrics.coverage_edit;
+                            break;
+                        }
+                    }
+                    edit_grades.insert(fragment.tape_id.clone(), grade);
+                }
+                edits += 1;
+                if is_low_trust_grade(edit_grades[&fragment.tape_id].as_deref()) {
+                    weak += 1;
+                }
+            }
+        }
+        let mut value = edge_to_json(edge);
+        value["low_trust"] = json!(edits > 0 && weak == edits);
+        for (key, anchor) in [
+            ("from_preview", &edge.from_anchor),
+            ("to_preview", &edge.to_anchor),
+        ] {
+            if let Some(preview) = previews.get(anchor) {
+                value[key] = json!(preview);
+            }
+        }
+        out.push(value);
+    }
+    Ok(out)
+}
+
+/// Send the returned sessions' windows to the configured `summarize:`
+/// endpoint and return the narrative with citations back to tape offsets.
+fn summarize_sessions(
+    context: &RuntimeContext,
+    target: &str,
+    sessions: &[Value],
+) -> Result<Value, CliError> {
+    let config = context.summarize.as_ref().ok_or_else(|| {
+        CliError::new(
+            "summarize_not_configured",
+            "add a summarize: section with base_url and model to config.yml",
+        )
+    })?;
+    let mut cache = HashMap::new();
+    let mut excerpts = Vec::new();
+    for session in sessions {
+        let session_id = session
+            .get("session_id")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let window_start = session
+            .get("window_start")
+            .and_then(Value::as_u64)
+            .unwrap_or(1);
+        let window_end = session
+            .get("window_end")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        let rows = load_tape_rows_cached(context, &mut cache, session_id)?;
+        excerpts.extend(
+            rows.iter()
+                .filter(|row| (window_start..=window_end).contains(&(row.offset + 1)))
+                .filter_map(|row| {
+                    excerpt_text(&row.value).map(|text| NarrativeExcerpt {
+                        tape_id: session_id.to_string(),
+                        offset: row.offset,
+                        text,
+                    })
+                }),
+        );
+    }
+
+    let narrative = request_narrative(config, target, &excerpts)
+        .map_err(|err| CliError::new("summarize_failed", err.to_string()))?;
+    Ok(json!({
+        "model": narrative.model,
+        "text": narrative.text,
+        "citations": narrative
+            .citations
+            .iter()
+            .map(|citation| json!({"tape_id": citation.tape_id, "offset": citation.offset}))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// Group returned sessions into episodes (see `cluster_episodes`) using each
+/// tape's `repo_head` and user prompts, and tag every session with the index
+/// of its episode.
+fn build_episodes(
+    context: &RuntimeContext,
+    sessions: &mut [Value],
+) -> Result<Vec<Value>, CliError> {
+    let mut cache = HashMap::new();
+    let mut inputs = Vec::with_capacity(sessions.len());
+    for session in sessions.iter() {
+        let session_id = session
+            .get("session_id")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let rows = load_tape_rows_cached(context, &mut cache, session_id)?;
+        let repo_head = rows
+            .iter()
+            .filter(|row| row.value.get("k").and_then(Value::as_str) == Some("meta"))
+            .find_map(|row| row.value.get("repo_head").and_then(Value::as_str))
+            .filter(|head| !head.is_empty())
+            .map(ToOwned::to_owned);
+        let keywords = rows
+            .iter()
+            .filter(|row| row.value.get("k").and_then(Value::as_str) == Some("msg.in"))
+            .filter_map(|row| row.value.get("text").and_then(Value::as_str))
+            .flat_map(prompt_keywords)
+            .collect();
+        let timestamp = session
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .and_then(|raw| chrono::DateTime::parse_from_rfc3339(raw).ok())
+            .map(|value| value.with_timezone(&chrono::Utc));
+        inputs.push(EpisodeSession {
+            session_id: session_id.to_string(),
+            timestamp,
+            repo_head,
+            keywords,
+        });
+    }
+
+    let episodes = cluster_episodes(
+        &inputs,
+        chrono::Duration::hours(DEFAULT_EPISODE_MAX_GAP_HOURS),
+        DEFAULT_EPISODE_MIN_KEYWORD_OVERLAP,
+    );
+    let mut episode_by_session = HashMap::new();
+    for (idx, episode) in episodes.iter().enumerate() {
+        for session_id in &episode.session_ids {
+            episode_by_session.insert(session_id.as_str(), idx);
+        }
+    }
+    for session in sessions.iter_mut() {
+        let episode = session
+            .get("session_id")
+            .and_then(Value::as_str)
+            .and_then(|id| episode_by_session.get(id).copied());
+        session["episode"] = json!(episode);
+    }
+
+    let format_ts = |ts: Option<chrono::DateTime<chrono::Utc>>| {
+        ts.map(|ts| ts.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+    };
+    Ok(episodes
+        .iter()
+        .enumerate()
+        .map(|(idx, episode)| {
+            json!({
+                "episode": idx,
+                "summary": episode.summary(),
+                "session_ids": episode.session_ids,
+                "started_at": format_ts(episode.started_at),
+                "ended_at": format_ts(episode.ended_at),
+                "repo_heads": episode.repo_heads,
+                "keywords": episode.keywords,
+            })
+        })
+        .collect())
+}
+
 fn dispatch_ref_counts(index: &SqliteIndex, tape_id: &str) -> Result<(usize, usize), CliError> {
     let mut up = 0usize;
     let mut down = 0usize;
@@ -2666,19 +9869,25 @@ fn extract_latest_timestamp_from_rows(rows: &[TapeRow]) -> String {
 }
 
 fn collect_files_touched_from_rows(rows: &[TapeRow]) -> Vec<String> {
-    let mut files = HashSet::new();
-    for row in rows {
-        if let Some(file) = row.value.get("file").and_then(Value::as_str) {
-            files.insert(file.to_string());
-        }
-        if let Some(file) = row.value.get("from_file").and_then(Value::as_str) {
-            files.insert(file.to_string());
-        }
-        if let Some(file) = row.value.get("to_file").and_then(Value::as_str) {
-            files.insert(file.to_string());
+    dedup_file_paths(rows.iter().flat_map(|row| {
+        ["file", "from_file", "to_file"]
+            .into_iter()
+            .filter_map(|field| row.value.get(field).and_then(Value::as_str))
+    }))
+}
+
+/// Sorted, de-duplicated file list where spellings of the same file (slash
+/// direction, drive letter or case on case-insensitive filesystems) collapse
+/// to the first one seen.
+fn dedup_file_paths<'a>(files: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let policy = PathPolicy::host();
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for file in files {
+        if seen.insert(policy.key(file)) {
+            out.push(slash_path(file));
         }
     }
-    let mut out = files.into_iter().collect::<Vec<_>>();
     out.sort();
     out
 }
@@ -2725,6 +9934,170 @@ fn apply_session_truncation(
     (sessions, returned_count, total, time_range, truncated)
 }
 
+/// `explain --exclude-*`, `--issue` and `--project` filters: evidence from
+/// these harnesses, tapes or paths, from tapes mentioning none of the issue
+/// keys, or outside the projects, is dropped before sessions are built,
+/// without touching the store.
+#[derive(Debug, Clone, Default)]
+struct EvidenceExclusions {
+    harnesses: Vec<String>,
+    tapes: Vec<String>,
+    paths: Vec<(String, glob::Pattern)>,
+    issues: Vec<String>,
+    projects: Vec<String>,
+}
+
+impl EvidenceExclusions {
+    fn parse(
+        harnesses: &[String],
+        tapes: &[String],
+        paths: &[String],
+        issues: &[String],
+        projects: &[String],
+    ) -> Result<Self, CliError> {
+        let paths = paths
+            .iter()
+            .map(|raw| {
+                let raw = raw.trim_end_matches('/').to_string();
+                glob::Pattern::new(&raw)
+                    .map(|pattern| (raw.clone(), pattern))
+                    .map_err(|err| CliError::new("invalid_exclude_path", format!("`{raw}`: {err}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            harnesses: harnesses.to_vec(),
+            tapes: tapes.to_vec(),
+            paths,
+            issues: issues.to_vec(),
+            projects: projects.to_vec(),
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.harnesses.is_empty()
+            && self.tapes.is_empty()
+            && self.paths.is_empty()
+            && self.issues.is_empty()
+            && self.projects.is_empty()
+    }
+
+    /// Whether a touch's event was tagged with none of the `--project`
+    /// names.
+    fn excludes_touch_project(
+        &self,
+        indexes: &[SqliteIndex],
+        touch: &EvidenceFragmentRef,
+    ) -> Result<bool, CliError> {
+        if self.projects.is_empty() {
+            return Ok(false);
+        }
+        for index in indexes {
+            if let Some(project) = index.evidence_project(&touch.tape_id, touch.event_offset)? {
+                return Ok(!self.projects.contains(&project));
+            }
+        }
+        Ok(true)
+    }
+
+    fn excludes_path(&self, file_path: &str) -> bool {
+        self.paths.iter().any(|(raw, pattern)| {
+            pattern.matches_with(file_path, watch_glob_match_options())
+                || file_path
+                    .strip_prefix(raw.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// Whether the tape is excluded by id, by the harness that recorded it,
+    /// for mentioning none of the `--issue` keys or for having no evidence in
+    /// the `--project` projects.
+    fn excludes_tape(
+        &self,
+        indexes: &[SqliteIndex],
+        cache: &mut HashMap<String, bool>,
+        tape_id: &str,
+    ) -> Result<bool, CliError> {
+        if let Some(excluded) = cache.get(tape_id) {
+            return Ok(*excluded);
+        }
+        let mut excluded = self.tapes.iter().any(|prefix| tape_id.starts_with(prefix));
+        if !excluded && !self.harnesses.is_empty() {
+            for index in indexes {
+                if let Some(harness) = index.tape_metrics(tape_id)?.and_then(|m| m.harness) {
+                    excluded = self.harnesses.contains(&harness);
+                    break;
+                }
+            }
+        }
+        if !excluded && !self.issues.is_empty() {
+            let mut mentioned = false;
+            for index in indexes {
+                mentioned |= index.issue_refs(tape_id)?.iter().any(|issue| {
+                    self.issues
+                        .iter()
+                        .any(|key| key.eq_ignore_ascii_case(&issue.key))
+                });
+            }
+            excluded = !mentioned;
+        }
+        if !excluded && !self.projects.is_empty() {
+            let mut in_project = false;
+            for index in indexes {
+                in_project |= index
+                    .tape_projects(tape_id)?
+                    .iter()
+                    .any(|project| self.projects.contains(project));
+            }
+            excluded = !in_project;
+        }
+        cache.insert(tape_id.to_string(), excluded);
+        Ok(excluded)
+    }
+
+    fn filter_touches(
+        &self,
+        indexes: &[SqliteIndex],
+        touches: Vec<EvidenceFragmentRef>,
+    ) -> Result<Vec<EvidenceFragmentRef>, CliError> {
+        if self.is_empty() {
+            return Ok(touches);
+        }
+        let mut cache = HashMap::new();
+        let mut kept = Vec::with_capacity(touches.len());
+        for touch in touches {
+            if !self.excludes_path(&touch.file_path)
+                && !self.excludes_tape(indexes, &mut cache, &touch.tape_id)?
+                && !self.excludes_touch_project(indexes, &touch)?
+            {
+                kept.push(touch);
+            }
+        }
+        Ok(kept)
+    }
+
+    fn filter_sessions(
+        &self,
+        indexes: &[SqliteIndex],
+        sessions: Vec<Value>,
+    ) -> Result<Vec<Value>, CliError> {
+        if self.is_empty() {
+            return Ok(sessions);
+        }
+        let mut cache = HashMap::new();
+        let mut kept = Vec::with_capacity(sessions.len());
+        for session in sessions {
+            let tape_id = session
+                .get("tape_id")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if !self.excludes_tape(indexes, &mut cache, tape_id)? {
+                kept.push(session);
+            }
+        }
+        Ok(kept)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct DateFilter {
     since: Option<chrono::DateTime<Utc>>,
@@ -2922,14 +10295,12 @@ fn default_peek_anchor_line(index: &SqliteIndex, session_id: &str, rows: &[TapeR
             .into_iter()
             .filter(|link| matches!(link.direction, DispatchDirection::Received))
             .min_by_key(|link| link.first_turn_index)
+        && let Some(offset) = message_turn_to_event_offset(rows, received.first_turn_index)
+        && let Some(pos) = rows.iter().position(|row| row.offset == offset)
     {
-        if let Some(offset) = message_turn_to_event_offset(rows, received.first_turn_index)
-            && let Some(pos) = rows.iter().position(|row| row.offset == offset)
-        {
-            return pos + 1;
-        }
+        return pos + 1;
     }
-    if rows.is_empty() { 1 } else { 1 }
+    1
 }
 
 fn collect_dispatch_upstream_sessions(
@@ -2982,29 +10353,107 @@ fn collect_dispatch_upstream_sessions(
                 if !visited.insert(hop_key) {
                     break;
                 }
-
-                chain.push(json!({
-                    "session": current_tape,
-                    "edit_turn_index": current_turn,
-                    "received_uuid": received.uuid,
-                    "received_turn_index": received.first_turn_index,
-                    "parent_session": parent.tape_id,
-                    "parent_sent_turn_index": parent.first_turn_index,
+
+                chain.push(json!({
+                    "session": current_tape,
+                    "edit_turn_index": current_turn,
+                    "received_uuid": received.uuid,
+                    "received_turn_index": received.first_turn_index,
+                    "parent_session": parent.tape_id,
+                    "parent_sent_turn_index": parent.first_turn_index,
+                }));
+
+                if seen_tapes.insert(parent.tape_id.clone())
+                    && let Some(extra) = build_dispatch_session(context, &mut rows_cache, &parent)?
+                {
+                    extras.push(extra);
+                }
+
+                current_tape = parent.tape_id;
+                current_turn = parent.first_turn_index;
+            }
+        }
+    }
+
+    Ok((chain, extras))
+}
+
+/// Follow `spawned` links up from the returned sessions to the sessions
+/// whose commands started them. Returns the links, as chain entries like
+/// dispatch lineage, and a session for each spawning tape not already
+/// returned, windowed around the spawning call.
+fn collect_spawn_upstream_sessions(
+    context: &RuntimeContext,
+    indexes: &[SqliteIndex],
+    sessions: &[Value],
+) -> Result<(Vec<Value>, Vec<Value>), CliError> {
+    let mut links = Vec::new();
+    let mut extras = Vec::new();
+    let mut pending = sessions
+        .iter()
+        .filter_map(|session| session.get("tape_id").and_then(Value::as_str))
+        .map(ToOwned::to_owned)
+        .collect::<Vec<_>>();
+    let mut seen_tapes = pending.iter().cloned().collect::<HashSet<_>>();
+    let mut seen_links = HashSet::new();
+    let mut rows_cache = HashMap::<String, Vec<TapeRow>>::new();
+
+    while let Some(tape_id) = pending.pop() {
+        for index in indexes {
+            for link in index.spawn_parents(&tape_id)? {
+                if !seen_links.insert((
+                    link.parent_tape_id.clone(),
+                    link.parent_offset,
+                    link.child_tape_id.clone(),
+                )) {
+                    continue;
+                }
+                links.push(json!({
+                    "session": link.child_tape_id,
+                    "parent_session": link.parent_tape_id,
+                    "parent_event_offset": link.parent_offset,
+                    "command": link.command,
                 }));
-
-                if seen_tapes.insert(parent.tape_id.clone())
-                    && let Some(extra) = build_dispatch_session(context, &mut rows_cache, &parent)?
-                {
-                    extras.push(extra);
+                if seen_tapes.insert(link.parent_tape_id.clone()) {
+                    let rows =
+                        load_tape_rows_cached(context, &mut rows_cache, &link.parent_tape_id)?;
+                    let windows = event_window(rows, link.parent_offset, TRANSCRIPT_WINDOW_RADIUS)
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    extras.push(json!({
+                        "tape_id": link.parent_tape_id,
+                        "touch_count": 0,
+                        "latest_touch_timestamp": "",
+                        "touches": [],
+                        "windows": windows,
+                    }));
+                    pending.push(link.parent_tape_id);
                 }
-
-                current_tape = parent.tape_id;
-                current_turn = parent.first_turn_index;
             }
         }
     }
 
-    Ok((chain, extras))
+    Ok((links, extras))
+}
+
+/// Mark each returned session that another session's command started with
+/// that session, the call's offset and the command.
+fn annotate_spawned_by(sessions: &mut [Value], spawned: &[Value]) {
+    for session in sessions {
+        let Some(id) = session.get("session_id").and_then(Value::as_str) else {
+            continue;
+        };
+        if let Some(link) = spawned
+            .iter()
+            .find(|link| link.get("session").and_then(Value::as_str) == Some(id))
+        {
+            session["spawned_by"] = json!({
+                "session": link["parent_session"],
+                "event_offset": link["parent_event_offset"],
+                "command": link["command"],
+            });
+        }
+    }
 }
 
 fn build_dispatch_session(
@@ -3075,6 +10524,7 @@ fn explain_across_indexes(
     let mut seen_direct = HashSet::new();
     let mut seen_lineage = HashSet::new();
     let mut seen_anchors = HashSet::new();
+    let mut truncated = None;
 
     for anchor in anchors {
         if seen_anchors.insert(anchor.clone()) {
@@ -3091,17 +10541,7 @@ fn explain_across_indexes(
             }
         }
         for edge in result.lineage {
-            let key = format!(
-                "{}:{}:{:.6}:{}:{}:{}:{}",
-                edge.from_anchor,
-                edge.to_anchor,
-                edge.confidence,
-                location_delta_name(edge.location_delta),
-                cardinality_name(edge.cardinality),
-                edge.agent_link,
-                edge.note.clone().unwrap_or_default()
-            );
-            if seen_lineage.insert(key) {
+            if seen_lineage.insert(edge_dedup_key(&edge)) {
                 lineage.push(edge);
             }
         }
@@ -3110,22 +10550,63 @@ fn explain_across_indexes(
                 touched_anchors.push(anchor);
             }
         }
+        truncated = TraversalTruncation::merge(truncated, result.truncated);
     }
 
     Ok(engram::query::explain::ExplainResult {
         direct,
         lineage,
         touched_anchors,
+        truncated,
     })
 }
 
+/// `explain --include-deleted`: where each touched anchor's span was deleted.
+fn explain_tombstones(
+    indexes: &[SqliteIndex],
+    touched_anchors: &[String],
+) -> Result<Vec<Value>, CliError> {
+    let mut tombstones = Vec::new();
+    for anchor in touched_anchors {
+        for index in indexes {
+            for tombstone in index.tombstones_for_anchor(anchor)? {
+                tombstones.push(json!({
+                    "anchor": anchor,
+                    "tape_id": tombstone.tape_id,
+                    "event_offset": tombstone.event_offset,
+                    "file_path": tombstone.file_path,
+                    "range": {
+                        "start": tombstone.range_at_deletion.start,
+                        "end": tombstone.range_at_deletion.end
+                    },
+                    "timestamp": tombstone.timestamp,
+                }));
+            }
+        }
+    }
+    Ok(tombstones)
+}
+
+fn edge_dedup_key(edge: &EdgeRow) -> String {
+    format!(
+        "{}:{}:{:.6}:{}:{}:{}:{}",
+        edge.from_anchor,
+        edge.to_anchor,
+        edge.confidence,
+        location_delta_name(edge.location_delta),
+        cardinality_name(edge.cardinality),
+        edge.agent_link,
+        edge.note.clone().unwrap_or_default()
+    )
+}
+
 fn touch_key(fragment: &EvidenceFragmentRef) -> String {
     format!(
         "{}:{}:{}:{}:{}",
         fragment.tape_id,
         fragment.event_offset,
         evidence_kind_name(fragment.kind),
-        fragment.file_path,
+        PathPolicy::host().key(&fragment.file_path),
         fragment.timestamp
     )
 }
@@ -3134,6 +10615,16 @@ fn build_session_windows(
     context: &RuntimeContext,
     touches: Vec<EvidenceFragmentRef>,
 ) -> Result<Vec<Value>, CliError> {
+    let mut sessions = session_touch_groups(touches);
+    for session in &mut sessions {
+        add_session_windows(context, session)?;
+    }
+    Ok(sessions)
+}
+
+/// One raw session per tape in `touches`, most touched first, without the
+/// transcript windows [`add_session_windows`] reads from the tape.
+fn session_touch_groups(touches: Vec<EvidenceFragmentRef>) -> Vec<Value> {
     let mut by_tape: HashMap<String, Vec<EvidenceFragmentRef>> = HashMap::new();
     for touch in touches {
         by_tape
@@ -3145,19 +10636,6 @@ fn build_session_windows(
     let mut sessions = Vec::new();
     for (tape_id, mut tape_touches) in by_tape {
         tape_touches.sort_by_key(|t| t.event_offset);
-        let tape_path = resolve_tape_path(context, &tape_id);
-        let windows = if let Some(tape_path) = tape_path.as_ref() {
-            let content = read_tape_content(&tape_path)?;
-            let rows = parse_jsonl_rows(&content)?;
-            tape_touches
-                .iter()
-                .filter_map(|touch| {
-                    event_window(&rows, touch.event_offset, TRANSCRIPT_WINDOW_RADIUS)
-                })
-                .collect::<Vec<_>>()
-        } else {
-            Vec::new()
-        };
 
         let latest_touch_timestamp = tape_touches
             .iter()
@@ -3180,11 +10658,9 @@ fn build_session_windows(
 
         sessions.push(json!({
             "tape_id": tape_id,
-            "tape_present_locally": tape_path.is_some(),
             "touch_count": tape_touches.len(),
             "latest_touch_timestamp": latest_touch_timestamp,
             "touches": touches_json,
-            "windows": windows,
         }));
     }
 
@@ -3204,7 +10680,33 @@ fn build_session_windows(
             .then_with(|| b_latest.cmp(a_latest))
     });
 
-    Ok(sessions)
+    sessions
+}
+
+/// Read the session's tape and add a transcript window around each touch.
+fn add_session_windows(context: &RuntimeContext, session: &mut Value) -> Result<(), CliError> {
+    let tape_id = session
+        .get("tape_id")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let tape_path = resolve_tape_path(context, tape_id);
+    let windows = if let Some(tape_path) = tape_path.as_ref() {
+        let content = read_tape_content(tape_path)?;
+        let rows = parse_jsonl_rows(&content)?;
+        session
+            .get("touches")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|touch| touch.get("event_offset").and_then(Value::as_u64))
+            .filter_map(|offset| event_window(&rows, offset, TRANSCRIPT_WINDOW_RADIUS))
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+    session["tape_present_locally"] = json!(tape_path.is_some());
+    session["windows"] = json!(windows);
+    Ok(())
 }
 
 fn event_window(rows: &[TapeRow], target_offset: u64, radius: usize) -> Option<Value> {
@@ -3227,13 +10729,71 @@ fn event_window(rows: &[TapeRow], target_offset: u64, radius: usize) -> Option<V
     }))
 }
 
+fn traversal_truncation_json(truncated: Option<TraversalTruncation>) -> Value {
+    match truncated {
+        Some(truncated) => json!({
+            "reason": truncated.reason.as_str(),
+            "at_depth": truncated.at_depth,
+            "dropped_frontier": truncated.dropped_frontier,
+        }),
+        None => Value::Null,
+    }
+}
+
+fn lineage_path_to_json(path: &LineagePath) -> Value {
+    json!({
+        "root": path.root(),
+        "target": path.target(),
+        "confidence": path.confidence,
+        "anchors": path.anchors,
+        "edges": path.edges.iter().map(edge_to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Distinct anchor previews listed under the pretty explain summary.
+const MAX_PRETTY_PREVIEWS: usize = 3;
+
+/// Human-readable explain output in tiers: a one-paragraph summary of the
+/// most recent session and its prompt, then sessions and annotations; `-v`
+/// adds lineage edges and paths, `-vv` per-touch detail, truncation and
+/// tombstones.
+#[allow(clippy::too_many_arguments)]
 fn print_pretty_explain(
+    context: &RuntimeContext,
     target: &str,
+    verbosity: u8,
+    zone: DisplayZone,
+    annotations: &[Value],
+    query_anchors: &[String],
+    previews: &BTreeMap<String, String>,
     lineage: &[EdgeRow],
+    paths: &[LineagePath],
     sessions: &[Value],
     tombstones: &[Value],
-) {
-    println!("target: {target}");
+    truncated: Option<TraversalTruncation>,
+) -> Result<(), CliError> {
+    let now = Utc::now();
+    println!(
+        "{}",
+        pretty_explain_summary(context, target, zone, now, lineage, sessions)?
+    );
+    // The recorded texts the target's anchors come from, most shared first.
+    let mut shown = Vec::<(&String, usize)>::new();
+    for preview in query_anchors
+        .iter()
+        .filter_map(|anchor| previews.get(anchor))
+    {
+        match shown.iter_mut().find(|(seen, _)| *seen == preview) {
+            Some((_, count)) => *count += 1,
+            None => shown.push((preview, 1)),
+        }
+    }
+    shown.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    for (preview, _) in shown.iter().take(MAX_PRETTY_PREVIEWS) {
+        println!("preview: {preview}");
+    }
+    println!();
+
     println!("sessions: {}", sessions.len());
     for session in sessions {
         let tape_id = session.get("tape_id").and_then(Value::as_str).unwrap_or("");
@@ -3241,7 +10801,40 @@ fn print_pretty_explain(
             .get("touch_count")
             .and_then(Value::as_u64)
             .unwrap_or(0);
-        println!("- tape={} touches={}", tape_id, touch_count);
+        let latest = session
+            .get("latest_touch_timestamp")
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        println!(
+            "- tape={tape_id} touches={touch_count} last={}",
+            display_timestamp(latest, zone, now)
+        );
+        if verbosity >= 2 {
+            for touch in session["touches"].as_array().into_iter().flatten() {
+                println!(
+                    "  - offset={} {} {} at {}",
+                    touch["event_offset"],
+                    touch["kind"].as_str().unwrap_or(""),
+                    touch["file_path"].as_str().unwrap_or(""),
+                    display_timestamp(touch["timestamp"].as_str().unwrap_or(""), zone, now)
+                );
+            }
+        }
+    }
+
+    if !annotations.is_empty() {
+        println!("annotations:");
+        for annotation in annotations {
+            println!(
+                "- {} ({}, {})",
+                annotation["note"].as_str().unwrap_or(""),
+                annotation["target"].as_str().unwrap_or(""),
+                display_timestamp(annotation["created_at"].as_str().unwrap_or(""), zone, now)
+            );
+        }
+    }
+    if verbosity == 0 {
+        return Ok(());
     }
 
     println!("lineage:");
@@ -3252,12 +10845,50 @@ fn print_pretty_explain(
             edge.stored_class == StoredEdgeClass::LocationOnly,
         );
         println!(
-            "- {} -> {} conf={:.2} tier={} agent_link={}",
+            "- {} -> {} conf={:.2} tier={} agent_link={}{}",
             edge.from_anchor,
             edge.to_anchor,
             edge.confidence,
             pretty_tier_name(tier),
-            edge.agent_link
+            edge.agent_link,
+            if edge.reverted { " reverted" } else { "" }
+        );
+        for (side, anchor) in [("from", &edge.from_anchor), ("to", &edge.to_anchor)] {
+            if let Some(preview) = previews.get(anchor) {
+                println!("  {side}: {preview}");
+            }
+        }
+        if verbosity >= 2
+            && let Some(note) = &edge.note
+        {
+            println!("  note: {note}");
+        }
+    }
+    if !paths.is_empty() {
+        println!("paths:");
+        for path in paths {
+            println!(
+                "- {} conf={:.2}",
+                path.anchors.join(" -> "),
+                path.confidence
+            );
+        }
+    }
+    if verbosity < 2 {
+        return Ok(());
+    }
+
+    if let Some(truncated) = truncated {
+        let flag = match truncated.reason {
+            TruncationReason::MaxEdges => "--max-edges",
+            TruncationReason::MaxFanout => "--max-fanout",
+            TruncationReason::MaxDepth => "--depth",
+        };
+        println!(
+            "lineage truncated: reason={} at_depth={} dropped_frontier={} (re-run with a larger {flag})",
+            truncated.reason.as_str(),
+            truncated.at_depth,
+            truncated.dropped_frontier
         );
     }
 
@@ -3267,14 +10898,104 @@ fn print_pretty_explain(
             println!("- {tombstone}");
         }
     }
+    Ok(())
+}
+
+/// The first tier of pretty explain: who last touched `target`, when, and
+/// the prompt that led to it.
+fn pretty_explain_summary(
+    context: &RuntimeContext,
+    target: &str,
+    zone: DisplayZone,
+    now: chrono::DateTime<Utc>,
+    lineage: &[EdgeRow],
+    sessions: &[Value],
+) -> Result<String, CliError> {
+    let latest = |session: &Value| {
+        session
+            .get("latest_touch_timestamp")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string()
+    };
+    let Some(session) = sessions.iter().max_by_key(|session| latest(session)) else {
+        return Ok(format!("No recorded session touched {target}."));
+    };
+    let tape_id = session.get("tape_id").and_then(Value::as_str).unwrap_or("");
+    let touch = session["touches"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .max_by_key(|touch| touch["timestamp"].as_str().unwrap_or("").to_string());
+    let verb = if touch.is_some_and(|touch| touch["kind"] == "edit") {
+        "changed"
+    } else {
+        "touched"
+    };
+    let timestamp = latest(session);
+    let mut summary = format!("{target} was last {verb} by session {tape_id}");
+    if let Some(date) = display_date(&timestamp, zone) {
+        let ago = chrono::DateTime::parse_from_rfc3339(&timestamp)
+            .map(|at| relative_time(at.with_timezone(&Utc), now))
+            .unwrap_or_default();
+        summary.push_str(&format!(" on {date} ({ago})"));
+    }
+    let offset = touch.and_then(|touch| touch["event_offset"].as_u64());
+    let prompt = match (resolve_tape_path(context, tape_id), offset) {
+        (Some(tape_path), Some(offset)) => {
+            prompt_before(&parse_jsonl_rows(&read_tape_content(&tape_path)?)?, offset)
+        }
+        _ => None,
+    };
+    match prompt {
+        Some(prompt) => summary.push_str(&format!(
+            ", prompted by \"{}\".",
+            prompt.trim_start_matches("user: ")
+        )),
+        None => summary.push('.'),
+    }
+    summary.push_str(&format!(
+        " {} session(s) touched it; {} lineage edge(s){}.",
+        sessions.len(),
+        lineage.len(),
+        if lineage.is_empty() {
+            ""
+        } else {
+            " (-v to show)"
+        }
+    ));
+    Ok(summary)
+}
+
+/// The last user prompt at or before `offset`, as an excerpt.
+fn prompt_before(rows: &[TapeRow], offset: u64) -> Option<String> {
+    rows.iter()
+        .rev()
+        .filter(|row| row.offset <= offset)
+        .find(|row| row.value.get("k").and_then(Value::as_str) == Some("msg.in"))
+        .and_then(|row| excerpt_text(&row.value))
 }
 
-fn derive_anchor_candidates(span_texts: &[String]) -> Vec<String> {
+/// The `anchors.normalizers` entry for `path`'s language, if any.
+fn span_normalizer(context: &RuntimeContext, path: &Path) -> Option<&'static SpanNormalizer> {
+    normalizer_for_path(&path.to_string_lossy(), &context.normalizers)
+}
+
+/// Fingerprint cache key suffix for a normalizer, so raw and normalized
+/// fingerprints of one file never stand in for each other.
+fn normalizer_key(normalizer: Option<&SpanNormalizer>) -> String {
+    normalizer.map_or_else(String::new, |normalizer| format!(":{}", normalizer.tag()))
+}
+
+fn derive_anchor_candidates(
+    span_texts: &[String],
+    normalizer: Option<&SpanNormalizer>,
+) -> Vec<String> {
     let mut seen = HashSet::new();
     let mut out = Vec::new();
 
     for span_text in span_texts {
-        for token in fingerprint_token_hashes(span_text) {
+        for token in normalized_token_hashes(span_text, normalizer) {
             if seen.insert(token.clone()) {
                 out.push(token);
             }
@@ -3328,8 +11049,23 @@ fn parse_file_range_target(target: &str) -> Result<(&str, u32, u32), CliError> {
     Ok((file, start, end))
 }
 
-fn read_file_span_variants(path: &Path, start: u32, end: u32) -> Result<Vec<String>, CliError> {
+fn read_file_span_variants(
+    path: &Path,
+    start: u32,
+    end: u32,
+    normalize: bool,
+) -> Result<Vec<String>, CliError> {
     let content = fs::read_to_string(path).map_err(|err| CliError::io("read_span_error", err))?;
+    let content = if is_notebook_path(&path.to_string_lossy()) {
+        notebook_view(&content).unwrap_or(content)
+    } else {
+        content
+    };
+    let content = if normalize {
+        normalize_line_endings(&content).into_owned()
+    } else {
+        content
+    };
     let start_idx = start as usize - 1;
     let end_idx = end as usize - 1;
     let lines = content.lines().collect::<Vec<_>>();
@@ -3486,7 +11222,7 @@ fn extract_dispatch_links_from_transcript(transcript: &str) -> Vec<DispatchLink>
     out
 }
 
-fn extract_message_objects<'a>(row: &'a Value) -> Vec<&'a Value> {
+fn extract_message_objects(row: &Value) -> Vec<&Value> {
     let mut out = Vec::new();
     let Some(obj) = row.as_object() else {
         return out;
@@ -3679,6 +11415,8 @@ fn edge_to_json(edge: &EdgeRow) -> Value {
         "agent_link": edge.agent_link,
         "note": edge.note,
         "stored_class": stored_class_name(edge.stored_class),
+        "reverted": edge.reverted,
+        "scorer": edge.scorer,
     })
 }
 
@@ -3688,22 +11426,18 @@ fn repo_paths(cwd: &Path) -> Result<RepoPaths, CliError> {
         tapes: root.join("tapes"),
         objects: root.join("objects"),
         cursors: root.join("cursors"),
+        inflight: root.join("inflight"),
+        sessions: root.join("sessions"),
         root,
     })
 }
 
-fn resolve_runtime_context(cwd: &Path) -> Result<RuntimeContext, CliError> {
-    resolve_runtime_context_with_override(cwd, None)
-}
-
-fn resolve_runtime_context_with_override(
-    cwd: &Path,
-    config_override: Option<&Path>,
-) -> Result<RuntimeContext, CliError> {
+fn resolve_runtime_context(cwd: &Path, profile: Option<&str>) -> Result<RuntimeContext, CliError> {
     let home = home_dir()?;
-    let config = load_effective_config_with_override(cwd, &home, config_override)
+    let config = load_effective_config_for_profile(cwd, &home, None, profile)
         .map_err(|err| CliError::new("config_error", err.to_string()))?;
-    let tape_lookup_dirs = tape_lookup_dirs(cwd, &home, &config);
+    migrate_storage(&home, &config)?;
+    let tape_lookup_dirs = tape_lookup_dirs(&home, &config);
     Ok(RuntimeContext {
         config_path: config.path,
         db_path: config.db,
@@ -3718,9 +11452,41 @@ fn resolve_runtime_context_with_override(
         metrics_enabled: config.metrics.enabled,
         metrics_log: config.metrics.log,
         watch: config.watch,
+        watch_log: config.watch_log,
+        summarize: config.summarize,
+        export: config.export,
+        embeddings: config.embeddings,
+        auto_maintain_after_ingests: config.maintenance.auto_after_ingests,
+        partitioning: config.maintenance.partitioning,
+        fast_ingest: config.fast_ingest,
+        retain_raw: config.retain_raw,
+        retain_edits: config.retain_edits,
+        snapshot_state: config.snapshot_state,
+        environment: config.environment.clone(),
+        normalize_line_endings: config.normalize_line_endings,
+        normalizers: config.normalizers.clone(),
+        link_threshold: config.link_threshold,
+        scorer: config.scorer.clone(),
+        fingerprint_cache: config.storage.cache_dir.join(FINGERPRINT_CACHE_FILE),
+        sample_outputs: config.sample_outputs.clone(),
+        ingest_limits: config.ingest_limits,
+        binary_files: config.binary_files,
+        ignore: config.ignore.clone(),
+        issue_patterns: config.issue_patterns.clone(),
+        projects: config.projects.clone(),
+        storage_quota: config.storage_quota,
     })
 }
 
+fn migrate_storage(home: &Path, config: &EffectiveConfig) -> Result<(), CliError> {
+    let moved = migrate_legacy_storage(home, &config.storage)
+        .map_err(|err| CliError::new("storage_migration_error", err.to_string()))?;
+    for (from, to) in moved {
+        eprintln!("migrated: {} -> {}", from.display(), to.display());
+    }
+    Ok(())
+}
+
 fn ensure_local_store(paths: &RepoPaths) -> Result<(), CliError> {
     fs::create_dir_all(&paths.root).map_err(|err| CliError::io("mkdir_error", err))?;
     fs::create_dir_all(&paths.tapes).map_err(|err| CliError::io("mkdir_error", err))?;
@@ -3729,6 +11495,61 @@ fn ensure_local_store(paths: &RepoPaths) -> Result<(), CliError> {
     Ok(())
 }
 
+/// The store lock as commands hold it. Dropping it releases the lock, then
+/// runs the watchlist hooks queued while it was held, so a slow hook never
+/// keeps other writers waiting.
+struct StoreGuard {
+    lock: Option<StoreLock>,
+}
+
+impl Drop for StoreGuard {
+    fn drop(&mut self) {
+        drop(self.lock.take());
+        run_pending_watch_hooks();
+    }
+}
+
+/// Hold the store-wide writer lock for `command` until the guard drops.
+fn lock_store(context: &RuntimeContext, command: &str) -> Result<StoreGuard, CliError> {
+    StoreLock::acquire(&lock_path_for_db(&context.db_path), command, &now_iso8601())
+        .map(|lock| StoreGuard { lock: Some(lock) })
+        .map_err(|err| match err {
+            LockError::Held { .. } => CliError::new("store_locked", err.to_string()),
+            LockError::Io { .. } => CliError::new("lock_error", err.to_string()),
+        })
+}
+
+/// [`lock_store`] for writers that should queue behind a running ingest or
+/// gc instead of failing (`record`, `hook-receiver`, `annotate`, `import`,
+/// `rate`, `protect`, `watchlist`, `calibrate --write`): retry while the
+/// lock is held, for up to [`STORE_LOCK_WAIT`].
+fn wait_for_store_lock(context: &RuntimeContext, command: &str) -> Result<StoreGuard, CliError> {
+    let deadline = Instant::now() + STORE_LOCK_WAIT;
+    loop {
+        match lock_store(context, command) {
+            Err(err) if err.code == "store_locked" && Instant::now() < deadline => {
+                std::thread::sleep(STORE_LOCK_POLL);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// The context's index.
+fn open_index(context: &RuntimeContext) -> rusqlite::Result<SqliteIndex> {
+    open_index_at(context, &context.db_path)
+}
+
+/// The index at `path` (a partition, sibling store or rebuild), set up like
+/// [`open_index`]: edges classified under `lineage.link_threshold` and
+/// scored with `lineage.scorer`.
+fn open_index_at(context: &RuntimeContext, path: &Path) -> rusqlite::Result<SqliteIndex> {
+    let scorer = scorer_for_name(&context.scorer).unwrap_or_else(default_scorer);
+    Ok(SqliteIndex::open(&path_string(path))?
+        .with_link_threshold(context.link_threshold)
+        .with_similarity_scorer(scorer))
+}
+
 fn ensure_db_parent(db_path: &Path) -> Result<(), CliError> {
     if let Some(parent) = db_path.parent() {
         fs::create_dir_all(parent).map_err(|err| CliError::io("mkdir_error", err))?;
@@ -3779,6 +11600,56 @@ fn append_metrics(
     let _ = writeln!(file, "{payload}");
 }
 
+/// Append one line to the local audit log when `audit.enabled` is set: the
+/// command, its arguments, who ran it from where, how long it took and how
+/// it ended. Nothing here can fail the command itself.
+fn append_audit(
+    cwd: &Path,
+    profile: Option<&str>,
+    command: &str,
+    elapsed: Duration,
+    result: &Result<ExitCode, CliError>,
+) {
+    let Ok(home) = home_dir() else {
+        return;
+    };
+    let Ok(config) = load_effective_config_for_profile(cwd, &home, None, profile) else {
+        return;
+    };
+    if !config.audit.enabled {
+        return;
+    }
+    if let Some(parent) = config.audit.log.parent()
+        && fs::create_dir_all(parent).is_err()
+    {
+        return;
+    }
+
+    let args = std::env::args_os()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let payload = json!({
+        "ts": Utc::now().to_rfc3339(),
+        "command": command,
+        "args": args,
+        "cwd": path_string(cwd),
+        "user": std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok(),
+        "profile": profile,
+        "duration_ms": elapsed.as_millis() as u64,
+        "status": if result.is_ok() { "ok" } else { "error" },
+        "error": result.as_ref().err().map(|err| err.code),
+    });
+    let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&config.audit.log)
+    else {
+        return;
+    };
+    let _ = writeln!(file, "{payload}");
+}
+
 fn home_dir() -> Result<PathBuf, CliError> {
     std::env::var_os("HOME")
         .map(PathBuf::from)
@@ -3793,14 +11664,11 @@ fn tape_path_for_tapes_dir(tapes_dir: &Path, tape_id: &str) -> PathBuf {
     tapes_dir.join(format!("{tape_id}{TAPE_SUFFIX}"))
 }
 
-fn tape_lookup_dirs(
-    cwd: &Path,
-    home: &Path,
-    config: &engram::config::EffectiveConfig,
-) -> Vec<PathBuf> {
+fn tape_lookup_dirs(home: &Path, config: &EffectiveConfig) -> Vec<PathBuf> {
     let mut dirs = Vec::new();
     push_tape_lookup_dir(&mut dirs, config.tapes_dir.clone());
-    push_tape_lookup_dir(&mut dirs, cwd.join(".engram").join("tapes"));
+    push_tape_lookup_dir(&mut dirs, config.store_root.join(".engram").join("tapes"));
+    push_tape_lookup_dir(&mut dirs, config.storage.data_dir.join("tapes"));
     push_tape_lookup_dir(&mut dirs, home.join(".engram").join("tapes"));
     for store in &config.additional_stores {
         let store_tapes = store
@@ -3818,12 +11686,72 @@ fn push_tape_lookup_dir(dirs: &mut Vec<PathBuf>, candidate: PathBuf) {
     }
 }
 
+/// The file of `tape_id`, following the ids `reindex` migrated.
 fn resolve_tape_path(context: &RuntimeContext, tape_id: &str) -> Option<PathBuf> {
     context
         .tape_lookup_dirs
         .iter()
         .map(|dir| tape_path_for_tapes_dir(dir, tape_id))
         .find(|path| path.exists())
+        .or_else(|| {
+            context.tape_lookup_dirs.iter().find_map(|dir| {
+                let aliases = load_tape_id_aliases(&tape_id_aliases_path(dir)).ok()?;
+                let path = tape_path_for_tapes_dir(dir, aliases.resolve(tape_id)?);
+                path.exists().then_some(path)
+            })
+        })
+}
+
+/// Tape content for a tape id, or the parts of a `session:<name>` reference
+/// stitched in part order.
+struct ResolvedTape {
+    path: PathBuf,
+    content: String,
+    parts: Option<Vec<SessionPart>>,
+}
+
+fn read_tape_or_session(
+    paths: &RepoPaths,
+    context: &RuntimeContext,
+    id: &str,
+) -> Result<Option<ResolvedTape>, CliError> {
+    let Some(name) = id.strip_prefix(SESSION_REF_PREFIX) else {
+        return resolve_tape_path(context, id)
+            .map(|path| {
+                read_tape_content(&path).map(|content| ResolvedTape {
+                    path,
+                    content,
+                    parts: None,
+                })
+            })
+            .transpose();
+    };
+    if !valid_session_name(name) {
+        return Ok(None);
+    }
+    let Some(session) = load_record_session(&paths.sessions, name)
+        .map_err(|err| CliError::new("session_read_error", err.to_string()))?
+    else {
+        return Ok(None);
+    };
+    let mut contents = Vec::with_capacity(session.parts.len());
+    for part in &session.parts {
+        let Some(path) = resolve_tape_path(context, &part.tape_id) else {
+            return Err(CliError::new(
+                "tape_not_found",
+                format!(
+                    "part {} of session `{name}` (tape `{}`) not found",
+                    part.part, part.tape_id
+                ),
+            ));
+        };
+        contents.push(read_tape_content(&path)?);
+    }
+    Ok(Some(ResolvedTape {
+        path: session_manifest_path(&paths.sessions, name),
+        content: stitch_parts(&contents),
+        parts: Some(session.parts),
+    }))
 }
 
 fn tape_id_from_path(path: &Path) -> Option<String> {
@@ -3831,29 +11759,76 @@ fn tape_id_from_path(path: &Path) -> Option<String> {
     file_name.strip_suffix(TAPE_SUFFIX).map(ToOwned::to_owned)
 }
 
+/// Decompress a tape, checking that its content still hashes to the tape id
+/// in its file name. Files not named by a content hash are not checked.
 fn read_tape_content(path: &Path) -> Result<String, CliError> {
     let bytes = fs::read(path).map_err(|err| CliError::io("read_error", err))?;
-    decompress_jsonl(&bytes).map_err(|err| CliError::io("decompress_error", err))
+    let content = decompress_jsonl(&bytes)
+        .map_err(|err| CliError::new("decompress_error", err.to_string()))?;
+    if let Some(expected) = tape_id_from_path(path).filter(|id| is_content_tape_id(id)) {
+        // Tapes not yet moved to canonical ids are named by their bytes.
+        if !names_content(&expected, &content) {
+            let actual = tape_id_for_contents(&content);
+            return Err(CliError::new(
+                "tape_corrupt",
+                format!(
+                    "{}: content hashes to {actual}, not its tape id",
+                    path.display()
+                ),
+            ));
+        }
+    }
+    Ok(content)
+}
+
+fn is_content_tape_id(tape_id: &str) -> bool {
+    tape_id.len() == 64
+        && tape_id
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
 }
 
 fn path_string(path: &Path) -> String {
     path.to_string_lossy().into_owned()
 }
 
+/// Write `value` as one line on stdout. Unlike `println!`, a reader that
+/// has gone away (`engram explain ... | head`) ends the command quietly
+/// instead of panicking.
 fn print_json(value: &Value) -> Result<(), CliError> {
     let rendered = serde_json::to_string(value)?;
-    println!("{rendered}");
-    Ok(())
+    writeln!(io::stdout().lock(), "{rendered}").map_err(|err| {
+        if err.kind() == io::ErrorKind::BrokenPipe {
+            CliError::new("broken_pipe", err.to_string())
+        } else {
+            CliError::io("stdout_error", err)
+        }
+    })
 }
 
-fn emit_query_result(index: &SqliteIndex, command: &str, payload: Value) -> Result<(), CliError> {
-    let payload_json = canonical_json_string(&payload)?;
+fn record_query_payload(
+    index: &SqliteIndex,
+    command: &str,
+    payload: &Value,
+) -> Result<String, CliError> {
+    let payload_json = canonical_json_string(payload)?;
     let result_id = format!(
         "result_{}",
         sha256_hex(&format!("{command}:{payload_json}"))
     );
     let created_at = Utc::now().to_rfc3339();
     index.record_query_result(&result_id, command, &payload_json, &created_at)?;
+    Ok(result_id)
+}
+
+fn rating_hint(result_id: &str) -> String {
+    format!(
+        "Rate this result: engram rate {result_id} --outcome <found_answer|partially_helped|noise|misleading|not_used>"
+    )
+}
+
+fn emit_query_result(index: &SqliteIndex, command: &str, payload: Value) -> Result<(), CliError> {
+    let result_id = record_query_payload(index, command, &payload)?;
 
     let mut object = match payload {
         Value::Object(map) => map,
@@ -3864,13 +11839,8 @@ fn emit_query_result(index: &SqliteIndex, command: &str, payload: Value) -> Resu
             ));
         }
     };
-    object.insert("result_id".to_string(), Value::String(result_id.clone()));
-    object.insert(
-        "rating_hint".to_string(),
-        Value::String(format!(
-            "Rate this result: engram rate {result_id} --outcome <found_answer|partially_helped|noise|misleading|not_used>"
-        )),
-    );
+    object.insert("rating_hint".to_string(), json!(rating_hint(&result_id)));
+    object.insert("result_id".to_string(), Value::String(result_id));
     print_json(&Value::Object(object))
 }
 
@@ -3917,7 +11887,7 @@ fn write_canonical_json(value: &Value, out: &mut String) -> Result<(), CliError>
 }
 
 fn tape_id_for_contents(input: &str) -> String {
-    sha256_hex(input)
+    canonical_tape_id(input)
 }
 
 fn sha256_hex(input: &str) -> String {
@@ -4036,7 +12006,8 @@ mod tests {
         let cwd = home.join("workspace");
         fs::create_dir_all(&cwd).expect("workspace");
 
-        let err = cmd_watch_with_home(&cwd, WatchArgs::default(), &home).expect_err("must fail");
+        let err =
+            cmd_watch_with_home(&cwd, None, WatchArgs::default(), &home).expect_err("must fail");
         assert_eq!(err.code, "watch_config_error");
         assert!(
             err.message.contains("watch config missing in config.yml"),
@@ -4057,6 +12028,7 @@ mod tests {
 
         let err = cmd_watch_with_home(
             &cwd,
+            None,
             WatchArgs {
                 config: Some(config_path),
             },
@@ -4291,7 +12263,7 @@ mod tests {
             .map(|line| format!("fn line_{line}() {{ value_{line}(); }}\n"))
             .collect::<String>();
 
-        let anchors = derive_anchor_candidates(&[text]);
+        let anchors = derive_anchor_candidates(&[text], None);
         assert!(anchors.len() <= MAX_QUERY_WINDOW_ANCHORS);
     }
 }
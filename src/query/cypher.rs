@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+use crate::index::EdgeRow;
+use crate::index::lineage::{
+    Cardinality, EvidenceFragmentRef, EvidenceKind, LocationDelta, StoredEdgeClass,
+};
+use crate::tape::metrics::TapeMetrics;
+
+/// A Cypher script that recreates the causal graph in Neo4j: `Anchor` and
+/// `Tape` nodes keyed by `id`, a `TOUCHED` relationship from a tape to each
+/// anchor it has evidence on, and a `LINEAGE` relationship along each edge.
+/// Tapes carry the metrics recorded at ingest when `tapes` has them.
+pub fn cypher_script(
+    evidence: &[(String, EvidenceFragmentRef)],
+    edges: &[EdgeRow],
+    tapes: &BTreeMap<String, TapeMetrics>,
+) -> String {
+    let anchors = evidence
+        .iter()
+        .map(|(anchor, _)| anchor.as_str())
+        .chain(
+            edges
+                .iter()
+                .flat_map(|edge| [edge.from_anchor.as_str(), edge.to_anchor.as_str()]),
+        )
+        .collect::<BTreeSet<_>>();
+    let tape_ids = evidence
+        .iter()
+        .map(|(_, fragment)| fragment.tape_id.as_str())
+        .collect::<BTreeSet<_>>();
+
+    let mut out = String::new();
+    out.push_str(
+        "CREATE CONSTRAINT engram_anchor_id IF NOT EXISTS FOR (a:Anchor) REQUIRE a.id IS UNIQUE;\n",
+    );
+    out.push_str(
+        "CREATE CONSTRAINT engram_tape_id IF NOT EXISTS FOR (t:Tape) REQUIRE t.id IS UNIQUE;\n",
+    );
+    for anchor in anchors {
+        let _ = writeln!(out, "CREATE (:Anchor {{id: {}}});", literal(anchor));
+    }
+    for tape_id in tape_ids {
+        let mut props = vec![("id", literal(tape_id))];
+        if let Some(metrics) = tapes.get(tape_id) {
+            props.push(("event_count", metrics.event_count.to_string()));
+            props.push(("edit_count", metrics.edit_count.to_string()));
+            props.push(("tool_error_count", metrics.tool_error_count.to_string()));
+            for (key, value) in [
+                ("started_at", &metrics.started_at),
+                ("ended_at", &metrics.ended_at),
+                ("model", &metrics.model),
+                ("harness", &metrics.harness),
+                ("session_id", &metrics.session_id),
+                ("hostname", &metrics.hostname),
+                ("user", &metrics.user),
+            ] {
+                if let Some(value) = value {
+                    props.push((key, literal(value)));
+                }
+            }
+        }
+        let _ = writeln!(out, "CREATE (:Tape {});", map(&props));
+    }
+    for (anchor, fragment) in evidence {
+        let props = [
+            ("offset", fragment.event_offset.to_string()),
+            ("kind", literal(evidence_kind(fragment.kind))),
+            ("file", literal(&fragment.file_path)),
+            ("at", literal(&fragment.timestamp)),
+        ];
+        let _ = writeln!(
+            out,
+            "MATCH (t:Tape {{id: {}}}), (a:Anchor {{id: {}}}) CREATE (t)-[:TOUCHED {}]->(a);",
+            literal(&fragment.tape_id),
+            literal(anchor),
+            map(&props)
+        );
+    }
+    for edge in edges {
+        let mut props = vec![
+            ("confidence", format!("{:.4}", edge.confidence)),
+            (
+                "location_delta",
+                literal(location_delta(edge.location_delta)),
+            ),
+            ("cardinality", literal(cardinality(edge.cardinality))),
+            ("agent_link", edge.agent_link.to_string()),
+            (
+                "location_only",
+                (edge.stored_class == StoredEdgeClass::LocationOnly).to_string(),
+            ),
+            ("reverted", edge.reverted.to_string()),
+        ];
+        if let Some(note) = &edge.note {
+            props.push(("note", literal(note)));
+        }
+        let _ = writeln!(
+            out,
+            "MATCH (f:Anchor {{id: {}}}), (t:Anchor {{id: {}}}) CREATE (f)-[:LINEAGE {}]->(t);",
+            literal(&edge.from_anchor),
+            literal(&edge.to_anchor),
+            map(&props)
+        );
+    }
+    out
+}
+
+/// A Cypher string literal. JSON string escapes are valid Cypher escapes.
+fn literal(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn map(props: &[(&str, String)]) -> String {
+    let fields = props
+        .iter()
+        .map(|(key, value)| format!("{key}: {value}"))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(", "))
+}
+
+fn evidence_kind(kind: EvidenceKind) -> &'static str {
+    match kind {
+        EvidenceKind::Edit => "edit",
+        EvidenceKind::Read => "read",
+        EvidenceKind::Tool => "tool",
+        EvidenceKind::Message => "message",
+    }
+}
+
+fn location_delta(delta: LocationDelta) -> &'static str {
+    match delta {
+        LocationDelta::Same => "same",
+        LocationDelta::Adjacent => "adjacent",
+        LocationDelta::Moved => "moved",
+        LocationDelta::Absent => "absent",
+    }
+}
+
+fn cardinality(cardinality: Cardinality) -> &'static str {
+    match cardinality {
+        Cardinality::OneToOne => "1:1",
+        Cardinality::OneToMany => "1:N",
+        Cardinality::ManyToOne => "N:1",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_creates_nodes_before_relationships_and_escapes_text() {
+        let evidence = vec![(
+            "winnow:b".to_string(),
+            EvidenceFragmentRef {
+                tape_id: "tape-1".to_string(),
+                event_offset: 4,
+                kind: EvidenceKind::Edit,
+                file_path: "src/\"quoted\".rs".to_string(),
+                timestamp: "2026-02-22T00:00:00Z".to_string(),
+            },
+        )];
+        let edges = vec![EdgeRow {
+            from_anchor: "winnow:a".to_string(),
+            to_anchor: "winnow:b".to_string(),
+            confidence: 0.8,
+            location_delta: LocationDelta::Same,
+            cardinality: Cardinality::OneToOne,
+            agent_link: false,
+            note: None,
+            stored_class: StoredEdgeClass::Lineage,
+            reverted: false,
+            scorer: None,
+        }];
+        let tapes = BTreeMap::from([(
+            "tape-1".to_string(),
+            TapeMetrics {
+                event_count: 9,
+                harness: Some("claude".to_string()),
+                ..TapeMetrics::default()
+            },
+        )]);
+
+        let script = cypher_script(&evidence, &edges, &tapes);
+        let lines = script.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[2], "CREATE (:Anchor {id: \"winnow:a\"});");
+        assert_eq!(
+            lines[4],
+            "CREATE (:Tape {id: \"tape-1\", event_count: 9, edit_count: 0, tool_error_count: 0, harness: \"claude\"});"
+        );
+        assert!(lines[5].contains("file: \"src/\\\"quoted\\\".rs\""));
+        assert!(lines[6].ends_with(
+            "CREATE (f)-[:LINEAGE {confidence: 0.8000, location_delta: \"same\", cardinality: \"1:1\", agent_link: false, location_only: false, reverted: false}]->(t);"
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+
+use crate::anchor::fingerprint_token_hashes;
+
+/// One agent edit whose result should still be in its file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedEdit {
+    pub tape_id: String,
+    pub offset: u64,
+    pub timestamp: String,
+    pub before_text: Option<String>,
+    pub after_text: String,
+}
+
+/// How much of an edit's result the file still holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftState {
+    /// The edit's result is in the file verbatim (ignoring whitespace).
+    Intact,
+    /// Some of its fingerprint tokens are still there.
+    Modified,
+    /// None of it is.
+    Missing,
+    /// The file itself is gone.
+    FileDeleted,
+}
+
+impl DriftState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Intact => "intact",
+            Self::Modified => "modified",
+            Self::Missing => "missing",
+            Self::FileDeleted => "file_deleted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpanCheck {
+    pub state: DriftState,
+    /// Share of the expected text's fingerprint tokens found in the file.
+    pub coverage: f32,
+}
+
+/// Drop edits of one file (in tape order) whose result a later edit of the
+/// same session replaced, wholly or in part, and edits that only deleted.
+pub fn surviving_edits(edits: Vec<ExpectedEdit>) -> Vec<ExpectedEdit> {
+    let rewritten = edits
+        .iter()
+        .enumerate()
+        .map(|(at, edit)| {
+            let after = normalize_whitespace(&edit.after_text);
+            edits[at + 1..].iter().any(|later| {
+                let before = later
+                    .before_text
+                    .as_deref()
+                    .map(normalize_whitespace)
+                    .unwrap_or_default();
+                !before.is_empty() && (before.contains(&after) || after.contains(&before))
+            })
+        })
+        .collect::<Vec<_>>();
+    edits
+        .into_iter()
+        .zip(rewritten)
+        .filter(|(edit, rewritten)| !rewritten && !edit.after_text.trim().is_empty())
+        .map(|(edit, _)| edit)
+        .collect()
+}
+
+/// Check `expected` against the file's current content (`None` when the
+/// file no longer exists).
+pub fn check_span(current: Option<&str>, expected: &str) -> SpanCheck {
+    let tokens = current.map(token_set).unwrap_or_default();
+    check_span_with_tokens(current, &tokens, expected)
+}
+
+/// [`check_span`] with the current content's fingerprint tokens (see
+/// [`content_tokens`]) computed once per file rather than per edit.
+pub fn check_span_with_tokens(
+    current: Option<&str>,
+    current_tokens: &HashSet<String>,
+    expected: &str,
+) -> SpanCheck {
+    let Some(current) = current else {
+        return SpanCheck {
+            state: DriftState::FileDeleted,
+            coverage: 0.0,
+        };
+    };
+    if normalize_whitespace(current).contains(&normalize_whitespace(expected)) {
+        return SpanCheck {
+            state: DriftState::Intact,
+            coverage: 1.0,
+        };
+    }
+    let expected_tokens = token_set(expected);
+    let coverage = if expected_tokens.is_empty() {
+        0.0
+    } else {
+        expected_tokens.intersection(current_tokens).count() as f32 / expected_tokens.len() as f32
+    };
+    SpanCheck {
+        state: if coverage > 0.0 {
+            DriftState::Modified
+        } else {
+            DriftState::Missing
+        },
+        coverage,
+    }
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The fingerprint tokens [`check_span_with_tokens`] compares against.
+pub fn content_tokens(text: &str) -> Vec<String> {
+    fingerprint_token_hashes(text)
+}
+
+fn token_set(text: &str) -> HashSet<String> {
+    content_tokens(text).into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(offset: u64, before: Option<&str>, after: &str) -> ExpectedEdit {
+        ExpectedEdit {
+            tape_id: "tape".to_string(),
+            offset,
+            timestamp: String::new(),
+            before_text: before.map(str::to_owned),
+            after_text: after.to_string(),
+        }
+    }
+
+    #[test]
+    fn keeps_only_edits_the_session_did_not_rewrite() {
+        let edits = vec![
+            edit(1, None, "fn retry() { attempts(1) }"),
+            edit(
+                2,
+                Some("fn retry() { attempts(1) }"),
+                "fn retry() { attempts(3) }",
+            ),
+            edit(3, Some("const A: u8 = 1;"), "const A: u8 = 2;"),
+            edit(4, Some("const B: u8 = 1;"), ""),
+        ];
+        let offsets = surviving_edits(edits)
+            .iter()
+            .map(|edit| edit.offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![2, 3]);
+    }
+
+    #[test]
+    fn classifies_current_content() {
+        let expected = "fn retry() {\n    attempts(3)\n}";
+        let intact = check_span(
+            Some("// header\nfn retry() {\n  attempts(3)\n}\n"),
+            expected,
+        );
+        assert_eq!(intact.state, DriftState::Intact);
+        assert_eq!(intact.coverage, 1.0);
+        assert_eq!(
+            check_span(Some("nothing like it"), expected).state,
+            DriftState::Missing
+        );
+        assert_eq!(check_span(None, expected).state, DriftState::FileDeleted);
+
+        let long = (0..40)
+            .map(|n| format!("let value_{n} = compute_{n}(input);\n"))
+            .collect::<String>();
+        let edited = long.replace("let value_39 = compute_39(input);", "let v = other();");
+        let modified = check_span(Some(&edited), &long);
+        assert_eq!(modified.state, DriftState::Modified);
+        assert!(modified.coverage > 0.5 && modified.coverage < 1.0);
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Sessions further apart than this never share an episode.
+pub const DEFAULT_EPISODE_MAX_GAP_HOURS: i64 = 6;
+/// Share of the smaller keyword set two sessions must have in common to be
+/// treated as the same topic when their repo heads differ.
+pub const DEFAULT_EPISODE_MIN_KEYWORD_OVERLAP: f64 = 0.25;
+const EPISODE_SUMMARY_KEYWORDS: usize = 5;
+const MIN_KEYWORD_LEN: usize = 4;
+
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "also", "because", "been", "before", "being", "could", "does",
+    "doing", "done", "each", "from", "have", "here", "into", "just", "like", "make", "more",
+    "need", "only", "other", "please", "should", "some", "than", "that", "their", "them", "then",
+    "there", "these", "they", "this", "those", "what", "when", "where", "which", "while", "will",
+    "with", "would", "your",
+];
+
+/// One explain session as seen by episode clustering.
+#[derive(Debug, Clone, Default)]
+pub struct EpisodeSession {
+    pub session_id: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub repo_head: Option<String>,
+    pub keywords: BTreeSet<String>,
+}
+
+/// A group of sessions that read as one story arc: close in time and either
+/// on the same repo head or prompted about the same things.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Episode {
+    /// Member sessions in the order they were given (relevance order).
+    pub session_ids: Vec<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub repo_heads: Vec<String>,
+    /// Most frequent prompt keywords across members, most common first.
+    pub keywords: Vec<String>,
+}
+
+impl Episode {
+    pub fn summary(&self) -> String {
+        let count = self.session_ids.len();
+        let mut out = format!("{count} session{}", if count == 1 { "" } else { "s" });
+        match (self.started_at, self.ended_at) {
+            (Some(start), Some(end)) if start.date_naive() == end.date_naive() => {
+                out.push_str(&format!(" on {}", start.format("%Y-%m-%d")));
+            }
+            (Some(start), Some(end)) => {
+                out.push_str(&format!(
+                    " from {} to {}",
+                    start.format("%Y-%m-%d"),
+                    end.format("%Y-%m-%d")
+                ));
+            }
+            _ => {}
+        }
+        if !self.repo_heads.is_empty() {
+            out.push_str(&format!(" at {}", self.repo_heads.join(", ")));
+        }
+        if !self.keywords.is_empty() {
+            out.push_str(&format!(": {}", self.keywords.join(", ")));
+        }
+        out
+    }
+}
+
+/// Lowercased words from prompt text that are long enough and common enough
+/// in intent to identify a topic.
+pub fn prompt_keywords(text: &str) -> BTreeSet<String> {
+    text.split(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+        .filter(|word| word.chars().count() >= MIN_KEYWORD_LEN)
+        .filter(|word| !word.chars().all(|ch| ch.is_ascii_digit()))
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Group sessions into episodes. Sessions are visited in time order and join
+/// the best-matching open episode whose last session is within `max_gap`;
+/// a match needs a shared repo head, enough keyword overlap, or no signal on
+/// either side. Episodes are returned in the order their first member appears
+/// in `sessions`, so a relevance-ordered input yields relevance-ordered arcs.
+pub fn cluster_episodes(
+    sessions: &[EpisodeSession],
+    max_gap: Duration,
+    min_keyword_overlap: f64,
+) -> Vec<Episode> {
+    struct Open {
+        members: Vec<usize>,
+        started_at: Option<DateTime<Utc>>,
+        ended_at: Option<DateTime<Utc>>,
+        repo_heads: BTreeSet<String>,
+        keywords: BTreeSet<String>,
+    }
+
+    let mut order = (0..sessions.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        match (sessions[a].timestamp, sessions[b].timestamp) {
+            (Some(left), Some(right)) => left.cmp(&right),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+        .then(a.cmp(&b))
+    });
+
+    let mut open: Vec<Open> = Vec::new();
+    for idx in order {
+        let session = &sessions[idx];
+        let mut best: Option<(usize, f64)> = None;
+        for (episode_idx, episode) in open.iter().enumerate() {
+            if let (Some(ts), Some(end)) = (session.timestamp, episode.ended_at)
+                && ts - end > max_gap
+            {
+                continue;
+            }
+            let head_match = session
+                .repo_head
+                .as_ref()
+                .is_some_and(|head| episode.repo_heads.contains(head));
+            let overlap = keyword_overlap(&session.keywords, &episode.keywords);
+            let no_signal = (session.repo_head.is_none() && session.keywords.is_empty())
+                || (episode.repo_heads.is_empty() && episode.keywords.is_empty());
+            if !(head_match || overlap >= min_keyword_overlap || no_signal) {
+                continue;
+            }
+            let score = f64::from(u8::from(head_match)) + overlap;
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((episode_idx, score));
+            }
+        }
+
+        let episode = match best {
+            Some((episode_idx, _)) => &mut open[episode_idx],
+            None => {
+                open.push(Open {
+                    members: Vec::new(),
+                    started_at: session.timestamp,
+                    ended_at: session.timestamp,
+                    repo_heads: BTreeSet::new(),
+                    keywords: BTreeSet::new(),
+                });
+                open.last_mut().expect("episode pushed")
+            }
+        };
+        episode.members.push(idx);
+        if let Some(ts) = session.timestamp {
+            episode.started_at = Some(episode.started_at.map_or(ts, |start| start.min(ts)));
+            episode.ended_at = Some(episode.ended_at.map_or(ts, |end| end.max(ts)));
+        }
+        if let Some(head) = &session.repo_head {
+            episode.repo_heads.insert(head.clone());
+        }
+        episode.keywords.extend(session.keywords.iter().cloned());
+    }
+
+    let mut episodes = open
+        .into_iter()
+        .map(|mut episode| {
+            episode.members.sort_unstable();
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for &member in &episode.members {
+                for keyword in &sessions[member].keywords {
+                    *counts.entry(keyword.as_str()).or_default() += 1;
+                }
+            }
+            let mut keywords = counts.into_iter().collect::<Vec<_>>();
+            keywords.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            (
+                episode.members[0],
+                Episode {
+                    session_ids: episode
+                        .members
+                        .iter()
+                        .map(|&member| sessions[member].session_id.clone())
+                        .collect(),
+                    started_at: episode.started_at,
+                    ended_at: episode.ended_at,
+                    repo_heads: episode.repo_heads.into_iter().collect(),
+                    keywords: keywords
+                        .into_iter()
+                        .take(EPISODE_SUMMARY_KEYWORDS)
+                        .map(|(keyword, _)| keyword.to_string())
+                        .collect(),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    episodes.sort_by_key(|(first, _)| *first);
+    episodes.into_iter().map(|(_, episode)| episode).collect()
+}
+
+fn keyword_overlap(left: &BTreeSet<String>, right: &BTreeSet<String>) -> f64 {
+    let smaller = left.len().min(right.len());
+    if smaller == 0 {
+        return 0.0;
+    }
+    left.intersection(right).count() as f64 / smaller as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, ts: &str, head: Option<&str>, prompt: &str) -> EpisodeSession {
+        EpisodeSession {
+            session_id: id.to_string(),
+            timestamp: Some(
+                DateTime::parse_from_rfc3339(ts)
+                    .expect("timestamp")
+                    .with_timezone(&Utc),
+            ),
+            repo_head: head.map(ToOwned::to_owned),
+            keywords: prompt_keywords(prompt),
+        }
+    }
+
+    #[test]
+    fn groups_by_head_and_topic_within_gap() {
+        let sessions = vec![
+            session(
+                "late-auth",
+                "2026-03-02T10:00:00Z",
+                Some("bbb"),
+                "refresh token expiry in auth",
+            ),
+            session(
+                "auth-1",
+                "2026-03-01T09:00:00Z",
+                Some("aaa"),
+                "fix the auth token refresh",
+            ),
+            session(
+                "docs",
+                "2026-03-01T09:30:00Z",
+                Some("ccc"),
+                "rewrite README install section",
+            ),
+            session("auth-2", "2026-03-01T10:00:00Z", Some("aaa"), "add logging"),
+            session(
+                "auth-3",
+                "2026-03-01T11:00:00Z",
+                Some("ddd"),
+                "token refresh race",
+            ),
+        ];
+        let episodes = cluster_episodes(
+            &sessions,
+            Duration::hours(DEFAULT_EPISODE_MAX_GAP_HOURS),
+            DEFAULT_EPISODE_MIN_KEYWORD_OVERLAP,
+        );
+
+        let ids = episodes
+            .iter()
+            .map(|episode| episode.session_ids.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                vec!["late-auth".to_string()],
+                vec!["auth-1".into(), "auth-2".into(), "auth-3".into()],
+                vec!["docs".to_string()],
+            ]
+        );
+        assert_eq!(episodes[1].repo_heads, vec!["aaa", "ddd"]);
+        assert_eq!(episodes[1].keywords[..2], ["refresh", "token"]);
+        assert_eq!(
+            episodes[1].summary(),
+            "3 sessions on 2026-03-01 at aaa, ddd: refresh, token, auth, logging, race"
+        );
+    }
+
+    #[test]
+    fn prompt_keywords_drop_short_words_numbers_and_stopwords() {
+        let keywords = prompt_keywords("Please fix THE parser_state in 2026 because tests fail");
+        assert_eq!(
+            keywords.into_iter().collect::<Vec<_>>(),
+            vec!["fail", "parser_state", "tests"]
+        );
+    }
+}
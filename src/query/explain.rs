@@ -1,7 +1,8 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use crate::index::EdgeRow;
 use crate::index::lineage::EvidenceFragmentRef;
-use crate::index::{EdgeRow, SqliteIndex};
+use crate::query::graph::LineageGraph;
 
 pub const MIN_CONFIDENCE_DEFAULT: f32 = 0.50;
 pub const MAX_FANOUT_DEFAULT: usize = 50;
@@ -51,17 +52,74 @@ pub fn pretty_tier(confidence: f32, moved: bool, location_only: bool) -> PrettyC
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncationReason {
+    MaxEdges,
+    MaxFanout,
+    MaxDepth,
+}
+
+impl TruncationReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MaxEdges => "max_edges",
+            Self::MaxFanout => "max_fanout",
+            Self::MaxDepth => "max_depth",
+        }
+    }
+}
+
+/// Marks a lineage walk cut short by an [`ExplainTraversal`] budget. `reason`
+/// and `at_depth` describe the first limit hit; `dropped_frontier` counts every
+/// edge or anchor left unexplored across all limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraversalTruncation {
+    pub reason: TruncationReason,
+    pub at_depth: usize,
+    pub dropped_frontier: usize,
+}
+
+impl TraversalTruncation {
+    /// Combine truncation from several walks (e.g. one per store).
+    pub fn merge(left: Option<Self>, right: Option<Self>) -> Option<Self> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(Self {
+                dropped_frontier: left.dropped_frontier + right.dropped_frontier,
+                ..left
+            }),
+            (left, right) => left.or(right),
+        }
+    }
+
+    fn record(slot: &mut Option<Self>, reason: TruncationReason, at_depth: usize, dropped: usize) {
+        if dropped == 0 {
+            return;
+        }
+        match slot {
+            Some(existing) => existing.dropped_frontier += dropped,
+            None => {
+                *slot = Some(Self {
+                    reason,
+                    at_depth,
+                    dropped_frontier: dropped,
+                })
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExplainResult {
     pub direct: Vec<EvidenceFragmentRef>,
     pub lineage: Vec<EdgeRow>,
     pub touched_anchors: Vec<String>,
+    pub truncated: Option<TraversalTruncation>,
 }
 
-pub fn retrieve_direct(
-    index: &SqliteIndex,
+pub fn retrieve_direct<G: LineageGraph + ?Sized>(
+    index: &G,
     anchors: &[String],
-) -> rusqlite::Result<Vec<EvidenceFragmentRef>> {
+) -> Result<Vec<EvidenceFragmentRef>, G::Error> {
     let mut all = Vec::new();
     for anchor in anchors {
         all.extend(index.evidence_for_anchor(anchor)?);
@@ -69,50 +127,229 @@ pub fn retrieve_direct(
     Ok(all)
 }
 
-pub fn retrieve_lineage(
-    index: &SqliteIndex,
+pub fn retrieve_lineage<G: LineageGraph + ?Sized>(
+    index: &G,
     anchors: &[String],
     traversal: ExplainTraversal,
     include_forensics: bool,
-) -> rusqlite::Result<Vec<EdgeRow>> {
-    let mut queue: VecDeque<(String, usize)> =
-        anchors.iter().cloned().map(|anchor| (anchor, 0)).collect();
-    let mut visited = HashSet::new();
-    let mut out = Vec::new();
-
-    while let Some((anchor, depth)) = queue.pop_front() {
-        if !visited.insert(anchor.clone()) {
-            continue;
+) -> Result<Vec<EdgeRow>, G::Error> {
+    LineageWalker::new(index, anchors, traversal, include_forensics)
+        .map(|step| step.map(|step| step.edge))
+        .collect()
+}
+
+/// Breadth-first walk over inbound lineage edges that hands each edge to
+/// `visit` as soon as it is read, so callers can stream results instead of
+/// buffering the whole traversal. Returns where the budget cut the walk
+/// short, if it did.
+pub fn walk_lineage<G: LineageGraph + ?Sized>(
+    index: &G,
+    anchors: &[String],
+    traversal: ExplainTraversal,
+    include_forensics: bool,
+    mut visit: impl FnMut(&EdgeRow),
+) -> Result<Option<TraversalTruncation>, G::Error> {
+    let mut walker = LineageWalker::new(index, anchors, traversal, include_forensics);
+    for step in walker.by_ref() {
+        visit(&step?.edge);
+    }
+    Ok(walker.truncation())
+}
+
+/// One edge reached by a [`LineageWalker`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineageStep {
+    pub edge: EdgeRow,
+    /// Hops between the queried anchor and `edge.to_anchor` (0 for edges
+    /// into a queried anchor).
+    pub depth: usize,
+    /// Anchors from `edge.from_anchor` down to the queried anchor the walk
+    /// reached it from, ordered like [`LineagePath::anchors`].
+    pub path: Vec<String>,
+}
+
+/// Lazy breadth-first lineage traversal: the same walk as [`walk_lineage`],
+/// one edge per `next()`, reading each anchor's inbound edges only when the
+/// walk gets to it. Stop early to skip the rest of the graph; after the
+/// iterator is exhausted, [`truncation`](Self::truncation) reports whether
+/// the [`ExplainTraversal`] budget cut it short.
+pub struct LineageWalker<'a, G: LineageGraph + ?Sized> {
+    index: &'a G,
+    traversal: ExplainTraversal,
+    include_forensics: bool,
+    queue: VecDeque<(String, usize)>,
+    visited: HashSet<String>,
+    roots: HashSet<String>,
+    /// First anchor each discovered anchor was reached from, toward a root.
+    reached_from: HashMap<String, String>,
+    pending: VecDeque<EdgeRow>,
+    pending_depth: usize,
+    emitted: usize,
+    truncated: Option<TraversalTruncation>,
+    done: bool,
+}
+
+impl<'a, G: LineageGraph + ?Sized> LineageWalker<'a, G> {
+    pub fn new(
+        index: &'a G,
+        anchors: &[String],
+        traversal: ExplainTraversal,
+        include_forensics: bool,
+    ) -> Self {
+        Self {
+            index,
+            traversal,
+            include_forensics,
+            queue: anchors.iter().cloned().map(|anchor| (anchor, 0)).collect(),
+            visited: HashSet::new(),
+            roots: anchors.iter().cloned().collect(),
+            reached_from: HashMap::new(),
+            pending: VecDeque::new(),
+            pending_depth: 0,
+            emitted: 0,
+            truncated: None,
+            done: false,
         }
-        if depth >= traversal.max_depth {
-            continue;
+    }
+
+    /// Where the budget cut the walk short, if it did. Only final once the
+    /// iterator has returned `None`.
+    pub fn truncation(&self) -> Option<TraversalTruncation> {
+        self.truncated
+    }
+
+    fn path_from(&self, edge: &EdgeRow) -> Vec<String> {
+        let mut path = vec![edge.from_anchor.clone(), edge.to_anchor.clone()];
+        let mut current = &edge.to_anchor;
+        while let Some(next) = self.reached_from.get(current) {
+            if path.len() > self.reached_from.len() + 1 {
+                break;
+            }
+            path.push(next.clone());
+            current = next;
+        }
+        path
+    }
+
+    fn step(&mut self, edge: EdgeRow) -> LineageStep {
+        if !self.visited.contains(&edge.from_anchor) {
+            if !self.roots.contains(&edge.from_anchor) {
+                self.reached_from
+                    .entry(edge.from_anchor.clone())
+                    .or_insert_with(|| edge.to_anchor.clone());
+            }
+            self.queue
+                .push_back((edge.from_anchor.clone(), self.pending_depth + 1));
         }
-        if out.len() >= traversal.max_edges {
-            break;
+        self.emitted += 1;
+        LineageStep {
+            path: self.path_from(&edge),
+            depth: self.pending_depth,
+            edge,
         }
-        let edges = index.inbound_edges(&anchor, traversal.min_confidence, include_forensics)?;
-        for edge in edges.into_iter().take(traversal.max_fanout) {
-            if out.len() >= traversal.max_edges {
+    }
+}
+
+impl<G: LineageGraph + ?Sized> Iterator for LineageWalker<'_, G> {
+    type Item = Result<LineageStep, G::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let traversal = self.traversal;
+        while !self.done {
+            if let Some(edge) = self.pending.pop_front() {
+                if self.emitted >= traversal.max_edges {
+                    // This edge and the rest of its anchor's batch.
+                    TraversalTruncation::record(
+                        &mut self.truncated,
+                        TruncationReason::MaxEdges,
+                        self.pending_depth,
+                        self.pending.len() + 1,
+                    );
+                    self.pending.clear();
+                    continue;
+                }
+                return Some(Ok(self.step(edge)));
+            }
+
+            let Some((anchor, depth)) = self.queue.pop_front() else {
+                self.done = true;
+                break;
+            };
+            if !self.visited.insert(anchor.clone()) {
+                continue;
+            }
+            if self.emitted >= traversal.max_edges {
+                let pending = 1 + self
+                    .queue
+                    .iter()
+                    .filter(|(pending, _)| !self.visited.contains(pending))
+                    .count();
+                TraversalTruncation::record(
+                    &mut self.truncated,
+                    TruncationReason::MaxEdges,
+                    depth,
+                    pending,
+                );
+                self.done = true;
                 break;
             }
-            if !visited.contains(&edge.from_anchor) {
-                queue.push_back((edge.from_anchor.clone(), depth + 1));
+            let edges = match self.index.inbound_edges(
+                &anchor,
+                traversal.min_confidence,
+                self.include_forensics,
+            ) {
+                Ok(edges) => edges,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            };
+            if depth >= traversal.max_depth {
+                TraversalTruncation::record(
+                    &mut self.truncated,
+                    TruncationReason::MaxDepth,
+                    depth,
+                    edges.len(),
+                );
+                continue;
             }
-            out.push(edge);
+            TraversalTruncation::record(
+                &mut self.truncated,
+                TruncationReason::MaxFanout,
+                depth,
+                edges.len().saturating_sub(traversal.max_fanout),
+            );
+            self.pending = edges.into_iter().take(traversal.max_fanout).collect();
+            self.pending_depth = depth;
         }
+        None
     }
+}
 
-    Ok(out)
+pub fn explain_by_anchor<G: LineageGraph + ?Sized>(
+    index: &G,
+    anchors: &[String],
+    traversal: ExplainTraversal,
+    include_forensics: bool,
+) -> Result<ExplainResult, G::Error> {
+    explain_by_anchor_with(index, anchors, traversal, include_forensics, |_| {})
 }
 
-pub fn explain_by_anchor(
-    index: &SqliteIndex,
+/// [`explain_by_anchor`] that also reports each lineage edge to `on_edge` as
+/// the traversal discovers it.
+pub fn explain_by_anchor_with<G: LineageGraph + ?Sized>(
+    index: &G,
     anchors: &[String],
     traversal: ExplainTraversal,
     include_forensics: bool,
-) -> rusqlite::Result<ExplainResult> {
+    mut on_edge: impl FnMut(&EdgeRow),
+) -> Result<ExplainResult, G::Error> {
     let direct = retrieve_direct(index, anchors)?;
-    let lineage = retrieve_lineage(index, anchors, traversal, include_forensics)?;
+    let mut lineage = Vec::new();
+    let truncated = walk_lineage(index, anchors, traversal, include_forensics, |edge| {
+        on_edge(edge);
+        lineage.push(edge.clone());
+    })?;
     let mut seen = HashSet::new();
     let mut touched_anchors = anchors.to_vec();
     for anchor in anchors {
@@ -130,12 +367,99 @@ pub fn explain_by_anchor(
         direct,
         lineage,
         touched_anchors,
+        truncated,
     })
 }
 
+/// One lineage chain, ordered from its root (the earliest anchor reached)
+/// to the queried anchor, with the product of edge confidences along it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineagePath {
+    pub anchors: Vec<String>,
+    pub edges: Vec<EdgeRow>,
+    pub confidence: f32,
+}
+
+impl LineagePath {
+    pub fn root(&self) -> &str {
+        self.anchors.first().map(String::as_str).unwrap_or_default()
+    }
+
+    pub fn target(&self) -> &str {
+        self.anchors.last().map(String::as_str).unwrap_or_default()
+    }
+}
+
+/// Highest-confidence path from any of `targets` back to each root of the
+/// `lineage` graph, where a root is an anchor with no inbound edge in
+/// `lineage`. Paths are ordered by cumulative confidence, best first.
+pub fn best_lineage_paths(lineage: &[EdgeRow], targets: &[String]) -> Vec<LineagePath> {
+    let mut inbound: HashMap<&str, Vec<&EdgeRow>> = HashMap::new();
+    for edge in lineage {
+        inbound
+            .entry(edge.to_anchor.as_str())
+            .or_default()
+            .push(edge);
+    }
+
+    // Confidence products only shrink along a path, so plain relaxation
+    // settles within |anchors| rounds and needs no priority queue.
+    let mut best: HashMap<&str, (f32, Option<&EdgeRow>)> = HashMap::new();
+    for target in targets {
+        best.insert(target.as_str(), (1.0, None));
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for edge in lineage {
+            let Some(&(reach, _)) = best.get(edge.to_anchor.as_str()) else {
+                continue;
+            };
+            let candidate = reach * edge.confidence;
+            let improves = best
+                .get(edge.from_anchor.as_str())
+                .is_none_or(|(current, _)| candidate > *current);
+            if improves {
+                best.insert(edge.from_anchor.as_str(), (candidate, Some(edge)));
+                changed = true;
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    for (&anchor, &(confidence, via)) in &best {
+        if via.is_none() || inbound.contains_key(anchor) {
+            continue;
+        }
+        let mut anchors = vec![anchor.to_string()];
+        let mut edges = Vec::new();
+        let mut cursor = via;
+        while let Some(edge) = cursor {
+            anchors.push(edge.to_anchor.clone());
+            edges.push(edge.clone());
+            cursor = best.get(edge.to_anchor.as_str()).and_then(|(_, via)| *via);
+        }
+        paths.push(LineagePath {
+            anchors,
+            edges,
+            confidence,
+        });
+    }
+    paths.sort_by(|left, right| {
+        right
+            .confidence
+            .partial_cmp(&left.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| left.anchors.len().cmp(&right.anchors.len()))
+            .then_with(|| left.anchors.cmp(&right.anchors))
+    });
+    paths
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::index::SqliteIndex;
     use crate::index::lineage::LINK_THRESHOLD_DEFAULT;
     use crate::index::lineage::{Cardinality, LocationDelta, SpanEdge};
     use crate::tape::event::{CodeEditEvent, FileRange, TapeEvent, TapeEventAt, TapeEventData};
@@ -158,6 +482,8 @@ mod tests {
                     before_anchor_hashes: vec!["a".to_string()],
                     after_anchor_hashes: vec!["b".to_string()],
                     similarity: Some(0.80),
+                    normalizer: None,
+                    project: None,
                 }),
             },
         }];
@@ -207,6 +533,8 @@ mod tests {
                         before_anchor_hashes: vec!["a".to_string()],
                         after_anchor_hashes: vec!["b".to_string()],
                         similarity: Some(0.80),
+                        normalizer: None,
+                        project: None,
                     }),
                 },
             },
@@ -225,6 +553,8 @@ mod tests {
                         before_anchor_hashes: vec!["b".to_string()],
                         after_anchor_hashes: vec!["c".to_string()],
                         similarity: Some(0.80),
+                        normalizer: None,
+                        project: None,
                     }),
                 },
             },
@@ -321,4 +651,166 @@ mod tests {
         assert_eq!(lineage[0].from_anchor, "b");
         assert_eq!(lineage[0].to_anchor, "c");
     }
+
+    fn insert_test_edge(index: &SqliteIndex, from: &str, to: &str) {
+        index
+            .insert_edge(
+                &SpanEdge {
+                    from_anchor: from.to_string(),
+                    to_anchor: to.to_string(),
+                    confidence: 0.90,
+                    location_delta: LocationDelta::Same,
+                    cardinality: Cardinality::OneToOne,
+                    agent_link: false,
+                    note: None,
+                },
+                LINK_THRESHOLD_DEFAULT,
+            )
+            .expect("insert edge");
+    }
+
+    #[test]
+    fn truncation_reports_first_budget_hit_and_dropped_frontier() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        insert_test_edge(&index, "a", "b");
+        insert_test_edge(&index, "b", "c");
+        insert_test_edge(&index, "x", "c");
+        let anchors = ["c".to_string()];
+
+        let complete = explain_by_anchor(&index, &anchors, ExplainTraversal::default(), false)
+            .expect("complete explain");
+        assert_eq!(complete.truncated, None);
+        assert_eq!(complete.lineage.len(), 3);
+
+        let shallow = explain_by_anchor(
+            &index,
+            &anchors,
+            ExplainTraversal {
+                max_depth: 1,
+                ..ExplainTraversal::default()
+            },
+            false,
+        )
+        .expect("shallow explain");
+        assert_eq!(
+            shallow.truncated,
+            Some(TraversalTruncation {
+                reason: TruncationReason::MaxDepth,
+                at_depth: 1,
+                dropped_frontier: 1,
+            })
+        );
+
+        let narrow = explain_by_anchor(
+            &index,
+            &anchors,
+            ExplainTraversal {
+                max_fanout: 1,
+                ..ExplainTraversal::default()
+            },
+            false,
+        )
+        .expect("narrow explain");
+        let truncated = narrow.truncated.expect("fanout truncation");
+        assert_eq!(truncated.reason, TruncationReason::MaxFanout);
+        assert_eq!(truncated.at_depth, 0);
+
+        let budgeted = explain_by_anchor(
+            &index,
+            &anchors,
+            ExplainTraversal {
+                max_edges: 1,
+                ..ExplainTraversal::default()
+            },
+            false,
+        )
+        .expect("budgeted explain");
+        assert_eq!(budgeted.lineage.len(), 1);
+        assert_eq!(
+            budgeted.truncated.map(|truncated| truncated.reason),
+            Some(TruncationReason::MaxEdges)
+        );
+    }
+
+    #[test]
+    fn walker_yields_edges_lazily_with_depth_and_path() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        insert_test_edge(&index, "a", "b");
+        insert_test_edge(&index, "b", "c");
+        insert_test_edge(&index, "x", "c");
+        let anchors = ["c".to_string()];
+
+        let steps = LineageWalker::new(&index, &anchors, ExplainTraversal::default(), false)
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .expect("walk");
+        let summary = steps
+            .iter()
+            .map(|step| {
+                (
+                    step.edge.from_anchor.as_str(),
+                    step.depth,
+                    step.path.join(">"),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("b", 0, "b>c".to_string()),
+                ("x", 0, "x>c".to_string()),
+                ("a", 1, "a>b>c".to_string()),
+            ]
+        );
+        assert_eq!(
+            steps
+                .iter()
+                .map(|step| step.edge.clone())
+                .collect::<Vec<_>>(),
+            retrieve_lineage(&index, &anchors, ExplainTraversal::default(), false)
+                .expect("lineage")
+        );
+
+        let mut walker = LineageWalker::new(&index, &anchors, ExplainTraversal::default(), false);
+        let first = walker.next().expect("first step").expect("edge");
+        assert_eq!(first.depth, 0);
+        assert_eq!(walker.truncation(), None);
+    }
+
+    #[test]
+    fn best_paths_pick_highest_cumulative_confidence_to_each_root() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        for (from, to, confidence) in [
+            ("root", "mid-weak", 0.60),
+            ("mid-weak", "target", 0.60),
+            ("root", "mid-strong", 0.95),
+            ("mid-strong", "target", 0.90),
+            ("other-root", "target", 0.70),
+        ] {
+            index
+                .insert_edge(
+                    &SpanEdge {
+                        from_anchor: from.to_string(),
+                        to_anchor: to.to_string(),
+                        confidence,
+                        location_delta: LocationDelta::Same,
+                        cardinality: Cardinality::OneToOne,
+                        agent_link: false,
+                        note: None,
+                    },
+                    LINK_THRESHOLD_DEFAULT,
+                )
+                .expect("insert edge");
+        }
+        let targets = ["target".to_string()];
+        let result = explain_by_anchor(&index, &targets, ExplainTraversal::default(), false)
+            .expect("explain");
+
+        let paths = best_lineage_paths(&result.lineage, &targets);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].anchors, vec!["root", "mid-strong", "target"]);
+        assert!((paths[0].confidence - 0.855).abs() < 1e-5);
+        assert_eq!(paths[0].edges.len(), 2);
+        assert_eq!(paths[1].root(), "other-root");
+        assert_eq!(paths[1].target(), "target");
+    }
 }
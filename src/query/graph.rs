@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+#[cfg(feature = "native")]
+use crate::index::SqliteIndex;
+use crate::index::lineage::EvidenceFragmentRef;
+use crate::index::{EdgeRow, filter_edges};
+
+/// The reads `explain` makes of the causal graph. [`SqliteIndex`] answers
+/// them from the index; [`MemoryGraph`] from anchors and edges held in
+/// memory, with no SQLite underneath (snapshots, browser builds).
+pub trait LineageGraph {
+    type Error;
+
+    fn evidence_for_anchor(&self, anchor: &str) -> Result<Vec<EvidenceFragmentRef>, Self::Error>;
+
+    /// Edges into `to_anchor`, highest confidence first, filtered like
+    /// [`SqliteIndex::inbound_edges`].
+    fn inbound_edges(
+        &self,
+        to_anchor: &str,
+        min_confidence: f32,
+        include_forensics: bool,
+    ) -> Result<Vec<EdgeRow>, Self::Error>;
+}
+
+#[cfg(feature = "native")]
+impl LineageGraph for SqliteIndex {
+    type Error = rusqlite::Error;
+
+    fn evidence_for_anchor(&self, anchor: &str) -> rusqlite::Result<Vec<EvidenceFragmentRef>> {
+        SqliteIndex::evidence_for_anchor(self, anchor)
+    }
+
+    fn inbound_edges(
+        &self,
+        to_anchor: &str,
+        min_confidence: f32,
+        include_forensics: bool,
+    ) -> rusqlite::Result<Vec<EdgeRow>> {
+        SqliteIndex::inbound_edges(self, to_anchor, min_confidence, include_forensics)
+    }
+}
+
+/// A causal graph held in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryGraph {
+    evidence: HashMap<String, Vec<EvidenceFragmentRef>>,
+    inbound: HashMap<String, Vec<EdgeRow>>,
+}
+
+impl MemoryGraph {
+    pub fn add_evidence(&mut self, anchor: impl Into<String>, fragment: EvidenceFragmentRef) {
+        self.evidence
+            .entry(anchor.into())
+            .or_default()
+            .push(fragment);
+    }
+
+    pub fn add_edge(&mut self, edge: EdgeRow) {
+        let edges = self.inbound.entry(edge.to_anchor.clone()).or_default();
+        let at = edges.partition_point(|existing| existing.confidence >= edge.confidence);
+        edges.insert(at, edge);
+    }
+
+    pub fn anchor_count(&self) -> usize {
+        self.evidence.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.inbound.values().map(Vec::len).sum()
+    }
+}
+
+impl LineageGraph for MemoryGraph {
+    type Error = Infallible;
+
+    fn evidence_for_anchor(&self, anchor: &str) -> Result<Vec<EvidenceFragmentRef>, Infallible> {
+        Ok(self.evidence.get(anchor).cloned().unwrap_or_default())
+    }
+
+    fn inbound_edges(
+        &self,
+        to_anchor: &str,
+        min_confidence: f32,
+        include_forensics: bool,
+    ) -> Result<Vec<EdgeRow>, Infallible> {
+        let edges = self.inbound.get(to_anchor).cloned().unwrap_or_default();
+        Ok(filter_edges(edges, min_confidence, include_forensics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::lineage::{
+        Cardinality, EvidenceKind, LINK_THRESHOLD_DEFAULT, LocationDelta, SpanEdge,
+    };
+    use crate::query::explain::{ExplainTraversal, explain_by_anchor};
+
+    #[test]
+    fn memory_graph_explains_like_the_index() {
+        let index = SqliteIndex::open_in_memory().expect("sqlite");
+        let mut graph = MemoryGraph::default();
+        for (from, to, confidence) in [("a", "b", 0.9), ("b", "c", 0.7), ("x", "c", 0.1)] {
+            let edge = SpanEdge {
+                from_anchor: from.to_string(),
+                to_anchor: to.to_string(),
+                confidence,
+                location_delta: LocationDelta::Same,
+                cardinality: Cardinality::OneToOne,
+                agent_link: false,
+                note: None,
+            };
+            index
+                .insert_edge(&edge, LINK_THRESHOLD_DEFAULT)
+                .expect("insert edge");
+            graph.add_edge(EdgeRow {
+                stored_class: edge.stored_class(LINK_THRESHOLD_DEFAULT),
+                from_anchor: edge.from_anchor,
+                to_anchor: edge.to_anchor,
+                confidence: edge.confidence,
+                location_delta: edge.location_delta,
+                cardinality: edge.cardinality,
+                agent_link: edge.agent_link,
+                note: edge.note,
+                reverted: false,
+                scorer: None,
+            });
+        }
+        graph.add_evidence(
+            "c",
+            EvidenceFragmentRef {
+                tape_id: "tape-1".to_string(),
+                event_offset: 3,
+                kind: EvidenceKind::Edit,
+                file_path: "src/lib.rs".to_string(),
+                timestamp: "2026-02-22T00:00:00Z".to_string(),
+            },
+        );
+
+        let anchors = ["c".to_string()];
+        let from_index = explain_by_anchor(&index, &anchors, ExplainTraversal::default(), false)
+            .expect("index explain");
+        let Ok(from_memory) =
+            explain_by_anchor(&graph, &anchors, ExplainTraversal::default(), false);
+        assert_eq!(from_memory.lineage, from_index.lineage);
+        assert_eq!(from_memory.touched_anchors, ["c", "b", "a"]);
+        assert_eq!(from_memory.direct.len(), 1);
+        assert_eq!((graph.anchor_count(), graph.edge_count()), (1, 3));
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Local, Utc};
+
+/// Time zone timestamps are shown in by human-readable output. JSON output
+/// always keeps the recorded RFC 3339 UTC strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayZone {
+    #[default]
+    Local,
+    Utc,
+}
+
+/// `raw` (RFC 3339) as a date and time in `zone` followed by how long before
+/// `now` it was, e.g. `2026-02-22 09:30 UTC (3 days ago)`. Text that does
+/// not parse is returned unchanged.
+pub fn display_timestamp(raw: &str, zone: DisplayZone, now: DateTime<Utc>) -> String {
+    let Ok(at) = DateTime::parse_from_rfc3339(raw) else {
+        return raw.to_string();
+    };
+    let at = at.with_timezone(&Utc);
+    let shown = match zone {
+        DisplayZone::Utc => at.format("%Y-%m-%d %H:%M UTC").to_string(),
+        DisplayZone::Local => at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M %:z")
+            .to_string(),
+    };
+    format!("{shown} ({})", relative_time(at, now))
+}
+
+/// The date part of `raw` in `zone`, or `None` when it does not parse.
+pub fn display_date(raw: &str, zone: DisplayZone) -> Option<String> {
+    let at = DateTime::parse_from_rfc3339(raw).ok()?;
+    Some(match zone {
+        DisplayZone::Utc => at.with_timezone(&Utc).format("%Y-%m-%d").to_string(),
+        DisplayZone::Local => at.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+    })
+}
+
+/// `at` relative to `now` in the largest whole unit: `just now`,
+/// `5 minutes ago`, `in 2 days`.
+pub fn relative_time(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let secs = (now - at).num_seconds();
+    let magnitude = secs.unsigned_abs();
+    if magnitude < 45 {
+        return "just now".to_string();
+    }
+    let (count, unit) = [
+        (365 * 86_400, "year"),
+        (30 * 86_400, "month"),
+        (7 * 86_400, "week"),
+        (86_400, "day"),
+        (3_600, "hour"),
+        (60, "minute"),
+    ]
+    .into_iter()
+    .find(|(size, _)| magnitude >= *size)
+    .map_or((1, "minute"), |(size, unit)| (magnitude / size, unit));
+    let plural = if count == 1 { "" } else { "s" };
+    if secs >= 0 {
+        format!("{count} {unit}{plural} ago")
+    } else {
+        format!("in {count} {unit}{plural}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .expect("timestamp")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn relative_times_use_the_largest_whole_unit() {
+        let now = at("2026-03-01T12:00:00Z");
+        assert_eq!(relative_time(at("2026-03-01T11:59:30Z"), now), "just now");
+        assert_eq!(
+            relative_time(at("2026-03-01T11:59:00Z"), now),
+            "1 minute ago"
+        );
+        assert_eq!(relative_time(at("2026-02-26T10:00:00Z"), now), "3 days ago");
+        assert_eq!(relative_time(at("2025-01-01T00:00:00Z"), now), "1 year ago");
+        assert_eq!(relative_time(at("2026-03-01T15:00:00Z"), now), "in 3 hours");
+        assert_eq!(
+            display_timestamp("2026-02-26T10:00:00+02:00", DisplayZone::Utc, now),
+            "2026-02-26 08:00 UTC (3 days ago)"
+        );
+        assert_eq!(
+            display_timestamp("yesterday", DisplayZone::Utc, now),
+            "yesterday"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Code lens feed: the regions of a file an editor shows provenance above,
+//! one per definition plus content-defined chunks for the code between
+//! definitions.
+
+use crate::anchor::chunk::{LineChunk, content_defined_chunks};
+use crate::anchor::symbol::definitions;
+use crate::query::narrative::truncate_chars;
+
+/// Longest definition header kept as a region's label.
+const MAX_LABEL_CHARS: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LensRegion {
+    pub span: LineChunk,
+    /// The definition's header line, trimmed; `None` for a chunk of code
+    /// outside every definition.
+    pub label: Option<String>,
+}
+
+/// Regions of `text` ordered by first line, enclosing regions before the
+/// ones nested in them. Every definition is a region; lines outside all
+/// definitions are split into content-defined chunks, without blank lines
+/// at their edges.
+pub fn lens_regions(text: &str) -> Vec<LensRegion> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut regions = Vec::new();
+    let mut gap_start = 1u32;
+    let mut covered_until = 0u32;
+    for span in definitions(text) {
+        if span.start > covered_until {
+            push_gap(&lines, gap_start, span.start - 1, &mut regions);
+            gap_start = span.end + 1;
+            covered_until = span.end;
+        }
+        let header = lines[span.start as usize - 1].trim();
+        regions.push(LensRegion {
+            span,
+            label: Some(truncate_chars(
+                header.trim_end_matches('{').trim_end(),
+                MAX_LABEL_CHARS,
+            )),
+        });
+    }
+    push_gap(&lines, gap_start, lines.len() as u32, &mut regions);
+    regions.sort_by_key(|region| (region.span.start, std::cmp::Reverse(region.span.end)));
+    regions
+}
+
+fn push_gap(lines: &[&str], start: u32, end: u32, regions: &mut Vec<LensRegion>) {
+    if start > end {
+        return;
+    }
+    let text = lines[start as usize - 1..end as usize].join("\n");
+    let blank = |line: u32| lines[line as usize - 1].trim().is_empty();
+    for chunk in content_defined_chunks(&text) {
+        let mut span = LineChunk {
+            start: chunk.start + start - 1,
+            end: chunk.end + start - 1,
+        };
+        while span.start <= span.end && blank(span.start) {
+            span.start += 1;
+        }
+        while span.end >= span.start && blank(span.end) {
+            span.end -= 1;
+        }
+        if span.start <= span.end {
+            regions.push(LensRegion { span, label: None });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_a_file_into_definitions_and_the_chunks_between_them() {
+        let rust = "\
+use std::fmt;
+use std::io;
+
+impl Widget {
+    pub fn new() -> Self {
+        Self { size: 1 }
+    }
+}
+
+const LIMIT: u32 = 4;
+";
+        let regions = lens_regions(rust)
+            .into_iter()
+            .map(|region| (region.span.start, region.span.end, region.label))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            regions,
+            vec![
+                (1, 2, None),
+                (4, 8, Some("impl Widget".to_string())),
+                (5, 7, Some("pub fn new() -> Self".to_string())),
+                (10, 10, None),
+            ]
+        );
+        assert!(lens_regions("").is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+//! A tape as Markdown (`engram show --format markdown`), for posting a
+//! session walkthrough to Slack or a wiki: messages as text under a
+//! speaker heading, each tool call and its result folded into a
+//! `<details>` block, and edits as `diff` fences.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use serde_json::Value;
+
+use crate::tape::patch::unified_hunks;
+
+/// Lines of tool output kept per stream; the rest is summarized.
+const MAX_OUTPUT_LINES: usize = 40;
+
+/// The old and new text of a `code.edit`, when either is recoverable.
+pub type EditTexts = (Option<String>, Option<String>);
+
+/// Render `rows` (tape offset, event) as Markdown. `edit_texts` recovers an
+/// edit's before/after text (from the event itself or retained objects).
+pub fn tape_markdown(
+    tape_id: &str,
+    rows: &[(u64, Value)],
+    edit_texts: impl Fn(&Value) -> EditTexts,
+) -> String {
+    let field =
+        |row: &Value, name: &str| row.get(name).and_then(Value::as_str).map(ToOwned::to_owned);
+    let kind = |row: &Value| field(row, "k").unwrap_or_default();
+    let meta = rows
+        .iter()
+        .map(|(_, row)| row)
+        .find(|row| kind(row) == "meta");
+    let results = rows
+        .iter()
+        .filter(|(_, row)| kind(row) == "tool.result")
+        .filter_map(|(offset, row)| Some((field(row, "call_id")?, (*offset, row))))
+        .collect::<HashMap<_, _>>();
+
+    let mut out = String::new();
+    let title = meta
+        .and_then(|meta| field(meta, "label"))
+        .unwrap_or_else(|| format!("Session {}", short_id(tape_id)));
+    let _ = writeln!(out, "# {}\n", inline(&title));
+    let mut about = vec![format!("tape `{tape_id}`")];
+    if let Some(harness) = meta.and_then(|meta| meta.pointer("/source/harness")?.as_str()) {
+        about.push(harness.to_string());
+    }
+    if let Some(model) = meta.and_then(|meta| field(meta, "model")) {
+        about.push(model);
+    }
+    if let Some(started) = rows.iter().find_map(|(_, row)| field(row, "t")) {
+        about.push(format!("started {started}"));
+    }
+    let _ = writeln!(out, "_{}_\n", about.join(" · "));
+
+    let mut shown_results = Vec::new();
+    for (offset, row) in rows {
+        let at = field(row, "t").unwrap_or_default();
+        match kind(row).as_str() {
+            "msg.in" | "msg.out" => {
+                let Some(text) = field(row, "text").filter(|text| !text.trim().is_empty()) else {
+                    continue;
+                };
+                let speaker = match (kind(row).as_str(), field(row, "author")) {
+                    (_, Some(author)) => author,
+                    ("msg.in", None) => "User".to_string(),
+                    _ => "Assistant".to_string(),
+                };
+                let _ = writeln!(out, "**{}** · {at}\n", inline(&speaker));
+                let _ = writeln!(out, "{}\n", text.trim_end());
+            }
+            "tool.call" => {
+                let tool = field(row, "tool").unwrap_or_else(|| "tool".to_string());
+                let result = field(row, "call_id").and_then(|id| results.get(&id));
+                let mut summary =
+                    format!("{} <code>{}</code>", html(&tool), html(&call_summary(row)));
+                if let Some(exit) = result.and_then(|(_, result)| result.get("exit")?.as_i64()) {
+                    let _ = write!(summary, " · exit {exit}");
+                }
+                let _ = writeln!(out, "<details><summary>{summary}</summary>\n");
+                out.push_str(&fence("json", &pretty_args(row)));
+                if let Some((result_offset, result)) = result {
+                    shown_results.push(*result_offset);
+                    out.push_str(&tool_output(result));
+                }
+                out.push_str("</details>\n\n");
+            }
+            "tool.result" if !shown_results.contains(offset) => {
+                let output = tool_output(row);
+                if output.is_empty() {
+                    continue;
+                }
+                let tool = field(row, "tool").unwrap_or_else(|| "tool".to_string());
+                let _ = writeln!(out, "<details><summary>{} result</summary>\n", html(&tool));
+                out.push_str(&output);
+                out.push_str("</details>\n\n");
+            }
+            "code.edit" => {
+                let file = field(row, "file").unwrap_or_default();
+                let (before, after) = edit_texts(row);
+                if before.is_none() && after.is_none() {
+                    let _ = writeln!(
+                        out,
+                        "_Edited `{file}`{} (text not recorded)_\n",
+                        range_suffix(row.get("after_range").or_else(|| row.get("before_range")))
+                    );
+                    continue;
+                }
+                let start = |key: &str| {
+                    row.get(key)
+                        .and_then(|range| range.get(0))
+                        .and_then(Value::as_u64)
+                        .map_or(1, |line| line as u32)
+                };
+                let hunks = unified_hunks(
+                    before.as_deref().unwrap_or(""),
+                    after.as_deref().unwrap_or(""),
+                    start("before_range"),
+                    start("after_range"),
+                );
+                let _ = writeln!(out, "Edited `{file}`:\n");
+                out.push_str(&fence(
+                    "diff",
+                    &format!("--- a/{file}\n+++ b/{file}\n{hunks}"),
+                ));
+                out.push('\n');
+            }
+            "code.read" => {
+                let file = field(row, "file").unwrap_or_default();
+                let _ = writeln!(out, "_Read `{file}`{}_\n", range_suffix(row.get("range")));
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// One line describing a call: its command, path or pattern when it has
+/// one, else the compact arguments.
+fn call_summary(row: &Value) -> String {
+    let args = parsed_args(row);
+    let text = [
+        "command",
+        "cmd",
+        "file_path",
+        "path",
+        "pattern",
+        "query",
+        "url",
+    ]
+    .iter()
+    .find_map(|key| match args.get(key)? {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        _ => None,
+    })
+    .unwrap_or_else(|| match &args {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    });
+    let line = text.lines().next().unwrap_or_default();
+    let mut summary = line.chars().take(80).collect::<String>();
+    if summary.len() < text.len() {
+        summary.push('…');
+    }
+    summary
+}
+
+fn parsed_args(row: &Value) -> Value {
+    match row.get("args") {
+        Some(Value::String(text)) => {
+            serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.clone()))
+        }
+        Some(other) => other.clone(),
+        None => Value::Null,
+    }
+}
+
+fn pretty_args(row: &Value) -> String {
+    match parsed_args(row) {
+        Value::String(text) => text,
+        other => serde_json::to_string_pretty(&other).unwrap_or_default(),
+    }
+}
+
+/// The result's output streams as fences, each capped at
+/// [`MAX_OUTPUT_LINES`].
+fn tool_output(result: &Value) -> String {
+    let mut out = String::new();
+    for name in ["stdout", "stderr", "output", "text"] {
+        let Some(text) = result.get(name).and_then(Value::as_str) else {
+            continue;
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+        let lines = text.lines().collect::<Vec<_>>();
+        let mut shown = lines
+            .iter()
+            .take(MAX_OUTPUT_LINES)
+            .copied()
+            .collect::<Vec<_>>()
+            .join("\n");
+        if lines.len() > MAX_OUTPUT_LINES {
+            let _ = write!(shown, "\n… {} more lines", lines.len() - MAX_OUTPUT_LINES);
+        }
+        let _ = writeln!(out, "{name}:\n");
+        out.push_str(&fence("", &shown));
+    }
+    out
+}
+
+/// A fenced block long enough not to be closed by backticks in `text`.
+fn fence(lang: &str, text: &str) -> String {
+    let longest = text.split(|ch| ch != '`').map(str::len).max().unwrap_or(0);
+    let ticks = "`".repeat(longest.max(2) + 1);
+    format!("{ticks}{lang}\n{}\n{ticks}\n", text.trim_end_matches('\n'))
+}
+
+fn range_suffix(range: Option<&Value>) -> String {
+    let line = |at: usize| {
+        range
+            .and_then(|range| range.get(at))
+            .and_then(Value::as_u64)
+    };
+    match (line(0), line(1)) {
+        (Some(start), Some(end)) if start != end => format!(" lines {start}-{end}"),
+        (Some(start), _) => format!(" line {start}"),
+        _ => String::new(),
+    }
+}
+
+/// Text safe inside a `<summary>` element.
+fn html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Text safe in a one-line Markdown heading.
+fn inline(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn short_id(tape_id: &str) -> &str {
+    &tape_id[..tape_id.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn folds_tool_calls_and_renders_edits_as_diffs() {
+        let rows = [
+            json!({"t": "2026-03-01T10:00:00Z", "k": "meta", "source": {"harness": "claude-code"}}),
+            json!({"t": "2026-03-01T10:00:01Z", "k": "msg.in", "text": "bump the retry count"}),
+            json!({"t": "2026-03-01T10:00:02Z", "k": "tool.call", "tool": "Bash", "call_id": "c1",
+                   "args": "{\"command\":\"grep -n retries src/lib.rs\"}"}),
+            json!({"t": "2026-03-01T10:00:03Z", "k": "tool.result", "tool": "Bash", "call_id": "c1",
+                   "exit": 0, "stdout": "2: let retries = 3; // ```"}),
+            json!({"t": "2026-03-01T10:00:04Z", "k": "code.edit", "file": "src/lib.rs",
+                   "before_range": [2, 2], "after_range": [2, 2],
+                   "before_text": "let retries = 3;", "after_text": "let retries = 5;"}),
+            json!({"t": "2026-03-01T10:00:05Z", "k": "code.edit", "file": "src/main.rs",
+                   "after_range": [4, 6]}),
+            json!({"t": "2026-03-01T10:00:06Z", "k": "msg.out", "text": "Done."}),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(offset, row)| (offset as u64, row))
+        .collect::<Vec<_>>();
+        let texts = |row: &Value| {
+            let side = |key: &str| row.get(key).and_then(Value::as_str).map(str::to_owned);
+            (side("before_text"), side("after_text"))
+        };
+
+        let markdown = tape_markdown("0123456789abcdef", &rows, texts);
+        assert!(markdown.starts_with("# Session 0123456789ab\n\n_tape `0123456789abcdef` · claude-code · started 2026-03-01T10:00:00Z_\n"));
+        assert!(markdown.contains("**User** · 2026-03-01T10:00:01Z\n\nbump the retry count\n"));
+        assert!(markdown.contains(
+            "<details><summary>Bash <code>grep -n retries src/lib.rs</code> · exit 0</summary>"
+        ));
+        assert!(markdown.contains("````\n2: let retries = 3; // ```\n````\n"));
+        assert!(markdown.contains(
+            "```diff\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -2 +2 @@\n-let retries = 3;\n+let retries = 5;\n```"
+        ));
+        assert!(markdown.contains("_Edited `src/main.rs` lines 4-6 (text not recorded)_"));
+        assert!(markdown.contains("**Assistant** · 2026-03-01T10:00:06Z\n\nDone.\n"));
+        assert_eq!(markdown.matches("<details>").count(), 1);
+    }
+}
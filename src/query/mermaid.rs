@@ -0,0 +1,170 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write as _;
+
+use crate::index::EdgeRow;
+
+/// A session to draw next to the lineage chain, with the anchors it has
+/// evidence on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MermaidSession {
+    pub session_id: String,
+    pub confidence: f64,
+    pub anchors: BTreeSet<String>,
+}
+
+/// A Mermaid flowchart of an explain result: the queried span as one node,
+/// lineage edges from older anchors toward it labeled with their confidence,
+/// and each session as a rounded node joined by dotted lines to the anchors
+/// it touched.
+pub fn lineage_flowchart(
+    target: &str,
+    query_anchors: &[String],
+    lineage: &[EdgeRow],
+    sessions: &[MermaidSession],
+) -> String {
+    let queried = query_anchors
+        .iter()
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+    let mut ids = HashMap::<&str, String>::new();
+    let mut out = String::from("flowchart LR\n");
+    let _ = writeln!(out, "    target[\"{}\"]", label(target));
+    let mut drawn = HashSet::new();
+    for edge in lineage {
+        let from = node_id(&edge.from_anchor, &queried, &mut ids, &mut out);
+        let to = node_id(&edge.to_anchor, &queried, &mut ids, &mut out);
+        if from == to || !drawn.insert((from.clone(), to.clone())) {
+            continue;
+        }
+        let mut text = format!("{:.2}", edge.confidence);
+        if edge.agent_link {
+            text.push_str(" agent link");
+        }
+        if edge.reverted {
+            text.push_str(" revert");
+        }
+        let _ = writeln!(out, "    {from} -- \"{}\" --> {to}", label(&text));
+    }
+
+    for (at, session) in sessions.iter().enumerate() {
+        let id = format!("s{at}");
+        let _ = writeln!(
+            out,
+            "    {id}([\"{} · {:.2}\"])",
+            label(&short_session(&session.session_id)),
+            session.confidence
+        );
+        let mut joined = BTreeSet::new();
+        for anchor in &session.anchors {
+            let target = if queried.contains(anchor.as_str()) {
+                Some("target".to_string())
+            } else {
+                ids.get(anchor.as_str()).cloned()
+            };
+            if let Some(target) = target
+                && joined.insert(target.clone())
+            {
+                let _ = writeln!(out, "    {id} -.-> {target}");
+            }
+        }
+    }
+    out
+}
+
+/// The node drawn for `anchor`: every queried anchor is the target node,
+/// other anchors get a node the first time they appear.
+fn node_id<'a>(
+    anchor: &'a str,
+    queried: &HashSet<&str>,
+    ids: &mut HashMap<&'a str, String>,
+    out: &mut String,
+) -> String {
+    if queried.contains(anchor) {
+        return "target".to_string();
+    }
+    if let Some(id) = ids.get(anchor) {
+        return id.clone();
+    }
+    let id = format!("a{}", ids.len());
+    let _ = writeln!(out, "    {id}[\"{}\"]", label(&short_anchor(anchor)));
+    ids.insert(anchor, id.clone());
+    id
+}
+
+/// Mermaid labels are quoted; quotes inside become `#quot;`.
+fn label(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+fn short_anchor(anchor: &str) -> String {
+    match anchor.split_once(':') {
+        Some((kind, hash)) if hash.chars().count() > 8 => {
+            let tail = hash
+                .chars()
+                .skip(hash.chars().count() - 8)
+                .collect::<String>();
+            format!("{kind}:…{tail}")
+        }
+        _ => anchor.to_string(),
+    }
+}
+
+fn short_session(session_id: &str) -> String {
+    session_id.chars().take(12).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::lineage::{Cardinality, LocationDelta, StoredEdgeClass};
+
+    fn edge(from: &str, to: &str, confidence: f32) -> EdgeRow {
+        EdgeRow {
+            from_anchor: from.to_string(),
+            to_anchor: to.to_string(),
+            confidence,
+            location_delta: LocationDelta::Same,
+            cardinality: Cardinality::OneToOne,
+            agent_link: false,
+            note: None,
+            stored_class: StoredEdgeClass::Lineage,
+            reverted: false,
+            scorer: None,
+        }
+    }
+
+    #[test]
+    fn flowchart_collapses_query_anchors_and_links_sessions() {
+        let query = vec![
+            "winnow:00000000000000c1".to_string(),
+            "winnow:00000000000000c2".to_string(),
+        ];
+        let lineage = vec![
+            edge("winnow:00000000000000b1", "winnow:00000000000000c1", 0.9),
+            edge("winnow:00000000000000b1", "winnow:00000000000000c2", 0.8),
+            edge("winnow:00000000000000a1", "winnow:00000000000000b1", 0.6),
+        ];
+        let sessions = vec![MermaidSession {
+            session_id: "0123456789abcdef".to_string(),
+            confidence: 0.75,
+            anchors: BTreeSet::from([
+                "winnow:00000000000000a1".to_string(),
+                "winnow:00000000000000c2".to_string(),
+            ]),
+        }];
+
+        let chart = lineage_flowchart("src/\"a\".rs:1-4", &query, &lineage, &sessions);
+        assert_eq!(
+            chart,
+            "flowchart LR\n\
+             \x20   target[\"src/#quot;a#quot;.rs:1-4\"]\n\
+             \x20   a0[\"winnow:…000000b1\"]\n\
+             \x20   a0 -- \"0.90\" --> target\n\
+             \x20   a1[\"winnow:…000000a1\"]\n\
+             \x20   a1 -- \"0.60\" --> a0\n\
+             \x20   s0([\"0123456789ab · 0.75\"])\n\
+             \x20   s0 -.-> a1\n\
+             \x20   s0 -.-> target\n"
+        );
+    }
+}
@@ -1,6 +1,21 @@
+pub mod cypher;
+pub mod drift;
+pub mod episodes;
 pub mod explain;
+pub mod graph;
+pub mod humanize;
+pub mod lens;
+pub mod markdown;
+pub mod mermaid;
+pub mod narrative;
+pub mod porcelain;
+pub mod pseudonym;
 pub mod rank;
+pub mod session_graph;
+pub mod snapshot;
 
 pub use explain::{
-    ExplainResult, ExplainTraversal, explain_by_anchor, retrieve_direct, retrieve_lineage,
+    ExplainResult, ExplainTraversal, LineageStep, LineageWalker, explain_by_anchor,
+    retrieve_direct, retrieve_lineage,
 };
+pub use graph::{LineageGraph, MemoryGraph};
@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+#[cfg(feature = "native")]
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+#[cfg(feature = "native")]
+use crate::config::EffectiveSummarizeConfig;
+
+/// Longest excerpt text sent for a single tape event.
+const MAX_EXCERPT_CHARS: usize = 400;
+/// Upper bound on excerpt text sent per request, across all sessions.
+const MAX_PROMPT_EXCERPT_CHARS: usize = 24_000;
+
+const SYSTEM_PROMPT: &str = "You explain why code looks the way it does, using only the \
+agent session excerpts provided. Write a short narrative (a few paragraphs) of the decisions \
+that produced the code, in order. Every claim must cite the excerpt it comes from as \
+[<tape_id>:<offset>], copied exactly from the excerpt headers. If the excerpts do not explain \
+something, say so instead of guessing.";
+
+/// One tape event offered to the model as evidence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NarrativeExcerpt {
+    pub tape_id: String,
+    pub offset: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NarrativeCitation {
+    pub tape_id: String,
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Narrative {
+    pub model: String,
+    pub text: String,
+    /// Citations in order of first mention, limited to excerpts that were
+    /// actually sent; anything else the model cites is dropped.
+    pub citations: Vec<NarrativeCitation>,
+}
+
+#[derive(Debug)]
+pub enum NarrativeError {
+    MissingApiKey(String),
+    Http(String),
+    Response(String),
+}
+
+impl std::fmt::Display for NarrativeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingApiKey(var) => write!(f, "environment variable `{var}` is not set"),
+            Self::Http(message) => write!(f, "summarize request failed: {message}"),
+            Self::Response(message) => write!(f, "unexpected summarize response: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for NarrativeError {}
+
+/// Condensed single-line text for a normalized tape row, or `None` for rows
+/// that carry nothing worth narrating.
+pub fn excerpt_text(row: &Value) -> Option<String> {
+    let kind = row.get("k").and_then(Value::as_str)?;
+    let field = |name: &str| row.get(name).and_then(Value::as_str).unwrap_or_default();
+    let text = match kind {
+        "msg.in" => format!("user: {}", field("text")),
+        "msg.out" => format!("assistant: {}", field("text")),
+        "tool.call" => format!("tool call {}: {}", field("tool"), field("args")),
+        "tool.result" => format!("tool result {}: {}", field("tool"), field("stdout")),
+        "code.edit" => format!("edit {}: {}", field("file"), field("after_text")),
+        "code.read" => format!("read {}", field("file")),
+        _ => return None,
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(truncate_chars(&text, MAX_EXCERPT_CHARS))
+}
+
+/// Chat messages for the narrative request. Excerpts are included in order
+/// until the prompt budget is spent.
+pub fn narrative_messages(target: &str, excerpts: &[NarrativeExcerpt]) -> Vec<Value> {
+    let mut body = format!("Code in question: {target}\n\nSession excerpts:\n");
+    let mut any = false;
+    for excerpt in sent_excerpts(excerpts) {
+        any = true;
+        body.push_str(&format!(
+            "[{}:{}] {}\n",
+            excerpt.tape_id, excerpt.offset, excerpt.text
+        ));
+    }
+    if !any {
+        body.push_str("(none)\n");
+    }
+    vec![
+        json!({"role": "system", "content": SYSTEM_PROMPT}),
+        json!({"role": "user", "content": body}),
+    ]
+}
+
+fn sent_excerpts(excerpts: &[NarrativeExcerpt]) -> impl Iterator<Item = &NarrativeExcerpt> {
+    let mut budget = MAX_PROMPT_EXCERPT_CHARS;
+    excerpts.iter().take_while(move |excerpt| {
+        let fits = excerpt.text.len() <= budget;
+        budget = budget.saturating_sub(excerpt.text.len());
+        fits
+    })
+}
+
+/// Ask the configured OpenAI-compatible endpoint (`POST {base_url}/chat/completions`)
+/// for a narrative over `excerpts`.
+#[cfg(feature = "native")]
+pub fn request_narrative(
+    config: &EffectiveSummarizeConfig,
+    target: &str,
+    excerpts: &[NarrativeExcerpt],
+) -> Result<Narrative, NarrativeError> {
+    let api_key = match config.api_key_env.as_deref() {
+        Some(var) => {
+            Some(std::env::var(var).map_err(|_| NarrativeError::MissingApiKey(var.to_string()))?)
+        }
+        None => None,
+    };
+    let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .build();
+    let mut request = agent.post(&url);
+    if let Some(key) = api_key {
+        request = request.set("Authorization", &format!("Bearer {key}"));
+    }
+    let response: Value = request
+        .send_json(json!({
+            "model": config.model,
+            "messages": narrative_messages(target, excerpts),
+            "temperature": 0,
+        }))
+        .map_err(|err| NarrativeError::Http(err.to_string()))?
+        .into_json()
+        .map_err(|err| NarrativeError::Response(err.to_string()))?;
+    let text = response
+        .pointer("/choices/0/message/content")
+        .and_then(Value::as_str)
+        .ok_or_else(|| NarrativeError::Response("missing choices[0].message.content".into()))?
+        .trim()
+        .to_string();
+    let model = response
+        .get("model")
+        .and_then(Value::as_str)
+        .unwrap_or(&config.model)
+        .to_string();
+    let citations = parse_citations(&text, excerpts);
+    Ok(Narrative {
+        model,
+        text,
+        citations,
+    })
+}
+
+/// `[tape_id:offset]` references in `text` that name one of the sent excerpts.
+pub fn parse_citations(text: &str, excerpts: &[NarrativeExcerpt]) -> Vec<NarrativeCitation> {
+    let known = sent_excerpts(excerpts)
+        .map(|excerpt| (excerpt.tape_id.as_str(), excerpt.offset))
+        .collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for chunk in text.split('[').skip(1) {
+        let Some((inner, _)) = chunk.split_once(']') else {
+            continue;
+        };
+        for reference in inner.split([',', ';']) {
+            let Some((tape_id, offset)) = reference.trim().rsplit_once(':') else {
+                continue;
+            };
+            let Ok(offset) = offset.trim().parse::<u64>() else {
+                continue;
+            };
+            let tape_id = tape_id.trim();
+            if known.contains(&(tape_id, offset)) && seen.insert((tape_id.to_string(), offset)) {
+                out.push(NarrativeCitation {
+                    tape_id: tape_id.to_string(),
+                    offset,
+                });
+            }
+        }
+    }
+    out
+}
+
+/// `text` cut to `max` characters, with an ellipsis when anything was cut.
+pub fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn excerpt(tape_id: &str, offset: u64, text: &str) -> NarrativeExcerpt {
+        NarrativeExcerpt {
+            tape_id: tape_id.to_string(),
+            offset,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn messages_label_excerpts_with_citation_keys() {
+        let excerpts = vec![
+            excerpt("t1", 3, "user: switch to tokio"),
+            excerpt("t2", 0, "edit src/main.rs: #[tokio::main]"),
+        ];
+        let messages = narrative_messages("src/main.rs:1-4", &excerpts);
+        let user = messages[1]["content"].as_str().expect("user content");
+        assert!(user.starts_with("Code in question: src/main.rs:1-4"));
+        assert!(user.contains("[t1:3] user: switch to tokio\n"));
+        assert!(user.contains("[t2:0] edit src/main.rs: #[tokio::main]\n"));
+    }
+
+    #[test]
+    fn citations_keep_only_sent_excerpts_in_mention_order() {
+        let excerpts = vec![excerpt("t1", 3, "a"), excerpt("t2", 0, "b")];
+        let text = "We moved to tokio [t2:0] after a request [t1:3; t1:9]. Again [t2:0] [note].";
+        assert_eq!(
+            parse_citations(text, &excerpts),
+            vec![
+                NarrativeCitation {
+                    tape_id: "t2".into(),
+                    offset: 0
+                },
+                NarrativeCitation {
+                    tape_id: "t1".into(),
+                    offset: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn excerpt_text_condenses_rows() {
+        let row = json!({"k": "msg.in", "text": "why   is\nthis here"});
+        assert_eq!(
+            excerpt_text(&row).as_deref(),
+            Some("user: why is this here")
+        );
+        assert_eq!(excerpt_text(&json!({"k": "meta"})), None);
+        let long = json!({"k": "msg.out", "text": "x".repeat(1000)});
+        assert_eq!(
+            excerpt_text(&long).expect("text").chars().count(),
+            MAX_EXCERPT_CHARS + 1
+        );
+    }
+}
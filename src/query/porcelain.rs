@@ -0,0 +1,48 @@
+//! `--porcelain` output for editor plugins: one tab-separated record per
+//! line, led by the record type. The fields of a porcelain version never
+//! change meaning or position; new fields only appear under a new version.
+
+/// Versions of the porcelain format a caller can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PorcelainVersion {
+    #[default]
+    V1,
+}
+
+/// `fields` joined by tabs. A missing value is an empty field; backslash,
+/// tab, carriage return and newline inside a field are written as `\\`,
+/// `\t`, `\r` and `\n`, so a record is always exactly one line.
+pub fn porcelain_line(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|field| escape_field(field))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+fn escape_field(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    for ch in field.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_separators_inside_fields() {
+        assert_eq!(
+            porcelain_line(&["why", "src/a b.rs", "", "tab\there", "two\nlines", "c:\\x"]),
+            "why\tsrc/a b.rs\t\ttab\\there\ttwo\\nlines\tc:\\\\x"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Pseudonyms for shared exports (`export.pseudonymize`): tape ids, harness
+//! session ids, host names and user names are replaced with keyed HMAC
+//! digests. The same key maps a value to the same pseudonym in every export,
+//! so exports from many machines still join up for org-wide analytics, while
+//! nobody without the key can tell who ran which session.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::index::lineage::EvidenceFragmentRef;
+use crate::tape::metrics::TapeMetrics;
+
+/// Hex digits of the HMAC kept in a pseudonym (64 bits).
+const PSEUDONYM_HEX_DIGITS: usize = 16;
+
+pub struct Pseudonymizer {
+    key: Vec<u8>,
+}
+
+impl Pseudonymizer {
+    pub fn new(key: &[u8]) -> Self {
+        Self { key: key.to_vec() }
+    }
+
+    /// `<kind>-<hex>`: the first 64 bits of HMAC-SHA256 over `kind:value`.
+    /// The kind keeps equal strings in different roles (a host and a user
+    /// both called `build`) from sharing a pseudonym.
+    pub fn pseudonym(&self, kind: &str, value: &str) -> String {
+        let digest = hmac_sha256(&self.key, format!("{kind}:{value}").as_bytes());
+        let hex = digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        format!("{kind}-{}", &hex[..PSEUDONYM_HEX_DIGITS])
+    }
+
+    pub fn tape_id(&self, tape_id: &str) -> String {
+        self.pseudonym("tape", tape_id)
+    }
+
+    pub fn fragment(&self, fragment: &mut EvidenceFragmentRef) {
+        fragment.tape_id = self.tape_id(&fragment.tape_id);
+    }
+
+    /// Replace the identifying fields of `metrics`; counts, times, model and
+    /// harness stay as they are.
+    pub fn metrics(&self, metrics: &mut TapeMetrics) {
+        for (kind, slot) in [
+            ("session", &mut metrics.session_id),
+            ("host", &mut metrics.hostname),
+            ("user", &mut metrics.user),
+        ] {
+            if let Some(value) = slot.as_mut() {
+                *value = self.pseudonym(kind, value);
+            }
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pseudonyms_are_keyed_stable_hmacs() {
+        let hex = |digest: [u8; 32]| {
+            digest
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        };
+        // RFC 4231, test cases 2 and 6 (a key longer than the block size).
+        assert_eq!(
+            hex(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex(hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+
+        let ours = Pseudonymizer::new(b"org secret");
+        let theirs = Pseudonymizer::new(b"other secret");
+        assert_eq!(ours.pseudonym("user", "ada"), ours.pseudonym("user", "ada"));
+        assert_ne!(ours.pseudonym("user", "ada"), ours.pseudonym("host", "ada"));
+        assert_ne!(
+            ours.pseudonym("user", "ada"),
+            theirs.pseudonym("user", "ada")
+        );
+        assert!(ours.tape_id("abc").starts_with("tape-"));
+        assert_eq!(
+            ours.tape_id("abc").len(),
+            "tape-".len() + PSEUDONYM_HEX_DIGITS
+        );
+
+        let mut metrics = TapeMetrics {
+            model: Some("gpt-5".to_string()),
+            session_id: Some("s-1".to_string()),
+            hostname: Some("laptop".to_string()),
+            ..TapeMetrics::default()
+        };
+        ours.metrics(&mut metrics);
+        assert_eq!(metrics.model.as_deref(), Some("gpt-5"));
+        assert_eq!(metrics.session_id, Some(ours.pseudonym("session", "s-1")));
+        assert_eq!(metrics.hostname, Some(ours.pseudonym("host", "laptop")));
+        assert_eq!(metrics.user, None);
+    }
+}
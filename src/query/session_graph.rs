@@ -0,0 +1,397 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::tape::rotation::uncompressed_path;
+
+/// A tape drawn in the session graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionNode {
+    pub tape_id: String,
+    pub harness: Option<String>,
+    /// The harness's session id, from the tape meta.
+    pub session_id: Option<String>,
+    pub started_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SessionLinkKind {
+    /// The next tape of the same session: a later ingest of the same
+    /// harness file, or the next part of a `record --session`.
+    Continues,
+    /// A subagent transcript of the session.
+    Subagent,
+    /// A dispatch marker sent by one tape and received by the other.
+    Dispatch,
+    /// A command in one tape started the other (see [`crate::tape::spawn`]).
+    Spawned,
+    /// Both tapes edited the same files; undirected.
+    SharedFiles,
+}
+
+impl SessionLinkKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Continues => "continues",
+            Self::Subagent => "subagent",
+            Self::Dispatch => "dispatch",
+            Self::Spawned => "spawned",
+            Self::SharedFiles => "shared_files",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SessionLink {
+    pub from: String,
+    pub to: String,
+    pub kind: SessionLinkKind,
+    /// The spawning command, or the number of shared files.
+    pub label: Option<String>,
+}
+
+/// A tape converted from a harness file, with where in the file it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourcedTape {
+    pub tape_id: String,
+    pub source_path: String,
+    pub source_start: u64,
+}
+
+/// Link each tape of a session to the next. `chains` holds every session's
+/// tapes in order.
+pub fn continuation_links(chains: &[Vec<String>]) -> Vec<SessionLink> {
+    chains
+        .iter()
+        .flat_map(|chain| chain.windows(2))
+        .map(|pair| SessionLink {
+            from: pair[0].clone(),
+            to: pair[1].clone(),
+            kind: SessionLinkKind::Continues,
+            label: None,
+        })
+        .collect()
+}
+
+/// Tapes that share a harness and harness session id, oldest first: one
+/// chain per session.
+pub fn session_id_chains(nodes: &[SessionNode]) -> Vec<Vec<String>> {
+    let mut by_session = BTreeMap::<(&str, &str), Vec<&SessionNode>>::new();
+    for node in nodes {
+        if let Some(session_id) = node.session_id.as_deref() {
+            by_session
+                .entry((node.harness.as_deref().unwrap_or_default(), session_id))
+                .or_default()
+                .push(node);
+        }
+    }
+    by_session
+        .into_values()
+        .map(|mut nodes| {
+            nodes.sort_by(|a, b| {
+                a.started_at
+                    .cmp(&b.started_at)
+                    .then_with(|| a.tape_id.cmp(&b.tape_id))
+            });
+            nodes.into_iter().map(|node| node.tape_id.clone()).collect()
+        })
+        .collect()
+}
+
+/// The tapes of each harness file in file order: one chain per session.
+/// A rotated `session.jsonl.gz` is the same file as `session.jsonl`.
+pub fn source_chains(tapes: &[SourcedTape]) -> Vec<Vec<String>> {
+    let mut by_source = BTreeMap::<PathBuf, Vec<&SourcedTape>>::new();
+    for tape in tapes {
+        by_source
+            .entry(uncompressed_path(Path::new(&tape.source_path)))
+            .or_default()
+            .push(tape);
+    }
+    by_source
+        .into_values()
+        .map(|mut tapes| {
+            tapes.sort_by(|a, b| {
+                a.source_start
+                    .cmp(&b.source_start)
+                    .then_with(|| a.tape_id.cmp(&b.tape_id))
+            });
+            tapes.into_iter().map(|tape| tape.tape_id.clone()).collect()
+        })
+        .collect()
+}
+
+/// Link a session to each subagent transcript stored beside it: Claude Code
+/// keeps `<session>/subagents/agent-*.jsonl` next to `<session>.jsonl`. The
+/// link joins the first tape of each file.
+pub fn subagent_links(tapes: &[SourcedTape]) -> Vec<SessionLink> {
+    let chains = source_chains(tapes);
+    let first_by_stem = chains
+        .iter()
+        .filter_map(|chain| chain.first())
+        .filter_map(|tape_id| {
+            let tape = tapes.iter().find(|tape| &tape.tape_id == tape_id)?;
+            Some((transcript_stem(&tape.source_path), tape_id.clone()))
+        })
+        .collect::<HashMap<_, _>>();
+    let mut out = Vec::new();
+    for (stem, tape_id) in &first_by_stem {
+        let Some(dir) = stem.parent() else {
+            continue;
+        };
+        if dir.file_name().and_then(|name| name.to_str()) != Some("subagents") {
+            continue;
+        }
+        if let Some(parent) = dir.parent().and_then(|session| first_by_stem.get(session)) {
+            out.push(SessionLink {
+                from: parent.clone(),
+                to: tape_id.clone(),
+                kind: SessionLinkKind::Subagent,
+                label: None,
+            });
+        }
+    }
+    out.sort();
+    out
+}
+
+/// Link every two tapes that edited at least `min_shared` of the same
+/// files, labeled with how many.
+pub fn shared_file_links(
+    files_by_tape: &BTreeMap<String, BTreeSet<String>>,
+    min_shared: usize,
+) -> Vec<SessionLink> {
+    let tapes = files_by_tape.iter().collect::<Vec<_>>();
+    let mut out = Vec::new();
+    for (at, (from, from_files)) in tapes.iter().enumerate() {
+        for (to, to_files) in &tapes[at + 1..] {
+            let shared = from_files.intersection(to_files).count();
+            if shared > 0 && shared >= min_shared {
+                out.push(SessionLink {
+                    from: (*from).clone(),
+                    to: (*to).clone(),
+                    kind: SessionLinkKind::SharedFiles,
+                    label: Some(format!(
+                        "{shared} file{}",
+                        if shared == 1 { "" } else { "s" }
+                    )),
+                });
+            }
+        }
+    }
+    out
+}
+
+/// The session graph as Graphviz DOT: one box per tape, solid arrows for
+/// continuations, subagents, dispatches and spawns, dashed undirected lines
+/// for shared files.
+pub fn session_graph_dot(nodes: &[SessionNode], links: &[SessionLink]) -> String {
+    let mut out = String::from("digraph sessions {\n    rankdir=LR;\n    node [shape=box];\n");
+    for node in nodes {
+        let _ = writeln!(
+            out,
+            "    \"{}\" [label=\"{}\"];",
+            dot_escape(&node.tape_id),
+            dot_escape(&node_label(node, "\n"))
+        );
+    }
+    for link in links {
+        let style = match link.kind {
+            SessionLinkKind::SharedFiles => ", style=dashed, dir=none",
+            SessionLinkKind::Spawned => ", style=bold",
+            _ => "",
+        };
+        let _ = writeln!(
+            out,
+            "    \"{}\" -> \"{}\" [label=\"{}\"{style}];",
+            dot_escape(&link.from),
+            dot_escape(&link.to),
+            dot_escape(&link_label(link))
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// The session graph as a Mermaid flowchart, with the same conventions as
+/// [`session_graph_dot`].
+pub fn session_graph_mermaid(nodes: &[SessionNode], links: &[SessionLink]) -> String {
+    let mut ids = HashMap::<&str, String>::new();
+    let mut out = String::from("flowchart LR\n");
+    for node in nodes {
+        let id = format!("t{}", ids.len());
+        let _ = writeln!(
+            out,
+            "    {id}[\"{}\"]",
+            mermaid_escape(&node_label(node, "<br/>"))
+        );
+        ids.insert(&node.tape_id, id);
+    }
+    for link in links {
+        let (Some(from), Some(to)) = (ids.get(link.from.as_str()), ids.get(link.to.as_str()))
+        else {
+            continue;
+        };
+        let label = mermaid_escape(&link_label(link));
+        match link.kind {
+            SessionLinkKind::SharedFiles => {
+                let _ = writeln!(out, "    {from} -.-|\"{label}\"| {to}");
+            }
+            SessionLinkKind::Spawned => {
+                let _ = writeln!(out, "    {from} == \"{label}\" ==> {to}");
+            }
+            _ => {
+                let _ = writeln!(out, "    {from} -- \"{label}\" --> {to}");
+            }
+        }
+    }
+    out
+}
+
+/// `path` without `.gz` and its transcript extension.
+fn transcript_stem(path: &str) -> PathBuf {
+    uncompressed_path(Path::new(path)).with_extension("")
+}
+
+fn node_label(node: &SessionNode, separator: &str) -> String {
+    let mut label = node.tape_id.chars().take(12).collect::<String>();
+    for part in [&node.harness, &node.started_at].into_iter().flatten() {
+        label.push_str(separator);
+        label.push_str(part);
+    }
+    label
+}
+
+/// Spawn commands are cut to keep the drawing readable.
+fn link_label(link: &SessionLink) -> String {
+    match (&link.label, link.kind) {
+        (Some(command), SessionLinkKind::Spawned) => {
+            let mut text = command.chars().take(40).collect::<String>();
+            if command.chars().count() > 40 {
+                text.push('…');
+            }
+            format!("spawned: {text}")
+        }
+        (Some(label), _) => label.clone(),
+        (None, kind) => kind.as_str().to_string(),
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sourced(tape_id: &str, source_path: &str, source_start: u64) -> SourcedTape {
+        SourcedTape {
+            tape_id: tape_id.to_string(),
+            source_path: source_path.to_string(),
+            source_start,
+        }
+    }
+
+    #[test]
+    fn links_sessions_subagents_and_shared_files() {
+        let tapes = vec![
+            sourced("late", "/p/s1.jsonl", 400),
+            sourced("early", "/p/s1.jsonl", 0),
+            sourced("agent", "/p/s1/subagents/agent-a.jsonl.gz", 0),
+            sourced("other", "/p/s2.jsonl", 0),
+        ];
+        assert_eq!(
+            continuation_links(&source_chains(&tapes)),
+            vec![SessionLink {
+                from: "early".to_string(),
+                to: "late".to_string(),
+                kind: SessionLinkKind::Continues,
+                label: None,
+            }]
+        );
+        assert_eq!(
+            subagent_links(&tapes),
+            vec![SessionLink {
+                from: "early".to_string(),
+                to: "agent".to_string(),
+                kind: SessionLinkKind::Subagent,
+                label: None,
+            }]
+        );
+
+        let files = BTreeMap::from([
+            (
+                "a".to_string(),
+                BTreeSet::from(["x.rs".to_string(), "y.rs".to_string()]),
+            ),
+            (
+                "b".to_string(),
+                BTreeSet::from(["x.rs".to_string(), "y.rs".to_string()]),
+            ),
+            ("c".to_string(), BTreeSet::from(["x.rs".to_string()])),
+        ]);
+        let shared = shared_file_links(&files, 2);
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].label.as_deref(), Some("2 files"));
+        assert_eq!(shared_file_links(&files, 1).len(), 3);
+    }
+
+    #[test]
+    fn renders_dot_and_mermaid() {
+        let nodes = vec![
+            SessionNode {
+                tape_id: "0123456789abcdef".to_string(),
+                harness: Some("claude-code".to_string()),
+                session_id: None,
+                started_at: Some("2026-03-01T10:00:00Z".to_string()),
+            },
+            SessionNode {
+                tape_id: "fedcba9876543210".to_string(),
+                harness: None,
+                session_id: None,
+                started_at: None,
+            },
+        ];
+        let links = vec![
+            SessionLink {
+                from: nodes[0].tape_id.clone(),
+                to: nodes[1].tape_id.clone(),
+                kind: SessionLinkKind::Spawned,
+                label: Some("codex exec \"fix\"".to_string()),
+            },
+            SessionLink {
+                from: nodes[0].tape_id.clone(),
+                to: nodes[1].tape_id.clone(),
+                kind: SessionLinkKind::SharedFiles,
+                label: Some("1 file".to_string()),
+            },
+        ];
+
+        assert_eq!(
+            session_graph_dot(&nodes, &links),
+            "digraph sessions {\n\
+             \x20   rankdir=LR;\n\
+             \x20   node [shape=box];\n\
+             \x20   \"0123456789abcdef\" [label=\"0123456789ab\\nclaude-code\\n2026-03-01T10:00:00Z\"];\n\
+             \x20   \"fedcba9876543210\" [label=\"fedcba987654\"];\n\
+             \x20   \"0123456789abcdef\" -> \"fedcba9876543210\" [label=\"spawned: codex exec \\\"fix\\\"\", style=bold];\n\
+             \x20   \"0123456789abcdef\" -> \"fedcba9876543210\" [label=\"1 file\", style=dashed, dir=none];\n\
+             }\n"
+        );
+        assert_eq!(
+            session_graph_mermaid(&nodes, &links),
+            "flowchart LR\n\
+             \x20   t0[\"0123456789ab<br/>claude-code<br/>2026-03-01T10:00:00Z\"]\n\
+             \x20   t1[\"fedcba987654\"]\n\
+             \x20   t0 == \"spawned: codex exec #quot;fix#quot;\" ==> t1\n\
+             \x20   t0 -.-|\"1 file\"| t1\n"
+        );
+    }
+}
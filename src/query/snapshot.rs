@@ -0,0 +1,324 @@
+//! Graph snapshots: the anchors, edges and evidence references of an index
+//! in one compact file (`engram export --graph`), loadable into a
+//! [`MemoryGraph`] without SQLite.
+//!
+//! Layout, all integers little-endian:
+//!
+//! ```text
+//! magic      b"ENGRAMG\0"
+//! version    u32                     (GRAPH_SNAPSHOT_VERSION)
+//! strings    u32 count, then count × (u32 byte length, UTF-8 bytes)
+//! evidence   u32 count, then count × (
+//!                u32 anchor, u32 tape_id, u64 event_offset, u8 kind,
+//!                u32 file_path, u32 timestamp)
+//! edges      u32 count, then count × (
+//!                u32 from_anchor, u32 to_anchor, f32 confidence,
+//!                u8 location_delta, u8 cardinality, u8 flags, u32 note)
+//! ```
+//!
+//! Every `u32` naming text is an index into the string table, which holds
+//! each distinct anchor, tape id, path, timestamp and note once. `note` is
+//! `u32::MAX` when the edge has none. `kind` is 0 edit, 1 read, 2 tool,
+//! 3 message; `location_delta` 0 same, 1 adjacent, 2 moved, 3 absent;
+//! `cardinality` 0 1:1, 1 1:N, 2 N:1. `flags` bit 0 is `agent_link`, bit 1
+//! marks a location-only edge, bit 2 `reverted`.
+
+use std::collections::HashMap;
+
+use crate::index::EdgeRow;
+use crate::index::lineage::{
+    Cardinality, EvidenceFragmentRef, EvidenceKind, LocationDelta, StoredEdgeClass,
+};
+use crate::query::graph::MemoryGraph;
+
+pub const GRAPH_SNAPSHOT_MAGIC: &[u8; 8] = b"ENGRAMG\0";
+pub const GRAPH_SNAPSHOT_VERSION: u32 = 1;
+const NO_NOTE: u32 = u32::MAX;
+const FLAG_AGENT_LINK: u8 = 1;
+const FLAG_LOCATION_ONLY: u8 = 1 << 1;
+const FLAG_REVERTED: u8 = 1 << 2;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum SnapshotError {
+    #[error("not an engram graph snapshot")]
+    BadMagic,
+    #[error("unsupported graph snapshot version {0}")]
+    Version(u32),
+    #[error("graph snapshot is truncated")]
+    Truncated,
+    #[error("graph snapshot is corrupt: {0}")]
+    Corrupt(&'static str),
+}
+
+/// Encode `evidence` (each with its anchor) and `edges` as a snapshot.
+pub fn encode_graph_snapshot(
+    evidence: &[(String, EvidenceFragmentRef)],
+    edges: &[EdgeRow],
+) -> Vec<u8> {
+    let mut strings = StringTable::default();
+    let mut body = Vec::new();
+    put_u32(&mut body, evidence.len() as u32);
+    for (anchor, fragment) in evidence {
+        put_u32(&mut body, strings.intern(anchor));
+        put_u32(&mut body, strings.intern(&fragment.tape_id));
+        body.extend_from_slice(&fragment.event_offset.to_le_bytes());
+        body.push(match fragment.kind {
+            EvidenceKind::Edit => 0,
+            EvidenceKind::Read => 1,
+            EvidenceKind::Tool => 2,
+            EvidenceKind::Message => 3,
+        });
+        put_u32(&mut body, strings.intern(&fragment.file_path));
+        put_u32(&mut body, strings.intern(&fragment.timestamp));
+    }
+    put_u32(&mut body, edges.len() as u32);
+    for edge in edges {
+        put_u32(&mut body, strings.intern(&edge.from_anchor));
+        put_u32(&mut body, strings.intern(&edge.to_anchor));
+        body.extend_from_slice(&edge.confidence.to_le_bytes());
+        body.push(match edge.location_delta {
+            LocationDelta::Same => 0,
+            LocationDelta::Adjacent => 1,
+            LocationDelta::Moved => 2,
+            LocationDelta::Absent => 3,
+        });
+        body.push(match edge.cardinality {
+            Cardinality::OneToOne => 0,
+            Cardinality::OneToMany => 1,
+            Cardinality::ManyToOne => 2,
+        });
+        let mut flags = 0;
+        if edge.agent_link {
+            flags |= FLAG_AGENT_LINK;
+        }
+        if edge.stored_class == StoredEdgeClass::LocationOnly {
+            flags |= FLAG_LOCATION_ONLY;
+        }
+        if edge.reverted {
+            flags |= FLAG_REVERTED;
+        }
+        body.push(flags);
+        let note = edge
+            .note
+            .as_deref()
+            .map_or(NO_NOTE, |note| strings.intern(note));
+        put_u32(&mut body, note);
+    }
+
+    let mut out = Vec::with_capacity(body.len() + strings.bytes);
+    out.extend_from_slice(GRAPH_SNAPSHOT_MAGIC);
+    put_u32(&mut out, GRAPH_SNAPSHOT_VERSION);
+    put_u32(&mut out, strings.values.len() as u32);
+    for value in &strings.values {
+        put_u32(&mut out, value.len() as u32);
+        out.extend_from_slice(value.as_bytes());
+    }
+    out.extend_from_slice(&body);
+    out
+}
+
+/// Load a snapshot written by [`encode_graph_snapshot`].
+pub fn decode_graph_snapshot(bytes: &[u8]) -> Result<MemoryGraph, SnapshotError> {
+    let mut reader = Reader { bytes };
+    if reader.take(GRAPH_SNAPSHOT_MAGIC.len())? != GRAPH_SNAPSHOT_MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = reader.u32()?;
+    if version != GRAPH_SNAPSHOT_VERSION {
+        return Err(SnapshotError::Version(version));
+    }
+    let mut strings = Vec::new();
+    for _ in 0..reader.u32()? {
+        let len = reader.u32()? as usize;
+        let text = std::str::from_utf8(reader.take(len)?)
+            .map_err(|_| SnapshotError::Corrupt("string is not UTF-8"))?;
+        strings.push(text.to_string());
+    }
+    let string = |at: u32| {
+        strings
+            .get(at as usize)
+            .cloned()
+            .ok_or(SnapshotError::Corrupt("string index out of range"))
+    };
+
+    let mut graph = MemoryGraph::default();
+    for _ in 0..reader.u32()? {
+        let anchor = string(reader.u32()?)?;
+        let tape_id = string(reader.u32()?)?;
+        let event_offset = reader.u64()?;
+        let kind = match reader.u8()? {
+            0 => EvidenceKind::Edit,
+            1 => EvidenceKind::Read,
+            2 => EvidenceKind::Tool,
+            3 => EvidenceKind::Message,
+            _ => return Err(SnapshotError::Corrupt("unknown evidence kind")),
+        };
+        graph.add_evidence(
+            anchor,
+            EvidenceFragmentRef {
+                tape_id,
+                event_offset,
+                kind,
+                file_path: string(reader.u32()?)?,
+                timestamp: string(reader.u32()?)?,
+            },
+        );
+    }
+    for _ in 0..reader.u32()? {
+        let from_anchor = string(reader.u32()?)?;
+        let to_anchor = string(reader.u32()?)?;
+        let confidence = f32::from_bits(reader.u32()?);
+        let location_delta = match reader.u8()? {
+            0 => LocationDelta::Same,
+            1 => LocationDelta::Adjacent,
+            2 => LocationDelta::Moved,
+            3 => LocationDelta::Absent,
+            _ => return Err(SnapshotError::Corrupt("unknown location delta")),
+        };
+        let cardinality = match reader.u8()? {
+            0 => Cardinality::OneToOne,
+            1 => Cardinality::OneToMany,
+            2 => Cardinality::ManyToOne,
+            _ => return Err(SnapshotError::Corrupt("unknown cardinality")),
+        };
+        let flags = reader.u8()?;
+        let note = match reader.u32()? {
+            NO_NOTE => None,
+            at => Some(string(at)?),
+        };
+        graph.add_edge(EdgeRow {
+            from_anchor,
+            to_anchor,
+            confidence,
+            location_delta,
+            cardinality,
+            agent_link: flags & FLAG_AGENT_LINK != 0,
+            note,
+            stored_class: if flags & FLAG_LOCATION_ONLY != 0 {
+                StoredEdgeClass::LocationOnly
+            } else {
+                StoredEdgeClass::Lineage
+            },
+            reverted: flags & FLAG_REVERTED != 0,
+            scorer: None,
+        });
+    }
+    if !reader.bytes.is_empty() {
+        return Err(SnapshotError::Corrupt("trailing bytes"));
+    }
+    Ok(graph)
+}
+
+#[derive(Default)]
+struct StringTable {
+    values: Vec<String>,
+    ids: HashMap<String, u32>,
+    bytes: usize,
+}
+
+impl StringTable {
+    fn intern(&mut self, value: &str) -> u32 {
+        if let Some(id) = self.ids.get(value) {
+            return *id;
+        }
+        let id = self.values.len() as u32;
+        self.values.push(value.to_string());
+        self.ids.insert(value.to_string(), id);
+        self.bytes += 4 + value.len();
+        id
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::explain::{ExplainTraversal, explain_by_anchor};
+
+    fn edge(from: &str, to: &str, confidence: f32, note: Option<&str>) -> EdgeRow {
+        EdgeRow {
+            from_anchor: from.to_string(),
+            to_anchor: to.to_string(),
+            confidence,
+            location_delta: LocationDelta::Moved,
+            cardinality: Cardinality::OneToMany,
+            agent_link: note.is_some(),
+            note: note.map(ToOwned::to_owned),
+            stored_class: StoredEdgeClass::Lineage,
+            reverted: false,
+            scorer: None,
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trips_into_a_memory_graph() {
+        let evidence = vec![(
+            "c".to_string(),
+            EvidenceFragmentRef {
+                tape_id: "tape-1".to_string(),
+                event_offset: 7,
+                kind: EvidenceKind::Tool,
+                file_path: "src/lib.rs".to_string(),
+                timestamp: "2026-02-22T00:00:00Z".to_string(),
+            },
+        )];
+        let edges = vec![
+            edge("b", "c", 0.8, None),
+            edge("a", "b", 0.6, Some("split")),
+        ];
+        let bytes = encode_graph_snapshot(&evidence, &edges);
+        assert_eq!(&bytes[..8], GRAPH_SNAPSHOT_MAGIC);
+
+        let graph = decode_graph_snapshot(&bytes).expect("decode");
+        let Ok(result) = explain_by_anchor(
+            &graph,
+            &["c".to_string()],
+            ExplainTraversal::default(),
+            false,
+        );
+        assert_eq!(result.direct, vec![evidence[0].1.clone()]);
+        assert_eq!(result.lineage, edges);
+
+        assert!(matches!(
+            decode_graph_snapshot(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::Truncated)
+        ));
+        assert!(matches!(
+            decode_graph_snapshot(b"not a snapshot"),
+            Err(SnapshotError::BadMagic)
+        ));
+    }
+}
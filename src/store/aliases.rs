@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::atomic::atomic_write;
+use crate::error::{Error, Result};
+
+/// Old tape ids and the ids their tapes carry now, written when `reindex`
+/// moves tapes named by their raw bytes to canonical ids (see
+/// [`crate::tape::identity`]). Ids quoted in notes, manifests and issue
+/// comments keep resolving through it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TapeIdAliases {
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// The alias file of the store whose tapes live in `tapes_dir`.
+pub fn tape_id_aliases_path(tapes_dir: &Path) -> PathBuf {
+    tapes_dir.with_file_name("tape-ids.json")
+}
+
+pub fn load_tape_id_aliases(path: &Path) -> Result<TapeIdAliases> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|source| Error::Store {
+            path: path.to_path_buf(),
+            source,
+        }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(TapeIdAliases::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn save_tape_id_aliases(path: &Path, aliases: &TapeIdAliases) -> Result<()> {
+    let bytes = serde_json::to_vec_pretty(aliases).map_err(|source| Error::Store {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(atomic_write(path, &bytes)?)
+}
+
+impl TapeIdAliases {
+    /// Record that `old` is now `new`. Earlier aliases of `old` follow it.
+    pub fn insert(&mut self, old: String, new: String) {
+        for target in self.aliases.values_mut() {
+            if *target == old {
+                target.clone_from(&new);
+            }
+        }
+        self.aliases.insert(old, new);
+    }
+
+    /// The current id of `tape_id`, if it was migrated.
+    pub fn resolve(&self, tape_id: &str) -> Option<&str> {
+        self.aliases.get(tape_id).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_round_trip_and_follow_later_migrations() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = tape_id_aliases_path(&dir.path().join("tapes"));
+        assert_eq!(path, dir.path().join("tape-ids.json"));
+        let mut aliases = load_tape_id_aliases(&path).expect("missing file is empty");
+        assert!(aliases.is_empty());
+        aliases.insert("a".to_string(), "b".to_string());
+        aliases.insert("b".to_string(), "c".to_string());
+        save_tape_id_aliases(&path, &aliases).expect("save");
+
+        let loaded = load_tape_id_aliases(&path).expect("load");
+        assert_eq!(loaded.resolve("a"), Some("c"));
+        assert_eq!(loaded.resolve("b"), Some("c"));
+        assert_eq!(loaded.resolve("c"), None);
+    }
+}
@@ -7,6 +7,9 @@ use std::time::{SystemTime, UNIX_EPOCH};
 static ATOMIC_COUNTER: AtomicU64 = AtomicU64::new(0);
 const TEMP_PREFIX: &str = ".engram.tmp.";
 
+/// Replace `path` with `bytes` through a temp file and rename. An existing
+/// file keeps its permissions (an executable script stays executable, a
+/// 0600 file stays private).
 pub fn atomic_write(path: &Path, bytes: &[u8]) -> io::Result<()> {
     let parent = path.parent().ok_or_else(|| {
         io::Error::new(
@@ -22,6 +25,11 @@ pub fn atomic_write(path: &Path, bytes: &[u8]) -> io::Result<()> {
     let write_result = (|| -> io::Result<()> {
         tmp_file.write_all(bytes)?;
         tmp_file.flush()?;
+        match fs::metadata(path) {
+            Ok(existing) => tmp_file.set_permissions(existing.permissions())?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
         tmp_file.sync_all()?;
         drop(tmp_file);
 
@@ -108,6 +116,24 @@ mod tests {
         assert_eq!(content, "new-content");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn atomic_write_keeps_the_existing_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        for mode in [0o755, 0o600] {
+            let path = dir.path().join(format!("file-{mode:o}"));
+            fs::write(&path, "old").expect("seed");
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).expect("chmod");
+
+            atomic_write(&path, b"new").expect("atomic overwrite");
+            let meta = fs::metadata(&path).expect("metadata");
+            assert_eq!(meta.permissions().mode() & 0o777, mode);
+            assert_eq!(fs::read_to_string(&path).expect("read"), "new");
+        }
+    }
+
     #[test]
     fn atomic_write_cleans_up_temp_files() {
         let dir = tempfile::tempdir().expect("tempdir");
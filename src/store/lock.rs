@@ -0,0 +1,168 @@
+//! A store-wide writer lock: an OS advisory lock (`flock` on Unix,
+//! `LockFileEx` on Windows) on a file next to the index, so two writers
+//! never interleave cursor and index writes and gc never sweeps a tape that
+//! is still being recorded.
+//!
+//! `ingest` (including the runs `watch` starts), `fingerprint`,
+//! `scan-comments`, `reindex`, `maintain`, `gc`, `split`, `join` and
+//! `verify --salvage` fail while another writer holds it; `record`,
+//! `hook-receiver`, `annotate`, `import`, `rate`, `protect` and `watchlist`
+//! (`add`/`remove`) and `calibrate --write` wait for it before they store.
+//!
+//! The kernel drops the lock when its holder exits, however it exits, so a
+//! writer that died never leaves the store locked. The file stays in place
+//! and only names the current holder for [`LockError::Held`]: removing it
+//! while another process waits on it would let two writers lock different
+//! files.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LockError {
+    #[error(
+        "another engram process is running (pid {pid}, {command}, since {since}) and holds {}",
+        path.display()
+    )]
+    Held {
+        path: PathBuf,
+        pid: u32,
+        command: String,
+        since: String,
+    },
+    #[error("lock {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LockOwner {
+    pid: u32,
+    command: String,
+    since: String,
+}
+
+/// Held until dropped.
+#[derive(Debug)]
+pub struct StoreLock {
+    path: PathBuf,
+    /// Closing it releases the lock.
+    _file: File,
+}
+
+impl StoreLock {
+    /// Take the lock at `path` for `command`. Another process holding it,
+    /// or another `StoreLock` in this one (a watch ingest that outlived its
+    /// timeout), is [`LockError::Held`].
+    pub fn acquire(path: &Path, command: &str, now: &str) -> Result<Self, LockError> {
+        let io_err = |source| LockError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(io_err)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Err(held(path, read_owner(path))),
+            Err(TryLockError::Error(err)) => return Err(io_err(err)),
+        }
+        let owner = LockOwner {
+            pid: std::process::id(),
+            command: command.to_string(),
+            since: now.to_string(),
+        };
+        let body = serde_json::to_vec(&owner).unwrap_or_default();
+        file.set_len(0)
+            .and_then(|()| file.write_all(&body))
+            .and_then(|()| file.sync_all())
+            .map_err(io_err)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            _file: file,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// The lock file guarding the index at `db_path`: `<db file name>.lock`.
+pub fn lock_path_for_db(db_path: &Path) -> PathBuf {
+    let mut name = db_path.as_os_str().to_os_string();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+fn held(path: &Path, holder: Option<LockOwner>) -> LockError {
+    // Unreadable while the holder is still writing it.
+    let holder = holder.unwrap_or_else(|| LockOwner {
+        pid: 0,
+        command: "unknown".to_string(),
+        since: "unknown".to_string(),
+    });
+    LockError::Held {
+        path: path.to_path_buf(),
+        pid: holder.pid,
+        command: holder.command,
+        since: holder.since,
+    }
+}
+
+fn read_owner(path: &Path) -> Option<LockOwner> {
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_acquire_fails_until_the_first_is_dropped() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = lock_path_for_db(&dir.path().join("index.sqlite"));
+        assert_eq!(path, dir.path().join("index.sqlite.lock"));
+
+        let lock = StoreLock::acquire(&path, "ingest", "2026-02-22T00:00:00Z").expect("lock");
+        let err = StoreLock::acquire(&path, "gc", "2026-02-22T00:00:01Z").expect_err("held");
+        assert!(
+            err.to_string()
+                .starts_with("another engram process is running"),
+            "{err}"
+        );
+        assert!(err.to_string().contains("ingest"), "{err}");
+        drop(lock);
+        assert!(path.exists());
+        StoreLock::acquire(&path, "gc", "2026-02-22T00:00:02Z").expect("free again");
+    }
+
+    #[test]
+    fn lock_file_left_by_an_exited_writer_is_reused() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("index.sqlite.lock");
+        fs::write(
+            &path,
+            r#"{"pid":4294967,"command":"ingest","since":"2026-02-22T00:00:00Z","extra":"padding"}"#,
+        )
+        .expect("stale lock");
+
+        let lock = StoreLock::acquire(&path, "gc", "2026-02-22T00:00:01Z").expect("take over");
+        let owner = read_owner(lock.path()).expect("owner");
+        assert_eq!(owner.pid, std::process::id());
+        assert_eq!(owner.command, "gc");
+    }
+}
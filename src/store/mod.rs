@@ -1,4 +1,9 @@
+pub mod aliases;
 pub mod atomic;
+pub mod lock;
+pub mod protected;
+pub mod sessions;
+pub mod watchlist;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StorageLayout {
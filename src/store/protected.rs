@@ -0,0 +1,105 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::atomic::atomic_write;
+use crate::error::{Error, Result};
+
+/// Tapes that `gc` never deletes (`engram protect add <tape-id>`), for
+/// sessions kept as evidence after nothing in the index references them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectedTapes {
+    pub entries: Vec<ProtectedTape>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectedTape {
+    pub tape_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub protected_at: String,
+}
+
+pub fn protected_tapes_path(engram_dir: &Path) -> PathBuf {
+    engram_dir.join("protected.json")
+}
+
+pub fn load_protected_tapes(path: &Path) -> Result<ProtectedTapes> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|source| Error::Store {
+            path: path.to_path_buf(),
+            source,
+        }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(ProtectedTapes::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn save_protected_tapes(path: &Path, protected: &ProtectedTapes) -> Result<()> {
+    let bytes = serde_json::to_vec_pretty(protected).map_err(|source| Error::Store {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(atomic_write(path, &bytes)?)
+}
+
+impl ProtectedTapes {
+    /// Protect `entry.tape_id`, replacing the note of an existing entry.
+    /// Returns `false` when the tape was already protected.
+    pub fn add(&mut self, entry: ProtectedTape) -> bool {
+        match self
+            .entries
+            .iter_mut()
+            .find(|existing| existing.tape_id == entry.tape_id)
+        {
+            Some(existing) => {
+                *existing = entry;
+                false
+            }
+            None => {
+                self.entries.push(entry);
+                true
+            }
+        }
+    }
+
+    /// Whether `tape_id` was protected (and now is not).
+    pub fn remove(&mut self, tape_id: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.tape_id != tape_id);
+        before != self.entries.len()
+    }
+
+    pub fn contains(&self, tape_id: &str) -> bool {
+        self.entries.iter().any(|entry| entry.tape_id == tape_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protected_tapes_round_trip_and_dedupe() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = protected_tapes_path(dir.path());
+        let mut protected = load_protected_tapes(&path).expect("missing file is empty");
+        let entry = |note: &str| ProtectedTape {
+            tape_id: "tape-1".to_string(),
+            note: Some(note.to_string()),
+            protected_at: "2026-03-01T00:00:00Z".to_string(),
+        };
+        assert!(protected.add(entry("incident 42")));
+        assert!(!protected.add(entry("incident 43")));
+        save_protected_tapes(&path, &protected).expect("save");
+
+        let mut loaded = load_protected_tapes(&path).expect("load");
+        assert_eq!(loaded, protected);
+        assert_eq!(loaded.entries[0].note.as_deref(), Some("incident 43"));
+        assert!(loaded.contains("tape-1"));
+        assert!(loaded.remove("tape-1"));
+        assert!(!loaded.remove("tape-1"));
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::atomic::atomic_write;
+use crate::error::{Error, Result};
+
+/// Prefix that addresses a recorded session instead of a single tape
+/// (`engram show session:my-task`).
+pub const SESSION_REF_PREFIX: &str = "session:";
+
+/// A named, multi-part recording built by repeated `engram record --session`.
+/// Each part is an ordinary content-addressed tape; the manifest only fixes
+/// their order so readers can stitch them back into one transcript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordSession {
+    pub name: String,
+    pub parts: Vec<SessionPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionPart {
+    pub part: u32,
+    pub tape_id: String,
+    pub recorded_at: String,
+}
+
+/// Session names become file names, so only `[A-Za-z0-9._-]` is allowed and
+/// the name may not start with a dot.
+pub fn valid_session_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'))
+}
+
+pub fn session_manifest_path(sessions_dir: &Path, name: &str) -> PathBuf {
+    sessions_dir.join(format!("{name}.json"))
+}
+
+pub fn load_record_session(sessions_dir: &Path, name: &str) -> Result<Option<RecordSession>> {
+    let path = session_manifest_path(sessions_dir, name);
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|source| Error::Store { path, source }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// All manifests in `sessions_dir`, sorted by name.
+pub fn list_record_sessions(sessions_dir: &Path) -> Result<Vec<RecordSession>> {
+    let entries = match fs::read_dir(sessions_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut sessions = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(name) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".json"))
+        else {
+            continue;
+        };
+        if let Some(session) = load_record_session(sessions_dir, name)? {
+            sessions.push(session);
+        }
+    }
+    sessions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(sessions)
+}
+
+/// Append `tape_id` as the next part of session `name`, creating the
+/// manifest on first use. Recording identical content twice resolves to the
+/// same tape and does not add a second part.
+pub fn append_session_part(
+    sessions_dir: &Path,
+    name: &str,
+    tape_id: &str,
+    recorded_at: &str,
+) -> Result<(RecordSession, SessionPart)> {
+    let mut session = load_record_session(sessions_dir, name)?.unwrap_or_else(|| RecordSession {
+        name: name.to_string(),
+        parts: Vec::new(),
+    });
+    if let Some(existing) = session.parts.iter().find(|part| part.tape_id == tape_id) {
+        let existing = existing.clone();
+        return Ok((session, existing));
+    }
+    let part = SessionPart {
+        part: session.parts.last().map_or(1, |last| last.part + 1),
+        tape_id: tape_id.to_string(),
+        recorded_at: recorded_at.to_string(),
+    };
+    session.parts.push(part.clone());
+    save_record_session(sessions_dir, &session)?;
+    Ok((session, part))
+}
+
+/// Write `session`'s manifest, replacing the one stored under its name.
+pub fn save_record_session(sessions_dir: &Path, session: &RecordSession) -> Result<()> {
+    let path = session_manifest_path(sessions_dir, &session.name);
+    let bytes = serde_json::to_vec_pretty(session).map_err(|source| Error::Store {
+        path: path.clone(),
+        source,
+    })?;
+    atomic_write(&path, &bytes)?;
+    Ok(())
+}
+
+/// Concatenate part tapes, in part order, into one JSONL transcript. Each part
+/// keeps its own `meta` line, which marks where the part starts.
+pub fn stitch_parts<S: AsRef<str>>(parts: &[S]) -> String {
+    let mut out = String::new();
+    for part in parts {
+        let part = part.as_ref();
+        out.push_str(part);
+        if !part.is_empty() && !part.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parts_append_in_order_and_dedupe_identical_tapes() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let dir = temp.path().join("sessions");
+
+        let (_, first) =
+            append_session_part(&dir, "my-task", "tape-a", "2026-03-01T10:00:00Z").expect("part 1");
+        let (_, second) =
+            append_session_part(&dir, "my-task", "tape-b", "2026-03-01T10:05:00Z").expect("part 2");
+        let (session, repeat) =
+            append_session_part(&dir, "my-task", "tape-a", "2026-03-01T10:09:00Z").expect("repeat");
+
+        assert_eq!((first.part, second.part, repeat.part), (1, 2, 1));
+        assert_eq!(
+            session
+                .parts
+                .iter()
+                .map(|part| part.tape_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["tape-a", "tape-b"]
+        );
+        assert_eq!(
+            load_record_session(&dir, "my-task").expect("load"),
+            Some(session)
+        );
+        assert_eq!(list_record_sessions(&dir).expect("list").len(), 1);
+        assert_eq!(load_record_session(&dir, "other").expect("load"), None);
+    }
+
+    #[test]
+    fn session_names_are_file_safe_and_parts_stitch_with_newlines() {
+        assert!(valid_session_name("my-task_2.v1"));
+        assert!(!valid_session_name(""));
+        assert!(!valid_session_name(".hidden"));
+        assert!(!valid_session_name("../escape"));
+        assert_eq!(
+            stitch_parts(&["{\"a\":1}", "{\"b\":2}\n"]),
+            "{\"a\":1}\n{\"b\":2}\n"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::atomic::atomic_write;
+use crate::error::{Error, Result};
+use crate::tape::event::{TapeEventAt, TapeEventData};
+use crate::tape::paths::repo_relative_path;
+
+/// Paths or anchors whose owners want to hear when an agent touches them
+/// (`engram watchlist add src/payments/**`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watchlist {
+    pub entries: Vec<WatchEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchEntry {
+    /// Glob over repo-relative file paths, or over anchor hashes with `anchor`.
+    pub pattern: String,
+    #[serde(default)]
+    pub anchor: bool,
+    /// Shell command run with each notification as JSON on stdin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook: Option<String>,
+    pub added_at: String,
+}
+
+/// Every event of one tape that matched one watchlist entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchHit {
+    pub pattern: String,
+    pub hook: Option<String>,
+    pub matches: Vec<WatchMatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchMatch {
+    pub event_offset: u64,
+    /// `read` or `edit`.
+    pub kind: &'static str,
+    pub file: String,
+    /// The matching anchor, for anchor entries.
+    pub anchor: Option<String>,
+}
+
+pub fn watchlist_path(engram_dir: &Path) -> PathBuf {
+    engram_dir.join("watchlist.json")
+}
+
+pub fn load_watchlist(path: &Path) -> Result<Watchlist> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|source| Error::Store {
+            path: path.to_path_buf(),
+            source,
+        }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Watchlist::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn save_watchlist(path: &Path, watchlist: &Watchlist) -> Result<()> {
+    let bytes = serde_json::to_vec_pretty(watchlist).map_err(|source| Error::Store {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(atomic_write(path, &bytes)?)
+}
+
+impl Watchlist {
+    /// Add `entry`, replacing an existing entry with the same pattern and kind.
+    /// Returns `false` when it replaced one.
+    pub fn add(&mut self, entry: WatchEntry) -> bool {
+        match self
+            .entries
+            .iter_mut()
+            .find(|existing| existing.pattern == entry.pattern && existing.anchor == entry.anchor)
+        {
+            Some(existing) => {
+                *existing = entry;
+                false
+            }
+            None => {
+                self.entries.push(entry);
+                true
+            }
+        }
+    }
+
+    /// Remove every entry with `pattern`; returns how many were removed.
+    pub fn remove(&mut self, pattern: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.pattern != pattern);
+        before - self.entries.len()
+    }
+
+    /// Match a tape's `code.read` / `code.edit` events against every entry.
+    /// File paths are made repo-relative against the tape's `meta.cwd`, as the
+    /// index stores them. Entries with invalid globs never match.
+    pub fn matches(&self, events: &[TapeEventAt]) -> Vec<WatchHit> {
+        let project_root = events
+            .iter()
+            .find_map(|item| match &item.event.data {
+                TapeEventData::Meta(meta) => meta.cwd.as_deref(),
+                _ => None,
+            })
+            .map(Path::new);
+        let mut hits = Vec::new();
+        for entry in &self.entries {
+            let Ok(pattern) = glob::Pattern::new(&entry.pattern) else {
+                continue;
+            };
+            let mut matches = Vec::new();
+            for item in events {
+                let (kind, file, anchors): (_, _, Vec<&String>) = match &item.event.data {
+                    TapeEventData::CodeRead(read) => {
+                        ("read", &read.file, read.anchor_hashes.iter().collect())
+                    }
+                    TapeEventData::CodeEdit(edit) => (
+                        "edit",
+                        &edit.file,
+                        edit.before_anchor_hashes
+                            .iter()
+                            .chain(&edit.after_anchor_hashes)
+                            .collect(),
+                    ),
+                    _ => continue,
+                };
+                let file = repo_relative_path(file, project_root);
+                let anchor = if entry.anchor {
+                    match anchors.into_iter().find(|anchor| pattern.matches(anchor)) {
+                        Some(anchor) => Some(anchor.clone()),
+                        None => continue,
+                    }
+                } else if pattern.matches(&file) {
+                    None
+                } else {
+                    continue;
+                };
+                matches.push(WatchMatch {
+                    event_offset: item.offset,
+                    kind,
+                    file,
+                    anchor,
+                });
+            }
+            if !matches.is_empty() {
+                hits.push(WatchHit {
+                    pattern: entry.pattern.clone(),
+                    hook: entry.hook.clone(),
+                    matches,
+                });
+            }
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tape::event::parse_jsonl_events;
+
+    fn entry(pattern: &str, anchor: bool) -> WatchEntry {
+        WatchEntry {
+            pattern: pattern.to_string(),
+            anchor,
+            hook: None,
+            added_at: "2026-03-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn matches_repo_relative_paths_and_anchors() {
+        let events = parse_jsonl_events(concat!(
+            r#"{"t":"2026-03-01T00:00:00Z","k":"meta","cwd":"/repo"}"#,
+            "\n",
+            r#"{"t":"2026-03-01T00:00:01Z","k":"code.read","file":"/repo/src/payments/charge.rs","range":[1,2],"anchor_hashes":["anchor-pay"]}"#,
+            "\n",
+            r#"{"t":"2026-03-01T00:00:02Z","k":"code.read","file":"/repo/src/lib.rs","range":[1,2],"anchor_hashes":["anchor-lib"]}"#,
+            "\n"
+        ))
+        .expect("events");
+
+        let mut watchlist = Watchlist::default();
+        assert!(watchlist.add(entry("src/payments/**", false)));
+        assert!(watchlist.add(entry("anchor-lib", true)));
+        assert!(watchlist.add(entry("docs/**", false)));
+        assert!(!watchlist.add(entry("docs/**", false)));
+
+        let hits = watchlist.matches(&events);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].pattern, "src/payments/**");
+        assert_eq!(hits[0].matches[0].file, "src/payments/charge.rs");
+        assert_eq!(hits[0].matches[0].event_offset, 1);
+        assert_eq!(hits[1].matches[0].anchor.as_deref(), Some("anchor-lib"));
+
+        assert_eq!(watchlist.remove("docs/**"), 1);
+        assert_eq!(watchlist.entries.len(), 2);
+    }
+
+    #[test]
+    fn watchlist_round_trips_and_defaults_to_empty() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = watchlist_path(temp.path());
+        assert_eq!(
+            load_watchlist(&path).expect("missing"),
+            Watchlist::default()
+        );
+        let mut watchlist = Watchlist::default();
+        watchlist.add(WatchEntry {
+            hook: Some("notify-send engram".to_string()),
+            ..entry("src/**", false)
+        });
+        save_watchlist(&path, &watchlist).expect("save");
+        assert_eq!(load_watchlist(&path).expect("load"), watchlist);
+    }
+}
@@ -1,6 +1,6 @@
 use std::fs;
-use std::io::{BufRead, BufReader};
-use std::path::{Component, Path, PathBuf};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use serde_json::Value;
@@ -8,9 +8,14 @@ use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 
 use super::adapters::{
-    claude_jsonl_to_tape_jsonl, codex_jsonl_to_tape_jsonl, cursor_jsonl_to_tape_jsonl,
-    gemini_json_to_tape_jsonl, openclaw_jsonl_to_tape_jsonl, opencode_json_to_tape_jsonl,
+    chatgpt_json_to_tape_jsonl, claude_jsonl_to_tape_jsonl, codex_jsonl_to_tape_jsonl,
+    cursor_jsonl_to_tape_jsonl, gemini_json_to_tape_jsonl, openclaw_jsonl_to_tape_jsonl,
+    opencode_json_to_tape_jsonl, vscode_chat_json_to_tape_jsonl,
 };
+use super::event::ParseIssue;
+use super::jsonl::{lenient_jsonl, strip_byte_order_mark};
+use super::paths::canonicalize_or_normalize;
+use super::rotation::{has_transcript_extension, open_transcript};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AdapterId {
@@ -20,6 +25,8 @@ pub enum AdapterId {
     GeminiCli,
     Cursor,
     OpenClaw,
+    ChatGpt,
+    VsCodeChat,
 }
 
 impl AdapterId {
@@ -31,8 +38,41 @@ impl AdapterId {
             Self::GeminiCli => "gemini-cli",
             Self::Cursor => "cursor",
             Self::OpenClaw => "openclaw",
+            Self::ChatGpt => "chatgpt",
+            Self::VsCodeChat => "vscode-chat",
         }
     }
+
+    /// Semver of this adapter's conversion, stamped into every tape it
+    /// produces. Bump it whenever the adapter's output changes.
+    pub const fn version(self) -> &'static str {
+        match self {
+            Self::ClaudeCode => "1.1.0",
+            Self::CodexCli
+            | Self::OpenCode
+            | Self::GeminiCli
+            | Self::Cursor
+            | Self::OpenClaw
+            | Self::ChatGpt
+            | Self::VsCodeChat => "1.0.0",
+        }
+    }
+}
+
+/// Whether a tape stamped with adapter `recorded` predates `current`.
+/// Versions compare as `major.minor.patch`; an unparseable stamp counts as
+/// older so reconversion can replace it.
+pub fn adapter_version_is_older(recorded: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Option<(u64, u64, u64)> {
+        let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+        let parsed = (parts.next()??, parts.next()??, parts.next()??);
+        parts.next().is_none().then_some(parsed)
+    }
+    match (parse(recorded), parse(current)) {
+        (Some(recorded), Some(current)) => recorded < current,
+        (None, Some(_)) => true,
+        _ => false,
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -322,6 +362,60 @@ pub fn adapter_registry() -> &'static [AdapterDescriptor] {
                 tool: CoverageGrade::Full,
             },
         },
+        AdapterDescriptor {
+            id: AdapterId::ChatGpt,
+            status: AdapterStatus::Implemented,
+            artifact_path_templates: &["~/Downloads/<export>/conversations.json"],
+            schema_sample_set: &["tests/fixtures/chatgpt/conversations.json"],
+            mapping_table: &[
+                MappingRule {
+                    source: "mapping[current_node..root][author.role=user]",
+                    target: "msg.in",
+                    note: "current branch only; text parts joined",
+                },
+                MappingRule {
+                    source: "mapping[current_node..root][author.role=assistant]",
+                    target: "msg.out",
+                    note: "model from metadata.model_slug",
+                },
+                MappingRule {
+                    source: "fenced code block naming a file",
+                    target: "code.edit",
+                    note: "candidate: true; after_text only, no ranges",
+                },
+            ],
+            coverage: CoverageGrades {
+                read: CoverageGrade::None,
+                edit: CoverageGrade::Partial,
+                tool: CoverageGrade::None,
+            },
+        },
+        AdapterDescriptor {
+            id: AdapterId::VsCodeChat,
+            status: AdapterStatus::Implemented,
+            artifact_path_templates: &[
+                "~/.config/Code/User/workspaceStorage/<workspace>/chatEditingSessions/<session>/state.json",
+                "~/Library/Application Support/Code/User/workspaceStorage/<workspace>/chatEditingSessions/<session>/state.json",
+            ],
+            schema_sample_set: &["tests/fixtures/vscode/chatEditingSessions/session-1"],
+            mapping_table: &[
+                MappingRule {
+                    source: "entries[].telemetryInfo.modelId",
+                    target: "meta",
+                    note: "model + fixed coverage grades",
+                },
+                MappingRule {
+                    source: "linearHistory[].stops[-1].entries[]",
+                    target: "code.edit",
+                    note: "one per changed line run between snapshots; rejected entries skipped",
+                },
+            ],
+            coverage: CoverageGrades {
+                read: CoverageGrade::None,
+                edit: CoverageGrade::Full,
+                tool: CoverageGrade::None,
+            },
+        },
     ]
 }
 
@@ -341,24 +435,63 @@ pub fn discovery_scaffold(id: AdapterId, home_dir: &Path) -> Vec<PathBuf> {
         .collect()
 }
 
-fn normalize_path(path: &Path) -> PathBuf {
-    let mut out = PathBuf::new();
-    for component in path.components() {
-        match component {
-            Component::CurDir => {}
-            Component::ParentDir => {
-                let _ = out.pop();
-            }
-            Component::RootDir | Component::Prefix(_) | Component::Normal(_) => {
-                out.push(component.as_os_str())
-            }
-        }
-    }
-    out
+/// Where an artifact path template's files live, in watch-source terms:
+/// the fixed directory before the first placeholder, the file name pattern,
+/// and the glob of the path below the directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactLocation {
+    pub root: String,
+    pub pattern: String,
+    pub glob: Option<String>,
 }
 
-fn canonicalize_or_normalize(path: &Path) -> PathBuf {
-    fs::canonicalize(path).unwrap_or_else(|_| normalize_path(path))
+/// The location of `template`'s files, or `None` when the template starts
+/// with a placeholder (such as `<capture>`) that only the user can fill in.
+/// Placeholders (`<project>`, `YYYY`) become `*`.
+pub fn artifact_location(template: &str) -> Option<ArtifactLocation> {
+    let segments = template.split('/').collect::<Vec<_>>();
+    let (file, dirs) = segments.split_last()?;
+    let is_placeholder = |segment: &str| {
+        segment.contains(['<', '*', '?', '['])
+            || (!segment.is_empty() && segment.chars().all(|c| matches!(c, 'Y' | 'M' | 'D')))
+    };
+    let fixed = dirs
+        .iter()
+        .position(|segment| is_placeholder(segment))
+        .unwrap_or(dirs.len());
+    if fixed == 0 {
+        return None;
+    }
+    let wildcard = |segment: &str| {
+        if segment == "**" {
+            return segment.to_string();
+        }
+        let mut out = String::new();
+        let mut rest = segment;
+        while let Some(start) = rest.find('<') {
+            out.push_str(&rest[..start]);
+            out.push('*');
+            rest = rest[start..]
+                .find('>')
+                .map_or("", |end| &rest[start + end + 1..]);
+        }
+        out.push_str(rest);
+        if is_placeholder(&out) && !out.contains(['*', '?', '[']) {
+            "*".to_string()
+        } else {
+            out
+        }
+    };
+    let pattern = wildcard(file);
+    let below = dirs[fixed..]
+        .iter()
+        .map(|segment| wildcard(segment))
+        .collect::<Vec<_>>();
+    Some(ArtifactLocation {
+        root: dirs[..fixed].join("/"),
+        glob: (!below.is_empty()).then(|| format!("{}/{pattern}", below.join("/"))),
+        pattern,
+    })
 }
 
 fn sorted_unique(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
@@ -377,11 +510,7 @@ fn list_files_by_extension_recursive(root: &Path, extension: &str) -> Vec<PathBu
             continue;
         }
         let path = entry.path();
-        if path
-            .extension()
-            .and_then(|value| value.to_str())
-            .is_some_and(|value| value.eq_ignore_ascii_case(extension))
-        {
+        if has_transcript_extension(path, extension) {
             out.push(path.to_path_buf());
         }
     }
@@ -401,11 +530,7 @@ fn list_files_by_extension_shallow(root: &Path, extension: &str) -> Vec<PathBuf>
         if !path.is_file() {
             continue;
         }
-        if path
-            .extension()
-            .and_then(|value| value.to_str())
-            .is_some_and(|value| value.eq_ignore_ascii_case(extension))
-        {
+        if has_transcript_extension(&path, extension) {
             out.push(path);
         }
     }
@@ -433,8 +558,7 @@ fn sha256_hex(input: &str) -> String {
 }
 
 fn read_first_matching_codex_cwd(path: &Path) -> Option<PathBuf> {
-    let file = fs::File::open(path).ok()?;
-    let reader = BufReader::new(file);
+    let reader = open_transcript(path).ok()?;
     for line in reader.lines().map_while(Result::ok) {
         let trimmed = line.trim();
         if trimmed.is_empty() {
@@ -525,10 +649,9 @@ fn discover_gemini_sessions(repo_path: &Path, home_dir: &Path) -> Vec<PathBuf> {
 }
 
 fn read_matches_repo_hint(path: &Path, repo_path: &Path) -> bool {
-    let Ok(file) = fs::File::open(path) else {
+    let Ok(reader) = open_transcript(path) else {
         return false;
     };
-    let reader = BufReader::new(file);
     let repo = canonicalize_or_normalize(repo_path);
     let repo_text = repo.to_string_lossy();
     for line in reader.lines().map_while(Result::ok).take(80) {
@@ -695,23 +818,28 @@ fn discover_opencode_sessions(repo_path: &Path, home_dir: &Path) -> Vec<PathBuf>
 }
 
 fn cursor_workspace_storage_roots(home_dir: &Path) -> Vec<PathBuf> {
+    workspace_storage_roots(home_dir, "Cursor")
+}
+
+/// `workspaceStorage` directories of the VS Code family editor `app`.
+fn workspace_storage_roots(home_dir: &Path, app: &str) -> Vec<PathBuf> {
     let mut roots = vec![
         home_dir
             .join("Library")
             .join("Application Support")
-            .join("Cursor")
+            .join(app)
             .join("User")
             .join("workspaceStorage"),
         home_dir
             .join(".config")
-            .join("Cursor")
+            .join(app)
             .join("User")
             .join("workspaceStorage"),
     ];
     if let Some(app_data) = std::env::var_os("APPDATA") {
         roots.push(
             PathBuf::from(app_data)
-                .join("Cursor")
+                .join(app)
                 .join("User")
                 .join("workspaceStorage"),
         );
@@ -746,12 +874,10 @@ fn collect_workspace_paths_from_manifest(value: &Value, out: &mut Vec<PathBuf>)
                         || lower.contains("path")
                         || lower.contains("workspace")
                         || lower.contains("uri"))
+                    && let Some(text) = nested.as_str()
+                    && let Some(path) = normalize_workspace_manifest_path(text)
                 {
-                    if let Some(text) = nested.as_str()
-                        && let Some(path) = normalize_workspace_manifest_path(text)
-                    {
-                        out.push(path);
-                    }
+                    out.push(path);
                 }
                 collect_workspace_paths_from_manifest(nested, out);
             }
@@ -813,6 +939,34 @@ fn discover_cursor_sessions(repo_path: &Path, home_dir: &Path) -> Vec<PathBuf> {
     sorted_unique(out)
 }
 
+fn discover_vscode_chat_sessions(repo_path: &Path, home_dir: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for root in workspace_storage_roots(home_dir, "Code") {
+        let Ok(entries) = fs::read_dir(&root) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let workspace_dir = entry.path();
+            let workspace_json = workspace_dir.join("workspace.json");
+            if !workspace_json.is_file()
+                || !workspace_manifest_matches_repo(&workspace_json, repo_path)
+            {
+                continue;
+            }
+            let Ok(sessions) = fs::read_dir(workspace_dir.join("chatEditingSessions")) else {
+                continue;
+            };
+            for session in sessions.filter_map(Result::ok) {
+                let state = session.path().join("state.json");
+                if state.is_file() {
+                    out.push(state);
+                }
+            }
+        }
+    }
+    sorted_unique(out)
+}
+
 #[derive(Debug)]
 pub enum AdapterError {
     Json(serde_json::Error),
@@ -951,7 +1105,54 @@ impl HarnessAdapter for OpenClawAdapter {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct ChatGptAdapter;
+
+impl HarnessAdapter for ChatGptAdapter {
+    fn adapter_id(&self) -> AdapterId {
+        AdapterId::ChatGpt
+    }
+
+    fn convert_to_tape_jsonl(&self, input: &str) -> Result<String, AdapterError> {
+        Ok(chatgpt_json_to_tape_jsonl(input)?)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct VsCodeChatAdapter;
+
+impl HarnessAdapter for VsCodeChatAdapter {
+    fn adapter_id(&self) -> AdapterId {
+        AdapterId::VsCodeChat
+    }
+
+    fn discover_sessions_for_repo(&self, repo_path: &Path, home_dir: &Path) -> Vec<PathBuf> {
+        discover_vscode_chat_sessions(repo_path, home_dir)
+    }
+
+    fn convert_to_tape_jsonl(&self, input: &str) -> Result<String, AdapterError> {
+        Ok(vscode_chat_json_to_tape_jsonl(input)?)
+    }
+}
+
+/// Convert with adapter `id`. A byte order mark is ignored, and JSON Lines
+/// adapters skip lines that are not JSON (see [`lenient_jsonl`]) as long as
+/// some line is; input with no JSON at all fails as before.
 pub fn convert_with_adapter(id: AdapterId, input: &str) -> Result<String, AdapterError> {
+    let cleaned;
+    let input = match id {
+        AdapterId::OpenCode | AdapterId::GeminiCli | AdapterId::ChatGpt | AdapterId::VsCodeChat => {
+            strip_byte_order_mark(input)
+        }
+        _ => {
+            cleaned = lenient_jsonl(input);
+            if cleaned.json_lines == 0 {
+                input
+            } else {
+                &cleaned.text
+            }
+        }
+    };
     match id {
         AdapterId::ClaudeCode => ClaudeCodeAdapter.convert_to_tape_jsonl(input),
         AdapterId::CodexCli => CodexCliAdapter.convert_to_tape_jsonl(input),
@@ -959,6 +1160,24 @@ pub fn convert_with_adapter(id: AdapterId, input: &str) -> Result<String, Adapte
         AdapterId::Cursor => CursorAdapter.convert_to_tape_jsonl(input),
         AdapterId::GeminiCli => GeminiCliAdapter.convert_to_tape_jsonl(input),
         AdapterId::OpenClaw => OpenClawAdapter.convert_to_tape_jsonl(input),
+        AdapterId::ChatGpt => ChatGptAdapter.convert_to_tape_jsonl(input),
+        AdapterId::VsCodeChat => VsCodeChatAdapter.convert_to_tape_jsonl(input),
+    }
+}
+
+/// The input lines [`convert_with_adapter`] skips for adapter `id`.
+pub fn skipped_input_lines(id: AdapterId, input: &str) -> Vec<ParseIssue> {
+    if matches!(
+        id,
+        AdapterId::OpenCode | AdapterId::GeminiCli | AdapterId::ChatGpt | AdapterId::VsCodeChat
+    ) {
+        return Vec::new();
+    }
+    let cleaned = lenient_jsonl(input);
+    if cleaned.json_lines == 0 {
+        Vec::new()
+    } else {
+        cleaned.skipped
     }
 }
 
@@ -976,6 +1195,8 @@ pub fn discover_sessions_with_adapter(
         AdapterId::Cursor => CursorAdapter.discover_sessions_for_repo(repo_path, home_dir),
         AdapterId::GeminiCli => GeminiCliAdapter.discover_sessions_for_repo(repo_path, home_dir),
         AdapterId::OpenClaw => OpenClawAdapter.discover_sessions_for_repo(repo_path, home_dir),
+        AdapterId::ChatGpt => ChatGptAdapter.discover_sessions_for_repo(repo_path, home_dir),
+        AdapterId::VsCodeChat => VsCodeChatAdapter.discover_sessions_for_repo(repo_path, home_dir),
     }
 }
 
@@ -1096,11 +1317,11 @@ fn validate_contract_row(line: usize, row: &Value, issues: &mut Vec<ConformanceI
                         line,
                         detail: format!("meta missing string field `{field}`"),
                     });
-                } else if !obj
+                } else if obj
                     .get(field)
                     .and_then(Value::as_str)
                     .and_then(coverage_grade_from_str)
-                    .is_some()
+                    .is_none()
                 {
                     issues.push(ConformanceIssue {
                         line,
@@ -1117,7 +1338,7 @@ fn validate_contract_row(line: usize, row: &Value, issues: &mut Vec<ConformanceI
                     detail: "span.link missing string field `from_file`".to_string(),
                 });
             }
-            if !obj.get("from_range").is_some() {
+            if obj.get("from_range").is_none() {
                 issues.push(ConformanceIssue {
                     line,
                     detail: "span.link missing field `from_range`".to_string(),
@@ -1129,7 +1350,7 @@ fn validate_contract_row(line: usize, row: &Value, issues: &mut Vec<ConformanceI
                     detail: "span.link missing string field `to_file`".to_string(),
                 });
             }
-            if !obj.get("to_range").is_some() {
+            if obj.get("to_range").is_none() {
                 issues.push(ConformanceIssue {
                     line,
                     detail: "span.link missing field `to_range`".to_string(),
@@ -1143,7 +1364,7 @@ fn validate_contract_row(line: usize, row: &Value, issues: &mut Vec<ConformanceI
                     detail: "tool.call missing string field `tool`".to_string(),
                 });
             }
-            if !obj.get("args").is_some() {
+            if obj.get("args").is_none() {
                 issues.push(ConformanceIssue {
                     line,
                     detail: "tool.call missing field `args`".to_string(),
@@ -1165,7 +1386,7 @@ fn validate_contract_row(line: usize, row: &Value, issues: &mut Vec<ConformanceI
                     detail: "code.read missing string field `file`".to_string(),
                 });
             }
-            if !obj.get("range").is_some() {
+            if obj.get("range").is_none() {
                 issues.push(ConformanceIssue {
                     line,
                     detail: "code.read missing field `range`".to_string(),
@@ -1190,7 +1411,8 @@ fn validate_contract_row(line: usize, row: &Value, issues: &mut Vec<ConformanceI
 #[cfg(test)]
 mod tests {
     use super::{
-        AdapterId, AdapterStatus, CoverageGrade, adapter_registry, descriptor_for,
+        AdapterId, AdapterStatus, ArtifactLocation, CoverageGrade, adapter_registry,
+        adapter_version_is_older, artifact_location, descriptor_for,
         discover_sessions_with_adapter, discovery_scaffold, run_conformance,
     };
     use crate::anchor::{fingerprint_anchor_hashes, fingerprint_token_hashes};
@@ -1200,6 +1422,19 @@ mod tests {
     use std::fs;
     use std::process::Command;
 
+    #[test]
+    fn adapter_versions_compare_numerically() {
+        assert!(adapter_version_is_older("1.2.9", "1.10.0"));
+        assert!(adapter_version_is_older(
+            "0.9.0",
+            AdapterId::CodexCli.version()
+        ));
+        assert!(adapter_version_is_older("dev", "1.0.0"));
+        assert!(!adapter_version_is_older("1.0.0", "1.0.0"));
+        assert!(!adapter_version_is_older("2.0.0", "1.9.9"));
+        assert!(!adapter_version_is_older("1.0", "1.0.0.1"));
+    }
+
     #[test]
     fn codex_conformance_harness_passes() {
         let input = r#"{"timestamp":"2026-02-22T00:00:00Z","type":"session_meta","payload":{"model_provider":"openai","git":{"commit_hash":"abc123"}}}
@@ -1285,7 +1520,10 @@ mod tests {
         );
         // Evidence is stored at individual-token granularity; sample a few.
         let tokens = fingerprint_token_hashes(after_text);
-        assert!(tokens.len() >= 3, "expected token-level evidence, got {tokens:?}");
+        assert!(
+            tokens.len() >= 3,
+            "expected token-level evidence, got {tokens:?}"
+        );
 
         let index = SqliteIndex::open_in_memory().expect("sqlite");
         index
@@ -1364,6 +1602,35 @@ mod tests {
         assert_eq!(report.coverage.edit, CoverageGrade::Full);
     }
 
+    #[test]
+    fn chatgpt_export_conformance_harness_passes() {
+        let input = include_str!("../../tests/fixtures/chatgpt/conversations.json");
+        let report = run_conformance(AdapterId::ChatGpt, input).expect("adapter should parse");
+        assert_eq!(
+            report.event_count, 9,
+            "meta + 5 messages + 3 candidate edits"
+        );
+        assert!(report.issues.is_empty(), "issues={:?}", report.issues);
+        assert_eq!(report.coverage.tool, CoverageGrade::None);
+        assert_eq!(report.coverage.read, CoverageGrade::None);
+        assert_eq!(report.coverage.edit, CoverageGrade::Partial);
+    }
+
+    #[test]
+    fn vscode_chat_conformance_harness_passes() {
+        let state = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/vscode/chatEditingSessions/session-1/state.json");
+        let bundle = crate::tape::adapters::vscode::chat_editing_session_bundle(&state)
+            .expect("bundle session");
+        let input = String::from_utf8(bundle).expect("utf-8 bundle");
+        let report = run_conformance(AdapterId::VsCodeChat, &input).expect("adapter should parse");
+        assert_eq!(report.event_count, 4, "meta + one run per changed file");
+        assert!(report.issues.is_empty(), "issues={:?}", report.issues);
+        assert_eq!(report.coverage.tool, CoverageGrade::None);
+        assert_eq!(report.coverage.read, CoverageGrade::None);
+        assert_eq!(report.coverage.edit, CoverageGrade::Full);
+    }
+
     #[test]
     fn opencode_conformance_harness_passes() {
         let input = r#"{
@@ -1538,7 +1805,7 @@ mod tests {
 
     #[test]
     fn registry_covers_all_known_adapters() {
-        assert_eq!(adapter_registry().len(), 6);
+        assert_eq!(adapter_registry().len(), 8);
     }
 
     #[test]
@@ -1550,6 +1817,43 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn artifact_locations_split_fixed_dirs_from_placeholders() {
+        let location = |root: &str, pattern: &str, glob: Option<&str>| ArtifactLocation {
+            root: root.to_string(),
+            pattern: pattern.to_string(),
+            glob: glob.map(str::to_string),
+        };
+        assert_eq!(
+            artifact_location("~/.claude/projects/<project>/<session>.jsonl"),
+            Some(location("~/.claude/projects", "*.jsonl", Some("*/*.jsonl")))
+        );
+        assert_eq!(
+            artifact_location("~/.codex/sessions/YYYY/MM/DD/*.jsonl"),
+            Some(location(
+                "~/.codex/sessions",
+                "*.jsonl",
+                Some("*/*/*/*.jsonl")
+            ))
+        );
+        assert_eq!(
+            artifact_location("~/.codex/history.jsonl"),
+            Some(location("~/.codex", "history.jsonl", None))
+        );
+        assert_eq!(
+            artifact_location("~/.openclaw/sessions/**/*.jsonl"),
+            Some(location(
+                "~/.openclaw/sessions",
+                "*.jsonl",
+                Some("**/*.jsonl")
+            ))
+        );
+        assert_eq!(
+            artifact_location("<capture>/cursor-stream-jsonl.ndjson"),
+            None
+        );
+    }
+
     #[test]
     fn claude_discovery_finds_repo_bucket_sessions_and_subagents() {
         let temp = tempfile::tempdir().expect("tempdir");
@@ -0,0 +1,398 @@
+//! ChatGPT data exports: the `conversations.json` in the archive from
+//! Settings > Data controls > Export. The file holds every conversation, each
+//! a tree of message nodes (`mapping`) whose visible branch runs from
+//! `current_node` up through `parent` links; regenerated replies sit on
+//! other branches and are left out. One export becomes one tape, with each
+//! event's `source.session_id` naming its conversation.
+//!
+//! ChatGPT never sees the repo, so there are no reads or tool calls. Fenced
+//! code blocks in replies that name a file (` ```rust src/lib.rs `,
+//! ` ```rust:src/lib.rs `, ` ```toml title="Cargo.toml" ` or a first-line
+//! `// src/lib.rs` comment) become `code.edit` events marked
+//! `"candidate": true`: code proposed in the chat, which may or may not have
+//! been pasted into the repo. Code that changed on the way in can still be
+//! tied to the chat with a `span.link`.
+
+use std::collections::HashSet;
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+use serde::de::Error as _;
+use serde_json::{Map, Value, json};
+
+const HARNESS: &str = "chatgpt";
+
+pub fn chatgpt_json_to_tape_jsonl(input: &str) -> Result<String, serde_json::Error> {
+    let root: Value = serde_json::from_str(input)?;
+    let conversations = root
+        .as_array()
+        .filter(|rows| {
+            !rows.is_empty()
+                && rows
+                    .iter()
+                    .all(|row| row.get("mapping").is_some_and(Value::is_object))
+        })
+        .ok_or_else(|| {
+            serde_json::Error::custom("not a ChatGPT export: expected an array of conversations")
+        })?;
+
+    // Exports list the newest conversation first.
+    let mut conversations = conversations.iter().collect::<Vec<_>>();
+    conversations.sort_by(|a, b| epoch_seconds(a).total_cmp(&epoch_seconds(b)));
+
+    let first_timestamp = conversations
+        .first()
+        .and_then(|conversation| timestamp(conversation.get("create_time")))
+        .unwrap_or_else(|| "1970-01-01T00:00:00Z".to_string());
+    let mut model: Option<&str> = None;
+    let mut out = vec![json!({
+        "t": first_timestamp,
+        "k": "meta",
+        "source": source_block(None),
+        "coverage.tool": "none",
+        "coverage.read": "none",
+        // Code blocks are only candidates: what was applied is unknown.
+        "coverage.edit": "partial"
+    })];
+
+    for conversation in conversations {
+        let session_id = conversation
+            .get("conversation_id")
+            .or_else(|| conversation.get("id"))
+            .and_then(Value::as_str);
+        let default_timestamp =
+            timestamp(conversation.get("create_time")).unwrap_or_else(|| first_timestamp.clone());
+        let title = conversation.get("title").and_then(Value::as_str);
+        let mut titled = false;
+
+        for message in current_branch(conversation) {
+            let role = message
+                .get("author")
+                .and_then(|author| author.get("role"))
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            let hidden = message
+                .get("metadata")
+                .and_then(|metadata| metadata.get("is_visually_hidden_from_conversation"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let kind = match role {
+                "user" => "msg.in",
+                "assistant" => "msg.out",
+                _ => continue,
+            };
+            let content = message_text(message);
+            if hidden || content.trim().is_empty() {
+                continue;
+            }
+            let t =
+                timestamp(message.get("create_time")).unwrap_or_else(|| default_timestamp.clone());
+            if role == "assistant" && model.is_none() {
+                model = message
+                    .get("metadata")
+                    .and_then(|metadata| metadata.get("model_slug"))
+                    .and_then(Value::as_str);
+            }
+
+            let mut event = json!({
+                "t": t,
+                "k": kind,
+                "source": source_block(session_id),
+                "role": role,
+                "content": content
+            });
+            if !titled && let Some(title) = title {
+                event["conversation_title"] = json!(title);
+                titled = true;
+            }
+            out.push(event);
+
+            if role != "assistant" {
+                continue;
+            }
+            for block in fenced_blocks(&content) {
+                out.push(json!({
+                    "t": t,
+                    "k": "code.edit",
+                    "source": source_block(session_id),
+                    "file": block.file,
+                    "after_text": block.code,
+                    "candidate": true
+                }));
+            }
+        }
+    }
+
+    if let Some(model) = model
+        && let Some(meta) = out.first_mut().and_then(Value::as_object_mut)
+    {
+        meta.insert("model".to_string(), json!(model));
+    }
+    to_jsonl(&out)
+}
+
+/// The messages on the branch the conversation shows, oldest first.
+fn current_branch(conversation: &Value) -> Vec<&Map<String, Value>> {
+    let Some(mapping) = conversation.get("mapping").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let Some(mut node_id) = conversation.get("current_node").and_then(Value::as_str) else {
+        // No recorded branch: every message, in time order.
+        let mut messages = mapping
+            .values()
+            .filter_map(|node| node.get("message").and_then(Value::as_object))
+            .collect::<Vec<_>>();
+        messages.sort_by(|a, b| {
+            let at = |message: &Map<String, Value>| {
+                message
+                    .get("create_time")
+                    .and_then(Value::as_f64)
+                    .unwrap_or(0.0)
+            };
+            at(a).total_cmp(&at(b))
+        });
+        return messages;
+    };
+
+    let mut branch = Vec::new();
+    let mut seen = HashSet::new();
+    while seen.insert(node_id) {
+        let Some(node) = mapping.get(node_id) else {
+            break;
+        };
+        if let Some(message) = node.get("message").and_then(Value::as_object) {
+            branch.push(message);
+        }
+        let Some(parent) = node.get("parent").and_then(Value::as_str) else {
+            break;
+        };
+        node_id = parent;
+    }
+    branch.reverse();
+    branch
+}
+
+fn message_text(message: &Map<String, Value>) -> String {
+    let Some(content) = message.get("content") else {
+        return String::new();
+    };
+    if let Some(parts) = content.get("parts").and_then(Value::as_array) {
+        // Image and file parts are objects; only the text is kept.
+        return parts
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    content
+        .get("text")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_string()
+}
+
+struct FencedBlock {
+    file: String,
+    code: String,
+}
+
+/// The fenced code blocks in `text` that name a file.
+fn fenced_blocks(text: &str) -> Vec<FencedBlock> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        let fence_char = match trimmed.chars().next() {
+            Some(c @ ('`' | '~')) => c,
+            _ => continue,
+        };
+        let fence_len = trimmed.chars().take_while(|&c| c == fence_char).count();
+        if fence_len < 3 {
+            continue;
+        }
+        let info = trimmed[fence_len..].trim();
+        let mut body = Vec::new();
+        for line in lines.by_ref() {
+            let closing = line.trim();
+            if closing.len() >= fence_len && closing.chars().all(|c| c == fence_char) {
+                break;
+            }
+            body.push(line);
+        }
+
+        let file = match info_path_hint(info) {
+            Some(file) => Some(file),
+            None => match body.first().and_then(|first| comment_path_hint(first)) {
+                Some(file) => {
+                    body.remove(0);
+                    Some(file)
+                }
+                None => None,
+            },
+        };
+        if let Some(file) = file {
+            let mut code = body.join("\n");
+            code.push('\n');
+            blocks.push(FencedBlock { file, code });
+        }
+    }
+    blocks
+}
+
+/// A path in a fence's info string: `rust src/lib.rs`, `rust:src/lib.rs`,
+/// `src/lib.rs` or `title="src/lib.rs"`.
+fn info_path_hint(info: &str) -> Option<String> {
+    info.split_whitespace().find_map(|token| {
+        let token = ["title=", "file=", "filename=", "path="]
+            .iter()
+            .find_map(|prefix| token.strip_prefix(prefix))
+            .unwrap_or(token)
+            .trim_matches(['"', '\'']);
+        let token = token.split_once(':').map_or(token, |(_, path)| path);
+        looks_like_path(token).then(|| token.to_string())
+    })
+}
+
+/// A path named by a comment on a block's first line: `// src/lib.rs`,
+/// `# file: app.py`, `<!-- index.html -->`.
+fn comment_path_hint(line: &str) -> Option<String> {
+    let line = line.trim();
+    let body = ["//", "#", "--", "/*", "<!--", ";"]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))?;
+    let body = body
+        .trim()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim();
+    let body = ["file:", "path:", "filename:"]
+        .iter()
+        .find_map(|prefix| body.strip_prefix(prefix))
+        .unwrap_or(body)
+        .trim();
+    looks_like_path(body).then(|| body.to_string())
+}
+
+/// A relative or absolute file path with an extension, not a URL or a word.
+fn looks_like_path(token: &str) -> bool {
+    if token.is_empty() || token.contains("://") || token.contains(char::is_whitespace) {
+        return false;
+    }
+    let name = token.rsplit('/').next().unwrap_or(token);
+    let Some((_, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    (1..=10).contains(&extension.len())
+        && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        && token
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '@' | '+'))
+}
+
+fn epoch_seconds(conversation: &Value) -> f64 {
+    conversation
+        .get("create_time")
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0)
+}
+
+fn timestamp(value: Option<&Value>) -> Option<String> {
+    let seconds = value?.as_f64()?;
+    Utc.timestamp_millis_opt((seconds * 1000.0) as i64)
+        .single()
+        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+fn source_block(session_id: Option<&str>) -> Value {
+    match session_id {
+        Some(session_id) => json!({
+            "harness": HARNESS,
+            "session_id": session_id
+        }),
+        None => json!({
+            "harness": HARNESS
+        }),
+    }
+}
+
+fn to_jsonl(events: &[Value]) -> Result<String, serde_json::Error> {
+    let mut out = String::new();
+    for event in events {
+        out.push_str(&serde_json::to_string(event)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::chatgpt_json_to_tape_jsonl;
+
+    #[test]
+    fn chatgpt_export_follows_current_branch_and_emits_candidate_edits() {
+        let input = include_str!("../../../tests/fixtures/chatgpt/conversations.json");
+        let out = chatgpt_json_to_tape_jsonl(input).expect("adapter should parse");
+        let events: Vec<Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid JSON event"))
+            .collect();
+
+        let meta = &events[0];
+        assert_eq!(meta["k"], "meta");
+        assert_eq!(meta["source"]["harness"], "chatgpt");
+        assert_eq!(meta["coverage.edit"], "partial");
+        assert_eq!(meta["model"], "gpt-4o-mini");
+        assert_eq!(meta["t"], "2026-03-01T10:00:00Z");
+
+        // The earlier conversation comes first, then the current branch of
+        // the later one; the regenerated reply and hidden system prompt are
+        // left out.
+        let messages = events
+            .iter()
+            .filter(|event| event["k"] == "msg.in" || event["k"] == "msg.out")
+            .map(|event| {
+                (
+                    event["source"]["session_id"].as_str().unwrap_or(""),
+                    event["k"].as_str().unwrap_or(""),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                ("conv-earlier", "msg.in"),
+                ("conv-earlier", "msg.out"),
+                ("conv-retry", "msg.in"),
+                ("conv-retry", "msg.out"),
+                ("conv-retry", "msg.in"),
+            ]
+        );
+        assert!(!out.contains("abandoned branch"));
+        assert_eq!(events[1]["conversation_title"], "Earlier chat");
+
+        let edits = events
+            .iter()
+            .filter(|event| event["k"] == "code.edit")
+            .collect::<Vec<_>>();
+        let files = edits
+            .iter()
+            .map(|event| event["file"].as_str().unwrap_or(""))
+            .collect::<Vec<_>>();
+        assert_eq!(files, vec!["Cargo.toml", "src/retry.rs", "src/client.rs"]);
+        assert!(edits.iter().all(|event| event["candidate"] == true));
+        assert_eq!(
+            edits[1]["after_text"],
+            "pub fn backoff(attempt: u32) -> u64 {\n    100 * 2u64.pow(attempt)\n}\n"
+        );
+        assert_eq!(edits[2]["after_text"], "let delay = backoff(attempt);\n");
+        assert_eq!(edits[1]["t"], "2026-03-02T10:00:30Z");
+    }
+
+    #[test]
+    fn other_json_is_not_a_chatgpt_export() {
+        assert!(chatgpt_json_to_tape_jsonl("[]").is_err());
+        assert!(chatgpt_json_to_tape_jsonl(r#"[{"type":"user","message":"hi"}]"#).is_err());
+        assert!(chatgpt_json_to_tape_jsonl(r#"{"sessionId":"s","messages":[]}"#).is_err());
+    }
+}
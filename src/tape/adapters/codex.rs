@@ -48,11 +48,16 @@ pub fn codex_jsonl_to_tape_jsonl(input: &str) -> Result<String, serde_json::Erro
                     .and_then(|git| git.get("commit_hash"))
                     .and_then(Value::as_str)
                     .map(ToOwned::to_owned);
+                let cwd = payload
+                    .and_then(|obj| obj.get("cwd"))
+                    .and_then(Value::as_str)
+                    .map(ToOwned::to_owned);
                 out.push(codex_meta_event(
                     timestamp,
                     session_id.as_deref(),
                     model,
                     repo_head,
+                    cwd,
                 ));
                 emitted_meta = true;
             }
@@ -175,6 +180,7 @@ pub fn codex_jsonl_to_tape_jsonl(input: &str) -> Result<String, serde_json::Erro
                 session_id.as_deref(),
                 None,
                 None,
+                None,
             ),
         );
     }
@@ -249,6 +255,7 @@ fn codex_meta_event(
     session_id: Option<&str>,
     model: Option<String>,
     repo_head: Option<String>,
+    cwd: Option<String>,
 ) -> Value {
     let mut event = serde_json::Map::new();
     event.insert("t".to_string(), json!(timestamp));
@@ -263,6 +270,9 @@ fn codex_meta_event(
     if repo_head.is_some() {
         event.insert("repo_head".to_string(), json!(repo_head));
     }
+    if cwd.is_some() {
+        event.insert("cwd".to_string(), json!(cwd));
+    }
     Value::Object(event)
 }
 
@@ -411,13 +421,14 @@ mod tests {
 
     #[test]
     fn codex_adapter_emits_tool_and_apply_patch_edit() {
-        let input = r#"{"timestamp":"2026-02-22T00:00:00Z","type":"session_meta","payload":{"model_provider":"openai","git":{"commit_hash":"abc123"}}}
+        let input = r#"{"timestamp":"2026-02-22T00:00:00Z","type":"session_meta","payload":{"model_provider":"openai","cwd":"/work/repo","git":{"commit_hash":"abc123"}}}
 {"timestamp":"2026-02-22T00:00:01Z","type":"response_item","payload":{"type":"function_call","name":"exec_command","call_id":"call_1","arguments":"{\"cmd\":\"echo hi\"}"}}
 {"timestamp":"2026-02-22T00:00:02Z","type":"response_item","payload":{"type":"function_call_output","call_id":"call_1","output":"Process exited with code 7\nOutput:\nboom"}}
 {"timestamp":"2026-02-22T00:00:03Z","type":"response_item","payload":{"type":"function_call","name":"apply_patch","call_id":"call_2","arguments":"*** Begin Patch\n*** Update File: src/main.rs\n*** End Patch\n"}} "#;
 
         let out = codex_jsonl_to_tape_jsonl(input).expect("adapter should parse");
         assert!(out.contains(r#""k":"meta""#), "out={out}");
+        assert!(out.contains(r#""cwd":"/work/repo""#), "out={out}");
         assert!(out.contains(r#""k":"tool.call""#), "out={out}");
         assert!(out.contains(r#""tool":"exec_command""#), "out={out}");
         assert!(out.contains(r#""k":"tool.result""#), "out={out}");
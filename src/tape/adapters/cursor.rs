@@ -42,19 +42,21 @@ pub fn cursor_jsonl_to_tape_jsonl(input: &str) -> Result<String, serde_json::Err
 
         let row_type = row.get("type").and_then(Value::as_str).unwrap_or("");
         match row_type {
-            "system" => {
-                if row.get("subtype").and_then(Value::as_str) == Some("init") {
-                    out.push(json!({
-                        "t": timestamp,
-                        "k": "meta",
-                        "source": cursor_source(session_id.as_deref()),
-                        "model": row.get("model").and_then(Value::as_str),
-                        "coverage.tool": CURSOR_COVERAGE_TOOL,
-                        "coverage.read": CURSOR_COVERAGE_READ,
-                        "coverage.edit": CURSOR_COVERAGE_EDIT
-                    }));
-                    emitted_meta = true;
+            "system" if row.get("subtype").and_then(Value::as_str) == Some("init") => {
+                let mut meta = json!({
+                    "t": timestamp,
+                    "k": "meta",
+                    "source": cursor_source(session_id.as_deref()),
+                    "model": row.get("model").and_then(Value::as_str),
+                    "coverage.tool": CURSOR_COVERAGE_TOOL,
+                    "coverage.read": CURSOR_COVERAGE_READ,
+                    "coverage.edit": CURSOR_COVERAGE_EDIT
+                });
+                if let Some(cwd) = row.get("cwd").and_then(Value::as_str) {
+                    meta["cwd"] = json!(cwd);
                 }
+                out.push(meta);
+                emitted_meta = true;
             }
             "user" | "assistant" => {
                 let message = row.get("message").and_then(Value::as_object);
@@ -247,10 +247,11 @@ fn extract_gemini_tool_result(tool_call: &Value) -> (String, String, i32) {
         }
     }
 
-    if stdout.is_empty() && stderr.is_empty() {
-        if let Some(display) = tool_call.get("resultDisplay").and_then(Value::as_str) {
-            stdout = display.to_string();
-        }
+    if stdout.is_empty()
+        && stderr.is_empty()
+        && let Some(display) = tool_call.get("resultDisplay").and_then(Value::as_str)
+    {
+        stdout = display.to_string();
     }
 
     (stdout, stderr, exit)
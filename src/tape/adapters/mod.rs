@@ -1,13 +1,17 @@
+pub mod chatgpt;
 pub mod claude;
 pub mod codex;
 pub mod cursor;
 pub mod gemini;
 pub mod openclaw;
 pub mod opencode;
+pub mod vscode;
 
+pub use chatgpt::chatgpt_json_to_tape_jsonl;
 pub use claude::claude_jsonl_to_tape_jsonl;
 pub use codex::codex_jsonl_to_tape_jsonl;
 pub use cursor::cursor_jsonl_to_tape_jsonl;
 pub use gemini::gemini_json_to_tape_jsonl;
 pub use openclaw::openclaw_jsonl_to_tape_jsonl;
 pub use opencode::opencode_json_to_tape_jsonl;
+pub use vscode::vscode_chat_json_to_tape_jsonl;
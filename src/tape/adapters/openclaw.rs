@@ -402,9 +402,7 @@ fn extract_line_range(args: &Value) -> [u32; 2] {
 }
 
 fn extract_before_text(args: &Value) -> Option<String> {
-    let Some(obj) = args.as_object() else {
-        return None;
-    };
+    let obj = args.as_object()?;
     obj.get("old_string")
         .and_then(Value::as_str)
         .or_else(|| obj.get("oldString").and_then(Value::as_str))
@@ -413,9 +411,7 @@ fn extract_before_text(args: &Value) -> Option<String> {
 }
 
 fn extract_after_text(args: &Value) -> Option<String> {
-    let Some(obj) = args.as_object() else {
-        return None;
-    };
+    let obj = args.as_object()?;
     obj.get("new_string")
         .and_then(Value::as_str)
         .or_else(|| obj.get("newString").and_then(Value::as_str))
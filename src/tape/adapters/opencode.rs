@@ -101,23 +101,23 @@ pub fn opencode_json_to_tape_jsonl(input: &str) -> Result<String, serde_json::Er
                         }
                         out.push(Value::Object(call));
 
-                        if tool.eq_ignore_ascii_case("read") {
-                            if let Some(file) = tool_input
+                        if tool.eq_ignore_ascii_case("read")
+                            && let Some(file) = tool_input
                                 .get("filePath")
                                 .and_then(Value::as_str)
                                 .map(ToOwned::to_owned)
-                            {
-                                let start_zero = tool_input
-                                    .get("offset")
-                                    .and_then(Value::as_u64)
-                                    .unwrap_or(0);
-                                let start = start_zero.saturating_add(1) as u32;
-                                let end = tool_input
-                                    .get("limit")
-                                    .and_then(Value::as_u64)
-                                    .map(|n| start.saturating_add((n as u32).saturating_sub(1)))
-                                    .unwrap_or(start);
-                                out.push(json!({
+                        {
+                            let start_zero = tool_input
+                                .get("offset")
+                                .and_then(Value::as_u64)
+                                .unwrap_or(0);
+                            let start = start_zero.saturating_add(1) as u32;
+                            let end = tool_input
+                                .get("limit")
+                                .and_then(Value::as_u64)
+                                .map(|n| start.saturating_add((n as u32).saturating_sub(1)))
+                                .unwrap_or(start);
+                            out.push(json!({
                                     "t": timestamp,
                                     "k": "code.read",
                                     "source": source_block("opencode", session_id.as_deref()),
@@ -126,40 +126,37 @@ pub fn opencode_json_to_tape_jsonl(input: &str) -> Result<String, serde_json::Er
                                     "text": state.and_then(|obj| obj.get("output")).and_then(Value::as_str),
                                     "range_basis": "line"
                                 }));
-                            }
                         }
 
-                        if tool.eq_ignore_ascii_case("edit") {
-                            if let Some(file) = tool_input
+                        if tool.eq_ignore_ascii_case("edit")
+                            && let Some(file) = tool_input
                                 .get("filePath")
                                 .and_then(Value::as_str)
                                 .map(ToOwned::to_owned)
-                            {
-                                out.push(json!({
-                                    "t": timestamp,
-                                    "k": "code.edit",
-                                    "source": source_block("opencode", session_id.as_deref()),
-                                    "file": file,
-                                    "before_text": tool_input.get("oldString").and_then(Value::as_str),
-                                    "after_text": tool_input.get("newString").and_then(Value::as_str)
-                                }));
-                            }
+                        {
+                            out.push(json!({
+                                "t": timestamp,
+                                "k": "code.edit",
+                                "source": source_block("opencode", session_id.as_deref()),
+                                "file": file,
+                                "before_text": tool_input.get("oldString").and_then(Value::as_str),
+                                "after_text": tool_input.get("newString").and_then(Value::as_str)
+                            }));
                         }
 
-                        if tool.eq_ignore_ascii_case("write") {
-                            if let Some(file) = tool_input
+                        if tool.eq_ignore_ascii_case("write")
+                            && let Some(file) = tool_input
                                 .get("filePath")
                                 .and_then(Value::as_str)
                                 .map(ToOwned::to_owned)
-                            {
-                                out.push(json!({
-                                    "t": timestamp,
-                                    "k": "code.edit",
-                                    "source": source_block("opencode", session_id.as_deref()),
-                                    "file": file,
-                                    "after_text": tool_input.get("content").and_then(Value::as_str)
-                                }));
-                            }
+                        {
+                            out.push(json!({
+                                "t": timestamp,
+                                "k": "code.edit",
+                                "source": source_block("opencode", session_id.as_deref()),
+                                "file": file,
+                                "after_text": tool_input.get("content").and_then(Value::as_str)
+                            }));
                         }
 
                         if tool.eq_ignore_ascii_case("patch") {
@@ -0,0 +1,362 @@
+//! VS Code chat editing sessions (Copilot edits, agent mode and inline
+//! chat): `workspaceStorage/<workspace>/chatEditingSessions/<session>/`
+//! holds a `state.json` and a `contents/` directory of document snapshots
+//! named by hash. `state.json` lists the files each request touched:
+//! `initialFileContents` pairs a file URI with the hash of its content when
+//! the session began, and every `linearHistory[]` request's last stop has an
+//! entry per file with the `currentHash` the request left it at.
+//!
+//! The snapshots live next to the state, so ingest reads the session through
+//! [`chat_editing_session_bundle`], which inlines them as `snapshotContents`
+//! (hash to text) with their write times as `snapshotTimes`. Each request's
+//! change to a file becomes one `code.edit` per run of changed lines, with
+//! real line ranges and before/after text. Edits the user rejected
+//! (`state: 2`) are left out. Prompts are stored elsewhere (`chatSessions/`),
+//! so these tapes carry edits only.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::Error as _;
+use serde_json::{Map, Value, json};
+
+use crate::tape::patch::changed_lines;
+
+const HARNESS: &str = "vscode-chat";
+
+/// `ModifiedFileEntryState.Rejected` in VS Code.
+const ENTRY_REJECTED: u64 = 2;
+
+/// Whether `path` is the `state.json` of a chat editing session.
+pub fn is_chat_editing_state_path(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == "state.json")
+        && path
+            .parent()
+            .and_then(Path::parent)
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == "chatEditingSessions")
+}
+
+/// `state.json` with the session's snapshots inlined, as the adapter reads
+/// it. Snapshots that are not UTF-8 are left out.
+pub fn chat_editing_session_bundle(state_path: &Path) -> io::Result<Vec<u8>> {
+    let mut state: Value = serde_json::from_slice(&fs::read(state_path)?)?;
+    let Some(object) = state.as_object_mut() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "chat editing state is not a JSON object",
+        ));
+    };
+    let mut contents = Map::new();
+    let mut times = Map::new();
+    let contents_dir = state_path.with_file_name("contents");
+    if let Ok(entries) = fs::read_dir(&contents_dir) {
+        for entry in entries.filter_map(Result::ok) {
+            let Some(hash) = entry.file_name().to_str().map(ToOwned::to_owned) else {
+                continue;
+            };
+            let Ok(text) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            if let Ok(modified) = entry.metadata().and_then(|meta| meta.modified()) {
+                times.insert(hash.clone(), json!(rfc3339(modified.into())));
+            }
+            contents.insert(hash, json!(text));
+        }
+    }
+    object.insert("snapshotContents".to_string(), Value::Object(contents));
+    object.insert("snapshotTimes".to_string(), Value::Object(times));
+    if let Ok(modified) = fs::metadata(state_path).and_then(|meta| meta.modified()) {
+        object.insert("stateModified".to_string(), json!(rfc3339(modified.into())));
+    }
+    Ok(serde_json::to_vec(&state)?)
+}
+
+pub fn vscode_chat_json_to_tape_jsonl(input: &str) -> Result<String, serde_json::Error> {
+    let root: Value = serde_json::from_str(input)?;
+    let history = root
+        .get("linearHistory")
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            serde_json::Error::custom("not a VS Code chat editing session: no linearHistory")
+        })?;
+    let session_id = root.get("sessionId").and_then(Value::as_str);
+    let contents = root.get("snapshotContents").and_then(Value::as_object);
+    let times = root.get("snapshotTimes").and_then(Value::as_object);
+    let default_timestamp = root
+        .get("stateModified")
+        .and_then(Value::as_str)
+        .unwrap_or("1970-01-01T00:00:00Z");
+    let text_of = |hash: Option<&str>| hash.and_then(|hash| contents?.get(hash)?.as_str());
+
+    // The hash each file is at, starting from its content when the session
+    // began.
+    let mut current = HashMap::<&str, &str>::new();
+    if let Some(initial) = root.get("initialFileContents").and_then(Value::as_array) {
+        for pair in initial {
+            if let (Some(resource), Some(hash)) = (
+                pair.get(0).and_then(Value::as_str),
+                pair.get(1).and_then(Value::as_str),
+            ) {
+                current.insert(resource, hash);
+            }
+        }
+    }
+
+    let mut out = vec![json!({
+        "t": default_timestamp,
+        "k": "meta",
+        "source": source_block(session_id),
+        "coverage.tool": "none",
+        "coverage.read": "none",
+        "coverage.edit": "full"
+    })];
+    let mut model: Option<&str> = None;
+    let mut edit_total = 0u32;
+    let mut edit_emitted = 0u32;
+
+    for request in history {
+        let request_id = request.get("requestId").and_then(Value::as_str);
+        let Some(entries) = request
+            .get("stops")
+            .and_then(Value::as_array)
+            .and_then(|stops| stops.last())
+            .and_then(|stop| stop.get("entries"))
+            .and_then(Value::as_array)
+        else {
+            continue;
+        };
+        for entry in entries {
+            let Some(resource) = entry.get("resource").and_then(Value::as_str) else {
+                continue;
+            };
+            let Some(after_hash) = entry.get("currentHash").and_then(Value::as_str) else {
+                continue;
+            };
+            let before_hash = current
+                .get(resource)
+                .copied()
+                .or_else(|| entry.get("originalHash").and_then(Value::as_str));
+            if before_hash == Some(after_hash)
+                || entry.get("state").and_then(Value::as_u64) == Some(ENTRY_REJECTED)
+            {
+                continue;
+            }
+            current.insert(resource, after_hash);
+            edit_total = edit_total.saturating_add(1);
+
+            let telemetry = entry.get("telemetryInfo");
+            let agent = telemetry
+                .and_then(|info| info.get("agentId"))
+                .and_then(Value::as_str);
+            if model.is_none() {
+                model = telemetry
+                    .and_then(|info| info.get("modelId"))
+                    .and_then(Value::as_str);
+            }
+            let timestamp = times
+                .and_then(|times| times.get(after_hash))
+                .and_then(Value::as_str)
+                .unwrap_or(default_timestamp);
+            let file = file_uri_path(resource);
+            let edit = json!({
+                "t": timestamp,
+                "k": "code.edit",
+                "source": source_block(session_id),
+                "file": file,
+                "request_id": request_id,
+                "agent": agent
+            });
+
+            let (Some(before), Some(after)) = (text_of(before_hash), text_of(Some(after_hash)))
+            else {
+                // The snapshots are gone: the file was edited, but not where.
+                out.push(edit);
+                continue;
+            };
+            edit_emitted = edit_emitted.saturating_add(1);
+            for run in changed_lines(before, after) {
+                let mut event = edit.clone();
+                if run.before_lines > 0 {
+                    event["before_range"] =
+                        json!([run.before_start, run.before_start + run.before_lines - 1]);
+                    event["before_text"] = json!(run.before_text);
+                }
+                if run.after_lines > 0 {
+                    event["after_range"] =
+                        json!([run.after_start, run.after_start + run.after_lines - 1]);
+                    event["after_text"] = json!(run.after_text);
+                }
+                out.push(event);
+            }
+        }
+    }
+
+    if let Some(meta) = out.first_mut().and_then(Value::as_object_mut) {
+        if edit_emitted < edit_total {
+            meta.insert("coverage.edit".to_string(), json!("partial"));
+        }
+        if let Some(model) = model {
+            meta.insert("model".to_string(), json!(model));
+        }
+    }
+    to_jsonl(&out)
+}
+
+/// The filesystem path of a `file://` URI; other URIs are kept as they are.
+fn file_uri_path(uri: &str) -> String {
+    let Some(path) = uri.strip_prefix("file://") else {
+        return uri.to_string();
+    };
+    let decoded = percent_decode(path);
+    // `file:///c%3A/repo` is `c:/repo` on Windows.
+    match decoded.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => decoded[1..].to_string(),
+        _ => decoded,
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%'
+            && let Some(byte) = text
+                .get(idx + 1..idx + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(byte);
+            idx += 3;
+            continue;
+        }
+        out.push(bytes[idx]);
+        idx += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn rfc3339(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn source_block(session_id: Option<&str>) -> Value {
+    match session_id {
+        Some(session_id) => json!({
+            "harness": HARNESS,
+            "session_id": session_id
+        }),
+        None => json!({
+            "harness": HARNESS
+        }),
+    }
+}
+
+fn to_jsonl(events: &[Value]) -> Result<String, serde_json::Error> {
+    let mut out = String::new();
+    for event in events {
+        out.push_str(&serde_json::to_string(event)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::{Value, json};
+
+    use super::{
+        chat_editing_session_bundle, file_uri_path, is_chat_editing_state_path,
+        vscode_chat_json_to_tape_jsonl,
+    };
+
+    fn fixture_state() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/vscode/chatEditingSessions/session-1/state.json")
+    }
+
+    fn events(input: &str) -> Vec<Value> {
+        vscode_chat_json_to_tape_jsonl(input)
+            .expect("adapter should parse")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid JSON event"))
+            .collect()
+    }
+
+    #[test]
+    fn vscode_chat_session_emits_changed_runs_and_skips_rejected_edits() {
+        let state = fixture_state();
+        assert!(is_chat_editing_state_path(&state));
+        let bundle = chat_editing_session_bundle(&state).expect("bundle session");
+        let events = events(std::str::from_utf8(&bundle).expect("utf-8 bundle"));
+
+        let meta = &events[0];
+        assert_eq!(meta["k"], "meta");
+        assert_eq!(meta["source"]["harness"], "vscode-chat");
+        assert_eq!(meta["source"]["session_id"], "session-1");
+        assert_eq!(meta["coverage.edit"], "full");
+        assert_eq!(meta["model"], "gpt-4.1");
+
+        let edits = &events[1..];
+        assert!(edits.iter().all(|event| event["k"] == "code.edit"));
+        let summary = edits
+            .iter()
+            .map(|event| {
+                (
+                    event["request_id"].as_str().unwrap_or(""),
+                    event["file"].as_str().unwrap_or(""),
+                    event["after_range"].clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        // request_2 was rejected, so request_3 diffs against request_1's
+        // content, and the unchanged new file is not repeated.
+        assert_eq!(
+            summary,
+            vec![
+                ("request_1", "/repo/src/lib.rs", json!([2, 2])),
+                ("request_1", "/repo/src/back off.rs", json!([1, 3])),
+                ("request_3", "/repo/src/lib.rs", json!([6, 10])),
+            ]
+        );
+        assert_eq!(edits[0]["before_text"], "    3\n");
+        assert_eq!(edits[0]["after_text"], "    5\n");
+        assert_eq!(edits[0]["agent"], "github.copilot.editsAgent");
+        assert!(edits[1].get("before_range").is_none());
+        assert_eq!(edits[2]["before_range"], json!([6, 6]));
+        assert_eq!(edits[2]["before_text"], "    \"sync\"\n");
+        assert!(
+            edits[2]["after_text"]
+                .as_str()
+                .is_some_and(|text| text.contains("sync-client") && text.contains("timeout_ms"))
+        );
+    }
+
+    #[test]
+    fn vscode_chat_session_without_snapshots_has_partial_edit_coverage() {
+        let input = std::fs::read_to_string(fixture_state()).expect("read state");
+        let events = events(&input);
+        assert_eq!(events[0]["coverage.edit"], "partial");
+        assert_eq!(events.len(), 4, "meta + one file-only edit per change");
+        assert!(events[1].get("after_range").is_none());
+        assert_eq!(events[1]["file"], "/repo/src/lib.rs");
+    }
+
+    #[test]
+    fn vscode_chat_rejects_other_json() {
+        assert!(vscode_chat_json_to_tape_jsonl(r#"{"mapping": {}}"#).is_err());
+        assert!(!is_chat_editing_state_path(Path::new("/tmp/state.json")));
+    }
+
+    #[test]
+    fn file_uris_decode_to_paths() {
+        assert_eq!(file_uri_path("file:///repo/a%20b.rs"), "/repo/a b.rs");
+        assert_eq!(file_uri_path("file:///c%3A/repo/x.rs"), "c:/repo/x.rs");
+        assert_eq!(file_uri_path("untitled:Untitled-1"), "untitled:Untitled-1");
+    }
+}
@@ -0,0 +1,259 @@
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::config::{BinaryFilePolicy, BinaryPolicy};
+use crate::tape::notebook::{is_notebook_path, notebook_view};
+
+/// Extensions of files whose content is not source text.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "icns", "webp", "tif", "tiff", "psd", "pdf", "zip",
+    "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "jar", "war", "class", "so", "dylib", "dll",
+    "exe", "o", "a", "lib", "bin", "wasm", "pyc", "woff", "woff2", "ttf", "otf", "eot", "mp3",
+    "mp4", "mov", "avi", "wav", "flac", "ogg", "webm", "sqlite", "db", "parquet", "avro", "onnx",
+    "pt", "pth", "npy", "npz", "h5", "pkl",
+];
+
+/// Suffixes of build outputs that are text but not written by hand.
+const GENERATED_SUFFIXES: &[&str] = &[".min.js", ".min.css", ".js.map", ".css.map"];
+
+/// Evidence text fields per event kind, with the anchor field precomputed
+/// from each.
+const EVIDENCE_FIELDS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "code.edit",
+        &[
+            ("before_text", "before_anchor_hashes"),
+            ("after_text", "after_anchor_hashes"),
+        ],
+    ),
+    ("code.read", &[("text", "anchor_hashes")]),
+];
+
+/// Only this much of a text is sniffed for binary content.
+const SNIFF_CHARS: usize = 8000;
+
+/// What [`apply_binary_policy`] did to one tape.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BinaryCounts {
+    /// Events dropped under `skip`.
+    pub skipped: usize,
+    /// Events kept without their text under `evidence-only`.
+    pub evidence_only: usize,
+}
+
+/// Why `file` (with evidence `texts`) is treated as binary: `extension`,
+/// `generated`, `content` (NUL or control characters, or bytes that were
+/// not UTF-8) or `size` (a text over `max_text_bytes`).
+pub fn binary_reason(file: &str, texts: &[&str], max_text_bytes: usize) -> Option<&'static str> {
+    let name = file
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(file)
+        .to_ascii_lowercase();
+    if name
+        .rsplit_once('.')
+        .is_some_and(|(_, extension)| BINARY_EXTENSIONS.contains(&extension))
+    {
+        return Some("extension");
+    }
+    if GENERATED_SUFFIXES
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+    {
+        return Some("generated");
+    }
+    if texts.iter().any(|text| looks_binary(text)) {
+        return Some("content");
+    }
+    // A notebook is measured by its cell view: outputs such as embedded
+    // images do not count (see `tape::notebook`).
+    let size = |text: &str| {
+        if is_notebook_path(file) {
+            notebook_view(text).map_or(text.len(), |view| view.len())
+        } else {
+            text.len()
+        }
+    };
+    if texts.iter().any(|text| size(text) > max_text_bytes) {
+        return Some("size");
+    }
+    None
+}
+
+fn looks_binary(text: &str) -> bool {
+    let mut total = 0usize;
+    let mut control = 0usize;
+    for c in text.chars().take(SNIFF_CHARS) {
+        if c == '\0' || c == char::REPLACEMENT_CHARACTER {
+            return true;
+        }
+        if c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b') {
+            control += 1;
+        }
+        total += 1;
+    }
+    total > 0 && control * 10 > total
+}
+
+/// Apply `policy` to the `code.read` / `code.edit` events of a tape whose
+/// file [`binary_reason`] flags: `skip` drops them, `evidence-only` keeps
+/// them with each text replaced by `<field>_omitted` (its size and SHA-256)
+/// and the reason under `binary`, `full` leaves the tape alone. Other lines
+/// pass through byte for byte.
+pub fn apply_binary_policy(
+    jsonl: &str,
+    policy: &BinaryFilePolicy,
+) -> serde_json::Result<(String, BinaryCounts)> {
+    let mut counts = BinaryCounts::default();
+    if policy.policy == BinaryPolicy::Full {
+        return Ok((jsonl.to_string(), counts));
+    }
+    let mut out = String::with_capacity(jsonl.len());
+    for line in jsonl.lines() {
+        if !line.contains("\"code.edit\"") && !line.contains("\"code.read\"") {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let mut row: Value = serde_json::from_str(line)?;
+        let kind = row.get("k").and_then(Value::as_str).unwrap_or_default();
+        let Some(fields) = EVIDENCE_FIELDS
+            .iter()
+            .find(|(event_kind, _)| *event_kind == kind)
+            .map(|(_, fields)| *fields)
+        else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+        let file = row.get("file").and_then(Value::as_str).unwrap_or_default();
+        let texts = fields
+            .iter()
+            .filter_map(|(field, _)| row.get(*field).and_then(Value::as_str))
+            .collect::<Vec<_>>();
+        let Some(reason) = binary_reason(file, &texts, policy.max_text_bytes) else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+        if policy.policy == BinaryPolicy::Skip {
+            counts.skipped += 1;
+            continue;
+        }
+        counts.evidence_only += 1;
+        let Some(object) = row.as_object_mut() else {
+            continue;
+        };
+        for (field, anchors) in fields {
+            object.remove(*anchors);
+            if let Some(Value::String(text)) = object.remove(*field) {
+                object.insert(
+                    format!("{field}_omitted"),
+                    json!({
+                        "bytes": text.len(),
+                        "sha256": format!("{:x}", Sha256::digest(text.as_bytes())),
+                    }),
+                );
+            }
+        }
+        object.insert("binary".to_string(), json!(reason));
+        out.push_str(&serde_json::to_string(&row)?);
+        out.push('\n');
+    }
+    Ok((out, counts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_binary_generated_and_oversized_files() {
+        assert_eq!(
+            binary_reason("assets/Logo.PNG", &[], 1024),
+            Some("extension")
+        );
+        assert_eq!(
+            binary_reason("dist/app.min.js", &["x"], 1024),
+            Some("generated")
+        );
+        assert_eq!(
+            binary_reason("data/blob", &["GIF89a\0\0"], 1024),
+            Some("content")
+        );
+        assert_eq!(
+            binary_reason("data/blob", &["\u{1}\u{2}\u{3}ab"], 1024),
+            Some("content")
+        );
+        assert_eq!(
+            binary_reason("src/big.rs", &[&"a".repeat(2048)], 1024),
+            Some("size")
+        );
+        assert_eq!(
+            binary_reason("src/lib.rs", &["fn main() {}\n\tok\r\n"], 1024),
+            None
+        );
+        assert_eq!(binary_reason("src/target.rs", &[], 1024), None);
+        let notebook = format!(
+            r#"{{"cells": [{{"cell_type": "code", "source": ["x = 1"], "outputs": ["{}"]}}]}}"#,
+            "A".repeat(2048)
+        );
+        assert_eq!(binary_reason("nb/plot.ipynb", &[&notebook], 1024), None);
+    }
+
+    #[test]
+    fn policies_skip_strip_or_keep_binary_evidence() {
+        let jsonl = concat!(
+            r#"{"t":"T1","k":"code.edit","file":"img/logo.png","after_text":"\u0089PNG\u0000","after_anchor_hashes":["winnow:a"]}"#,
+            "\n",
+            r#"{"t":"T2","k":"code.edit","file":"src/main.rs","after_text":"fn main() {}"}"#,
+            "\n",
+            r#"{"t":"T3","k":"code.read","file":"dist/app.js.map","range":[1,1],"text":"{}"}"#,
+            "\n",
+            r#"{"t":"T4","k":"tool.call","tool":"cat","args":"logo.png"}"#,
+            "\n",
+        );
+        let policy = |policy| BinaryFilePolicy {
+            policy,
+            ..BinaryFilePolicy::default()
+        };
+
+        let (kept, counts) = apply_binary_policy(jsonl, &policy(BinaryPolicy::Skip)).expect("skip");
+        assert_eq!(
+            counts,
+            BinaryCounts {
+                skipped: 2,
+                evidence_only: 0
+            }
+        );
+        assert_eq!(
+            kept.lines().collect::<Vec<_>>(),
+            vec![jsonl.lines().nth(1).unwrap(), jsonl.lines().nth(3).unwrap()]
+        );
+
+        let (kept, counts) =
+            apply_binary_policy(jsonl, &policy(BinaryPolicy::EvidenceOnly)).expect("evidence");
+        assert_eq!(
+            counts,
+            BinaryCounts {
+                skipped: 0,
+                evidence_only: 2
+            }
+        );
+        let rows = kept
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0]["binary"], "extension");
+        assert!(rows[0].get("after_text").is_none());
+        assert!(rows[0].get("after_anchor_hashes").is_none());
+        assert_eq!(rows[0]["after_text_omitted"]["bytes"], 6);
+        assert_eq!(rows[2]["binary"], "generated");
+        assert_eq!(rows[2]["range"], json!([1, 1]));
+        assert_eq!(kept.lines().nth(1), jsonl.lines().nth(1));
+
+        let (kept, counts) = apply_binary_policy(jsonl, &policy(BinaryPolicy::Full)).expect("full");
+        assert_eq!((kept.as_str(), counts), (jsonl, BinaryCounts::default()));
+    }
+}
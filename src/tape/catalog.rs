@@ -0,0 +1,102 @@
+use serde_json::Value;
+
+use crate::tape::paths::repo_relative_path;
+
+/// The queryable columns of one tape event, stored in the index so
+/// `engram events` can filter across every tape without decompressing them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRecord {
+    /// Line number in the tape, as used for evidence offsets.
+    pub offset: u64,
+    pub kind: String,
+    pub tool: Option<String>,
+    /// Repo-relative when the tape's meta names the project root.
+    pub file: Option<String>,
+    pub timestamp: String,
+    /// Exit code of a `tool.result`.
+    pub exit: Option<i64>,
+    /// Size of a `tool.result`'s stdout and stderr, before any sampling.
+    pub output_bytes: Option<u64>,
+}
+
+/// One [`EventRecord`] per event of a normalized tape. Lines that fail to
+/// parse or have no kind are skipped.
+pub fn event_records_from_jsonl(input: &str) -> Vec<EventRecord> {
+    let rows = input
+        .lines()
+        .enumerate()
+        .filter_map(|(offset, line)| {
+            let row = serde_json::from_str::<Value>(line).ok()?;
+            Some((offset as u64, row))
+        })
+        .collect::<Vec<_>>();
+    let project_root = rows.iter().find_map(|(_, row)| {
+        (row.get("k").and_then(Value::as_str) == Some("meta"))
+            .then(|| row.get("cwd").and_then(Value::as_str))
+            .flatten()
+    });
+    let field = |row: &Value, name: &str| row.get(name).and_then(Value::as_str).map(str::to_owned);
+    rows.iter()
+        .filter_map(|(offset, row)| {
+            Some(EventRecord {
+                offset: *offset,
+                kind: field(row, "k")?,
+                tool: field(row, "tool"),
+                file: field(row, "file")
+                    .map(|file| repo_relative_path(&file, project_root.map(std::path::Path::new))),
+                timestamp: field(row, "t").unwrap_or_default(),
+                exit: row.get("exit").and_then(Value::as_i64),
+                output_bytes: (row.get("k").and_then(Value::as_str) == Some("tool.result"))
+                    .then(|| output_bytes(row)),
+            })
+        })
+        .collect()
+}
+
+/// Bytes of stdout and stderr, taken from `<field>_sample` when ingest
+/// sampled the field.
+fn output_bytes(row: &Value) -> u64 {
+    ["stdout", "stderr"]
+        .iter()
+        .map(|name| {
+            row.get(format!("{name}_sample"))
+                .and_then(|sample| sample.get("bytes"))
+                .and_then(Value::as_u64)
+                .or_else(|| {
+                    row.get(*name)
+                        .and_then(Value::as_str)
+                        .map(|text| text.len() as u64)
+                })
+                .unwrap_or(0)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_kind_tool_and_relative_file_per_event() {
+        let jsonl = concat!(
+            r#"{"t":"T0","k":"meta","cwd":"/work/app"}"#,
+            "\n",
+            r#"{"t":"T1","k":"tool.call","tool":"bash","args":"ls"}"#,
+            "\n",
+            r#"{"t":"T2","k":"tool.result","tool":"bash","exit":2,"stdout":"abc","stderr":"x\n[engram: stderr sampled]","stderr_sample":{"bytes":500}}"#,
+            "\n",
+            r#"{"t":"T3","k":"code.edit","file":"/work/app/src/lib.rs"}"#,
+            "\n",
+        );
+        let records = event_records_from_jsonl(jsonl);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[1].tool.as_deref(), Some("bash"));
+        assert_eq!(records[1].output_bytes, None);
+        assert_eq!(records[2].exit, Some(2));
+        assert_eq!(records[2].output_bytes, Some(503));
+        assert_eq!(records[3].offset, 3);
+        assert_eq!(records[3].kind, "code.edit");
+        assert_eq!(records[3].file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(records[3].timestamp, "T3");
+    }
+}
@@ -0,0 +1,191 @@
+use super::event::FileRange;
+
+/// Comment markers recognised in front of a directive, longest first.
+const COMMENT_MARKERS: &[&str] = &["<!--", "///", "//!", "//", "/*", "--", ";;", "#", ";", "*"];
+
+/// A structured `engram:` comment found in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommentDirective {
+    /// `engram:note <text>`
+    Note { text: String },
+    /// `engram:link <file>:<start>-<end> [note]`
+    Link {
+        to_file: String,
+        to_range: FileRange,
+        note: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedDirective {
+    /// 1-based line of the comment.
+    pub line: u32,
+    /// The code block the comment documents: the lines after it (skipping
+    /// further comment lines) up to the next blank line or directive.
+    pub span: FileRange,
+    pub directive: CommentDirective,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveIssue {
+    pub line: u32,
+    pub error: String,
+}
+
+/// Find every `engram:note` / `engram:link` comment in `text`. Malformed
+/// directives, and directives with no code after them, are reported as
+/// issues instead.
+pub fn scan_comment_directives(text: &str) -> (Vec<ScannedDirective>, Vec<DirectiveIssue>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut directives = Vec::new();
+    let mut issues = Vec::new();
+    for (idx, raw) in lines.iter().enumerate() {
+        let Some(body) = comment_body(raw) else {
+            continue;
+        };
+        let Some(rest) = body.strip_prefix("engram:") else {
+            continue;
+        };
+        let line = idx as u32 + 1;
+        let (kind, argument) = rest
+            .split_once(char::is_whitespace)
+            .map(|(kind, argument)| (kind, argument.trim()))
+            .unwrap_or((rest, ""));
+        let directive = match kind {
+            "note" if argument.is_empty() => Err("engram:note needs text".to_string()),
+            "note" => Ok(CommentDirective::Note {
+                text: argument.to_string(),
+            }),
+            "link" => parse_link(argument),
+            other => Err(format!("unknown directive `engram:{other}`")),
+        };
+        let directive = match directive {
+            Ok(directive) => directive,
+            Err(error) => {
+                issues.push(DirectiveIssue { line, error });
+                continue;
+            }
+        };
+        match following_block(&lines, idx + 1) {
+            Some(span) => directives.push(ScannedDirective {
+                line,
+                span,
+                directive,
+            }),
+            None => issues.push(DirectiveIssue {
+                line,
+                error: "no code follows the comment".to_string(),
+            }),
+        }
+    }
+    (directives, issues)
+}
+
+/// Text of a comment line with its marker (and any closing `*/` / `-->`)
+/// removed, or `None` for code lines. `#[`/`#!` attributes are code.
+fn comment_body(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("#[") || trimmed.starts_with("#!") {
+        return None;
+    }
+    let marker = COMMENT_MARKERS
+        .iter()
+        .find(|marker| trimmed.starts_with(**marker))?;
+    let body = trimmed[marker.len()..].trim();
+    let body = body
+        .strip_suffix("-->")
+        .or_else(|| body.strip_suffix("*/"))
+        .unwrap_or(body);
+    Some(body.trim())
+}
+
+fn is_directive(line: &str) -> bool {
+    comment_body(line).is_some_and(|body| body.starts_with("engram:"))
+}
+
+fn parse_link(argument: &str) -> Result<CommentDirective, String> {
+    let (target, note) = argument
+        .split_once(char::is_whitespace)
+        .map(|(target, note)| (target, Some(note.trim().to_string())))
+        .unwrap_or((argument, None));
+    let parsed = target.rsplit_once(':').and_then(|(file, range)| {
+        let (start, end) = range.split_once('-')?;
+        let (start, end) = (start.parse::<u32>().ok()?, end.parse::<u32>().ok()?);
+        (!file.is_empty() && start >= 1 && start <= end).then_some((file, FileRange { start, end }))
+    });
+    match parsed {
+        Some((file, range)) => Ok(CommentDirective::Link {
+            to_file: file.to_string(),
+            to_range: range,
+            note: note.filter(|note| !note.is_empty()),
+        }),
+        None => Err(format!(
+            "engram:link needs `<file>:<start>-<end>`, got `{target}`"
+        )),
+    }
+}
+
+fn following_block(lines: &[&str], from: usize) -> Option<FileRange> {
+    let start = (from..lines.len()).find(|&idx| comment_body(lines[idx]).is_none())?;
+    if lines[start].trim().is_empty() {
+        return None;
+    }
+    let end = (start..lines.len())
+        .take_while(|&idx| !lines[idx].trim().is_empty() && !is_directive(lines[idx]))
+        .last()?;
+    Some(FileRange {
+        start: start as u32 + 1,
+        end: end as u32 + 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directives_cover_the_block_after_the_comment() {
+        let text = concat!(
+            "use std::io;\n",
+            "\n",
+            "// engram:note keep the retry; see incident 423\n",
+            "/// Charges a card.\n",
+            "#[inline]\n",
+            "fn charge() {\n",
+            "    retry();\n",
+            "}\n",
+            "\n",
+            "# engram:link src/old.rs:10-20 moved from the legacy client\n",
+            "def refund(): pass\n",
+            "<!-- engram:link docs/a.md:3-1 -->\n",
+            "x\n",
+            "// engram:sometime later\n",
+            "// engram:note trailing\n",
+        );
+        let (directives, issues) = scan_comment_directives(text);
+        assert_eq!(
+            directives,
+            vec![
+                ScannedDirective {
+                    line: 3,
+                    span: FileRange { start: 5, end: 8 },
+                    directive: CommentDirective::Note {
+                        text: "keep the retry; see incident 423".to_string(),
+                    },
+                },
+                ScannedDirective {
+                    line: 10,
+                    span: FileRange { start: 11, end: 11 },
+                    directive: CommentDirective::Link {
+                        to_file: "src/old.rs".to_string(),
+                        to_range: FileRange { start: 10, end: 20 },
+                        note: Some("moved from the legacy client".to_string()),
+                    },
+                },
+            ]
+        );
+        let issue_lines = issues.iter().map(|issue| issue.line).collect::<Vec<_>>();
+        assert_eq!(issue_lines, vec![12, 14, 15]);
+        assert!(issues[1].error.contains("engram:sometime"));
+    }
+}
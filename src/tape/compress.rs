@@ -1,3 +1,7 @@
+use std::io::Read;
+
+use crate::error::TapeError;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionFormat {
     Zstd,
@@ -7,8 +11,91 @@ pub fn compress_jsonl(input: &str) -> std::io::Result<Vec<u8>> {
     zstd::stream::encode_all(input.as_bytes(), 0)
 }
 
-pub fn decompress_jsonl(input: &[u8]) -> std::io::Result<String> {
-    let decompressed = zstd::stream::decode_all(input)?;
-    String::from_utf8(decompressed)
-        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))
+pub fn decompress_jsonl(input: &[u8]) -> Result<String, TapeError> {
+    let decompressed = zstd::stream::decode_all(input).map_err(TapeError::Decompress)?;
+    Ok(String::from_utf8(decompressed)?)
+}
+
+/// What [`salvage_jsonl`] got out of a tape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SalvagedJsonl {
+    /// Every complete line before the damage, each ending in a newline.
+    pub content: String,
+    /// Whether the whole input decompressed; `content` is then the tape.
+    pub complete: bool,
+}
+
+/// Decompress as much of a damaged tape (a write cut short, a bad block) as
+/// zstd yields before failing, keeping the complete UTF-8 lines of it.
+pub fn salvage_jsonl(input: &[u8]) -> SalvagedJsonl {
+    let mut decoder = match zstd::stream::read::Decoder::new(input) {
+        Ok(decoder) => decoder,
+        Err(_) => {
+            return SalvagedJsonl {
+                content: String::new(),
+                complete: false,
+            };
+        }
+    };
+    let mut bytes = Vec::new();
+    let mut chunk = [0u8; 16 * 1024];
+    let mut complete = false;
+    loop {
+        match decoder.read(&mut chunk) {
+            Ok(0) => {
+                complete = true;
+                break;
+            }
+            Ok(read) => bytes.extend_from_slice(&chunk[..read]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) => break,
+        }
+    }
+    let valid = match std::str::from_utf8(&bytes) {
+        Ok(text) => text,
+        Err(err) => {
+            complete = false;
+            std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default()
+        }
+    };
+    let keep = if complete {
+        valid.len()
+    } else {
+        valid.rfind('\n').map_or(0, |at| at + 1)
+    };
+    SalvagedJsonl {
+        content: valid[..keep].to_string(),
+        complete,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salvage_keeps_complete_lines_before_truncation() {
+        // Hard to compress, so the stream spans many zstd blocks.
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+        let lines = (0..20_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                format!("{{\"k\":\"msg.in\",\"text\":\"{seed:016x}\"}}\n")
+            })
+            .collect::<String>();
+        let compressed = compress_jsonl(&lines).expect("compress");
+
+        let whole = salvage_jsonl(&compressed);
+        assert!(whole.complete);
+        assert_eq!(whole.content, lines);
+
+        let cut = salvage_jsonl(&compressed[..compressed.len() / 2]);
+        assert!(!cut.complete);
+        assert!(decompress_jsonl(&compressed[..compressed.len() / 2]).is_err());
+        assert!(!cut.content.is_empty());
+        assert!(lines.starts_with(&cut.content));
+        assert!(cut.content.ends_with('\n'));
+
+        assert_eq!(salvage_jsonl(b"not zstd").content, "");
+    }
 }
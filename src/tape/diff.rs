@@ -0,0 +1,156 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde_json::Value;
+
+/// An event of tape B aligned with one of tape A whose content differs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedEvent {
+    pub key: String,
+    pub offset_a: u64,
+    pub offset_b: u64,
+    /// Top-level fields whose values differ, sorted.
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TapeDiff {
+    pub same: usize,
+    /// Offsets of tape A events with no counterpart in tape B, in tape order.
+    pub only_a: Vec<u64>,
+    /// Offsets of tape B events with no counterpart in tape A, in tape order.
+    pub only_b: Vec<u64>,
+    pub changed: Vec<ChangedEvent>,
+}
+
+impl TapeDiff {
+    pub fn is_identical(&self) -> bool {
+        self.only_a.is_empty() && self.only_b.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Alignment key of a tape event: its kind plus `call_id` when present,
+/// otherwise its timestamp, numbered by occurrence so repeated keys (several
+/// events stamped with the same second) pair up in tape order.
+fn base_key(event: &Value) -> String {
+    let kind = event.get("k").and_then(Value::as_str).unwrap_or("");
+    match event.get("call_id").and_then(Value::as_str) {
+        Some(call_id) if !call_id.is_empty() => format!("{kind}@call:{call_id}"),
+        _ => {
+            let timestamp = event.get("t").and_then(Value::as_str).unwrap_or("");
+            format!("{kind}@t:{timestamp}")
+        }
+    }
+}
+
+fn keyed(rows: &[(u64, Value)]) -> Vec<(String, u64, &Value)> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    rows.iter()
+        .map(|(offset, event)| {
+            let base = base_key(event);
+            let occurrence = seen.entry(base.clone()).or_default();
+            let key = format!("{base}#{occurrence}");
+            *occurrence += 1;
+            (key, *offset, event)
+        })
+        .collect()
+}
+
+/// Align two tapes' events (as `(offset, event)` rows) by call id or
+/// timestamp and report what only one side has and what changed.
+pub fn diff_tape_rows(rows_a: &[(u64, Value)], rows_b: &[(u64, Value)]) -> TapeDiff {
+    let keyed_a = keyed(rows_a);
+    let keyed_b = keyed(rows_b);
+    let by_key_b = keyed_b
+        .iter()
+        .map(|(key, offset, event)| (key.as_str(), (*offset, *event)))
+        .collect::<HashMap<_, _>>();
+    let keys_a = keyed_a
+        .iter()
+        .map(|(key, _, _)| key.as_str())
+        .collect::<BTreeSet<_>>();
+
+    let mut diff = TapeDiff::default();
+    for (key, offset_a, event_a) in &keyed_a {
+        match by_key_b.get(key.as_str()) {
+            None => diff.only_a.push(*offset_a),
+            Some((_, event_b)) if event_a == event_b => diff.same += 1,
+            Some((offset_b, event_b)) => diff.changed.push(ChangedEvent {
+                key: key.clone(),
+                offset_a: *offset_a,
+                offset_b: *offset_b,
+                fields: changed_fields(event_a, event_b),
+            }),
+        }
+    }
+    diff.only_b = keyed_b
+        .iter()
+        .filter(|(key, _, _)| !keys_a.contains(key.as_str()))
+        .map(|(_, offset, _)| *offset)
+        .collect();
+    diff
+}
+
+fn changed_fields(a: &Value, b: &Value) -> Vec<String> {
+    let (Some(a), Some(b)) = (a.as_object(), b.as_object()) else {
+        return Vec::new();
+    };
+    a.keys()
+        .chain(b.keys())
+        .filter(|key| a.get(*key) != b.get(*key))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn aligns_by_call_id_then_timestamp_occurrence() {
+        let a = vec![
+            (0, json!({"t": "T0", "k": "meta", "model": "m1"})),
+            (1, json!({"t": "T1", "k": "msg.in", "content": "hi"})),
+            (
+                2,
+                json!({"t": "T2", "k": "tool.call", "call_id": "c1", "args": "ls"}),
+            ),
+            (
+                3,
+                json!({"t": "T2", "k": "tool.result", "call_id": "c1", "stdout": "a"}),
+            ),
+            (4, json!({"t": "T3", "k": "msg.out", "content": "dropped"})),
+        ];
+        let b = vec![
+            (0, json!({"t": "T0", "k": "meta", "model": "m1"})),
+            (1, json!({"t": "T1", "k": "msg.in", "content": "hi"})),
+            (
+                2,
+                json!({"t": "T2", "k": "tool.call", "call_id": "c1", "args": "ls"}),
+            ),
+            (
+                3,
+                json!({"t": "T9", "k": "tool.result", "call_id": "c1", "stdout": "a b"}),
+            ),
+            (4, json!({"t": "T4", "k": "msg.in", "content": "new turn"})),
+        ];
+        let diff = diff_tape_rows(&a, &b);
+        assert_eq!(diff.same, 3);
+        assert_eq!(diff.only_a, vec![4]);
+        assert_eq!(diff.only_b, vec![4]);
+        assert_eq!(
+            diff.changed,
+            vec![ChangedEvent {
+                key: "tool.result@call:c1#0".into(),
+                offset_a: 3,
+                offset_b: 3,
+                fields: vec!["stdout".into(), "t".into()],
+            }]
+        );
+        assert!(!diff.is_identical());
+        assert!(diff_tape_rows(&a, &a).is_identical());
+    }
+}
@@ -0,0 +1,85 @@
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+/// Old and new text of `code.edit` events, keyed by SHA-256 for the objects
+/// directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditObject {
+    pub sha256: String,
+    pub text: String,
+}
+
+/// Record `before_object` / `after_object` (the SHA-256 of `before_text` /
+/// `after_text`) on every `code.edit` event that carries text, and return
+/// the texts to store under those keys. Other lines pass through untouched.
+pub fn stamp_edit_objects(jsonl: &str) -> serde_json::Result<(String, Vec<EditObject>)> {
+    let mut out = String::with_capacity(jsonl.len());
+    let mut objects: Vec<EditObject> = Vec::new();
+    for line in jsonl.lines() {
+        if !line.contains("\"code.edit\"") {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let mut row: Value = serde_json::from_str(line)?;
+        if row.get("k").and_then(Value::as_str) != Some("code.edit") {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let mut stamped = false;
+        for side in ["before", "after"] {
+            let Some(text) = row.get(format!("{side}_text")).and_then(Value::as_str) else {
+                continue;
+            };
+            let sha256 = format!("{:x}", Sha256::digest(text.as_bytes()));
+            if objects.iter().all(|object| object.sha256 != sha256) {
+                objects.push(EditObject {
+                    sha256: sha256.clone(),
+                    text: text.to_string(),
+                });
+            }
+            row[format!("{side}_object")] = json!(sha256);
+            stamped = true;
+        }
+        if stamped {
+            out.push_str(&serde_json::to_string(&row)?);
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    Ok((out, objects))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamps_object_keys_for_edit_texts() {
+        let jsonl = concat!(
+            r#"{"t":"T1","k":"code.edit","file":"a.rs","before_text":"x\n","after_text":"y\n"}"#,
+            "\n",
+            r#"{"t":"T2","k":"code.edit","file":"a.rs","before_text":"y\n"}"#,
+            "\n",
+            r#"{"t":"T3","k":"msg.out","text":"\"code.edit\""}"#,
+            "\n",
+        );
+        let (stamped, objects) = stamp_edit_objects(jsonl).expect("stamp");
+        assert_eq!(
+            objects.iter().map(|o| o.text.as_str()).collect::<Vec<_>>(),
+            vec!["x\n", "y\n"]
+        );
+        let rows = stamped
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows[0]["before_object"], objects[0].sha256);
+        assert_eq!(rows[0]["after_object"], objects[1].sha256);
+        assert_eq!(rows[1]["before_object"], objects[1].sha256);
+        assert!(rows[1].get("after_object").is_none());
+        assert_eq!(stamped.lines().nth(2), jsonl.lines().nth(2));
+        assert_eq!(stamp_edit_objects(&stamped).expect("again").0, stamped);
+    }
+}
@@ -0,0 +1,190 @@
+//! Where a session was captured: machine identity and tool versions that
+//! ingest and `record` stamp under `environment` in a tape's meta event
+//! (`ingest.environment`), so merged multi-user stores can tell sessions
+//! from different machines apart and bug reports carry the context.
+
+use std::fs;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::adapter::AdapterId;
+use super::origin::stamp_meta_field;
+
+/// What `ingest.environment` asks ingest to record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvironmentField {
+    /// The machine's host name.
+    Hostname,
+    /// The login name of the user running engram.
+    User,
+    /// Operating system and CPU architecture.
+    Os,
+    /// The engram version and, where the transcript records it, the
+    /// harness version.
+    Versions,
+}
+
+/// The `environment` object of a meta event. Fields that were not asked
+/// for, or could not be determined, are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TapeEnvironment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engram_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub harness_version: Option<String>,
+}
+
+impl TapeEnvironment {
+    /// The environment of this process, limited to `fields`.
+    /// `harness_version` comes from the transcript (see [`harness_version`]).
+    pub fn capture(fields: &[EnvironmentField], harness_version: Option<String>) -> Self {
+        let mut environment = Self::default();
+        for field in fields {
+            match field {
+                EnvironmentField::Hostname => environment.hostname = hostname(),
+                EnvironmentField::User => {
+                    environment.user = ["USER", "USERNAME", "LOGNAME"]
+                        .into_iter()
+                        .find_map(|name| non_empty(std::env::var(name).ok()))
+                }
+                EnvironmentField::Os => {
+                    environment.os = Some(std::env::consts::OS.to_string());
+                    environment.arch = Some(std::env::consts::ARCH.to_string());
+                }
+                EnvironmentField::Versions => {
+                    environment.engram_version = Some(env!("CARGO_PKG_VERSION").to_string());
+                    environment.harness_version = harness_version.clone();
+                }
+            }
+        }
+        environment
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The harness version a raw transcript records, for the harnesses that
+/// write one: Claude Code's per-line `version` and Codex's `cli_version` in
+/// `session_meta`.
+pub fn harness_version(adapter: AdapterId, raw: &str) -> Option<String> {
+    let field = match adapter {
+        AdapterId::ClaudeCode => "\"version\"",
+        AdapterId::CodexCli => "\"cli_version\"",
+        _ => return None,
+    };
+    raw.lines()
+        .filter(|line| line.contains(field))
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find_map(|row| {
+            let version = match adapter {
+                AdapterId::CodexCli => row.get("payload")?.get("cli_version"),
+                _ => row.get("version"),
+            };
+            non_empty(version?.as_str().map(str::to_owned))
+        })
+}
+
+/// Record `environment` on the tape's first meta event, prepending one when
+/// there is none. A meta event that already carries an `environment` (one
+/// a `record` client sent, or the original on `ingest --reconvert`) keeps it.
+pub fn stamp_environment(jsonl: &str, environment: &TapeEnvironment) -> serde_json::Result<String> {
+    let recorded = jsonl
+        .lines()
+        .filter(|line| line.contains("\"meta\""))
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|row| row.get("k").and_then(Value::as_str) == Some("meta"))
+        .is_some_and(|row| row.get("environment").is_some());
+    if recorded || environment.is_empty() {
+        return Ok(jsonl.to_string());
+    }
+    stamp_meta_field(jsonl, "environment", serde_json::to_value(environment)?)
+}
+
+fn hostname() -> Option<String> {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .into_iter()
+        .find_map(|name| non_empty(std::env::var(name).ok()))
+        .or_else(|| {
+            ["/proc/sys/kernel/hostname", "/etc/hostname"]
+                .into_iter()
+                .find_map(|path| non_empty(fs::read_to_string(path).ok()))
+        })
+        .or_else(|| {
+            let output = Command::new("hostname").output().ok()?;
+            non_empty(String::from_utf8(output.stdout).ok())
+        })
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tape::event::{TapeEventData, parse_jsonl_events};
+
+    #[test]
+    fn stamps_requested_fields_once() {
+        let environment = TapeEnvironment::capture(
+            &[EnvironmentField::Os, EnvironmentField::Versions],
+            Some("1.2.3".to_string()),
+        );
+        assert_eq!(environment.os.as_deref(), Some(std::env::consts::OS));
+        assert_eq!(environment.harness_version.as_deref(), Some("1.2.3"));
+        assert!(environment.hostname.is_none() && environment.user.is_none());
+        assert!(TapeEnvironment::capture(&[], Some("1.2.3".to_string())).is_empty());
+
+        let jsonl = concat!(
+            r#"{"t":"2026-03-01T00:00:00Z","k":"msg.in","text":"hi"}"#,
+            "\n"
+        );
+        let stamped = stamp_environment(jsonl, &environment).expect("stamp");
+        let events = parse_jsonl_events(&stamped).expect("parse");
+        let TapeEventData::Meta(meta) = &events[0].event.data else {
+            panic!("meta first: {stamped}");
+        };
+        assert_eq!(meta.environment.as_ref(), Some(&environment));
+
+        let other = TapeEnvironment {
+            hostname: Some("elsewhere".to_string()),
+            ..TapeEnvironment::default()
+        };
+        assert_eq!(stamp_environment(&stamped, &other).expect("again"), stamped);
+    }
+
+    #[test]
+    fn reads_harness_versions_from_raw_transcripts() {
+        let claude = concat!(
+            r#"{"type":"summary","summary":"s"}"#,
+            "\n",
+            r#"{"type":"user","version":"1.0.98","message":{"content":"hi"}}"#,
+            "\n"
+        );
+        assert_eq!(
+            harness_version(AdapterId::ClaudeCode, claude).as_deref(),
+            Some("1.0.98")
+        );
+        let codex = r#"{"type":"session_meta","payload":{"id":"s","cli_version":"0.46.0"}}"#;
+        assert_eq!(
+            harness_version(AdapterId::CodexCli, codex).as_deref(),
+            Some("0.46.0")
+        );
+        assert_eq!(harness_version(AdapterId::GeminiCli, claude), None);
+    }
+}
@@ -1,5 +1,9 @@
 use serde::Deserialize;
 
+use super::environment::TapeEnvironment;
+use super::jsonl::strip_byte_order_mark;
+use super::origin::TapeOrigin;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EventKind {
     MsgIn,
@@ -38,10 +42,17 @@ pub enum TapeEventData {
 pub struct MetaEvent {
     pub model: Option<String>,
     pub repo_head: Option<String>,
+    /// Project root the harness was running in, used to repo-relativize paths.
+    pub cwd: Option<String>,
     pub label: Option<String>,
     pub coverage_read: Option<String>,
     pub coverage_edit: Option<String>,
     pub coverage_tool: Option<String>,
+    /// Raw harness file this tape was converted from, for ingested tapes.
+    pub origin: Option<TapeOrigin>,
+    /// Machine and tool versions the session was captured with
+    /// (`ingest.environment`).
+    pub environment: Option<TapeEnvironment>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -50,6 +61,11 @@ pub struct CodeReadEvent {
     pub range: FileRange,
     pub text: Option<String>,
     pub anchor_hashes: Vec<String>,
+    /// Tag of the span normalizer the text is fingerprinted through
+    /// (`anchors.normalizers`).
+    pub normalizer: Option<String>,
+    /// Monorepo project the file belongs to (`projects`).
+    pub project: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -64,6 +80,10 @@ pub struct CodeEditEvent {
     pub before_anchor_hashes: Vec<String>,
     pub after_anchor_hashes: Vec<String>,
     pub similarity: Option<f32>,
+    /// See [`CodeReadEvent::normalizer`].
+    pub normalizer: Option<String>,
+    /// See [`CodeReadEvent::project`].
+    pub project: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -87,8 +107,11 @@ pub struct ParseIssue {
     pub error: String,
 }
 
+/// Parse a tape. A leading byte order mark is ignored; any other line that
+/// is not a tape event fails the parse, see [`parse_jsonl_events_lossy`].
 pub fn parse_jsonl_events(input: &str) -> Result<Vec<TapeEventAt>, serde_json::Error> {
     let mut out = Vec::new();
+    let input = strip_byte_order_mark(input);
 
     for (idx, line) in input.lines().enumerate() {
         if line.trim().is_empty() {
@@ -106,6 +129,7 @@ pub fn parse_jsonl_events(input: &str) -> Result<Vec<TapeEventAt>, serde_json::E
 
 pub fn parse_jsonl_events_lossy(input: &str) -> (Vec<TapeEventAt>, Vec<ParseIssue>) {
     let mut out = Vec::new();
+    let input = strip_byte_order_mark(input);
     let mut issues = Vec::new();
 
     for (idx, line) in input.lines().enumerate() {
@@ -138,6 +162,8 @@ struct RawEvent {
     #[serde(default)]
     repo_head: Option<String>,
     #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
     label: Option<String>,
     #[serde(default, rename = "coverage.read")]
     coverage_read: Option<String>,
@@ -172,6 +198,10 @@ struct RawEvent {
     #[serde(default)]
     anchor_hashes: Option<Vec<String>>,
     #[serde(default)]
+    normalizer: Option<String>,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
     from_file: Option<String>,
     #[serde(default)]
     from_range: Option<[u32; 2]>,
@@ -181,6 +211,10 @@ struct RawEvent {
     to_range: Option<[u32; 2]>,
     #[serde(default)]
     note: Option<String>,
+    #[serde(default)]
+    origin: Option<serde_json::Value>,
+    #[serde(default)]
+    environment: Option<serde_json::Value>,
 }
 
 impl RawEvent {
@@ -193,6 +227,8 @@ impl RawEvent {
                     range: file_range(range),
                     text: self.text,
                     anchor_hashes: self.anchor_hashes.unwrap_or_default(),
+                    normalizer: self.normalizer,
+                    project: self.project,
                 }),
                 _ => TapeEventData::Other { kind },
             },
@@ -208,6 +244,8 @@ impl RawEvent {
                     before_anchor_hashes: self.before_anchor_hashes.unwrap_or_default(),
                     after_anchor_hashes: self.after_anchor_hashes.unwrap_or_default(),
                     similarity: self.similarity,
+                    normalizer: self.normalizer,
+                    project: self.project,
                 }),
                 None => TapeEventData::Other { kind },
             },
@@ -226,10 +264,17 @@ impl RawEvent {
             "meta" => TapeEventData::Meta(MetaEvent {
                 model: self.model,
                 repo_head: self.repo_head,
+                cwd: self.cwd,
                 label: self.label,
                 coverage_read: self.coverage_read,
                 coverage_edit: self.coverage_edit,
                 coverage_tool: self.coverage_tool,
+                origin: self
+                    .origin
+                    .and_then(|origin| serde_json::from_value(origin).ok()),
+                environment: self
+                    .environment
+                    .and_then(|environment| serde_json::from_value(environment).ok()),
             }),
             _ => TapeEventData::Other { kind },
         };
@@ -0,0 +1,390 @@
+//! GitHub pull request reviews as tapes (`engram import github-pr`): the
+//! description, each submitted review and each review comment become
+//! `msg.in` events, and every comment on a line range is followed by a
+//! `code.read` of the commented lines so explain finds the discussion from
+//! the code it was about.
+
+use std::collections::HashMap;
+#[cfg(feature = "native")]
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+/// Harness name recorded in imported tapes' `source.harness`.
+pub const GITHUB_PR_HARNESS: &str = "github-pr";
+
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+#[cfg(feature = "native")]
+const PAGE_SIZE: usize = 100;
+
+#[cfg(feature = "native")]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+pub enum GithubError {
+    #[error("github request {url} failed: {message}")]
+    Http { url: String, message: String },
+    #[error("github response from {url} is not usable: {message}")]
+    Response { url: String, message: String },
+}
+
+/// `owner/repo#123`, or the pull request's URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequestRef {
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+}
+
+impl PullRequestRef {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (owner, repo, number) = if let Some((path, number)) = text.split_once('#') {
+            let (owner, repo) = path.split_once('/')?;
+            (owner, repo, number)
+        } else {
+            let path = text
+                .strip_prefix("https://github.com/")
+                .or_else(|| text.strip_prefix("http://github.com/"))?;
+            let mut parts = path.trim_end_matches('/').split('/');
+            let (owner, repo) = (parts.next()?, parts.next()?);
+            if parts.next()? != "pull" {
+                return None;
+            }
+            (owner, repo, parts.next()?)
+        };
+        let valid = |part: &str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+        };
+        if !valid(owner) || !valid(repo) {
+            return None;
+        }
+        Some(Self {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            number: number.parse().ok()?,
+        })
+    }
+}
+
+impl std::fmt::Display for PullRequestRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}#{}", self.owner, self.repo, self.number)
+    }
+}
+
+/// A pull request as the REST API returns it: the pull itself, its review
+/// comments and its reviews.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PullRequestReview {
+    pub pull: Value,
+    pub comments: Vec<Value>,
+    pub reviews: Vec<Value>,
+}
+
+/// Fetch `pr` from the REST API at `api_url`, authenticating with `token`
+/// when given (public repositories work without one, rate-limited).
+#[cfg(feature = "native")]
+pub fn fetch_pull_request_review(
+    api_url: &str,
+    token: Option<&str>,
+    pr: &PullRequestRef,
+) -> Result<PullRequestReview, GithubError> {
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let base = format!(
+        "{}/repos/{}/{}/pulls/{}",
+        api_url.trim_end_matches('/'),
+        pr.owner,
+        pr.repo,
+        pr.number
+    );
+    let get = |url: &str| -> Result<Value, GithubError> {
+        let mut request = agent
+            .get(url)
+            .set("Accept", "application/vnd.github+json")
+            .set("User-Agent", "engram");
+        if let Some(token) = token {
+            request = request.set("Authorization", &format!("Bearer {token}"));
+        }
+        request
+            .call()
+            .map_err(|err| GithubError::Http {
+                url: url.to_string(),
+                message: err.to_string(),
+            })?
+            .into_json()
+            .map_err(|err| GithubError::Response {
+                url: url.to_string(),
+                message: err.to_string(),
+            })
+    };
+    let get_all = |path: &str| -> Result<Vec<Value>, GithubError> {
+        let mut out = Vec::new();
+        for page in 1.. {
+            let url = format!("{base}/{path}?per_page={PAGE_SIZE}&page={page}");
+            let Value::Array(items) = get(&url)? else {
+                return Err(GithubError::Response {
+                    url,
+                    message: "expected a JSON array".to_string(),
+                });
+            };
+            let last = items.len() < PAGE_SIZE;
+            out.extend(items);
+            if last {
+                break;
+            }
+        }
+        Ok(out)
+    };
+    Ok(PullRequestReview {
+        pull: get(&base)?,
+        comments: get_all("comments")?,
+        reviews: get_all("reviews")?,
+    })
+}
+
+/// Tape JSONL for a fetched review, events in time order. Comments keep
+/// their author, review state, thread (`in_reply_to`) and URL.
+pub fn review_tape_jsonl(pr: &PullRequestRef, review: &PullRequestReview) -> String {
+    let text = |value: &Value, field: &str| {
+        value
+            .get(field)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let login = |value: &Value| {
+        value
+            .pointer("/user/login")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned)
+    };
+    let pull = &review.pull;
+    let opened_at = text(pull, "created_at");
+    let mut meta = json!({
+        "t": opened_at,
+        "k": "meta",
+        "source": {"harness": GITHUB_PR_HARNESS, "session_id": pr.to_string()},
+        "label": text(pull, "title"),
+        "url": pull.get("html_url"),
+    });
+    if let Some(head) = pull.pointer("/head/sha").and_then(Value::as_str) {
+        meta["repo_head"] = json!(head);
+    }
+
+    // (timestamp, order within the timestamp, event)
+    let mut events = Vec::<(String, usize, Value)>::new();
+    let body = text(pull, "body");
+    if !body.trim().is_empty() {
+        events.push((
+            opened_at.clone(),
+            events.len(),
+            json!({"t": opened_at, "k": "msg.in", "text": body, "author": login(pull)}),
+        ));
+    }
+    let mut state_by_review = HashMap::new();
+    for item in &review.reviews {
+        let state = text(item, "state");
+        if let Some(id) = item.get("id").and_then(Value::as_u64) {
+            state_by_review.insert(id, state.clone());
+        }
+        let body = text(item, "body");
+        if state == "PENDING" || (body.trim().is_empty() && state == "COMMENTED") {
+            continue;
+        }
+        let at = text(item, "submitted_at");
+        events.push((
+            at.clone(),
+            events.len(),
+            json!({
+                "t": at,
+                "k": "msg.in",
+                "text": body,
+                "author": login(item),
+                "review_state": state.to_ascii_lowercase(),
+                "url": item.get("html_url"),
+            }),
+        ));
+    }
+    for comment in &review.comments {
+        let at = text(comment, "created_at");
+        let mut message = json!({
+            "t": at,
+            "k": "msg.in",
+            "text": text(comment, "body"),
+            "author": login(comment),
+            "url": comment.get("html_url"),
+        });
+        if let Some(state) = comment
+            .get("pull_request_review_id")
+            .and_then(Value::as_u64)
+            .and_then(|id| state_by_review.get(&id))
+        {
+            message["review_state"] = json!(state.to_ascii_lowercase());
+        }
+        if let Some(parent) = comment.get("in_reply_to_id").and_then(Value::as_u64) {
+            message["in_reply_to"] = json!(parent);
+        }
+        let read = commented_lines(comment).map(|(start, end, lines)| {
+            message["file"] = json!(text(comment, "path"));
+            message["range"] = json!([start, end]);
+            json!({
+                "t": at,
+                "k": "code.read",
+                "file": text(comment, "path"),
+                "range": [start, end],
+                "text": lines,
+            })
+        });
+        events.push((at.clone(), events.len(), message));
+        if let Some(read) = read {
+            events.push((at, events.len(), read));
+        }
+    }
+    events.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    let mut out = String::new();
+    for value in std::iter::once(meta).chain(events.into_iter().map(|(_, _, event)| event)) {
+        out.push_str(&value.to_string());
+        out.push('\n');
+    }
+    out
+}
+
+/// The line range a review comment is on and those lines' text, taken from
+/// the end of its diff hunk. Comments on removed lines (`side: LEFT`) read
+/// the old side. Outdated comments fall back to their original lines.
+fn commented_lines(comment: &Value) -> Option<(u64, u64, String)> {
+    let field = |name: &str| comment.get(name).and_then(Value::as_u64);
+    let end = field("line").or_else(|| field("original_line"))?;
+    let start = field("start_line")
+        .or_else(|| field("original_start_line"))
+        .filter(|start| *start <= end)
+        .unwrap_or(end);
+    let old_side = comment.get("side").and_then(Value::as_str) == Some("LEFT");
+    let hunk = comment.get("diff_hunk").and_then(Value::as_str)?;
+    let side = hunk
+        .lines()
+        .filter(|line| !line.starts_with("@@") && !line.starts_with('\\'))
+        .filter_map(|line| {
+            let (marker, rest) = line.split_at(line.len().min(1));
+            match marker {
+                " " | "" => Some(rest),
+                "+" if !old_side => Some(rest),
+                "-" if old_side => Some(rest),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    let count = usize::try_from(end - start + 1).ok()?.min(side.len());
+    if count == 0 {
+        return None;
+    }
+    let text = side[side.len() - count..].join("\n");
+    Some((end + 1 - count as u64, end, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_short_refs_and_urls() {
+        let expected = PullRequestRef {
+            owner: "octo".to_string(),
+            repo: "engine.rs".to_string(),
+            number: 42,
+        };
+        assert_eq!(
+            PullRequestRef::parse("octo/engine.rs#42"),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            PullRequestRef::parse("https://github.com/octo/engine.rs/pull/42/files"),
+            Some(expected.clone())
+        );
+        assert_eq!(expected.to_string(), "octo/engine.rs#42");
+        assert_eq!(PullRequestRef::parse("octo#42"), None);
+        assert_eq!(PullRequestRef::parse("octo/engine#x"), None);
+    }
+
+    #[test]
+    fn converts_reviews_and_anchors_comments_to_their_lines() {
+        let pr = PullRequestRef::parse("octo/app#7").expect("ref");
+        let review = PullRequestReview {
+            pull: json!({
+                "title": "Retry uploads",
+                "body": "Adds a retry loop.",
+                "created_at": "2026-03-01T09:00:00Z",
+                "html_url": "https://github.com/octo/app/pull/7",
+                "user": {"login": "author"},
+                "head": {"sha": "abc123"},
+            }),
+            reviews: vec![json!({
+                "id": 11,
+                "state": "CHANGES_REQUESTED",
+                "body": "Needs a backoff.",
+                "submitted_at": "2026-03-01T10:00:00Z",
+                "user": {"login": "reviewer"},
+            })],
+            comments: vec![
+                json!({
+                    "id": 21,
+                    "pull_request_review_id": 11,
+                    "path": "src/upload.rs",
+                    "start_line": 3,
+                    "line": 4,
+                    "side": "RIGHT",
+                    "diff_hunk": "@@ -1,2 +1,4 @@\n fn upload() {\n-    send();\n+    loop {\n+        send();\n+    }",
+                    "body": "Why loop forever?",
+                    "created_at": "2026-03-01T09:59:00Z",
+                    "user": {"login": "reviewer"},
+                }),
+                json!({
+                    "id": 22,
+                    "in_reply_to_id": 21,
+                    "path": "src/upload.rs",
+                    "line": null,
+                    "original_line": 4,
+                    "diff_hunk": "@@ -1,2 +1,4 @@\n+    }",
+                    "body": "It breaks on success.",
+                    "created_at": "2026-03-01T10:05:00Z",
+                    "user": {"login": "author"},
+                }),
+            ],
+        };
+
+        let rows = review_tape_jsonl(&pr, &review)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("json"))
+            .collect::<Vec<_>>();
+        let kinds = rows
+            .iter()
+            .map(|row| row["k"].as_str().expect("kind"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                "meta",
+                "msg.in",
+                "msg.in",
+                "code.read",
+                "msg.in",
+                "msg.in",
+                "code.read"
+            ]
+        );
+        assert_eq!(rows[0]["source"]["session_id"], "octo/app#7");
+        assert_eq!(rows[0]["repo_head"], "abc123");
+        assert_eq!(rows[2]["review_state"], "changes_requested");
+        assert_eq!(rows[3]["range"], json!([3, 4]));
+        assert_eq!(rows[3]["text"], "        send();\n    }");
+        assert_eq!(rows[4]["text"], "Needs a backoff.");
+        assert_eq!(rows[5]["in_reply_to"], 21);
+        assert_eq!(rows[6]["range"], json!([4, 4]));
+    }
+}
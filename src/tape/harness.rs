@@ -15,6 +15,7 @@ pub fn claude_jsonl_to_tape_jsonl(input: &str) -> Result<String, serde_json::Err
     let mut session_id: Option<String> = None;
     let mut first_timestamp: Option<String> = None;
     let mut model: Option<String> = None;
+    let mut cwd: Option<String> = None;
 
     let mut read_total = 0u32;
     let mut read_emitted = 0u32;
@@ -36,6 +37,12 @@ pub fn claude_jsonl_to_tape_jsonl(input: &str) -> Result<String, serde_json::Err
         if session_id.is_none() {
             session_id = claude_session_id(&row);
         }
+        if cwd.is_none() {
+            cwd = row
+                .get("cwd")
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned);
+        }
         let row_type = row.get("type").and_then(Value::as_str).unwrap_or("");
 
         match row_type {
@@ -222,6 +229,24 @@ pub fn claude_jsonl_to_tape_jsonl(input: &str) -> Result<String, serde_json::Err
                                             edit_emitted = edit_emitted.saturating_add(1);
                                         }
                                     }
+                                    "NotebookEdit" => {
+                                        edit_total = edit_total.saturating_add(1);
+                                        if let Some(file) = tool_input
+                                            .get("notebook_path")
+                                            .and_then(Value::as_str)
+                                            .map(ToOwned::to_owned)
+                                        {
+                                            out.push(json!({
+                                                "t": timestamp,
+                                                "k": "code.edit",
+                                                "source": source_block("claude-code", session_id.as_deref()),
+                                                "file": file,
+                                                "cell_id": tool_input.get("cell_id").and_then(Value::as_str),
+                                                "after_text": tool_input.get("new_source").and_then(Value::as_str)
+                                            }));
+                                            edit_emitted = edit_emitted.saturating_add(1);
+                                        }
+                                    }
                                     "MultiEdit" => {
                                         if let Some(file) = tool_input
                                             .get("file_path")
@@ -266,18 +291,19 @@ pub fn claude_jsonl_to_tape_jsonl(input: &str) -> Result<String, serde_json::Err
         }
     }
 
-    out.insert(
-        0,
-        json!({
-            "t": first_timestamp.unwrap_or_else(|| "1970-01-01T00:00:00Z".to_string()),
-            "k": "meta",
-            "model": model,
-            "source": source_block("claude-code", session_id.as_deref()),
-            "coverage.read": coverage_grade(read_total, read_emitted),
-            "coverage.edit": coverage_grade(edit_total, edit_emitted),
-            "coverage.tool": "full"
-        }),
-    );
+    let mut meta = json!({
+        "t": first_timestamp.unwrap_or_else(|| "1970-01-01T00:00:00Z".to_string()),
+        "k": "meta",
+        "model": model,
+        "source": source_block("claude-code", session_id.as_deref()),
+        "coverage.read": coverage_grade(read_total, read_emitted),
+        "coverage.edit": coverage_grade(edit_total, edit_emitted),
+        "coverage.tool": "full"
+    });
+    if let Some(cwd) = cwd {
+        meta["cwd"] = json!(cwd);
+    }
+    out.insert(0, meta);
 
     to_jsonl(&out)
 }
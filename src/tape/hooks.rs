@@ -0,0 +1,359 @@
+use serde_json::{Value, json};
+
+/// Tape events derived from one lifecycle-hook invocation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookEvents {
+    pub harness: &'static str,
+    pub session_id: String,
+    pub cwd: Option<String>,
+    pub events: Vec<Value>,
+    /// The in-progress tape should be sealed: the Claude Code session ended
+    /// (`SessionEnd`; `Stop` only ends a turn, so a session stays one tape),
+    /// or a Codex turn completed, since Codex names each turn on its own and
+    /// sends nothing at session end.
+    pub flush: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookPayloadError {
+    /// Neither a Claude Code hook (`hook_event_name`) nor a Codex notification (`type`).
+    UnknownShape,
+    /// A Codex notification type other than `agent-turn-complete`.
+    UnsupportedCodexType(String),
+}
+
+impl std::fmt::Display for HookPayloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownShape => write!(
+                f,
+                "expected a Claude Code hook payload (hook_event_name) or a Codex notify payload (type)"
+            ),
+            Self::UnsupportedCodexType(kind) => {
+                write!(f, "unsupported Codex notification type `{kind}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HookPayloadError {}
+
+/// Normalize a Claude Code hook payload (read from stdin) or a Codex `notify`
+/// payload (passed as the last argv entry). Hooks carry no timestamps, so
+/// every event is stamped with `now`.
+pub fn hook_payload_to_events(payload: &Value, now: &str) -> Result<HookEvents, HookPayloadError> {
+    if let Some(hook) = payload.get("hook_event_name").and_then(Value::as_str) {
+        return Ok(claude_hook_events(payload, hook, now));
+    }
+    match payload.get("type").and_then(Value::as_str) {
+        Some("agent-turn-complete") => Ok(codex_turn_events(payload, now)),
+        Some(other) => Err(HookPayloadError::UnsupportedCodexType(other.to_string())),
+        None => Err(HookPayloadError::UnknownShape),
+    }
+}
+
+/// Meta header written as the first line of an in-progress hook tape.
+pub fn hook_tape_meta(harness: &str, session_id: &str, cwd: Option<&str>, now: &str) -> Value {
+    let mut meta = json!({
+        "t": now,
+        "k": "meta",
+        "source": source_block(harness, session_id),
+        "capture": "hook",
+        "coverage.tool": "full"
+    });
+    if let Some(cwd) = cwd {
+        meta["cwd"] = json!(cwd);
+    }
+    meta
+}
+
+fn claude_hook_events(payload: &Value, hook: &str, now: &str) -> HookEvents {
+    let session_id = payload
+        .get("session_id")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown")
+        .to_string();
+    let source = source_block("claude-code", &session_id);
+    let mut events = Vec::new();
+    let mut flush = false;
+
+    match hook {
+        "UserPromptSubmit" => {
+            if let Some(prompt) = payload.get("prompt").and_then(Value::as_str) {
+                events.push(json!({
+                    "t": now,
+                    "k": "msg.in",
+                    "source": source,
+                    "role": "user",
+                    "content": prompt
+                }));
+            }
+        }
+        "PostToolUse" => {
+            let tool = payload
+                .get("tool_name")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            let input = payload.get("tool_input").cloned().unwrap_or(Value::Null);
+            let response = payload.get("tool_response").unwrap_or(&Value::Null);
+            let call_id = payload.get("tool_use_id").cloned().unwrap_or(Value::Null);
+            let is_error = response.get("is_error").and_then(Value::as_bool) == Some(true)
+                || response.get("success").and_then(Value::as_bool) == Some(false);
+            events.push(json!({
+                "t": now,
+                "k": "tool.call",
+                "source": source,
+                "tool": tool,
+                "call_id": call_id,
+                "args": serde_json::to_string(&input).unwrap_or_else(|_| "{}".to_string())
+            }));
+            events.push(json!({
+                "t": now,
+                "k": "tool.result",
+                "source": source,
+                "tool": tool,
+                "call_id": call_id,
+                "exit": if is_error { 1 } else { 0 },
+                "stdout": response_text(response),
+                "stderr": response.get("stderr").and_then(Value::as_str).unwrap_or("")
+            }));
+            events.extend(claude_code_events(tool, &input, response, &source, now));
+        }
+        "SessionEnd" => flush = true,
+        _ => {}
+    }
+
+    HookEvents {
+        harness: "claude-code",
+        session_id,
+        cwd: payload
+            .get("cwd")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned),
+        events,
+        flush,
+    }
+}
+
+/// `code.read` / `code.edit` events for file tools, mirroring the Claude
+/// transcript adapter.
+fn claude_code_events(
+    tool: &str,
+    input: &Value,
+    response: &Value,
+    source: &Value,
+    now: &str,
+) -> Vec<Value> {
+    let Some(file) = input
+        .get("file_path")
+        .or_else(|| input.get("notebook_path"))
+        .and_then(Value::as_str)
+    else {
+        return Vec::new();
+    };
+    let text = |value: &Value, key: &str| value.get(key).and_then(Value::as_str).map(str::to_owned);
+    match tool {
+        "Read" => {
+            let Some(read) = response.get("file") else {
+                return Vec::new();
+            };
+            let start = read
+                .get("startLine")
+                .and_then(Value::as_u64)
+                .unwrap_or(1)
+                .max(1);
+            let lines = read.get("numLines").and_then(Value::as_u64).unwrap_or(1);
+            let end = start + lines.saturating_sub(1);
+            vec![json!({
+                "t": now,
+                "k": "code.read",
+                "source": source,
+                "file": file,
+                "range": [start, end],
+                "text": text(read, "content").unwrap_or_default(),
+                "range_basis": "line"
+            })]
+        }
+        "Edit" => vec![json!({
+            "t": now,
+            "k": "code.edit",
+            "source": source,
+            "file": file,
+            "before_text": text(input, "old_string"),
+            "after_text": text(input, "new_string")
+        })],
+        "Write" => vec![json!({
+            "t": now,
+            "k": "code.edit",
+            "source": source,
+            "file": file,
+            "after_text": text(input, "content")
+        })],
+        "NotebookEdit" => vec![json!({
+            "t": now,
+            "k": "code.edit",
+            "source": source,
+            "file": file,
+            "cell_id": text(input, "cell_id"),
+            "after_text": text(input, "new_source")
+        })],
+        "MultiEdit" => input
+            .get("edits")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|edit| {
+                json!({
+                    "t": now,
+                    "k": "code.edit",
+                    "source": source,
+                    "file": file,
+                    "before_text": text(edit, "old_string"),
+                    "after_text": text(edit, "new_string")
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn codex_turn_events(payload: &Value, now: &str) -> HookEvents {
+    let session_id = ["thread-id", "session-id", "turn-id"]
+        .iter()
+        .find_map(|key| payload.get(*key).and_then(Value::as_str))
+        .unwrap_or("unknown")
+        .to_string();
+    let source = source_block("codex", &session_id);
+    let mut events = Vec::new();
+    for message in payload
+        .get("input-messages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        events.push(json!({
+            "t": now,
+            "k": "msg.in",
+            "source": source,
+            "role": "user",
+            "content": message
+        }));
+    }
+    if let Some(message) = payload
+        .get("last-assistant-message")
+        .and_then(Value::as_str)
+    {
+        events.push(json!({
+            "t": now,
+            "k": "msg.out",
+            "source": source,
+            "role": "assistant",
+            "content": message
+        }));
+    }
+    HookEvents {
+        harness: "codex",
+        session_id,
+        cwd: payload
+            .get("cwd")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned),
+        events,
+        flush: true,
+    }
+}
+
+fn response_text(response: &Value) -> String {
+    match response {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        _ => ["stdout", "output", "content"]
+            .iter()
+            .find_map(|key| response.get(*key).and_then(Value::as_str))
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| response.to_string()),
+    }
+}
+
+fn source_block(harness: &str, session_id: &str) -> Value {
+    json!({
+        "harness": harness,
+        "session_id": session_id
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const NOW: &str = "2026-03-01T10:00:00Z";
+
+    #[test]
+    fn claude_post_tool_use_emits_call_result_and_edit() {
+        let payload = json!({
+            "session_id": "abc",
+            "cwd": "/repo",
+            "hook_event_name": "PostToolUse",
+            "tool_name": "Edit",
+            "tool_use_id": "toolu_1",
+            "tool_input": {"file_path": "/repo/src/lib.rs", "old_string": "a", "new_string": "b"},
+            "tool_response": {"filePath": "/repo/src/lib.rs", "success": true}
+        });
+        let hook = hook_payload_to_events(&payload, NOW).expect("claude payload");
+        assert_eq!(hook.harness, "claude-code");
+        assert_eq!(hook.session_id, "abc");
+        assert_eq!(hook.cwd.as_deref(), Some("/repo"));
+        assert!(!hook.flush);
+        let kinds = hook
+            .events
+            .iter()
+            .map(|event| event["k"].as_str().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec!["tool.call", "tool.result", "code.edit"]);
+        assert_eq!(hook.events[0]["call_id"], "toolu_1");
+        assert_eq!(hook.events[1]["exit"], 0);
+        assert_eq!(hook.events[2]["after_text"], "b");
+        assert_eq!(hook.events[2]["source"]["session_id"], "abc");
+
+        let stop = json!({"session_id": "abc", "hook_event_name": "Stop"});
+        let stop = hook_payload_to_events(&stop, NOW).expect("stop payload");
+        assert!(!stop.flush);
+        assert!(stop.events.is_empty());
+        let end = json!({"session_id": "abc", "hook_event_name": "SessionEnd"});
+        assert!(
+            hook_payload_to_events(&end, NOW)
+                .expect("end payload")
+                .flush
+        );
+    }
+
+    #[test]
+    fn codex_turn_complete_emits_messages_and_flushes() {
+        let payload = json!({
+            "type": "agent-turn-complete",
+            "turn-id": "turn-7",
+            "input-messages": ["rename the flag"],
+            "last-assistant-message": "Renamed it."
+        });
+        let hook = hook_payload_to_events(&payload, NOW).expect("codex payload");
+        assert_eq!(hook.harness, "codex");
+        assert_eq!(hook.session_id, "turn-7");
+        assert!(hook.flush);
+        assert_eq!(hook.events[0]["k"], "msg.in");
+        assert_eq!(hook.events[1]["content"], "Renamed it.");
+
+        assert_eq!(
+            hook_payload_to_events(&json!({"type": "approval-requested"}), NOW),
+            Err(HookPayloadError::UnsupportedCodexType(
+                "approval-requested".into()
+            ))
+        );
+        assert_eq!(
+            hook_payload_to_events(&json!({}), NOW),
+            Err(HookPayloadError::UnknownShape)
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Tape ids. A tape id is the SHA-256 of the tape's canonical event stream:
+//! every event re-serialized with sorted keys and null fields dropped, its
+//! envelope fields ([`ENVELOPE_FIELDS`]) trimmed, blank lines skipped. Two
+//! tapes that differ only in how an adapter formatted them get the same id,
+//! so a serialization change no longer re-mints every tape. Recorded content
+//! (messages, code, tool output) is hashed exactly as written: tapes that
+//! differ in its whitespace are different tapes.
+//!
+//! Tapes written before canonical ids are named by the hash of their exact
+//! bytes; [`legacy_tape_id`] computes that, and `reindex` renames them,
+//! keeping the old id as an alias (see [`crate::store::aliases`]).
+
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// Top-level event fields naming what an event is rather than what it
+/// recorded; surrounding whitespace in them does not change the id.
+pub const ENVELOPE_FIELDS: &[&str] = &["t", "k", "file", "tool", "model"];
+
+/// The id of a tape with content `input`.
+pub fn tape_id(input: &str) -> String {
+    hex(&Sha256::digest(canonical_jsonl(input).as_bytes()))
+}
+
+/// The id `input` had before canonical ids: the hash of its exact bytes.
+pub fn legacy_tape_id(input: &str) -> String {
+    hex(&Sha256::digest(input.as_bytes()))
+}
+
+/// Whether `tape_id` names `input`, by its canonical or a legacy id.
+pub fn names_content(tape_id: &str, input: &str) -> bool {
+    self::tape_id(input) == tape_id || legacy_tape_id(input) == tape_id
+}
+
+/// The event stream `tape_id` hashes: one canonical JSON line per event.
+/// Lines that are not JSON are kept, trimmed.
+pub fn canonical_jsonl(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for line in input.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(line) {
+            Ok(row) => out.push_str(&canonical_event(row).to_string()),
+            Err(_) => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// `row` with sorted keys, no null fields and its envelope fields trimmed.
+fn canonical_event(row: Value) -> Value {
+    let Value::Object(map) = row else {
+        return canonical(row);
+    };
+    let map = map
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(text) if ENVELOPE_FIELDS.contains(&key.as_str()) => {
+                let text = match text.trim() {
+                    trimmed if trimmed.len() == text.len() => text,
+                    trimmed => trimmed.to_string(),
+                };
+                (key, Value::String(text))
+            }
+            value => (key, value),
+        })
+        .collect::<Map<_, _>>();
+    canonical(Value::Object(map))
+}
+
+/// `value` with object keys sorted and null fields dropped, at every depth.
+fn canonical(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(canonical).collect()),
+        Value::Object(map) => {
+            let mut fields = map
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .collect::<Vec<_>>();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key, canonical(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        other => other,
+    }
+}
+
+fn hex(digest: &[u8]) -> String {
+    use std::fmt::Write as _;
+    let mut out = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cosmetic_formatting_keeps_the_id() {
+        let tape = concat!(
+            r#"{"t":"2026-03-01T10:00:00Z","k":"msg.in","text":"fix the build"}"#,
+            "\n",
+            r#"{"t":"2026-03-01T10:00:01Z","k":"code.edit","file":"src/lib.rs","after_range":[1,2]}"#,
+            "\n",
+        );
+        let reformatted = concat!(
+            r#"{ "k": "msg.in", "t": " 2026-03-01T10:00:00Z", "text": "fix the build" }"#,
+            "\n\n",
+            r#"{"after_range":[1, 2],"file":" src/lib.rs","k":"code.edit","t":"2026-03-01T10:00:01Z","model":null}"#,
+            "\n",
+        );
+        assert_eq!(tape_id(tape), tape_id(reformatted));
+        assert_ne!(legacy_tape_id(tape), legacy_tape_id(reformatted));
+        assert_ne!(tape_id(tape), tape_id(&tape.replace("[1,2]", "[1,3]")));
+
+        assert!(names_content(&tape_id(reformatted), tape));
+        assert!(names_content(&legacy_tape_id(reformatted), reformatted));
+        assert!(!names_content(&legacy_tape_id(reformatted), tape));
+    }
+
+    #[test]
+    fn whitespace_inside_recorded_content_changes_the_id() {
+        let edit = |after: &str| {
+            let row = serde_json::json!({
+                "t": "2026-03-01T10:00:01Z",
+                "k": "code.edit",
+                "file": "src/lib.rs",
+                "before_text": "fn a() {}",
+                "after_text": after,
+            });
+            format!("{row}\n")
+        };
+        let indented = edit("fn a() {\n    b();\n}");
+        assert_ne!(tape_id(&indented), tape_id(&edit("fn a() {\n  b();\n}")));
+        assert_ne!(
+            tape_id(&indented),
+            tape_id(&edit("fn a() {\n    b();\n}\n"))
+        );
+        assert_ne!(
+            tape_id(r#"{"t":"2026-03-01T10:00:00Z","k":"msg.in","text":"hi"}"#),
+            tape_id(r#"{"t":"2026-03-01T10:00:00Z","k":"msg.in","text":"hi\n"}"#)
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use serde_json::Value;
+
+/// Event kinds that place evidence on a file.
+const FILE_EVIDENCE_KINDS: &[&str] = &["code.read", "code.edit"];
+
+/// Compiled `ignore:` globs. Like an unanchored `.gitignore` entry, a glob
+/// matches a path when it matches the whole path or any trailing part of it
+/// that starts at a directory boundary, so `target/**` ignores every
+/// `target` directory and `*.lock` every lock file.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<glob::Pattern>,
+}
+
+impl IgnoreRules {
+    pub fn new(globs: &[String]) -> Result<Self, glob::PatternError> {
+        Ok(Self {
+            patterns: globs
+                .iter()
+                .map(|raw| glob::Pattern::new(raw.trim_start_matches("./")))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        let path = path.replace('\\', "/");
+        let suffixes = std::iter::once(path.as_str()).chain(
+            path.match_indices('/')
+                .map(|(at, _)| &path[at + 1..])
+                .filter(|rest| !rest.is_empty()),
+        );
+        suffixes
+            .flat_map(|suffix| self.patterns.iter().map(move |pattern| (pattern, suffix)))
+            .any(|(pattern, suffix)| pattern.matches_with(suffix, options))
+    }
+}
+
+/// Drop `code.read` / `code.edit` events on files matched by `rules`. Other
+/// lines pass through byte for byte. Returns the filtered tape and the
+/// number of events dropped.
+pub fn drop_ignored_events(
+    jsonl: &str,
+    rules: &IgnoreRules,
+) -> serde_json::Result<(String, usize)> {
+    if rules.is_empty() {
+        return Ok((jsonl.to_string(), 0));
+    }
+    let mut out = String::with_capacity(jsonl.len());
+    let mut dropped = 0usize;
+    for line in jsonl.lines() {
+        if !line.trim().is_empty() {
+            let row = serde_json::from_str::<Value>(line)?;
+            let on_file = row
+                .get("k")
+                .and_then(Value::as_str)
+                .is_some_and(|kind| FILE_EVIDENCE_KINDS.contains(&kind));
+            if on_file
+                && row
+                    .get("file")
+                    .and_then(Value::as_str)
+                    .is_some_and(|file| rules.matches(file))
+            {
+                dropped += 1;
+                continue;
+            }
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    Ok((out, dropped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_reads_and_edits_on_ignored_files() {
+        let rules = IgnoreRules::new(&[
+            "node_modules/**".to_string(),
+            "target/**".to_string(),
+            "*.lock".to_string(),
+        ])
+        .expect("rules");
+        assert!(rules.matches("/work/app/node_modules/left-pad/index.js"));
+        assert!(rules.matches("target/debug/build.rs"));
+        assert!(rules.matches("Cargo.lock"));
+        assert!(rules.matches("crates/core/Cargo.lock"));
+        assert!(!rules.matches("src/lock.rs"));
+        assert!(!rules.matches("src/target.rs"));
+
+        let jsonl = concat!(
+            r#"{"t":"T1","k":"code.read","file":"node_modules/x/index.js"}"#,
+            "\n",
+            r#"{"t":"T2","k":"code.edit","file":"src/main.rs","after_text":"fn main() {}"}"#,
+            "\n",
+            r#"{"t":"T3","k":"code.edit","file":"Cargo.lock","after_text":"[[package]]"}"#,
+            "\n",
+            r#"{"t":"T4","k":"tool.call","tool":"cat","file":"Cargo.lock"}"#,
+            "\n",
+        );
+        let (kept, dropped) = drop_ignored_events(jsonl, &rules).expect("filter");
+        assert_eq!(dropped, 2);
+        assert_eq!(
+            kept.lines().collect::<Vec<_>>(),
+            vec![jsonl.lines().nth(1).unwrap(), jsonl.lines().nth(3).unwrap()]
+        );
+
+        let (unchanged, dropped) =
+            drop_ignored_events(jsonl, &IgnoreRules::default()).expect("noop");
+        assert_eq!((unchanged.as_str(), dropped), (jsonl, 0));
+    }
+}
@@ -0,0 +1,145 @@
+//! Stitching tapes of one task into a single logical tape (`engram join`),
+//! for harnesses that write many tiny files per task. Events are merged by
+//! timestamp and each keeps a `joined_from` reference to the tape and
+//! offset it came from; the joined tape's `meta` lists its members with
+//! their origins, so the link survives `reindex`.
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value, json};
+
+/// `meta` key listing the tapes a joined tape was stitched from.
+pub const JOIN_META_KEY: &str = "join";
+
+/// Event key pointing back at the member tape and offset of an event.
+pub const JOINED_FROM_KEY: &str = "joined_from";
+
+/// Stitch `tapes` (tape id, content) into one tape. The first member's
+/// meta, without its `origin`, heads the result; every member's origin is
+/// kept under `join.tapes`. Events are ordered by timestamp, ties and
+/// untimed events staying in member order.
+pub fn join_tapes_jsonl(tapes: &[(String, String)]) -> String {
+    let mut meta = None::<Map<String, Value>>;
+    let mut members = Vec::new();
+    // (timestamp, member, offset, event)
+    let mut events = Vec::<(Option<DateTime<Utc>>, usize, u64, Value)>::new();
+    for (member, (tape_id, content)) in tapes.iter().enumerate() {
+        let mut origin = Value::Null;
+        let mut last_at = None;
+        for (offset, line) in content.lines().enumerate() {
+            let Ok(mut row) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            if row.get("k").and_then(Value::as_str) == Some("meta") {
+                let Value::Object(mut map) = row else {
+                    continue;
+                };
+                if origin.is_null()
+                    && let Some(found) = map.remove("origin")
+                {
+                    origin = found;
+                }
+                if meta.is_none() {
+                    meta = Some(map);
+                }
+                continue;
+            }
+            let at = row
+                .get("t")
+                .and_then(Value::as_str)
+                .and_then(|raw| DateTime::parse_from_rfc3339(raw).ok())
+                .map(|at| at.with_timezone(&Utc))
+                .or(last_at);
+            last_at = at;
+            row[JOINED_FROM_KEY] = json!({"tape_id": tape_id, "offset": offset});
+            events.push((at, member, offset as u64, row));
+        }
+        let mut entry = json!({"tape_id": tape_id});
+        if !origin.is_null() {
+            entry["origin"] = origin;
+        }
+        members.push(entry);
+    }
+    events.sort_by(|a, b| {
+        let at = |event: &(Option<DateTime<Utc>>, usize, u64, Value)| event.0.unwrap_or_default();
+        at(a).cmp(&at(b)).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2))
+    });
+
+    let mut meta = meta.unwrap_or_default();
+    meta.insert("k".to_string(), json!("meta"));
+    if let Some(first) = events
+        .iter()
+        .find_map(|(_, _, _, row)| row.get("t").cloned())
+    {
+        meta.insert("t".to_string(), first);
+    }
+    meta.insert(JOIN_META_KEY.to_string(), json!({"tapes": members}));
+    let mut out = Value::Object(meta).to_string();
+    out.push('\n');
+    for (_, _, _, row) in events {
+        out.push_str(&row.to_string());
+        out.push('\n');
+    }
+    out
+}
+
+/// The member tape ids of a joined tape, in join order.
+pub fn tape_join_members(input: &str) -> Vec<String> {
+    input
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|row| row.get("k").and_then(Value::as_str) == Some("meta"))
+        .and_then(|meta| {
+            Some(
+                meta.get(JOIN_META_KEY)?
+                    .get("tapes")?
+                    .as_array()?
+                    .iter()
+                    .filter_map(|member| member.get("tape_id")?.as_str().map(ToOwned::to_owned))
+                    .collect(),
+            )
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_by_time_and_keeps_references_to_members() {
+        let a = [
+            r#"{"t":"2026-03-01T10:00:00Z","k":"meta","source":{"harness":"cursor"},"origin":{"path":"a.json"}}"#,
+            r#"{"t":"2026-03-01T10:00:01Z","k":"msg.in","text":"start"}"#,
+            r#"{"t":"2026-03-01T10:00:05Z","k":"msg.out","text":"done"}"#,
+        ]
+        .join("\n");
+        let b = [
+            r#"{"t":"2026-03-01T10:00:02Z","k":"meta","source":{"harness":"cursor"},"origin":{"path":"b.json"}}"#,
+            r#"{"t":"2026-03-01T10:00:03Z","k":"code.edit","file":"src/lib.rs"}"#,
+            r#"{"k":"code.read","file":"src/lib.rs"}"#,
+        ]
+        .join("\n");
+
+        let joined = join_tapes_jsonl(&[("a".to_string(), a), ("b".to_string(), b)]);
+        let rows = joined
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("json"))
+            .collect::<Vec<_>>();
+        let kinds = rows
+            .iter()
+            .map(|row| row["k"].as_str().expect("kind"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            ["meta", "msg.in", "code.edit", "code.read", "msg.out"]
+        );
+        assert_eq!(rows[0]["t"], "2026-03-01T10:00:01Z");
+        assert!(rows[0].get("origin").is_none());
+        assert_eq!(rows[0]["join"]["tapes"][1]["origin"]["path"], "b.json");
+        assert_eq!(rows[3]["joined_from"], json!({"tape_id": "b", "offset": 2}));
+        assert_eq!(
+            tape_join_members(&joined),
+            ["a".to_string(), "b".to_string()]
+        );
+    }
+}
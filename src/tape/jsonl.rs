@@ -0,0 +1,90 @@
+//! Harness JSON Lines as found on disk: a UTF-8 byte order mark, a line
+//! cut short by a crash, or plain-text log lines interleaved with the JSON.
+//! Adapters see the JSON lines only; the rest is reported per line.
+
+use std::borrow::Cow;
+
+use serde::de::IgnoredAny;
+
+use super::event::ParseIssue;
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// `input` with the lines that are not JSON removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LenientJsonl<'a> {
+    pub text: Cow<'a, str>,
+    /// Non-blank lines kept.
+    pub json_lines: usize,
+    /// Lines removed, numbered from 1 in the original input.
+    pub skipped: Vec<ParseIssue>,
+}
+
+pub fn strip_byte_order_mark(input: &str) -> &str {
+    input.strip_prefix(BYTE_ORDER_MARK).unwrap_or(input)
+}
+
+/// Drop a leading byte order mark and every non-blank line that does not
+/// parse as JSON. Borrows `input` when nothing is dropped.
+pub fn lenient_jsonl(input: &str) -> LenientJsonl<'_> {
+    let input = strip_byte_order_mark(input);
+    let mut json_lines = 0usize;
+    let mut skipped = Vec::new();
+    let mut kept = String::new();
+    let mut kept_until = 0usize;
+    let mut at = 0usize;
+    for (idx, line) in input.split_inclusive('\n').enumerate() {
+        let start = at;
+        at += line.len();
+        let body = line.trim_end_matches(['\n', '\r']);
+        if body.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<IgnoredAny>(body) {
+            Ok(_) => json_lines += 1,
+            Err(err) => {
+                kept.push_str(&input[kept_until..start]);
+                kept_until = at;
+                skipped.push(ParseIssue {
+                    line: idx + 1,
+                    error: err.to_string(),
+                });
+            }
+        }
+    }
+    let text = if skipped.is_empty() {
+        Cow::Borrowed(input)
+    } else {
+        kept.push_str(&input[kept_until..]);
+        Cow::Owned(kept)
+    };
+    LenientJsonl {
+        text,
+        json_lines,
+        skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_json_lines_and_reports_the_rest() {
+        let input = "\u{feff}{\"a\":1}\n[INFO] harness started\n\n{\"b\":2}\r\n// note\n{\"c\":";
+        let cleaned = lenient_jsonl(input);
+        assert_eq!(cleaned.text, "{\"a\":1}\n\n{\"b\":2}\r\n");
+        assert_eq!(cleaned.json_lines, 2);
+        assert_eq!(
+            cleaned
+                .skipped
+                .iter()
+                .map(|issue| issue.line)
+                .collect::<Vec<_>>(),
+            [2, 5, 6]
+        );
+
+        let clean = "{\"a\":1}\n";
+        assert!(matches!(lenient_jsonl(clean).text, Cow::Borrowed(_)));
+    }
+}
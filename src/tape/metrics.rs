@@ -0,0 +1,366 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde_json::Value;
+
+/// Per-tape quality counters computed once at ingest and stored alongside
+/// the tape row so `tapes --with-metrics` and `stats` never re-read tapes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TapeMetrics {
+    pub event_count: u64,
+    pub message_count: u64,
+    pub tool_call_count: u64,
+    pub tool_error_count: u64,
+    pub edit_count: u64,
+    pub revert_count: u64,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub duration_secs: Option<i64>,
+    pub model: Option<String>,
+    pub harness: Option<String>,
+    /// The harness's own id for the session (`source.session_id`), shared
+    /// by every tape of a session captured in several pieces.
+    pub session_id: Option<String>,
+    /// Machine and login name from the meta `environment`
+    /// (`ingest.environment`), when recorded.
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    /// Number of [`ThrashLoop`]s in the tape.
+    pub thrash_loop_count: u64,
+    /// The adapter's coverage grades (`full`, `partial`, `none`) from the
+    /// tape's `meta`, for weighing the evidence the tape produced.
+    pub coverage_read: Option<String>,
+    pub coverage_edit: Option<String>,
+    pub coverage_tool: Option<String>,
+}
+
+impl TapeMetrics {
+    pub fn tool_error_rate(&self) -> Option<f64> {
+        ratio(self.tool_error_count, self.tool_call_count)
+    }
+
+    pub fn revert_ratio(&self) -> Option<f64> {
+        ratio(self.revert_count, self.edit_count)
+    }
+
+    pub fn messages_per_edit(&self) -> Option<f64> {
+        ratio(self.message_count, self.edit_count)
+    }
+
+    /// The coverage grade the tape's adapter declared for `kind` (`read`,
+    /// `edit` or `tool`), when it declared one.
+    pub fn coverage(&self, kind: &str) -> Option<&str> {
+        match kind {
+            "read" => self.coverage_read.as_deref(),
+            "edit" => self.coverage_edit.as_deref(),
+            "tool" => self.coverage_tool.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+fn ratio(numerator: u64, denominator: u64) -> Option<f64> {
+    if denominator == 0 {
+        None
+    } else {
+        Some(numerator as f64 / denominator as f64)
+    }
+}
+
+/// Compute [`TapeMetrics`] from normalized tape JSONL. Lines that fail to
+/// parse are skipped; metrics are advisory and never block ingest.
+pub fn tape_metrics_from_jsonl(input: &str) -> TapeMetrics {
+    let mut metrics = TapeMetrics::default();
+    let mut first_ts: Option<chrono::DateTime<chrono::Utc>> = None;
+    let mut last_ts: Option<chrono::DateTime<chrono::Utc>> = None;
+    let mut before_sides: HashMap<String, Vec<String>> = HashMap::new();
+
+    for line in input.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(row) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        metrics.event_count += 1;
+
+        if let Some(ts) = row
+            .get("t")
+            .and_then(Value::as_str)
+            .and_then(|raw| chrono::DateTime::parse_from_rfc3339(raw).ok())
+            .map(|value| value.with_timezone(&chrono::Utc))
+        {
+            if first_ts.is_none_or(|first| ts < first) {
+                first_ts = Some(ts);
+            }
+            if last_ts.is_none_or(|last| ts > last) {
+                last_ts = Some(ts);
+            }
+        }
+
+        match row.get("k").and_then(Value::as_str) {
+            Some("msg.in" | "msg.out") => metrics.message_count += 1,
+            Some("tool.call") => metrics.tool_call_count += 1,
+            Some("tool.result")
+                if row
+                    .get("exit")
+                    .and_then(Value::as_i64)
+                    .is_some_and(|exit| exit != 0) =>
+            {
+                metrics.tool_error_count += 1;
+            }
+            Some("code.edit") => {
+                metrics.edit_count += 1;
+                let file = row.get("file").and_then(Value::as_str).unwrap_or_default();
+                let seen = before_sides.entry(file.to_string()).or_default();
+                if let Some(after) = edit_side_identity(&row, "after")
+                    && seen.contains(&after)
+                {
+                    metrics.revert_count += 1;
+                }
+                if let Some(before) = edit_side_identity(&row, "before") {
+                    seen.push(before);
+                }
+            }
+            Some("meta") => {
+                if metrics.model.is_none() {
+                    metrics.model = row
+                        .get("model")
+                        .and_then(Value::as_str)
+                        .map(ToOwned::to_owned);
+                }
+                if metrics.harness.is_none() {
+                    metrics.harness = row
+                        .get("source")
+                        .and_then(|source| source.get("harness"))
+                        .and_then(Value::as_str)
+                        .map(ToOwned::to_owned);
+                }
+                if metrics.session_id.is_none() {
+                    metrics.session_id = row
+                        .get("source")
+                        .and_then(|source| source.get("session_id"))
+                        .and_then(Value::as_str)
+                        .map(ToOwned::to_owned);
+                }
+                for (field, slot) in [
+                    ("hostname", &mut metrics.hostname),
+                    ("user", &mut metrics.user),
+                ] {
+                    if slot.is_none() {
+                        *slot = row
+                            .get("environment")
+                            .and_then(|environment| environment.get(field))
+                            .and_then(Value::as_str)
+                            .map(ToOwned::to_owned);
+                    }
+                }
+                for (field, slot) in [
+                    ("read", &mut metrics.coverage_read),
+                    ("edit", &mut metrics.coverage_edit),
+                    ("tool", &mut metrics.coverage_tool),
+                ] {
+                    if slot.is_none() {
+                        *slot = row
+                            .get(format!("coverage.{field}"))
+                            .and_then(Value::as_str)
+                            .map(ToOwned::to_owned);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    metrics.started_at = first_ts.map(|ts| ts.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    metrics.ended_at = last_ts.map(|ts| ts.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    metrics.duration_secs = match (first_ts, last_ts) {
+        (Some(first), Some(last)) => Some((last - first).num_seconds()),
+        _ => None,
+    };
+    metrics.thrash_loop_count = thrash_loops_from_jsonl(input).len() as u64;
+    metrics
+}
+
+/// Identical failures of the same call before they count as a thrash loop.
+pub const THRASH_MIN_REPEATS: usize = 3;
+
+/// The same tool call failing the same way over and over within one tape:
+/// an agent retrying without changing anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThrashLoop {
+    pub tool: String,
+    /// The call's `args`, as recorded.
+    pub args: Option<String>,
+    pub exit: i64,
+    /// Offsets of the failing `tool.result` events, in tape order.
+    pub result_offsets: Vec<u64>,
+}
+
+/// Group failed tool results by their call and their output, and keep the
+/// groups that repeat at least [`THRASH_MIN_REPEATS`] times. Results pair
+/// with calls by `call_id`, or else with the oldest unanswered call of the
+/// same tool; results with no call are not judged.
+pub fn thrash_loops_from_jsonl(input: &str) -> Vec<ThrashLoop> {
+    let mut by_call_id = HashMap::<String, (String, Option<String>)>::new();
+    let mut by_tool = HashMap::<String, VecDeque<(String, Option<String>)>>::new();
+    let mut loops = Vec::<ThrashLoop>::new();
+    let mut loop_by_key = HashMap::<(String, String), usize>::new();
+
+    for (offset, line) in input.lines().enumerate() {
+        let Ok(row) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let tool = row
+            .get("tool")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let call_id = row.get("call_id").and_then(Value::as_str);
+        match row.get("k").and_then(Value::as_str) {
+            Some("tool.call") => {
+                let args = row.get("args").map(|args| match args {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                });
+                let call = (event_identity(&row), args);
+                match call_id {
+                    Some(call_id) => {
+                        by_call_id.insert(call_id.to_string(), call);
+                    }
+                    None => by_tool.entry(tool).or_default().push_back(call),
+                }
+            }
+            Some("tool.result") => {
+                let call = match call_id {
+                    Some(call_id) => by_call_id.remove(call_id),
+                    None => by_tool.get_mut(&tool).and_then(VecDeque::pop_front),
+                };
+                let exit = row.get("exit").and_then(Value::as_i64).unwrap_or(0);
+                let Some((call_identity, args)) = call.filter(|_| exit != 0) else {
+                    continue;
+                };
+                let key = (call_identity, event_identity(&row));
+                let at = *loop_by_key.entry(key).or_insert_with(|| {
+                    loops.push(ThrashLoop {
+                        tool,
+                        args,
+                        exit,
+                        result_offsets: Vec::new(),
+                    });
+                    loops.len() - 1
+                });
+                loops[at].result_offsets.push(offset as u64);
+            }
+            _ => {}
+        }
+    }
+    loops.retain(|thrash| thrash.result_offsets.len() >= THRASH_MIN_REPEATS);
+    loops
+}
+
+/// An event without the fields that differ between identical retries.
+fn event_identity(row: &Value) -> String {
+    let mut row = row.clone();
+    if let Some(object) = row.as_object_mut() {
+        for field in ["t", "call_id", "source"] {
+            object.remove(field);
+        }
+    }
+    row.to_string()
+}
+
+/// Identity of one side of a `code.edit` row: the recorded hash when present,
+/// otherwise the trimmed text. Empty sides have no identity.
+pub fn edit_side_identity(row: &Value, side: &str) -> Option<String> {
+    if let Some(hash) = row
+        .get(format!("{side}_hash"))
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+    {
+        return Some(format!("hash:{hash}"));
+    }
+    row.get(format!("{side}_text"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|text| format!("text:{text}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_errors_edits_reverts_and_duration() {
+        let jsonl = r#"{"t":"2026-02-22T00:00:00Z","k":"meta","model":"gpt-5","source":{"harness":"codex-cli","session_id":"s-1"},"environment":{"hostname":"laptop"},"coverage.read":"partial","coverage.edit":"partial","coverage.tool":"full"}
+{"t":"2026-02-22T00:00:01Z","k":"msg.in","text":"fix it"}
+{"t":"2026-02-22T00:00:02Z","k":"tool.call","tool":"cargo test"}
+{"t":"2026-02-22T00:00:03Z","k":"tool.result","tool":"cargo test","exit":101}
+{"t":"2026-02-22T00:00:04Z","k":"tool.call","tool":"ls"}
+{"t":"2026-02-22T00:00:05Z","k":"tool.result","tool":"ls","exit":0}
+{"t":"2026-02-22T00:00:06Z","k":"code.edit","file":"src/lib.rs","before_text":"a()","after_text":"b()"}
+{"t":"2026-02-22T00:00:07Z","k":"code.edit","file":"src/lib.rs","before_text":"b()","after_text":"a()"}
+{"t":"2026-02-22T00:01:00Z","k":"msg.out","text":"done"}"#;
+
+        let metrics = tape_metrics_from_jsonl(jsonl);
+        assert_eq!(metrics.event_count, 9);
+        assert_eq!(metrics.message_count, 2);
+        assert_eq!(metrics.tool_call_count, 2);
+        assert_eq!(metrics.tool_error_count, 1);
+        assert_eq!(metrics.edit_count, 2);
+        assert_eq!(metrics.revert_count, 1);
+        assert_eq!(metrics.duration_secs, Some(60));
+        assert_eq!(metrics.model.as_deref(), Some("gpt-5"));
+        assert_eq!(metrics.harness.as_deref(), Some("codex-cli"));
+        assert_eq!(metrics.session_id.as_deref(), Some("s-1"));
+        assert_eq!(metrics.hostname.as_deref(), Some("laptop"));
+        assert_eq!(metrics.user, None);
+        assert_eq!(metrics.coverage("edit"), Some("partial"));
+        assert_eq!(metrics.coverage("tool"), Some("full"));
+        assert_eq!(metrics.tool_error_rate(), Some(0.5));
+        assert_eq!(metrics.revert_ratio(), Some(0.5));
+        assert_eq!(metrics.messages_per_edit(), Some(1.0));
+    }
+
+    #[test]
+    fn flags_identical_failures_of_identical_calls() {
+        let mut lines = Vec::new();
+        for (n, stderr) in ["boom", "boom", "boom", "other"].iter().enumerate() {
+            lines.push(format!(
+                r#"{{"t":"T{n}","k":"tool.call","tool":"bash","args":"cargo test","call_id":"c{n}"}}"#
+            ));
+            lines.push(format!(
+                r#"{{"t":"T{n}","k":"tool.result","tool":"bash","exit":101,"stderr":"{stderr}","call_id":"c{n}"}}"#
+            ));
+        }
+        // Same call answered without call ids, but it succeeds once.
+        for exit in [1, 1, 0] {
+            lines.push(r#"{"t":"T9","k":"tool.call","tool":"ls","args":"x"}"#.to_string());
+            lines.push(format!(
+                r#"{{"t":"T9","k":"tool.result","tool":"ls","exit":{exit}}}"#
+            ));
+        }
+        let jsonl = lines.join("\n");
+
+        let loops = thrash_loops_from_jsonl(&jsonl);
+        assert_eq!(
+            loops,
+            vec![ThrashLoop {
+                tool: "bash".to_string(),
+                args: Some("cargo test".to_string()),
+                exit: 101,
+                result_offsets: vec![1, 3, 5],
+            }]
+        );
+        assert_eq!(tape_metrics_from_jsonl(&jsonl).thrash_loop_count, 1);
+    }
+
+    #[test]
+    fn ratios_are_none_without_denominator() {
+        let metrics = tape_metrics_from_jsonl(r#"{"t":"2026-02-22T00:00:00Z","k":"msg.in"}"#);
+        assert_eq!(metrics.tool_error_rate(), None);
+        assert_eq!(metrics.revert_ratio(), None);
+        assert_eq!(metrics.messages_per_edit(), None);
+        assert_eq!(metrics.duration_secs, Some(0));
+    }
+}
@@ -1,7 +1,34 @@
 pub mod adapter;
 pub mod adapters;
+pub mod binary;
+pub mod catalog;
+pub mod comments;
+#[cfg(feature = "native")]
 pub mod compress;
+pub mod diff;
+pub mod edits;
+pub mod environment;
 pub mod event;
+pub mod github;
 pub mod harness;
+pub mod hooks;
+pub mod identity;
+pub mod ignore;
+pub mod join;
+pub mod jsonl;
+pub mod metrics;
+pub mod normalize;
+pub mod notebook;
+pub mod origin;
+pub mod patch;
+pub mod paths;
+pub mod projects;
+pub mod refs;
+pub mod rotation;
+pub mod sample;
+pub mod snapshot;
+pub mod spawn;
+pub mod split;
+pub mod worktree;
 
 pub use event::{TapeEventAt, parse_jsonl_events};
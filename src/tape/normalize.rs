@@ -0,0 +1,155 @@
+use serde_json::Value;
+
+use crate::anchor::normalize::normalizer_for_path;
+use crate::anchor::normalize_line_endings;
+
+/// Text fields that evidence anchors are computed from, per event kind.
+const EVIDENCE_TEXT_FIELDS: &[(&str, &[&str])] = &[
+    ("code.edit", &["before_text", "after_text"]),
+    ("code.read", &["text"]),
+];
+
+/// Pass the evidence text of `code.edit` / `code.read` events through
+/// [`normalize_line_endings`]. Lines without such text pass through byte
+/// for byte. Returns the tape and the number of fields changed.
+pub fn normalize_evidence_text(jsonl: &str) -> serde_json::Result<(String, usize)> {
+    let mut out = String::with_capacity(jsonl.len());
+    let mut changed = 0usize;
+    for line in jsonl.lines() {
+        if !line.contains("\"code.edit\"") && !line.contains("\"code.read\"") {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let mut row: Value = serde_json::from_str(line)?;
+        let kind = row.get("k").and_then(Value::as_str).unwrap_or_default();
+        let fields = EVIDENCE_TEXT_FIELDS
+            .iter()
+            .find(|(event_kind, _)| *event_kind == kind)
+            .map_or(&[][..], |(_, fields)| fields);
+        let mut row_changed = false;
+        for field in fields {
+            let Some(text) = row.get(*field).and_then(Value::as_str) else {
+                continue;
+            };
+            let normalized = normalize_line_endings(text);
+            if normalized != text {
+                row[*field] = Value::String(normalized.into_owned());
+                row_changed = true;
+                changed += 1;
+            }
+        }
+        if row_changed {
+            out.push_str(&serde_json::to_string(&row)?);
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    Ok((out, changed))
+}
+
+/// Set `normalizer` on `code.edit` / `code.read` events to the tag of the
+/// span normalizer `enabled` selects for their file, and clear it where
+/// none applies, so the index fingerprints each event under the current
+/// `anchors.normalizers`. Other lines pass through byte for byte. Returns
+/// the tape and the number of events changed.
+pub fn stamp_span_normalizers(
+    jsonl: &str,
+    enabled: &[String],
+) -> serde_json::Result<(String, usize)> {
+    let mut out = String::with_capacity(jsonl.len());
+    let mut changed = 0usize;
+    for line in jsonl.lines() {
+        let is_evidence = line.contains("\"code.edit\"") || line.contains("\"code.read\"");
+        if !is_evidence || (enabled.is_empty() && !line.contains("\"normalizer\"")) {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let mut row: Value = serde_json::from_str(line)?;
+        let kind = row.get("k").and_then(Value::as_str).unwrap_or_default();
+        let tag = row
+            .get("file")
+            .and_then(Value::as_str)
+            .and_then(|file| normalizer_for_path(file, enabled))
+            .map(|normalizer| normalizer.tag());
+        let current = row.get("normalizer").and_then(Value::as_str);
+        if !EVIDENCE_TEXT_FIELDS
+            .iter()
+            .any(|(event_kind, _)| *event_kind == kind)
+            || current == tag.as_deref()
+        {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let Some(object) = row.as_object_mut() else {
+            continue;
+        };
+        match tag {
+            Some(tag) => object.insert("normalizer".to_string(), Value::String(tag)),
+            None => object.remove("normalizer"),
+        };
+        changed += 1;
+        out.push_str(&serde_json::to_string(&row)?);
+        out.push('\n');
+    }
+    Ok((out, changed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_only_evidence_text() {
+        let jsonl = concat!(
+            r#"{"t":"T1","k":"code.edit","file":"a.rs","before_text":"﻿a\r\nb\r\n","after_text":"a\nc\n"}"#,
+            "\n",
+            r#"{"t":"T2","k":"code.read","file":"a.rs","text":"x\ry"}"#,
+            "\n",
+            r#"{"t":"T3","k":"tool.result","stdout":"code.edit\r\n"}"#,
+            "\n",
+        );
+        let (normalized, changed) = normalize_evidence_text(jsonl).expect("normalize");
+        assert_eq!(changed, 2);
+        let rows = normalized
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows[0]["before_text"], "a\nb\n");
+        assert_eq!(rows[1]["text"], "x\ny");
+        assert_eq!(normalized.lines().nth(2), jsonl.lines().nth(2));
+        assert_eq!(normalize_evidence_text(&normalized).expect("again").1, 0);
+    }
+
+    #[test]
+    fn stamps_the_enabled_normalizer_per_file() {
+        let jsonl = concat!(
+            r#"{"t":"T1","k":"code.edit","file":"src/lib.rs","after_text":"fn a() {}\n"}"#,
+            "\n",
+            r#"{"t":"T2","k":"code.read","file":"app.py","text":"x = 1\n","normalizer":"python.v1"}"#,
+            "\n",
+            r#"{"t":"T3","k":"tool.call","tool":"cat","args":"src/lib.rs"}"#,
+            "\n",
+        );
+        let (stamped, changed) =
+            stamp_span_normalizers(jsonl, &["rust".to_string()]).expect("stamp");
+        assert_eq!(changed, 2);
+        let rows = stamped
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows[0]["normalizer"], "rust.v1");
+        assert!(rows[1].get("normalizer").is_none());
+        assert_eq!(stamped.lines().nth(2), jsonl.lines().nth(2));
+        assert_eq!(
+            stamp_span_normalizers(&stamped, &["rust".to_string()])
+                .expect("again")
+                .1,
+            0
+        );
+        assert_eq!(stamp_span_normalizers(jsonl, &[]).expect("off").1, 1);
+    }
+}
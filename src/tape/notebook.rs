@@ -0,0 +1,287 @@
+//! Jupyter notebooks (`.ipynb`, which is also what Databricks exports) are
+//! JSON: a cell's source is an array of string literals next to its outputs
+//! and execution counts. Fingerprinting that JSON ties lineage to escaping
+//! and to output churn, so evidence sees a notebook as its cell view
+//! instead: each cell is a `# %% cell=<id>` header line followed by the
+//! cell's source, normalized, with outputs left out.
+
+use serde_json::Value;
+
+use crate::anchor::normalize_line_endings;
+
+/// One notebook cell as evidence sees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotebookCell {
+    /// The cell's `id` (nbformat 4.5+), or its 1-based position as `#<n>`
+    /// for older notebooks.
+    pub id: String,
+    pub cell_type: String,
+    pub source: String,
+}
+
+/// Whether `path` names a Jupyter notebook.
+pub fn is_notebook_path(path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, extension)| extension.eq_ignore_ascii_case("ipynb"))
+}
+
+/// The cells of notebook JSON `text`, or `None` when it is not a notebook.
+pub fn notebook_cells(text: &str) -> Option<Vec<NotebookCell>> {
+    let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+    if !trimmed.starts_with('{') || !trimmed.contains("\"cells\"") {
+        return None;
+    }
+    let root: Value = serde_json::from_str(trimmed).ok()?;
+    let cells = root.get("cells")?.as_array()?;
+    Some(
+        cells
+            .iter()
+            .enumerate()
+            .map(|(idx, cell)| NotebookCell {
+                id: cell
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .map_or_else(|| format!("#{}", idx + 1), ToOwned::to_owned),
+                cell_type: cell
+                    .get("cell_type")
+                    .and_then(Value::as_str)
+                    .unwrap_or("code")
+                    .to_string(),
+                source: normalized_source(cell.get("source")),
+            })
+            .collect(),
+    )
+}
+
+/// The cell view of notebook JSON `text`, or `None` when it is not a
+/// notebook.
+pub fn notebook_view(text: &str) -> Option<String> {
+    let mut out = String::new();
+    for cell in notebook_cells(text)? {
+        out.push_str("# %% ");
+        if cell.cell_type != "code" {
+            out.push_str(&format!("[{}] ", cell.cell_type));
+        }
+        out.push_str(&format!("cell={}\n", cell.id));
+        out.push_str(&cell.source);
+    }
+    Some(out)
+}
+
+/// The source lines inside a fragment of raw notebook JSON, such as an
+/// edit's `old_string` taken from the file itself: every line that is a
+/// single JSON string literal (`"x = 1\n",`), decoded and joined. `None`
+/// unless some line is and every other line is JSON structure (brackets or
+/// a `"key":` line), so source code is never mistaken for a fragment.
+pub fn notebook_json_fragment_source(text: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut decoded_any = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let literal = trimmed.strip_suffix(',').unwrap_or(trimmed);
+        if literal.len() >= 2
+            && literal.starts_with('"')
+            && literal.ends_with('"')
+            && let Ok(Value::String(decoded)) = serde_json::from_str::<Value>(literal)
+        {
+            out.push_str(&decoded);
+            decoded_any = true;
+        } else if !is_json_structure_line(trimmed) {
+            return None;
+        }
+    }
+    decoded_any.then(|| normalize_source_text(&out))
+}
+
+/// A blank line, brackets and commas only, or a line opening with a
+/// `"key":`.
+fn is_json_structure_line(line: &str) -> bool {
+    if line
+        .chars()
+        .all(|c| matches!(c, '[' | ']' | '{' | '}' | ','))
+    {
+        return true;
+    }
+    line.strip_prefix('"')
+        .and_then(|rest| rest.split_once('"'))
+        .is_some_and(|(key, rest)| !key.contains('\\') && rest.trim_start().starts_with(':'))
+}
+
+/// Text fields that evidence anchors are computed from, per event kind,
+/// with the range field that numbers their lines.
+const EVIDENCE_FIELDS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "code.edit",
+        &[
+            ("before_text", "before_range"),
+            ("after_text", "after_range"),
+        ],
+    ),
+    ("code.read", &[("text", "range")]),
+];
+
+/// Rewrite the evidence text of `code.edit` / `code.read` events on
+/// notebooks to what anchors should see: a whole notebook becomes its cell
+/// view, a raw JSON fragment its decoded source. Line ranges of rewritten
+/// fields numbered JSON lines, so they are dropped. Other lines pass
+/// through byte for byte. Returns the tape and the number of fields changed.
+pub fn normalize_notebook_evidence(jsonl: &str) -> serde_json::Result<(String, usize)> {
+    let mut out = String::with_capacity(jsonl.len());
+    let mut changed = 0usize;
+    for line in jsonl.lines() {
+        if !line.contains(".ipynb") && !line.contains(".IPYNB") {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let mut row: Value = serde_json::from_str(line)?;
+        let kind = row.get("k").and_then(Value::as_str).unwrap_or_default();
+        let is_notebook = row
+            .get("file")
+            .and_then(Value::as_str)
+            .is_some_and(is_notebook_path);
+        let fields = EVIDENCE_FIELDS
+            .iter()
+            .find(|(event_kind, _)| is_notebook && *event_kind == kind)
+            .map_or(&[][..], |(_, fields)| fields);
+        let mut row_changed = false;
+        for (field, range) in fields {
+            let Some(text) = row.get(*field).and_then(Value::as_str) else {
+                continue;
+            };
+            let Some(rewritten) =
+                notebook_view(text).or_else(|| notebook_json_fragment_source(text))
+            else {
+                continue;
+            };
+            if rewritten != text {
+                row[*field] = Value::String(rewritten);
+                if let Some(object) = row.as_object_mut() {
+                    object.remove(*range);
+                }
+                row_changed = true;
+                changed += 1;
+            }
+        }
+        if row_changed {
+            out.push_str(&serde_json::to_string(&row)?);
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    Ok((out, changed))
+}
+
+/// A cell's `source` (a string or an array of lines) as one normalized text.
+fn normalized_source(source: Option<&Value>) -> String {
+    let text = match source {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    };
+    normalize_source_text(&text)
+}
+
+/// Line endings normalized and a final newline added, so a cell's last
+/// line reads the same wherever it sits.
+fn normalize_source_text(text: &str) -> String {
+    let mut text = normalize_line_endings(text).into_owned();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{
+        is_notebook_path, normalize_notebook_evidence, notebook_json_fragment_source, notebook_view,
+    };
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {"cell_type": "markdown", "id": "intro", "metadata": {}, "source": ["# Load\r\n", "Reads the table."]},
+  {"cell_type": "code", "id": "load", "execution_count": 7, "metadata": {},
+   "outputs": [{"output_type": "stream", "name": "stdout", "text": ["42 rows\n"]}],
+   "source": ["df = spark.read.table(\"events\")\n", "df.count()"]}
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##;
+
+    #[test]
+    fn notebook_view_keeps_cell_ids_and_sources_without_outputs() {
+        assert_eq!(
+            notebook_view(NOTEBOOK).expect("notebook"),
+            concat!(
+                "# %% [markdown] cell=intro\n",
+                "# Load\n",
+                "Reads the table.\n",
+                "# %% cell=load\n",
+                "df = spark.read.table(\"events\")\n",
+                "df.count()\n",
+            )
+        );
+        let old = NOTEBOOK.replace(r#""id": "load", "#, "");
+        assert!(
+            notebook_view(&old)
+                .expect("notebook")
+                .contains("# %% cell=#2\n")
+        );
+        assert_eq!(notebook_view("{\"k\": 1}"), None);
+        assert!(is_notebook_path("analysis/Report.IPYNB"));
+        assert!(!is_notebook_path("src/lib.rs"));
+    }
+
+    #[test]
+    fn json_fragments_decode_to_cell_source() {
+        let fragment =
+            "    \"df = spark.read.table(\\\"events\\\")\\n\",\n    \"df.count()\"\n   ]";
+        assert_eq!(
+            notebook_json_fragment_source(fragment).as_deref(),
+            Some("df = spark.read.table(\"events\")\ndf.count()\n")
+        );
+        assert_eq!(notebook_json_fragment_source("   },\n   {"), None);
+        assert_eq!(
+            notebook_json_fragment_source("print(\"x\")\n\"literal\"\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn notebook_evidence_is_rewritten_and_other_files_pass_through() {
+        let write = serde_json::json!({
+            "t": "T1", "k": "code.edit", "file": "nb/load.ipynb",
+            "after_range": [1, 20], "after_text": NOTEBOOK
+        });
+        let fragment = serde_json::json!({
+            "t": "T2", "k": "code.edit", "file": "nb/load.ipynb",
+            "before_text": "    \"df.count()\"\n", "after_text": "    \"df.count()\\n\",\n    \"df.show()\"\n"
+        });
+        let plain = r#"{"t":"T3","k":"code.edit","file":"src/lib.rs","after_text":"    \"x\",\n"}"#;
+        let jsonl = format!("{write}\n{fragment}\n{plain}\n");
+        let (normalized, changed) = normalize_notebook_evidence(&jsonl).expect("normalize");
+        assert_eq!(changed, 3);
+        let rows = normalized
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+            .collect::<Vec<_>>();
+        assert!(
+            rows[0]["after_text"]
+                .as_str()
+                .is_some_and(|text| text.starts_with("# %% [markdown] cell=intro\n"))
+        );
+        assert!(rows[0].get("after_range").is_none());
+        assert_eq!(rows[1]["before_text"], "df.count()\n");
+        assert_eq!(rows[1]["after_text"], "df.count()\ndf.show()\n");
+        assert_eq!(normalized.lines().nth(2), Some(plain));
+        assert_eq!(
+            normalize_notebook_evidence(&normalized).expect("again").1,
+            0
+        );
+    }
+}
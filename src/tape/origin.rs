@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use super::adapter::AdapterId;
+use super::event::{TapeEventAt, TapeEventData};
+
+/// Where a converted tape came from, stored under `origin` in its meta event
+/// so `engram show --origin` can trace it back to the raw harness file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TapeOrigin {
+    /// Raw harness file, as passed to ingest (absolute).
+    pub path: String,
+    /// Byte range `[start, end)` of the file that was converted; incremental
+    /// ingest converts only what was appended since the last run.
+    pub byte_range: [u64; 2],
+    /// SHA-256 of the bytes in `byte_range`.
+    pub sha256: String,
+    pub adapter: String,
+    pub adapter_version: String,
+    /// When ingest converted the file. Kept out of the tape (tape ids are
+    /// content hashes, so re-converting the same input must yield the same
+    /// bytes) and recorded in the index only.
+    #[serde(skip)]
+    pub converted_at: Option<String>,
+}
+
+impl TapeOrigin {
+    /// Origin of `raw`, read from `path` starting at byte `start`.
+    pub fn for_input(path: &str, start: u64, raw: &[u8], adapter: AdapterId) -> Self {
+        Self {
+            path: path.to_string(),
+            byte_range: [start, start + raw.len() as u64],
+            sha256: format!("{:x}", Sha256::digest(raw)),
+            adapter: adapter.as_str().to_string(),
+            adapter_version: adapter.version().to_string(),
+            converted_at: None,
+        }
+    }
+}
+
+/// The origin recorded in a tape's first meta event, if any.
+pub fn tape_origin(events: &[TapeEventAt]) -> Option<&TapeOrigin> {
+    events.iter().find_map(|item| match &item.event.data {
+        TapeEventData::Meta(meta) => meta.origin.as_ref(),
+        _ => None,
+    })
+}
+
+/// Record `origin` on the first meta event of a normalized tape, prepending a
+/// meta event (stamped with the first event's time) when there is none.
+/// Other lines are passed through untouched.
+pub fn stamp_origin(jsonl: &str, origin: &TapeOrigin) -> serde_json::Result<String> {
+    stamp_meta_field(jsonl, "origin", serde_json::to_value(origin)?)
+}
+
+/// Set `key` to `value` on the first meta event of a normalized tape,
+/// prepending a meta event (stamped with the first event's time) when there
+/// is none. Other lines are passed through untouched.
+pub fn stamp_meta_field(jsonl: &str, key: &str, value: Value) -> serde_json::Result<String> {
+    let mut out = String::with_capacity(jsonl.len() + 256);
+    let mut stamped = false;
+    let mut first_timestamp = None;
+    for line in jsonl.lines() {
+        if !stamped && !line.trim().is_empty() {
+            let mut row: Value = serde_json::from_str(line)?;
+            if first_timestamp.is_none() {
+                first_timestamp = row.get("t").cloned();
+            }
+            if row.get("k").and_then(Value::as_str) == Some("meta") {
+                row[key] = value.clone();
+                out.push_str(&serde_json::to_string(&row)?);
+                out.push('\n');
+                stamped = true;
+                continue;
+            }
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    if stamped {
+        return Ok(out);
+    }
+    let meta = json!({
+        "t": first_timestamp.unwrap_or_else(|| json!("")),
+        "k": "meta",
+        key: value,
+    });
+    Ok(format!("{}\n{out}", serde_json::to_string(&meta)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tape::event::parse_jsonl_events;
+
+    #[test]
+    fn stamps_the_meta_event_or_prepends_one() {
+        let origin = TapeOrigin::for_input("/logs/s.jsonl", 10, b"raw", AdapterId::ClaudeCode);
+        assert_eq!(origin.adapter, "claude-code");
+        assert_eq!(origin.byte_range, [10, 13]);
+
+        let with_meta = concat!(
+            r#"{"t":"2026-03-01T00:00:00Z","k":"msg.in","text":"hi"}"#,
+            "\n",
+            r#"{"t":"2026-03-01T00:00:01Z","k":"meta","model":"m"}"#,
+            "\n",
+        );
+        let stamped = stamp_origin(with_meta, &origin).expect("stamp");
+        assert!(stamped.starts_with(r#"{"t":"2026-03-01T00:00:00Z","k":"msg.in","text":"hi"}"#));
+        let events = parse_jsonl_events(&stamped).expect("parse");
+        assert_eq!(tape_origin(&events), Some(&origin));
+        assert_eq!(stamp_origin(with_meta, &origin).expect("again"), stamped);
+
+        let without_meta = concat!(
+            r#"{"t":"2026-03-01T00:00:00Z","k":"msg.in","text":"hi"}"#,
+            "\n"
+        );
+        let stamped = stamp_origin(without_meta, &origin).expect("stamp");
+        let events = parse_jsonl_events(&stamped).expect("parse");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event.timestamp, "2026-03-01T00:00:00Z");
+        assert_eq!(tape_origin(&events), Some(&origin));
+    }
+}
@@ -0,0 +1,352 @@
+/// Lines of context around each change, as in `diff -u`.
+const CONTEXT_LINES: usize = 3;
+
+/// Above this many line pairs the edit is rendered as one remove-all/add-all
+/// hunk instead of running the quadratic LCS.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Unified-diff hunks (without `---`/`+++` headers) turning `before` into
+/// `after`, numbered from `before_start` / `after_start` in the file.
+/// Empty when the texts are equal.
+pub fn unified_hunks(before: &str, after: &str, before_start: u32, after_start: u32) -> String {
+    let old = before.lines().collect::<Vec<_>>();
+    let new = after.lines().collect::<Vec<_>>();
+    let ops = line_ops(&old, &new);
+
+    // Every op with its (old, new) line index at that point.
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut at_old, mut at_new) = (0usize, 0usize);
+    for op in &ops {
+        positions.push((*op, at_old, at_new));
+        match op {
+            Op::Keep => {
+                at_old += 1;
+                at_new += 1;
+            }
+            Op::Remove => at_old += 1,
+            Op::Add => at_new += 1,
+        }
+    }
+
+    let changed = positions
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _, _))| *op != Op::Keep)
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    let mut out = String::new();
+    let mut cursor = 0usize;
+    while cursor < changed.len() {
+        let first = changed[cursor];
+        let mut last = first;
+        while cursor + 1 < changed.len() && changed[cursor + 1] - last <= 2 * CONTEXT_LINES + 1 {
+            cursor += 1;
+            last = changed[cursor];
+        }
+        cursor += 1;
+        let from = first.saturating_sub(CONTEXT_LINES);
+        let to = (last + CONTEXT_LINES + 1).min(positions.len());
+        let hunk = &positions[from..to];
+        let old_count = hunk.iter().filter(|(op, _, _)| *op != Op::Add).count();
+        let new_count = hunk.iter().filter(|(op, _, _)| *op != Op::Remove).count();
+        let (_, old_at, new_at) = hunk[0];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(before_start as usize + old_at, old_count),
+            hunk_range(after_start as usize + new_at, new_count),
+        ));
+        for (op, old_idx, new_idx) in hunk {
+            let (marker, line) = match op {
+                Op::Keep => (' ', old[*old_idx]),
+                Op::Remove => ('-', old[*old_idx]),
+                Op::Add => ('+', new[*new_idx]),
+            };
+            out.push(marker);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// One run of changed lines between two versions of a file, without
+/// context. Starts are 1-based; a side with no lines has empty text and
+/// starts where the other side's lines go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedLines {
+    pub before_start: u32,
+    pub before_lines: u32,
+    pub before_text: String,
+    pub after_start: u32,
+    pub after_lines: u32,
+    pub after_text: String,
+}
+
+/// The runs of changed lines turning `before` into `after`, in file order.
+/// Each side's text keeps a trailing newline per line.
+pub fn changed_lines(before: &str, after: &str) -> Vec<ChangedLines> {
+    let old = before.lines().collect::<Vec<_>>();
+    let new = after.lines().collect::<Vec<_>>();
+    let mut runs = Vec::new();
+    let (mut at_old, mut at_new) = (0usize, 0usize);
+    let mut current: Option<ChangedLines> = None;
+    for op in line_ops(&old, &new) {
+        if op == Op::Keep {
+            runs.extend(current.take());
+            at_old += 1;
+            at_new += 1;
+            continue;
+        }
+        let run = current.get_or_insert_with(|| ChangedLines {
+            before_start: at_old as u32 + 1,
+            before_lines: 0,
+            before_text: String::new(),
+            after_start: at_new as u32 + 1,
+            after_lines: 0,
+            after_text: String::new(),
+        });
+        if op == Op::Remove {
+            run.before_lines += 1;
+            run.before_text.push_str(old[at_old]);
+            run.before_text.push('\n');
+            at_old += 1;
+        } else {
+            run.after_lines += 1;
+            run.after_text.push_str(new[at_new]);
+            run.after_text.push('\n');
+            at_new += 1;
+        }
+    }
+    runs.extend(current);
+    runs
+}
+
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        // An empty side is numbered from the line before it.
+        0 => format!("{},0", start.saturating_sub(1)),
+        1 => start.to_string(),
+        _ => format!("{start},{count}"),
+    }
+}
+
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops = vec![Op::Keep; prefix];
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_LCS_CELLS {
+        ops.extend(std::iter::repeat_n(Op::Remove, old_mid.len()));
+        ops.extend(std::iter::repeat_n(Op::Add, new_mid.len()));
+    } else {
+        // lcs[i][j]: longest common subsequence of old_mid[i..] and new_mid[j..].
+        let width = new_mid.len() + 1;
+        let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0usize, 0usize);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                ops.push(Op::Keep);
+                i += 1;
+                j += 1;
+            } else if i < old_mid.len()
+                && (j == new_mid.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push(Op::Remove);
+                i += 1;
+            } else {
+                ops.push(Op::Add);
+                j += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Keep, suffix));
+    ops
+}
+
+/// Result of applying one edit to a file's current content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditApplication {
+    /// The file's new content.
+    Applied(String),
+    /// The file already holds the edit's result.
+    AlreadyApplied,
+}
+
+/// Apply an edit (`before` replaced by `after`) to `current`, the file's
+/// content or `None` when it does not exist. `before_line` (1-based)
+/// disambiguates when `before` occurs more than once. Conflicts come back
+/// as a short reason.
+pub fn apply_edit(
+    current: Option<&str>,
+    before: Option<&str>,
+    after: Option<&str>,
+    before_line: Option<u32>,
+) -> Result<EditApplication, &'static str> {
+    let before = before.filter(|text| !text.is_empty());
+    let after = after.unwrap_or("");
+    let Some(current) = current else {
+        return match before {
+            None => Ok(EditApplication::Applied(after.to_string())),
+            Some(_) => Err("file_missing"),
+        };
+    };
+    let Some(before) = before else {
+        return if current == after || (!after.is_empty() && current.contains(after)) {
+            Ok(EditApplication::AlreadyApplied)
+        } else if current.is_empty() {
+            Ok(EditApplication::Applied(after.to_string()))
+        } else {
+            Err("file_exists")
+        };
+    };
+    let found = current
+        .match_indices(before)
+        .map(|(at, _)| at)
+        .collect::<Vec<_>>();
+    let at = match found.as_slice() {
+        [] if !after.is_empty() && current.contains(after) => {
+            return Ok(EditApplication::AlreadyApplied);
+        }
+        [] => return Err("before_text_not_found"),
+        [at] => *at,
+        _ => {
+            let line_of = |at: usize| current[..at].matches('\n').count() as u32 + 1;
+            match before_line.and_then(|line| found.iter().find(|at| line_of(**at) == line)) {
+                Some(at) => *at,
+                None => return Err("ambiguous_before_text"),
+            }
+        }
+    };
+    Ok(EditApplication::Applied(format!(
+        "{}{after}{}",
+        &current[..at],
+        &current[at + before.len()..]
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_lines_reports_runs_without_context() {
+        let before = (1..=8).map(|n| format!("line {n}\n")).collect::<String>();
+        let after = before
+            .replace("line 2\n", "line two\n")
+            .replace("line 5\n", "")
+            .replace("line 8\n", "line 8\nline 9\n");
+        let runs = changed_lines(&before, &after);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(
+            runs[0],
+            ChangedLines {
+                before_start: 2,
+                before_lines: 1,
+                before_text: "line 2\n".to_string(),
+                after_start: 2,
+                after_lines: 1,
+                after_text: "line two\n".to_string(),
+            }
+        );
+        assert_eq!((runs[1].before_start, runs[1].before_lines), (5, 1));
+        assert_eq!((runs[1].after_start, runs[1].after_lines), (5, 0));
+        assert_eq!(runs[2].after_text, "line 9\n");
+        assert_eq!((runs[2].before_start, runs[2].before_lines), (9, 0));
+        assert_eq!((runs[2].after_start, runs[2].after_lines), (8, 1));
+        assert!(changed_lines(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn renders_unified_hunks_with_context() {
+        let before = (1..=20).map(|n| format!("line {n}\n")).collect::<String>();
+        let after = before
+            .replace("line 2\n", "line two\n")
+            .replace("line 15\n", "")
+            .replace("line 20\n", "line 20\nline 21\n");
+        assert_eq!(
+            unified_hunks(&before, &after, 10, 10),
+            concat!(
+                "@@ -10,5 +10,5 @@\n",
+                " line 1\n",
+                "-line 2\n",
+                "+line two\n",
+                " line 3\n",
+                " line 4\n",
+                " line 5\n",
+                "@@ -21,9 +21,9 @@\n",
+                " line 12\n",
+                " line 13\n",
+                " line 14\n",
+                "-line 15\n",
+                " line 16\n",
+                " line 17\n",
+                " line 18\n",
+                " line 19\n",
+                " line 20\n",
+                "+line 21\n",
+            )
+        );
+        assert_eq!(unified_hunks("a\n", "a\n", 1, 1), "");
+        assert_eq!(unified_hunks("", "new\n", 1, 1), "@@ -0,0 +1 @@\n+new\n");
+    }
+
+    #[test]
+    fn applies_edits_and_reports_conflicts() {
+        use EditApplication::*;
+        assert_eq!(
+            apply_edit(None, None, Some("new\n"), None),
+            Ok(Applied("new\n".into()))
+        );
+        assert_eq!(
+            apply_edit(None, Some("x"), Some("y"), None),
+            Err("file_missing")
+        );
+        assert_eq!(
+            apply_edit(Some("a\nb\n"), Some("b\n"), Some("c\n"), None),
+            Ok(Applied("a\nc\n".into()))
+        );
+        assert_eq!(
+            apply_edit(Some("a\nc\n"), Some("b\n"), Some("c\n"), None),
+            Ok(AlreadyApplied)
+        );
+        assert_eq!(
+            apply_edit(Some("a\nz\n"), Some("b\n"), Some("c\n"), None),
+            Err("before_text_not_found")
+        );
+        assert_eq!(
+            apply_edit(Some("x\ny\nx\n"), Some("x\n"), Some("w\n"), None),
+            Err("ambiguous_before_text")
+        );
+        assert_eq!(
+            apply_edit(Some("x\ny\nx\n"), Some("x\n"), Some("w\n"), Some(3)),
+            Ok(Applied("x\ny\nw\n".into()))
+        );
+        assert_eq!(
+            apply_edit(Some("keep\n"), None, Some("other\n"), None),
+            Err("file_exists")
+        );
+    }
+}
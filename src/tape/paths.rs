@@ -0,0 +1,274 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Lexically resolve `.` and `..` components without touching the filesystem.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let _ = out.pop();
+            }
+            Component::RootDir | Component::Prefix(_) | Component::Normal(_) => {
+                out.push(component.as_os_str())
+            }
+        }
+    }
+    out
+}
+
+pub fn canonicalize_or_normalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| normalize_path(path))
+}
+
+/// How recorded file paths are compared across evidence, excludes and explain
+/// grouping. Comparison always happens on [`slash_path`] form; case folding
+/// follows the host filesystem, and Windows drive paths always fold because
+/// the filesystem that produced them did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathPolicy {
+    pub case_insensitive: bool,
+}
+
+impl PathPolicy {
+    pub fn host() -> Self {
+        Self {
+            case_insensitive: cfg!(any(windows, target_os = "macos")),
+        }
+    }
+
+    pub fn folds_case(&self, path: &str) -> bool {
+        self.case_insensitive || drive_prefix(path).is_some()
+    }
+
+    /// Comparison key for `raw`: two paths name the same file under this
+    /// policy exactly when their keys are equal.
+    pub fn key(&self, raw: &str) -> String {
+        let slash = slash_path(raw);
+        if self.folds_case(&slash) {
+            slash.to_lowercase()
+        } else {
+            slash
+        }
+    }
+
+    pub fn same_file(&self, left: &str, right: &str) -> bool {
+        self.key(left) == self.key(right)
+    }
+
+    /// `path` relative to `root` when it lies strictly inside it.
+    pub fn strip_root(&self, path: &str, root: &str) -> Option<String> {
+        let path = slash_path(path);
+        let root = slash_path(root);
+        let fold = self.folds_case(&path) || self.folds_case(&root);
+        if !is_absolute_slash(&path) || !is_absolute_slash(&root) {
+            return None;
+        }
+        let path_parts = path
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+        let root_parts = root
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+        if path_parts.len() <= root_parts.len() {
+            return None;
+        }
+        let inside = root_parts
+            .iter()
+            .zip(&path_parts)
+            .all(|(root_part, path_part)| {
+                if fold {
+                    root_part.to_lowercase() == path_part.to_lowercase()
+                } else {
+                    root_part == path_part
+                }
+            });
+        inside.then(|| path_parts[root_parts.len()..].join("/"))
+    }
+
+    pub fn glob_options(&self) -> glob::MatchOptions {
+        glob::MatchOptions {
+            case_sensitive: !self.case_insensitive,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        }
+    }
+
+    pub fn matches_glob(&self, pattern: &glob::Pattern, raw: &str) -> bool {
+        let slash = slash_path(raw);
+        let mut options = self.glob_options();
+        options.case_sensitive = !self.folds_case(&slash);
+        pattern.matches_with(&slash, options)
+    }
+}
+
+/// Lexically normalized, `/`-separated form of a recorded path: backslashes
+/// become `/`, `.` and `..` are resolved, repeated separators collapse and
+/// drive letters are uppercased (`c:\src\lib.rs` becomes `C:/src/lib.rs`).
+pub fn slash_path(raw: &str) -> String {
+    let unified = raw.replace('\\', "/");
+    let (prefix, rest) = if let Some(drive) = drive_prefix(&unified) {
+        (format!("{}:/", drive.to_ascii_uppercase()), &unified[2..])
+    } else if let Some(rest) = unified.strip_prefix("//") {
+        ("//".to_string(), rest)
+    } else if let Some(rest) = unified.strip_prefix('/') {
+        ("/".to_string(), rest)
+    } else {
+        (String::new(), unified.as_str())
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.last().is_some_and(|last| *last != "..") {
+                    parts.pop();
+                } else if prefix.is_empty() {
+                    parts.push("..");
+                }
+            }
+            _ => parts.push(part),
+        }
+    }
+    let joined = parts.join("/");
+    if prefix.is_empty() && joined.is_empty() {
+        ".".to_string()
+    } else {
+        format!("{prefix}{joined}")
+    }
+}
+
+/// True for `/unix`, `//unc/share` and `C:/windows` style paths, whatever
+/// the host platform.
+pub fn is_absolute_slash(path: &str) -> bool {
+    path.starts_with('/') || drive_prefix(path).is_some()
+}
+
+fn drive_prefix(path: &str) -> Option<char> {
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    (chars.next() == Some(':') && matches!(chars.next(), Some('/' | '\\'))).then_some(drive)
+}
+
+/// Rewrite a file path recorded by a harness so it is relative to the
+/// session's project root, in [`slash_path`] form. Paths outside the root
+/// are normalized but otherwise kept.
+///
+/// The lexical comparison runs first so tapes ingested on another machine
+/// still relativize; the canonical comparison then covers symlinked roots
+/// such as `/tmp` vs `/private/tmp`.
+pub fn repo_relative_path(raw: &str, project_root: Option<&Path>) -> String {
+    if raw.is_empty() {
+        return String::new();
+    }
+    let slash = slash_path(raw);
+    if !is_absolute_slash(&slash) {
+        return slash;
+    }
+    let Some(root) = project_root else {
+        return slash;
+    };
+    let policy = PathPolicy::host();
+    if let Some(relative) = policy.strip_root(&slash, &root.to_string_lossy()) {
+        return relative;
+    }
+
+    let path = Path::new(raw);
+    if path.is_absolute() && root.is_absolute() {
+        let canonical = canonicalize_or_normalize(path);
+        let canonical_root = canonicalize_or_normalize(root);
+        if let Some(relative) = policy.strip_root(
+            &canonical.to_string_lossy(),
+            &canonical_root.to_string_lossy(),
+        ) {
+            return relative;
+        }
+    }
+
+    slash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_project_root_prefix() {
+        let root = Path::new("/Users/dev/project");
+        assert_eq!(
+            repo_relative_path("/Users/dev/project/src/lib.rs", Some(root)),
+            "src/lib.rs"
+        );
+        assert_eq!(
+            repo_relative_path("/Users/dev/project/./src/../src/lib.rs", Some(root)),
+            "src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn leaves_outside_and_rootless_paths_alone() {
+        let root = Path::new("/Users/dev/project");
+        assert_eq!(repo_relative_path("/etc/hosts", Some(root)), "/etc/hosts");
+        assert_eq!(
+            repo_relative_path("/Users/dev/project/src/lib.rs", None),
+            "/Users/dev/project/src/lib.rs"
+        );
+        assert_eq!(repo_relative_path("./src/lib.rs", Some(root)), "src/lib.rs");
+    }
+
+    #[test]
+    fn windows_paths_normalize_and_relativize_case_insensitively() {
+        assert_eq!(
+            slash_path(r"c:\Users\Dev\.\proj\src\lib.rs"),
+            "C:/Users/Dev/proj/src/lib.rs"
+        );
+        assert_eq!(slash_path(r"..\shared//util.rs"), "../shared/util.rs");
+        assert!(is_absolute_slash("D:/work"));
+        assert_eq!(
+            repo_relative_path(
+                r"C:\Users\Dev\Proj\src\lib.rs",
+                Some(Path::new(r"c:\users\dev\proj"))
+            ),
+            "src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn policy_keys_fold_case_only_when_configured_or_on_drive_paths() {
+        let sensitive = PathPolicy {
+            case_insensitive: false,
+        };
+        let insensitive = PathPolicy {
+            case_insensitive: true,
+        };
+        assert!(!sensitive.same_file("src/Lib.rs", "src/lib.rs"));
+        assert!(insensitive.same_file("src/Lib.rs", "./src\\lib.rs"));
+        assert!(sensitive.same_file(r"C:\Src\lib.rs", "c:/src/LIB.rs"));
+
+        let pattern = glob::Pattern::new("src/**/*.rs").expect("pattern");
+        assert!(sensitive.matches_glob(&pattern, r"src\a\b.rs"));
+        assert!(!sensitive.matches_glob(&pattern, "SRC/a.rs"));
+        assert!(insensitive.matches_glob(&pattern, "SRC/a.rs"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolves_symlinked_project_roots() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let real = temp.path().join("real");
+        fs::create_dir_all(real.join("src")).expect("mkdir");
+        fs::write(real.join("src/lib.rs"), "fn main() {}\n").expect("write");
+        let link = temp.path().join("link");
+        std::os::unix::fs::symlink(&real, &link).expect("symlink");
+
+        let raw = link.join("src/lib.rs");
+        assert_eq!(
+            repo_relative_path(&raw.to_string_lossy(), Some(&real)),
+            "src/lib.rs"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Monorepo projects: named sets of repo-relative globs (`projects:`) that
+//! ingest stamps on each `code.read` / `code.edit` event, so lineage can be
+//! sliced by team without separate stores.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use crate::tape::paths::repo_relative_path;
+
+/// Compiled `projects:` globs. Unlike `ignore:`, a glob is anchored at the
+/// repository root; it matches a path when it matches the whole path or one
+/// of its parent directories, so `services/payments` and
+/// `services/payments/**` both cover every file under that directory.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectRules {
+    projects: Vec<(String, Vec<glob::Pattern>)>,
+}
+
+impl ProjectRules {
+    pub fn new(projects: &BTreeMap<String, Vec<String>>) -> Result<Self, glob::PatternError> {
+        Ok(Self {
+            projects: projects
+                .iter()
+                .map(|(name, globs)| {
+                    let patterns = globs
+                        .iter()
+                        .map(|raw| {
+                            glob::Pattern::new(raw.trim_start_matches("./").trim_end_matches('/'))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok((name.clone(), patterns))
+                })
+                .collect::<Result<_, glob::PatternError>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.projects.is_empty()
+    }
+
+    /// The project a repo-relative path belongs to: the first, by name,
+    /// with a matching glob.
+    pub fn project_for(&self, path: &str) -> Option<&str> {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        let path = path.replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        let prefixes = std::iter::once(path)
+            .chain(path.match_indices('/').map(|(at, _)| &path[..at]))
+            .filter(|prefix| !prefix.is_empty())
+            .collect::<Vec<_>>();
+        self.projects
+            .iter()
+            .find(|(_, patterns)| {
+                patterns.iter().any(|pattern| {
+                    prefixes
+                        .iter()
+                        .any(|prefix| pattern.matches_with(prefix, options))
+                })
+            })
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Set `project` on `code.edit` / `code.read` events to the project their
+/// file belongs to, relative to the tape's meta `cwd`, and clear it where
+/// none applies. Other lines pass through byte for byte. Returns the tape
+/// and the number of events changed.
+pub fn stamp_projects(jsonl: &str, rules: &ProjectRules) -> serde_json::Result<(String, usize)> {
+    let cwd = jsonl
+        .lines()
+        .filter(|line| line.contains("\"meta\""))
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|row| row.get("k").and_then(Value::as_str) == Some("meta"))
+        .and_then(|row| row.get("cwd").and_then(Value::as_str).map(PathBuf::from));
+    let mut out = String::with_capacity(jsonl.len());
+    let mut changed = 0usize;
+    for line in jsonl.lines() {
+        let is_evidence = line.contains("\"code.edit\"") || line.contains("\"code.read\"");
+        if !is_evidence || (rules.is_empty() && !line.contains("\"project\"")) {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let mut row: Value = serde_json::from_str(line)?;
+        let kind = row.get("k").and_then(Value::as_str).unwrap_or_default();
+        let file = row.get("file").and_then(Value::as_str);
+        let (Some(file), true) = (file, kind == "code.edit" || kind == "code.read") else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+        let relative = repo_relative_path(file, cwd.as_deref());
+        let project = rules.project_for(&relative).map(ToOwned::to_owned);
+        if row.get("project").and_then(Value::as_str) == project.as_deref() {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let Some(object) = row.as_object_mut() else {
+            continue;
+        };
+        match project {
+            Some(project) => object.insert("project".to_string(), Value::String(project)),
+            None => object.remove("project"),
+        };
+        changed += 1;
+        out.push_str(&serde_json::to_string(&row)?);
+        out.push('\n');
+    }
+    Ok((out, changed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamps_the_first_matching_project_by_name() {
+        let mut projects = BTreeMap::new();
+        projects.insert(
+            "payments".to_string(),
+            vec![
+                "services/payments/**".to_string(),
+                "libs/billing".to_string(),
+            ],
+        );
+        projects.insert("platform".to_string(), vec!["services/*".to_string()]);
+        let rules = ProjectRules::new(&projects).expect("rules");
+        assert_eq!(
+            rules.project_for("services/payments/src/lib.rs"),
+            Some("payments")
+        );
+        assert_eq!(rules.project_for("libs/billing/mod.rs"), Some("payments"));
+        assert_eq!(rules.project_for("libs/billing.rs"), None);
+        assert_eq!(
+            rules.project_for("services/search/main.go"),
+            Some("platform")
+        );
+        assert_eq!(rules.project_for("README.md"), None);
+
+        let jsonl = concat!(
+            r#"{"t":"T0","k":"meta","cwd":"/repo"}"#,
+            "\n",
+            r#"{"t":"T1","k":"code.edit","file":"/repo/services/payments/a.rs","after_text":"x"}"#,
+            "\n",
+            r#"{"t":"T2","k":"code.read","file":"README.md","text":"y","project":"old"}"#,
+            "\n",
+            r#"{"t":"T3","k":"msg.in","text":"services/payments"}"#,
+            "\n",
+        );
+        let (stamped, changed) = stamp_projects(jsonl, &rules).expect("stamp");
+        assert_eq!(changed, 2);
+        let rows = stamped
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("json"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows[1]["project"], "payments");
+        assert!(rows[2].get("project").is_none());
+        assert_eq!(stamped.lines().nth(3), jsonl.lines().nth(3));
+        let (_, again) = stamp_projects(&stamped, &rules).expect("stamp");
+        assert_eq!(again, 0);
+        assert!(
+            ProjectRules::new(&BTreeMap::from([("x".to_string(), vec!["[".to_string()])])).is_err()
+        );
+    }
+}
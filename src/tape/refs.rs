@@ -0,0 +1,191 @@
+//! Issue tracker keys (`ENG-123`, `PROJ-42`) a tape mentions, so sessions
+//! can be found by ticket. Keys come from messages, from the strings in
+//! `meta` events (branch names, labels, URLs) and from the git commands a
+//! session ran (`git commit -m "ENG-123: ..."`, `git switch -c eng-123-fix`).
+
+use std::collections::BTreeSet;
+
+use regex::Regex;
+use serde_json::Value;
+
+/// Jira and Linear style keys: a project prefix, a dash and a number.
+pub const DEFAULT_ISSUE_PATTERNS: &[&str] = &[r"\b[A-Z][A-Z0-9]+-[1-9][0-9]*\b"];
+
+/// Compiled `issue_patterns:`. A pattern with a capture group yields the
+/// group, otherwise the whole match.
+#[derive(Debug, Clone, Default)]
+pub struct IssuePatterns {
+    patterns: Vec<Regex>,
+}
+
+impl IssuePatterns {
+    pub fn new(patterns: &[String]) -> Result<Self, regex::Error> {
+        Ok(Self {
+            patterns: patterns
+                .iter()
+                .map(|raw| Regex::new(raw))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Every key in `text`, in order of appearance per pattern.
+    pub fn keys_in(&self, text: &str) -> Vec<String> {
+        let mut keys = Vec::new();
+        for pattern in &self.patterns {
+            for captures in pattern.captures_iter(text) {
+                let Some(found) = captures.get(1).or_else(|| captures.get(0)) else {
+                    continue;
+                };
+                if !found.as_str().is_empty() {
+                    keys.push(found.as_str().to_string());
+                }
+            }
+        }
+        keys
+    }
+}
+
+/// Where in the tape an issue key was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueRefSource {
+    Message,
+    Meta,
+    Git,
+}
+
+impl IssueRefSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Message => "message",
+            Self::Meta => "meta",
+            Self::Git => "git",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "message" => Some(Self::Message),
+            "meta" => Some(Self::Meta),
+            "git" => Some(Self::Git),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IssueRef {
+    pub key: String,
+    /// Line number of the event in the tape.
+    pub offset: u64,
+    pub source: IssueRefSource,
+}
+
+/// Every issue key in a normalized tape, once per event.
+pub fn issue_refs_from_jsonl(input: &str, patterns: &IssuePatterns) -> Vec<IssueRef> {
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+    let mut refs = BTreeSet::new();
+    for (offset, line) in input.lines().enumerate() {
+        let Ok(row) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let (source, texts) = match row.get("k").and_then(Value::as_str) {
+            Some("msg.in" | "msg.out") => (
+                IssueRefSource::Message,
+                row.get("text")
+                    .and_then(Value::as_str)
+                    .map(ToOwned::to_owned)
+                    .into_iter()
+                    .collect(),
+            ),
+            Some("meta") => {
+                let mut texts = Vec::new();
+                collect_strings(&row, &mut texts);
+                (IssueRefSource::Meta, texts)
+            }
+            Some("tool.call") => match git_command(&row) {
+                Some(command) => (IssueRefSource::Git, vec![command]),
+                None => continue,
+            },
+            _ => continue,
+        };
+        for text in texts {
+            for key in patterns.keys_in(&text) {
+                refs.insert(IssueRef {
+                    key,
+                    offset: offset as u64,
+                    source,
+                });
+            }
+        }
+    }
+    refs.into_iter().collect()
+}
+
+fn collect_strings(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(text) => out.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, out)),
+        _ => {}
+    }
+}
+
+/// The `args` of a tool call that runs git, as text.
+fn git_command(row: &Value) -> Option<String> {
+    let args = match row.get("args")? {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    let runs_git = args
+        .split(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '/' | '.')))
+        .any(|word| word == "git" || word.ends_with("/git"));
+    runs_git.then_some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_keys_in_messages_meta_and_git_commands() {
+        let jsonl = [
+            r#"{"t":"2026-03-01T10:00:00Z","k":"meta","source":{"harness":"claude-code"},"branch":"feature/ENG-42-retry"}"#,
+            r#"{"t":"2026-03-01T10:00:01Z","k":"msg.in","text":"Fix ENG-42 and PAY-7, not ENG-042 or eng-9"}"#,
+            r#"{"t":"2026-03-01T10:00:02Z","k":"tool.call","tool":"Bash","args":"{\"command\":\"git commit -m 'ENG-42: retry uploads'\"}"}"#,
+            r#"{"t":"2026-03-01T10:00:03Z","k":"tool.call","tool":"Bash","args":"{\"command\":\"grep -r OPS-1 src\"}"}"#,
+        ]
+        .join("\n");
+        let defaults = DEFAULT_ISSUE_PATTERNS
+            .iter()
+            .map(|raw| raw.to_string())
+            .collect::<Vec<_>>();
+
+        let refs = issue_refs_from_jsonl(&jsonl, &IssuePatterns::new(&defaults).expect("regex"));
+        let found = refs
+            .iter()
+            .map(|r| (r.key.as_str(), r.offset, r.source))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("ENG-42", 0, IssueRefSource::Meta),
+                ("ENG-42", 1, IssueRefSource::Message),
+                ("ENG-42", 2, IssueRefSource::Git),
+                ("PAY-7", 1, IssueRefSource::Message),
+            ]
+        );
+
+        let linear = IssuePatterns::new(&["(?i)\\b(eng-[0-9]+)\\b".to_string()]).expect("regex");
+        assert_eq!(
+            linear.keys_in("see eng-9 and ENG-10"),
+            ["eng-9".to_string(), "ENG-10".to_string()]
+        );
+        assert!(IssuePatterns::new(&["(".to_string()]).is_err());
+    }
+}
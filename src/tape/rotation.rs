@@ -0,0 +1,96 @@
+//! Harness logs rotated by logrotate or by the harness itself: a transcript
+//! compressed to `session.jsonl.gz` is still a `.jsonl` transcript, read
+//! through gzip.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::MultiGzDecoder;
+
+/// True for `*.gz` paths.
+pub fn is_gzip_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|value| value.to_str())
+        .is_some_and(|value| value.eq_ignore_ascii_case("gz"))
+}
+
+/// The path the transcript had before compression: `a.jsonl.gz` is
+/// `a.jsonl`; other paths are returned as they are.
+pub fn uncompressed_path(path: &Path) -> PathBuf {
+    if is_gzip_path(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// The lowercase transcript extension of `path`, looking through `.gz`.
+pub fn transcript_extension(path: &Path) -> Option<String> {
+    uncompressed_path(path)
+        .extension()
+        .and_then(|value| value.to_str())
+        .map(|value| value.to_ascii_lowercase())
+}
+
+/// Whether `path` has extension `extension`, directly or under `.gz`.
+pub fn has_transcript_extension(path: &Path, extension: &str) -> bool {
+    transcript_extension(path).is_some_and(|value| value.eq_ignore_ascii_case(extension))
+}
+
+/// A buffered reader over the transcript's uncompressed bytes.
+pub fn open_transcript(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    if is_gzip_path(path) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// The transcript's uncompressed bytes.
+pub fn read_transcript(path: &Path) -> io::Result<Vec<u8>> {
+    if !is_gzip_path(path) {
+        return fs::read(path);
+    }
+    let mut out = Vec::new();
+    MultiGzDecoder::new(File::open(path)?).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use super::*;
+
+    #[test]
+    fn gzip_transcripts_read_as_their_uncompressed_form() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let plain = dir.path().join("session.jsonl");
+        let rotated = dir.path().join("session.jsonl.GZ");
+        fs::write(&plain, b"{\"a\":1}\n").expect("plain");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"{\"a\":1}\n").expect("gzip");
+        fs::write(&rotated, encoder.finish().expect("gzip")).expect("rotated");
+
+        assert_eq!(transcript_extension(&rotated).as_deref(), Some("jsonl"));
+        assert!(has_transcript_extension(&rotated, "jsonl"));
+        assert!(!has_transcript_extension(&rotated, "json"));
+        assert_eq!(uncompressed_path(&rotated), plain);
+        assert_eq!(
+            read_transcript(&rotated).expect("read"),
+            read_transcript(&plain).expect("read")
+        );
+        let first = open_transcript(&rotated)
+            .expect("open")
+            .lines()
+            .next()
+            .expect("line")
+            .expect("line");
+        assert_eq!(first, "{\"a\":1}");
+    }
+}
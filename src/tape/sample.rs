@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::config::OutputSampling;
+
+/// Fields that identify an event rather than carry its payload.
+const STRUCTURAL_FIELDS: &[&str] = &["t", "k", "tool", "call_id", "file"];
+
+/// Replace oversized string fields of events whose kind has a rule in
+/// `rules` by their first and last `keep_bytes`, with a marker between them,
+/// and record the original size, line count and SHA-256 under
+/// `<field>_sample`. Lines without an oversized field pass through byte for
+/// byte. Returns the rewritten tape and the number of fields sampled.
+pub fn sample_large_fields(
+    jsonl: &str,
+    rules: &BTreeMap<String, OutputSampling>,
+) -> serde_json::Result<(String, usize)> {
+    if rules.is_empty() {
+        return Ok((jsonl.to_string(), 0));
+    }
+    let mut out = String::with_capacity(jsonl.len());
+    let mut sampled = 0usize;
+    for line in jsonl.lines() {
+        let row = if line.trim().is_empty() {
+            None
+        } else {
+            Some(serde_json::from_str::<Value>(line)?)
+        };
+        let rule = row
+            .as_ref()
+            .and_then(|row| row.get("k"))
+            .and_then(Value::as_str)
+            .and_then(|kind| rules.get(kind))
+            .copied();
+        let (Some(mut row), Some(rule)) = (row, rule) else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+        let oversized = row
+            .as_object()
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter(|(name, value)| {
+                        !STRUCTURAL_FIELDS.contains(&name.as_str())
+                            && value
+                                .as_str()
+                                .is_some_and(|text| text.len() > rule.max_bytes)
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if oversized.is_empty() {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        for name in oversized {
+            let text = row[&name].as_str().unwrap_or_default().to_string();
+            row[&name] = json!(sampled_text(&name, &text, rule.keep_bytes));
+            row[format!("{name}_sample")] = json!({
+                "bytes": text.len(),
+                "lines": text.lines().count(),
+                "sha256": format!("{:x}", Sha256::digest(text.as_bytes())),
+            });
+            sampled += 1;
+        }
+        out.push_str(&serde_json::to_string(&row)?);
+        out.push('\n');
+    }
+    Ok((out, sampled))
+}
+
+fn sampled_text(name: &str, text: &str, keep_bytes: usize) -> String {
+    let mut head = keep_bytes.min(text.len());
+    while !text.is_char_boundary(head) {
+        head -= 1;
+    }
+    let mut tail = text.len().saturating_sub(keep_bytes).max(head);
+    while !text.is_char_boundary(tail) {
+        tail += 1;
+    }
+    format!(
+        "{}\n[engram: {name} sampled, {} of {} bytes omitted]\n{}",
+        &text[..head],
+        tail - head,
+        text.len(),
+        &text[tail..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_only_oversized_fields_of_configured_kinds() {
+        let stdout = (1..=400)
+            .map(|n| format!("test case_{n} ... ok\n"))
+            .collect::<String>();
+        let jsonl = format!(
+            "{}\n{}\n",
+            json!({"t": "T1", "k": "tool.result", "tool": "cargo", "stdout": stdout, "stderr": "é"}),
+            json!({"t": "T2", "k": "msg.out", "text": stdout}),
+        );
+        let rules = BTreeMap::from([(
+            "tool.result".to_string(),
+            OutputSampling {
+                max_bytes: 1024,
+                keep_bytes: 64,
+            },
+        )]);
+
+        let (sampled, count) = sample_large_fields(&jsonl, &rules).expect("sample");
+        assert_eq!(count, 1);
+        let rows = sampled
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+            .collect::<Vec<_>>();
+        let text = rows[0]["stdout"].as_str().expect("stdout");
+        assert!(text.starts_with("test case_1 ... ok\n"));
+        assert!(text.ends_with("test case_400 ... ok\n"));
+        assert!(text.contains("[engram: stdout sampled,"));
+        assert_eq!(rows[0]["stdout_sample"]["bytes"], stdout.len());
+        assert_eq!(rows[0]["stdout_sample"]["lines"], 400);
+        assert_eq!(
+            rows[0]["stdout_sample"]["sha256"],
+            format!("{:x}", Sha256::digest(stdout.as_bytes()))
+        );
+        assert_eq!(rows[0]["stderr"], "é");
+        assert_eq!(sampled.lines().nth(1), jsonl.lines().nth(1));
+
+        let (unchanged, count) = sample_large_fields(&jsonl, &BTreeMap::new()).expect("noop");
+        assert_eq!((unchanged.as_str(), count), (jsonl.as_str(), 0));
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::tape::paths::repo_relative_path;
+
+/// How a file recorded in a `state.snapshot` compares with the disk now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFileState {
+    /// Same content as when the snapshot was taken.
+    Unchanged,
+    /// Changed since: edits made outside any recorded session.
+    Drifted,
+    /// Deleted since, or missing when the snapshot was taken but present now.
+    Missing,
+}
+
+impl SnapshotFileState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unchanged => "unchanged",
+            Self::Drifted => "drifted",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+/// A `state.snapshot` event for a normalized tape: `head` and the SHA-256 of
+/// every file the tape edits, read from `root` as it is now (`null` for
+/// files that no longer exist). Paths are recorded relative to `root`.
+/// `None` when the tape edits nothing.
+pub fn snapshot_event(jsonl: &str, root: &Path, head: Option<&str>, at: &str) -> Option<Value> {
+    let mut files = Vec::<String>::new();
+    for line in jsonl.lines() {
+        let Ok(row) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if row.get("k").and_then(Value::as_str) != Some("code.edit") {
+            continue;
+        }
+        let Some(file) = row.get("file").and_then(Value::as_str) else {
+            continue;
+        };
+        let file = repo_relative_path(file, Some(root));
+        if !file.is_empty() && !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    if files.is_empty() {
+        return None;
+    }
+    let files = files
+        .into_iter()
+        .map(|file| {
+            let sha256 = file_sha256(&root.join(&file));
+            json!({"file": file, "sha256": sha256})
+        })
+        .collect::<Vec<_>>();
+    Some(json!({
+        "t": at,
+        "k": "state.snapshot",
+        "root": root.to_string_lossy(),
+        "head": head,
+        "files": files,
+    }))
+}
+
+/// Compare one `files` entry of a `state.snapshot` event with the disk.
+pub fn snapshot_file_state(root: &Path, file: &str, sha256: Option<&str>) -> SnapshotFileState {
+    match (sha256, file_sha256(&root.join(file))) {
+        (Some(recorded), Some(current)) if recorded == current => SnapshotFileState::Unchanged,
+        (Some(_), Some(_)) => SnapshotFileState::Drifted,
+        (None, None) => SnapshotFileState::Unchanged,
+        (Some(_), None) | (None, Some(_)) => SnapshotFileState::Missing,
+    }
+}
+
+fn file_sha256(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_edited_files_and_detects_drift() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).expect("mkdir");
+        fs::write(root.join("src/lib.rs"), "fn a() {}\n").expect("write");
+        let absolute = root.join("src/lib.rs");
+        let jsonl = format!(
+            "{}\n{}\n{}\n",
+            json!({"t": "T1", "k": "code.read", "file": "README.md"}),
+            json!({"t": "T2", "k": "code.edit", "file": absolute.to_string_lossy()}),
+            json!({"t": "T3", "k": "code.edit", "file": "src/gone.rs"}),
+        );
+
+        let event = snapshot_event(&jsonl, root, Some("abc123"), "T4").expect("snapshot");
+        assert_eq!(event["k"], "state.snapshot");
+        assert_eq!(event["head"], "abc123");
+        let files = event["files"].as_array().expect("files");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["file"], "src/lib.rs");
+        assert_eq!(files[1]["sha256"], Value::Null);
+
+        let recorded = files[0]["sha256"].as_str();
+        assert_eq!(
+            snapshot_file_state(root, "src/lib.rs", recorded),
+            SnapshotFileState::Unchanged
+        );
+        assert_eq!(
+            snapshot_file_state(root, "src/gone.rs", None),
+            SnapshotFileState::Unchanged
+        );
+        fs::write(root.join("src/lib.rs"), "fn b() {}\n").expect("rewrite");
+        assert_eq!(
+            snapshot_file_state(root, "src/lib.rs", recorded),
+            SnapshotFileState::Drifted
+        );
+        fs::remove_file(root.join("src/lib.rs")).expect("remove");
+        assert_eq!(
+            snapshot_file_state(root, "src/lib.rs", recorded),
+            SnapshotFileState::Missing
+        );
+
+        let reads_only = format!("{}\n", json!({"t": "T1", "k": "code.read", "file": "a"}));
+        assert_eq!(snapshot_event(&reads_only, root, None, "T2"), None);
+    }
+}
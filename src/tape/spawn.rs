@@ -0,0 +1,233 @@
+//! Agents that run other agents: a Claude Code session piping a task into
+//! `codex exec` through its Bash tool, say. The command is a `tool.call` in
+//! one tape and the session it started is a tape of its own; matching the
+//! command's harness, start time and working directory against the other
+//! tape's meta links the two.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde_json::Value;
+
+/// Harness CLIs that start a session engram can ingest, with the
+/// `source.harness` their tapes record.
+pub const HARNESS_COMMANDS: &[(&str, &str)] = &[
+    ("claude", "claude-code"),
+    ("codex", "codex-cli"),
+    ("gemini", "gemini-cli"),
+    ("opencode", "opencode"),
+    ("cursor-agent", "cursor"),
+    ("openclaw", "openclaw"),
+];
+
+/// Clock skew allowed between the calling and the spawned harness.
+pub const SPAWN_SKEW_SECS: i64 = 5;
+
+/// How long after the call a spawned session may start, for calls whose
+/// result came later (or never).
+pub const SPAWN_START_WINDOW_SECS: i64 = 300;
+
+/// A `tool.call` that runs a harness CLI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpawnCall {
+    /// Line number of the `tool.call` in the tape.
+    pub offset: u64,
+    /// `source.harness` of the session the command starts.
+    pub harness: String,
+    pub command: String,
+    /// Directory the command ran in: the call's own `cwd`/`workdir`, else
+    /// the calling tape's.
+    pub cwd: Option<String>,
+    /// The spawned session must start between these, RFC 3339 UTC seconds
+    /// like `started_at` in the tape metrics.
+    pub earliest: String,
+    pub latest: String,
+}
+
+/// Every [`SpawnCall`] in a normalized tape. Calls without a parseable
+/// timestamp are skipped.
+pub fn spawn_calls_from_jsonl(input: &str) -> Vec<SpawnCall> {
+    let rows = input
+        .lines()
+        .enumerate()
+        .filter_map(|(offset, line)| {
+            Some((offset as u64, serde_json::from_str::<Value>(line).ok()?))
+        })
+        .collect::<Vec<_>>();
+    let kind = |row: &Value| row.get("k").and_then(Value::as_str).map(ToOwned::to_owned);
+    let tape_cwd = tape_cwd(input);
+    let results = rows
+        .iter()
+        .filter(|(_, row)| kind(row).as_deref() == Some("tool.result"))
+        .filter_map(|(_, row)| {
+            let call_id = row.get("call_id").and_then(Value::as_str)?;
+            Some((call_id.to_string(), timestamp(row)?))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut out = Vec::new();
+    for (offset, row) in &rows {
+        if kind(row).as_deref() != Some("tool.call") {
+            continue;
+        }
+        let Some(called_at) = timestamp(row) else {
+            continue;
+        };
+        let args = parsed_args(row);
+        let Some(command) = args.as_ref().and_then(command_text) else {
+            continue;
+        };
+        let Some(harness) = spawned_harness(&command) else {
+            continue;
+        };
+        let cwd = args
+            .as_ref()
+            .and_then(|args| {
+                ["cwd", "workdir"]
+                    .iter()
+                    .find_map(|field| args.get(field).and_then(Value::as_str))
+            })
+            .map(ToOwned::to_owned)
+            .or_else(|| tape_cwd.clone());
+        let window_end = called_at + Duration::seconds(SPAWN_START_WINDOW_SECS);
+        let returned_at = row
+            .get("call_id")
+            .and_then(Value::as_str)
+            .and_then(|call_id| results.get(call_id))
+            .copied()
+            .filter(|returned| *returned >= called_at);
+        let latest = returned_at.map_or(window_end, |returned| returned.min(window_end));
+        out.push(SpawnCall {
+            offset: *offset,
+            harness: harness.to_string(),
+            command,
+            cwd,
+            earliest: rfc3339(called_at - Duration::seconds(SPAWN_SKEW_SECS)),
+            latest: rfc3339(latest + Duration::seconds(SPAWN_SKEW_SECS)),
+        });
+    }
+    out
+}
+
+/// The directory the tape's harness ran in, from its first `meta` that
+/// names one.
+pub fn tape_cwd(input: &str) -> Option<String> {
+    input
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|row| row.get("k").and_then(Value::as_str) == Some("meta"))
+        .find_map(|row| {
+            row.get("cwd")
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned)
+        })
+}
+
+/// The `source.harness` of the session `command` starts, when one of its
+/// commands (after `;`, `&&`, `|`, a subshell or inside quotes, past any
+/// `VAR=value` prefix) is a harness CLI.
+pub fn spawned_harness(command: &str) -> Option<&'static str> {
+    command
+        .split([';', '|', '&', '(', ')', '`', '\'', '"', '\n'])
+        .filter_map(|segment| segment.split_whitespace().find(|word| !word.contains('=')))
+        .find_map(|program| {
+            let name = program.rsplit('/').next().unwrap_or(program);
+            HARNESS_COMMANDS
+                .iter()
+                .find(|(cli, _)| *cli == name)
+                .map(|(_, harness)| *harness)
+        })
+}
+
+/// A tool call's `args`: recorded as a JSON string by most adapters.
+fn parsed_args(row: &Value) -> Option<Value> {
+    match row.get("args")? {
+        Value::String(text) => {
+            Some(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.clone())))
+        }
+        other => Some(other.clone()),
+    }
+}
+
+/// The shell command a call ran: the plain `args` string, or the
+/// `command`/`cmd` field. An argv array is joined, except that the script
+/// of `bash -lc <script>` stands for the whole.
+fn command_text(args: &Value) -> Option<String> {
+    let command = match args {
+        Value::String(_) => args,
+        Value::Object(map) => map.get("command").or_else(|| map.get("cmd"))?,
+        _ => return None,
+    };
+    match command {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(argv) => {
+            let argv = argv.iter().filter_map(Value::as_str).collect::<Vec<_>>();
+            match argv.as_slice() {
+                [shell, .., flag, script]
+                    if ["sh", "bash", "zsh"]
+                        .contains(&shell.rsplit('/').next().unwrap_or(shell))
+                        && flag.starts_with('-')
+                        && flag.contains('c') =>
+                {
+                    Some((*script).to_string())
+                }
+                _ => Some(argv.join(" ")),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn timestamp(row: &Value) -> Option<DateTime<Utc>> {
+    let raw = row.get("t").and_then(Value::as_str)?;
+    Some(DateTime::parse_from_rfc3339(raw).ok()?.with_timezone(&Utc))
+}
+
+fn rfc3339(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_harness_commands_and_their_start_window() {
+        let jsonl = [
+            r#"{"t":"2026-03-01T10:00:00Z","k":"meta","cwd":"/work/app"}"#,
+            r#"{"t":"2026-03-01T10:00:01Z","k":"tool.call","tool":"Bash","call_id":"c1","args":"{\"command\":\"cd api && OPENAI_KEY=x codex exec 'fix the test'\"}"}"#,
+            r#"{"t":"2026-03-01T10:00:40Z","k":"tool.result","tool":"Bash","call_id":"c1","exit":0}"#,
+            r#"{"t":"2026-03-01T10:01:00Z","k":"tool.call","tool":"shell","args":"{\"command\":[\"grep\",\"codex\",\"README.md\"],\"workdir\":\"/work/other\"}"}"#,
+            r#"{"t":"2026-03-01T10:02:00Z","k":"tool.call","tool":"shell","args":"{\"command\":[\"/bin/bash\",\"-lc\",\"/usr/local/bin/claude -p review\"],\"workdir\":\"/work/other\"}"}"#,
+        ]
+        .join("\n");
+
+        let calls = spawn_calls_from_jsonl(&jsonl);
+        assert_eq!(
+            calls,
+            vec![
+                SpawnCall {
+                    offset: 1,
+                    harness: "codex-cli".to_string(),
+                    command: "cd api && OPENAI_KEY=x codex exec 'fix the test'".to_string(),
+                    cwd: Some("/work/app".to_string()),
+                    earliest: "2026-03-01T09:59:56Z".to_string(),
+                    latest: "2026-03-01T10:00:45Z".to_string(),
+                },
+                SpawnCall {
+                    offset: 4,
+                    harness: "claude-code".to_string(),
+                    command: "/usr/local/bin/claude -p review".to_string(),
+                    cwd: Some("/work/other".to_string()),
+                    earliest: "2026-03-01T10:01:55Z".to_string(),
+                    latest: "2026-03-01T10:07:05Z".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            spawned_harness("echo done; gemini -p hi"),
+            Some("gemini-cli")
+        );
+        assert_eq!(spawned_harness("cat codex.log"), None);
+    }
+}
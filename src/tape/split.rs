@@ -0,0 +1,264 @@
+//! Cutting a long tape into one tape per task (`engram split`). Each part
+//! starts with a copy of the parent's `meta` carrying a `split` field
+//! (parent tape id, part number, the parent offsets it covers), so the
+//! link to the parent survives `reindex`.
+
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+
+/// `meta` key linking a part to the tape it was cut from.
+pub const SPLIT_META_KEY: &str = "split";
+
+/// Default `--idle-gap` for automatic splitting.
+pub const DEFAULT_IDLE_GAP_SECS: i64 = 30 * 60;
+
+/// Where a part came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TapeSplit {
+    pub parent_tape_id: String,
+    /// Numbered from 1.
+    pub part: u64,
+    pub parts: u64,
+    /// First and last parent offsets in the part, inclusive.
+    pub first_offset: u64,
+    pub last_offset: u64,
+}
+
+/// A `--at` value: a tape offset, or a timestamp that splits before the
+/// first event at or after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitAt {
+    Offset(u64),
+    Time(DateTime<Utc>),
+}
+
+impl SplitAt {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if let Ok(offset) = raw.parse() {
+            return Some(Self::Offset(offset));
+        }
+        DateTime::parse_from_rfc3339(raw)
+            .ok()
+            .map(|at| Self::Time(at.with_timezone(&Utc)))
+    }
+}
+
+/// The tape offset `at` splits before, if it falls inside the tape.
+pub fn resolve_split_at(input: &str, at: SplitAt) -> Option<u64> {
+    match at {
+        SplitAt::Offset(offset) => (offset < input.lines().count() as u64).then_some(offset),
+        SplitAt::Time(at) => rows(input)
+            .find(|(_, row)| kind(row) != Some("meta") && timestamp(row).is_some_and(|t| t >= at))
+            .map(|(offset, _)| offset),
+    }
+}
+
+/// Task boundaries: every user prompt (`msg.in`) that comes after at
+/// least `idle_gap_secs` without events.
+pub fn auto_split_points(input: &str, idle_gap_secs: i64) -> Vec<u64> {
+    let mut points = Vec::new();
+    let mut last_at = None;
+    for (offset, row) in rows(input) {
+        if kind(&row) == Some("meta") {
+            continue;
+        }
+        let Some(at) = timestamp(&row) else {
+            continue;
+        };
+        if kind(&row) == Some("msg.in")
+            && last_at.is_some_and(|last: DateTime<Utc>| (at - last).num_seconds() >= idle_gap_secs)
+        {
+            points.push(offset);
+        }
+        last_at = Some(last_at.map_or(at, |last: DateTime<Utc>| last.max(at)));
+    }
+    points
+}
+
+/// Cut `input` (the tape `parent_tape_id`) before each of `points`. Points
+/// that would leave a part without events are dropped. Returns each part
+/// with its tape content; a single part means nothing was cut.
+pub fn split_tape_jsonl(
+    parent_tape_id: &str,
+    input: &str,
+    points: &[u64],
+) -> Vec<(TapeSplit, String)> {
+    let lines = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(offset, line)| (offset as u64, line))
+        .collect::<Vec<_>>();
+    let is_meta = |line: &str| {
+        serde_json::from_str::<Value>(line)
+            .ok()
+            .is_some_and(|row| kind(&row) == Some("meta"))
+    };
+    let meta = lines
+        .iter()
+        .find(|(_, line)| is_meta(line))
+        .and_then(|(_, line)| serde_json::from_str::<Value>(line).ok());
+
+    let mut points = points.to_vec();
+    points.sort_unstable();
+    points.dedup();
+    let mut ranges = Vec::<Vec<(u64, &str)>>::new();
+    let mut current = Vec::<(u64, &str)>::new();
+    let mut next_point = points.iter().peekable();
+    for (offset, line) in &lines {
+        let mut cut = false;
+        while next_point.peek().is_some_and(|point| **point <= *offset) {
+            next_point.next();
+            cut = true;
+        }
+        let has_events = current.iter().any(|(_, line)| !is_meta(line));
+        if cut && has_events {
+            ranges.push(std::mem::take(&mut current));
+        }
+        current.push((*offset, *line));
+    }
+    if !current.is_empty() {
+        ranges.push(current);
+    }
+
+    let parts = ranges.len() as u64;
+    ranges
+        .into_iter()
+        .enumerate()
+        .map(|(at, range)| {
+            let split = TapeSplit {
+                parent_tape_id: parent_tape_id.to_string(),
+                part: at as u64 + 1,
+                parts,
+                first_offset: range.first().map_or(0, |(offset, _)| *offset),
+                last_offset: range.last().map_or(0, |(offset, _)| *offset),
+            };
+            let mut out = String::new();
+            // The first part stamps the parent's own meta line below.
+            let stamp_own = at == 0 && range.iter().any(|(_, line)| is_meta(line));
+            if !stamp_own {
+                let mut meta = meta.clone().unwrap_or_else(|| json!({"k": "meta"}));
+                if let Some(first_t) = range.iter().find_map(|(_, line)| {
+                    serde_json::from_str::<Value>(line).ok()?.get("t").cloned()
+                }) {
+                    meta["t"] = first_t;
+                }
+                out.push_str(&with_split(meta, &split).to_string());
+                out.push('\n');
+            }
+            let mut stamped = !stamp_own;
+            for (_, line) in &range {
+                if !stamped
+                    && let Ok(row) = serde_json::from_str::<Value>(line)
+                    && kind(&row) == Some("meta")
+                {
+                    out.push_str(&with_split(row, &split).to_string());
+                    stamped = true;
+                } else {
+                    out.push_str(line);
+                }
+                out.push('\n');
+            }
+            (split, out)
+        })
+        .collect()
+}
+
+/// The `split` field of the tape's first `meta`, for tapes cut from another.
+pub fn tape_split(input: &str) -> Option<TapeSplit> {
+    let meta = rows(input).find(|(_, row)| kind(row) == Some("meta"))?.1;
+    let split = meta.get(SPLIT_META_KEY)?;
+    let offsets = split.get("offsets")?;
+    Some(TapeSplit {
+        parent_tape_id: split.get("parent")?.as_str()?.to_string(),
+        part: split.get("part")?.as_u64()?,
+        parts: split.get("parts")?.as_u64()?,
+        first_offset: offsets.get(0)?.as_u64()?,
+        last_offset: offsets.get(1)?.as_u64()?,
+    })
+}
+
+fn with_split(mut meta: Value, split: &TapeSplit) -> Value {
+    if let Some(map) = meta.as_object_mut() {
+        // The parent's origin describes the parent's bytes, not the part's.
+        map.remove("origin");
+        map.insert(
+            SPLIT_META_KEY.to_string(),
+            json!({
+                "parent": split.parent_tape_id,
+                "part": split.part,
+                "parts": split.parts,
+                "offsets": [split.first_offset, split.last_offset],
+            }),
+        );
+    }
+    meta
+}
+
+fn rows(input: &str) -> impl Iterator<Item = (u64, Value)> + '_ {
+    input.lines().enumerate().filter_map(|(offset, line)| {
+        Some((offset as u64, serde_json::from_str::<Value>(line).ok()?))
+    })
+}
+
+fn kind(row: &Value) -> Option<&str> {
+    row.get("k").and_then(Value::as_str)
+}
+
+fn timestamp(row: &Value) -> Option<DateTime<Utc>> {
+    let raw = row.get("t").and_then(Value::as_str)?;
+    Some(DateTime::parse_from_rfc3339(raw).ok()?.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAPE: &str = concat!(
+        r#"{"t":"2026-03-01T09:00:00Z","k":"meta","source":{"harness":"claude-code"},"origin":{"path":"s.jsonl"}}"#,
+        "\n",
+        r#"{"t":"2026-03-01T09:00:01Z","k":"msg.in","text":"fix the login bug"}"#,
+        "\n",
+        r#"{"t":"2026-03-01T09:05:00Z","k":"code.edit","file":"src/login.rs"}"#,
+        "\n",
+        r#"{"t":"2026-03-01T09:20:00Z","k":"msg.in","text":"also rename it"}"#,
+        "\n",
+        r#"{"t":"2026-03-01T13:00:00Z","k":"msg.in","text":"now the billing export"}"#,
+        "\n",
+        r#"{"t":"2026-03-01T13:10:00Z","k":"code.edit","file":"src/billing.rs"}"#,
+        "\n",
+    );
+
+    #[test]
+    fn finds_boundaries_and_cuts_parts_that_link_back() {
+        assert_eq!(auto_split_points(TAPE, DEFAULT_IDLE_GAP_SECS), [4]);
+        assert_eq!(auto_split_points(TAPE, 10 * 60), [3, 4]);
+        let at = SplitAt::parse("2026-03-01T12:00:00Z").expect("timestamp");
+        assert_eq!(resolve_split_at(TAPE, at), Some(4));
+        assert_eq!(resolve_split_at(TAPE, SplitAt::Offset(9)), None);
+
+        let parts = split_tape_jsonl("parent", TAPE, &[0, 4]);
+        assert_eq!(parts.len(), 2);
+        let (first, first_text) = &parts[0];
+        assert_eq!(
+            (first.part, first.first_offset, first.last_offset),
+            (1, 0, 3)
+        );
+        assert_eq!(tape_split(first_text).as_ref(), Some(first));
+        assert_eq!(first_text.lines().count(), 4);
+        assert!(!first_text.contains("origin"));
+
+        let (second, second_text) = &parts[1];
+        assert_eq!((second.part, second.parts), (2, 2));
+        assert_eq!((second.first_offset, second.last_offset), (4, 5));
+        let meta =
+            serde_json::from_str::<Value>(second_text.lines().next().expect("meta")).expect("json");
+        assert_eq!(meta["t"], "2026-03-01T13:00:00Z");
+        assert_eq!(meta["source"]["harness"], "claude-code");
+        assert_eq!(meta["split"]["offsets"], json!([4, 5]));
+        assert_eq!(second_text.lines().count(), 3);
+
+        assert_eq!(split_tape_jsonl("parent", TAPE, &[1]).len(), 1);
+    }
+}
@@ -0,0 +1,286 @@
+//! Git checkout layout: linked worktrees (whose `.git` is a file pointing
+//! into the main repository's `.git/worktrees/<name>`) and submodules
+//! (`.git/modules/<name>`, listed in `.gitmodules`). Read from the
+//! filesystem, without running git.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::tape::paths::{repo_relative_path, slash_path};
+
+/// Where a directory sits in a git checkout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoLayout {
+    /// Top of the checkout the directory is in.
+    pub root: PathBuf,
+    /// Top of the main worktree: `root` unless this is a linked worktree of
+    /// a non-bare repository.
+    pub main_root: PathBuf,
+    /// Name of the linked worktree (`.git/worktrees/<name>`); `None` in the
+    /// main worktree.
+    pub worktree: Option<String>,
+    /// This checkout's path inside its superproject when it is a submodule.
+    pub submodule: Option<String>,
+    /// Submodule paths registered in `root/.gitmodules`, slash-separated.
+    pub submodules: Vec<String>,
+}
+
+impl RepoLayout {
+    /// The layout of the checkout containing `start`, or `None` outside git.
+    pub fn discover(start: &Path) -> Option<Self> {
+        let root = start
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())?
+            .to_path_buf();
+        let dot_git = root.join(".git");
+        let mut layout = Self {
+            main_root: root.clone(),
+            worktree: None,
+            submodule: None,
+            submodules: registered_submodules(&root),
+            root,
+        };
+        if dot_git.is_dir() {
+            return Some(layout);
+        }
+        let Some(git_dir) = linked_git_dir(&dot_git) else {
+            return Some(layout);
+        };
+        if let Ok(common) = fs::read_to_string(git_dir.join("commondir")) {
+            let common_dir = normalize(&git_dir.join(common.trim()));
+            layout.worktree = git_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            if common_dir.file_name().is_some_and(|name| name == ".git")
+                && let Some(main_root) = common_dir.parent()
+            {
+                layout.main_root = main_root.to_path_buf();
+            }
+        } else if git_dir
+            .components()
+            .any(|component| component.as_os_str() == "modules")
+            && let Some(superproject) = layout.root.parent().and_then(Self::discover)
+            && let Ok(path) = layout.root.strip_prefix(&superproject.root)
+        {
+            layout.submodule = Some(slash_path(&path.to_string_lossy()));
+        }
+        Some(layout)
+    }
+
+    pub fn is_linked_worktree(&self) -> bool {
+        self.worktree.is_some()
+    }
+
+    /// The submodule a root-relative `path` belongs to: the registered
+    /// submodule it is under, else this checkout's own submodule path.
+    pub fn submodule_for(&self, path: &str) -> Option<String> {
+        let path = slash_path(path);
+        self.submodules
+            .iter()
+            .find(|submodule| {
+                path.strip_prefix(submodule.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .cloned()
+            .or_else(|| self.submodule.clone())
+    }
+}
+
+/// The directory a `.git` file (`gitdir: <path>`) points at.
+fn linked_git_dir(dot_git: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(dot_git).ok()?;
+    let target = content.trim().strip_prefix("gitdir:")?.trim();
+    let target = Path::new(target);
+    Some(if target.is_absolute() {
+        normalize(target)
+    } else {
+        normalize(&dot_git.parent()?.join(target))
+    })
+}
+
+/// Resolve `.` and `..` lexically; the worktree may not be reachable through
+/// symlinks the way git recorded it.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn registered_submodules(root: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(root.join(".gitmodules")) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "path")
+                .then(|| slash_path(value.trim()).trim_end_matches('/').to_string())
+        })
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Set `worktree` and `submodule` on `code.edit` / `code.read` events to
+/// the linked worktree and submodule their file belongs to, from the layout
+/// of the tape's meta `cwd`. Tapes whose `cwd` is not (or no longer) in a
+/// git checkout pass through unchanged. Returns the tape and the number of
+/// events changed.
+pub fn stamp_repo_scopes(jsonl: &str) -> serde_json::Result<(String, usize)> {
+    let cwd = jsonl
+        .lines()
+        .filter(|line| line.contains("\"meta\""))
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|row| row.get("k").and_then(Value::as_str) == Some("meta"))
+        .and_then(|row| row.get("cwd").and_then(Value::as_str).map(PathBuf::from));
+    match cwd
+        .as_deref()
+        .and_then(|cwd| Some((cwd, RepoLayout::discover(cwd)?)))
+    {
+        Some((cwd, layout)) => stamp_repo_scopes_with(jsonl, cwd, &layout),
+        None => Ok((jsonl.to_string(), 0)),
+    }
+}
+
+/// [`stamp_repo_scopes`] with the layout already known; relative event
+/// paths are taken from `cwd`.
+pub fn stamp_repo_scopes_with(
+    jsonl: &str,
+    cwd: &Path,
+    layout: &RepoLayout,
+) -> serde_json::Result<(String, usize)> {
+    let mut out = String::with_capacity(jsonl.len());
+    let mut changed = 0usize;
+    for line in jsonl.lines() {
+        let is_evidence = line.contains("\"code.edit\"") || line.contains("\"code.read\"");
+        if !is_evidence {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let mut row: Value = serde_json::from_str(line)?;
+        let kind = row.get("k").and_then(Value::as_str).unwrap_or_default();
+        let file = row.get("file").and_then(Value::as_str);
+        let (Some(file), true) = (file, kind == "code.edit" || kind == "code.read") else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+        let relative = repo_relative_path(
+            &cwd.join(file).to_string_lossy(),
+            Some(layout.root.as_path()),
+        );
+        let scopes = [
+            ("worktree", layout.worktree.clone()),
+            ("submodule", layout.submodule_for(&relative)),
+        ];
+        if scopes
+            .iter()
+            .all(|(key, value)| row.get(*key).and_then(Value::as_str) == value.as_deref())
+        {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let Some(object) = row.as_object_mut() else {
+            continue;
+        };
+        for (key, value) in scopes {
+            match value {
+                Some(value) => object.insert(key.to_string(), Value::String(value)),
+                None => object.remove(key),
+            };
+        }
+        changed += 1;
+        out.push_str(&serde_json::to_string(&row)?);
+        out.push('\n');
+    }
+    Ok((out, changed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovers_linked_worktrees_and_submodules() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let main = temp.path().join("main");
+        let linked = temp.path().join("feature");
+        fs::create_dir_all(main.join(".git/worktrees/feature")).expect("main git");
+        fs::create_dir_all(main.join(".git/modules/vendor/lib")).expect("modules");
+        fs::create_dir_all(main.join("vendor/lib/src")).expect("submodule");
+        fs::create_dir_all(linked.join("src")).expect("linked");
+        fs::write(main.join(".git/worktrees/feature/commondir"), "../..\n").expect("commondir");
+        fs::write(
+            linked.join(".git"),
+            format!(
+                "gitdir: {}\n",
+                main.join(".git/worktrees/feature").display()
+            ),
+        )
+        .expect("linked .git");
+        fs::write(
+            main.join("vendor/lib/.git"),
+            "gitdir: ../../.git/modules/vendor/lib\n",
+        )
+        .expect("submodule .git");
+        fs::write(
+            main.join(".gitmodules"),
+            "[submodule \"lib\"]\n\tpath = vendor/lib\n\turl = ../lib.git\n",
+        )
+        .expect(".gitmodules");
+
+        let layout = RepoLayout::discover(&main.join("src")).expect("main layout");
+        assert_eq!(layout.root, main);
+        assert!(!layout.is_linked_worktree());
+        assert_eq!(layout.submodules, vec!["vendor/lib"]);
+        assert_eq!(
+            layout.submodule_for("vendor/lib/src/a.rs").as_deref(),
+            Some("vendor/lib")
+        );
+        assert_eq!(layout.submodule_for("vendor/library.rs"), None);
+
+        let layout = RepoLayout::discover(&linked.join("src")).expect("linked layout");
+        assert_eq!(layout.root, linked);
+        assert_eq!(layout.main_root, main);
+        assert_eq!(layout.worktree.as_deref(), Some("feature"));
+
+        let layout = RepoLayout::discover(&main.join("vendor/lib/src")).expect("submodule");
+        assert_eq!(layout.root, main.join("vendor/lib"));
+        assert_eq!(layout.main_root, layout.root);
+        assert_eq!(layout.submodule.as_deref(), Some("vendor/lib"));
+        assert!(RepoLayout::discover(temp.path()).is_none());
+
+        let main_layout = RepoLayout::discover(&main).expect("main layout");
+        let jsonl = concat!(
+            r#"{"t":"T0","k":"meta","cwd":"/repo"}"#,
+            "\n",
+            r#"{"t":"T1","k":"code.edit","file":"vendor/lib/src/a.rs","after_text":"x"}"#,
+            "\n",
+            r#"{"t":"T2","k":"code.read","file":"src/main.rs","text":"y","submodule":"old"}"#,
+            "\n",
+        );
+        let (stamped, changed) = stamp_repo_scopes_with(jsonl, &main, &main_layout).expect("stamp");
+        assert_eq!(changed, 2);
+        let rows = stamped
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("json"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows[1]["submodule"], "vendor/lib");
+        assert!(rows[1].get("worktree").is_none());
+        assert!(rows[2].get("submodule").is_none());
+        let (_, again) = stamp_repo_scopes_with(&stamped, &main, &main_layout).expect("stamp");
+        assert_eq!(again, 0);
+    }
+}
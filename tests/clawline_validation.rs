@@ -55,12 +55,8 @@ fn clawline_style_tape_yields_agent_useful_peek_content() {
     let sessions = explain["sessions"].as_array().expect("sessions");
     assert_eq!(sessions.len(), 1);
 
-    let session_id = sessions[0]["session_id"]
-        .as_str()
-        .expect("session_id");
-    let window_start = sessions[0]["window_start"]
-        .as_u64()
-        .expect("window_start") as usize;
+    let session_id = sessions[0]["session_id"].as_str().expect("session_id");
+    let window_start = sessions[0]["window_start"].as_u64().expect("window_start") as usize;
     let window_end = sessions[0]["window_end"].as_u64().expect("window_end") as usize;
     let window_lines = window_end.saturating_sub(window_start).saturating_add(1);
 
@@ -77,7 +73,10 @@ fn clawline_style_tape_yields_agent_useful_peek_content() {
         None,
     );
     let content = peek["session"]["content"].as_array().expect("content");
-    assert!(!content.is_empty(), "expected peek content around touch events");
+    assert!(
+        !content.is_empty(),
+        "expected peek content around touch events"
+    );
 
     let mut saw_contextual_event = false;
     for line in content {
@@ -7,8 +7,8 @@ use engram::anchor::{
     expand_winnow_anchor, fingerprint_anchor_hashes, fingerprint_similarity, fingerprint_text,
 };
 use rusqlite::Connection;
-use serde_json::Value;
-use sha2::{Digest, Sha256};
+use serde_json::{Value, json};
+use sha2::Digest;
 
 fn run_cli(repo: &Path, args: &[&str], stdin: Option<&str>) -> Output {
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_engram"));
@@ -44,15 +44,21 @@ fn run_json(repo: &Path, args: &[&str], stdin: Option<&str>) -> Value {
 }
 
 fn tape_id_for_contents(input: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(input.as_bytes());
-    let digest = hasher.finalize();
-    let mut out = String::with_capacity(digest.len() * 2);
-    for byte in digest {
-        use std::fmt::Write as _;
-        let _ = write!(&mut out, "{byte:02x}");
-    }
-    out
+    engram::tape::identity::tape_id(input)
+}
+
+/// Hold the store lock at `path` the way another engram process would.
+fn hold_lock(path: &Path, command: &str) -> fs::File {
+    let mut file = fs::File::create(path).expect("lock file");
+    file.try_lock().expect("lock");
+    let owner = json!({
+        "pid": std::process::id(),
+        "command": command,
+        "since": "2026-02-22T00:00:00Z",
+    });
+    file.write_all(owner.to_string().as_bytes())
+        .expect("lock owner");
+    file
 }
 
 #[test]
@@ -106,7 +112,7 @@ fn init_record_tapes_show_and_explain_roundtrip() {
     let result_id = explain["result_id"].as_str().expect("result_id");
     assert!(result_id.starts_with("result_"));
     let query_anchors = explain["query"]["anchors"].as_array().expect("anchors");
-    assert!(query_anchors.len() >= 1);
+    assert!(!query_anchors.is_empty());
     // query_anchors are individual tokens; verify at least one token from the
     // span's fingerprint is present.
     let span_tokens = expand_winnow_anchor(&span_anchor);
@@ -122,6 +128,62 @@ fn init_record_tapes_show_and_explain_roundtrip() {
     assert!(sessions[0]["confidence"].as_f64().unwrap_or(0.0) >= 0.0);
     assert!(sessions[0]["window_start"].as_u64().unwrap_or(0) >= 1);
     assert!(sessions[0]["window_end"].as_u64().unwrap_or(0) >= 1);
+    assert_eq!(sessions[0]["episode"], 0);
+    let episodes = explain["episodes"].as_array().expect("episodes");
+    assert_eq!(episodes.len(), 1);
+    assert_eq!(episodes[0]["repo_heads"], json!(["abc123"]));
+    assert_eq!(episodes[0]["summary"], "1 session on 2026-02-22 at abc123");
+
+    let streamed = run_cli(repo, &["explain", "src/lib.rs:2-2", "--json-lines"], None);
+    assert!(streamed.status.success());
+    let records = String::from_utf8_lossy(&streamed.stdout)
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("ndjson record"))
+        .collect::<Vec<_>>();
+    assert_eq!(records.first().expect("query record")["type"], "query");
+    let summary = records.last().expect("summary record");
+    assert_eq!(summary["type"], "summary");
+    assert!(
+        summary["result_id"]
+            .as_str()
+            .is_some_and(|id| id.starts_with("result_"))
+    );
+    assert_eq!(summary["episodes"], explain["episodes"]);
+    assert_eq!(summary["total"], explain["total"]);
+    let streamed_sessions = records
+        .iter()
+        .filter(|record| record["type"] == "session")
+        .map(|record| &record["session"]["session_id"])
+        .collect::<Vec<_>>();
+    assert_eq!(streamed_sessions, vec![&sessions[0]["session_id"]]);
+
+    // A reader that closes the pipe early ends the stream quietly.
+    let mut closed = Command::new(env!("CARGO_BIN_EXE_engram"))
+        .current_dir(repo)
+        .args(["explain", "src/lib.rs:2-2", "--json-lines"])
+        .env("HOME", repo.join(".home"))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("command spawns");
+    drop(closed.stdout.take());
+    let closed = closed.wait_with_output().expect("command output");
+    assert!(
+        closed.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&closed.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&closed.stderr);
+    assert!(!stderr.contains("panicked") && !stderr.contains("\"error\""));
+
+    let with_paths = run_json(repo, &["explain", "src/lib.rs:2-2", "--paths"], None);
+    for path in with_paths["paths"].as_array().expect("paths array") {
+        let anchors = path["anchors"].as_array().expect("path anchors");
+        assert_eq!(anchors.first(), Some(&path["root"]));
+        assert_eq!(anchors.last(), Some(&path["target"]));
+        assert!(query_anchors.contains(&path["target"]));
+    }
+    assert!(explain.get("paths").is_none());
 
     let rate = run_json(
         repo,
@@ -321,7 +383,7 @@ fn explain_matches_winnow_edit_anchor_for_span_targets() {
     );
     // With individual-token edges, each matched token produces one lineage edge.
     assert!(
-        explain["lineage"].as_array().expect("lineage").len() >= 1,
+        !explain["lineage"].as_array().expect("lineage").is_empty(),
         "expected at least one inbound edit linkage for matched winnow anchor"
     );
 }
@@ -387,6 +449,14 @@ fn explain_matches_winnow_edit_anchor_for_multiline_span_with_trailing_newline()
     let _ = run_json(repo, &["record", "--stdin"], Some(&transcript));
 
     let explain = run_json(repo, &["explain", "src/lib.rs:1-3"], None);
+    eprintln!(
+        "{}",
+        run_json(
+            repo,
+            &["explain", "src/lib.rs", "--min-confidence", "0"],
+            None
+        )["lineage"]
+    );
     let query_anchors = explain["query"]["anchors"].as_array().expect("anchors");
     assert!(
         query_anchors
@@ -612,7 +682,7 @@ fn record_command_captures_tool_events_and_exit_status() {
     let repo = temp.path();
     let _ = run_json(repo, &["init"], None);
 
-    let record = run_json(
+    let output = run_cli(
         repo,
         &[
             "record",
@@ -622,6 +692,8 @@ fn record_command_captures_tool_events_and_exit_status() {
         ],
         None,
     );
+    assert_eq!(output.status.code(), Some(7), "child exit code propagates");
+    let record: Value = serde_json::from_slice(&output.stdout).expect("json stdout");
     let tape_id = record["tape_id"].as_str().expect("tape id");
     assert_eq!(record["status"], "ok");
     assert_eq!(record["recorded_command"]["exit"], 7);
@@ -705,6 +777,52 @@ fn gc_removes_unreferenced_tapes() {
     assert_eq!(after["tapes"].as_array().expect("tapes").len(), 1);
 }
 
+#[test]
+fn gc_dry_run_deletes_nothing_and_protected_tapes_survive() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let mut unreferenced = Vec::new();
+    for model in ["gpt-5", "o4"] {
+        let transcript = format!(r#"{{"t":"2026-02-22T00:00:00Z","k":"meta","model":"{model}"}}"#);
+        let record = run_json(
+            repo,
+            &["record", "--stdin"],
+            Some(&format!("{transcript}\n")),
+        );
+        unreferenced.push(record["tape_id"].as_str().expect("tape id").to_string());
+    }
+    unreferenced.sort();
+
+    let dry = run_json(repo, &["gc", "--dry-run"], None);
+    assert_eq!(dry["dry_run"], true);
+    assert_eq!(dry["deleted_tape_ids"], json!(unreferenced));
+    let tapes = run_json(repo, &["tapes"], None);
+    assert_eq!(tapes["tapes"].as_array().expect("tapes").len(), 2);
+
+    let kept = &unreferenced[0];
+    let protect = run_json(
+        repo,
+        &["protect", "add", kept, "--note", "incident evidence"],
+        None,
+    );
+    assert_eq!(protect["added"], true);
+    assert_eq!(protect["entries"][0]["note"], "incident evidence");
+
+    let gc = run_json(repo, &["gc"], None);
+    assert_eq!(gc["deleted_tape_ids"], json!([unreferenced[1]]));
+    assert_eq!(gc["protected_tape_ids"], json!([kept]));
+    let tapes = run_json(repo, &["tapes"], None);
+    assert_eq!(tapes["tapes"].as_array().expect("tapes").len(), 1);
+
+    run_json(repo, &["protect", "remove", kept], None);
+    let missing = run_cli(repo, &["protect", "remove", kept], None);
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("tape_not_protected"));
+    let gc = run_json(repo, &["gc"], None);
+    assert_eq!(gc["deleted_tape_ids"], json!([kept]));
+}
+
 #[test]
 fn record_recovers_when_tape_file_exists_but_index_missing() {
     let temp = tempfile::tempdir().expect("tempdir");
@@ -782,7 +900,13 @@ fn record_command_keeps_trace_for_failed_process() {
 
     let record = run_json(
         repo,
-        &["record", "/bin/sh", "-c", "echo boom >&2; exit 7"],
+        &[
+            "record",
+            "--no-propagate",
+            "/bin/sh",
+            "-c",
+            "echo boom >&2; exit 7",
+        ],
         None,
     );
     assert_eq!(record["status"], "ok");
@@ -832,3 +956,3456 @@ fn watch_errors_clearly_when_watch_config_is_missing() {
         "stderr={stderr}"
     );
 }
+
+#[test]
+fn tapes_with_metrics_and_stats_report_session_quality() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let transcript = concat!(
+        r#"{"t":"2026-02-22T00:00:00Z","k":"meta","model":"gpt-5","source":{"harness":"codex-cli"}}"#,
+        "\n",
+        r#"{"t":"2026-02-22T00:00:01Z","k":"msg.in","text":"fix the flake"}"#,
+        "\n",
+        r#"{"t":"2026-02-22T00:00:02Z","k":"tool.call","tool":"cargo test"}"#,
+        "\n",
+        r#"{"t":"2026-02-22T00:00:03Z","k":"tool.result","tool":"cargo test","exit":101}"#,
+        "\n",
+        r#"{"t":"2026-02-22T00:00:04Z","k":"code.edit","file":"src/lib.rs","before_text":"retry(1)","after_text":"retry(3)"}"#,
+        "\n",
+        r#"{"t":"2026-02-22T00:00:30Z","k":"code.edit","file":"src/lib.rs","before_text":"retry(3)","after_text":"retry(1)"}"#,
+        "\n"
+    );
+    let _ = run_json(repo, &["record", "--stdin"], Some(transcript));
+
+    let plain = run_json(repo, &["tapes"], None);
+    assert!(plain["tapes"][0].get("metrics").is_none());
+
+    let tapes = run_json(repo, &["tapes", "--with-metrics"], None);
+    let metrics = &tapes["tapes"][0]["metrics"];
+    assert_eq!(metrics["tool_call_count"], 1);
+    assert_eq!(metrics["tool_error_count"], 1);
+    assert_eq!(metrics["tool_error_rate"], 1.0);
+    assert_eq!(metrics["edit_count"], 2);
+    assert_eq!(metrics["revert_count"], 1);
+    assert_eq!(metrics["messages_per_edit"], 0.5);
+    assert_eq!(metrics["duration_secs"], 30);
+
+    let stats = run_json(repo, &["stats"], None);
+    assert_eq!(stats["totals"]["tapes"], 1);
+    assert_eq!(stats["by_harness"][0]["harness"], "codex-cli");
+    assert_eq!(stats["by_model"][0]["model"], "gpt-5");
+    assert_eq!(stats["by_model"][0]["revert_ratio"], 0.5);
+}
+
+#[test]
+fn tapes_and_stats_flag_thrash_loops() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let mut transcript = String::new();
+    for second in 0..3 {
+        transcript.push_str(&format!(
+            "{}\n{}\n",
+            json!({"t": format!("2026-02-22T00:00:0{second}Z"), "k": "tool.call", "tool": "bash", "args": "npm test"}),
+            json!({"t": format!("2026-02-22T00:00:0{second}Z"), "k": "tool.result", "tool": "bash", "exit": 1, "stderr": "Cannot find module 'jest'"}),
+        ));
+    }
+    let _ = run_json(repo, &["record", "--stdin"], Some(&transcript));
+
+    let tapes = run_json(repo, &["tapes", "--with-metrics"], None);
+    let tape = &tapes["tapes"][0];
+    assert_eq!(tape["metrics"]["thrash_loop_count"], 1, "{tape:#}");
+    assert_eq!(tape["thrash_loops"][0]["args"], "npm test");
+    assert_eq!(tape["thrash_loops"][0]["exit"], 1);
+    assert_eq!(tape["thrash_loops"][0]["repeats"], 3);
+    assert_eq!(tape["thrash_loops"][0]["offsets"], json!([1, 3, 5]));
+
+    let stats = run_json(repo, &["stats"], None);
+    assert_eq!(stats["totals"]["thrash_loop_count"], 1);
+    assert_eq!(stats["totals"]["thrashing_tapes"], 1);
+}
+
+#[test]
+fn completions_and_man_are_generated_from_cli_definitions() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+
+    let bash = run_cli(repo, &["completions", "bash"], None);
+    assert!(bash.status.success());
+    let script = String::from_utf8_lossy(&bash.stdout);
+    assert!(script.contains("engram"), "script={script}");
+    assert!(script.contains("explain"), "script={script}");
+
+    let man = run_cli(repo, &["man"], None);
+    assert!(man.status.success());
+    assert!(String::from_utf8_lossy(&man.stdout).contains(".TH engram"));
+
+    let written = run_json(
+        repo,
+        &["completions", "zsh", "--out-dir", "completions"],
+        None,
+    );
+    assert_eq!(written["status"], "ok");
+    assert!(repo.join("completions/_engram").is_file());
+
+    fs::create_dir_all(repo.join("man")).expect("man dir");
+    fs::write(repo.join("man/other-tool.1"), ".TH other-tool 1\n").expect("stray page");
+    let pages = run_json(repo, &["man", "--out-dir", "man"], None);
+    assert_eq!(pages["status"], "ok");
+    assert!(repo.join("man/engram.1").is_file());
+    assert!(repo.join("man/engram-explain.1").is_file());
+    let listed = pages["pages"].as_array().expect("pages");
+    assert!(listed.iter().any(|page| {
+        page.as_str()
+            .is_some_and(|page| page.ends_with("engram-explain.1"))
+    }));
+    assert!(
+        !listed.iter().any(|page| page
+            .as_str()
+            .is_some_and(|page| page.ends_with("other-tool.1"))),
+        "{listed:?}"
+    );
+}
+
+#[test]
+fn explain_summarize_sends_windows_to_configured_endpoint_and_keeps_citations() {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    let _ = run_json(repo, &["init"], None);
+
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let transcript = format!(
+        concat!(
+            "{{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"msg.in\",\"text\":\"make omega add one\"}}\n",
+            "{{\"t\":\"2026-02-22T00:00:01Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",",
+            "\"before_range\":[2,2],\"after_range\":[2,2],",
+            "\"before_anchor_hashes\":[\"winnow:00000000000000cc\"],",
+            "\"after_anchor_hashes\":[\"{0}\"],\"after_text\":\"{1}\"}}\n"
+        ),
+        span_anchor, span_text
+    );
+    let record = run_json(repo, &["record", "--stdin"], Some(&transcript));
+    let tape_id = record["tape_id"].as_str().expect("tape id").to_string();
+
+    let unconfigured = run_cli(repo, &["explain", "src/lib.rs:2-2", "--summarize"], None);
+    assert!(!unconfigured.status.success());
+    assert!(String::from_utf8_lossy(&unconfigured.stderr).contains("summarize_not_configured"));
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let reply_tape_id = tape_id.clone();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).expect("request line");
+        let mut content_length = 0usize;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).expect("header");
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().expect("content length");
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).expect("body");
+        let reply = json!({
+            "model": "local-test",
+            "choices": [{"message": {"content": format!(
+                "The user asked for it [{reply_tape_id}:0], then it was written [{reply_tape_id}:1] [other:7]."
+            )}}],
+        })
+        .to_string();
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+            reply.len()
+        )
+        .expect("response");
+        (
+            request_line,
+            serde_json::from_slice::<Value>(&body).expect("json body"),
+        )
+    });
+
+    let mut config = fs::read_to_string(repo.join(".home/.engram/config.yml")).expect("config");
+    config.push_str(&format!(
+        "summarize:\n  base_url: http://127.0.0.1:{port}/v1\n  model: local-test\n"
+    ));
+    fs::write(repo.join(".home/.engram/config.yml"), config).expect("write config");
+
+    let explain = run_json(repo, &["explain", "src/lib.rs:2-2", "--summarize"], None);
+    let (request_line, body) = server.join().expect("server thread");
+    assert!(request_line.starts_with("POST /v1/chat/completions "));
+    assert_eq!(body["model"], "local-test");
+    let prompt = body["messages"][1]["content"].as_str().expect("prompt");
+    assert!(prompt.contains(&format!("[{tape_id}:0] user: make omega add one")));
+
+    let narrative = &explain["narrative"];
+    assert_eq!(narrative["model"], "local-test");
+    assert!(
+        narrative["text"]
+            .as_str()
+            .expect("text")
+            .contains("[other:7]")
+    );
+    assert_eq!(
+        narrative["citations"],
+        json!([
+            {"tape_id": tape_id, "offset": 0},
+            {"tape_id": tape_id, "offset": 1},
+        ])
+    );
+    assert!(
+        explain["sessions"]
+            .as_array()
+            .is_some_and(|s| !s.is_empty())
+    );
+}
+
+#[test]
+fn semantic_search_ranks_sessions_by_embedded_events() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let unconfigured = run_cli(repo, &["search", "--semantic", "tokio"], None);
+    assert!(!unconfigured.status.success());
+    assert!(String::from_utf8_lossy(&unconfigured.stderr).contains("embeddings_not_configured"));
+
+    let config_path = repo.join(".home/.engram/config.yml");
+    let mut config = fs::read_to_string(&config_path).expect("config");
+    config.push_str("embeddings:\n  provider: hashing\n  dims: 512\n");
+    fs::write(&config_path, config).expect("write config");
+
+    let runtime = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(concat!(
+            "{\"t\":\"2026-03-01T00:00:00Z\",\"k\":\"msg.in\",\"text\":\"the blocking runtime stalls, move the server to tokio\"}\n",
+            "{\"t\":\"2026-03-01T00:00:01Z\",\"k\":\"tool.call\",\"tool\":\"cargo\",\"args\":\"add tokio --features full\"}\n",
+        )),
+    );
+    assert!(runtime.get("embedding_error").is_none());
+    let runtime_id = runtime["tape_id"].as_str().expect("tape id").to_string();
+    let docs = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(
+            "{\"t\":\"2026-03-02T00:00:00Z\",\"k\":\"msg.in\",\"text\":\"rename the README install heading\"}\n",
+        ),
+    );
+    let docs_id = docs["tape_id"].as_str().expect("tape id").to_string();
+
+    let literal = run_cli(repo, &["search", "tokio"], None);
+    assert!(!literal.status.success());
+    assert!(String::from_utf8_lossy(&literal.stderr).contains("invalid_search"));
+
+    let search = run_json(
+        repo,
+        &["search", "--semantic", "why did we switch to tokio"],
+        None,
+    );
+    assert!(search["result_id"].is_string());
+    assert_eq!(search["query"]["provider"], "hashing-512");
+    let sessions = search["sessions"].as_array().expect("sessions");
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0]["session_id"], runtime_id.as_str());
+    assert_eq!(sessions[1]["session_id"], docs_id.as_str());
+    assert!(
+        sessions[0]["confidence"].as_f64().expect("score")
+            > sessions[1]["confidence"].as_f64().expect("score")
+    );
+    let matches = sessions[0]["matches"].as_array().expect("matches");
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0]["kind"], "msg.in");
+}
+
+#[test]
+fn record_timeout_and_output_limit_leave_markers_in_tape() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let limited = run_json(
+        repo,
+        &[
+            "record",
+            "--max-output-bytes",
+            "10",
+            "/bin/sh",
+            "-c",
+            "printf '0123456789abcdefghij'",
+        ],
+        None,
+    );
+    assert_eq!(limited["recorded_command"]["stdout_bytes"], 20);
+    assert_eq!(limited["recorded_command"]["stdout_truncated"], true);
+    assert_eq!(limited["recorded_command"]["stderr_truncated"], false);
+    let tape_id = limited["tape_id"].as_str().expect("tape id");
+    let raw = run_cli(repo, &["show", tape_id, "--raw"], None);
+    let raw_text = String::from_utf8_lossy(&raw.stdout);
+    assert!(
+        raw_text.contains(
+            "\"stdout\":\"0123456789\\n[engram: stdout truncated after 10 of 20 bytes]\""
+        ),
+        "raw={raw_text}"
+    );
+    assert!(raw_text.contains("\"stdout_truncated\":true"));
+
+    let started = std::time::Instant::now();
+    let timed_out = run_cli(
+        repo,
+        &[
+            "record",
+            "--timeout",
+            "1",
+            "/bin/sh",
+            "-c",
+            "echo started; sleep 30",
+        ],
+        None,
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(20));
+    assert_eq!(timed_out.status.code(), Some(124));
+    let record: Value = serde_json::from_slice(&timed_out.stdout).expect("json stdout");
+    assert_eq!(record["recorded_command"]["timed_out"], true);
+    assert_eq!(record["recorded_command"]["success"], false);
+    let tape_id = record["tape_id"].as_str().expect("tape id");
+    let raw = run_cli(repo, &["show", tape_id, "--raw"], None);
+    let raw_text = String::from_utf8_lossy(&raw.stdout);
+    assert!(raw_text.contains("\"timed_out\":true"), "raw={raw_text}");
+    assert!(raw_text.contains("\"timeout_secs\":1"), "raw={raw_text}");
+}
+
+#[test]
+fn hook_receiver_appends_inflight_events_and_seals_tape_on_session_end() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let prompt = json!({
+        "session_id": "sess-1",
+        "cwd": repo,
+        "hook_event_name": "UserPromptSubmit",
+        "prompt": "switch the retry loop to exponential backoff"
+    });
+    let appended = run_json(repo, &["hook-receiver"], Some(&prompt.to_string()));
+    assert_eq!(appended["appended"], 1);
+    assert_eq!(appended["flushed"], false);
+    let inflight = repo.join(".engram/inflight/claude-code-sess-1.jsonl");
+    assert!(inflight.exists());
+
+    let tool = json!({
+        "session_id": "sess-1",
+        "hook_event_name": "PostToolUse",
+        "tool_name": "Write",
+        "tool_use_id": "toolu_1",
+        "tool_input": {"file_path": "src/retry.rs", "content": "fn backoff() {}\n"},
+        "tool_response": {"filePath": "src/retry.rs", "success": true}
+    });
+    let appended = run_json(repo, &["hook-receiver"], Some(&tool.to_string()));
+    assert_eq!(appended["appended"], 3);
+
+    let stop = json!({"session_id": "sess-1", "hook_event_name": "Stop"});
+    let turn_done = run_json(repo, &["hook-receiver"], Some(&stop.to_string()));
+    assert_eq!(turn_done["flushed"], false);
+    assert!(inflight.exists());
+    let next = json!({
+        "session_id": "sess-1",
+        "hook_event_name": "UserPromptSubmit",
+        "prompt": "and cap it at a minute"
+    });
+    let appended = run_json(repo, &["hook-receiver"], Some(&next.to_string()));
+    assert_eq!(appended["appended"], 1);
+    let lines = fs::read_to_string(&inflight).expect("inflight");
+    assert_eq!(
+        lines
+            .lines()
+            .filter(|line| line.contains("\"k\":\"meta\""))
+            .count(),
+        1
+    );
+
+    let end = json!({"session_id": "sess-1", "hook_event_name": "SessionEnd"});
+    let sealed = run_json(repo, &["hook-receiver"], Some(&end.to_string()));
+    assert_eq!(sealed["flushed"], true);
+    assert_eq!(sealed["tape"]["event_count"], 6);
+    assert_eq!(sealed["tape"]["meta"]["coverage.tool"], "full");
+    assert!(!inflight.exists());
+
+    let grep = run_json(repo, &["grep", "exponential"], None);
+    assert_eq!(grep["sessions"][0]["session_id"], sealed["tape"]["tape_id"]);
+
+    let codex = json!({
+        "type": "agent-turn-complete",
+        "turn-id": "turn-1",
+        "input-messages": ["rename the flag"],
+        "last-assistant-message": "Renamed it."
+    });
+    let sealed = run_json(repo, &["hook-receiver", &codex.to_string()], None);
+    assert_eq!(sealed["harness"], "codex");
+    assert_eq!(sealed["tape"]["event_count"], 3);
+
+    let invalid = run_cli(repo, &["hook-receiver", "{\"foo\":1}"], None);
+    assert!(!invalid.status.success());
+    assert!(String::from_utf8_lossy(&invalid.stderr).contains("invalid_hook_payload"));
+}
+
+#[test]
+fn record_session_appends_parts_and_show_stitches_them() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let first = run_json(
+        repo,
+        &[
+            "record",
+            "--session",
+            "my-task",
+            "/bin/sh",
+            "-c",
+            "echo one",
+        ],
+        None,
+    );
+    assert_eq!(first["session"]["part"], 1);
+    assert_eq!(first["session"]["ref"], "session:my-task");
+    let transcript = concat!(
+        "{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"meta\",\"model\":\"gpt-5\"}\n",
+        "{\"t\":\"2026-02-22T00:00:01Z\",\"k\":\"msg.in\",\"role\":\"user\",\"content\":\"second part\"}\n"
+    );
+    let second = run_json(
+        repo,
+        &["record", "--session", "my-task", "--stdin"],
+        Some(transcript),
+    );
+    assert_eq!(second["session"]["part"], 2);
+    assert_eq!(second["session"]["part_count"], 2);
+    assert_ne!(first["tape_id"], second["tape_id"]);
+
+    let show = run_json(repo, &["show", "session:my-task"], None);
+    let parts = show["parts"].as_array().expect("parts");
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0]["tape_id"], first["tape_id"]);
+    assert_eq!(parts[1]["tape_id"], second["tape_id"]);
+    assert_eq!(
+        show["event_count"].as_u64(),
+        Some(first["event_count"].as_u64().expect("count") + 2)
+    );
+
+    let raw = run_cli(repo, &["show", "session:my-task", "--raw"], None);
+    assert!(String::from_utf8_lossy(&raw.stdout).ends_with(transcript));
+
+    let peek = run_json(
+        repo,
+        &["peek", "session:my-task", "--grep-filter", "second part"],
+        None,
+    );
+    assert_eq!(peek["session"]["session_id"], "session:my-task");
+
+    let tapes = run_json(repo, &["tapes"], None);
+    let tagged = tapes["tapes"]
+        .as_array()
+        .expect("tapes")
+        .iter()
+        .filter(|tape| tape["session"]["name"] == "my-task")
+        .count();
+    assert_eq!(tagged, 2);
+
+    let invalid = run_cli(
+        repo,
+        &["record", "--session", "../x", "--stdin"],
+        Some(transcript),
+    );
+    assert!(!invalid.status.success());
+    assert!(String::from_utf8_lossy(&invalid.stderr).contains("invalid_session_name"));
+}
+
+#[test]
+fn gc_keeps_tapes_named_only_by_a_session_manifest() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    for word in ["hi", "there"] {
+        run_json(
+            repo,
+            &["record", "--session", "task", "--", "echo", word],
+            None,
+        );
+    }
+
+    let dry_run = run_json(repo, &["gc", "--dry-run"], None);
+    assert_eq!(dry_run["deleted_count"], 0);
+    let gc = run_json(repo, &["gc"], None);
+    assert_eq!(gc["deleted_count"], 0);
+    assert_eq!(gc["kept_count"], 2);
+
+    let show = run_json(repo, &["show", "session:task"], None);
+    assert_eq!(show["parts"].as_array().expect("parts").len(), 2);
+}
+
+#[test]
+fn show_diff_reports_events_unique_to_each_tape() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let snapshot = concat!(
+        "{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"meta\",\"model\":\"gpt-5\"}\n",
+        "{\"t\":\"2026-02-22T00:00:01Z\",\"k\":\"msg.in\",\"role\":\"user\",\"content\":\"run tests\"}\n",
+        "{\"t\":\"2026-02-22T00:00:02Z\",\"k\":\"tool.call\",\"tool\":\"bash\",\"call_id\":\"c1\",\"args\":\"cargo test\"}\n",
+        "{\"t\":\"2026-02-22T00:00:03Z\",\"k\":\"tool.result\",\"tool\":\"bash\",\"call_id\":\"c1\",\"exit\":1}\n"
+    );
+    let grown = concat!(
+        "{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"meta\",\"model\":\"gpt-5\"}\n",
+        "{\"t\":\"2026-02-22T00:00:01Z\",\"k\":\"msg.in\",\"role\":\"user\",\"content\":\"run tests\"}\n",
+        "{\"t\":\"2026-02-22T00:00:02Z\",\"k\":\"tool.call\",\"tool\":\"bash\",\"call_id\":\"c1\",\"args\":\"cargo test\"}\n",
+        "{\"t\":\"2026-02-22T00:00:03Z\",\"k\":\"tool.result\",\"tool\":\"bash\",\"call_id\":\"c1\",\"exit\":0}\n",
+        "{\"t\":\"2026-02-22T00:00:04Z\",\"k\":\"msg.out\",\"role\":\"assistant\",\"content\":\"all green\"}\n"
+    );
+    let a = run_json(repo, &["record", "--stdin"], Some(snapshot));
+    let b = run_json(repo, &["record", "--stdin"], Some(grown));
+    let tape_a = a["tape_id"].as_str().expect("tape a");
+    let tape_b = b["tape_id"].as_str().expect("tape b");
+
+    let diff = run_json(repo, &["show", "--diff", tape_a, tape_b], None);
+    assert_eq!(diff["identical"], false);
+    assert_eq!(
+        diff["summary"],
+        json!({"same": 3, "only_a": 0, "only_b": 1, "changed": 1})
+    );
+    assert_eq!(diff["only_b"][0]["k"], "msg.out");
+    assert_eq!(diff["only_b"][0]["offset"], 4);
+    assert_eq!(diff["changed"][0]["fields"], json!(["exit"]));
+    assert_eq!(diff["changed"][0]["a"]["exit"], 1);
+    assert_eq!(diff["changed"][0]["b"]["exit"], 0);
+
+    let same = run_json(repo, &["show", "--diff", tape_a, tape_a], None);
+    assert_eq!(same["identical"], true);
+
+    let missing_other = run_cli(repo, &["show", "--diff", tape_a], None);
+    assert!(!missing_other.status.success());
+}
+
+#[test]
+fn annotations_surface_in_explain_and_survive_reindex() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let _ = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(concat!(
+            r#"{"t":"2026-03-01T00:00:00Z","k":"code.read","file":"src/lib.rs","range":[1,1],"anchor_hashes":["anchor-retry"]}"#,
+            "\n"
+        )),
+    );
+
+    let annotated = run_json(
+        repo,
+        &[
+            "annotate",
+            "anchor-retry",
+            "--anchor",
+            "--note",
+            "do not simplify this; see incident 423",
+        ],
+        None,
+    );
+    assert_eq!(annotated["anchor_count"], 1);
+
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    fs::write(repo.join("src/lib.rs"), "fn retry() {\n    backoff();\n}\n").expect("write source");
+    let span = run_json(
+        repo,
+        &["annotate", "src/lib.rs:1-3", "--note", "owned by payments"],
+        None,
+    );
+    assert!(span["anchor_count"].as_u64().unwrap_or(0) > 0);
+    let empty = run_cli(
+        repo,
+        &["annotate", "anchor-retry", "--anchor", "--note", " "],
+        None,
+    );
+    assert!(!empty.status.success());
+
+    let explain = run_json(repo, &["explain", "anchor-retry", "--anchor"], None);
+    let annotations = explain["annotations"].as_array().expect("annotations");
+    assert_eq!(annotations.len(), 1);
+    assert_eq!(
+        annotations[0]["note"],
+        "do not simplify this; see incident 423"
+    );
+    assert_eq!(annotations[0]["target"], "anchor-retry");
+
+    let reindex = run_json(repo, &["reindex"], None);
+    assert_eq!(reindex["preserved_annotations"], 2);
+    let explain = run_json(repo, &["explain", "anchor-retry", "--anchor"], None);
+    assert_eq!(
+        explain["annotations"]
+            .as_array()
+            .expect("annotations")
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn scan_comments_materializes_notes_and_span_links() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    fs::write(repo.join("src/old.rs"), "fn legacy_charge() {}\n").expect("write old");
+    let source = concat!(
+        "// engram:note keep the retry; see incident 423\n",
+        "fn charge() {\n",
+        "    retry_with_backoff();\n",
+        "}\n",
+        "\n",
+        "// engram:link src/old.rs:1-1 moved from the legacy client\n",
+        "fn refund() {}\n",
+        "// engram:link src/missing.rs:1-2\n",
+        "fn void() {}\n",
+    );
+    fs::write(repo.join("src/pay.rs"), source).expect("write pay");
+    let _ = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(concat!(
+            r#"{"t":"2026-03-01T00:00:00Z","k":"code.read","file":"src/pay.rs","range":[2,4],"text":"fn charge() {\n    retry_with_backoff();\n}\n"}"#,
+            "\n"
+        )),
+    );
+
+    let scanned = run_json(repo, &["scan-comments"], None);
+    assert_eq!(scanned["notes_added"], 1);
+    assert_eq!(scanned["links"], 1);
+    assert_eq!(scanned["issue_count"], 1);
+    assert_eq!(scanned["issues"][0]["line"], 8);
+    assert_eq!(scanned["tape"]["event_count"], 2);
+
+    let explain = run_json(repo, &["explain", "src/pay.rs:2-4"], None);
+    let annotations = explain["annotations"].as_array().expect("annotations");
+    assert_eq!(annotations.len(), 1);
+    assert_eq!(annotations[0]["target"], "src/pay.rs:2-4");
+
+    let rescanned = run_json(repo, &["scan-comments", "src"], None);
+    assert_eq!(rescanned["notes_added"], 0);
+    assert_eq!(rescanned["notes_existing"], 1);
+    assert_eq!(rescanned["tape"]["tape_id"], scanned["tape"]["tape_id"]);
+    assert_eq!(rescanned["tape"]["already_exists"], true);
+}
+
+#[test]
+fn watchlist_notifies_when_new_evidence_touches_watched_paths() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let hook_out = repo.join("hook.json");
+    // `maintain` fails with `store_locked` unless the hook runs after
+    // record released the lock.
+    let hook = format!(
+        "cat > '{}'; echo hook-stderr >&2; '{}' maintain > /dev/null",
+        hook_out.display(),
+        env!("CARGO_BIN_EXE_engram")
+    );
+    let added = run_json(
+        repo,
+        &["watchlist", "add", "src/payments/**", "--hook", &hook],
+        None,
+    );
+    assert_eq!(added["added"], true);
+    let _ = run_json(repo, &["watchlist", "add", "docs/**"], None);
+    let invalid = run_cli(repo, &["watchlist", "add", "src/[payments"], None);
+    assert!(!invalid.status.success());
+
+    let unrelated = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(concat!(
+            r#"{"t":"2026-03-01T00:00:00Z","k":"code.read","file":"src/lib.rs","range":[1,1],"anchor_hashes":["anchor-lib"]}"#,
+            "\n"
+        )),
+    );
+    assert!(unrelated.get("watchlist").is_none());
+
+    let touched = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(concat!(
+            r#"{"t":"2026-03-01T00:01:00Z","k":"code.edit","file":"src/payments/charge.rs","before_hash":"old","after_hash":"new"}"#,
+            "\n"
+        )),
+    );
+    let hits = touched["watchlist"].as_array().expect("watchlist hits");
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0]["pattern"], "src/payments/**");
+    assert_eq!(hits[0]["matches"][0]["file"], "src/payments/charge.rs");
+    assert_eq!(hits[0]["matches"][0]["kind"], "edit");
+    let hooked: Value = serde_json::from_str(&fs::read_to_string(&hook_out).expect("hook output"))
+        .expect("hook json");
+    assert_eq!(hooked["tape_id"], touched["tape_id"]);
+    let log = fs::read_to_string(repo.join(".engram/notifications.jsonl")).expect("log");
+    assert_eq!(log.lines().count(), 1);
+    let watch_log = fs::read_to_string(repo.join(".home/.engram/watch.log")).expect("watch log");
+    assert!(watch_log.contains("hook-stderr"), "{watch_log}");
+    assert!(
+        watch_log.contains(&format!(
+            "watch hook pattern=src/payments/** tape_id={} exit=0",
+            touched["tape_id"].as_str().expect("tape id")
+        )),
+        "{watch_log}"
+    );
+
+    let repeat = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(concat!(
+            r#"{"t":"2026-03-01T00:01:00Z","k":"code.edit","file":"src/payments/charge.rs","before_hash":"old","after_hash":"new"}"#,
+            "\n"
+        )),
+    );
+    assert!(repeat.get("watchlist").is_none());
+
+    let removed = run_json(repo, &["watchlist", "remove", "docs/**"], None);
+    assert_eq!(removed["removed"], 1);
+    let listed = run_json(repo, &["watchlist", "list"], None);
+    assert_eq!(listed["entries"].as_array().expect("entries").len(), 1);
+    let missing = run_cli(repo, &["watchlist", "remove", "docs/**"], None);
+    assert!(!missing.status.success());
+}
+
+#[test]
+fn monthly_partitions_are_queried_transparently_and_dropped_whole() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let config_path = repo.join(".home/.engram/config.yml");
+    let mut config = fs::read_to_string(&config_path).expect("config");
+    config.push_str("maintenance:\n  partitioning: monthly\n");
+    fs::write(&config_path, config).expect("write config");
+
+    let november = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(concat!(
+            r#"{"t":"2025-11-20T00:00:00Z","k":"code.read","file":"src/lib.rs","range":[1,1],"anchor_hashes":["anchor-nov"]}"#,
+            "\n"
+        )),
+    );
+    let december = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some(concat!(
+            r#"{"t":"2025-12-02T00:00:00Z","k":"code.read","file":"src/lib.rs","range":[1,1],"anchor_hashes":["anchor-dec"]}"#,
+            "\n"
+        )),
+    );
+
+    let maintain = run_json(repo, &["maintain"], None);
+    assert_eq!(maintain["partitions"]["moved_tapes"], 2);
+    let partitions_dir = repo.join(".home/.engram/index.partitions");
+    assert!(partitions_dir.join("2025-11.sqlite").is_file());
+    assert!(partitions_dir.join("2025-12.sqlite").is_file());
+    let again = run_json(repo, &["maintain"], None);
+    assert_eq!(again["partitions"]["moved_tapes"], 0);
+
+    let explain = run_json(repo, &["explain", "anchor-nov", "--anchor"], None);
+    assert_eq!(explain["sessions"][0]["session_id"], november["tape_id"]);
+    assert_eq!(explain["stores_queried"], 3);
+
+    let invalid = run_cli(repo, &["gc", "--drop-partitions-before", "2025-13"], None);
+    assert!(!invalid.status.success());
+    let gc = run_json(repo, &["gc", "--drop-partitions-before", "2025-12"], None);
+    assert_eq!(gc["dropped_partitions"][0]["partition"], "2025-11");
+    assert_eq!(gc["deleted_tape_ids"], json!([november["tape_id"]]));
+    assert!(!partitions_dir.join("2025-11.sqlite").exists());
+
+    let dropped = run_cli(repo, &["explain", "anchor-nov", "--anchor"], None);
+    assert!(String::from_utf8_lossy(&dropped.stderr).contains("no_results"));
+    let kept = run_json(repo, &["explain", "anchor-dec", "--anchor"], None);
+    assert_eq!(kept["sessions"][0]["session_id"], december["tape_id"]);
+}
+
+#[test]
+fn reindex_rebuilds_from_tapes_and_keeps_ratings() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let transcript = concat!(
+        "{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"meta\",\"model\":\"gpt-5\"}\n",
+        "{\"t\":\"2026-02-22T00:00:01Z\",\"k\":\"msg.in\",\"role\":\"user\",\"content\":\"retry logic discussion\"}\n"
+    );
+    let record = run_json(repo, &["record", "--stdin"], Some(transcript));
+    let grep = run_json(repo, &["grep", "retry"], None);
+    let result_id = grep["result_id"].as_str().expect("result_id");
+    let _ = run_json(
+        repo,
+        &["rate", result_id, "--outcome", "found_answer"],
+        None,
+    );
+
+    let reindex = run_json(repo, &["reindex"], None);
+    assert_eq!(reindex["status"], "ok");
+    assert_eq!(reindex["reindexed_tapes"], 1);
+    assert_eq!(reindex["preserved_feedback"], 1);
+    let rerate = run_json(repo, &["rate", result_id, "--outcome", "noise"], None);
+    assert_eq!(rerate["status"], "ok");
+    let grep = run_json(repo, &["grep", "retry"], None);
+    assert_eq!(grep["sessions"][0]["session_id"], record["tape_id"]);
+
+    let home_db = repo.join(".home/.engram/index.sqlite");
+    fs::write(&home_db, b"not a sqlite database").expect("corrupt db");
+    let _ = fs::remove_file(repo.join(".home/.engram/index.sqlite-wal"));
+    let _ = fs::remove_file(repo.join(".home/.engram/index.sqlite-shm"));
+    let recovered = run_json(repo, &["reindex"], None);
+    assert_eq!(recovered["reindexed_tapes"], 1);
+    assert!(recovered["feedback_error"].is_string());
+    let grep = run_json(repo, &["grep", "retry"], None);
+    assert_eq!(grep["sessions"][0]["session_id"], record["tape_id"]);
+}
+
+#[test]
+fn maintain_runs_on_demand_and_after_configured_ingest_count() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+
+    let config_path = repo.join(".home/.engram/config.yml");
+    let mut config = fs::read_to_string(&config_path).expect("config");
+    config.push_str("maintenance:\n  auto_after_ingests: 2\n");
+    fs::write(&config_path, config).expect("write config");
+
+    let first = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some("{\"t\":\"2026-03-01T00:00:00Z\",\"k\":\"msg.in\",\"content\":\"first\"}\n"),
+    );
+    assert!(first.get("maintenance").is_none());
+    let second = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some("{\"t\":\"2026-03-01T00:01:00Z\",\"k\":\"msg.in\",\"content\":\"second\"}\n"),
+    );
+    assert_eq!(second["maintenance"]["trigger"], "auto");
+    let repeat = run_json(
+        repo,
+        &["record", "--stdin"],
+        Some("{\"t\":\"2026-03-01T00:01:00Z\",\"k\":\"msg.in\",\"content\":\"second\"}\n"),
+    );
+    assert!(repeat.get("maintenance").is_none());
+
+    let maintain = run_json(repo, &["maintain"], None);
+    assert_eq!(maintain["status"], "ok");
+    assert_eq!(maintain["converted_to_incremental_vacuum"], false);
+    assert_eq!(maintain["free_pages_after"], 0);
+}
+
+#[test]
+fn explain_also_follows_lineage_into_sibling_indexes() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let upstream = temp.path().join("upstream");
+    let downstream = temp.path().join("downstream");
+    fs::create_dir_all(&upstream).expect("upstream dir");
+    fs::create_dir_all(&downstream).expect("downstream dir");
+    let _ = run_json(&upstream, &["init"], None);
+    let _ = run_json(&downstream, &["init"], None);
+
+    let edit = |t: &str, before: &str, after: &str| {
+        format!(
+            concat!(
+                "{{\"t\":\"{0}\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",",
+                "\"before_range\":[1,1],\"after_range\":[1,1],",
+                "\"before_anchor_hashes\":[\"{1}\"],\"after_anchor_hashes\":[\"{2}\"]}}\n"
+            ),
+            t, before, after
+        )
+    };
+    let original = "winnow:00000000000000a1";
+    let copied = "winnow:00000000000000a2";
+    let reworked = "winnow:00000000000000a3";
+    let _ = run_json(
+        &upstream,
+        &["record", "--stdin"],
+        Some(&edit("2026-02-20T00:00:00Z", original, copied)),
+    );
+    let _ = run_json(
+        &downstream,
+        &["record", "--stdin"],
+        Some(&edit("2026-02-22T00:00:00Z", copied, reworked)),
+    );
+
+    let local = run_json(
+        &downstream,
+        &["explain", reworked, "--anchor", "--forensics"],
+        None,
+    );
+    assert_eq!(local["lineage"].as_array().expect("lineage").len(), 1);
+
+    // Tests run with HOME inside each repository, so the index lives there.
+    let sibling = upstream.join(".home").join(".engram");
+    let sibling = sibling.to_str().expect("utf8 path");
+    let across = run_json(
+        &downstream,
+        &[
+            "explain",
+            reworked,
+            "--anchor",
+            "--forensics",
+            "--also",
+            sibling,
+        ],
+        None,
+    );
+    let lineage = across["lineage"].as_array().expect("lineage");
+    assert_eq!(lineage.len(), 2, "{across:#}");
+    assert!(
+        lineage
+            .iter()
+            .any(|edge| edge["from_anchor"] == original && edge["to_anchor"] == copied)
+    );
+    assert_eq!(across["sessions"].as_array().expect("sessions").len(), 2);
+
+    let missing = run_cli(
+        &downstream,
+        &["explain", reworked, "--anchor", "--also", "nope"],
+        None,
+    );
+    assert!(!missing.status.success());
+}
+
+#[test]
+fn provenance_scan_reports_spans_seen_by_other_stores() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let upstream = temp.path().join("upstream");
+    let downstream = temp.path().join("downstream");
+    fs::create_dir_all(&upstream).expect("upstream dir");
+    fs::create_dir_all(downstream.join("src")).expect("downstream dir");
+    let _ = run_json(&upstream, &["init"], None);
+    let _ = run_json(&downstream, &["init"], None);
+
+    let block = concat!(
+        "fn settle(ledger: &mut Ledger, amount: i64) -> Result<(), Error> {\n",
+        "    let account = ledger.account_mut(\"settlement\")?;\n",
+        "    account.balance = account.balance.checked_add(amount).ok_or(Error::Overflow)?;\n",
+        "    ledger.journal.push(Entry::settled(amount));\n",
+        "    Ok(())\n",
+        "}"
+    );
+    let tokens = engram::anchor::fingerprint_token_hashes(block);
+    let transcript = format!(
+        "{}\n",
+        json!({
+            "t": "2026-02-20T00:00:00Z",
+            "k": "code.edit",
+            "file": "billing/ledger.rs",
+            "before_range": [1, 1],
+            "after_range": [1, 6],
+            "after_anchor_hashes": tokens,
+        })
+    );
+    let recorded = run_json(&upstream, &["record", "--stdin"], Some(&transcript));
+
+    fs::write(
+        downstream.join("src/copied.rs"),
+        format!("use crate::ledger::*;\n\n{block}\n"),
+    )
+    .expect("write copied");
+    fs::write(
+        downstream.join("src/own.rs"),
+        "pub fn greet(name: &str) -> String {\n    format!(\"hello {name}\")\n}\n",
+    )
+    .expect("write own");
+
+    let sibling = upstream.join(".home").join(".engram");
+    let sibling = sibling.to_str().expect("utf8 path");
+    let report = run_json(&downstream, &["provenance", "scan", sibling], None);
+    assert_eq!(report["match_count"], 1, "{report:#}");
+    let found = &report["matches"][0];
+    assert_eq!(found["file"], "src/copied.rs");
+    assert_eq!(found["range"], json!({"start": 3, "end": 8}));
+    assert_eq!(found["overlap"], 1.0);
+    assert_eq!(found["sessions"][0]["tape_id"], recorded["tape_id"]);
+    assert_eq!(found["sessions"][0]["files"], json!(["billing/ledger.rs"]));
+
+    let missing = run_cli(&downstream, &["provenance", "scan", "nope"], None);
+    assert!(!missing.status.success());
+}
+
+#[test]
+fn replay_applies_tape_edits_onto_a_directory_or_git_ref() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let edits = [
+        json!({"t": "2026-02-22T00:00:01Z", "k": "code.edit", "file": "src/lib.rs",
+               "before_text": "fn old() {}\n", "after_text": "fn new() {}\n"}),
+        json!({"t": "2026-02-22T00:00:02Z", "k": "code.edit", "file": "src/added.rs",
+               "after_text": "pub fn added() {}\n"}),
+        json!({"t": "2026-02-22T00:00:03Z", "k": "code.edit", "file": "src/lib.rs",
+               "before_text": "fn missing() {}\n", "after_text": "fn other() {}\n"}),
+    ];
+    let transcript = edits
+        .iter()
+        .map(|edit| format!("{edit}\n"))
+        .collect::<String>();
+    let recorded = run_json(repo, &["record", "--stdin"], Some(&transcript));
+    let tape_id = recorded["tape_id"].as_str().expect("tape id");
+
+    let seed = |dir: &Path| {
+        fs::create_dir_all(dir.join("src")).expect("src dir");
+        fs::write(dir.join("src/lib.rs"), "fn keep() {}\nfn old() {}\n").expect("seed lib");
+    };
+    seed(&repo.join("scratch"));
+    let replay = run_json(repo, &["replay", tape_id, "--onto", "scratch"], None);
+    assert_eq!(replay["status"], "conflicts", "{replay:#}");
+    assert_eq!(replay["applied"].as_array().expect("applied").len(), 2);
+    assert_eq!(replay["conflicts"][0]["reason"], "before_text_not_found");
+    assert_eq!(
+        fs::read_to_string(repo.join("scratch/src/lib.rs")).expect("lib"),
+        "fn keep() {}\nfn new() {}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(repo.join("scratch/src/added.rs")).expect("added"),
+        "pub fn added() {}\n"
+    );
+
+    seed(&repo.join("partial"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(
+            repo.join("partial/src/lib.rs"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .expect("chmod");
+    }
+    let first = replay["applied"][0]["offset"].to_string();
+    let partial = run_json(
+        repo,
+        &["replay", tape_id, "--onto", "partial", "--through", &first],
+        None,
+    );
+    assert_eq!(partial["status"], "ok");
+    assert!(!repo.join("partial/src/added.rs").exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(repo.join("partial/src/lib.rs"))
+            .expect("replayed lib")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755, "replay kept the file's mode");
+    }
+
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .output()
+            .expect("git runs")
+            .status;
+        assert!(status.success(), "git {args:?}");
+    };
+    seed(repo);
+    git(&["init", "-q"]);
+    git(&["add", "src"]);
+    git(&["commit", "-qm", "seed"]);
+    let from_ref = run_json(repo, &["replay", tape_id, "--onto", "HEAD"], None);
+    assert_eq!(from_ref["created_worktree"], true);
+    let worktree = Path::new(from_ref["worktree"].as_str().expect("worktree"));
+    assert!(worktree.starts_with(repo.join(".engram/replay")));
+    assert_eq!(
+        fs::read_to_string(worktree.join("src/lib.rs")).expect("worktree lib"),
+        "fn keep() {}\nfn new() {}\n"
+    );
+}
+
+#[test]
+fn bisect_ranks_sessions_that_edited_the_span_between_good_and_bad() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    let _ = run_json(repo, &["init"], None);
+
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let ancestor = "winnow:00000000000000ee";
+    let edit = |t: &str, before: &str, after: &str| {
+        let event = json!({
+            "t": t,
+            "k": "code.edit",
+            "file": "src/lib.rs",
+            "before_range": [2, 2],
+            "after_range": [2, 2],
+            "before_anchor_hashes": [before],
+            "after_anchor_hashes": [after],
+            "similarity": 0.95,
+        });
+        run_json(repo, &["record", "--stdin"], Some(&format!("{event}\n")))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let _too_early = edit(
+        "2026-02-10T00:00:00Z",
+        "winnow:00000000000000aa",
+        &span_anchor,
+    );
+    let ancestor_edit = edit("2026-02-20T00:00:00Z", "winnow:00000000000000dd", ancestor);
+    let span_edit = edit("2026-02-22T00:00:00Z", ancestor, &span_anchor);
+
+    let bisect = run_json(
+        repo,
+        &[
+            "bisect",
+            "src/lib.rs:2-2",
+            "--good",
+            "2026-02-15",
+            "--bad",
+            "2026-02-25T00:00:00Z",
+        ],
+        None,
+    );
+    let sessions = bisect["sessions"].as_array().expect("sessions");
+    assert_eq!(sessions.len(), 2, "{bisect:#}");
+    assert_eq!(sessions[0]["tape_id"], span_edit.as_str());
+    assert_eq!(sessions[0]["direct_edits"], 1);
+    assert_eq!(sessions[1]["tape_id"], ancestor_edit.as_str());
+    assert_eq!(sessions[1]["lineage_edits"], 1);
+    let likelihood = sessions[0]["likelihood"].as_f64().expect("likelihood");
+    assert!((likelihood - 2.0 / 3.0).abs() < 1e-6);
+
+    let inverted = run_cli(
+        repo,
+        &[
+            "bisect",
+            "src/lib.rs:2-2",
+            "--good",
+            "2026-02-25",
+            "--bad",
+            "2026-02-15",
+        ],
+        None,
+    );
+    assert!(!inverted.status.success());
+}
+
+#[test]
+fn explain_at_reads_the_index_and_the_target_as_they_were_then() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let old_span = "fn omega() { return value + 1; }";
+    let new_span = "fn omega() { return value * 2 + offset; }";
+    let _ = run_json(repo, &["init"], None);
+    let commit = |span: &str, at: &str| {
+        fs::write(repo.join("src/lib.rs"), format!("alpha\n{span}\nzeta\n")).expect("write");
+        for args in [&["add", "src"][..], &["commit", "-qm", span][..]] {
+            let output = Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", at)
+                .env("GIT_COMMITTER_DATE", at)
+                .current_dir(repo)
+                .output()
+                .expect("git runs");
+            assert!(output.status.success(), "git {args:?}");
+        }
+    };
+    let edit = |t: &str, before: &str, after: &str| {
+        let event = json!({
+            "t": t,
+            "k": "code.edit",
+            "file": "src/lib.rs",
+            "before_range": [2, 2],
+            "after_range": [2, 2],
+            "before_anchor_hashes": [before],
+            "after_anchor_hashes": [after],
+            "similarity": 0.95,
+        });
+        run_json(repo, &["record", "--stdin"], Some(&format!("{event}\n")))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let old_anchor = fingerprint_text(old_span).fingerprint;
+    let new_anchor = fingerprint_text(new_span).fingerprint;
+    let status = Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(repo)
+        .status()
+        .expect("git init");
+    assert!(status.success());
+    commit(old_span, "2026-02-15T00:00:00Z");
+    let first = String::from_utf8(
+        Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(repo)
+            .output()
+            .expect("rev-parse")
+            .stdout,
+    )
+    .expect("utf8")
+    .trim()
+    .to_string();
+    commit(new_span, "2026-03-01T00:00:00Z");
+    let introduced = edit(
+        "2026-02-10T00:00:00Z",
+        "winnow:00000000000000aa",
+        &old_anchor,
+    );
+    let rewritten = edit("2026-02-25T00:00:00Z", &old_anchor, &new_anchor);
+
+    let now = run_json(repo, &["explain", "src/lib.rs:2-2"], None);
+    let sessions = now["sessions"].as_array().expect("sessions");
+    assert!(
+        sessions
+            .iter()
+            .any(|session| session["session_id"] == rewritten.as_str()),
+        "{now:#}"
+    );
+
+    for at in [first.as_str(), "2026-02-20"] {
+        let then = run_json(repo, &["explain", "src/lib.rs:2-2", "--at", at], None);
+        assert_eq!(then["query"]["at"]["commit"], first.as_str(), "{then:#}");
+        let sessions = then["sessions"].as_array().expect("sessions");
+        assert_eq!(sessions.len(), 1, "{then:#}");
+        assert_eq!(sessions[0]["session_id"], introduced.as_str());
+    }
+    assert_eq!(
+        run_json(repo, &["explain", "src/lib.rs:2-2", "--at", &first], None)["query"]["at"]["cutoff"],
+        "2026-02-15T00:00:00Z"
+    );
+
+    let invalid = run_cli(repo, &["explain", "src/lib.rs:2-2", "--at", "nope"], None);
+    assert!(!invalid.status.success());
+}
+
+#[test]
+fn explain_from_git_reads_a_target_missing_from_a_sparse_checkout() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span = "fn omega() { return value + 1; }";
+    fs::write(repo.join("src/lib.rs"), format!("alpha\n{span}\nzeta\n")).expect("write");
+    let _ = run_json(repo, &["init"], None);
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .output()
+            .expect("git runs");
+        assert!(output.status.success(), "git {args:?}: {output:?}");
+        String::from_utf8(output.stdout)
+            .expect("utf8")
+            .trim()
+            .to_string()
+    };
+    git(&["init", "-q"]);
+    git(&["add", "src"]);
+    git(&["commit", "-qm", "seed"]);
+    let head = git(&["rev-parse", "HEAD"]);
+    let event = json!({
+        "t": "2026-02-10T00:00:00Z",
+        "k": "code.edit",
+        "file": "src/lib.rs",
+        "before_range": [2, 2],
+        "after_range": [2, 2],
+        "before_anchor_hashes": ["winnow:00000000000000aa"],
+        "after_anchor_hashes": [fingerprint_text(span).fingerprint],
+        "similarity": 0.95,
+    });
+    let tape_id = run_json(repo, &["record", "--stdin"], Some(&format!("{event}\n")))["tape_id"]
+        .as_str()
+        .expect("tape id")
+        .to_string();
+    fs::remove_file(repo.join("src/lib.rs")).expect("sparse");
+
+    let missing = run_cli(repo, &["explain", "src/lib.rs:2-2", "--auto-span"], None);
+    assert!(!missing.status.success());
+    assert!(
+        String::from_utf8_lossy(&missing.stderr).contains("read_span_error"),
+        "{missing:?}"
+    );
+
+    let explain = run_json(repo, &["explain", "src/lib.rs:2-2", "--from-git"], None);
+    assert_eq!(explain["query"]["from_git"], head.as_str(), "{explain:#}");
+    let sessions = explain["sessions"].as_array().expect("sessions");
+    assert_eq!(sessions.len(), 1, "{explain:#}");
+    assert_eq!(sessions[0]["session_id"], tape_id.as_str());
+    assert!(!repo.join("src/lib.rs").exists());
+    let hunk = run_json(
+        repo,
+        &["explain", "src/lib.rs:2-2", "--auto-span", "--from-git"],
+        None,
+    );
+    assert_eq!(hunk["query"]["from_git"], head.as_str(), "{hunk:#}");
+
+    let untracked = run_cli(repo, &["explain", "src/gone.rs:1-1", "--from-git"], None);
+    assert!(!untracked.status.success());
+}
+
+#[test]
+fn explain_rev_and_staged_read_the_target_from_git() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let old_span = "fn omega() { return value + 1; }";
+    let new_span = "let ledger = accounts.iter().map(|entry| entry.balance).sum::<i64>();";
+    let _ = run_json(repo, &["init"], None);
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .output()
+            .expect("git runs");
+        assert!(output.status.success(), "git {args:?}: {output:?}");
+        String::from_utf8(output.stdout)
+            .expect("utf8")
+            .trim()
+            .to_string()
+    };
+    let write = |span: &str| {
+        fs::write(repo.join("src/lib.rs"), format!("alpha\n{span}\nzeta\n")).expect("write");
+    };
+    let record = |t: &str, before: &str, span: &str| {
+        let event = json!({
+            "t": t,
+            "k": "code.edit",
+            "file": "src/lib.rs",
+            "before_range": [2, 2],
+            "after_range": [2, 2],
+            "before_anchor_hashes": [before],
+            "after_anchor_hashes": [fingerprint_text(span).fingerprint],
+            "similarity": 0.95,
+        });
+        run_json(repo, &["record", "--stdin"], Some(&format!("{event}\n")))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    git(&["init", "-q"]);
+    write(old_span);
+    git(&["add", "src"]);
+    git(&["commit", "-qm", "old"]);
+    let first = git(&["rev-parse", "HEAD"]);
+    write(new_span);
+    git(&["commit", "-qam", "new"]);
+    let old_tape = record("2026-02-10T00:00:00Z", "winnow:00000000000000aa", old_span);
+    let new_tape = record("2026-02-20T00:00:00Z", "winnow:00000000000000bb", new_span);
+    write("fn omega() { todo!() }");
+
+    let sessions_for = |args: &[&str]| {
+        let explain = run_json(repo, args, None);
+        let ids = explain["sessions"]
+            .as_array()
+            .expect("sessions")
+            .iter()
+            .map(|session| session["session_id"].as_str().expect("id").to_string())
+            .collect::<Vec<_>>();
+        (explain, ids)
+    };
+    let (explain, ids) = sessions_for(&["explain", "src/lib.rs:2-2", "--rev", "HEAD~1"]);
+    assert_eq!(ids, vec![old_tape.clone()], "{explain:#}");
+    assert_eq!(explain["query"]["rev"]["commit"], first.as_str());
+    let (_, ids) = sessions_for(&["explain", "src/lib.rs:2-2", "--rev", "HEAD"]);
+    assert_eq!(ids, vec![new_tape]);
+
+    write(old_span);
+    git(&["add", "src/lib.rs"]);
+    write("fn omega() { todo!() }");
+    let (explain, ids) = sessions_for(&["explain", "src/lib.rs:2-2", "--staged"]);
+    assert_eq!(ids, vec![old_tape], "{explain:#}");
+    assert_eq!(explain["query"]["staged"], true);
+
+    let invalid = run_cli(repo, &["explain", "src/lib.rs:2-2", "--rev", "nope"], None);
+    assert!(!invalid.status.success());
+    assert!(String::from_utf8_lossy(&invalid.stderr).contains("invalid_rev"));
+}
+
+#[test]
+fn origin_reports_the_session_that_first_introduced_a_span() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    let _ = run_json(repo, &["init"], None);
+
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let ancestor = "winnow:00000000000000ee";
+    let record = |lines: Vec<Value>| {
+        let input = lines
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        run_json(repo, &["record", "--stdin"], Some(&input))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let edit = |t: &str, before: Option<&str>, after: &str| {
+        json!({
+            "t": t,
+            "k": "code.edit",
+            "file": "src/lib.rs",
+            "after_range": [2, 2],
+            "before_anchor_hashes": before.into_iter().collect::<Vec<_>>(),
+            "after_anchor_hashes": [after],
+            "similarity": 0.95,
+        })
+    };
+
+    let unrelated = run_json(repo, &["origin", "src/lib.rs:2-2"], None);
+    assert_eq!(unrelated["found"], false, "{unrelated:#}");
+
+    let introduced = record(vec![
+        json!({"t": "2026-02-20T00:00:00Z", "k": "meta", "model": "gpt-5", "repo_head": "abc123"}),
+        json!({"t": "2026-02-20T00:00:01Z", "k": "msg.in", "text": "add the omega helper"}),
+        json!({"t": "2026-02-20T00:00:02Z", "k": "code.read", "file": "src/lib.rs"}),
+        edit("2026-02-20T00:00:03Z", None, ancestor),
+    ]);
+    let _rewrote = record(vec![
+        json!({"t": "2026-02-22T00:00:00Z", "k": "msg.in", "text": "tweak omega"}),
+        edit("2026-02-22T00:00:01Z", Some(ancestor), &span_anchor),
+    ]);
+
+    let origin = run_json(repo, &["origin", "src/lib.rs:2-2"], None);
+    assert_eq!(origin["found"], true, "{origin:#}");
+    assert_eq!(origin["origin"]["tape_id"], introduced.as_str());
+    assert_eq!(origin["origin"]["kind"], "edit");
+    assert_eq!(origin["origin"]["offset"], 3);
+    assert_eq!(origin["origin"]["anchor"], ancestor);
+    assert_eq!(origin["origin"]["prompt"], "user: add the omega helper");
+    assert_eq!(origin["origin"]["model"], "gpt-5");
+    assert_eq!(origin["origin"]["repo_head"], "abc123");
+    assert_eq!(origin["chain"]["anchors"][0], ancestor);
+    assert_eq!(origin["chain"]["hops"], 1);
+}
+
+#[test]
+fn why_prints_one_sentence_about_the_last_change() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    let _ = run_json(repo, &["init"], None);
+    let why = || {
+        let output = run_cli(repo, &["why", "src/lib.rs:2", "--timezone", "utc"], None);
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).expect("utf8")
+    };
+    assert_eq!(
+        why(),
+        "No recorded session changed src/lib.rs:2 (lines 2-2).\n"
+    );
+    let porcelain = || {
+        let output = run_cli(repo, &["why", "src/lib.rs:2", "--porcelain"], None);
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).expect("utf8")
+    };
+    assert_eq!(porcelain(), "why\tsrc/lib.rs\t2\t2\n");
+
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let record = |t: &str, prompt: &str| {
+        let input = [
+            json!({"t": t, "k": "meta", "model": "gpt-5", "source": {"harness": "codex-cli"}}),
+            json!({"t": t, "k": "msg.in", "text": prompt}),
+            json!({
+                "t": t,
+                "k": "code.edit",
+                "file": "src/lib.rs",
+                "after_range": [2, 2],
+                "after_anchor_hashes": [span_anchor],
+            }),
+        ]
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+        run_json(repo, &["record", "--stdin"], Some(&input))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let _earlier = record("2026-02-10T12:00:00Z", "add omega");
+    let latest = record("2026-02-20T12:00:00Z", "make omega\n  return the successor");
+    assert_eq!(
+        why(),
+        format!(
+            "src/lib.rs:2 (lines 2-2) was last changed by session {latest} on 2026-02-20 \
+             (codex-cli/gpt-5): \"make omega return the successor\"\n"
+        )
+    );
+    assert_eq!(
+        porcelain(),
+        format!(
+            "why\tsrc/lib.rs\t2\t2\t{latest}\t2026-02-20T12:00:00Z\tcodex-cli\tgpt-5\t\
+             make omega return the successor\n"
+        )
+    );
+
+    let invalid = run_cli(repo, &["why", "src/lib.rs"], None);
+    assert!(!invalid.status.success());
+    assert!(String::from_utf8_lossy(&invalid.stderr).contains("invalid_why_target"));
+}
+
+#[test]
+fn lens_reports_touches_per_definition_for_editors() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let settle =
+        "fn settle(ledger: &mut Ledger) {\n    ledger.balance = ledger.entries.iter().sum();\n}";
+    let render = "fn render(frame: &Frame) -> String {\n    format!(\"{}x{}\", frame.width, frame.height)\n}";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("use std::fmt;\n\n{settle}\n\n{render}\n"),
+    )
+    .expect("seed file");
+    let _ = run_json(repo, &["init"], None);
+
+    let settle_anchor = fingerprint_text(settle).fingerprint;
+    let record = |t: &str, file: &str| {
+        let event = json!({
+            "t": t,
+            "k": "code.edit",
+            "file": file,
+            "after_range": [3, 5],
+            "after_anchor_hashes": [settle_anchor],
+        });
+        run_json(repo, &["record", "--stdin"], Some(&format!("{event}\n")))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let _first = record("2026-02-10T00:00:00Z", "src/lib.rs");
+    let latest = record(
+        "2026-02-20T00:00:00Z",
+        &repo.join("src/lib.rs").to_string_lossy(),
+    );
+    let _elsewhere = record("2026-02-25T00:00:00Z", "src/other.rs");
+
+    let lens = run_json(repo, &["lens", "src/lib.rs"], None);
+    assert_eq!(lens["file"], "src/lib.rs");
+    assert_eq!(lens["line_count"], 9);
+    let regions = lens["regions"].as_array().expect("regions");
+    let spans = regions
+        .iter()
+        .map(|region| {
+            (
+                region["start"].as_u64().expect("start"),
+                region["end"].as_u64().expect("end"),
+                region["kind"].as_str().expect("kind").to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        vec![
+            (1, 1, "chunk".to_string()),
+            (3, 5, "definition".to_string()),
+            (7, 9, "definition".to_string()),
+        ],
+        "{lens:#}"
+    );
+    let settled = &regions[1];
+    assert_eq!(settled["label"], "fn settle(ledger: &mut Ledger)");
+    assert_eq!(settled["touch_count"], 2, "{lens:#}");
+    assert_eq!(settled["session_count"], 2);
+    assert_eq!(settled["last_session"]["tape_id"], latest.as_str());
+    assert_eq!(settled["last_session"]["kind"], "edit");
+    assert!(
+        settled["last_session"]["confidence"]
+            .as_f64()
+            .expect("confidence")
+            > 0.0
+    );
+    assert_eq!(regions[2]["touch_count"], 0);
+    assert!(regions[2]["last_session"].is_null());
+
+    let output = run_cli(repo, &["lens", "src/lib.rs", "--porcelain=v1"], None);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).expect("utf8");
+    let records = stdout
+        .lines()
+        .map(|line| line.split('\t').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(records.len(), 3, "{stdout}");
+    assert!(records.iter().all(|record| record.len() == 12));
+    assert_eq!(
+        records[1][..10],
+        [
+            "region",
+            "src/lib.rs",
+            "3",
+            "5",
+            "definition",
+            "2",
+            "2",
+            latest.as_str(),
+            "2026-02-20T00:00:00Z",
+            "edit",
+        ]
+    );
+    assert_eq!(records[1][11], "fn settle(ledger: &mut Ledger)");
+    assert_eq!(
+        records[0],
+        [
+            "region",
+            "src/lib.rs",
+            "1",
+            "1",
+            "chunk",
+            "0",
+            "0",
+            "",
+            "",
+            "",
+            "",
+            ""
+        ]
+    );
+}
+
+#[test]
+fn explain_exclusion_filters_drop_noisy_sources() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    let _ = run_json(repo, &["init"], None);
+
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let record = |harness: &str, file: &str, t: &str| {
+        let input = format!(
+            "{}\n{}\n",
+            json!({"t": t, "k": "meta", "source": {"harness": harness}}),
+            json!({
+                "t": t,
+                "k": "code.edit",
+                "file": file,
+                "after_range": [2, 2],
+                "after_anchor_hashes": [span_anchor],
+            }),
+        );
+        run_json(repo, &["record", "--stdin"], Some(&input))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let claude = record("claude-code", "src/lib.rs", "2026-02-20T00:00:00Z");
+    let gemini = record("gemini-cli", "src/lib.rs", "2026-02-21T00:00:00Z");
+    let vendored = record("codex-cli", "vendor/dep/lib.rs", "2026-02-22T00:00:00Z");
+    let session_ids = |explain: &Value| {
+        let mut ids = explain["sessions"]
+            .as_array()
+            .expect("sessions")
+            .iter()
+            .map(|session| session["session_id"].as_str().expect("id").to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+    let sorted = |mut ids: Vec<String>| {
+        ids.sort();
+        ids
+    };
+
+    let all = run_json(repo, &["explain", "src/lib.rs:2-2"], None);
+    assert_eq!(
+        session_ids(&all),
+        sorted(vec![claude.clone(), gemini.clone(), vendored.clone()])
+    );
+
+    let filtered = run_json(
+        repo,
+        &[
+            "explain",
+            "src/lib.rs:2-2",
+            "--exclude-harness",
+            "gemini-cli",
+            "--exclude-path",
+            "vendor",
+        ],
+        None,
+    );
+    assert_eq!(session_ids(&filtered), vec![claude.clone()]);
+    assert_eq!(filtered["query"]["exclude_harness"], json!(["gemini-cli"]));
+
+    let by_tape = run_json(
+        repo,
+        &[
+            "explain",
+            "src/lib.rs:2-2",
+            "--exclude-tape",
+            &claude[..12],
+            "--exclude-path",
+            "vendor/**/*.rs",
+        ],
+        None,
+    );
+    assert_eq!(session_ids(&by_tape), vec![gemini]);
+}
+
+#[test]
+fn events_filters_the_catalog_across_tapes() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let record = |lines: Vec<Value>| {
+        let input = lines
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        run_json(repo, &["record", "--stdin"], Some(&input))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let first = record(vec![
+        json!({"t": "2026-02-20T00:00:00Z", "k": "tool.call", "tool": "bash", "args": "cargo test"}),
+        json!({"t": "2026-02-20T00:00:01Z", "k": "code.edit", "file": "src/lib.rs", "after_text": "x"}),
+    ]);
+    let second = record(vec![
+        json!({"t": "2026-02-21T00:00:00Z", "k": "tool.call", "tool": "bash", "args": "ls -la"}),
+        json!({"t": "2026-02-21T00:00:01Z", "k": "tool.call", "tool": "read_file", "args": "x"}),
+        json!({"t": "2026-02-21T00:00:02Z", "k": "code.read", "file": "docs/guide.md"}),
+    ]);
+
+    let bash = run_json(
+        repo,
+        &["events", "--kind", "tool.call", "--tool", "bash"],
+        None,
+    );
+    assert_eq!(bash["total"], 2, "{bash:#}");
+    assert_eq!(bash["events"][0]["tape_id"], first.as_str());
+    assert_eq!(bash["events"][1]["tape_id"], second.as_str());
+    assert_eq!(bash["events"][1]["offset"], 0);
+
+    let grep = run_json(repo, &["events", "--tool", "bash", "--grep", "cargo"], None);
+    assert_eq!(grep["total"], 1);
+    assert_eq!(grep["events"][0]["tape_id"], first.as_str());
+
+    let files = run_json(repo, &["events", "--file", "src/*"], None);
+    assert_eq!(files["total"], 1);
+    assert_eq!(files["events"][0]["kind"], "code.edit");
+
+    let recent = run_json(
+        repo,
+        &["events", "--since", "2026-02-21", "--limit", "2"],
+        None,
+    );
+    assert_eq!(recent["total"], 3);
+    assert_eq!(recent["returned"], 2);
+    assert_eq!(recent["truncated"], true);
+}
+
+#[test]
+fn drift_reports_edits_no_longer_on_disk() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let record = |lines: Vec<Value>| {
+        let input = lines
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        run_json(repo, &["record", "--stdin"], Some(&input))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let old = "fn total() { legacy_sum(items) }";
+    let current = "fn total() { return compute_total(&items); }";
+    let helper = "fn helper() { return format_output(value); }";
+    let _ = record(vec![
+        json!({"t": "2026-05-01T00:00:00Z", "k": "code.edit", "file": "src/a.rs", "after_text": old}),
+    ]);
+    let latest = record(vec![
+        json!({"t": "2026-05-02T00:00:00Z", "k": "code.edit", "file": "src/a.rs", "before_text": old, "after_text": current}),
+        json!({"t": "2026-05-02T00:00:01Z", "k": "code.edit", "file": "src/b.rs", "after_text": helper}),
+        json!({"t": "2026-05-02T00:00:02Z", "k": "code.edit", "file": "src/c.rs", "after_text": "fn gone() {}"}),
+    ]);
+    fs::create_dir_all(repo.join("src")).expect("src");
+    fs::write(repo.join("src/a.rs"), format!("// header\n{current}\n")).expect("a.rs");
+    fs::write(repo.join("src/b.rs"), "fn helper() { 0 }\n").expect("b.rs");
+
+    let drift = run_json(repo, &["drift"], None);
+    assert_eq!(drift["files_checked"], 3, "{drift:#}");
+    assert_eq!(drift["edits_checked"], 3);
+    assert_eq!(drift["drifted"], 2);
+    let edits = drift["edits"].as_array().expect("edits");
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[0]["file"], "src/b.rs");
+    assert_eq!(edits[0]["tape_id"], latest.as_str());
+    assert_eq!(edits[0]["offset"], 1);
+    assert_ne!(edits[0]["state"], "intact");
+    assert_eq!(edits[1]["file"], "src/c.rs");
+    assert_eq!(edits[1]["state"], "file_deleted");
+
+    let a = run_json(repo, &["drift", "src/a.rs", "--all"], None);
+    assert_eq!(a["drifted"], 0, "{a:#}");
+    assert_eq!(a["edits"][0]["state"], "intact");
+    assert_eq!(a["edits"][0]["offset"], 0);
+}
+
+#[test]
+fn prompt_log_lists_user_prompts_with_the_files_they_touched() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let record = |lines: Vec<Value>| {
+        let input = lines
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        run_json(repo, &["record", "--stdin"], Some(&input))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let later = record(vec![
+        json!({"t": "2026-04-02T09:00:00Z", "k": "msg.in", "text": "Document the retry policy"}),
+        json!({"t": "2026-04-02T09:00:05Z", "k": "code.edit", "file": "docs/retry.md", "after_text": "x"}),
+    ]);
+    let earlier = record(vec![
+        json!({"t": "2026-04-01T09:00:00Z", "k": "msg.in", "text": "Why does the client retry?"}),
+        json!({"t": "2026-04-01T09:00:05Z", "k": "code.read", "file": "src/client.rs"}),
+        json!({"t": "2026-04-01T09:01:00Z", "k": "msg.in", "text": "Cap retries at 3"}),
+        json!({"t": "2026-04-01T09:01:05Z", "k": "code.edit", "file": "src/client.rs", "after_text": "y"}),
+        json!({"t": "2026-04-01T09:01:06Z", "k": "code.edit", "file": "src/config.rs", "after_text": "z"}),
+    ]);
+
+    let log = run_json(repo, &["prompt-log"], None);
+    assert_eq!(log["total"], 3, "{log:#}");
+    let prompts = log["prompts"].as_array().expect("prompts");
+    assert_eq!(prompts[0]["text"], "Why does the client retry?");
+    assert_eq!(prompts[0]["tape_id"], earlier.as_str());
+    assert_eq!(prompts[1]["offset"], 2);
+    assert_eq!(
+        prompts[1]["files"],
+        json!(["src/client.rs", "src/config.rs"])
+    );
+    assert_eq!(prompts[2]["tape_id"], later.as_str());
+
+    let config = run_json(repo, &["prompt-log", "--file", "src/config.rs"], None);
+    assert_eq!(config["total"], 1);
+    assert_eq!(config["prompts"][0]["text"], "Cap retries at 3");
+
+    let recent = run_json(repo, &["prompt-log", "--since", "2026-04-02"], None);
+    assert_eq!(recent["total"], 1);
+    assert_eq!(recent["prompts"][0]["text"], "Document the retry policy");
+}
+
+#[test]
+fn tools_reports_calls_failures_and_result_sizes() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let record = |lines: Vec<Value>| {
+        let input = lines
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        let _ = run_json(repo, &["record", "--stdin"], Some(&input));
+    };
+    record(vec![
+        json!({"t": "2026-03-01T00:00:00Z", "k": "meta", "source": {"harness": "codex-cli"}}),
+        json!({"t": "2026-03-01T00:00:01Z", "k": "tool.call", "tool": "bash", "args": "cargo test"}),
+        json!({"t": "2026-03-01T00:00:02Z", "k": "tool.result", "tool": "bash", "exit": 101, "stdout": "", "stderr": "failed"}),
+        json!({"t": "2026-03-01T00:00:03Z", "k": "tool.call", "tool": "bash", "args": "ls"}),
+        json!({"t": "2026-03-01T00:00:04Z", "k": "tool.result", "tool": "bash", "exit": 0, "stdout": "a\nb\n", "stderr": ""}),
+    ]);
+    record(vec![
+        json!({"t": "2026-03-05T00:00:00Z", "k": "tool.call", "tool": "read_file", "args": "x"}),
+        json!({"t": "2026-03-05T00:00:01Z", "k": "tool.result", "tool": "read_file", "stdout": "0123456789"}),
+    ]);
+
+    let all = run_json(repo, &["tools"], None);
+    assert_eq!(all["tapes"], 2, "{all:#}");
+    let bash = &all["tools"][0];
+    assert_eq!(bash["tool"], "bash");
+    assert_eq!(bash["calls"], 2);
+    assert_eq!(bash["errors"], 1);
+    assert_eq!(bash["error_rate"], 0.5);
+    assert_eq!(bash["median_result_bytes"], 5);
+    assert_eq!(all["tools"][1]["tool"], "read_file");
+    assert_eq!(all["tools"][1]["median_result_bytes"], 10);
+    let harnesses = all["harnesses"].as_array().expect("harnesses");
+    assert_eq!(harnesses.len(), 2);
+    assert_eq!(harnesses[0]["harness"], "codex-cli");
+    assert_eq!(harnesses[0]["tools"][0]["tool"], "bash");
+
+    let recent = run_json(repo, &["tools", "--since", "2026-03-02"], None);
+    assert_eq!(recent["tapes"], 1);
+    assert_eq!(recent["tools"].as_array().map(Vec::len), Some(1));
+    assert_eq!(recent["tools"][0]["tool"], "read_file");
+    assert_eq!(recent["tools"][0]["errors"], 0);
+}
+
+#[test]
+fn explain_auto_span_widens_a_line_to_its_chunk() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let file = (0..120)
+        .map(|n| format!("fn step_{n}(input: u32) -> u32 {{ input * {n} + offset_{n} }}\n"))
+        .collect::<String>();
+    let edited = file
+        .lines()
+        .skip(40)
+        .take(40)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let input = format!(
+        "{}\n",
+        json!({"t": "2026-05-01T00:00:00Z", "k": "code.edit", "file": "src/steps.rs", "after_text": edited})
+    );
+    let _ = run_json(repo, &["record", "--stdin"], Some(&input));
+    fs::create_dir_all(repo.join("src")).expect("src");
+    fs::write(repo.join("src/steps.rs"), &file).expect("steps.rs");
+
+    let explain = run_json(repo, &["explain", "src/steps.rs:60", "--auto-span"], None);
+    let span = &explain["query"]["resolved_span"];
+    assert_eq!(explain["query"]["granularity"], "hunk");
+    assert_eq!(span["requested"]["start"], 60, "{explain:#}");
+    let (start, end) = (
+        span["start"].as_u64().expect("start"),
+        span["end"].as_u64().expect("end"),
+    );
+    assert!(start <= 60 && 60 <= end && end > start, "{span}");
+    assert!(!explain["sessions"].as_array().expect("sessions").is_empty());
+
+    let past_end = run_cli(repo, &["explain", "src/steps.rs:500", "--auto-span"], None);
+    assert!(!past_end.status.success());
+}
+
+#[test]
+fn explain_whole_file_summarizes_history_per_region() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let file = (0..120)
+        .map(|n| format!("const LIMIT_{n}: u64 = {};\n", n * 37 + 11))
+        .collect::<String>();
+    let record = |lines: std::ops::Range<usize>| {
+        let after_text = file
+            .lines()
+            .skip(lines.start)
+            .take(lines.len())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let input = format!(
+            "{}\n",
+            json!({"t": "2026-05-01T00:00:00Z", "k": "code.edit", "file": "src/steps.rs", "after_text": after_text})
+        );
+        run_json(repo, &["record", "--stdin"], Some(&input))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let top = record(0..30);
+    let bottom = record(80..120);
+    fs::create_dir_all(repo.join("src")).expect("src");
+    fs::write(repo.join("src/steps.rs"), &file).expect("steps.rs");
+
+    let explain = run_json(repo, &["explain", "src/steps.rs"], None);
+    let regions = explain["regions"].as_array().expect("regions");
+    let sessions_at = |line: u64| {
+        let region = regions
+            .iter()
+            .find(|region| {
+                region["start"].as_u64() <= Some(line) && Some(line) <= region["end"].as_u64()
+            })
+            .unwrap_or_else(|| panic!("no region at {line}: {explain:#}"));
+        region["sessions"]
+            .as_array()
+            .expect("sessions")
+            .iter()
+            .map(|session| session["session_id"].as_str().expect("id").to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sessions_at(5), vec![top]);
+    assert_eq!(sessions_at(110), vec![bottom]);
+    assert!(
+        regions
+            .windows(2)
+            .all(|pair| pair[0]["end"].as_u64() < pair[1]["start"].as_u64())
+    );
+}
+
+#[test]
+fn explain_granularity_picks_the_enclosing_function_or_whole_file() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let function = (0..12)
+        .map(|n| {
+            format!(
+                "        let limit_{n} = budget.share({n}, {});\n",
+                n * 37 + 11
+            )
+        })
+        .collect::<String>();
+    let file = format!(
+        "use std::fmt;\n\nimpl Budget {{\n    fn plan(&self) -> Plan {{\n{function}        Plan::default()\n    }}\n}}\n"
+    );
+    let input = format!(
+        "{}\n",
+        json!({"t": "2026-05-01T00:00:00Z", "k": "code.edit", "file": "src/budget.rs", "after_text": function})
+    );
+    let _ = run_json(repo, &["record", "--stdin"], Some(&input));
+    fs::create_dir_all(repo.join("src")).expect("src");
+    fs::write(repo.join("src/budget.rs"), &file).expect("budget.rs");
+
+    let explain = run_json(
+        repo,
+        &["explain", "src/budget.rs:9", "--granularity", "function"],
+        None,
+    );
+    let span = &explain["query"]["resolved_span"];
+    assert_eq!(explain["query"]["granularity"], "function");
+    assert_eq!(
+        (span["start"].clone(), span["end"].clone()),
+        (json!(4), json!(18))
+    );
+    assert_eq!(span["definition"], true, "{explain:#}");
+    assert!(!explain["sessions"].as_array().expect("sessions").is_empty());
+
+    let explain = run_json(
+        repo,
+        &["explain", "src/budget.rs:9", "--granularity", "file"],
+        None,
+    );
+    assert_eq!(explain["query"]["resolved_span"]["end"], 19);
+    assert!(explain["regions"].is_array(), "{explain:#}");
+
+    let line = run_json(
+        repo,
+        &["explain", "src/budget.rs:9", "--granularity", "line"],
+        None,
+    );
+    assert_eq!(line["query"]["resolved_span"]["start"], 9);
+    assert_eq!(line["query"]["resolved_span"]["end"], 9);
+}
+
+#[test]
+fn explain_caches_working_tree_fingerprints_until_the_file_changes() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let _ = run_json(repo, &["init"], None);
+    let text = (0..6)
+        .map(|n| format!("const LIMIT_{n}: u64 = {};\n", n * 37 + 11))
+        .collect::<String>();
+    let input = format!(
+        "{}\n",
+        json!({"t": "2026-05-01T00:00:00Z", "k": "code.edit", "file": "src/limits.rs", "after_text": text})
+    );
+    let _ = run_json(repo, &["record", "--stdin"], Some(&input));
+    fs::create_dir_all(repo.join("src")).expect("src");
+    fs::write(repo.join("src/limits.rs"), &text).expect("limits.rs");
+
+    let first = run_json(repo, &["explain", "src/limits.rs:1-6"], None);
+    let cache = repo.join(".home/.engram-cache/fingerprints.sqlite");
+    let cached = Connection::open(&cache)
+        .expect("cache")
+        .query_row("SELECT COUNT(*) FROM cached_anchors", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .expect("count");
+    assert_eq!(cached, 1);
+    let second = run_json(repo, &["explain", "src/limits.rs:1-6"], None);
+    assert_eq!(first["query"]["anchors"], second["query"]["anchors"]);
+
+    fs::write(
+        repo.join("src/limits.rs"),
+        "fn unrelated() {}\nfn other() {}\nfn more() {}\nfn and() {}\nfn so() {}\nfn on() {}\n",
+    )
+    .expect("rewrite");
+    let changed = run_cli(repo, &["explain", "src/limits.rs:1-6"], None);
+    assert!(!changed.status.success(), "stale anchors were reused");
+}
+
+#[test]
+fn export_graph_writes_a_snapshot_that_explains_like_the_index() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+    let transcript = concat!(
+        "{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"code.read\",\"file\":\"src/lib.rs\",\"range\":[1,3],\"anchor_hashes\":[\"winnow:00000000000000aa\"]}\n",
+        "{\"t\":\"2026-02-22T00:00:01Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",\"before_range\":[1,3],\"after_range\":[1,4],\"before_anchor_hashes\":[\"winnow:00000000000000aa\"],\"after_anchor_hashes\":[\"winnow:00000000000000bb\"],\"similarity\":0.9}\n"
+    );
+    run_json(repo, &["record", "--stdin"], Some(transcript));
+
+    let export = run_json(repo, &["export", "--graph", "graph.bin"], None);
+    assert_eq!(export["format_version"], 1);
+    assert_eq!(export["anchors"], 2);
+    assert_eq!(export["edges"], 1);
+    assert_eq!(export["evidence"], 3);
+
+    let bytes = fs::read(repo.join("graph.bin")).expect("snapshot");
+    assert_eq!(export["bytes"], bytes.len());
+    let graph = engram::query::snapshot::decode_graph_snapshot(&bytes).expect("decode");
+    let Ok(result) = engram::query::explain_by_anchor(
+        &graph,
+        &["winnow:00000000000000bb".to_string()],
+        engram::query::ExplainTraversal::default(),
+        false,
+    );
+    assert_eq!(result.direct.len(), 1);
+    assert_eq!(result.lineage.len(), 1);
+    assert_eq!(result.lineage[0].from_anchor, "winnow:00000000000000aa");
+}
+
+#[test]
+fn export_graph_as_cypher_creates_nodes_and_relationships() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+    let transcript = concat!(
+        "{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"meta\",\"model\":\"gpt-5\"}\n",
+        "{\"t\":\"2026-02-22T00:00:01Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",\"before_range\":[1,3],\"after_range\":[1,4],\"before_anchor_hashes\":[\"winnow:00000000000000aa\"],\"after_anchor_hashes\":[\"winnow:00000000000000bb\"],\"similarity\":0.9}\n"
+    );
+    let record = run_json(repo, &["record", "--stdin"], Some(transcript));
+    let tape_id = record["tape_id"].as_str().expect("tape id");
+
+    let export = run_json(
+        repo,
+        &["export", "--graph", "graph.cypher", "--format", "cypher"],
+        None,
+    );
+    assert_eq!(export["format"], "cypher");
+    assert_eq!(export["format_version"], Value::Null);
+    let script = fs::read_to_string(repo.join("graph.cypher")).expect("script");
+    assert!(script.contains("CREATE (:Anchor {id: \"winnow:00000000000000aa\"});"));
+    assert!(script.contains(&format!("CREATE (:Tape {{id: \"{tape_id}\"")));
+    assert!(script.contains("model: \"gpt-5\""));
+    assert_eq!(script.matches("-[:TOUCHED").count(), 2);
+    assert_eq!(script.matches("-[:LINEAGE").count(), 1);
+}
+
+#[test]
+fn explain_format_mermaid_draws_lineage_and_sessions() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    run_json(repo, &["init"], None);
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let transcript = format!(
+        "{{\"t\":\"2026-02-22T00:00:02Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",\"before_range\":[2,2],\"after_range\":[2,2],\"before_anchor_hashes\":[\"winnow:00000000000000aa\"],\"after_anchor_hashes\":[\"{span_anchor}\"],\"similarity\":0.9}}\n"
+    );
+    let record = run_json(repo, &["record", "--stdin"], Some(&transcript));
+    let tape_id = record["tape_id"].as_str().expect("tape id");
+
+    let output = run_cli(
+        repo,
+        &["explain", "src/lib.rs:2-2", "--format", "mermaid"],
+        None,
+    );
+    assert!(output.status.success(), "explain --format mermaid fails");
+    let chart = String::from_utf8_lossy(&output.stdout);
+    let lines = chart.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "flowchart LR");
+    assert_eq!(lines[1], "    target[\"src/lib.rs:2-2\"]");
+    assert!(lines.contains(&"    a0[\"winnow:…000000aa\"]"), "{chart}");
+    assert!(
+        lines.iter().any(|line| line.starts_with("    a0 -- \"0.")),
+        "{chart}"
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with(&format!("    s0([\"{}", &tape_id[..12]))),
+        "{chart}"
+    );
+    assert!(lines.contains(&"    s0 -.-> target"), "{chart}");
+}
+
+#[test]
+fn explain_flags_evidence_from_partial_coverage_adapters() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    run_json(repo, &["init"], None);
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let edit = format!(
+        "{{\"t\":\"2026-02-22T00:00:02Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",\"before_range\":[2,2],\"after_range\":[2,2],\"before_anchor_hashes\":[\"winnow:00000000000000aa\"],\"after_anchor_hashes\":[\"{span_anchor}\"],\"similarity\":0.9}}\n"
+    );
+    let partial = format!(
+        "{{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"meta\",\"coverage.read\":\"partial\",\"coverage.edit\":\"partial\",\"coverage.tool\":\"full\"}}\n{edit}"
+    );
+    run_json(repo, &["record", "--stdin"], Some(&partial));
+
+    let explained = run_json(repo, &["explain", "src/lib.rs:2-2"], None);
+    let session = &explained["sessions"][0];
+    assert_eq!(session["coverage"]["edit"], "partial");
+    assert_eq!(session["coverage"]["tool"], "full");
+    assert_eq!(session["low_trust"], true);
+    assert_eq!(explained["lineage"][0]["low_trust"], true, "{explained}");
+
+    // A second session with no declared gaps backs the same edit.
+    run_json(repo, &["record", "--stdin"], Some(&edit));
+    let explained = run_json(repo, &["explain", "src/lib.rs:2-2"], None);
+    assert_eq!(explained["lineage"][0]["low_trust"], false, "{explained}");
+    let sessions = explained["sessions"].as_array().expect("sessions");
+    assert_eq!(sessions.len(), 2);
+    assert!(
+        sessions
+            .iter()
+            .any(|session| session.get("coverage").is_none())
+    );
+}
+
+#[test]
+fn explain_groups_sessions_spawned_by_another_harness() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    run_json(repo, &["init"], None);
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let child = format!(
+        "{{\"t\":\"2026-03-01T10:00:04Z\",\"k\":\"meta\",\"cwd\":\"/work/app/api\",\"source\":{{\"harness\":\"codex-cli\"}}}}\n\
+         {{\"t\":\"2026-03-01T10:00:20Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",\"before_range\":[2,2],\"after_range\":[2,2],\"before_anchor_hashes\":[\"winnow:00000000000000aa\"],\"after_anchor_hashes\":[\"{span_anchor}\"],\"similarity\":0.9}}\n"
+    );
+    let parent = "{\"t\":\"2026-03-01T09:59:00Z\",\"k\":\"meta\",\"cwd\":\"/work/app\",\"source\":{\"harness\":\"claude-code\"}}\n\
+         {\"t\":\"2026-03-01T10:00:02Z\",\"k\":\"tool.call\",\"tool\":\"Bash\",\"call_id\":\"c1\",\"args\":\"{\\\"command\\\":\\\"cd api && codex exec 'fix omega'\\\"}\"}\n\
+         {\"t\":\"2026-03-01T10:00:30Z\",\"k\":\"tool.result\",\"tool\":\"Bash\",\"call_id\":\"c1\",\"exit\":0}\n";
+    let child_id = run_json(repo, &["record", "--stdin"], Some(&child))["tape_id"]
+        .as_str()
+        .expect("child tape")
+        .to_string();
+    let parent_id = run_json(repo, &["record", "--stdin"], Some(parent))["tape_id"]
+        .as_str()
+        .expect("parent tape")
+        .to_string();
+
+    let explained = run_json(repo, &["explain", "src/lib.rs:2-2"], None);
+    assert_eq!(
+        explained["spawned"].as_array().map(Vec::len),
+        Some(1),
+        "{explained}"
+    );
+    assert_eq!(explained["spawned"][0]["session"], child_id.as_str());
+    assert_eq!(
+        explained["spawned"][0]["parent_session"],
+        parent_id.as_str()
+    );
+    assert_eq!(explained["spawned"][0]["parent_event_offset"], 1);
+    let sessions = explained["sessions"].as_array().expect("sessions");
+    let child_session = sessions
+        .iter()
+        .find(|session| session["session_id"] == child_id.as_str())
+        .expect("child session");
+    assert_eq!(child_session["parent"], parent_id.as_str());
+    assert_eq!(
+        child_session["spawned_by"]["command"],
+        "cd api && codex exec 'fix omega'"
+    );
+    let parent_session = sessions
+        .iter()
+        .find(|session| session["session_id"] == parent_id.as_str())
+        .expect("spawning session is returned");
+    assert_eq!(parent_session["children"], json!([child_id]));
+}
+
+#[test]
+fn sessions_graph_links_continuations_spawns_and_shared_files() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+    let record = |transcript: &str| {
+        run_json(repo, &["record", "--stdin"], Some(transcript))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let first = record(
+        "{\"t\":\"2026-03-01T10:00:00Z\",\"k\":\"meta\",\"source\":{\"harness\":\"claude-code\",\"session_id\":\"sess-1\"}}\n\
+         {\"t\":\"2026-03-01T10:00:02Z\",\"k\":\"tool.call\",\"tool\":\"Bash\",\"args\":\"codex exec 'fix it'\"}\n",
+    );
+    let child = record(
+        "{\"t\":\"2026-03-01T10:00:05Z\",\"k\":\"meta\",\"source\":{\"harness\":\"codex-cli\"}}\n\
+         {\"t\":\"2026-03-01T10:00:06Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",\"before_text\":\"a\",\"after_text\":\"b\"}\n",
+    );
+    let second = record(
+        "{\"t\":\"2026-03-01T11:00:00Z\",\"k\":\"meta\",\"source\":{\"harness\":\"claude-code\",\"session_id\":\"sess-1\"}}\n\
+         {\"t\":\"2026-03-01T11:00:01Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",\"before_text\":\"b\",\"after_text\":\"c\"}\n",
+    );
+
+    let graph = run_json(repo, &["sessions"], None);
+    let ids = graph["sessions"]
+        .as_array()
+        .expect("sessions")
+        .iter()
+        .map(|node| node["tape_id"].as_str().expect("tape id").to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [first.clone(), child.clone(), second.clone()]);
+    let links = graph["links"].as_array().expect("links");
+    let has = |from: &str, to: &str, kind: &str| {
+        links.iter().any(|link| {
+            link["kind"] == kind
+                && ((link["from"] == from && link["to"] == to)
+                    || (kind == "shared_files" && link["from"] == to && link["to"] == from))
+        })
+    };
+    assert!(has(&first, &second, "continues"), "{graph}");
+    assert!(has(&first, &child, "spawned"), "{graph}");
+    assert!(has(&child, &second, "shared_files"), "{graph}");
+    assert_eq!(links.len(), 3, "{graph}");
+
+    let output = run_cli(repo, &["sessions", "--graph", "--format", "mermaid"], None);
+    assert!(output.status.success(), "sessions --graph fails");
+    let chart = String::from_utf8_lossy(&output.stdout);
+    assert!(chart.starts_with("flowchart LR\n"), "{chart}");
+    assert!(chart.contains("t0 -- \"continues\" --> t2"), "{chart}");
+    assert!(
+        chart.contains("t0 == \"spawned: codex exec 'fix it'\" ==> t1"),
+        "{chart}"
+    );
+
+    let output = run_cli(repo, &["sessions", "--graph"], None);
+    let dot = String::from_utf8_lossy(&output.stdout);
+    assert!(dot.starts_with("digraph sessions {"), "{dot}");
+}
+
+#[test]
+fn explain_pretty_leads_with_summary_and_tiers_detail_by_verbosity() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn omega() { return value + 1; }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    run_json(repo, &["init"], None);
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let transcript = format!(
+        "{{\"t\":\"2026-02-22T00:00:01Z\",\"k\":\"msg.in\",\"text\":\"make omega add one\"}}\n\
+         {{\"t\":\"2026-02-22T00:00:02Z\",\"k\":\"code.edit\",\"file\":\"src/lib.rs\",\"before_range\":[2,2],\"after_range\":[2,2],\"before_anchor_hashes\":[\"winnow:00000000000000aa\"],\"after_anchor_hashes\":[\"{span_anchor}\"],\"similarity\":0.9}}\n"
+    );
+    let record = run_json(repo, &["record", "--stdin"], Some(&transcript));
+    let tape_id = record["tape_id"].as_str().expect("tape id");
+
+    let pretty = |extra: &[&str]| {
+        let mut args = vec!["explain", "src/lib.rs:2-2", "--pretty", "--timezone", "utc"];
+        args.extend_from_slice(extra);
+        let output = run_cli(repo, &args, None);
+        assert!(output.status.success(), "explain --pretty {extra:?} fails");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let summary = pretty(&[]);
+    let first = summary.lines().next().expect("summary line");
+    assert!(
+        first.starts_with(&format!(
+            "src/lib.rs:2-2 was last changed by session {tape_id} on 2026-02-22 ("
+        )),
+        "{summary}"
+    );
+    assert!(
+        first.contains(" ago), prompted by \"make omega add one\"."),
+        "{summary}"
+    );
+    assert!(first.contains("(-v to show)"), "{summary}");
+    assert!(summary.contains("sessions: 1"), "{summary}");
+    assert!(!summary.contains("lineage:"), "{summary}");
+
+    let verbose = pretty(&["-v"]);
+    assert!(
+        verbose.contains("- winnow:00000000000000aa -> "),
+        "{verbose}"
+    );
+    assert!(!verbose.contains("  - offset="), "{verbose}");
+
+    let forensic = pretty(&["-vv"]);
+    assert!(
+        forensic.contains("  - offset=1 edit src/lib.rs at 2026-02-22 00:00 UTC ("),
+        "{forensic}"
+    );
+}
+
+#[test]
+fn profile_selects_a_separate_store() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let home = repo.join(".home");
+    fs::create_dir_all(home.join(".engram")).expect("home config dir");
+    fs::write(
+        home.join(".engram/config.yml"),
+        "db: ~/.engram/index.sqlite\nprofiles:\n  work:\n    store: ~/work/.engram\n",
+    )
+    .expect("config");
+    let transcript = "{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"msg.in\",\"text\":\"hello\"}\n";
+    run_json(
+        repo,
+        &["--profile", "work", "record", "--stdin"],
+        Some(transcript),
+    );
+
+    assert!(home.join("work/.engram/index.sqlite").is_file());
+    assert!(!home.join(".engram/index.sqlite").exists());
+    let work = run_json(repo, &["tapes", "--profile", "work"], None);
+    assert_eq!(work["tapes"].as_array().map(Vec::len), Some(1), "{work}");
+
+    let output = run_cli(repo, &["--profile", "missing", "tapes"], None);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("profiles.missing"));
+}
+
+#[test]
+fn audit_log_records_commands_when_enabled() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let home = repo.join(".home");
+    run_json(repo, &["stats"], None);
+    let audit_log = home.join(".engram-cache/audit.log");
+    assert!(!audit_log.exists(), "audit log is opt-in");
+
+    fs::write(
+        home.join(".engram/config.yml"),
+        "db: ~/.engram/index.sqlite\naudit:\n  enabled: true\n",
+    )
+    .expect("config");
+    run_json(repo, &["stats"], None);
+    let output = run_cli(repo, &["show", "no-such-tape"], None);
+    assert!(!output.status.success());
+
+    let entries = fs::read_to_string(&audit_log)
+        .expect("audit log")
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("audit json"))
+        .collect::<Vec<_>>();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["command"], "stats");
+    assert_eq!(entries[0]["status"], "ok");
+    assert!(entries[0]["duration_ms"].is_u64());
+    assert_eq!(entries[1]["command"], "show");
+    assert_eq!(entries[1]["args"], json!(["show", "no-such-tape"]));
+    assert_eq!(entries[1]["status"], "error");
+    assert!(entries[1]["error"].is_string());
+}
+
+#[test]
+fn writers_refuse_to_run_while_another_process_holds_the_store_lock() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+    let lock_path = repo.join(".home/.engram/index.sqlite.lock");
+    let holder = hold_lock(&lock_path, "ingest");
+
+    for args in [
+        &["ingest"][..],
+        &["gc"][..],
+        &["maintain"][..],
+        &["fingerprint"][..],
+        &["scan-comments"][..],
+    ] {
+        let output = run_cli(repo, args, None);
+        assert!(!output.status.success(), "{args:?} ran under a held lock");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("store_locked"), "{stderr}");
+        assert!(
+            stderr.contains("another engram process is running"),
+            "{stderr}"
+        );
+    }
+
+    drop(holder);
+    run_json(repo, &["ingest"], None);
+    assert!(
+        fs::File::open(&lock_path)
+            .expect("lock file")
+            .try_lock()
+            .is_ok(),
+        "lock is released after ingest"
+    );
+}
+
+#[test]
+fn record_waits_for_the_store_lock_before_storing_its_tape() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+    let lock_path = repo.join(".home/.engram/index.sqlite.lock");
+    let tapes_dir = repo.join(".engram/tapes");
+    let holder = hold_lock(&lock_path, "gc");
+
+    let mut record = Command::new(env!("CARGO_BIN_EXE_engram"))
+        .current_dir(repo)
+        .args(["record", "--stdin"])
+        .env("HOME", repo.join(".home"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("command spawns");
+    record
+        .stdin
+        .take()
+        .expect("stdin pipe")
+        .write_all(b"{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"msg.in\",\"text\":\"hi\"}\n")
+        .expect("stdin write");
+    std::thread::sleep(std::time::Duration::from_millis(800));
+    let stored = fs::read_dir(&tapes_dir).map_or(0, |entries| entries.count());
+    assert_eq!(stored, 0, "record stored a tape while gc held the lock");
+
+    drop(holder);
+    let output = record.wait_with_output().expect("command output");
+    assert!(
+        output.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let recorded: Value = serde_json::from_slice(&output.stdout).expect("json stdout");
+    let tape_id = recorded["tape_id"].as_str().expect("tape id");
+    assert!(tapes_dir.join(format!("{tape_id}.jsonl.zst")).exists());
+    assert!(
+        fs::File::open(&lock_path)
+            .expect("lock file")
+            .try_lock()
+            .is_ok(),
+        "lock is released after record"
+    );
+}
+
+#[test]
+fn corrupt_tapes_fail_reads_and_verify_lists_them() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+    let transcript = "{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"msg.in\",\"text\":\"hello\"}\n";
+    let record = run_json(repo, &["record", "--stdin"], Some(transcript));
+    let tape_id = record["tape_id"].as_str().expect("tape id");
+
+    let clean = run_json(repo, &["verify", "--tapes"], None);
+    assert_eq!(clean["status"], "ok");
+    assert_eq!(clean["checked_tapes"], 1);
+
+    let tape_path = repo
+        .join(".engram/tapes")
+        .join(format!("{tape_id}.jsonl.zst"));
+    let tampered = transcript.replace("hello", "jello");
+    fs::write(
+        &tape_path,
+        zstd::stream::encode_all(tampered.as_bytes(), 0).expect("compress"),
+    )
+    .expect("tamper");
+
+    let show = run_cli(repo, &["show", tape_id], None);
+    assert!(!show.status.success());
+    assert!(
+        String::from_utf8_lossy(&show.stderr).contains("tape_corrupt"),
+        "{}",
+        String::from_utf8_lossy(&show.stderr)
+    );
+
+    let report = run_json(repo, &["verify", "--tapes"], None);
+    assert_eq!(report["status"], "corrupt");
+    assert_eq!(report["corrupt_count"], 1);
+    assert_eq!(report["corrupt"][0]["tape_id"], tape_id);
+    assert_eq!(report["corrupt"][0]["error"], "tape_corrupt");
+}
+
+#[test]
+fn verify_salvages_truncated_tapes() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+    // Hard to compress, so the tape spans many zstd blocks.
+    let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+    let transcript = (0..20_000)
+        .map(|_| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            format!(
+                "{{\"t\":\"2026-02-22T00:00:00Z\",\"k\":\"msg.in\",\"text\":\"{seed:016x}\"}}\n"
+            )
+        })
+        .collect::<String>();
+    let record = run_json(repo, &["record", "--stdin"], Some(&transcript));
+    let tape_id = record["tape_id"].as_str().expect("tape id");
+    let tape_path = repo
+        .join(".engram/tapes")
+        .join(format!("{tape_id}.jsonl.zst"));
+    let bytes = fs::read(&tape_path).expect("tape");
+    fs::write(&tape_path, &bytes[..bytes.len() / 2]).expect("truncate tape");
+
+    let report = run_json(repo, &["verify", "--tapes"], None);
+    assert_eq!(report["status"], "corrupt");
+    assert_eq!(report["corrupt"][0]["error"], "decompress_error");
+    let salvageable = report["corrupt"][0]["salvageable_lines"]
+        .as_u64()
+        .expect("salvageable lines");
+    assert!(salvageable > 0 && salvageable < 20_000, "{report}");
+
+    let salvage = run_json(repo, &["verify", "--tapes", "--salvage"], None);
+    let salvaged = &salvage["salvaged"][0];
+    assert_eq!(salvaged["tape_id"], tape_id);
+    assert_eq!(salvaged["reindexed"], true);
+    assert_eq!(salvaged["recovered_events"].as_u64(), Some(salvageable));
+    assert!(!tape_path.exists());
+
+    let after = run_json(repo, &["verify", "--tapes"], None);
+    assert_eq!(after["status"], "ok", "{after}");
+    let new_tape_id = salvaged["new_tape_id"].as_str().expect("new tape id");
+    run_json(repo, &["show", new_tape_id], None);
+}
+
+#[test]
+fn import_github_pr_anchors_review_comments_to_commented_lines() {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    fs::write(
+        repo.join("src/upload.rs"),
+        "fn upload() {\n    loop {\n        send_with_retry_budget(request, attempts);\n    }\n}\n",
+    )
+    .expect("seed file");
+    run_json(repo, &["init"], None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let server = std::thread::spawn(move || {
+        let mut paths = Vec::new();
+        for _ in 0..3 {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).expect("request line");
+            let mut authorized = false;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).expect("header");
+                if header.trim().is_empty() {
+                    break;
+                }
+                authorized |= header.to_ascii_lowercase().starts_with("authorization:");
+            }
+            let path = request_line
+                .split_whitespace()
+                .nth(1)
+                .expect("path")
+                .to_string();
+            let reply = if path.starts_with("/repos/octo/app/pulls/7/comments") {
+                json!([{
+                    "id": 21,
+                    "pull_request_review_id": 11,
+                    "path": "src/upload.rs",
+                    "start_line": 2,
+                    "line": 3,
+                    "side": "RIGHT",
+                    "diff_hunk": "@@ -1,2 +1,3 @@\n fn upload() {\n-    send(request);\n+    loop {\n+        send_with_retry_budget(request, attempts);",
+                    "body": "This never stops retrying.",
+                    "created_at": "2026-03-01T09:59:00Z",
+                    "user": {"login": "reviewer"},
+                }])
+            } else if path.starts_with("/repos/octo/app/pulls/7/reviews") {
+                json!([{
+                    "id": 11,
+                    "state": "CHANGES_REQUESTED",
+                    "body": "Bound the retries.",
+                    "submitted_at": "2026-03-01T10:00:00Z",
+                    "user": {"login": "reviewer"},
+                }])
+            } else {
+                json!({
+                    "title": "Retry uploads",
+                    "body": "Adds a retry loop.",
+                    "created_at": "2026-03-01T09:00:00Z",
+                    "user": {"login": "author"},
+                    "head": {"sha": "abc123"},
+                })
+            }
+            .to_string();
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                reply.len()
+            )
+            .expect("response");
+            paths.push((path, authorized));
+        }
+        paths
+    });
+
+    let api_url = format!("http://127.0.0.1:{port}");
+    let import = run_json(
+        repo,
+        &[
+            "import",
+            "github-pr",
+            "https://github.com/octo/app/pull/7",
+            "--api-url",
+            &api_url,
+            "--token-env",
+            "ENGRAM_TEST_UNSET_GITHUB_TOKEN",
+        ],
+        None,
+    );
+    let requests = server.join().expect("server thread");
+    assert_eq!(requests.len(), 3);
+    assert!(requests.iter().all(|(_, authorized)| !authorized));
+    assert_eq!(import["import"]["pull_request"], "octo/app#7");
+    assert_eq!(import["import"]["comments"], 1);
+    let tape_id = import["tape_id"].as_str().expect("tape id");
+
+    let explain = run_json(repo, &["explain", "src/upload.rs:2-3"], None);
+    let sessions = explain["sessions"].as_array().expect("sessions");
+    assert!(
+        sessions
+            .iter()
+            .any(|session| session["session_id"] == tape_id),
+        "{explain}"
+    );
+    let bad = run_cli(repo, &["import", "github-pr", "octo#7"], None);
+    assert!(String::from_utf8_lossy(&bad.stderr).contains("invalid_pull_request"));
+}
+
+#[test]
+fn issue_keys_filter_tapes_and_explain() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let span_text = "fn upload() { retry(request, 3) }";
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("alpha\n{span_text}\nzeta\n"),
+    )
+    .expect("seed file");
+    run_json(repo, &["init"], None);
+
+    let span_anchor = fingerprint_text(span_text).fingerprint;
+    let record = |meta: Value, message: &str, t: &str| {
+        let input = format!(
+            "{}\n{}\n{}\n",
+            meta,
+            json!({"t": t, "k": "msg.in", "text": message}),
+            json!({
+                "t": t,
+                "k": "code.edit",
+                "file": "src/lib.rs",
+                "after_range": [2, 2],
+                "after_anchor_hashes": [span_anchor],
+            }),
+        );
+        run_json(repo, &["record", "--stdin"], Some(&input))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let branch = record(
+        json!({"t": "2026-03-01T10:00:00Z", "k": "meta", "branch": "feature/ENG-42-retry"}),
+        "add retries",
+        "2026-03-01T10:00:00Z",
+    );
+    let message = record(
+        json!({"t": "2026-03-02T10:00:00Z", "k": "meta"}),
+        "PAY-7 asks for three attempts, see #88",
+        "2026-03-02T10:00:00Z",
+    );
+    record(
+        json!({"t": "2026-03-03T10:00:00Z", "k": "meta"}),
+        "tidy up",
+        "2026-03-03T10:00:00Z",
+    );
+    let tape_ids = |tapes: &Value| {
+        let mut ids = tapes["tapes"]
+            .as_array()
+            .expect("tapes")
+            .iter()
+            .map(|tape| tape["tape_id"].as_str().expect("id").to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    let explain = run_json(
+        repo,
+        &["explain", "src/lib.rs:2-2", "--issue", "eng-42"],
+        None,
+    );
+    let sessions = explain["sessions"].as_array().expect("sessions");
+    assert_eq!(sessions.len(), 1, "{explain}");
+    assert_eq!(sessions[0]["session_id"], branch);
+    assert_eq!(explain["query"]["issue"], json!(["eng-42"]));
+
+    let tapes = run_json(
+        repo,
+        &["tapes", "--issue", "ENG-42", "--issue", "PAY-7"],
+        None,
+    );
+    let mut expected = vec![branch.clone(), message.clone()];
+    expected.sort();
+    assert_eq!(tape_ids(&tapes), expected);
+    assert!(
+        tapes["tapes"]
+            .as_array()
+            .expect("tapes")
+            .iter()
+            .any(|tape| tape["issues"] == json!(["PAY-7"]))
+    );
+    assert!(tape_ids(&run_json(repo, &["tapes", "--issue", "88"], None)).is_empty());
+
+    let mut config = fs::read_to_string(repo.join(".home/.engram/config.yml")).expect("config");
+    config.push_str("issue_patterns: ['#([0-9]+)']\n");
+    fs::write(repo.join(".home/.engram/config.yml"), config).expect("write config");
+    run_json(repo, &["reindex"], None);
+    assert_eq!(
+        tape_ids(&run_json(repo, &["tapes", "--issue", "88"], None)),
+        vec![message]
+    );
+    assert!(tape_ids(&run_json(repo, &["tapes", "--issue", "ENG-42"], None)).is_empty());
+}
+
+#[test]
+fn show_format_markdown_renders_a_session_walkthrough() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+    let transcript = [
+        json!({"t": "2026-03-01T10:00:00Z", "k": "meta", "label": "Retry tuning", "source": {"harness": "codex-cli"}}),
+        json!({"t": "2026-03-01T10:00:01Z", "k": "msg.in", "text": "raise the retry count"}),
+        json!({"t": "2026-03-01T10:00:02Z", "k": "tool.call", "tool": "shell", "call_id": "c1",
+               "args": "{\"command\":[\"cargo\",\"test\"]}"}),
+        json!({"t": "2026-03-01T10:00:09Z", "k": "tool.result", "tool": "shell", "call_id": "c1",
+               "exit": 101, "stdout": "test retries ... FAILED"}),
+        json!({"t": "2026-03-01T10:00:10Z", "k": "code.edit", "file": "src/lib.rs",
+               "before_range": [1, 1], "after_range": [1, 1],
+               "before_text": "const RETRIES: u32 = 3;", "after_text": "const RETRIES: u32 = 5;"}),
+    ]
+    .iter()
+    .map(|row| format!("{row}\n"))
+    .collect::<String>();
+    let tape_id = run_json(repo, &["record", "--stdin"], Some(&transcript))["tape_id"]
+        .as_str()
+        .expect("tape id")
+        .to_string();
+
+    let output = run_cli(repo, &["show", &tape_id, "--format", "markdown"], None);
+    assert!(output.status.success());
+    let markdown = String::from_utf8(output.stdout).expect("utf8");
+    assert!(markdown.starts_with("# Retry tuning\n"), "{markdown}");
+    assert!(markdown.contains("**User** · 2026-03-01T10:00:01Z\n\nraise the retry count"));
+    assert!(
+        markdown.contains("<details><summary>shell <code>cargo test</code> · exit 101</summary>")
+    );
+    assert!(markdown.contains("test retries ... FAILED"));
+    assert!(markdown.contains("-const RETRIES: u32 = 3;\n+const RETRIES: u32 = 5;"));
+
+    let conflict = run_cli(
+        repo,
+        &["show", &tape_id, "--raw", "--format", "markdown"],
+        None,
+    );
+    assert!(!conflict.status.success());
+}
+
+#[test]
+fn split_cuts_a_tape_at_task_boundaries_and_survives_reindex_and_gc() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let login = "fn login() { check_password(user) }";
+    let billing = "fn export_billing() { write_csv(rows) }";
+    fs::write(repo.join("src/lib.rs"), format!("{login}\n{billing}\n")).expect("seed file");
+    run_json(repo, &["init"], None);
+
+    let edit = |t: &str, line: u64, text: &str| {
+        json!({
+            "t": t,
+            "k": "code.edit",
+            "file": "src/lib.rs",
+            "after_range": [line, line],
+            "after_anchor_hashes": [fingerprint_text(text).fingerprint],
+        })
+    };
+    let transcript = [
+        json!({"t": "2026-03-01T09:00:00Z", "k": "meta", "source": {"harness": "claude-code"}}),
+        json!({"t": "2026-03-01T09:00:01Z", "k": "msg.in", "text": "fix login"}),
+        edit("2026-03-01T09:05:00Z", 1, login),
+        json!({"t": "2026-03-01T13:00:00Z", "k": "msg.in", "text": "now the billing export"}),
+        edit("2026-03-01T13:10:00Z", 2, billing),
+    ]
+    .iter()
+    .map(|row| format!("{row}\n"))
+    .collect::<String>();
+    let parent = run_json(repo, &["record", "--stdin"], Some(&transcript))["tape_id"]
+        .as_str()
+        .expect("tape id")
+        .to_string();
+    let sessions = |target: &str| {
+        run_json(repo, &["explain", target], None)["sessions"]
+            .as_array()
+            .expect("sessions")
+            .iter()
+            .map(|session| session["session_id"].as_str().expect("id").to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sessions("src/lib.rs:1-1"), vec![parent.clone()]);
+
+    let preview = run_json(repo, &["split", &parent, "--auto", "--dry-run"], None);
+    assert_eq!(preview["parts"][1]["offsets"], json!([3, 4]));
+    assert!(preview["parts"][0].get("tape_id").is_none());
+    assert_eq!(sessions("src/lib.rs:1-1"), vec![parent.clone()]);
+
+    let split = run_json(repo, &["split", &parent, "--auto"], None);
+    let part = |at: usize| {
+        split["parts"][at]["tape_id"]
+            .as_str()
+            .expect("part")
+            .to_string()
+    };
+    let (first, second) = (part(0), part(1));
+    assert_eq!(sessions("src/lib.rs:1-1"), vec![first.clone()]);
+    assert_eq!(sessions("src/lib.rs:2-2"), vec![second.clone()]);
+    let raw = run_cli(repo, &["show", &second, "--raw"], None);
+    let meta = String::from_utf8(raw.stdout).expect("utf8");
+    let meta = serde_json::from_str::<Value>(meta.lines().next().expect("meta")).expect("json");
+    assert_eq!(meta["split"]["parent"], parent);
+    assert_eq!(meta["split"]["part"], 2);
+
+    let reindex = run_json(repo, &["reindex"], None);
+    assert_eq!(reindex["split_parents_skipped"], 1);
+    assert_eq!(sessions("src/lib.rs:1-1"), vec![first]);
+
+    run_json(repo, &["gc"], None);
+    run_json(repo, &["show", &parent], None);
+    let again = run_cli(repo, &["split", &parent, "--at", "3"], None);
+    assert!(String::from_utf8_lossy(&again.stderr).contains("already_split"));
+}
+
+#[test]
+fn join_stitches_tapes_into_one_session_and_survives_reindex_and_gc() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let parse = "fn parse() { read_header(input) }";
+    let render = "fn render() { write_body(output) }";
+    fs::write(repo.join("src/lib.rs"), format!("{parse}\n{render}\n")).expect("seed file");
+    run_json(repo, &["init"], None);
+
+    let tape = |t: [&str; 2], line: u64, text: &str| {
+        [
+            json!({"t": t[0], "k": "meta", "source": {"harness": "cursor"}}),
+            json!({"t": t[0], "k": "msg.in", "text": format!("edit line {line}")}),
+            json!({
+                "t": t[1],
+                "k": "code.edit",
+                "file": "src/lib.rs",
+                "after_range": [line, line],
+                "after_anchor_hashes": [fingerprint_text(text).fingerprint],
+            }),
+        ]
+        .iter()
+        .map(|row| format!("{row}\n"))
+        .collect::<String>()
+    };
+    let record = |content: String| {
+        run_json(repo, &["record", "--stdin"], Some(&content))["tape_id"]
+            .as_str()
+            .expect("tape id")
+            .to_string()
+    };
+    let second = record(tape(
+        ["2026-03-01T10:00:02Z", "2026-03-01T10:00:03Z"],
+        2,
+        render,
+    ));
+    let first = record(tape(
+        ["2026-03-01T10:00:00Z", "2026-03-01T10:00:01Z"],
+        1,
+        parse,
+    ));
+    let sessions = |target: &str| {
+        run_json(repo, &["explain", target], None)["sessions"]
+            .as_array()
+            .expect("sessions")
+            .iter()
+            .map(|session| session["session_id"].as_str().expect("id").to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sessions("src/lib.rs:2-2"), vec![second.clone()]);
+
+    let preview = run_json(repo, &["join", &second, &first, "--dry-run"], None);
+    assert_eq!(preview["event_count"], 5);
+    assert!(preview.get("tape_id").is_none());
+    assert_eq!(sessions("src/lib.rs:2-2"), vec![second.clone()]);
+
+    let joined = run_json(repo, &["join", &second, &first], None)["tape_id"]
+        .as_str()
+        .expect("joined")
+        .to_string();
+    assert_eq!(sessions("src/lib.rs:1-1"), vec![joined.clone()]);
+    assert_eq!(sessions("src/lib.rs:2-2"), vec![joined.clone()]);
+    let raw = run_cli(repo, &["show", &joined, "--raw"], None);
+    let rows = String::from_utf8(raw.stdout)
+        .expect("utf8")
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("json"))
+        .collect::<Vec<_>>();
+    assert_eq!(rows[0]["join"]["tapes"][0]["tape_id"], second);
+    assert_eq!(rows[0]["t"], "2026-03-01T10:00:00Z");
+    assert_eq!(
+        rows[1]["joined_from"],
+        json!({"tape_id": first, "offset": 1})
+    );
+    assert_eq!(rows[4]["joined_from"]["tape_id"], second);
+
+    let reindex = run_json(repo, &["reindex"], None);
+    assert_eq!(reindex["joined_members_skipped"], 2);
+    assert_eq!(sessions("src/lib.rs:1-1"), vec![joined.clone()]);
+
+    run_json(repo, &["gc"], None);
+    run_json(repo, &["show", &first], None);
+    let again = run_cli(repo, &["join", &first, &joined], None);
+    assert!(String::from_utf8_lossy(&again.stderr).contains("already_joined"));
+}
+
+#[test]
+fn reindex_moves_legacy_tape_ids_to_canonical_ids_and_keeps_old_ids_resolving() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+
+    // Written by an older adapter: keys unsorted, padded fields, and named
+    // by the hash of its exact bytes.
+    let transcript = concat!(
+        r#"{"k": "code.read", "t": "2026-02-22T00:00:00Z", "file": "src/lib.rs ", "range": [1, 1], "anchor_hashes": ["legacy-anchor"]}"#,
+        "\n"
+    );
+    let legacy_id = {
+        let digest = sha2::Sha256::digest(transcript.as_bytes());
+        digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+    };
+    let tapes = repo.join(".engram").join("tapes");
+    fs::create_dir_all(&tapes).expect("tape dir");
+    let compressed = zstd::stream::encode_all(transcript.as_bytes(), 0).expect("compress");
+    fs::write(tapes.join(format!("{legacy_id}.jsonl.zst")), compressed).expect("write tape");
+    run_json(repo, &["protect", "add", &legacy_id], None);
+    let manifest = repo.join(".engram").join("sessions").join("legacy.json");
+    fs::create_dir_all(manifest.parent().expect("sessions dir")).expect("sessions dir");
+    fs::write(
+        &manifest,
+        json!({
+            "name": "legacy",
+            "parts": [{"part": 1, "tape_id": legacy_id, "recorded_at": "2026-02-22T00:00:00Z"}],
+        })
+        .to_string(),
+    )
+    .expect("manifest");
+
+    let reindex = run_json(repo, &["reindex"], None);
+    let canonical = tape_id_for_contents(transcript);
+    assert_ne!(canonical, legacy_id);
+    assert_eq!(
+        reindex["migrated_tape_ids"],
+        json!([{"tape_id": legacy_id, "new_tape_id": canonical}])
+    );
+    assert!(tapes.join(format!("{canonical}.jsonl.zst")).exists());
+    assert!(!tapes.join(format!("{legacy_id}.jsonl.zst")).exists());
+    let aliases: Value = serde_json::from_slice(
+        &fs::read(repo.join(".engram").join("tape-ids.json")).expect("aliases"),
+    )
+    .expect("aliases json");
+    assert_eq!(aliases["aliases"][&legacy_id], canonical);
+
+    run_json(repo, &["show", &legacy_id], None);
+    let explain = run_json(repo, &["explain", "legacy-anchor", "--anchor"], None);
+    assert_eq!(explain["sessions"][0]["session_id"], canonical);
+    let protected = run_json(repo, &["protect", "list"], None);
+    assert!(protected.to_string().contains(&canonical));
+    let manifest: Value =
+        serde_json::from_slice(&fs::read(&manifest).expect("manifest")).expect("manifest json");
+    assert_eq!(manifest["parts"][0]["tape_id"], canonical);
+
+    // A reformatted copy is the same tape.
+    let reformatted = concat!(
+        r#"{"t":"2026-02-22T00:00:00Z","k":"code.read","file":"src/lib.rs","range":[1,1],"anchor_hashes":["legacy-anchor"]}"#,
+        "\n"
+    );
+    assert_eq!(tape_id_for_contents(reformatted), canonical);
+    let again = run_json(repo, &["reindex"], None);
+    assert_eq!(again["migrated_tape_ids"], json!([]));
+    assert_eq!(again["reindexed_tapes"], 1);
+}
+
+#[test]
+fn reindex_keeps_both_tapes_when_the_canonical_path_holds_other_events() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+
+    let transcript = concat!(
+        r#"{"k": "code.read", "t": "2026-02-22T00:00:00Z", "file": "src/lib.rs", "range": [1, 1], "anchor_hashes": ["legacy-anchor"]}"#,
+        "\n"
+    );
+    let legacy_id = {
+        let digest = sha2::Sha256::digest(transcript.as_bytes());
+        digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+    };
+    let canonical = tape_id_for_contents(transcript);
+    let tapes = repo.join(".engram").join("tapes");
+    fs::create_dir_all(&tapes).expect("tape dir");
+    let legacy_path = tapes.join(format!("{legacy_id}.jsonl.zst"));
+    let occupied_path = tapes.join(format!("{canonical}.jsonl.zst"));
+    let write = |path: &Path, content: &str| {
+        let compressed = zstd::stream::encode_all(content.as_bytes(), 0).expect("compress");
+        fs::write(path, compressed).expect("write tape");
+    };
+    write(&legacy_path, transcript);
+    let other = concat!(
+        r#"{"t":"2026-02-23T00:00:00Z","k":"msg.in","text":"something else"}"#,
+        "\n"
+    );
+    write(&occupied_path, other);
+
+    let reindex = run_json(repo, &["reindex"], None);
+    assert_eq!(reindex["migrated_tape_ids"], json!([]));
+    assert_eq!(
+        reindex["tape_id_conflicts"][0]["tape_id"],
+        legacy_id.as_str(),
+        "payload={reindex}"
+    );
+    assert_eq!(reindex["tape_id_conflicts"][0]["new_tape_id"], canonical);
+    let read = |path: &Path| {
+        String::from_utf8(
+            zstd::stream::decode_all(fs::read(path).expect("tape").as_slice()).expect("decompress"),
+        )
+        .expect("utf8")
+    };
+    assert_eq!(read(&legacy_path), transcript);
+    assert_eq!(read(&occupied_path), other);
+    run_json(repo, &["show", &legacy_id], None);
+}
+
+#[test]
+fn explain_previews_anchors_from_recorded_text() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    let before = "fn retry_upload(attempts: u32) { backoff(attempts, 100) }";
+    let after = "fn retry_upload(attempts: u32) {\n    backoff(attempts, 250)\n}";
+    let after_preview = "fn retry_upload(attempts: u32) { backoff(attempts, 250) }";
+    fs::write(repo.join("src/lib.rs"), format!("{after}\n")).expect("seed file");
+    run_json(repo, &["init"], None);
+    let read = |t: &str, text: &str| json!({"t": t, "k": "code.read", "file": "src/lib.rs", "range": [1, 3], "text": text});
+    let transcript = [
+        json!({"t": "2026-03-01T10:00:00Z", "k": "msg.in", "text": "slow the retries down"}),
+        read("2026-03-01T10:00:01Z", before),
+        json!({
+            "t": "2026-03-01T10:00:02Z",
+            "k": "code.edit",
+            "file": "src/lib.rs",
+            "before_range": [1, 1],
+            "after_range": [1, 3],
+            "before_anchor_hashes": [fingerprint_text(before).fingerprint],
+            "after_anchor_hashes": [fingerprint_text(after).fingerprint],
+            "similarity": 0.9,
+        }),
+        read("2026-03-01T10:00:03Z", after),
+    ]
+    .iter()
+    .map(|row| format!("{row}\n"))
+    .collect::<String>();
+    run_json(repo, &["record", "--stdin"], Some(&transcript));
+
+    let explain = run_json(repo, &["explain", "src/lib.rs:1-3"], None);
+    let previews = explain["query"]["anchor_previews"]
+        .as_object()
+        .expect("previews");
+    assert!(!previews.is_empty(), "{explain}");
+    // Tokens both texts share keep the preview of the text seen first.
+    assert!(
+        previews
+            .values()
+            .all(|preview| preview == before || preview == after_preview),
+        "{explain}"
+    );
+    assert!(previews.values().any(|preview| preview == after_preview));
+    let lineage = explain["lineage"].as_array().expect("lineage");
+    assert!(lineage.iter().all(|edge| edge["from_preview"] == before));
+    assert!(
+        lineage
+            .iter()
+            .any(|edge| edge["to_preview"] == after_preview),
+        "{explain}"
+    );
+
+    let output = run_cli(
+        repo,
+        &[
+            "explain",
+            "src/lib.rs:1-3",
+            "--pretty",
+            "-v",
+            "--timezone",
+            "utc",
+        ],
+        None,
+    );
+    let pretty = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        pretty.contains(&format!("preview: {after_preview}\n")),
+        "{pretty}"
+    );
+    assert!(pretty.contains(&format!("  from: {before}\n")), "{pretty}");
+}
+
+#[test]
+fn sources_status_reports_matches_adapters_and_recent_failures() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    run_json(repo, &["init"], None);
+
+    let sessions = repo.join(".claude/projects");
+    fs::create_dir_all(sessions.join("nested")).expect("sessions dir");
+    fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/claude_adapter_input.jsonl"),
+        sessions.join("nested/claude.jsonl"),
+    )
+    .expect("copy fixture");
+    fs::write(sessions.join("notes.txt"), "not a transcript\n").expect("notes");
+    let log = repo.join("watch.log");
+    let failed = sessions.join("nested/claude.jsonl");
+    fs::write(
+        &log,
+        format!(
+            "[2026-03-01T10:00:00+00:00] watch started sources=2\n\
+             [2026-03-01T10:00:05+00:00] ingest ok path={path}\n\
+             [2026-03-01T10:05:00+00:00] ingest failed path={path} code=store_locked message=another engram process is running\n",
+            path = failed.display()
+        ),
+    )
+    .expect("log");
+
+    let config_path = repo.join(".home/.engram/config.yml");
+    let mut config = fs::read_to_string(&config_path).unwrap_or_default();
+    config.push_str(&format!(
+        "watch:\n  log: {}\n  sources:\n    - path: {}\n      pattern: \"*.jsonl\"\n    - path: {}\n      pattern: \"*.jsonl\"\n",
+        log.display(),
+        sessions.display(),
+        repo.join("missing").display()
+    ));
+    fs::write(&config_path, config).expect("write config");
+
+    let status = run_json(repo, &["sources", "status"], None);
+    assert_eq!(status["status"], "degraded");
+    let sources = status["sources"].as_array().expect("sources");
+    assert_eq!(sources.len(), 2);
+
+    let present = &sources[0];
+    assert_eq!(present["exists"], true);
+    assert_eq!(present["files_matched"], 1);
+    assert!(present["newest_session_at"].is_string());
+    assert_eq!(present["adapters"]["claude-code"], 1);
+    assert_eq!(present["last_scan_at"], "2026-03-01T10:05:00Z");
+    assert_eq!(present["status"], "failing");
+    let failures = present["recent_failures"].as_array().expect("failures");
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0]["code"], "store_locked");
+    assert_eq!(failures[0]["message"], "another engram process is running");
+
+    assert_eq!(sources[1]["exists"], false);
+    assert_eq!(sources[1]["status"], "missing");
+}
+
+#[test]
+fn discover_reports_harness_data_and_appends_sources_to_config() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let home = repo.join(".home");
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    fs::create_dir_all(home.join(".claude/projects/demo")).expect("claude dir");
+    fs::copy(
+        fixtures.join("claude_adapter_input.jsonl"),
+        home.join(".claude/projects/demo/session.jsonl"),
+    )
+    .expect("claude session");
+    fs::create_dir_all(home.join(".codex/sessions/2026/03/01")).expect("codex dir");
+    fs::copy(
+        fixtures.join("codex/supported_paths.jsonl"),
+        home.join(".codex/sessions/2026/03/01/rollout.jsonl"),
+    )
+    .expect("codex session");
+
+    let report = run_json(repo, &["discover"], None);
+    let harness = |id: &str| {
+        report["harnesses"]
+            .as_array()
+            .expect("harnesses")
+            .iter()
+            .find(|harness| harness["adapter"] == id)
+            .cloned()
+            .expect("harness")
+    };
+    let claude = harness("claude-code");
+    assert_eq!(claude["found"], true);
+    assert_eq!(claude["files"], 1);
+    assert!(claude["newest_at"].is_string());
+    assert_eq!(claude["locations"][0]["detected"], "claude-code");
+    assert_eq!(harness("codex-cli")["files"], 1);
+    assert_eq!(harness("gemini-cli")["found"], false);
+    let cursor = harness("cursor");
+    assert_eq!(cursor["locations"][0]["probed"], false);
+    assert!(report.get("added").is_none());
+    let suggested = report["sources"].as_array().expect("sources");
+    assert!(
+        suggested
+            .iter()
+            .any(|source| source["path"] == "~/.claude/projects"
+                && source["pattern"] == "*.jsonl"
+                && source["glob"] == "*/*.jsonl")
+    );
+
+    let added = run_json(repo, &["discover", "--add"], None);
+    assert_eq!(
+        added["added"].as_array().expect("added").len(),
+        suggested.len()
+    );
+    let again = run_json(repo, &["discover", "--add"], None);
+    assert_eq!(again["added"], json!([]));
+
+    let status = run_json(repo, &["sources", "status"], None);
+    let sources = status["sources"].as_array().expect("sources");
+    assert_eq!(sources.len(), suggested.len());
+    let claude_source = sources
+        .iter()
+        .find(|source| source["path"] == home.join(".claude/projects").to_string_lossy().as_ref())
+        .expect("claude source");
+    assert_eq!(claude_source["files_matched"], 1);
+    assert_eq!(claude_source["adapters"]["claude-code"], 1);
+}
+
+#[test]
+fn interactive_init_writes_sources_storage_retention_and_redaction() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path();
+    let home = repo.join(".home");
+    fs::create_dir_all(home.join(".claude/projects/demo")).expect("claude dir");
+    fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/claude_adapter_input.jsonl"),
+        home.join(".claude/projects/demo/session.jsonl"),
+    )
+    .expect("claude session");
+
+    // Watch the Claude sessions, keep the store in the repo, retain raw
+    // input, and take the defaults for the rest.
+    let init = run_json(repo, &["init", "--interactive"], Some("\nrepo\ny\n"));
+    assert_eq!(init["storage"], "repo");
+    assert_eq!(init["created"], true);
+    assert_eq!(init["retain_raw"], true);
+    assert_eq!(init["retain_edits"], false);
+    assert_eq!(init["sources_added"][0]["path"], "~/.claude/projects");
+    assert!(
+        init["ignore"]
+            .as_array()
+            .expect("ignore")
+            .contains(&json!(".env"))
+    );
+
+    let local: serde_yaml::Value = serde_yaml::from_str(
+        &fs::read_to_string(repo.join(".engram/config.yml")).expect("local config"),
+    )
+    .expect("yaml");
+    assert_eq!(local["db"], ".engram/index.sqlite");
+    assert_eq!(local["ingest"]["retain_raw"], true);
+    let status = run_json(repo, &["sources", "status"], None);
+    assert_eq!(status["sources"][0]["files_matched"], 1);
+
+    // Declining everything and choosing global storage leaves the sources
+    // alone and writes retention to the user config.
+    let again = run_json(repo, &["init", "--interactive"], Some("n\ng\n"));
+    assert_eq!(again["storage"], "global");
+    assert_eq!(again["sources_added"], json!([]));
+    assert_eq!(
+        again["shadowed_by"],
+        repo.join(".engram/config.yml").to_string_lossy().as_ref()
+    );
+    let user: serde_yaml::Value = serde_yaml::from_str(
+        &fs::read_to_string(home.join(".engram/config.yml")).expect("user config"),
+    )
+    .expect("yaml");
+    assert_eq!(user["ingest"]["retain_raw"], false);
+    assert_eq!(
+        user["watch"]["sources"].as_sequence().map(Vec::len),
+        Some(1)
+    );
+}
@@ -151,10 +151,9 @@ fn compact_restart_reingest_adds_new_tape_without_duplication() {
     let explain = run_json(repo, &["explain", "src/engine.rs:2-2"], None);
     assert_eq!(explain["sessions"].as_array().expect("sessions").len(), 3);
     assert!(
-        explain["dispatch_lineage"]
+        !explain["dispatch_lineage"]
             .as_array()
             .expect("dispatch lineage")
-            .len()
-            >= 1
+            .is_empty()
     );
 }
@@ -279,7 +279,7 @@ fn ingest_handles_partial_trailing_record_without_advancing_cursor() {
         .append(true)
         .open(&source_path)
         .expect("open source")
-        .write_all(appended_line[split_at..].as_bytes())
+        .write_all(&appended_line.as_bytes()[split_at..])
         .expect("append completion bytes");
 
     let third = run_json(&repo, &["ingest"], None, &home);
@@ -953,3 +953,1494 @@ fn explain_errors_when_additional_store_is_not_sqlite_database() {
     let err = stderr_json_line(&output.stderr);
     assert_eq!(err["error"]["code"], "sqlite_error");
 }
+
+#[test]
+fn fast_ingest_indexes_durably_and_leaves_no_wal_behind() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    fs::write(
+        repo.join("input.codex.jsonl"),
+        include_str!("fixtures/codex/supported_paths.jsonl"),
+    )
+    .expect("seed source");
+
+    let fast = run_json(&repo, &["ingest", "--fast-ingest"], None, &home);
+    assert_eq!(fast["status"], "ok");
+    assert_eq!(fast["fast_ingest"], true);
+    assert_eq!(fast["imported_tapes"], 1);
+    let wal = home.join(".engram/index.sqlite-wal");
+    assert!(
+        fs::metadata(&wal).map_or(true, |meta| meta.len() == 0),
+        "bulk load should end with a checkpointed WAL"
+    );
+
+    let again = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(again["fast_ingest"], false);
+    assert_eq!(again["skipped_unchanged"], 1);
+}
+
+#[test]
+fn show_origin_traces_ingested_tape_to_raw_source() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    let raw = include_str!("fixtures/codex/supported_paths.jsonl");
+    let source_path = repo.join("input.codex.jsonl");
+    fs::write(&source_path, raw).expect("seed source");
+
+    let _ = run_json(&repo, &["ingest"], None, &home);
+    let tape_file = fs::read_dir(home.join(".engram/tapes"))
+        .expect("tapes dir")
+        .filter_map(Result::ok)
+        .find(|entry| entry.path().is_file())
+        .expect("tape file");
+    let tape_id = tape_file
+        .file_name()
+        .to_string_lossy()
+        .trim_end_matches(".jsonl.zst")
+        .to_string();
+
+    let shown = run_json(&repo, &["show", &tape_id, "--origin"], None, &home);
+    let origin = &shown["origin"];
+    assert!(
+        origin["path"]
+            .as_str()
+            .is_some_and(|path| path.ends_with("input.codex.jsonl")),
+        "origin={origin}"
+    );
+    assert_eq!(origin["sha256"], sha256_hex(raw));
+    assert_eq!(origin["adapter"], "codex-cli");
+    assert!(origin["adapter_version"].is_string());
+    assert!(origin["converted_at"].is_string());
+    assert_eq!(origin["source_exists"], true);
+    assert_eq!(origin["source_matches"], true);
+
+    let reindex = run_json(&repo, &["reindex"], None, &home);
+    assert_eq!(reindex["preserved_conversion_times"], 1);
+    fs::remove_file(&source_path).expect("remove source");
+    let shown = run_json(&repo, &["show", &tape_id, "--origin"], None, &home);
+    assert_eq!(shown["origin"]["converted_at"], origin["converted_at"]);
+    assert_eq!(shown["origin"]["source_exists"], false);
+}
+
+#[test]
+fn reconvert_regenerates_tapes_from_older_adapter_versions() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    let source_path = repo.join("input.codex.jsonl");
+    fs::write(
+        &source_path,
+        include_str!("fixtures/codex/supported_paths.jsonl"),
+    )
+    .expect("seed source");
+    let _ = run_json(&repo, &["ingest"], None, &home);
+
+    let db = home.join(".engram/index.sqlite");
+    let age_tapes = || {
+        rusqlite::Connection::open(&db)
+            .expect("open db")
+            .execute("UPDATE tapes SET adapter_version = '0.9.0'", [])
+            .expect("age tapes")
+    };
+    assert_eq!(age_tapes(), 1);
+    let current = run_json(&repo, &["ingest", "--reconvert"], None, &home);
+    assert_eq!(current["reconverted_tapes"], 1, "payload={current}");
+    let entry = &current["reconverted"][0];
+    assert_eq!(entry["adapter"], "codex-cli");
+    assert_eq!(entry["from_version"], "0.9.0");
+    let tape_id = entry["new_tape_id"].as_str().expect("new tape id");
+
+    let shown = run_json(&repo, &["show", tape_id, "--origin"], None, &home);
+    assert_eq!(shown["origin"]["adapter_version"], entry["to_version"]);
+    let again = run_json(&repo, &["ingest", "--reconvert"], None, &home);
+    assert_eq!(again["reconverted_tapes"], 0);
+    let incremental = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(incremental["skipped_unchanged"], 1);
+
+    age_tapes();
+    fs::remove_file(&source_path).expect("remove source");
+    let missing = run_json(&repo, &["ingest", "--reconvert"], None, &home);
+    assert_eq!(missing["reconverted_tapes"], 0);
+    assert_eq!(missing["skipped"][0]["reason"], "source_missing");
+}
+
+#[test]
+fn retained_raw_copies_outlive_rotated_harness_files() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\ningest:\n  retain_raw: true\n",
+    )
+    .expect("home config");
+    let raw = include_str!("fixtures/codex/supported_paths.jsonl");
+    let source_path = repo.join("input.codex.jsonl");
+    fs::write(&source_path, raw).expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["retained_raw_copies"], 1);
+    let copy = repo
+        .join(".engram/objects/raw")
+        .join(format!("{}.zst", sha256_hex(raw)));
+    let restored = zstd::stream::decode_all(fs::read(&copy).expect("raw copy").as_slice())
+        .expect("decompress raw copy");
+    assert_eq!(restored, raw.as_bytes());
+
+    fs::remove_file(&source_path).expect("rotate source");
+    rusqlite::Connection::open(home.join(".engram/index.sqlite"))
+        .expect("open db")
+        .execute("UPDATE tapes SET adapter_version = '0.9.0'", [])
+        .expect("age tapes");
+    let reconvert = run_json(&repo, &["ingest", "--reconvert"], None, &home);
+    assert_eq!(reconvert["reconverted_tapes"], 1, "payload={reconvert}");
+    assert_eq!(reconvert["reconverted"][0]["from_raw_copy"], true);
+
+    let tape_id = reconvert["reconverted"][0]["new_tape_id"]
+        .as_str()
+        .expect("tape id");
+    let shown = run_json(&repo, &["show", tape_id, "--origin"], None, &home);
+    assert_eq!(shown["origin"]["source_exists"], false);
+    assert_eq!(shown["origin"]["raw_copy"], true);
+}
+
+#[test]
+fn ingest_skips_non_json_lines_with_diagnostics() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    let clean = include_str!("fixtures/codex/supported_paths.jsonl");
+    let lines = clean.lines().collect::<Vec<_>>();
+    let mangled = format!(
+        "\u{feff}{}\n[harness] reconnecting\n{}\n{{\"timestamp\":\"2026-02\n{}\n",
+        lines[0],
+        lines[1..3].join("\n"),
+        lines[3..].join("\n"),
+    );
+    fs::write(repo.join("input.codex.jsonl"), &mangled).expect("seed source");
+    fs::write(repo.join("clean.codex.jsonl"), clean).expect("clean source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["status"], "ok", "payload={ingest}");
+    assert_eq!(ingest["imported_tapes"], 2, "payload={ingest}");
+    assert_eq!(ingest["skipped_lines"], 2);
+    let skipped = &ingest["skipped_input"][0];
+    assert!(
+        skipped["path"]
+            .as_str()
+            .expect("path")
+            .ends_with("input.codex.jsonl")
+    );
+    assert_eq!(skipped["lines"][0]["line"], 2);
+    assert_eq!(skipped["lines"][1]["line"], 5);
+
+    // The mangled copy converts to as many tape lines as the clean one.
+    let tape_lines = fs::read_dir(home.join(".engram/tapes"))
+        .expect("tapes dir")
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let bytes = fs::read(entry.path()).expect("tape");
+            let text = zstd::stream::decode_all(bytes.as_slice()).expect("decompress tape");
+            String::from_utf8(text).expect("utf8 tape").lines().count()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(tape_lines.len(), 2);
+    assert_eq!(tape_lines[0], tape_lines[1]);
+}
+
+#[test]
+fn gzip_rotated_transcripts_continue_from_the_original_cursor() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    let raw = include_str!("fixtures/codex/supported_paths.jsonl");
+    let source_path = repo.join("input.codex.jsonl");
+    fs::write(&source_path, raw).expect("seed source");
+
+    let first = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(first["imported_tapes"], 1);
+
+    // logrotate: compress the transcript away and start a fresh one.
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(raw.as_bytes()).expect("gzip");
+    let rotated_path = repo.join("input.codex.jsonl.gz");
+    fs::write(&rotated_path, encoder.finish().expect("gzip")).expect("rotated source");
+    fs::remove_file(&source_path).expect("rotate source");
+
+    let second = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(second["status"], "ok", "payload={second}");
+    assert_eq!(second["imported_tapes"], 0, "payload={second}");
+    assert_eq!(second["skipped_unchanged"], 1);
+    let rotated = fs::canonicalize(&rotated_path).expect("canonical rotated path");
+    assert_eq!(
+        second["rotated_sources"],
+        serde_json::json!([rotated.to_string_lossy()])
+    );
+    assert!(cursor_state_path(&repo, &rotated_path).exists());
+
+    let third = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(third["imported_tapes"], 0);
+    assert_eq!(third["skipped_unchanged"], 1);
+    assert_eq!(third["rotated_sources"], serde_json::json!([]));
+    let tape_count = fs::read_dir(home.join(".engram/tapes"))
+        .expect("tapes dir")
+        .filter_map(Result::ok)
+        .count();
+    assert_eq!(tape_count, 1);
+}
+
+#[test]
+fn ingest_samples_oversized_outputs_per_event_kind() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        concat!(
+            "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "ingest:\n  sample:\n    tool.result:\n      max_bytes: 4\n      keep_bytes: 2\n",
+        ),
+    )
+    .expect("home config");
+    fs::write(
+        repo.join("input.codex.jsonl"),
+        include_str!("fixtures/codex/supported_paths.jsonl"),
+    )
+    .expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+    assert_eq!(ingest["sampled_fields"], 2);
+
+    let tape = fs::read_dir(home.join(".engram/tapes"))
+        .expect("tapes dir")
+        .next()
+        .expect("tape file")
+        .expect("entry")
+        .path();
+    let jsonl = zstd::stream::decode_all(fs::read(tape).expect("tape").as_slice())
+        .expect("decompress tape");
+    let rows = String::from_utf8(jsonl)
+        .expect("utf8 tape")
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+        .collect::<Vec<_>>();
+    let sampled = rows
+        .iter()
+        .rev()
+        .find(|row| row.get("stdout_sample").is_some())
+        .expect("sampled tool.result");
+    assert_eq!(sampled["k"], "tool.result");
+    assert_eq!(sampled["stdout_sample"]["sha256"], sha256_hex("Done."));
+    assert_eq!(
+        sampled["stdout"],
+        "Do\n[engram: stdout sampled, 1 of 5 bytes omitted]\ne."
+    );
+}
+
+#[test]
+fn ingest_drops_evidence_on_ignored_paths() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        concat!(
+            "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "ignore:\n  - \"target/**\"\n  - \"*.lock\"\n",
+        ),
+    )
+    .expect("home config");
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": concat!(
+                "*** Begin Patch\n",
+                "*** Update File: src/lib.rs\n@@\n-fn old() {}\n+fn new() {}\n",
+                "*** Update File: target/debug/gen.rs\n@@\n-a\n+b\n",
+                "*** Update File: Cargo.lock\n@@\n-v1\n+v2\n",
+                "*** End Patch\n",
+            ),
+        },
+    });
+    let raw = format!(
+        "{}\n{patch}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta")
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+    assert_eq!(ingest["ignored_events"], 2);
+
+    let tape = fs::read_dir(home.join(".engram/tapes"))
+        .expect("tapes dir")
+        .next()
+        .expect("tape file")
+        .expect("entry")
+        .path();
+    let jsonl = zstd::stream::decode_all(fs::read(tape).expect("tape").as_slice())
+        .expect("decompress tape");
+    let edited = String::from_utf8(jsonl)
+        .expect("utf8 tape")
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+        .filter(|row| row["k"] == "code.edit")
+        .map(|row| row["file"].as_str().expect("file").to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(edited, vec!["src/lib.rs"]);
+}
+
+#[test]
+fn ingest_limits_index_oversized_tapes_partially() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        concat!(
+            "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "ingest:\n  limits:\n    max_events: 2\n",
+        ),
+    )
+    .expect("home config");
+    fs::write(
+        repo.join("input.codex.jsonl"),
+        include_str!("fixtures/codex/supported_paths.jsonl"),
+    )
+    .expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["status"], "partial_ingest", "payload={ingest}");
+    assert_eq!(ingest["imported_tapes"], 1);
+    let partial = ingest["partial_ingest"].as_array().expect("partial_ingest");
+    assert_eq!(partial.len(), 1);
+    assert_eq!(partial[0]["events_indexed"], 2);
+    assert!(partial[0]["events_total"].as_u64().expect("events_total") > 2);
+    assert!(
+        partial[0]["path"]
+            .as_str()
+            .expect("path")
+            .ends_with("input.codex.jsonl")
+    );
+}
+
+#[test]
+fn retained_edit_texts_render_as_show_patch() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\ningest:\n  retain_edits: true\n",
+    )
+    .expect("home config");
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": "*** Begin Patch\n*** Update File: src/lib.rs\n@@\n fn keep() {}\n-fn old() {}\n+fn new() {}\n*** End Patch\n",
+        },
+    });
+    let raw = format!(
+        "{}\n{patch}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta")
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+    assert_eq!(ingest["retained_edit_objects"], 2);
+    for text in ["fn keep() {}\nfn old() {}\n", "fn keep() {}\nfn new() {}\n"] {
+        let object = repo
+            .join(".engram/objects/edits")
+            .join(format!("{}.zst", sha256_hex(text)));
+        let stored = zstd::stream::decode_all(fs::read(&object).expect("edit object").as_slice())
+            .expect("decompress edit object");
+        assert_eq!(stored, text.as_bytes());
+    }
+
+    let tape_id = fs::read_dir(home.join(".engram/tapes"))
+        .expect("tapes dir")
+        .filter_map(Result::ok)
+        .find(|entry| entry.path().is_file())
+        .expect("tape file")
+        .file_name()
+        .to_string_lossy()
+        .trim_end_matches(".jsonl.zst")
+        .to_string();
+    let shown = run_json(&repo, &["show", &tape_id, "--patch"], None, &home);
+    assert_eq!(shown["edit_count"], 1, "payload={shown}");
+    assert_eq!(shown["edits_from_objects"], 1);
+    assert_eq!(shown["files"][0]["file"], "src/lib.rs");
+    assert_eq!(
+        shown["files"][0]["patch"],
+        "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n fn keep() {}\n-fn old() {}\n+fn new() {}\n"
+    );
+}
+
+#[test]
+fn ingest_and_record_stamp_the_configured_environment_on_meta() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = home.join("repo");
+    fs::create_dir_all(repo.join(".engram")).expect("repo .engram");
+    fs::write(
+        repo.join(".engram/config.yml"),
+        concat!(
+            "db: .engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "ingest:\n  environment: [os, versions]\n",
+        ),
+    )
+    .expect("repo config");
+    let raw = concat!(
+        r#"{"timestamp":"2026-02-22T00:00:00Z","type":"session_meta","payload":{"id":"s-1","cli_version":"0.46.0","model_provider":"openai"}}"#,
+        "\n",
+        r#"{"timestamp":"2026-02-22T00:00:01Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"hi"}]}}"#,
+        "\n",
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+    let meta_environment = |tape_id: &str| {
+        let path = repo
+            .join(".engram/tapes")
+            .join(format!("{tape_id}.jsonl.zst"));
+        let content = zstd::stream::decode_all(fs::read(&path).expect("tape").as_slice())
+            .expect("decompress tape");
+        String::from_utf8(content)
+            .expect("utf8")
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("json"))
+            .find(|row| row["k"] == "meta")
+            .expect("meta event")["environment"]
+            .clone()
+    };
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+    let tapes = run_json(&repo, &["tapes"], None, &home);
+    let tape_id = tapes["tapes"][0]["tape_id"].as_str().expect("tape id");
+    assert_eq!(
+        meta_environment(tape_id),
+        serde_json::json!({
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "engram_version": env!("CARGO_PKG_VERSION"),
+            "harness_version": "0.46.0",
+        })
+    );
+
+    let event = r#"{"t":"2026-02-23T00:00:00Z","k":"msg.in","text":"record me"}"#;
+    let recorded = run_json(
+        &repo,
+        &["record", "--stdin"],
+        Some(&format!("{event}\n")),
+        &home,
+    );
+    let environment = meta_environment(recorded["tape_id"].as_str().expect("tape id"));
+    assert_eq!(environment["engram_version"], env!("CARGO_PKG_VERSION"));
+    assert!(environment.get("harness_version").is_none());
+
+    let sent = r#"{"t":"2026-02-24T00:00:00Z","k":"meta","environment":{"hostname":"ci-7"}}"#;
+    let recorded = run_json(
+        &repo,
+        &["record", "--stdin"],
+        Some(&format!("{sent}\n{event}\n")),
+        &home,
+    );
+    assert_eq!(
+        meta_environment(recorded["tape_id"].as_str().expect("tape id")),
+        serde_json::json!({"hostname": "ci-7"})
+    );
+}
+
+#[test]
+fn export_pseudonymizes_tape_session_host_and_user_ids() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = home.join("repo");
+    fs::create_dir_all(repo.join(".engram")).expect("repo .engram");
+    fs::write(
+        repo.join(".engram/config.yml"),
+        concat!(
+            "db: .engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "export:\n  pseudonymize_key_env: ENGRAM_TEST_PSEUDONYM_KEY\n",
+        ),
+    )
+    .expect("repo config");
+    let transcript = concat!(
+        r#"{"t":"2026-02-22T00:00:00Z","k":"meta","model":"gpt-5","source":{"harness":"codex-cli","session_id":"sess-secret"},"environment":{"hostname":"ada-laptop","user":"ada"}}"#,
+        "\n",
+        r#"{"t":"2026-02-22T00:00:01Z","k":"code.edit","file":"src/lib.rs","before_range":[1,3],"after_range":[1,4],"before_anchor_hashes":["winnow:00000000000000aa"],"after_anchor_hashes":["winnow:00000000000000bb"],"similarity":0.9}"#,
+        "\n",
+    );
+    let recorded = run_json(&repo, &["record", "--stdin"], Some(transcript), &home);
+    let tape_id = recorded["tape_id"].as_str().expect("tape id");
+    let export = |key: Option<&str>, graph: &str| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_engram"));
+        cmd.current_dir(&repo)
+            .args(["export", "--graph", graph, "--format", "cypher"])
+            .env("HOME", &home)
+            .env_remove("ENGRAM_TEST_PSEUDONYM_KEY");
+        if let Some(key) = key {
+            cmd.env("ENGRAM_TEST_PSEUDONYM_KEY", key);
+        }
+        cmd.output().expect("command runs")
+    };
+
+    let missing = export(None, "graph.cypher");
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("pseudonymize_key_missing"));
+
+    for graph in ["first.cypher", "second.cypher"] {
+        let output = export(Some("org secret"), graph);
+        assert!(
+            output.status.success(),
+            "stderr={}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let payload: Value = serde_json::from_slice(&output.stdout).expect("json");
+        assert_eq!(payload["pseudonymized"], true);
+    }
+    let script = fs::read_to_string(repo.join("first.cypher")).expect("script");
+    assert_eq!(
+        fs::read_to_string(repo.join("second.cypher")).expect("script"),
+        script
+    );
+    for secret in [tape_id, "sess-secret", "ada-laptop", "\"ada\""] {
+        assert!(!script.contains(secret), "{secret} leaked: {script}");
+    }
+    for pseudonym in [
+        "{id: \"tape-",
+        "session_id: \"session-",
+        "hostname: \"host-",
+        "user: \"user-",
+    ] {
+        assert!(script.contains(pseudonym), "{pseudonym} missing: {script}");
+    }
+    assert!(script.contains("model: \"gpt-5\""));
+
+    let other = export(Some("another secret"), "other.cypher");
+    assert!(other.status.success());
+    assert_ne!(
+        fs::read_to_string(repo.join("other.cypher")).expect("script"),
+        script
+    );
+}
+
+#[test]
+fn ingest_snapshots_edited_files_so_explain_reports_drift() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    let span_text = "fn beta() { return value + 2; }";
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    fs::write(repo.join("src/lib.rs"), format!("{span_text}\n")).expect("seed file");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        concat!(
+            "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "ingest:\n  snapshot: true\n",
+        ),
+    )
+    .expect("home config");
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": format!(
+                "*** Begin Patch\n*** Update File: src/lib.rs\n@@\n-fn alpha() {{ return value + 1; }}\n+{span_text}\n*** End Patch\n"
+            ),
+        },
+    });
+    let raw = format!(
+        "{}\n{patch}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta")
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+    assert_eq!(ingest["state_snapshots"], 1);
+
+    let explain = run_json(&repo, &["explain", "src/lib.rs:1-1"], None, &home);
+    let state = &explain["sessions"][0]["workspace_state"];
+    assert_eq!(state["state"], "unchanged", "explain={explain:#}");
+    assert_eq!(state["files"][0]["file"], "src/lib.rs");
+
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("{span_text}\nfn hand_written() {{}}\n"),
+    )
+    .expect("edit by hand");
+    let explain = run_json(&repo, &["explain", "src/lib.rs:1-1"], None, &home);
+    let state = &explain["sessions"][0]["workspace_state"];
+    assert_eq!(state["state"], "drifted", "explain={explain:#}");
+    assert_eq!(state["drifted_files"], 1);
+    assert_eq!(state["files"][0]["state"], "drifted");
+}
+
+#[test]
+fn normalized_anchors_match_bom_and_carriage_return_files() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    let lines = [
+        "fn beta() { return value + 2; }",
+        "fn gamma() { return beta() * 3; }",
+        "fn delta() { return gamma() - 4; }",
+    ];
+    fs::create_dir_all(repo.join("src")).expect("src dir");
+    // Written by an editor that saves old Mac line endings behind a BOM.
+    fs::write(
+        repo.join("src/lib.rs"),
+        format!("\u{feff}{}\r", lines.join("\r")),
+    )
+    .expect("seed file");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    let config = |normalize: bool| {
+        format!(
+            "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\nanchors:\n  normalize_line_endings: {normalize}\n"
+        )
+    };
+    fs::write(home.join(".engram/config.yml"), config(true)).expect("home config");
+    let added = lines
+        .iter()
+        .map(|line| format!("+{line}\n"))
+        .collect::<String>();
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": format!(
+                "*** Begin Patch\n*** Update File: src/lib.rs\n@@\n-fn alpha() {{ return value + 1; }}\n{added}*** End Patch\n"
+            ),
+        },
+    });
+    let raw = format!(
+        "{}\n{patch}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta")
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    let explain = run_json(&repo, &["explain", "src/lib.rs:1-3"], None, &home);
+    assert_eq!(explain["query"]["anchor_version"], 2);
+    assert!(
+        !explain["sessions"].as_array().expect("sessions").is_empty(),
+        "explain={explain:#}"
+    );
+    assert!(explain.get("anchor_version_mismatch").is_none());
+
+    // Raw anchors see one long line, and say the index was built otherwise.
+    fs::write(home.join(".engram/config.yml"), config(false)).expect("home config");
+    let span = run_cli(&repo, &["explain", "src/lib.rs:1-3"], None, &home);
+    assert!(!span.status.success());
+    let literal = lines.join("\n");
+    let explain = run_json(&repo, &["explain", &literal], None, &home);
+    assert_eq!(explain["query"]["anchor_version"], 1);
+    let mismatch = &explain["anchor_version_mismatch"];
+    assert_eq!(mismatch["configured"], 1, "explain={explain:#}");
+    assert_eq!(mismatch["tapes"][0]["anchor_version"], 2);
+}
+
+#[test]
+fn chatgpt_export_code_blocks_explain_the_code_they_became() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path().join("repo");
+    let home = temp.path().join("home");
+    fs::create_dir_all(repo.join("src")).expect("repo");
+    fs::write(
+        repo.join("src/retry.rs"),
+        "pub fn backoff(attempt: u32) -> u64 {\n    100 * 2u64.pow(attempt)\n}\n",
+    )
+    .expect("retry.rs");
+    let export = repo.join("chatgpt-export/conversations.json");
+    fs::create_dir_all(export.parent().expect("export dir")).expect("export dir");
+    fs::write(&export, include_str!("fixtures/chatgpt/conversations.json")).expect("export");
+
+    let ingest = run_json(&repo, &["ingest", "chatgpt-export"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    let explain = run_json(&repo, &["explain", "src/retry.rs:1-3"], None, &home);
+    let sessions = explain["sessions"].as_array().expect("sessions");
+    assert!(!sessions.is_empty(), "explain={explain:#}");
+    let tape_id = sessions[0]["session_id"].as_str().expect("tape id");
+    let raw = run_cli(&repo, &["show", tape_id, "--raw"], None, &home);
+    let raw = String::from_utf8_lossy(&raw.stdout);
+    assert!(raw.contains("\"harness\":\"chatgpt\""), "{raw}");
+    assert!(raw.contains("\"candidate\":true"), "{raw}");
+}
+
+#[test]
+fn vscode_chat_editing_session_explains_accepted_edits() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path().join("repo");
+    let home = temp.path().join("home");
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/vscode/chatEditingSessions/session-1");
+    let session = repo.join("vscode/chatEditingSessions/session-1");
+    fs::create_dir_all(session.join("contents")).expect("session dir");
+    fs::copy(fixture.join("state.json"), session.join("state.json")).expect("state");
+    for entry in fs::read_dir(fixture.join("contents")).expect("contents") {
+        let entry = entry.expect("snapshot");
+        fs::copy(
+            entry.path(),
+            session.join("contents").join(entry.file_name()),
+        )
+        .expect("copy");
+    }
+    fs::create_dir_all(repo.join("src")).expect("src");
+    fs::copy(fixture.join("contents/h3"), repo.join("src/lib.rs")).expect("lib.rs");
+
+    let ingest = run_json(&repo, &["ingest", "vscode"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    let explain = run_json(&repo, &["explain", "src/lib.rs:9-11"], None, &home);
+    let sessions = explain["sessions"].as_array().expect("sessions");
+    assert!(!sessions.is_empty(), "explain={explain:#}");
+    let tape_id = sessions[0]["session_id"].as_str().expect("tape id");
+    let raw = run_cli(&repo, &["show", tape_id, "--raw"], None, &home);
+    let raw = String::from_utf8_lossy(&raw.stdout);
+    assert!(raw.contains("\"harness\":\"vscode-chat\""), "{raw}");
+    assert!(raw.contains("\"request_id\":\"request_3\""), "{raw}");
+    assert!(!raw.contains("request_2"), "{raw}");
+}
+
+#[test]
+fn notebook_edits_anchor_on_cells_not_notebook_json() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let repo = temp.path().join("repo");
+    let home = temp.path().join("home");
+    fs::create_dir_all(repo.join("nb")).expect("nb dir");
+    let cell = |id: &str, source: &[&str], outputs: Value| {
+        serde_json::json!({
+            "cell_type": "code",
+            "id": id,
+            "execution_count": outputs.as_array().map(|_| 12),
+            "metadata": {},
+            "outputs": outputs,
+            "source": source,
+        })
+    };
+    let load = [
+        "events = spark.read.table(\"analytics.events\")\n",
+        "clicks = events.filter(events.kind == \"click\")\n",
+        "clicks = clicks.withColumn(\"day\", to_date(clicks.ts))\n",
+        "clicks.count()",
+    ];
+    let plot = [
+        "daily = clicks.groupBy(\"day\").agg(count(\"*\").alias(\"clicks\"))\n",
+        "daily = daily.orderBy(\"day\").limit(90)\n",
+        "display(daily)",
+    ];
+    let notebook = |cells: Vec<Value>| serde_json::json!({"cells": cells, "metadata": {}, "nbformat": 4, "nbformat_minor": 5});
+    // The session wrote the notebook unexecuted, then added a cell.
+    let written = serde_json::to_string_pretty(&notebook(vec![cell("load", &load, Value::Null)]))
+        .expect("notebook json");
+    let path = repo.join("nb/load.ipynb");
+    let transcript = [
+        serde_json::json!({
+            "type": "assistant", "sessionId": "session-nb", "timestamp": "2026-03-02T09:00:00Z",
+            "message": {"role": "assistant", "content": [{
+                "type": "tool_use", "id": "toolu_write", "name": "Write",
+                "input": {"file_path": path.to_string_lossy(), "content": written}
+            }]}
+        }),
+        serde_json::json!({
+            "type": "assistant", "sessionId": "session-nb", "timestamp": "2026-03-02T09:01:00Z",
+            "message": {"role": "assistant", "content": [{
+                "type": "tool_use", "id": "toolu_cell", "name": "NotebookEdit",
+                "input": {
+                    "notebook_path": path.to_string_lossy(), "cell_id": "plot",
+                    "new_source": plot.concat(), "edit_mode": "insert", "cell_type": "code"
+                }
+            }]}
+        }),
+    ]
+    .map(|event| event.to_string())
+    .join("\n");
+    let session = repo.join(".claude/projects/repo/session-nb.jsonl");
+    fs::create_dir_all(session.parent().expect("session dir")).expect("session dir");
+    fs::write(&session, format!("{transcript}\n")).expect("transcript");
+
+    // On disk the notebook has since been run: execution counts, outputs
+    // and a different JSON layout, with the same cells.
+    let executed = notebook(vec![
+        cell(
+            "load",
+            &load,
+            serde_json::json!([{"output_type": "execute_result", "data": {"text/plain": ["48213"]}}]),
+        ),
+        cell("plot", &plot, serde_json::json!([])),
+    ]);
+    fs::write(&path, executed.to_string()).expect("notebook");
+
+    let ingest = run_json(
+        &repo,
+        &["ingest", ".claude/projects/repo/session-nb.jsonl"],
+        None,
+        &home,
+    );
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    // Cell view: header, four `load` lines, header, three `plot` lines.
+    for span in ["nb/load.ipynb:2-5", "nb/load.ipynb:7-9"] {
+        let explain = run_json(&repo, &["explain", span], None, &home);
+        let sessions = explain["sessions"].as_array().expect("sessions");
+        assert!(!sessions.is_empty(), "{span}: explain={explain:#}");
+    }
+}
+
+#[test]
+fn ingest_keeps_binary_and_oversized_edits_as_evidence_only() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        concat!(
+            "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "ingest:\n  binary:\n    max_text_bytes: 64\n",
+        ),
+    )
+    .expect("home config");
+    let big = format!("+{}\n", "let table = [0u8; 4096];".repeat(4));
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": format!(
+                "*** Begin Patch\n\
+                 *** Update File: src/lib.rs\n@@\n-fn old() {{}}\n+fn new() {{}}\n\
+                 *** Update File: dist/app.min.js\n@@\n-var a=1;\n+var a=2;\n\
+                 *** Add File: src/table.rs\n{big}\
+                 *** End Patch\n"
+            ),
+        },
+    });
+    let raw = format!(
+        "{}\n{patch}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta")
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+    assert_eq!(
+        ingest["binary_events"],
+        serde_json::json!({"policy": "evidence-only", "skipped": 0, "evidence_only": 2})
+    );
+
+    let tape = fs::read_dir(home.join(".engram/tapes"))
+        .expect("tapes dir")
+        .next()
+        .expect("tape file")
+        .expect("entry")
+        .path();
+    let jsonl = zstd::stream::decode_all(fs::read(tape).expect("tape").as_slice())
+        .expect("decompress tape");
+    let edits = String::from_utf8(jsonl)
+        .expect("utf8 tape")
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("row"))
+        .filter(|row| row["k"] == "code.edit")
+        .map(|row| {
+            (
+                row["file"].as_str().expect("file").to_string(),
+                row["binary"].as_str().map(ToOwned::to_owned),
+                row.get("after_text").is_some(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        edits,
+        vec![
+            ("src/lib.rs".to_string(), None, true),
+            (
+                "dist/app.min.js".to_string(),
+                Some("generated".to_string()),
+                false
+            ),
+            ("src/table.rs".to_string(), Some("size".to_string()), false),
+        ]
+    );
+}
+
+#[test]
+fn span_normalizers_keep_lineage_through_cosmetic_edits() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(repo.join("src")).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        concat!(
+            "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "anchors:\n  normalizers: [python]\n",
+        ),
+    )
+    .expect("home config");
+    let source = [
+        "def load_orders(client, region):",
+        "    rows = client.query('select * from orders where region = %s', region)",
+        "    totals = {}",
+        "    for row in rows:",
+        "        totals[row['customer']] = totals.get(row['customer'], 0) + row['amount']",
+        "    return sorted(totals.items(), key=lambda item: item[1], reverse=True)",
+    ];
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": format!(
+                "*** Begin Patch\n*** Add File: src/orders.py\n{}*** End Patch\n",
+                source.iter().map(|line| format!("+{line}\n")).collect::<String>()
+            ),
+        },
+    });
+    let raw = format!(
+        "{}\n{patch}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta")
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    // A formatter swaps the quotes and someone adds comments.
+    let restyled = source
+        .iter()
+        .map(|line| format!("{}  # reviewed\n", line.replace('\'', "\"")))
+        .collect::<String>();
+    fs::write(repo.join("src/orders.py"), restyled).expect("restyle");
+    let explain = run_json(&repo, &["explain", "src/orders.py:1-6"], None, &home);
+    let sessions = explain["sessions"].as_array().expect("sessions");
+    assert!(!sessions.is_empty(), "explain={explain:#}");
+
+    let tape = fs::read_dir(home.join(".engram/tapes"))
+        .expect("tapes dir")
+        .next()
+        .expect("tape file")
+        .expect("entry")
+        .path();
+    let jsonl = zstd::stream::decode_all(fs::read(tape).expect("tape").as_slice())
+        .expect("decompress tape");
+    assert!(
+        String::from_utf8(jsonl)
+            .expect("utf8 tape")
+            .contains(r#""normalizer":"python.v1""#)
+    );
+}
+
+#[test]
+fn configured_scorer_scores_edits_and_tags_their_edges() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(repo.join("src")).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        concat!(
+            "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "lineage:\n  scorer: levenshtein\n",
+        ),
+    )
+    .expect("home config");
+    let before = concat!(
+        "fn order_total(orders: &[Order], fees: u64) -> u64 {\n",
+        "    let total = orders.iter().map(|order| order.amount).sum::<u64>();\n",
+        "    total\n",
+        "}\n",
+    );
+    let after = before.replace("sum::<u64>();", "sum::<u64>() + fees;");
+    fs::write(repo.join("src/total.rs"), &after).expect("seed file");
+    let hunk = before
+        .lines()
+        .map(|line| format!("-{line}\n"))
+        .chain(after.lines().map(|line| format!("+{line}\n")))
+        .collect::<String>();
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": format!(
+                "*** Begin Patch\n*** Update File: src/total.rs\n@@\n{hunk}*** End Patch\n"
+            ),
+        },
+    });
+    let raw = format!(
+        "{}\n{patch}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta")
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    let (scorer, confidence): (String, f64) =
+        rusqlite::Connection::open(home.join(".engram/index.sqlite"))
+            .expect("open db")
+            .query_row("SELECT scorer, confidence FROM edges", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .expect("edit edge");
+    assert_eq!(scorer, "levenshtein.v1");
+    assert!(confidence > 0.9 && confidence < 1.0, "{confidence}");
+
+    fs::write(
+        home.join(".engram/config.yml"),
+        "db: ~/.engram/index.sqlite\nlineage:\n  scorer: cosine\n",
+    )
+    .expect("bad config");
+    let output = run_cli(&repo, &["ingest"], None, &home);
+    assert!(!output.status.success());
+    let err = stderr_json_line(&output.stderr);
+    assert_eq!(err["error"]["code"], "config_error");
+    assert!(
+        err["error"]["message"]
+            .as_str()
+            .expect("message")
+            .contains("unknown similarity scorer `cosine`"),
+        "err={err}"
+    );
+}
+
+#[test]
+fn linked_worktrees_share_the_main_store_and_tag_their_events() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let main = home.join("main");
+    let feature = home.join("feature");
+    fs::create_dir_all(main.join("src")).expect("main repo");
+    let git = |cwd: &Path, args: &[&str]| {
+        let output = Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .current_dir(cwd)
+            .output()
+            .expect("git runs");
+        assert!(output.status.success(), "git {args:?}: {output:?}");
+    };
+    fs::write(main.join("src/lib.rs"), "pub fn seed() {}\n").expect("seed");
+    git(&main, &["init", "-q"]);
+    git(&main, &["add", "src"]);
+    git(&main, &["commit", "-qm", "seed"]);
+    git(&main, &["worktree", "add", "-q", "../feature"]);
+    fs::create_dir_all(main.join(".engram")).expect("main .engram");
+    fs::write(
+        main.join(".engram/config.yml"),
+        "db: .engram/index.sqlite\ntapes_dir: .engram/tapes\nworktrees:\n  share_store: true\n",
+    )
+    .expect("main config");
+
+    let meta = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:00Z",
+        "session_id": "sess_feature",
+        "type": "session_meta",
+        "payload": {"model_provider": "openai", "cwd": feature.to_string_lossy()},
+    });
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": "*** Begin Patch\n*** Add File: src/feature.rs\n+pub fn feature() -> u32 { 42 }\n*** End Patch\n",
+        },
+    });
+    fs::write(
+        feature.join("input.codex.jsonl"),
+        format!("{meta}\n{patch}\n"),
+    )
+    .expect("input");
+    let ingest = run_json(&feature, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    assert!(!feature.join(".engram/tapes").exists());
+    assert!(main.join(".engram/index.sqlite").is_file());
+    let tape = fs::read_dir(main.join(".engram/tapes"))
+        .expect("shared tapes dir")
+        .next()
+        .expect("tape file")
+        .expect("entry")
+        .path();
+    let jsonl = String::from_utf8(
+        zstd::stream::decode_all(fs::read(tape).expect("tape").as_slice())
+            .expect("decompress tape"),
+    )
+    .expect("utf8 tape");
+    let edit = jsonl
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("json"))
+        .find(|row| row["k"] == "code.edit")
+        .expect("edit event");
+    assert_eq!(edit["worktree"], "feature", "edit={edit}");
+    assert!(edit.get("submodule").is_none());
+
+    let from_main = run_json(&main, &["tapes"], None, &home);
+    assert_eq!(
+        from_main["tapes"].as_array().expect("tapes").len(),
+        1,
+        "tapes={from_main:#}"
+    );
+}
+
+#[test]
+fn calibrate_suggests_a_link_threshold_from_retained_edits() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    fs::create_dir_all(&repo).expect("repo");
+    fs::create_dir_all(home.join(".engram")).expect("home .engram");
+    fs::write(
+        home.join(".engram/config.yml"),
+        "db: ~/.engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+    )
+    .expect("home config");
+    // Twelve small tweaks and twelve rewrites.
+    let mut hunks = String::new();
+    for idx in 0..12 {
+        hunks.push_str(&format!(
+            "*** Update File: src/tweak_{idx}.rs\n@@\n\
+             -let total_{idx} = orders.iter().map(|order| order.amount).sum::<u64>() + fees_{idx};\n\
+             +let total_{idx} = orders.iter().map(|order| order.amount).sum::<u64>() + fees_{idx} * rate_{idx} - discount_{idx}.amount;\n"
+        ));
+        hunks.push_str(&format!(
+            "*** Update File: src/rewrite_{idx}.rs\n@@\n\
+             -fn render_{idx}(page: &Page) -> String {{ page.title.clone() }}\n\
+             +async fn fetch_{idx}(client: Client, url: Url) -> Result<Response> {{ client.get(url).await }}\n"
+        ));
+    }
+    let patch = serde_json::json!({
+        "timestamp": "2026-02-22T00:00:03Z",
+        "type": "response_item",
+        "payload": {
+            "type": "function_call",
+            "name": "apply_patch",
+            "call_id": "call_2",
+            "arguments": format!("*** Begin Patch\n{hunks}*** End Patch\n"),
+        },
+    });
+    let raw = format!(
+        "{}\n{patch}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta")
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    let report = run_json(&repo, &["calibrate"], None, &home);
+    assert_eq!(report["sampled"], 24, "report={report:#}");
+    assert_eq!(report["link_threshold"], 0.3);
+    assert_eq!(report["scorer"], "token-jaccard.v1");
+    // Ingest scored every edit with the same scorer calibrate rescores with.
+    assert_eq!(report["stored_confidence"]["missing"], 0);
+    assert_eq!(report["stored_confidence"]["other_scorer"], 0);
+    assert_eq!(report["stored_confidence"]["agrees"], 24);
+    assert_eq!(report["misclassified"], 0);
+    let suggested = report["suggested_link_threshold"]
+        .as_f64()
+        .expect("suggestion");
+    assert!(suggested > 0.0 && suggested < 1.0, "report={report:#}");
+    assert_eq!(
+        report["suggested"],
+        serde_json::json!({"lineage": 12, "location_only": 12})
+    );
+    assert!(report.get("written").is_none());
+
+    let written = run_json(&repo, &["calibrate", "--write"], None, &home);
+    let config = fs::read_to_string(home.join(".engram/config.yml")).expect("config");
+    assert_eq!(
+        written["written"],
+        home.join(".engram/config.yml").to_string_lossy().as_ref()
+    );
+    assert!(
+        config.contains(&format!("link_threshold: {suggested}")),
+        "{config}"
+    );
+    let again = run_json(&repo, &["calibrate"], None, &home);
+    assert_eq!(again["link_threshold"], suggested);
+    assert_eq!(again["current"], again["suggested"]);
+}
+
+#[test]
+fn projects_tag_evidence_and_filter_explain_tapes_and_stats() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = home.join("repo");
+    fs::create_dir_all(repo.join("services/payments")).expect("payments");
+    fs::create_dir_all(repo.join("services/search")).expect("search");
+    fs::create_dir_all(repo.join(".engram")).expect("repo .engram");
+    fs::write(
+        repo.join(".engram/config.yml"),
+        concat!(
+            "db: .engram/index.sqlite\ntapes_dir: .engram/tapes\n",
+            "projects:\n",
+            "  payments: [services/payments/**]\n",
+            "  search: [services/search]\n",
+        ),
+    )
+    .expect("repo config");
+    let body = concat!(
+        "fn settle(ledger: &mut Ledger, amount: u64) -> u64 {\n",
+        "    let balance = ledger.balance().saturating_sub(amount);\n",
+        "    ledger.record(balance);\n",
+        "    balance\n",
+        "}\n",
+    );
+    let ranked = concat!(
+        "pub fn rank(hits: Vec<Hit>, query: &str) -> Vec<Hit> {\n",
+        "    let mut scored = hits.into_iter().filter(|hit| hit.matches(query));\n",
+        "    scored.by_ref().take(TOP_K).collect()\n",
+        "}\n",
+    );
+    fs::write(repo.join("services/payments/ledger.rs"), body).expect("ledger");
+    fs::write(repo.join("services/search/rank.rs"), ranked).expect("rank");
+    let patch = |call_id: &str, file: &str, text: &str| {
+        let hunk = text
+            .lines()
+            .map(|line| format!("+{line}\n"))
+            .collect::<String>();
+        serde_json::json!({
+            "timestamp": "2026-02-22T00:00:03Z",
+            "type": "response_item",
+            "payload": {
+                "type": "function_call",
+                "name": "apply_patch",
+                "call_id": call_id,
+                "arguments": format!("*** Begin Patch\n*** Add File: {file}\n{hunk}*** End Patch\n"),
+            },
+        })
+    };
+    let raw = format!(
+        "{}\n{}\n{}\n",
+        include_str!("fixtures/codex/supported_paths.jsonl")
+            .lines()
+            .next()
+            .expect("session meta"),
+        patch("call_1", "services/payments/ledger.rs", body),
+        patch("call_2", "services/search/rank.rs", ranked),
+    );
+    fs::write(repo.join("input.codex.jsonl"), raw).expect("seed source");
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+
+    let projects = rusqlite::Connection::open(repo.join(".engram/index.sqlite"))
+        .expect("open db")
+        .prepare("SELECT DISTINCT file_path, project FROM evidence ORDER BY file_path")
+        .expect("prepare")
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .expect("query")
+        .collect::<Result<Vec<(String, String)>, _>>()
+        .expect("rows");
+    assert_eq!(
+        projects,
+        vec![
+            (
+                "services/payments/ledger.rs".to_string(),
+                "payments".to_string()
+            ),
+            ("services/search/rank.rs".to_string(), "search".to_string()),
+        ]
+    );
+
+    let explain_args = |project| {
+        [
+            "explain",
+            "services/payments/ledger.rs:1-5",
+            "--project",
+            project,
+        ]
+    };
+    let payments = run_json(&repo, &explain_args("payments"), None, &home);
+    assert_eq!(
+        payments["query"]["project"],
+        serde_json::json!(["payments"])
+    );
+    assert_eq!(
+        payments["sessions"].as_array().expect("sessions").len(),
+        1,
+        "payload={payments}"
+    );
+    let output = run_cli(&repo, &explain_args("search"), None, &home);
+    assert!(!output.status.success());
+    assert_eq!(stderr_json_line(&output.stderr)["error"], "no_results");
+
+    let tapes = run_json(&repo, &["tapes", "--project", "search"], None, &home);
+    assert_eq!(
+        tapes["tapes"][0]["projects"],
+        serde_json::json!(["payments", "search"])
+    );
+    let tapes = run_json(&repo, &["tapes", "--project", "billing"], None, &home);
+    assert!(tapes["tapes"].as_array().expect("tapes").is_empty());
+    let stats = run_json(&repo, &["stats", "--project", "payments"], None, &home);
+    assert_eq!(stats["totals"]["tapes"], 1);
+    let stats = run_json(&repo, &["stats", "--project", "billing"], None, &home);
+    assert_eq!(stats["totals"]["tapes"], 0);
+
+    fs::write(
+        repo.join(".engram/config.yml"),
+        "db: .engram/index.sqlite\nprojects:\n  payments: ['services/[payments']\n",
+    )
+    .expect("bad config");
+    let output = run_cli(&repo, &["ingest"], None, &home);
+    assert!(!output.status.success());
+    let err = stderr_json_line(&output.stderr);
+    assert_eq!(err["error"]["code"], "config_error");
+    assert!(
+        err["error"]["message"]
+            .as_str()
+            .expect("message")
+            .contains("invalid glob in `projects.payments`"),
+        "err={err}"
+    );
+}
+
+#[test]
+fn ingest_and_stats_forecast_store_growth_against_the_quota() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let home = temp.path().join("home");
+    let repo = home.join("repo");
+    fs::create_dir_all(repo.join(".engram")).expect("repo .engram");
+    let config = |max_bytes: u64| {
+        format!(
+            "db: .engram/index.sqlite\ntapes_dir: .engram/tapes\nstorage:\n  quota:\n    max_bytes: {max_bytes}\n    horizon_days: 7\n"
+        )
+    };
+    fs::write(repo.join(".engram/config.yml"), config(1)).expect("repo config");
+    fs::write(
+        repo.join("input.codex.jsonl"),
+        include_str!("fixtures/codex/supported_paths.jsonl"),
+    )
+    .expect("seed source");
+
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert_eq!(ingest["imported_tapes"], 1, "payload={ingest}");
+    let storage = &ingest["storage"];
+    let total = storage["total_bytes"].as_u64().expect("total bytes");
+    assert!(total > 0, "storage={storage}");
+    assert_eq!(
+        total,
+        storage["index_bytes"].as_u64().expect("index bytes")
+            + storage["tapes_bytes"].as_u64().expect("tapes bytes")
+    );
+    assert!(
+        storage["warning"]
+            .as_str()
+            .expect("warning")
+            .contains("over the `storage.quota` of 1 bytes"),
+        "storage={storage}"
+    );
+    assert!(
+        storage["suggestions"]
+            .as_array()
+            .expect("suggestions")
+            .iter()
+            .any(|suggestion| suggestion
+                .as_str()
+                .is_some_and(|text| text.contains("maintenance.partitioning: monthly"))),
+        "storage={storage}"
+    );
+
+    // A day ago the store was empty: at that rate it outgrows a quota of
+    // twice its size within the week.
+    let db = rusqlite::Connection::open(repo.join(".engram/index.sqlite")).expect("open db");
+    let samples: i64 = db
+        .query_row("SELECT COUNT(*) FROM growth_samples", [], |row| row.get(0))
+        .expect("samples");
+    assert_eq!(samples, 1);
+    let yesterday = (chrono::Utc::now() - chrono::Duration::days(1))
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    db.execute(
+        "INSERT INTO growth_samples (recorded_at, index_bytes, tapes_bytes) VALUES (?1, 0, 0)",
+        [yesterday],
+    )
+    .expect("older sample");
+    fs::write(repo.join(".engram/config.yml"), config(total * 2)).expect("repo config");
+    let stats = run_json(&repo, &["stats"], None, &home);
+    let storage = &stats["storage"];
+    assert_eq!(storage["horizon_days"], 7);
+    assert!(storage["bytes_per_day"].as_f64().expect("rate") > 0.0);
+    assert!(
+        storage["warning"]
+            .as_str()
+            .expect("warning")
+            .contains("projected to reach"),
+        "storage={storage}"
+    );
+
+    fs::write(repo.join(".engram/config.yml"), config(u64::MAX / 2)).expect("repo config");
+    let ingest = run_json(&repo, &["ingest"], None, &home);
+    assert!(ingest.get("storage").is_none(), "payload={ingest}");
+    let stats = run_json(&repo, &["stats"], None, &home);
+    assert!(stats["storage"].get("warning").is_none());
+}
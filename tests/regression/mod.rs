@@ -420,8 +420,12 @@ fn api_surface_errors_are_json_objects() {
         .expect("invalid span stderr line")
         .as_bytes()
         .to_vec();
-    let invalid_payload: Value = serde_json::from_slice(&invalid_json).expect("invalid span json error");
-    assert_eq!(invalid_payload["error"], Value::String("invalid_span".to_string()));
+    let invalid_payload: Value =
+        serde_json::from_slice(&invalid_json).expect("invalid span json error");
+    assert_eq!(
+        invalid_payload["error"],
+        Value::String("invalid_span".to_string())
+    );
 
     let no_results = run_cli(&repo, &["grep", "definitely-missing-pattern"], None, &home);
     assert!(!no_results.status.success());
@@ -432,8 +436,12 @@ fn api_surface_errors_are_json_objects() {
         .expect("no_results stderr line")
         .as_bytes()
         .to_vec();
-    let no_results_payload: Value = serde_json::from_slice(&no_results_json).expect("no_results json error");
-    assert_eq!(no_results_payload["error"], Value::String("no_results".to_string()));
+    let no_results_payload: Value =
+        serde_json::from_slice(&no_results_json).expect("no_results json error");
+    assert_eq!(
+        no_results_payload["error"],
+        Value::String("no_results".to_string())
+    );
 
     let missing_session = run_cli(&repo, &["peek", "missing-session-id"], None, &home);
     assert!(!missing_session.status.success());
@@ -444,7 +452,8 @@ fn api_surface_errors_are_json_objects() {
         .expect("session_not_found stderr line")
         .as_bytes()
         .to_vec();
-    let session_payload: Value = serde_json::from_slice(&session_json).expect("session_not_found json error");
+    let session_payload: Value =
+        serde_json::from_slice(&session_json).expect("session_not_found json error");
     assert_eq!(
         session_payload["error"],
         Value::String("session_not_found".to_string())
@@ -504,7 +513,12 @@ fn grep_count_returns_metadata_only() {
         let _ = run_json(&repo, &["record", "--stdin"], Some(&record_line), &home);
     }
 
-    let grep = run_json(&repo, &["grep", "count-only needle", "--count"], None, &home);
+    let grep = run_json(
+        &repo,
+        &["grep", "count-only needle", "--count"],
+        None,
+        &home,
+    );
     assert_eq!(grep["returned"], Value::from(3));
     assert_eq!(grep["total"], Value::from(3));
     assert_eq!(grep["sessions"], json!([]));
@@ -563,7 +577,7 @@ fn grep_ranks_provenance_matches_before_recent_text_mentions() {
     assert!(
         sessions
             .iter()
-            .any(|session| session["timestamp"] == Value::from("2026-03-17T00:00:00Z")),
+            .any(|session| session["timestamp"] == "2026-03-17T00:00:00Z"),
         "older provenance-bearing match should survive default-limit truncation"
     );
 }
@@ -744,9 +758,10 @@ fn peek_grep_filter_returns_match_context_window() {
     let content = session["content"].as_array().expect("content");
     assert_eq!(content.len(), 3);
     assert!(
-        content
-            .iter()
-            .any(|line| line["text"].as_str().unwrap_or("").contains("needle-in-session")),
+        content.iter().any(|line| line["text"]
+            .as_str()
+            .unwrap_or("")
+            .contains("needle-in-session")),
         "grep-filter output should include the matching line"
     );
 }
@@ -771,8 +786,16 @@ fn explain_chain_metadata_populates_parent_children_and_depth() {
     })
     .to_string()
         + "\n";
-    let parent = run_json(&repo, &["record", "--stdin"], Some(&parent_transcript), &home);
-    let parent_id = parent["tape_id"].as_str().expect("parent tape id").to_string();
+    let parent = run_json(
+        &repo,
+        &["record", "--stdin"],
+        Some(&parent_transcript),
+        &home,
+    );
+    let parent_id = parent["tape_id"]
+        .as_str()
+        .expect("parent tape id")
+        .to_string();
 
     let child_transcript = format!(
         "{}\n{}\n",
@@ -793,10 +816,23 @@ fn explain_chain_metadata_populates_parent_children_and_depth() {
             "similarity": 0.96
         })
     );
-    let child = run_json(&repo, &["record", "--stdin"], Some(&child_transcript), &home);
-    let child_id = child["tape_id"].as_str().expect("child tape id").to_string();
+    let child = run_json(
+        &repo,
+        &["record", "--stdin"],
+        Some(&child_transcript),
+        &home,
+    );
+    let child_id = child["tape_id"]
+        .as_str()
+        .expect("child tape id")
+        .to_string();
 
-    let explain = run_json(&repo, &["explain", "src/lib.rs:1-1", "--limit", "10"], None, &home);
+    let explain = run_json(
+        &repo,
+        &["explain", "src/lib.rs:1-1", "--limit", "10"],
+        None,
+        &home,
+    );
     let sessions = explain["sessions"].as_array().expect("sessions");
 
     let parent_session = sessions
@@ -955,7 +991,10 @@ fn explain_direct_string_query_returns_fingerprint_matches() {
 
     let explain = run_json(&repo, &["explain", query], None, &home);
     let sessions = explain["sessions"].as_array().expect("sessions");
-    assert!(!sessions.is_empty(), "expected literal explain to return matches");
+    assert!(
+        !sessions.is_empty(),
+        "expected literal explain to return matches"
+    );
     assert!(
         sessions[0]["confidence"].as_f64().unwrap_or(0.0) > 0.0,
         "direct string explain should return a positive confidence match"